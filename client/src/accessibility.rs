@@ -0,0 +1,174 @@
+//! Accessibility options, read from the player settings:
+//!
+//! - `a11y.palette`: colors telling the local player, other players and monsters apart, one of
+//!   `default`, `deuteranopia`, `protanopia`, `tritanopia` or `high_contrast`.
+//! - `a11y.ui_scale`: scale factor of every UI node (HUD, nameplates, command line).
+//! - `a11y.reduced_motion`: the camera follows without smoothing.
+//!
+//! Actor materials are re-tinted when the palette changes, nameplates pick it up every frame.
+
+use crate::{
+    actor::{ActorKind, ActorVisuals, LocalActor, actor_color},
+    settings::PlayerSettings,
+};
+use bevy::prelude::*;
+
+/// Bounds for the `a11y.ui_scale` setting.
+const MIN_UI_SCALE: f32 = 0.75;
+const MAX_UI_SCALE: f32 = 2.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Accessibility>();
+    app.add_systems(
+        Update,
+        (apply_accessibility_settings, retint_actors).chain(),
+    );
+}
+
+/// Colors of the actors and their health bars.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Palette {
+    pub local: Color,
+    pub remote: Color,
+    pub friendly_bar: Color,
+    pub hostile_bar: Color,
+}
+
+impl Palette {
+    pub const DEFAULT: Self = Self {
+        local: Color::linear_rgb(0.2, 0.9, 0.8),
+        remote: Color::linear_rgb(0.9, 0.2, 0.2),
+        friendly_bar: Color::srgb(0.2, 0.8, 0.3),
+        hostile_bar: Color::srgb(0.85, 0.2, 0.15),
+    };
+
+    /// Blue against orange, told apart without red/green.
+    pub const DEUTERANOPIA: Self = Self {
+        local: Color::srgb(0.0, 0.45, 0.7),
+        remote: Color::srgb(0.9, 0.6, 0.0),
+        friendly_bar: Color::srgb(0.35, 0.7, 0.9),
+        hostile_bar: Color::srgb(0.84, 0.37, 0.0),
+    };
+
+    /// Like deuteranopia, with brighter warm colors since reds look dark.
+    pub const PROTANOPIA: Self = Self {
+        local: Color::srgb(0.0, 0.45, 0.7),
+        remote: Color::srgb(0.95, 0.9, 0.25),
+        friendly_bar: Color::srgb(0.35, 0.7, 0.9),
+        hostile_bar: Color::srgb(0.9, 0.6, 0.0),
+    };
+
+    /// Teal against magenta, told apart without blue/yellow.
+    pub const TRITANOPIA: Self = Self {
+        local: Color::srgb(0.0, 0.6, 0.5),
+        remote: Color::srgb(0.8, 0.1, 0.45),
+        friendly_bar: Color::srgb(0.3, 0.8, 0.75),
+        hostile_bar: Color::srgb(0.85, 0.2, 0.35),
+    };
+
+    /// White against black, by brightness alone.
+    pub const HIGH_CONTRAST: Self = Self {
+        local: Color::WHITE,
+        remote: Color::srgb(0.05, 0.05, 0.05),
+        friendly_bar: Color::WHITE,
+        hostile_bar: Color::srgb(1.0, 0.85, 0.0),
+    };
+
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(Self::DEFAULT),
+            "deuteranopia" => Some(Self::DEUTERANOPIA),
+            "protanopia" => Some(Self::PROTANOPIA),
+            "tritanopia" => Some(Self::TRITANOPIA),
+            "high_contrast" => Some(Self::HIGH_CONTRAST),
+            _ => None,
+        }
+    }
+
+    /// Fill color of a nameplate health bar.
+    pub fn bar_color(&self, kind: Option<&ActorKind>) -> Color {
+        match kind {
+            Some(ActorKind::Monster { .. }) => self.hostile_bar,
+            _ => self.friendly_bar,
+        }
+    }
+}
+
+/// The accessibility options in effect.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct Accessibility {
+    pub palette: Palette,
+    pub ui_scale: f32,
+    pub reduced_motion: bool,
+}
+
+impl Default for Accessibility {
+    fn default() -> Self {
+        Self {
+            palette: Palette::DEFAULT,
+            ui_scale: 1.0,
+            reduced_motion: false,
+        }
+    }
+}
+
+impl Accessibility {
+    fn from_settings(settings: &PlayerSettings) -> Self {
+        Self {
+            palette: settings
+                .get("a11y.palette")
+                .and_then(Palette::parse)
+                .unwrap_or(Palette::DEFAULT),
+            ui_scale: settings
+                .get("a11y.ui_scale")
+                .and_then(|value| value.parse::<f32>().ok())
+                .filter(|scale| scale.is_finite())
+                .map(|scale| scale.clamp(MIN_UI_SCALE, MAX_UI_SCALE))
+                .unwrap_or(1.0),
+            reduced_motion: settings.get_bool("a11y.reduced_motion", false),
+        }
+    }
+
+    /// Moves the camera towards `target`, at once with reduced motion.
+    pub fn follow(&self, camera: &mut Vec3, target: Vec3, decay_rate: f32, delta_secs: f32) {
+        if self.reduced_motion {
+            *camera = target;
+        } else {
+            camera.smooth_nudge(&target, decay_rate, delta_secs);
+        }
+    }
+}
+
+fn apply_accessibility_settings(
+    settings: Res<PlayerSettings>,
+    mut accessibility: ResMut<Accessibility>,
+    mut ui_scale: ResMut<UiScale>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    let options = Accessibility::from_settings(&settings);
+    accessibility.set_if_neq(options);
+    if ui_scale.0 != options.ui_scale {
+        ui_scale.0 = options.ui_scale;
+    }
+}
+
+/// Re-tints the actors that already have visuals, new ones get the palette when attached.
+fn retint_actors(
+    accessibility: Res<Accessibility>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    actor_q: Query<(&ActorKind, &ActorVisuals, Has<LocalActor>)>,
+) {
+    if !accessibility.is_changed() {
+        return;
+    }
+    for (kind, visuals, is_local) in &actor_q {
+        if let Some(material) = materials.get_mut(&visuals.material) {
+            // Keep the alpha, stealth fades it.
+            let alpha = material.base_color.alpha();
+            material.base_color =
+                actor_color(&accessibility.palette, *kind, is_local).with_alpha(alpha);
+        }
+    }
+}
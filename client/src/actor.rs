@@ -1,11 +1,30 @@
-use crate::{module_bindings::CharacterInstanceRow, server::SpacetimeDB};
+//! The single actor lifecycle for the client.
+//!
+//! - Every replicated table handler resolves its entity through [`ensure_actor_entity`], so there
+//!   is exactly one [`ActorEntityMapping`] regardless of message ordering.
+//! - The "instance" tables (`character_instance_view`, `monster_instance_view`) decide what an
+//!   actor is ([`ActorKind`]) and own despawning.
+//! - Visuals are attached once per entity by [`attach_actor_visuals`] when the kind and capsule
+//!   are both known, sized from the replicated capsule and styled by kind. Culling (see
+//!   [`crate::culling`]) removes them again for far or off screen actors.
+
+use crate::{
+    accessibility::{Accessibility, Palette},
+    culling::Culled,
+    module_bindings::{ActorRow, CharacterInstanceRow, MonsterInstanceRow},
+    server::SpacetimeDB,
+};
 use bevy::{platform::collections::HashMap, prelude::*};
-use bevy_spacetimedb::{ReadDeleteMessage, ReadInsertMessage};
-use shared::ActorId;
+use bevy_spacetimedb::{ReadDeleteMessage, ReadInsertMessage, ReadUpdateMessage};
+use shared::{ActorFlags, ActorId, CellId, encode_cell_id, is_in_aoi_block};
 
-/// Marker to ensure we only attach active-character visuals once per entity.
+/// Attached once per entity with its visuals. The mesh sits on the `body` child so that
+/// presentation offsets (e.g. [`crate::foot_placement`]) never touch the actor's transform.
 #[derive(Component, Debug)]
-pub struct ActiveCharacterVisuals;
+pub struct ActorVisuals {
+    pub body: Entity,
+    pub material: Handle<StandardMaterial>,
+}
 
 #[derive(Resource, Default)]
 pub struct ActorEntityMapping(pub HashMap<ActorId, Entity>);
@@ -19,6 +38,51 @@ pub struct LocalActor;
 #[derive(Component, Debug)]
 pub struct RemoteActor;
 
+/// What kind of actor an entity is, drives its visuals.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActorKind {
+    Character,
+    Monster { archetype_id: u16 },
+}
+
+/// Replicated collision capsule of the actor.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct ActorCapsule {
+    pub radius: f32,
+    pub half_height: f32,
+}
+
+/// Replicated size of the actor relative to its archetype, the visuals are built at size `1.0`
+/// and scaled by it (see `emote::animate_actors`). [`ActorCapsule`] is already scaled.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct ActorScale(pub f32);
+
+impl Default for ActorScale {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+/// Replicated display name of the actor, see [`crate::nameplate`].
+#[derive(Component, Debug, Clone)]
+pub struct ActorName(pub String);
+
+/// How long a remote actor takes to fade out when it vanishes from within our AOI.
+const FADE_OUT_SECS: f32 = 0.6;
+
+/// A remote actor that left the AOI while still inside it (e.g. it stealthed), fading out
+/// before being despawned.
+#[derive(Component, Debug)]
+pub struct FadingOut(pub Timer);
+
+/// Replicated [`ActorFlags`] for visual states (stealth, combat, ...).
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Flags(pub ActorFlags);
+
+/// Replicated zone (instance) the actor is in, see [`crate::ambient`].
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Zone(pub u32);
+
 /// Ensures there is a Bevy `Entity` for the given `actor_id`, regardless of message ordering.
 ///
 /// This is the common pattern for replication timing issues:
@@ -59,35 +123,81 @@ pub fn ensure_local_remote_tags(commands: &mut Commands, entity: Entity, is_loca
 pub(super) fn plugin(app: &mut App) {
     app.insert_resource(ActorEntityMapping::default());
     app.add_systems(
-        Update,
+        PreUpdate,
         (
+            on_actor_inserted,
+            on_actor_updated,
             on_character_instance_inserted,
-            on_character_instance_deleted,
             on_monster_instance_inserted,
         ),
     );
+    app.add_systems(
+        Update,
+        (
+            on_character_instance_deleted,
+            on_monster_instance_deleted,
+            attach_actor_visuals,
+            apply_flag_visuals.after(attach_actor_visuals),
+            fade_out_actors,
+        ),
+    );
 }
 
-fn on_character_instance_deleted(
+fn on_actor_inserted(
     mut commands: Commands,
+    mut msgs: ReadInsertMessage<ActorRow>,
     mut oe_mapping: ResMut<ActorEntityMapping>,
-    mut msgs: ReadDeleteMessage<CharacterInstanceRow>,
 ) {
     for msg in msgs.read() {
-        if let Some(bevy_entity) = oe_mapping.0.remove(&msg.row.actor_id) {
-            commands.entity(bevy_entity).despawn();
+        let bevy_entity = ensure_actor_entity(&mut commands, &mut oe_mapping, msg.row.id);
+        commands.entity(bevy_entity).insert((
+            Flags(ActorFlags::from_bits(msg.row.flags)),
+            ActorCapsule {
+                radius: msg.row.capsule.radius,
+                half_height: msg.row.capsule.half_height,
+            },
+            ActorScale(msg.row.scale),
+            ActorName(msg.row.name.clone()),
+            Zone(msg.row.instance_id),
+        ));
+    }
+}
+
+fn on_actor_updated(
+    mut commands: Commands,
+    mut msgs: ReadUpdateMessage<ActorRow>,
+    oe_mapping: Res<ActorEntityMapping>,
+) {
+    for msg in msgs.read() {
+        let Some(&bevy_entity) = oe_mapping.0.get(&msg.new.id) else {
+            continue;
+        };
+        let mut entity_commands = commands.entity(bevy_entity);
+        entity_commands.insert((
+            Flags(ActorFlags::from_bits(msg.new.flags)),
+            Zone(msg.new.instance_id),
+        ));
+        // A pooled monster respawning as another size, the visuals are rebuilt for it.
+        if msg.new.capsule != msg.old.capsule || msg.new.scale != msg.old.scale {
+            entity_commands
+                .insert((
+                    ActorCapsule {
+                        radius: msg.new.capsule.radius,
+                        half_height: msg.new.capsule.half_height,
+                    },
+                    ActorScale(msg.new.scale),
+                ))
+                .remove::<ActorVisuals>()
+                .despawn_related::<Children>();
         }
     }
 }
 
 fn on_character_instance_inserted(
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
     mut msgs: ReadInsertMessage<CharacterInstanceRow>,
     mut oe_mapping: ResMut<ActorEntityMapping>,
     stdb: SpacetimeDB,
-    visuals_q: Query<(), With<ActiveCharacterVisuals>>,
 ) {
     for msg in msgs.read() {
         let is_local = msg.row.identity == stdb.identity();
@@ -95,96 +205,230 @@ fn on_character_instance_inserted(
         // Ensure the base entity exists even if other tables arrive first/last.
         let entity = ensure_actor_entity(&mut commands, &mut oe_mapping, msg.row.actor_id);
         ensure_local_remote_tags(&mut commands, entity, is_local);
+        commands.entity(entity).insert(ActorKind::Character);
 
-        // Attach visuals only once per entity.
-        if visuals_q.get(entity).is_err() {
-            let base_color = if is_local {
-                Color::linear_rgb(0.2, 0.9, 0.8)
-            } else {
-                Color::linear_rgb(0.9, 0.2, 0.2)
-            };
-
-            // Don't insert `Transform` / `NetTransform` here.
-            // Those are owned by transform replication (insert/update messages).
-            commands
-                .entity(entity)
-                .insert((
-                    ActiveCharacterVisuals,
-                    Mesh3d(meshes.add(Mesh::from(Capsule3d {
-                        radius: 0.3,
-                        half_length: 0.85,
-                    }))),
-                    MeshMaterial3d(materials.add(StandardMaterial {
-                        base_color,
-                        ..default()
-                    })),
-                ))
-                .with_children(|parent| {
-                    let eye_mesh = meshes.add(Mesh::from(Sphere { radius: 0.12 }));
-                    let eye_mat = materials.add(StandardMaterial {
-                        base_color: Color::srgb(1.0, 1.0, 1.0),
-                        ..default()
-                    });
-
-                    let x = 0.18;
-                    let y = 0.85;
-                    let z = -0.3;
-
-                    parent.spawn((
-                        Name::new("LeftEye"),
-                        Mesh3d(eye_mesh.clone()),
-                        MeshMaterial3d(eye_mat.clone()),
-                        Transform::from_translation(Vec3::new(-x, y, z)),
-                    ));
-                    parent.spawn((
-                        Name::new("RightEye"),
-                        Mesh3d(eye_mesh),
-                        MeshMaterial3d(eye_mat),
-                        Transform::from_translation(Vec3::new(x, y, z)),
-                    ));
-                });
+        println!("on_character_instance_inserted: {:?}", msg.row.actor_id);
+    }
+}
+
+fn on_monster_instance_inserted(
+    mut commands: Commands,
+    mut msgs: ReadInsertMessage<MonsterInstanceRow>,
+    mut oe_mapping: ResMut<ActorEntityMapping>,
+) {
+    for msg in msgs.read() {
+        let entity = ensure_actor_entity(&mut commands, &mut oe_mapping, msg.row.actor_id);
+        ensure_local_remote_tags(&mut commands, entity, false);
+        commands.entity(entity).insert(ActorKind::Monster {
+            archetype_id: msg.row.archetype_id,
+        });
+    }
+}
+
+fn on_character_instance_deleted(
+    mut commands: Commands,
+    mut oe_mapping: ResMut<ActorEntityMapping>,
+    mut msgs: ReadDeleteMessage<CharacterInstanceRow>,
+    local_q: Query<&Transform, With<LocalActor>>,
+    remote_q: Query<&Transform, Without<LocalActor>>,
+) {
+    let local_aoi = local_aoi_block(&local_q);
+    for msg in msgs.read() {
+        despawn_actor(
+            &mut commands,
+            &mut oe_mapping,
+            msg.row.actor_id,
+            local_aoi,
+            &remote_q,
+        );
+    }
+}
+
+fn on_monster_instance_deleted(
+    mut commands: Commands,
+    mut oe_mapping: ResMut<ActorEntityMapping>,
+    mut msgs: ReadDeleteMessage<MonsterInstanceRow>,
+    local_q: Query<&Transform, With<LocalActor>>,
+    remote_q: Query<&Transform, Without<LocalActor>>,
+) {
+    let local_aoi = local_aoi_block(&local_q);
+    for msg in msgs.read() {
+        despawn_actor(
+            &mut commands,
+            &mut oe_mapping,
+            msg.row.actor_id,
+            local_aoi,
+            &remote_q,
+        );
+    }
+}
+
+/// The cell the local actor's AOI block is centered on.
+fn local_aoi_block(local_q: &Query<&Transform, With<LocalActor>>) -> Option<CellId> {
+    local_q
+        .single()
+        .ok()
+        .map(|t| encode_cell_id(t.translation.x, t.translation.z))
+}
+
+/// Removes the actor from the mapping and despawns its entity.
+///
+/// Actors leaving because of distance are at the AOI edge, if it was still inside our block it
+/// vanished for another reason (stealth) so fade it out instead of popping.
+fn despawn_actor(
+    commands: &mut Commands,
+    oe_mapping: &mut ActorEntityMapping,
+    actor_id: ActorId,
+    local_aoi: Option<CellId>,
+    remote_q: &Query<&Transform, Without<LocalActor>>,
+) {
+    let Some(bevy_entity) = oe_mapping.0.remove(&actor_id) else {
+        return;
+    };
+
+    let still_in_aoi = match (local_aoi, remote_q.get(bevy_entity)) {
+        (Some(center), Ok(t)) => {
+            is_in_aoi_block(center, encode_cell_id(t.translation.x, t.translation.z))
         }
+        _ => false,
+    };
 
-        println!("on_character_instance_inserted: {:?}", msg.row.actor_id);
+    if still_in_aoi {
+        commands
+            .entity(bevy_entity)
+            .remove::<ActorEntity>()
+            .insert(FadingOut(Timer::from_seconds(
+                FADE_OUT_SECS,
+                TimerMode::Once,
+            )));
+    } else {
+        commands.entity(bevy_entity).despawn();
+    }
+}
+
+fn fade_out_actors(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut fading_q: Query<(Entity, &mut FadingOut, Option<&ActorVisuals>)>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (entity, mut fading, visuals) in &mut fading_q {
+        fading.0.tick(time.delta());
+        if fading.0.is_finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        if let Some(material) = visuals.and_then(|v| materials.get_mut(&v.material)) {
+            material.base_color.set_alpha(fading.0.fraction_remaining());
+            material.alpha_mode = AlphaMode::Blend;
+        }
+    }
+}
+
+/// Fades stealthed actors. GM invisible actors never reach other clients, the server filters them.
+fn apply_flag_visuals(
+    flags_q: Query<(&Flags, &ActorVisuals), Or<(Changed<Flags>, Added<ActorVisuals>)>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (flags, visuals) in &flags_q {
+        let Some(material) = materials.get_mut(&visuals.material) else {
+            continue;
+        };
+        if flags.0.contains(ActorFlags::STEALTHED) {
+            material.base_color.set_alpha(0.35);
+            material.alpha_mode = AlphaMode::Blend;
+        } else {
+            material.base_color.set_alpha(1.0);
+            material.alpha_mode = AlphaMode::Opaque;
+        }
+    }
+}
+
+pub(crate) fn actor_color(palette: &Palette, kind: ActorKind, is_local: bool) -> Color {
+    match kind {
+        ActorKind::Character if is_local => palette.local,
+        ActorKind::Character => palette.remote,
+        ActorKind::Monster { archetype_id } => {
+            // Stable, distinct-ish tint per archetype until monsters have real assets.
+            let hue = (archetype_id as f32 * 47.0) % 360.0;
+            Color::hsl(hue, 0.6, 0.4)
+        }
     }
 }
 
-fn on_monster_instance_inserted(// mut commands: Commands,
-    // mut msgs: ReadInsertMessage<Monster>,
-    // mut oe_mapping: ResMut<ActorEntityMapping>,
-    // stdb: SpacetimeDB,
+/// Attaches visuals once per entity, as soon as its kind and capsule are known, and again when
+/// it's no longer culled.
+fn attach_actor_visuals(
+    mut commands: Commands,
+    accessibility: Res<Accessibility>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    actor_q: Query<
+        (
+            Entity,
+            &ActorKind,
+            &ActorCapsule,
+            Option<&ActorScale>,
+            Has<LocalActor>,
+        ),
+        (Without<ActorVisuals>, Without<Culled>),
+    >,
 ) {
-    // for msg in msgs.read() {
-    //     // Not sure when this would happen but probably shouldn't allow duplicates
-    //     if oe_mapping.0.contains_key(&msg.row.actor_id) {
-    //         continue;
-    //     }
-
-    //     let entity = if msg.row.identity == stdb.identity() {
-    //         commands.spawn(LocalOwner(msg.row.owner))
-    //     } else {
-    //         commands.spawn(RemoteOwner(msg.row.owner))
-    //     };
-    //     oe_mapping.0.insert(msg.row.owner, entity.id());
-    // }
-}
-
-// mut meshes: ResMut<Assets<Mesh>>,
-// mut materials: ResMut<Assets<StandardMaterial>>,
-// Transform::default(),
-// Mesh3d(meshes.add(Mesh::from(Capsule3d {
-//     radius: 0.3,
-//     half_length: 0.85,
-// }))),
-// MeshMaterial3d(materials.add(StandardMaterial {
-//     base_color,
-//     ..default()
-// })),
-
-// fn on_character_deleted(mut commands: Commands) {
-//     for msg in msgs.read() {
-//         if let Some(bevy_entity) = actor_entity_mapping.0.remove(&msg.row.owner) {
-//             commands.entity(bevy_entity).despawn();
-//         }
-//     }
-// }
+    for (entity, &kind, capsule, scale, is_local) in &actor_q {
+        // Built at size 1, the entity's transform scale brings it to the capsule's size.
+        let scale = scale.copied().unwrap_or_default().0;
+        let capsule = ActorCapsule {
+            radius: capsule.radius / scale,
+            half_height: capsule.half_height / scale,
+        };
+
+        let eye_mesh = meshes.add(Mesh::from(Sphere {
+            radius: capsule.radius * 0.4,
+        }));
+        let eye_mat = materials.add(StandardMaterial {
+            base_color: Color::srgb(1.0, 1.0, 1.0),
+            ..default()
+        });
+
+        let x = capsule.radius * 0.6;
+        let y = capsule.half_height;
+        let z = -capsule.radius;
+
+        let material = materials.add(StandardMaterial {
+            base_color: actor_color(&accessibility.palette, kind, is_local),
+            ..default()
+        });
+        let body = commands
+            .spawn((
+                Name::new("Body"),
+                Mesh3d(meshes.add(Mesh::from(Capsule3d {
+                    radius: capsule.radius,
+                    half_length: capsule.half_height,
+                }))),
+                MeshMaterial3d(material.clone()),
+                Transform::default(),
+            ))
+            .with_children(|parent| {
+                parent.spawn((
+                    Name::new("LeftEye"),
+                    Mesh3d(eye_mesh.clone()),
+                    MeshMaterial3d(eye_mat.clone()),
+                    Transform::from_translation(Vec3::new(-x, y, z)),
+                ));
+                parent.spawn((
+                    Name::new("RightEye"),
+                    Mesh3d(eye_mesh),
+                    MeshMaterial3d(eye_mat),
+                    Transform::from_translation(Vec3::new(x, y, z)),
+                ));
+            })
+            .id();
+
+        // Don't insert `Transform` / `NetTransform` here.
+        // Those are owned by transform replication (insert/update messages).
+        commands
+            .entity(entity)
+            .insert(ActorVisuals { body, material })
+            .add_child(body);
+    }
+}
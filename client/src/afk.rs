@@ -0,0 +1,103 @@
+//! The AFK banner, shown while the server flags the local player idle (see `activity_view`).
+//!
+//! With an idle despawn policy the banner counts down to the removal of the character, any
+//! move or action clears it.
+
+use crate::{cooldown::ServerClock, module_bindings::ActivityRow};
+use bevy::prelude::*;
+use bevy_spacetimedb::{ReadDeleteMessage, ReadInsertMessage, ReadUpdateMessage};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Activity>();
+    app.add_systems(Startup, spawn_afk_banner);
+    app.add_systems(
+        PreUpdate,
+        (
+            on_activity_inserted,
+            on_activity_updated,
+            on_activity_deleted,
+        ),
+    );
+    app.add_systems(Update, update_afk_banner);
+}
+
+/// The local player's row of `activity_view`.
+#[derive(Resource, Debug, Default)]
+struct Activity(Option<ActivityRow>);
+
+#[derive(Component)]
+struct AfkBannerText;
+
+fn spawn_afk_banner(mut commands: Commands) {
+    commands.spawn((
+        AfkBannerText,
+        Text::default(),
+        TextFont {
+            font_size: 20.0,
+            ..default()
+        },
+        TextColor(Color::srgb(1.0, 0.8, 0.3)),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(25.0),
+            width: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        TextLayout::new_with_justify(Justify::Center),
+        Visibility::Hidden,
+        Pickable::IGNORE,
+    ));
+}
+
+fn on_activity_inserted(
+    mut msgs: ReadInsertMessage<ActivityRow>,
+    mut activity: ResMut<Activity>,
+    mut clock: ResMut<ServerClock>,
+) {
+    for msg in msgs.read() {
+        clock.observe(msg.row.last_active_at);
+        activity.0 = Some(msg.row.clone());
+    }
+}
+
+fn on_activity_updated(mut msgs: ReadUpdateMessage<ActivityRow>, mut activity: ResMut<Activity>) {
+    for msg in msgs.read() {
+        activity.0 = Some(msg.new.clone());
+    }
+}
+
+fn on_activity_deleted(mut msgs: ReadDeleteMessage<ActivityRow>, mut activity: ResMut<Activity>) {
+    for _ in msgs.read() {
+        activity.0 = None;
+    }
+}
+
+fn update_afk_banner(
+    activity: Res<Activity>,
+    clock: Res<ServerClock>,
+    banner: Single<(&mut Text, &mut Visibility), With<AfkBannerText>>,
+) {
+    let (mut text, mut visibility) = banner.into_inner();
+    let Some(row) = activity.0.as_ref().filter(|row| row.afk_since.is_some()) else {
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    };
+    visibility.set_if_neq(Visibility::Inherited);
+
+    let value = match &row.despawn_at {
+        Some(despawn_at) => {
+            let secs =
+                (despawn_at.to_micros_since_unix_epoch() - clock.now_micros()).max(0) / 1_000_000;
+            format!(
+                "You are AFK\nLeaving the world in {}:{:02}, move to stay",
+                secs / 60,
+                secs % 60
+            )
+        }
+        None => "You are AFK".to_string(),
+    };
+    if text.0 != value {
+        text.0 = value;
+    }
+}
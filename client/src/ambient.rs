@@ -0,0 +1,207 @@
+//! Zone soundscapes, looping music and ambience crossfaded on zone changes.
+//!
+//! - `zone_ambient_tbl` rows fill [`ZoneAmbients`], the local actor's replicated [`Zone`] picks
+//!   the one in [`CurrentAmbient`]. Zones without a row are silent.
+//! - Each [`AmbientLayer`] loops one track, loaded from `audio/<layer>/<id>.ogg`. A changed track
+//!   fades in over [`CROSSFADE_SECS`] while the previous one fades out, returning to a zone before
+//!   the fade finishes fades the old track back in instead of restarting it.
+//! - The reverb profile is kept in [`CurrentAmbient`] for whatever plays sounds to read, Bevy's
+//!   audio has no effects so nothing applies it yet.
+
+use crate::{
+    LocalActor,
+    actor::Zone,
+    module_bindings::{ReverbProfile, ZoneAmbientRow},
+};
+use bevy::{audio::Volume, platform::collections::HashMap, prelude::*};
+use bevy_spacetimedb::{ReadDeleteMessage, ReadInsertMessage, ReadUpdateMessage};
+
+/// Time (seconds) a track takes to fade in or out.
+const CROSSFADE_SECS: f32 = 2.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<ZoneAmbients>();
+    app.init_resource::<CurrentAmbient>();
+    app.add_systems(
+        PreUpdate,
+        (
+            on_zone_ambient_inserted,
+            on_zone_ambient_updated,
+            on_zone_ambient_deleted,
+        ),
+    );
+    app.add_systems(
+        Update,
+        (
+            resolve_current_ambient,
+            switch_tracks.run_if(resource_changed::<CurrentAmbient>),
+            crossfade,
+        )
+            .chain(),
+    );
+}
+
+/// The soundscape of a zone, see `zone_ambient_tbl`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ZoneAmbient {
+    pub music_track_id: Option<u16>,
+    pub ambient_sound_set: Option<u16>,
+    pub reverb: ReverbProfile,
+}
+
+impl From<&ZoneAmbientRow> for ZoneAmbient {
+    fn from(row: &ZoneAmbientRow) -> Self {
+        Self {
+            music_track_id: row.music_track_id,
+            ambient_sound_set: row.ambient_sound_set,
+            reverb: row.reverb.clone(),
+        }
+    }
+}
+
+/// Soundscapes by zone (instance) id.
+#[derive(Resource, Debug, Default)]
+pub struct ZoneAmbients(pub HashMap<u32, ZoneAmbient>);
+
+/// The soundscape of the local actor's zone, `None` when it's silent.
+#[derive(Resource, Debug, Default, PartialEq)]
+pub struct CurrentAmbient(pub Option<ZoneAmbient>);
+
+/// The tracks of a soundscape playing at the same time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AmbientLayer {
+    Music,
+    Ambience,
+}
+
+impl AmbientLayer {
+    const ALL: [Self; 2] = [Self::Music, Self::Ambience];
+
+    fn path(self, id: u16) -> String {
+        match self {
+            Self::Music => format!("audio/music/{id}.ogg"),
+            Self::Ambience => format!("audio/ambience/{id}.ogg"),
+        }
+    }
+
+    /// Linear volume of the layer when fully faded in.
+    fn volume(self) -> f32 {
+        match self {
+            Self::Music => 0.5,
+            Self::Ambience => 0.8,
+        }
+    }
+
+    fn track_id(self, ambient: &ZoneAmbient) -> Option<u16> {
+        match self {
+            Self::Music => ambient.music_track_id,
+            Self::Ambience => ambient.ambient_sound_set,
+        }
+    }
+}
+
+/// A looping track of a layer, fading in or out.
+#[derive(Component, Debug)]
+struct AmbientTrack {
+    layer: AmbientLayer,
+    id: u16,
+    fading_in: bool,
+    /// Current fade level, `0.0` silent to `1.0` at the layer's volume.
+    level: f32,
+}
+
+fn on_zone_ambient_inserted(
+    mut msgs: ReadInsertMessage<ZoneAmbientRow>,
+    mut ambients: ResMut<ZoneAmbients>,
+) {
+    for msg in msgs.read() {
+        ambients.0.insert(msg.row.instance_id, (&msg.row).into());
+    }
+}
+
+fn on_zone_ambient_updated(
+    mut msgs: ReadUpdateMessage<ZoneAmbientRow>,
+    mut ambients: ResMut<ZoneAmbients>,
+) {
+    for msg in msgs.read() {
+        ambients.0.insert(msg.new.instance_id, (&msg.new).into());
+    }
+}
+
+fn on_zone_ambient_deleted(
+    mut msgs: ReadDeleteMessage<ZoneAmbientRow>,
+    mut ambients: ResMut<ZoneAmbients>,
+) {
+    for msg in msgs.read() {
+        ambients.0.remove(&msg.row.instance_id);
+    }
+}
+
+fn resolve_current_ambient(
+    local_q: Query<&Zone, With<LocalActor>>,
+    ambients: Res<ZoneAmbients>,
+    mut current: ResMut<CurrentAmbient>,
+) {
+    let ambient = local_q
+        .single()
+        .ok()
+        .and_then(|zone| ambients.0.get(&zone.0))
+        .cloned();
+    current.set_if_neq(CurrentAmbient(ambient));
+}
+
+fn switch_tracks(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    current: Res<CurrentAmbient>,
+    mut track_q: Query<&mut AmbientTrack>,
+) {
+    for layer in AmbientLayer::ALL {
+        let wanted = current
+            .0
+            .as_ref()
+            .and_then(|ambient| layer.track_id(ambient));
+        let mut playing = false;
+        for mut track in track_q.iter_mut().filter(|track| track.layer == layer) {
+            track.fading_in = Some(track.id) == wanted;
+            playing |= track.fading_in;
+        }
+        let Some(id) = wanted.filter(|_| !playing) else {
+            continue;
+        };
+        commands.spawn((
+            AmbientTrack {
+                layer,
+                id,
+                fading_in: true,
+                level: 0.0,
+            },
+            AudioPlayer::new(asset_server.load(layer.path(id))),
+            PlaybackSettings::LOOP.with_volume(Volume::SILENT),
+        ));
+    }
+}
+
+fn crossfade(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut track_q: Query<(Entity, &mut AmbientTrack, Option<&mut AudioSink>)>,
+) {
+    let step = time.delta_secs() / CROSSFADE_SECS;
+    for (entity, mut track, sink) in &mut track_q {
+        let level = if track.fading_in {
+            (track.level + step).min(1.0)
+        } else {
+            (track.level - step).max(0.0)
+        };
+        if !track.fading_in && level <= 0.0 {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        track.level = level;
+        // The sink shows up once the track's asset loaded, it starts out silent.
+        if let Some(mut sink) = sink {
+            sink.set_volume(Volume::Linear(level * track.layer.volume()));
+        }
+    }
+}
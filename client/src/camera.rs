@@ -1,4 +1,7 @@
-use crate::actor::LocalActor;
+use crate::{
+    accessibility::Accessibility, actor::LocalActor, loading::LoadState,
+    presentation::PresentationConfig, spectator::is_spectating,
+};
 use bevy::{
     camera::Exposure,
     pbr::{AtmosphereMode, AtmosphereSettings},
@@ -7,11 +10,15 @@ use bevy::{
 
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(Startup, add_camera);
-    app.add_systems(PostUpdate, follow_player);
+    app.add_systems(
+        PostUpdate,
+        follow_player
+            .run_if(not(is_spectating))
+            .run_if(in_state(LoadState::Spawned)),
+    );
 }
 
-const CAMERA_OFFSET_GLOBAL: Vec3 = Vec3::new(0.0, 25.0, -10.0);
-const CAMERA_DECAY_RATE: f32 = 44.0;
+pub(crate) const CAMERA_OFFSET_GLOBAL: Vec3 = Vec3::new(0.0, 25.0, -10.0);
 
 fn add_camera(mut commands: Commands) {
     commands.spawn((
@@ -40,13 +47,18 @@ fn follow_player(
     mut camera_query: Query<&mut Transform, With<Camera3d>>,
     local_owner: Single<&Transform, (With<LocalActor>, Without<Camera3d>)>,
     time: Res<Time>,
+    config: Res<PresentationConfig>,
+    accessibility: Res<Accessibility>,
 ) {
     let Ok(mut cam_tf) = camera_query.single_mut() else {
         return;
     };
 
     let target = local_owner.translation + CAMERA_OFFSET_GLOBAL;
-    cam_tf
-        .translation
-        .smooth_nudge(&target, CAMERA_DECAY_RATE, time.delta_secs());
+    accessibility.follow(
+        &mut cam_tf.translation,
+        target,
+        config.camera_decay_rate,
+        time.delta_secs(),
+    );
 }
@@ -0,0 +1,141 @@
+//! Screenshot and clip capture for bug reports.
+//!
+//! - F12 saves a screenshot to `captures/screenshot-<unix millis>.png`.
+//! - F9 toggles clip recording: while on, the last [`CLIP_SECONDS`] are kept in memory at
+//!   [`CLIP_FPS`]. F10 saves them as numbered PNGs to `captures/clip-<unix millis>/`.
+//!
+//! Clip frames are full window sized images, recording is off by default to not hold on to them.
+//! Native only, the web build has no file system to write to.
+
+use crate::command::command_line_closed;
+use bevy::{
+    prelude::*,
+    render::view::screenshot::{Screenshot, ScreenshotCaptured, save_to_disk},
+    tasks::IoTaskPool,
+};
+use std::{
+    collections::VecDeque,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const CAPTURE_DIR: &str = "captures";
+
+/// Frames per second captured while recording a clip.
+const CLIP_FPS: f32 = 5.0;
+
+/// Length of the rolling clip (seconds).
+const CLIP_SECONDS: f32 = 5.0;
+
+const CLIP_FRAMES: usize = (CLIP_FPS * CLIP_SECONDS) as usize;
+
+pub(super) fn plugin(app: &mut App) {
+    app.insert_resource(ClipRecorder {
+        recording: false,
+        timer: Timer::from_seconds(1.0 / CLIP_FPS, TimerMode::Repeating),
+        frames: VecDeque::with_capacity(CLIP_FRAMES),
+    });
+    app.add_systems(
+        Update,
+        (
+            (take_screenshot, toggle_clip_recording, save_clip).run_if(command_line_closed),
+            record_clip_frame,
+        ),
+    );
+}
+
+/// The rolling clip, see the module docs.
+#[derive(Resource)]
+struct ClipRecorder {
+    recording: bool,
+    timer: Timer,
+    frames: VecDeque<Image>,
+}
+
+/// Unique enough for manual captures, and sorts by time.
+fn timestamped_path(prefix: &str, extension: &str) -> PathBuf {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_millis())
+        .unwrap_or_default();
+    PathBuf::from(CAPTURE_DIR).join(format!("{prefix}-{millis}{extension}"))
+}
+
+fn take_screenshot(mut commands: Commands, keys: Res<ButtonInput<KeyCode>>) {
+    if !keys.just_pressed(KeyCode::F12) {
+        return;
+    }
+    if let Err(err) = std::fs::create_dir_all(CAPTURE_DIR) {
+        error!("Unable to create {CAPTURE_DIR}: {err}");
+        return;
+    }
+    let path = timestamped_path("screenshot", ".png");
+    info!("Saving screenshot to {}", path.display());
+    commands
+        .spawn(Screenshot::primary_window())
+        .observe(save_to_disk(path));
+}
+
+fn toggle_clip_recording(keys: Res<ButtonInput<KeyCode>>, mut recorder: ResMut<ClipRecorder>) {
+    if !keys.just_pressed(KeyCode::F9) {
+        return;
+    }
+    recorder.recording = !recorder.recording;
+    recorder.frames.clear();
+    recorder.timer.reset();
+    info!(
+        "Clip recording {}",
+        if recorder.recording { "on" } else { "off" }
+    );
+}
+
+fn record_clip_frame(mut commands: Commands, time: Res<Time>, mut recorder: ResMut<ClipRecorder>) {
+    if !recorder.recording || !recorder.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    commands.spawn(Screenshot::primary_window()).observe(
+        |captured: On<ScreenshotCaptured>, mut recorder: ResMut<ClipRecorder>| {
+            // Toggled off while the frame was in flight.
+            if !recorder.recording {
+                return;
+            }
+            if recorder.frames.len() == CLIP_FRAMES {
+                recorder.frames.pop_front();
+            }
+            recorder.frames.push_back(captured.image.clone());
+        },
+    );
+}
+
+/// Writes the recorded frames off the main thread, recording goes on.
+fn save_clip(keys: Res<ButtonInput<KeyCode>>, recorder: Res<ClipRecorder>) {
+    if !keys.just_pressed(KeyCode::F10) {
+        return;
+    }
+    if recorder.frames.is_empty() {
+        warn!("No clip frames recorded, toggle recording with F9 first");
+        return;
+    }
+    let dir = timestamped_path("clip", "");
+    let frames: Vec<Image> = recorder.frames.iter().cloned().collect();
+    info!("Saving {} clip frames to {}", frames.len(), dir.display());
+    IoTaskPool::get()
+        .spawn(async move {
+            if let Err(err) = std::fs::create_dir_all(&dir) {
+                error!("Unable to create {}: {err}", dir.display());
+                return;
+            }
+            for (i, frame) in frames.into_iter().enumerate() {
+                let path = dir.join(format!("frame-{i:03}.png"));
+                let saved = frame
+                    .try_into_dynamic()
+                    .map_err(|err| err.to_string())
+                    .and_then(|image| image.to_rgb8().save(&path).map_err(|err| err.to_string()));
+                if let Err(err) = saved {
+                    error!("Unable to save {}: {err}", path.display());
+                    return;
+                }
+            }
+        })
+        .detach();
+}
@@ -0,0 +1,269 @@
+//! A window listing the local character's combat log, see the server's `combat_log_view`.
+//!
+//! Toggled with `L`. The filter buttons narrow the entries down, the list scrolls with the mouse
+//! wheel and shows the newest entry first.
+
+use crate::{
+    command::command_line_closed,
+    module_bindings::{CombatLogKind, CombatLogRow},
+};
+use bevy::{
+    input::mouse::MouseScrollUnit,
+    picking::events::{Pointer, Scroll},
+    platform::collections::HashMap,
+    prelude::*,
+};
+use bevy_spacetimedb::{ReadDeleteMessage, ReadInsertMessage};
+
+const WINDOW_WIDTH: f32 = 420.0;
+const LIST_HEIGHT: f32 = 260.0;
+
+/// Pixels scrolled per wheel line.
+const LINE_HEIGHT: f32 = 16.0;
+
+const PANEL_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.7);
+const BUTTON_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.1);
+const SELECTED_BUTTON_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.35);
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<CombatLog>();
+    app.init_resource::<CombatLogFilter>();
+    app.add_systems(Startup, spawn_combat_log_window);
+    app.add_systems(PreUpdate, (on_combat_log_inserted, on_combat_log_deleted));
+    app.add_systems(
+        Update,
+        (
+            toggle_combat_log_window.run_if(command_line_closed),
+            select_filter,
+            update_combat_log_text,
+        )
+            .chain(),
+    );
+}
+
+/// The rows of `combat_log_view` by id.
+#[derive(Resource, Debug, Default)]
+struct CombatLog(HashMap<u64, CombatLogRow>);
+
+/// Which entries the window lists.
+#[derive(Resource, Component, Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum CombatLogFilter {
+    #[default]
+    All,
+    /// Damage and healing done by the local character.
+    Dealt,
+    /// Damage and healing done to the local character.
+    Taken,
+    Heals,
+    Deaths,
+}
+
+impl CombatLogFilter {
+    const ALL: [Self; 5] = [
+        Self::All,
+        Self::Dealt,
+        Self::Taken,
+        Self::Heals,
+        Self::Deaths,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::All => "All",
+            Self::Dealt => "Dealt",
+            Self::Taken => "Taken",
+            Self::Heals => "Heals",
+            Self::Deaths => "Deaths",
+        }
+    }
+
+    fn matches(self, entry: &CombatLogRow) -> bool {
+        match self {
+            Self::All => true,
+            Self::Dealt => entry.outgoing,
+            Self::Taken => !entry.outgoing,
+            Self::Heals => entry.kind == CombatLogKind::Heal,
+            Self::Deaths => entry.kind == CombatLogKind::Death,
+        }
+    }
+}
+
+#[derive(Component)]
+struct CombatLogWindow;
+
+#[derive(Component)]
+struct CombatLogText;
+
+fn spawn_combat_log_window(mut commands: Commands) {
+    commands
+        .spawn((
+            CombatLogWindow,
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(12.0),
+                top: Val::Px(12.0),
+                width: Val::Px(WINDOW_WIDTH),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(6.0),
+                padding: UiRect::all(Val::Px(6.0)),
+                ..default()
+            },
+            BackgroundColor(PANEL_COLOR),
+            Visibility::Hidden,
+        ))
+        .with_children(|window| {
+            window
+                .spawn(Node {
+                    column_gap: Val::Px(4.0),
+                    ..default()
+                })
+                .with_children(|buttons| {
+                    for filter in CombatLogFilter::ALL {
+                        buttons
+                            .spawn((
+                                filter,
+                                Button,
+                                Node {
+                                    padding: UiRect::axes(Val::Px(8.0), Val::Px(2.0)),
+                                    ..default()
+                                },
+                                BackgroundColor(BUTTON_COLOR),
+                            ))
+                            .with_child((
+                                Text::new(filter.label()),
+                                TextFont {
+                                    font_size: 13.0,
+                                    ..default()
+                                },
+                                Pickable::IGNORE,
+                            ));
+                    }
+                });
+            window
+                .spawn((
+                    Node {
+                        height: Val::Px(LIST_HEIGHT),
+                        overflow: Overflow::scroll_y(),
+                        ..default()
+                    },
+                    ScrollPosition::default(),
+                ))
+                .observe(scroll_combat_log)
+                .with_child((
+                    CombatLogText,
+                    Text::default(),
+                    TextFont {
+                        font_size: 13.0,
+                        ..default()
+                    },
+                    Pickable::IGNORE,
+                ));
+        });
+}
+
+fn scroll_combat_log(scroll: On<Pointer<Scroll>>, mut scroll_q: Query<&mut ScrollPosition>) {
+    let Ok(mut position) = scroll_q.get_mut(scroll.entity) else {
+        return;
+    };
+    let dy = match scroll.unit {
+        MouseScrollUnit::Line => scroll.y * LINE_HEIGHT,
+        MouseScrollUnit::Pixel => scroll.y,
+    };
+    // Clamped to the content by the layout.
+    position.y = (position.y - dy).max(0.0);
+}
+
+fn on_combat_log_inserted(mut msgs: ReadInsertMessage<CombatLogRow>, mut log: ResMut<CombatLog>) {
+    for msg in msgs.read() {
+        log.0.insert(msg.row.id, msg.row.clone());
+    }
+}
+
+fn on_combat_log_deleted(mut msgs: ReadDeleteMessage<CombatLogRow>, mut log: ResMut<CombatLog>) {
+    for msg in msgs.read() {
+        log.0.remove(&msg.row.id);
+    }
+}
+
+fn toggle_combat_log_window(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut window_q: Query<&mut Visibility, With<CombatLogWindow>>,
+) {
+    if !keys.just_pressed(KeyCode::KeyL) {
+        return;
+    }
+    for mut visibility in &mut window_q {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Inherited,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+fn select_filter(
+    mut selected: ResMut<CombatLogFilter>,
+    mut button_q: Query<(&CombatLogFilter, &Interaction, &mut BackgroundColor)>,
+) {
+    for (&filter, interaction, _) in &button_q {
+        if *interaction == Interaction::Pressed {
+            selected.set_if_neq(filter);
+        }
+    }
+    for (&filter, _, mut color) in &mut button_q {
+        let wanted = if filter == *selected {
+            SELECTED_BUTTON_COLOR
+        } else {
+            BUTTON_COLOR
+        };
+        color.set_if_neq(BackgroundColor(wanted));
+    }
+}
+
+/// `HH:MM:SS` (UTC) of a server timestamp.
+fn format_time_of_day(micros: i64) -> String {
+    let secs = micros.div_euclid(1_000_000).rem_euclid(86_400);
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+fn format_entry(entry: &CombatLogRow) -> String {
+    let time = format_time_of_day(entry.timestamp.to_micros_since_unix_epoch());
+    let source = entry.source_name.as_deref().unwrap_or("Environment");
+    let target = &entry.target_name;
+    let amount = entry.amount;
+    match entry.kind {
+        CombatLogKind::Damage => format!("{time} {source} hits {target} for {amount}"),
+        CombatLogKind::CriticalDamage => {
+            format!("{time} {source} critically hits {target} for {amount}")
+        }
+        CombatLogKind::Heal => format!("{time} {source} heals {target} for {amount}"),
+        CombatLogKind::FallDamage => format!("{time} {target} takes {amount} fall damage"),
+        CombatLogKind::Death => match &entry.source_name {
+            Some(source) => format!("{time} {target} is killed by {source}"),
+            None => format!("{time} {target} dies"),
+        },
+    }
+}
+
+fn update_combat_log_text(
+    log: Res<CombatLog>,
+    filter: Res<CombatLogFilter>,
+    mut text_q: Query<&mut Text, With<CombatLogText>>,
+) {
+    if !log.is_changed() && !filter.is_changed() {
+        return;
+    }
+    let Ok(mut text) = text_q.single_mut() else {
+        return;
+    };
+    let mut entries: Vec<&CombatLogRow> = log
+        .0
+        .values()
+        .filter(|entry| filter.matches(entry))
+        .collect();
+    entries.sort_unstable_by(|a, b| b.id.cmp(&a.id));
+    text.0 = entries
+        .into_iter()
+        .map(format_entry)
+        .collect::<Vec<_>>()
+        .join("\n");
+}
@@ -0,0 +1,135 @@
+//! Floating combat text: damage and heal numbers that rise above their target and fade out.
+//!
+//! Driven by inserts into the server's `combat_event_view`, rows are deleted by the server shortly
+//! after so only inserts are handled.
+
+use crate::{
+    ActorEntityMapping,
+    module_bindings::{CombatEventKind, CombatEventRow},
+};
+use bevy::prelude::*;
+use bevy_spacetimedb::ReadInsertMessage;
+
+/// How long a number stays on screen.
+const LIFETIME_SECS: f32 = 1.2;
+
+/// Height above the target's origin the number starts at.
+const START_HEIGHT: f32 = 2.0;
+
+/// How far (meters) the number rises over its lifetime.
+const RISE_HEIGHT: f32 = 1.0;
+
+const FONT_SIZE: f32 = 20.0;
+
+/// Critical hits stand out with bigger numbers.
+const CRITICAL_FONT_SIZE: f32 = 28.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(Update, (spawn_combat_text, update_combat_text).chain());
+}
+
+#[derive(Component, Debug)]
+pub struct FloatingCombatText {
+    /// The target entity to follow, if it's still around.
+    target: Option<Entity>,
+    /// Where the target was when the event happened, used once the target is gone.
+    position: Vec3,
+    elapsed: f32,
+    color: Color,
+}
+
+fn spawn_combat_text(
+    mut commands: Commands,
+    mut msgs: ReadInsertMessage<CombatEventRow>,
+    oe_mapping: Res<ActorEntityMapping>,
+) {
+    for msg in msgs.read() {
+        let event = &msg.row;
+        let (text, color, font_size) = match event.kind {
+            CombatEventKind::Damage => (
+                format!("{}", event.amount),
+                Color::srgb(1.0, 0.85, 0.2),
+                FONT_SIZE,
+            ),
+            CombatEventKind::CriticalDamage => (
+                format!("{}!", event.amount),
+                Color::srgb(1.0, 0.45, 0.1),
+                CRITICAL_FONT_SIZE,
+            ),
+            CombatEventKind::Heal => (
+                format!("+{}", event.amount),
+                Color::srgb(0.3, 1.0, 0.4),
+                FONT_SIZE,
+            ),
+            CombatEventKind::FallDamage => (
+                format!("{} (fall)", event.amount),
+                Color::srgb(0.85, 0.75, 0.6),
+                FONT_SIZE,
+            ),
+        };
+        commands.spawn((
+            FloatingCombatText {
+                target: oe_mapping.0.get(&event.target).copied(),
+                position: event.position.clone().into(),
+                elapsed: 0.0,
+                color,
+            },
+            Text::new(text),
+            TextFont {
+                font_size,
+                ..default()
+            },
+            TextColor(color),
+            Node {
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            // Hidden until positioned, avoids a frame at the top left corner.
+            Visibility::Hidden,
+        ));
+    }
+}
+
+fn update_combat_text(
+    mut commands: Commands,
+    time: Res<Time>,
+    ui_scale: Res<UiScale>,
+    camera: Single<(&Camera, &GlobalTransform), With<Camera3d>>,
+    transforms: Query<&GlobalTransform, Without<Camera3d>>,
+    mut texts: Query<(
+        Entity,
+        &mut FloatingCombatText,
+        &mut Node,
+        &mut TextColor,
+        &mut Visibility,
+    )>,
+) {
+    let (camera, camera_transform) = *camera;
+    for (entity, mut combat_text, mut node, mut text_color, mut visibility) in &mut texts {
+        combat_text.elapsed += time.delta_secs();
+        let t = combat_text.elapsed / LIFETIME_SECS;
+        if t >= 1.0 {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        if let Some(target) = combat_text.target {
+            match transforms.get(target) {
+                Ok(transform) => combat_text.position = transform.translation(),
+                Err(_) => combat_text.target = None,
+            }
+        }
+
+        let world_pos = combat_text.position + Vec3::Y * (START_HEIGHT + RISE_HEIGHT * t);
+        let Ok(viewport_pos) = camera.world_to_viewport(camera_transform, world_pos) else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        // UI values are scaled by `UiScale`, viewport positions aren't.
+        let ui_pos = viewport_pos / ui_scale.0;
+        node.left = Val::Px(ui_pos.x);
+        node.top = Val::Px(ui_pos.y);
+        text_color.0 = combat_text.color.with_alpha(1.0 - t * t);
+        *visibility = Visibility::Inherited;
+    }
+}
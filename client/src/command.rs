@@ -0,0 +1,440 @@
+//! The chat box style command line.
+//!
+//! Enter (or `/`) opens it, Enter runs the typed `/command` and Escape cancels. Commands are
+//! parsed and validated client side into a [`Command`] and routed to their reducer, usage errors
+//! and `/help` are shown above the input. Other keyboard input is left alone while the command
+//! line is open, see [`command_line_closed`].
+
+use crate::{
+    LocalActor,
+    module_bindings::{
+        DbConnection, EmoteKind, MoveIntentData, WhoFilter, accept_duel, cancel_move,
+        create_character, delete_player_setting, emote, enter_game, enter_instance,
+        enter_spectator, leave_spectator, request_duel, request_unstuck, set_fly_mode,
+        set_player_setting, spawn_monster, who,
+    },
+    movement::{ClientIntentSeq, IntentBuffer},
+    net_audit::{DEFAULT_AUDIT_SECS, MAX_AUDIT_SECS, NetAudit},
+    server::SpacetimeDB,
+};
+use bevy::{
+    input::{
+        ButtonState,
+        keyboard::{Key, KeyboardInput},
+    },
+    prelude::*,
+};
+use bevy_spacetimedb::StdbConnection;
+use std::f32::consts::TAU;
+
+/// Upper bound for `/spawn_fake`, it's one reducer call per monster.
+const MAX_FAKE_SPAWNS: u16 = 100;
+
+/// Distance (meters) between fake spawns, they're placed on a spiral around the local actor.
+const FAKE_SPAWN_SPACING: f32 = 2.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<CommandLine>();
+    app.add_systems(Startup, spawn_command_line);
+    app.add_systems(
+        Update,
+        (edit_command_line, update_command_line_text).chain(),
+    );
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct CommandLine {
+    pub open: bool,
+    text: String,
+    /// Help or error text of the last command.
+    output: String,
+}
+
+/// Run condition for systems reading the keyboard, they shouldn't react to typed commands.
+pub fn command_line_closed(command_line: Res<CommandLine>) -> bool {
+    !command_line.open
+}
+
+struct CommandHelp {
+    name: &'static str,
+    usage: &'static str,
+    description: &'static str,
+}
+
+/// Listed by `/help`, in that order.
+const COMMANDS: &[CommandHelp] = &[
+    CommandHelp {
+        name: "help",
+        usage: "/help",
+        description: "Lists the commands",
+    },
+    CommandHelp {
+        name: "create_character",
+        usage: "/create_character <name>",
+        description: "Creates a character",
+    },
+    CommandHelp {
+        name: "enter",
+        usage: "/enter [character id]",
+        description: "Enters the world with a character, the first one by default",
+    },
+    CommandHelp {
+        name: "stop",
+        usage: "/stop",
+        description: "Cancels the current move",
+    },
+    CommandHelp {
+        name: "stuck",
+        usage: "/stuck",
+        description: "Moves your character out of geometry it's wedged in, once a minute",
+    },
+    CommandHelp {
+        name: "who",
+        usage: "/who [name] [level:N | level:MIN-MAX] [guild:TAG] [zone:NAME]",
+        description: "Lists the players in the world",
+    },
+    CommandHelp {
+        name: "tele",
+        usage: "/tele <instance id>",
+        description: "Moves to the nearest spawn point of an instance",
+    },
+    CommandHelp {
+        name: "duel",
+        usage: "/duel [accept]",
+        description: "Challenges the target to a duel, or accepts a challenge",
+    },
+    CommandHelp {
+        name: "emote",
+        usage: "/emote <wave|bow|cheer|dance>",
+        description: "Plays an emote",
+    },
+    CommandHelp {
+        name: "set",
+        usage: "/set <key> [value]",
+        description: "Sets an account setting, or resets it without a value",
+    },
+    CommandHelp {
+        name: "fly",
+        usage: "/fly <on|off>",
+        description: "Toggles GM fly mode, clicks then move through the air (admin)",
+    },
+    CommandHelp {
+        name: "spectate",
+        usage: "/spectate [instance id | off]",
+        description: "Flies a free camera over an instance, the overworld by default (admin)",
+    },
+    CommandHelp {
+        name: "netaudit",
+        usage: "/netaudit [seconds | off]",
+        description: "Samples replicated row traffic and reports the bytes per actor and table",
+    },
+    CommandHelp {
+        name: "spawn_fake",
+        usage: "/spawn_fake <count> [archetype id]",
+        description: "Spawns monsters around you (admin)",
+    },
+];
+
+/// A parsed and validated slash command, see [`Command::parse`].
+#[derive(Debug)]
+enum Command {
+    Help,
+    CreateCharacter { name: String },
+    Enter { character_id: u32 },
+    Stop,
+    Stuck,
+    Who(WhoFilter),
+    Tele { instance_id: u32 },
+    Duel,
+    AcceptDuel,
+    Emote(EmoteKind),
+    SetSetting { key: String, value: String },
+    ResetSetting { key: String },
+    Fly { enabled: bool },
+    Spectate { instance_id: u32 },
+    StopSpectating,
+    SpawnFake { count: u16, archetype_id: u16 },
+    NetAudit { secs: u32 },
+    StopNetAudit,
+}
+
+impl Command {
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut words = text.split_whitespace();
+        let Some(name) = words.next().and_then(|word| word.strip_prefix('/')) else {
+            return Err("Commands start with `/`, try /help".into());
+        };
+        let args: Vec<&str> = words.collect();
+
+        match (name, args.as_slice()) {
+            ("help", []) => Ok(Self::Help),
+            ("create_character", [name]) => Ok(Self::CreateCharacter {
+                name: name.to_string(),
+            }),
+            ("enter", []) => Ok(Self::Enter { character_id: 1 }),
+            ("enter", [character_id]) => Ok(Self::Enter {
+                character_id: parse_arg(character_id, "character id")?,
+            }),
+            ("stop", []) => Ok(Self::Stop),
+            ("stuck", []) => Ok(Self::Stuck),
+            ("who", args) => Ok(Self::Who(parse_who_filter(args)?)),
+            ("tele", [instance_id]) => Ok(Self::Tele {
+                instance_id: parse_arg(instance_id, "instance id")?,
+            }),
+            ("duel", []) => Ok(Self::Duel),
+            ("duel", ["accept"]) => Ok(Self::AcceptDuel),
+            ("emote", ["wave"]) => Ok(Self::Emote(EmoteKind::Wave)),
+            ("emote", ["bow"]) => Ok(Self::Emote(EmoteKind::Bow)),
+            ("emote", ["cheer"]) => Ok(Self::Emote(EmoteKind::Cheer)),
+            ("emote", ["dance"]) => Ok(Self::Emote(EmoteKind::Dance)),
+            ("set", [key]) => Ok(Self::ResetSetting {
+                key: key.to_string(),
+            }),
+            ("set", [key, value @ ..]) => Ok(Self::SetSetting {
+                key: key.to_string(),
+                value: value.join(" "),
+            }),
+            ("fly", ["on"]) => Ok(Self::Fly { enabled: true }),
+            ("fly", ["off"]) => Ok(Self::Fly { enabled: false }),
+            ("spectate", []) => Ok(Self::Spectate { instance_id: 0 }),
+            ("spectate", ["off"]) => Ok(Self::StopSpectating),
+            ("spectate", [instance_id]) => Ok(Self::Spectate {
+                instance_id: parse_arg(instance_id, "instance id")?,
+            }),
+            ("netaudit", []) => Ok(Self::NetAudit {
+                secs: DEFAULT_AUDIT_SECS,
+            }),
+            ("netaudit", ["off"]) => Ok(Self::StopNetAudit),
+            ("netaudit", [secs]) => {
+                let secs: u32 = parse_arg(secs, "seconds")?;
+                if !(1..=MAX_AUDIT_SECS).contains(&secs) {
+                    return Err(format!("Seconds must be 1–{MAX_AUDIT_SECS}"));
+                }
+                Ok(Self::NetAudit { secs })
+            }
+            ("spawn_fake", [count, rest @ ..]) if rest.len() <= 1 => {
+                let count: u16 = parse_arg(count, "count")?;
+                if !(1..=MAX_FAKE_SPAWNS).contains(&count) {
+                    return Err(format!("Count must be 1–{MAX_FAKE_SPAWNS}"));
+                }
+                let archetype_id = match rest {
+                    [archetype_id] => parse_arg(archetype_id, "archetype id")?,
+                    _ => 1,
+                };
+                Ok(Self::SpawnFake {
+                    count,
+                    archetype_id,
+                })
+            }
+            (name, _) => match COMMANDS.iter().find(|command| command.name == name) {
+                Some(command) => Err(format!("Usage: {}", command.usage)),
+                None => Err(format!("Unknown command /{name}, try /help")),
+            },
+        }
+    }
+
+    /// Calls the command's reducer, returns the text to show on success.
+    fn run(
+        self,
+        stdb: &StdbConnection<DbConnection>,
+        intent_seq: &mut ClientIntentSeq,
+        intent_buffer: &mut IntentBuffer,
+        net_audit: &mut NetAudit,
+        local_translation: Option<Vec3>,
+    ) -> Result<String, String> {
+        let reducers = stdb.reducers();
+        let called = match self {
+            Self::Help => {
+                return Ok(COMMANDS
+                    .iter()
+                    .map(|command| format!("{} - {}", command.usage, command.description))
+                    .collect::<Vec<_>>()
+                    .join("\n"));
+            }
+            Self::CreateCharacter { name } => reducers.create_character(name),
+            Self::Enter { character_id } => reducers.enter_game(character_id),
+            Self::Stop => {
+                let seq = intent_seq.next();
+                reducers
+                    .cancel_move(seq)
+                    .inspect(|_| intent_buffer.push(seq, MoveIntentData::None))
+            }
+            Self::Stuck => reducers.request_unstuck(),
+            Self::Who(filter) => reducers.who(filter),
+            Self::Tele { instance_id } => reducers.enter_instance(instance_id),
+            Self::Duel => reducers.request_duel(None),
+            Self::AcceptDuel => reducers.accept_duel(),
+            Self::Emote(kind) => reducers.emote(kind),
+            Self::SetSetting { key, value } => reducers.set_player_setting(key, value),
+            Self::ResetSetting { key } => reducers.delete_player_setting(key),
+            Self::Fly { enabled } => reducers.set_fly_mode(enabled),
+            // Starts above the local actor, or the world origin without one.
+            Self::Spectate { instance_id } => {
+                reducers.enter_spectator(instance_id, local_translation.unwrap_or_default().into())
+            }
+            Self::StopSpectating => reducers.leave_spectator(),
+            Self::NetAudit { secs } => {
+                net_audit.start(secs);
+                return Ok(format!("Sampling replication for {secs}s"));
+            }
+            Self::StopNetAudit => {
+                net_audit.stop();
+                Ok(())
+            }
+            Self::SpawnFake {
+                count,
+                archetype_id,
+            } => {
+                let Some(center) = local_translation else {
+                    return Err("Enter the world first".into());
+                };
+                (0..count).try_for_each(|i| {
+                    reducers.spawn_monster(archetype_id, fake_spawn_translation(center, i).into())
+                })
+            }
+        };
+        called.map(|_| String::new()).map_err(|err| err.to_string())
+    }
+}
+
+fn parse_arg<T: std::str::FromStr>(arg: &str, what: &str) -> Result<T, String> {
+    arg.parse().map_err(|_| format!("Invalid {what}: {arg}"))
+}
+
+/// `[name] [level:N | level:MIN-MAX] [guild:TAG] [zone:NAME]`
+fn parse_who_filter(args: &[&str]) -> Result<WhoFilter, String> {
+    let mut filter = WhoFilter {
+        name: None,
+        min_level: None,
+        max_level: None,
+        guild_tag: None,
+        zone: None,
+    };
+    for arg in args {
+        if let Some(levels) = arg.strip_prefix("level:") {
+            let (min, max) = levels.split_once('-').unwrap_or((levels, levels));
+            let (min, max): (u8, u8) = (parse_arg(min, "level")?, parse_arg(max, "level")?);
+            if min > max {
+                return Err(format!("Invalid level range: {levels}"));
+            }
+            filter.min_level = Some(min);
+            filter.max_level = Some(max);
+        } else if let Some(tag) = arg.strip_prefix("guild:") {
+            filter.guild_tag = Some(tag.into());
+        } else if let Some(zone) = arg.strip_prefix("zone:") {
+            filter.zone = Some(zone.into());
+        } else if filter.name.is_none() {
+            filter.name = Some(arg.to_string());
+        } else {
+            return Err("Only one name can be given".into());
+        }
+    }
+    Ok(filter)
+}
+
+/// The `i`th point of a golden angle spiral around `center`, keeping spawns apart.
+fn fake_spawn_translation(center: Vec3, i: u16) -> Vec3 {
+    let n = i as f32 + 1.0;
+    let angle = n * TAU * 0.381_966;
+    let radius = FAKE_SPAWN_SPACING * n.sqrt();
+    center + Vec3::new(angle.cos() * radius, 0.0, angle.sin() * radius)
+}
+
+fn spawn_command_line(mut commands: Commands) {
+    commands.spawn((
+        CommandLineText,
+        Text::default(),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(12.0),
+            bottom: Val::Px(12.0),
+            ..default()
+        },
+        // Over the loading screen, entering the world is a command.
+        GlobalZIndex(2),
+        Visibility::Hidden,
+    ));
+}
+
+#[derive(Component)]
+struct CommandLineText;
+
+fn edit_command_line(
+    mut inputs: MessageReader<KeyboardInput>,
+    mut command_line: ResMut<CommandLine>,
+    mut intent_seq: ResMut<ClientIntentSeq>,
+    mut intent_buffer: ResMut<IntentBuffer>,
+    mut net_audit: ResMut<NetAudit>,
+    local_q: Query<&Transform, With<LocalActor>>,
+    stdb: SpacetimeDB,
+) {
+    for input in inputs.read() {
+        if input.state != ButtonState::Pressed {
+            continue;
+        }
+        match (&input.logical_key, command_line.open) {
+            (Key::Enter, false) => {
+                command_line.open = true;
+                command_line.text.clear();
+            }
+            (Key::Character(chars), false) if chars.as_str() == "/" => {
+                command_line.open = true;
+                command_line.text = "/".into();
+            }
+            (Key::Enter, true) => {
+                let text = std::mem::take(&mut command_line.text);
+                let local_translation = local_q.single().ok().map(|t| t.translation);
+                let result = Command::parse(&text).and_then(|command| {
+                    command.run(
+                        &stdb,
+                        &mut intent_seq,
+                        &mut intent_buffer,
+                        &mut net_audit,
+                        local_translation,
+                    )
+                });
+                // Keep the line open to fix a mistyped command.
+                command_line.open = result.is_err();
+                command_line.output = result.unwrap_or_else(|err| err);
+                if command_line.open {
+                    command_line.text = text;
+                }
+            }
+            (Key::Escape, _) => {
+                command_line.open = false;
+                command_line.text.clear();
+                command_line.output.clear();
+            }
+            (Key::Backspace, true) => {
+                command_line.text.pop();
+            }
+            (Key::Space, true) => command_line.text.push(' '),
+            (Key::Character(chars), true) => command_line.text.push_str(chars),
+            _ => {}
+        }
+    }
+}
+
+fn update_command_line_text(
+    command_line: Res<CommandLine>,
+    text: Single<(&mut Text, &mut Visibility), With<CommandLineText>>,
+) {
+    if !command_line.is_changed() {
+        return;
+    }
+    let (mut text, mut visibility) = text.into_inner();
+    text.0 = match (command_line.output.is_empty(), command_line.open) {
+        (true, _) => format!("> {}", command_line.text),
+        (false, true) => format!("{}\n> {}", command_line.output, command_line.text),
+        (false, false) => command_line.output.clone(),
+    };
+    *visibility = if command_line.open || !command_line.output.is_empty() {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+}
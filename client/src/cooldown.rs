@@ -0,0 +1,145 @@
+//! Tracks the local player's cooldowns so the UI can gray out actions and show remaining time.
+//!
+//! Remaining time is measured against an estimate of the server clock ([`ServerClock`]) rather
+//! than the local clock, since `ready_at` is a server timestamp and the two clocks can disagree.
+
+use crate::module_bindings::{CooldownKind, CooldownRow};
+use bevy::{platform::collections::HashMap, prelude::*};
+use bevy_spacetimedb::{ReadDeleteMessage, ReadInsertMessage, ReadUpdateMessage};
+use spacetimedb_sdk::Timestamp;
+use std::time::Duration;
+
+/// Estimate of the server clock, derived from the server timestamps we receive.
+///
+/// Every cooldown row carries the server time it was started at. The row can only arrive after
+/// that time, so the smallest `local - server` difference seen is the best estimate of the offset
+/// between the clocks (skew plus the lowest observed latency).
+#[derive(Resource, Debug, Default)]
+pub struct ServerClock {
+    offset_micros: Option<i64>,
+}
+
+impl ServerClock {
+    /// Feeds a server timestamp that was just received into the estimate.
+    pub fn observe(&mut self, server_time: Timestamp) {
+        let offset = local_micros() - server_time.to_micros_since_unix_epoch();
+        self.offset_micros = Some(self.offset_micros.map_or(offset, |o| o.min(offset)));
+    }
+
+    /// The estimated current server time in microseconds since the unix epoch.
+    pub fn now_micros(&self) -> i64 {
+        local_micros() - self.offset_micros.unwrap_or(0)
+    }
+}
+
+fn local_micros() -> i64 {
+    Timestamp::now().to_micros_since_unix_epoch()
+}
+
+#[derive(Debug, Clone)]
+pub struct CooldownTimer {
+    pub kind: CooldownKind,
+    pub started_at_micros: i64,
+    pub ready_at_micros: i64,
+}
+
+impl CooldownTimer {
+    /// Total length of the cooldown.
+    pub fn duration(&self) -> Duration {
+        micros_to_duration(self.ready_at_micros - self.started_at_micros)
+    }
+
+    /// Time left until ready, zero when ready.
+    pub fn remaining(&self, clock: &ServerClock) -> Duration {
+        micros_to_duration(self.ready_at_micros - clock.now_micros())
+    }
+
+    /// Fraction of the cooldown remaining in `[0, 1]`, 0 when ready.
+    pub fn fraction_remaining(&self, clock: &ServerClock) -> f32 {
+        let total = self.duration().as_secs_f32();
+        if total <= 0.0 {
+            return 0.0;
+        }
+        (self.remaining(clock).as_secs_f32() / total).clamp(0.0, 1.0)
+    }
+
+    pub fn is_ready(&self, clock: &ServerClock) -> bool {
+        self.ready_at_micros <= clock.now_micros()
+    }
+}
+
+fn micros_to_duration(micros: i64) -> Duration {
+    Duration::from_micros(micros.max(0) as u64)
+}
+
+/// The local player's cooldowns keyed by the cooldown row id.
+#[derive(Resource, Debug, Default)]
+pub struct Cooldowns(pub HashMap<u64, CooldownTimer>);
+
+impl Cooldowns {
+    pub fn get(&self, kind: &CooldownKind) -> Option<&CooldownTimer> {
+        self.0.values().find(|timer| &timer.kind == kind)
+    }
+
+    /// Time left on the given cooldown, zero when it's ready or unknown.
+    pub fn remaining(&self, kind: &CooldownKind, clock: &ServerClock) -> Duration {
+        self.get(kind)
+            .map(|timer| timer.remaining(clock))
+            .unwrap_or_default()
+    }
+
+    pub fn is_ready(&self, kind: &CooldownKind, clock: &ServerClock) -> bool {
+        self.get(kind).is_none_or(|timer| timer.is_ready(clock))
+    }
+}
+
+impl From<&CooldownRow> for CooldownTimer {
+    fn from(row: &CooldownRow) -> Self {
+        Self {
+            kind: row.kind.clone(),
+            started_at_micros: row.started_at.to_micros_since_unix_epoch(),
+            ready_at_micros: row.ready_at.to_micros_since_unix_epoch(),
+        }
+    }
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.insert_resource(ServerClock::default());
+    app.insert_resource(Cooldowns::default());
+    app.add_systems(
+        PreUpdate,
+        (
+            on_cooldown_inserted,
+            on_cooldown_updated,
+            on_cooldown_deleted,
+        ),
+    );
+}
+
+fn on_cooldown_inserted(
+    mut cooldowns: ResMut<Cooldowns>,
+    mut clock: ResMut<ServerClock>,
+    mut msgs: ReadInsertMessage<CooldownRow>,
+) {
+    for msg in msgs.read() {
+        clock.observe(msg.row.started_at);
+        cooldowns.0.insert(msg.row.id, (&msg.row).into());
+    }
+}
+
+fn on_cooldown_updated(
+    mut cooldowns: ResMut<Cooldowns>,
+    mut clock: ResMut<ServerClock>,
+    mut msgs: ReadUpdateMessage<CooldownRow>,
+) {
+    for msg in msgs.read() {
+        clock.observe(msg.new.started_at);
+        cooldowns.0.insert(msg.new.id, (&msg.new).into());
+    }
+}
+
+fn on_cooldown_deleted(mut cooldowns: ResMut<Cooldowns>, mut msgs: ReadDeleteMessage<CooldownRow>) {
+    for msg in msgs.read() {
+        cooldowns.0.remove(&msg.row.id);
+    }
+}
@@ -0,0 +1,92 @@
+//! Presentation level culling of replicated actors.
+//!
+//! Actors beyond the render distance or outside the camera frustum lose their visuals (meshes,
+//! materials and the child meshes) and are marked [`Culled`], their data entity and replicated
+//! components stay. Once they come back into range [`Culled`] is removed and
+//! `attach_actor_visuals` builds the visuals again.
+//!
+//! The render distance is the `render.distance` player setting (meters).
+
+use crate::{
+    ActorEntity, LocalActor,
+    actor::{ActorCapsule, ActorVisuals},
+    settings::PlayerSettings,
+};
+use bevy::{
+    camera::primitives::{Frustum, Sphere},
+    prelude::*,
+};
+
+/// Render distance (meters) when the `render.distance` setting is unset or invalid.
+const DEFAULT_RENDER_DISTANCE: f32 = 80.0;
+
+/// Bounds for the `render.distance` setting (meters).
+const MIN_RENDER_DISTANCE: f32 = 20.0;
+const MAX_RENDER_DISTANCE: f32 = 500.0;
+
+/// Culled actors are re-materialized within this fraction of the render distance, so actors at
+/// the edge don't flicker.
+const RESTORE_DISTANCE_FRACTION: f32 = 0.9;
+
+/// Padding (meters) around an actor's capsule for the frustum test, so actors entering the screen
+/// edge already have their visuals.
+const FRUSTUM_MARGIN: f32 = 4.0;
+
+/// Marks an actor whose visuals were removed by culling.
+#[derive(Component, Debug)]
+pub struct Culled;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(PostUpdate, cull_actor_visuals);
+}
+
+fn render_distance(settings: &PlayerSettings) -> f32 {
+    settings
+        .get("render.distance")
+        .and_then(|value| value.parse::<f32>().ok())
+        .filter(|distance| distance.is_finite())
+        .map(|distance| distance.clamp(MIN_RENDER_DISTANCE, MAX_RENDER_DISTANCE))
+        .unwrap_or(DEFAULT_RENDER_DISTANCE)
+}
+
+fn cull_actor_visuals(
+    mut commands: Commands,
+    settings: Res<PlayerSettings>,
+    camera: Single<(&GlobalTransform, &Frustum), With<Camera3d>>,
+    actor_q: Query<
+        (
+            Entity,
+            &Transform,
+            &ActorCapsule,
+            Has<ActorVisuals>,
+            Has<Culled>,
+        ),
+        (With<ActorEntity>, Without<LocalActor>),
+    >,
+) {
+    let (camera_transform, frustum) = camera.into_inner();
+    let camera_translation = camera_transform.translation();
+    let cull_distance = render_distance(&settings);
+    let restore_distance = cull_distance * RESTORE_DISTANCE_FRACTION;
+
+    for (entity, transform, capsule, has_visuals, is_culled) in &actor_q {
+        let distance_sq = camera_translation.distance_squared(transform.translation);
+        let sphere = Sphere {
+            center: transform.translation.into(),
+            radius: capsule.half_height + capsule.radius + FRUSTUM_MARGIN,
+        };
+        let in_frustum = frustum.intersects_sphere(&sphere, false);
+
+        if !is_culled && (!in_frustum || distance_sq > cull_distance * cull_distance) {
+            let mut entity_commands = commands.entity(entity);
+            entity_commands.insert(Culled);
+            if has_visuals {
+                entity_commands
+                    .remove::<ActorVisuals>()
+                    .despawn_related::<Children>();
+            }
+        } else if is_culled && in_frustum && distance_sq <= restore_distance * restore_distance {
+            commands.entity(entity).remove::<Culled>();
+        }
+    }
+}
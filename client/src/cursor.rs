@@ -1,3 +1,21 @@
+//! The mouse cursor, and the ground-target reticle of AoE abilities.
+//!
+//! Pressing a ground targeted ability's slot (see [`crate::hud`]) starts targeting with it: a
+//! ring of the ability's radius follows the ground under the cursor, tinted by whether the point
+//! is within the ability's range and in line of sight of the local actor. Left click on a valid
+//! point casts it with `cast_ability_at_position`, right click or `Escape` cancels.
+//!
+//! The checks are local mirrors of the server's, the server validates the cast again.
+
+use crate::{
+    actor::LocalActor,
+    command::command_line_closed,
+    cooldown::ServerClock,
+    input::{InputThrottles, Press, ThrottledAction},
+    module_bindings::cast_ability_at_position,
+    server::SpacetimeDB,
+    world::ClientStaticQueryWorld,
+};
 use bevy::{
     asset::embedded_asset,
     // picking::pointer::PointerInteraction,
@@ -7,6 +25,25 @@ use bevy::{
         WindowResized,
     },
 };
+use nalgebra::{Point3, Vector3};
+use rapier3d::prelude::{QueryFilter, Ray};
+use shared::{GroundTargetAbility, StaticQueryWorld, dist_sq_xz, has_line_of_sight};
+use spacetimedb_sdk::Timestamp;
+
+/// Farthest distance (meters) from the camera the ground is picked at.
+const MAX_AIM_DISTANCE: f32 = 200.0;
+
+/// Mirror of the server's `GROUND_TARGET_SIGHT_HEIGHT`.
+const GROUND_TARGET_SIGHT_HEIGHT: f32 = 0.5;
+
+/// Height (meters) of the reticle above the ground, so it doesn't flicker into it.
+const RETICLE_LIFT: f32 = 0.05;
+
+/// Inner radius of the reticle ring, as a fraction of the ability's radius.
+const RETICLE_INNER_RADIUS: f32 = 0.92;
+
+const RETICLE_VALID_COLOR: Color = Color::srgba(0.2, 0.9, 0.3, 0.6);
+const RETICLE_INVALID_COLOR: Color = Color::srgba(0.9, 0.2, 0.2, 0.6);
 
 #[derive(Resource)]
 struct CursorAssets {
@@ -15,23 +52,80 @@ struct CursorAssets {
     ability: Handle<Image>,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum CursorKind {
     Default,
     Combat,
     Ability,
 }
 
-#[derive(Resource)]
+#[derive(Resource, PartialEq)]
 pub struct CurrentCursor(CursorKind);
 
+/// The ground point under the cursor while targeting.
+#[derive(Debug, Clone, Copy)]
+struct GroundAim {
+    point: Vec3,
+    /// Within range and in line of sight of the local actor.
+    valid: bool,
+}
+
+/// The ground targeted ability waiting for a point to be picked, see the module docs.
+#[derive(Resource, Default)]
+pub struct GroundTargeting {
+    ability: Option<GroundTargetAbility>,
+    aim: Option<GroundAim>,
+    /// The left click that ended targeting is still held, it must not move the actor.
+    holding_click: bool,
+}
+
+impl GroundTargeting {
+    /// Starts targeting with `ability`, or stops when already targeting with it.
+    pub fn toggle(&mut self, ability: GroundTargetAbility) {
+        self.ability = (self.ability != Some(ability)).then_some(ability);
+        self.aim = None;
+    }
+
+    fn stop(&mut self) {
+        self.ability = None;
+        self.aim = None;
+    }
+
+    /// Left clicks pick the target instead of moving the actor.
+    pub fn captures_click(&self) -> bool {
+        self.ability.is_some() || self.holding_click
+    }
+}
+
+/// The ring under the cursor while targeting.
+#[derive(Component)]
+struct Reticle;
+
+#[derive(Resource)]
+struct ReticleMaterials {
+    valid: Handle<StandardMaterial>,
+    invalid: Handle<StandardMaterial>,
+}
+
 pub(super) fn plugin(app: &mut App) {
     embedded_asset!(app, "../assets/embedded/cursors/default.png");
     embedded_asset!(app, "../assets/embedded/cursors/ability.png");
     embedded_asset!(app, "../assets/embedded/cursors/combat.png");
 
-    app.add_systems(Startup, load_cursor_assets);
+    app.add_systems(Startup, (load_cursor_assets, spawn_reticle));
     app.insert_resource(CurrentCursor(CursorKind::Default));
+    app.init_resource::<GroundTargeting>();
+
+    app.add_systems(
+        Update,
+        (
+            aim_ground_target,
+            confirm_ground_target.run_if(command_line_closed),
+            update_reticle,
+            update_cursor_kind,
+        )
+            .chain(),
+    );
 
     // Apply when our desired cursor changes
     app.add_systems(
@@ -146,27 +240,185 @@ fn reapply_on_enter(
     }
 }
 
-// flip the desired cursor like this and the apply_cursor system will
-// pick it up automatically due to resource_changed run condition.
-pub fn set_cursor_to_combat(mut cur: ResMut<CurrentCursor>) {
-    cur.0 = CursorKind::Combat;
+fn spawn_reticle(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let mut material = |base_color| {
+        materials.add(StandardMaterial {
+            base_color,
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        })
+    };
+    let reticle_materials = ReticleMaterials {
+        valid: material(RETICLE_VALID_COLOR),
+        invalid: material(RETICLE_INVALID_COLOR),
+    };
+    // A unit ring in the XY plane, laid flat and scaled to the ability's radius.
+    commands.spawn((
+        Reticle,
+        Pickable::IGNORE,
+        Visibility::Hidden,
+        Transform::from_rotation(Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2)),
+        Mesh3d(meshes.add(Annulus::new(RETICLE_INNER_RADIUS, 1.0))),
+        MeshMaterial3d(reticle_materials.valid.clone()),
+    ));
+    commands.insert_resource(reticle_materials);
+}
+
+/// The first static hit by `ray`, where the reticle rests.
+fn ground_point(query_world: &StaticQueryWorld, ray: Ray3d) -> Option<Vec3> {
+    let ray = Ray::new(
+        Point3::new(ray.origin.x, ray.origin.y, ray.origin.z),
+        Vector3::new(ray.direction.x, ray.direction.y, ray.direction.z),
+    );
+    let (_, toi) = query_world
+        .as_query_pipeline(QueryFilter::only_fixed())
+        .cast_ray(&ray, MAX_AIM_DISTANCE, true)?;
+    let point = ray.point_at(toi);
+    Some(Vec3::new(point.x, point.y, point.z))
+}
+
+/// Mirror of the checks of the server's `cast_ability_at_position` for a caster at `from`.
+fn is_valid_ground_target(
+    query_world: &StaticQueryWorld,
+    ability: &GroundTargetAbility,
+    from: Vec3,
+    point: Vec3,
+) -> bool {
+    let from = Vector3::new(from.x, from.y, from.z);
+    let point = Vector3::new(point.x, point.y, point.z);
+    dist_sq_xz(from, point) <= ability.range * ability.range
+        && has_line_of_sight(
+            query_world,
+            from,
+            point + Vector3::y() * GROUND_TARGET_SIGHT_HEIGHT,
+        )
+}
+
+fn aim_ground_target(
+    mut targeting: ResMut<GroundTargeting>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    camera: Single<(&Camera, &GlobalTransform), With<Camera3d>>,
+    local_q: Query<&Transform, With<LocalActor>>,
+    query_world: Res<ClientStaticQueryWorld>,
+) {
+    let Some(ability) = targeting.ability else {
+        return;
+    };
+    let (camera, camera_transform) = *camera;
+    let point = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world(camera_transform, cursor).ok())
+        .and_then(|ray| ground_point(&query_world.world, ray));
+    targeting.aim = point.map(|point| GroundAim {
+        point,
+        valid: local_q.single().is_ok_and(|caster| {
+            is_valid_ground_target(&query_world.world, &ability, caster.translation, point)
+        }),
+    });
+}
+
+fn confirm_ground_target(
+    mouse: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    clock: Res<ServerClock>,
+    time: Res<Time<Real>>,
+    mut targeting: ResMut<GroundTargeting>,
+    mut throttles: ResMut<InputThrottles>,
+    stdb: SpacetimeDB,
+) {
+    if targeting.holding_click
+        && !mouse.pressed(MouseButton::Left)
+        && !mouse.just_released(MouseButton::Left)
+    {
+        targeting.holding_click = false;
+    }
+    let Some(ability) = targeting.ability else {
+        return;
+    };
+    if mouse.just_pressed(MouseButton::Right) || keys.just_pressed(KeyCode::Escape) {
+        targeting.stop();
+        return;
+    }
+    if !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+    targeting.holding_click = true;
+    // Clicks on an invalid point keep targeting, the reticle already shows why.
+    let Some(aim) = targeting.aim.filter(|aim| aim.valid) else {
+        return;
+    };
+    if !throttles.allow(
+        ThrottledAction::GroundTarget,
+        time.elapsed(),
+        Press::Pressed,
+        aim.point,
+    ) {
+        return;
+    }
+    // What the player aims at is as old as the server state it was shown.
+    let seen_at = Some(Timestamp::from_micros_since_unix_epoch(clock.now_micros()));
+    if let Err(e) = stdb
+        .reducers()
+        .cast_ability_at_position(ability.id, aim.point.into(), seen_at)
+    {
+        println!("Error: {e}");
+    }
+    targeting.stop();
 }
-pub fn set_cursor_to_ability(mut cur: ResMut<CurrentCursor>) {
-    cur.0 = CursorKind::Ability;
+
+fn update_reticle(
+    targeting: Res<GroundTargeting>,
+    reticle_materials: Res<ReticleMaterials>,
+    mut reticle_q: Query<
+        (
+            &mut Transform,
+            &mut Visibility,
+            &mut MeshMaterial3d<StandardMaterial>,
+        ),
+        With<Reticle>,
+    >,
+) {
+    for (mut transform, mut visibility, mut material) in &mut reticle_q {
+        let (Some(ability), Some(aim)) = (targeting.ability, targeting.aim) else {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        };
+        visibility.set_if_neq(Visibility::Visible);
+        transform.translation = aim.point + Vec3::Y * RETICLE_LIFT;
+        transform.scale = Vec3::splat(ability.radius);
+        let wanted = if aim.valid {
+            &reticle_materials.valid
+        } else {
+            &reticle_materials.invalid
+        };
+        if material.0 != *wanted {
+            material.0 = wanted.clone();
+        }
+    }
 }
-pub fn set_cursor_to_default(mut cur: ResMut<CurrentCursor>) {
-    cur.0 = CursorKind::Default;
+
+fn update_cursor_kind(targeting: Res<GroundTargeting>, mut current: ResMut<CurrentCursor>) {
+    let kind = if targeting.ability.is_some() {
+        CursorKind::Ability
+    } else {
+        CursorKind::Default
+    };
+    current.set_if_neq(CurrentCursor(kind));
 }
 
 // Example of updating cursor based on what it is over...
 // TODO:
-// - combat when the entity it is over can enter combat
-// - ability when the LocalPlayer has some ability queued up
+// - combat when the entity it is over can enter combat, see `update_cursor_kind`
 // - default otherwise
 // fn update(
 //     player_q: Query<&RemotePlayer>,
 //     interactions: Query<&PointerInteraction>,
-//     current: ResMut<CurrentCursor>,
+//     mut current: ResMut<CurrentCursor>,
 // ) {
 //     let Ok(interaction) = interactions.single() else {
 //         return;
@@ -176,8 +428,8 @@ pub fn set_cursor_to_default(mut cur: ResMut<CurrentCursor>) {
 //     };
 
 //     if player_q.get(*entity).is_ok() {
-//         set_cursor_to_combat(current);
+//         current.set_if_neq(CurrentCursor(CursorKind::Combat));
 //     } else {
-//         set_cursor_to_default(current);
+//         current.set_if_neq(CurrentCursor(CursorKind::Default));
 //     }
 // }
@@ -1,3 +1,4 @@
+use crate::{command::command_line_closed, presentation::PresentationConfig};
 use bevy::diagnostic::{
     EntityCountDiagnosticsPlugin, FrameTimeDiagnosticsPlugin, SystemInformationDiagnosticsPlugin,
 };
@@ -5,6 +6,9 @@ use bevy::prelude::*;
 use bevy::render::diagnostic::RenderDiagnosticsPlugin;
 use iyes_perf_ui::prelude::*;
 
+/// Factor applied to the selected presentation value per F6/F7 press.
+const TUNE_STEP: f32 = 1.1;
+
 /// Add debug/perf tooling (intended for `dev_native` builds only).
 pub(super) fn plugin(app: &mut App) {
     app.add_plugins((
@@ -15,9 +19,96 @@ pub(super) fn plugin(app: &mut App) {
         PerfUiPlugin,
     ));
 
-    app.add_systems(Startup, spawn_perf_ui);
+    app.init_resource::<PresentationTuning>();
+    app.add_systems(Startup, (spawn_perf_ui, spawn_presentation_panel));
+    app.add_systems(
+        Update,
+        (
+            tune_presentation.run_if(command_line_closed),
+            update_presentation_panel,
+        )
+            .chain(),
+    );
 }
 
 fn spawn_perf_ui(mut commands: Commands) {
     commands.spawn(PerfUiAllEntries::default());
 }
+
+/// Index of the [`PresentationConfig`] value selected in the panel.
+#[derive(Resource, Default)]
+struct PresentationTuning(usize);
+
+#[derive(Component)]
+struct PresentationPanel;
+
+/// Names of the tunable [`PresentationConfig`] values, in panel order.
+const PRESENTATION_VALUES: [&str; 3] = ["translation decay", "rotation decay", "camera decay"];
+
+fn presentation_value(config: &mut PresentationConfig, index: usize) -> &mut f32 {
+    match index {
+        0 => &mut config.translation_decay_rate,
+        1 => &mut config.rotation_decay_rate,
+        _ => &mut config.camera_decay_rate,
+    }
+}
+
+fn spawn_presentation_panel(mut commands: Commands) {
+    commands.spawn((
+        PresentationPanel,
+        Text::default(),
+        TextFont {
+            font_size: 12.0,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            right: Val::Px(12.0),
+            bottom: Val::Px(12.0),
+            ..default()
+        },
+        Pickable::IGNORE,
+    ));
+}
+
+/// F5 selects the next value, F6/F7 scale the selected one down/up.
+fn tune_presentation(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut tuning: ResMut<PresentationTuning>,
+    mut config: ResMut<PresentationConfig>,
+) {
+    if keys.just_pressed(KeyCode::F5) {
+        tuning.0 = (tuning.0 + 1) % PRESENTATION_VALUES.len();
+    }
+    let factor = if keys.just_pressed(KeyCode::F6) {
+        1.0 / TUNE_STEP
+    } else if keys.just_pressed(KeyCode::F7) {
+        TUNE_STEP
+    } else {
+        return;
+    };
+    let value = presentation_value(&mut config, tuning.0);
+    *value *= factor;
+    info!(
+        "Presentation {}: {:.2}",
+        PRESENTATION_VALUES[tuning.0], *value
+    );
+}
+
+fn update_presentation_panel(
+    tuning: Res<PresentationTuning>,
+    config: Res<PresentationConfig>,
+    mut panel: Single<&mut Text, With<PresentationPanel>>,
+) {
+    if !tuning.is_changed() && !config.is_changed() {
+        return;
+    }
+    let mut text = String::from("Presentation (F5 select, F6/F7 -/+)");
+    let mut config = *config;
+    for (i, name) in PRESENTATION_VALUES.iter().enumerate() {
+        let marker = if i == tuning.0 { '>' } else { ' ' };
+        let value = *presentation_value(&mut config, i);
+        text.push_str(&format!("\n{marker} {name}: {value:.2}"));
+    }
+    panel.0 = text;
+}
@@ -0,0 +1,397 @@
+//! In-game world editor (`editor` feature), places, moves and deletes world statics through the
+//! admin reducers.
+//!
+//! Controls while active:
+//! - `F1` toggles editor mode, left click is taken over from movement while it's on.
+//! - `Tab` cycles the shape, `Q`/`E` rotate it, `[`/`]` scale it.
+//! - Left click places the ghost, it's only placeable when it doesn't overlap another static.
+//! - Right click selects a static, `G` moves it to the cursor and `Delete` removes it.
+//! - `Escape` clears the selection.
+
+use crate::{
+    input::InputAction,
+    module_bindings::{
+        CapsuleY, ColliderShape, Cylinder, WorldStatic, WorldStaticPose, delete_static,
+        place_static, update_static,
+    },
+    server::SpacetimeDB,
+    world::{ClientStaticQueryWorld, Ground, WorldStaticId, shape_mesh, to_world_static_def},
+};
+use bevy::{picking::pointer::PointerInteraction, prelude::*};
+use leafwing_input_manager::prelude::ActionState;
+use nalgebra::{Isometry3, Quaternion, Translation3, UnitQuaternion};
+use shared::{WORLD_OFFSET, WorldStaticDef, collider_from_def};
+
+const YAW_STEP: f32 = std::f32::consts::PI / 12.0;
+const SIZE_STEP: f32 = 1.25;
+const MIN_SIZE: f32 = 0.1;
+const MAX_SIZE: f32 = 50.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<EditorState>();
+    app.add_systems(Startup, spawn_ghost);
+    app.add_systems(
+        Update,
+        (
+            toggle_editor,
+            (edit_ghost, update_ghost, apply_edits, draw_gizmos)
+                .chain()
+                .run_if(editor_active),
+        )
+            .chain(),
+    );
+}
+
+/// Shapes that can be placed from the editor, sized by [`EditorState::size`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum EditorShape {
+    #[default]
+    Cuboid,
+    Sphere,
+    Cylinder,
+    Capsule,
+}
+
+impl EditorShape {
+    fn next(self) -> Self {
+        match self {
+            Self::Cuboid => Self::Sphere,
+            Self::Sphere => Self::Cylinder,
+            Self::Cylinder => Self::Capsule,
+            Self::Capsule => Self::Cuboid,
+        }
+    }
+
+    fn collider_shape(self, size: f32) -> ColliderShape {
+        let half = size * 0.5;
+        match self {
+            Self::Cuboid => ColliderShape::Cuboid(Vec3::splat(half).into()),
+            Self::Sphere => ColliderShape::Sphere(half),
+            Self::Cylinder => ColliderShape::Cylinder(Cylinder {
+                radius: half,
+                half_height: half,
+            }),
+            Self::Capsule => ColliderShape::CapsuleY(CapsuleY {
+                radius: half * 0.5,
+                half_height: half,
+            }),
+        }
+    }
+
+    /// Distance from the shape's center to its bottom, so the ghost rests on the surface.
+    fn rest_height(self, size: f32) -> f32 {
+        match self {
+            Self::Capsule => size * 0.75,
+            _ => size * 0.5,
+        }
+    }
+}
+
+#[derive(Resource, Debug)]
+struct EditorState {
+    active: bool,
+    shape: EditorShape,
+    size: f32,
+    yaw: f32,
+    /// The static picked with right click.
+    selected: Option<u64>,
+    /// Whether the ghost can be placed where it is.
+    ghost_valid: bool,
+    /// Cursor position on the surface under it.
+    cursor: Option<Vec3>,
+}
+
+impl Default for EditorState {
+    fn default() -> Self {
+        Self {
+            active: false,
+            shape: EditorShape::default(),
+            size: 1.0,
+            yaw: 0.0,
+            selected: None,
+            ghost_valid: false,
+            cursor: None,
+        }
+    }
+}
+
+impl EditorState {
+    fn ghost_shape(&self) -> ColliderShape {
+        self.shape.collider_shape(self.size)
+    }
+
+    fn rotation(&self) -> Quat {
+        Quat::from_rotation_y(self.yaw)
+    }
+}
+
+/// Translucent preview of the shape about to be placed.
+#[derive(Component)]
+struct EditorGhost;
+
+fn editor_active(state: Res<EditorState>) -> bool {
+    state.active
+}
+
+fn spawn_ghost(
+    mut commands: Commands,
+    state: Res<EditorState>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let mesh = shape_mesh(&state.ghost_shape()).unwrap_or_default();
+    commands.spawn((
+        EditorGhost,
+        Pickable::IGNORE,
+        Visibility::Hidden,
+        Transform::default(),
+        Mesh3d(meshes.add(mesh)),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            base_color: Color::srgba(0.2, 0.9, 0.3, 0.4),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        })),
+    ));
+}
+
+fn toggle_editor(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut state: ResMut<EditorState>,
+    mut actions: ResMut<ActionState<InputAction>>,
+    mut ghost_q: Query<&mut Visibility, With<EditorGhost>>,
+) {
+    if !keys.just_pressed(KeyCode::F1) {
+        return;
+    }
+    state.active = !state.active;
+    state.selected = None;
+    println!("Editor mode: {}", state.active);
+
+    // Left click places statics instead of moving while editing.
+    if state.active {
+        actions.disable_action(&InputAction::LeftClick);
+    } else {
+        actions.enable_action(&InputAction::LeftClick);
+    }
+    for mut visibility in &mut ghost_q {
+        *visibility = if state.active {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+fn edit_ghost(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut state: ResMut<EditorState>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    ghost_q: Query<&Mesh3d, With<EditorGhost>>,
+) {
+    let mut reshaped = false;
+    if keys.just_pressed(KeyCode::Tab) {
+        state.shape = state.shape.next();
+        reshaped = true;
+    }
+    if keys.just_pressed(KeyCode::BracketRight) {
+        state.size = (state.size * SIZE_STEP).min(MAX_SIZE);
+        reshaped = true;
+    }
+    if keys.just_pressed(KeyCode::BracketLeft) {
+        state.size = (state.size / SIZE_STEP).max(MIN_SIZE);
+        reshaped = true;
+    }
+    if keys.just_pressed(KeyCode::KeyQ) {
+        state.yaw += YAW_STEP;
+    }
+    if keys.just_pressed(KeyCode::KeyE) {
+        state.yaw -= YAW_STEP;
+    }
+
+    if !reshaped {
+        return;
+    }
+    let Some(mesh) = shape_mesh(&state.ghost_shape()) else {
+        return;
+    };
+    for mesh3d in &ghost_q {
+        if let Some(ghost_mesh) = meshes.get_mut(&mesh3d.0) {
+            *ghost_mesh = mesh.clone();
+        }
+    }
+}
+
+/// Returns true if a static with `def`'s shape can be placed at `translation`/`rotation`.
+///
+/// Mirrors the server's pose validation and additionally rejects overlapping other statics.
+fn is_placeable(
+    query_world: &ClientStaticQueryWorld,
+    def: &WorldStaticDef,
+    translation: Vec3,
+    rotation: Quat,
+    ignore_id: Option<u64>,
+) -> bool {
+    if !translation.is_finite()
+        || translation.x.abs() > WORLD_OFFSET
+        || translation.z.abs() > WORLD_OFFSET
+    {
+        return false;
+    }
+
+    let collider = collider_from_def(def);
+    let pose = Isometry3::from_parts(
+        Translation3::new(translation.x, translation.y, translation.z),
+        UnitQuaternion::from_quaternion(Quaternion::new(
+            rotation.w, rotation.x, rotation.y, rotation.z,
+        )),
+    );
+    !query_world
+        .world
+        .overlaps_static(pose, collider.shape(), ignore_id)
+}
+
+fn update_ghost(
+    mut state: ResMut<EditorState>,
+    interactions: Query<&PointerInteraction>,
+    query_world: Res<ClientStaticQueryWorld>,
+    mut ghost_q: Query<(&mut Transform, &MeshMaterial3d<StandardMaterial>), With<EditorGhost>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    state.cursor = interactions
+        .single()
+        .ok()
+        .and_then(|interaction| interaction.get_nearest_hit())
+        .and_then(|(_, hit)| hit.position);
+    let Some(cursor) = state.cursor else {
+        state.ghost_valid = false;
+        return;
+    };
+
+    let translation = cursor + Vec3::Y * state.shape.rest_height(state.size);
+    let rotation = state.rotation();
+    let def = to_world_static_def(&WorldStatic {
+        id: 0,
+        instance_id: 0,
+        translation: translation.into(),
+        rotation: rotation.into(),
+        scale: Vec3::ONE.into(),
+        shape: state.ghost_shape(),
+        surface_material: None,
+    });
+    state.ghost_valid = is_placeable(&query_world, &def, translation, rotation, None);
+
+    for (mut transform, material) in &mut ghost_q {
+        transform.translation = translation;
+        transform.rotation = rotation;
+        if let Some(material) = materials.get_mut(material) {
+            material.base_color = if state.ghost_valid {
+                Color::srgba(0.2, 0.9, 0.3, 0.4)
+            } else {
+                Color::srgba(0.9, 0.2, 0.2, 0.4)
+            };
+        }
+    }
+}
+
+fn apply_edits(
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut state: ResMut<EditorState>,
+    interactions: Query<&PointerInteraction>,
+    statics_q: Query<(&WorldStaticId, &Transform), Without<Ground>>,
+    query_world: Res<ClientStaticQueryWorld>,
+    stdb: SpacetimeDB,
+) {
+    if keys.just_pressed(KeyCode::Escape) {
+        state.selected = None;
+    }
+
+    if mouse.just_pressed(MouseButton::Right) {
+        state.selected = interactions
+            .single()
+            .ok()
+            .and_then(|interaction| interaction.get_nearest_hit())
+            .and_then(|(entity, _)| statics_q.get(*entity).ok())
+            .map(|(id, _)| id.0);
+        println!("Editor selected static: {:?}", state.selected);
+    }
+
+    if mouse.just_pressed(MouseButton::Left) && state.ghost_valid {
+        let Some(cursor) = state.cursor else {
+            return;
+        };
+        let pose = WorldStaticPose {
+            translation: (cursor + Vec3::Y * state.shape.rest_height(state.size)).into(),
+            rotation: state.rotation().into(),
+            scale: Vec3::ONE.into(),
+        };
+        if let Err(err) = stdb
+            .reducers()
+            .place_static(pose, state.ghost_shape(), None)
+        {
+            println!("Error placing static: {err}");
+        }
+    }
+
+    let Some(selected) = state.selected else {
+        return;
+    };
+
+    if keys.just_pressed(KeyCode::KeyG) {
+        let (Some(cursor), Some(def)) = (state.cursor, query_world.def(selected)) else {
+            return;
+        };
+        let Some((_, transform)) = statics_q.iter().find(|(id, _)| id.0 == selected) else {
+            return;
+        };
+        // Keep the static's height, only move it across the surface and apply the editor yaw.
+        let translation = Vec3::new(cursor.x, transform.translation.y, cursor.z);
+        let rotation = state.rotation();
+        if !is_placeable(&query_world, def, translation, rotation, Some(selected)) {
+            println!("Editor: static {selected} can't be moved there");
+            return;
+        }
+        let pose = WorldStaticPose {
+            translation: translation.into(),
+            rotation: rotation.into(),
+            scale: transform.scale.into(),
+        };
+        if let Err(err) = stdb.reducers().update_static(selected, pose) {
+            println!("Error moving static: {err}");
+        }
+    } else if keys.just_pressed(KeyCode::Delete) {
+        if let Err(err) = stdb.reducers().delete_static(selected) {
+            println!("Error deleting static: {err}");
+        }
+        state.selected = None;
+    }
+}
+
+fn draw_gizmos(
+    mut gizmos: Gizmos,
+    state: Res<EditorState>,
+    ghost_q: Query<&Transform, With<EditorGhost>>,
+    statics_q: Query<(&WorldStaticId, &Transform), Without<EditorGhost>>,
+) {
+    if state.cursor.is_some() {
+        for transform in &ghost_q {
+            gizmos.axes(*transform, state.size);
+            let forward = transform.rotation * Vec3::NEG_Z;
+            gizmos.arrow(
+                transform.translation,
+                transform.translation + forward * state.size,
+                Color::WHITE,
+            );
+        }
+    }
+
+    let Some(selected) = state.selected else {
+        return;
+    };
+    for (id, transform) in &statics_q {
+        if id.0 == selected {
+            gizmos.axes(*transform, state.size.max(1.0) * 1.5);
+        }
+    }
+}
@@ -0,0 +1,339 @@
+//! Visual effects for replicated gameplay events.
+//!
+//! - Replication handlers here translate table changes into [`EffectTriggered`] messages: combat
+//!   events (damage, heal), level ups, teleports (large jumps of an actor's transform) and
+//!   instance (zone) changes. Other plugins can write the message as well.
+//! - [`EffectRegistry`] maps each [`EffectKind`] to an [`EffectDef`], change or remove entries to
+//!   restyle effects without touching the replication code.
+//! - Effects are an expanding, fading, unlit sphere with an optional light flash, following the
+//!   actor they were triggered on. Culled actors (see [`crate::culling`]) get no effects.
+
+use crate::{
+    ActorEntityMapping,
+    culling::Culled,
+    module_bindings::{ActorRow, CombatEventKind, CombatEventRow, LevelRow, TransformRow},
+};
+use bevy::{platform::collections::HashMap, prelude::*};
+use bevy_spacetimedb::{ReadInsertMessage, ReadUpdateMessage};
+
+/// A transform update moving an actor further than this (meters) is treated as a teleport.
+const TELEPORT_DISTANCE: f32 = 15.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_message::<EffectTriggered>();
+    app.init_resource::<EffectRegistry>();
+    app.add_systems(Startup, load_effect_mesh);
+    app.add_systems(
+        Update,
+        (
+            (
+                on_combat_event_inserted,
+                on_level_updated,
+                on_transform_updated,
+                on_actor_updated,
+            ),
+            spawn_effects,
+            update_effects,
+        )
+            .chain(),
+    );
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EffectKind {
+    Damage,
+    Heal,
+    LevelUp,
+    Teleport,
+    ZoneEnter,
+    /// An actor started an emote, see [`crate::emote`].
+    Emote,
+}
+
+/// Requests the effect registered for `kind`.
+#[derive(Message, Debug, Clone)]
+pub struct EffectTriggered {
+    pub kind: EffectKind,
+    /// The actor entity the effect follows, if any.
+    pub entity: Option<Entity>,
+    /// Where the effect starts, used when there's no entity.
+    pub position: Vec3,
+}
+
+/// How an effect looks.
+#[derive(Debug, Clone, Copy)]
+pub struct EffectDef {
+    pub color: Color,
+    /// Sphere radius (meters) at the start and the end of the effect.
+    pub start_radius: f32,
+    pub end_radius: f32,
+    pub lifetime_secs: f32,
+    /// Height (meters) above the actor's origin.
+    pub height: f32,
+    /// Intensity of the light flash (lumens), `0.0` for none.
+    pub light_intensity: f32,
+}
+
+/// The effect per event kind, kinds without an entry show nothing.
+#[derive(Resource, Debug, Clone)]
+pub struct EffectRegistry(pub HashMap<EffectKind, EffectDef>);
+
+impl Default for EffectRegistry {
+    fn default() -> Self {
+        Self(HashMap::from_iter([
+            (
+                EffectKind::Damage,
+                EffectDef {
+                    color: Color::srgb(1.0, 0.3, 0.15),
+                    start_radius: 0.2,
+                    end_radius: 0.6,
+                    lifetime_secs: 0.25,
+                    height: 1.2,
+                    light_intensity: 0.0,
+                },
+            ),
+            (
+                EffectKind::Heal,
+                EffectDef {
+                    color: Color::srgb(0.3, 1.0, 0.4),
+                    start_radius: 0.3,
+                    end_radius: 0.9,
+                    lifetime_secs: 0.5,
+                    height: 1.0,
+                    light_intensity: 0.0,
+                },
+            ),
+            (
+                EffectKind::LevelUp,
+                EffectDef {
+                    color: Color::srgb(1.0, 0.85, 0.3),
+                    start_radius: 0.5,
+                    end_radius: 2.5,
+                    lifetime_secs: 1.2,
+                    height: 1.0,
+                    light_intensity: 200_000.0,
+                },
+            ),
+            (
+                EffectKind::Teleport,
+                EffectDef {
+                    color: Color::srgb(0.5, 0.6, 1.0),
+                    start_radius: 1.5,
+                    end_radius: 0.2,
+                    lifetime_secs: 0.6,
+                    height: 1.0,
+                    light_intensity: 100_000.0,
+                },
+            ),
+            (
+                EffectKind::ZoneEnter,
+                EffectDef {
+                    color: Color::srgb(0.8, 0.5, 1.0),
+                    start_radius: 0.2,
+                    end_radius: 3.0,
+                    lifetime_secs: 1.0,
+                    height: 1.0,
+                    light_intensity: 100_000.0,
+                },
+            ),
+            (
+                EffectKind::Emote,
+                EffectDef {
+                    color: Color::srgb(1.0, 0.95, 0.7),
+                    start_radius: 0.1,
+                    end_radius: 0.5,
+                    lifetime_secs: 0.8,
+                    height: 2.2,
+                    light_intensity: 0.0,
+                },
+            ),
+        ]))
+    }
+}
+
+/// The sphere mesh shared by all effects.
+#[derive(Resource)]
+struct EffectMesh(Handle<Mesh>);
+
+#[derive(Component, Debug)]
+struct Effect {
+    def: EffectDef,
+    elapsed: f32,
+}
+
+fn load_effect_mesh(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>) {
+    commands.insert_resource(EffectMesh(meshes.add(Sphere::new(1.0))));
+}
+
+fn on_combat_event_inserted(
+    mut msgs: ReadInsertMessage<CombatEventRow>,
+    mut effects: MessageWriter<EffectTriggered>,
+    oe_mapping: Res<ActorEntityMapping>,
+) {
+    for msg in msgs.read() {
+        let kind = match msg.row.kind {
+            CombatEventKind::Damage
+            | CombatEventKind::CriticalDamage
+            | CombatEventKind::FallDamage => EffectKind::Damage,
+            CombatEventKind::Heal => EffectKind::Heal,
+        };
+        effects.write(EffectTriggered {
+            kind,
+            entity: oe_mapping.0.get(&msg.row.target).copied(),
+            position: msg.row.position.clone().into(),
+        });
+    }
+}
+
+fn on_level_updated(
+    mut msgs: ReadUpdateMessage<LevelRow>,
+    mut effects: MessageWriter<EffectTriggered>,
+    oe_mapping: Res<ActorEntityMapping>,
+    transform_q: Query<&Transform>,
+) {
+    for msg in msgs.read() {
+        if msg.new.level <= msg.old.level {
+            continue;
+        }
+        let Some(&entity) = oe_mapping.0.get(&msg.new.actor_id) else {
+            continue;
+        };
+        effects.write(EffectTriggered {
+            kind: EffectKind::LevelUp,
+            entity: Some(entity),
+            position: transform_q
+                .get(entity)
+                .map(|transform| transform.translation)
+                .unwrap_or_default(),
+        });
+    }
+}
+
+fn on_transform_updated(
+    mut msgs: ReadUpdateMessage<TransformRow>,
+    mut effects: MessageWriter<EffectTriggered>,
+    oe_mapping: Res<ActorEntityMapping>,
+) {
+    for msg in msgs.read() {
+        let old: Vec3 = msg.old.translation.clone().into();
+        let new: Vec3 = msg.new.translation.clone().into();
+        if old.distance_squared(new) < TELEPORT_DISTANCE * TELEPORT_DISTANCE {
+            continue;
+        }
+        effects.write(EffectTriggered {
+            kind: EffectKind::Teleport,
+            entity: oe_mapping.0.get(&msg.new.actor_id).copied(),
+            position: new,
+        });
+    }
+}
+
+fn on_actor_updated(
+    mut msgs: ReadUpdateMessage<ActorRow>,
+    mut effects: MessageWriter<EffectTriggered>,
+    oe_mapping: Res<ActorEntityMapping>,
+    transform_q: Query<&Transform>,
+) {
+    for msg in msgs.read() {
+        if msg.new.instance_id == msg.old.instance_id {
+            continue;
+        }
+        let Some(&entity) = oe_mapping.0.get(&msg.new.id) else {
+            continue;
+        };
+        effects.write(EffectTriggered {
+            kind: EffectKind::ZoneEnter,
+            entity: Some(entity),
+            position: transform_q
+                .get(entity)
+                .map(|transform| transform.translation)
+                .unwrap_or_default(),
+        });
+    }
+}
+
+fn spawn_effects(
+    mut commands: Commands,
+    mut msgs: MessageReader<EffectTriggered>,
+    registry: Res<EffectRegistry>,
+    mesh: Res<EffectMesh>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    entity_q: Query<Has<Culled>>,
+) {
+    for msg in msgs.read() {
+        let Some(&def) = registry.0.get(&msg.kind) else {
+            continue;
+        };
+        // Follow the actor when it's around, otherwise stay at the event's position.
+        let parent = match msg.entity.map(|entity| (entity, entity_q.get(entity))) {
+            Some((_, Ok(true))) => continue,
+            Some((entity, Ok(false))) => Some(entity),
+            _ => None,
+        };
+        let offset = Vec3::Y * def.height;
+        let translation = match parent {
+            Some(_) => offset,
+            None => msg.position + offset,
+        };
+
+        let mut effect = commands.spawn((
+            Effect { def, elapsed: 0.0 },
+            Mesh3d(mesh.0.clone()),
+            MeshMaterial3d(materials.add(StandardMaterial {
+                base_color: def.color,
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..default()
+            })),
+            Transform::from_translation(translation).with_scale(Vec3::splat(def.start_radius)),
+            Pickable::IGNORE,
+        ));
+        if let Some(parent) = parent {
+            effect.insert(ChildOf(parent));
+        }
+        if def.light_intensity > 0.0 {
+            effect.with_child(PointLight {
+                color: def.color,
+                intensity: def.light_intensity,
+                shadows_enabled: false,
+                ..default()
+            });
+        }
+    }
+}
+
+fn update_effects(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut effect_q: Query<(
+        Entity,
+        &mut Effect,
+        &mut Transform,
+        &MeshMaterial3d<StandardMaterial>,
+        Option<&Children>,
+    )>,
+    mut light_q: Query<&mut PointLight>,
+) {
+    for (entity, mut effect, mut transform, material, children) in &mut effect_q {
+        effect.elapsed += time.delta_secs();
+        let t = effect.elapsed / effect.def.lifetime_secs;
+        if t >= 1.0 {
+            materials.remove(&material.0);
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let radius = effect.def.start_radius.lerp(effect.def.end_radius, t);
+        transform.scale = Vec3::splat(radius);
+        let fade = 1.0 - t * t;
+        if let Some(material) = materials.get_mut(&material.0) {
+            material.base_color = effect.def.color.with_alpha(fade);
+        }
+        for child in children.into_iter().flatten() {
+            if let Ok(mut light) = light_q.get_mut(*child) {
+                light.intensity = effect.def.light_intensity * fade;
+            }
+        }
+    }
+}
@@ -0,0 +1,208 @@
+//! Idle motion and emote playback.
+//!
+//! Actors have no skeletal animations yet, so both are procedural squash and stretch of the
+//! actor's scale, which transform replication leaves alone (it owns translation and rotation).
+//! Both are relative to the actor's [`ActorScale`].
+//!
+//! - Idle: a slow breathing pulse, its rate and phase derived from the actor id so a crowd
+//!   doesn't breathe in lockstep.
+//! - Emotes: `emote_view` rows carry the server time span the emote plays in, measured against
+//!   the [`ServerClock`] so a row received late (or long after, on entering the AOI) only plays
+//!   what's left of it. Starting an emote also triggers [`EffectKind::Emote`].
+
+use crate::{
+    ActorEntityMapping,
+    actor::{ActorEntity, ActorScale, ActorVisuals, ensure_actor_entity},
+    cooldown::ServerClock,
+    effects::{EffectKind, EffectTriggered},
+    module_bindings::{EmoteKind, EmoteRow},
+};
+use bevy::prelude::*;
+use bevy_spacetimedb::{ReadDeleteMessage, ReadInsertMessage, ReadUpdateMessage};
+use std::f32::consts::TAU;
+
+/// Idle breathing, relative change of the height at the top of a breath.
+const IDLE_AMPLITUDE: f32 = 0.02;
+
+/// Idle breaths per second are between this and `IDLE_RATE + IDLE_RATE_VARIANCE`.
+const IDLE_RATE: f32 = 0.25;
+const IDLE_RATE_VARIANCE: f32 = 0.1;
+
+/// Time (seconds) emotes take to blend in and out of the idle motion.
+const EMOTE_BLEND_SECS: f32 = 0.2;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        PreUpdate,
+        (on_emote_inserted, on_emote_updated, on_emote_deleted),
+    );
+    app.add_systems(Update, animate_actors);
+}
+
+/// The actor's latest emote, in server time.
+#[derive(Component, Debug, Clone)]
+pub struct Emote {
+    pub kind: EmoteKind,
+    pub started_at_micros: i64,
+    pub ends_at_micros: i64,
+}
+
+impl Emote {
+    /// Seconds into the emote, `None` when it isn't playing.
+    fn elapsed_secs(&self, clock: &ServerClock) -> Option<f32> {
+        let now = clock.now_micros();
+        (self.started_at_micros..self.ends_at_micros)
+            .contains(&now)
+            .then(|| (now - self.started_at_micros) as f32 / 1_000_000.0)
+    }
+
+    fn remaining_secs(&self, clock: &ServerClock) -> f32 {
+        (self.ends_at_micros - clock.now_micros()).max(0) as f32 / 1_000_000.0
+    }
+}
+
+impl From<&EmoteRow> for Emote {
+    fn from(row: &EmoteRow) -> Self {
+        Self {
+            kind: row.kind.clone(),
+            started_at_micros: row.started_at.to_micros_since_unix_epoch(),
+            ends_at_micros: row.ends_at.to_micros_since_unix_epoch(),
+        }
+    }
+}
+
+fn apply_emote(
+    commands: &mut Commands,
+    effects: &mut MessageWriter<EffectTriggered>,
+    clock: &mut ServerClock,
+    oe_mapping: &mut ActorEntityMapping,
+    row: &EmoteRow,
+) {
+    let entity = ensure_actor_entity(commands, oe_mapping, row.actor_id);
+    clock.observe(row.started_at);
+    let emote = Emote::from(row);
+    if emote.elapsed_secs(clock).is_some() {
+        effects.write(EffectTriggered {
+            kind: EffectKind::Emote,
+            entity: Some(entity),
+            position: Vec3::ZERO,
+        });
+    }
+    commands.entity(entity).insert(emote);
+}
+
+fn on_emote_inserted(
+    mut commands: Commands,
+    mut msgs: ReadInsertMessage<EmoteRow>,
+    mut effects: MessageWriter<EffectTriggered>,
+    mut clock: ResMut<ServerClock>,
+    mut oe_mapping: ResMut<ActorEntityMapping>,
+) {
+    for msg in msgs.read() {
+        apply_emote(
+            &mut commands,
+            &mut effects,
+            &mut clock,
+            &mut oe_mapping,
+            &msg.row,
+        );
+    }
+}
+
+fn on_emote_updated(
+    mut commands: Commands,
+    mut msgs: ReadUpdateMessage<EmoteRow>,
+    mut effects: MessageWriter<EffectTriggered>,
+    mut clock: ResMut<ServerClock>,
+    mut oe_mapping: ResMut<ActorEntityMapping>,
+) {
+    for msg in msgs.read() {
+        // An emote cut short only moves `ends_at`, that's not a new emote.
+        if msg.new.started_at == msg.old.started_at {
+            if let Some(&entity) = oe_mapping.0.get(&msg.new.actor_id) {
+                commands.entity(entity).insert(Emote::from(&msg.new));
+            }
+            continue;
+        }
+        apply_emote(
+            &mut commands,
+            &mut effects,
+            &mut clock,
+            &mut oe_mapping,
+            &msg.new,
+        );
+    }
+}
+
+fn on_emote_deleted(
+    mut commands: Commands,
+    mut msgs: ReadDeleteMessage<EmoteRow>,
+    oe_mapping: Res<ActorEntityMapping>,
+) {
+    for msg in msgs.read() {
+        if let Some(&entity) = oe_mapping.0.get(&msg.row.actor_id) {
+            commands.entity(entity).remove::<Emote>();
+        }
+    }
+}
+
+/// Scale of an emote `t` seconds in, `(width, height)` relative to the rest pose.
+fn emote_scale(kind: &EmoteKind, t: f32) -> Vec2 {
+    match kind {
+        // Side to side sway.
+        EmoteKind::Wave => Vec2::new(1.0 + 0.06 * (t * 3.0 * TAU).sin(), 1.0),
+        // One slow dip, held at the bottom.
+        EmoteKind::Bow => {
+            let depth = (t / 0.5).min(1.0) * 0.15;
+            Vec2::new(1.0 + depth * 0.5, 1.0 - depth)
+        }
+        // Quick hops.
+        EmoteKind::Cheer => {
+            let hop = (t * 2.5 * TAU).sin().abs();
+            Vec2::new(1.0 - 0.05 * hop, 1.0 + 0.12 * hop)
+        }
+        // Squash and stretch to a beat.
+        EmoteKind::Dance => {
+            let beat = (t * 2.0 * TAU).sin();
+            Vec2::new(1.0 - 0.08 * beat, 1.0 + 0.1 * beat)
+        }
+    }
+}
+
+/// Idle scale of `actor_id` at `time` seconds, see the module docs.
+fn idle_scale(actor_id: u32, time: f32) -> Vec2 {
+    // Cheap integer hash so neighbouring ids get unrelated rates and phases.
+    let hash = actor_id.wrapping_mul(0x9E37_79B9);
+    let rate = IDLE_RATE + IDLE_RATE_VARIANCE * (hash >> 16) as f32 / u16::MAX as f32;
+    let phase = (hash & 0xFFFF) as f32 / u16::MAX as f32;
+    let breath = ((time * rate + phase) * TAU).sin() * IDLE_AMPLITUDE;
+    Vec2::new(1.0 - breath * 0.5, 1.0 + breath)
+}
+
+fn animate_actors(
+    time: Res<Time>,
+    clock: Res<ServerClock>,
+    mut actor_q: Query<
+        (
+            &ActorEntity,
+            &mut Transform,
+            Option<&Emote>,
+            Option<&ActorScale>,
+        ),
+        With<ActorVisuals>,
+    >,
+) {
+    let now = time.elapsed_secs();
+    for (actor, mut transform, emote, actor_scale) in &mut actor_q {
+        let idle = idle_scale(actor.0, now);
+        let scale = match emote.and_then(|emote| Some((emote, emote.elapsed_secs(&clock)?))) {
+            Some((emote, t)) => {
+                let blend = (t.min(emote.remaining_secs(&clock)) / EMOTE_BLEND_SECS).min(1.0);
+                idle.lerp(emote_scale(&emote.kind, t), blend)
+            }
+            None => idle,
+        };
+        let scale = scale * actor_scale.copied().unwrap_or_default().0;
+        transform.scale = Vec3::new(scale.x, scale.y, scale.x);
+    }
+}
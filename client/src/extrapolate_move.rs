@@ -1,10 +1,15 @@
 use crate::ActorEntity;
+use crate::actor::Flags;
 use crate::module_bindings::MoveIntentData;
 use crate::movement_state::MovementState;
 use crate::secondary_stats::SecondaryStats;
 use bevy::prelude::*;
-use nalgebra::Vector2;
-use shared::{get_desired_delta, yaw_from_xz};
+use nalgebra::{Vector2, Vector3};
+use shared::{
+    ActorFlags, GRAVITY_MPS2, MOVEMENT_TICK_INTERVAL_SECS, TERMINAL_FALL_SPEED_MPS,
+    advance_vertical_velocity, consume_reached_waypoint, dequantize_vertical_velocity,
+    get_desired_delta, get_fly_delta, math::yaw::yaw_from_xz,
+};
 
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(PreUpdate, extrapolate_move);
@@ -24,26 +29,76 @@ pub(super) fn plugin(app: &mut App) {
 // let query_world = build_static_query_world(world_defs, dt);
 // let query_pipeline = query_world.as_query_pipeline(QueryFilter::only_fixed());
 
-fn extrapolate_move(
+pub(crate) fn extrapolate_move(
     time: Res<Time>,
-    mut query: Query<(&mut Transform, &MovementState, &SecondaryStats), With<ActorEntity>>,
+    mut query: Query<
+        (
+            &mut Transform,
+            &mut MovementState,
+            &SecondaryStats,
+            Option<&Flags>,
+        ),
+        With<ActorEntity>,
+    >,
 ) {
     let dt = time.delta_secs();
 
-    query
-        .iter_mut()
-        .for_each(|(mut transform, movement_state, secondary_stats)| {
+    query.iter_mut().for_each(
+        |(mut transform, mut movement_state, secondary_stats, flags)| {
             // TODO: add CapuleY to the actor state locally...?
             if !movement_state.should_move {
                 return;
             }
 
             let current_planar = transform.translation.xz();
+            // Launched actors go straight up and down, like on the server.
+            let airborne = flags.is_some_and(|flags| flags.0.contains(ActorFlags::AIRBORNE));
             let target_planar = match &movement_state.move_intent {
+                _ if airborne => current_planar,
                 MoveIntentData::Point(point) => Vec2::new((point).x, (point).z),
+                MoveIntentData::Point3(point) => Vec2::new(point.x, point.z),
+                MoveIntentData::Path(path) => path
+                    .first()
+                    .map(|point| Vec2::new(point.x, point.z))
+                    .unwrap_or(current_planar),
                 _ => current_planar,
             };
+            // Advance the fall (or the rise after a launch) exactly like the server does, once
+            // per movement tick with the tick's dt, so predicted falls accelerate the same way
+            // instead of falling at the last replicated speed.
+            if movement_state.vertical_velocity != 0 {
+                movement_state.fall_elapsed += dt;
+                while movement_state.fall_elapsed >= MOVEMENT_TICK_INTERVAL_SECS {
+                    movement_state.fall_elapsed -= MOVEMENT_TICK_INTERVAL_SECS;
+                    movement_state.vertical_velocity = advance_vertical_velocity(
+                        movement_state.vertical_velocity,
+                        MOVEMENT_TICK_INTERVAL_SECS,
+                    );
+                }
+            }
+
             let movement_speed_mps = secondary_stats.movement_speed;
+
+            // GM fly mode, the same free-fly integrator as the server's `fly_step_actor`.
+            if flags.is_some_and(|flags| flags.0.contains(ActorFlags::FLYING)) {
+                let target_y = match &movement_state.move_intent {
+                    MoveIntentData::Point3(point) => point.y,
+                    _ => transform.translation.y,
+                };
+                let current = Vector3::new(
+                    transform.translation.x,
+                    transform.translation.y,
+                    transform.translation.z,
+                );
+                let target = Vector3::new(target_planar.x, target_y, target_planar.y);
+                if let Some(yaw) = yaw_from_xz((target - current).xz()) {
+                    transform.rotation = Quat::from_rotation_y(yaw);
+                }
+                let delta = get_fly_delta(current, target, movement_speed_mps, dt);
+                transform.translation += Vec3::new(delta.x, delta.y, delta.z);
+                return;
+            }
+
             let direction = (target_planar - current_planar)
                 .try_normalize()
                 .unwrap_or_default();
@@ -52,18 +107,34 @@ fn extrapolate_move(
                 transform.rotation = Quat::from_rotation_y(yaw);
             }
 
-            let desired_delta = get_desired_delta(
+            let mut desired_delta = get_desired_delta(
                 Vector2::new(current_planar.x, current_planar.y),
                 Vector2::new(target_planar.x, target_planar.y),
                 movement_speed_mps,
                 movement_state.vertical_velocity,
                 dt,
             );
+            // Gravity acts during the tick, the server steps at the tick's average velocity.
+            if movement_state.vertical_velocity != 0 {
+                let v_mps = dequantize_vertical_velocity(movement_state.vertical_velocity)
+                    + GRAVITY_MPS2 * movement_state.fall_elapsed;
+                desired_delta.y = v_mps.max(TERMINAL_FALL_SPEED_MPS) * dt;
+            }
 
             println!("Desired Delta: {:?}", desired_delta);
 
             transform.translation.x += desired_delta.x;
             transform.translation.y += desired_delta.y;
             transform.translation.z += desired_delta.z;
-        });
+
+            // Mirror the server's waypoint consumption so multi-waypoint paths keep predicting
+            // between server updates, which replace the path with the authoritative one.
+            if let MoveIntentData::Path(path) = &mut movement_state.move_intent {
+                let current = Vector2::new(transform.translation.x, transform.translation.z);
+                if consume_reached_waypoint(path, current, |p| Vector2::new(p.x, p.z)) {
+                    movement_state.move_intent = MoveIntentData::None;
+                }
+            }
+        },
+    );
 }
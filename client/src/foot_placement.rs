@@ -0,0 +1,126 @@
+//! Keeps the rendered actors' feet on the ground.
+//!
+//! The rendered transform is the capsule's center, and a capsule resting on a step edge or
+//! pushed up a stair hovers over (or sinks into) the step below. Each frame a ray down from the
+//! center against the [`ClientStaticQueryWorld`] finds the surface under the actor, the visuals
+//! are moved to touch it and tilted a little with the slope.
+//!
+//! Presentation only: the offset is the local transform of the actor's body child (see
+//! [`ActorVisuals`]), the actor's own transform stays the smoothed capsule center.
+
+use crate::{
+    actor::{ActorCapsule, ActorEntity, ActorVisuals, Flags},
+    movement_state::MovementState,
+    world::ClientStaticQueryWorld,
+};
+use bevy::{prelude::*, transform::TransformSystems};
+use nalgebra::{Point3, Vector3};
+use rapier3d::prelude::{QueryFilter, Ray};
+use shared::ActorFlags;
+
+/// Farthest (meters) the visuals are lowered to reach the ground, about a stair step.
+const MAX_DROP: f32 = 0.35;
+
+/// Farthest (meters) the visuals are raised out of the ground.
+const MAX_RAISE: f32 = 0.2;
+
+/// Steepest surface (radians) the visuals stand on, steeper hits (walls, step risers) are ignored.
+const MAX_GROUND_SLOPE: f32 = 50.0_f32.to_radians();
+
+/// Largest tilt (radians) of the visuals towards the slope they stand on.
+const MAX_TILT: f32 = 6.0_f32.to_radians();
+
+/// Decay rate (1/s) of the applied offset and tilt towards the wanted ones, hides step pops.
+const DECAY_RATE: f32 = 18.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        PostUpdate,
+        apply_foot_placement.before(TransformSystems::Propagate),
+    );
+}
+
+/// The offset and tilt currently applied to an actor's body.
+#[derive(Component, Debug, Clone, Copy)]
+struct FootPlacement {
+    offset: f32,
+    tilt: Quat,
+}
+
+impl Default for FootPlacement {
+    fn default() -> Self {
+        Self {
+            offset: 0.0,
+            tilt: Quat::IDENTITY,
+        }
+    }
+}
+
+fn apply_foot_placement(
+    mut commands: Commands,
+    time: Res<Time>,
+    query_world: Res<ClientStaticQueryWorld>,
+    actor_q: Query<
+        (
+            &Transform,
+            &ActorCapsule,
+            &ActorVisuals,
+            Option<&Flags>,
+            Option<&MovementState>,
+        ),
+        With<ActorEntity>,
+    >,
+    mut body_q: Query<(&mut Transform, Option<&mut FootPlacement>), Without<ActorEntity>>,
+) {
+    let pipeline = query_world
+        .world
+        .as_query_pipeline(QueryFilter::only_fixed());
+    let t = 1.0 - (-DECAY_RATE * time.delta_secs()).exp();
+    for (transform, capsule, visuals, flags, movement_state) in &actor_q {
+        let Ok((mut body, placement)) = body_q.get_mut(visuals.body) else {
+            continue;
+        };
+        let flying = flags.is_some_and(|flags| flags.0.contains(ActorFlags::FLYING));
+        let falling = movement_state.is_some_and(|state| state.vertical_velocity != 0);
+        let bottom = capsule.half_height + capsule.radius;
+
+        // Where the visuals want to be, nothing to correct in the air or without ground.
+        let (wanted_offset, wanted_tilt) = if flying || falling {
+            (0.0, Quat::IDENTITY)
+        } else {
+            let center = transform.translation;
+            let ray = Ray::new(
+                Point3::new(center.x, center.y, center.z),
+                Vector3::new(0.0, -1.0, 0.0),
+            );
+            pipeline
+                .cast_ray_and_get_normal(&ray, bottom + MAX_DROP, true)
+                .map(|(_, hit)| {
+                    let normal = Vec3::new(hit.normal.x, hit.normal.y, hit.normal.z);
+                    (hit.time_of_impact, normal)
+                })
+                .filter(|(_, normal)| normal.angle_between(Vec3::Y) <= MAX_GROUND_SLOPE)
+                .map_or((0.0, Quat::IDENTITY), |(toi, normal)| {
+                    let offset = (bottom - toi).clamp(-MAX_DROP, MAX_RAISE);
+                    let tilt = Quat::from_rotation_arc(Vec3::Y, normal);
+                    let (axis, angle) = tilt.to_axis_angle();
+                    (offset, Quat::from_axis_angle(axis, angle.min(MAX_TILT)))
+                })
+        };
+
+        let previous = placement.as_deref().copied().unwrap_or_default();
+        let applied = FootPlacement {
+            offset: previous.offset.lerp(wanted_offset, t),
+            tilt: previous.tilt.slerp(wanted_tilt, t),
+        };
+        // The body is in the actor's rotated and scaled space, the offset and tilt are in world.
+        body.translation = Vec3::Y * applied.offset / transform.scale.y;
+        body.rotation = transform.rotation.inverse() * applied.tilt * transform.rotation;
+        match placement {
+            Some(mut placement) => *placement = applied,
+            None => {
+                commands.entity(visuals.body).insert(applied);
+            }
+        }
+    }
+}
@@ -0,0 +1,63 @@
+//! Emits a [`Footstep`] message every stride while a grounded actor moves, tagged with the
+//! surface it is walking on so audio and particle effects can vary by material.
+
+use crate::{ActorEntity, module_bindings::SurfaceMaterial, movement_state::MovementState};
+use bevy::prelude::*;
+
+/// Distance (meters) travelled on the ground between two footsteps.
+const STRIDE_LENGTH: f32 = 0.8;
+
+#[derive(Message, Debug, Clone)]
+pub struct Footstep {
+    pub entity: Entity,
+    pub position: Vec3,
+    /// The surface stepped on, `None` means the default surface.
+    pub material: Option<SurfaceMaterial>,
+}
+
+/// Ground distance travelled since the last footstep.
+#[derive(Component, Debug, Default)]
+struct Stride {
+    last_position: Option<Vec3>,
+    travelled: f32,
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_message::<Footstep>();
+    app.add_systems(Update, (insert_stride, emit_footsteps).chain());
+}
+
+fn insert_stride(mut commands: Commands, added_q: Query<Entity, Added<MovementState>>) {
+    for entity in &added_q {
+        commands.entity(entity).insert(Stride::default());
+    }
+}
+
+fn emit_footsteps(
+    mut footsteps: MessageWriter<Footstep>,
+    mut actor_q: Query<(Entity, &Transform, &MovementState, &mut Stride), With<ActorEntity>>,
+) {
+    for (entity, transform, movement_state, mut stride) in &mut actor_q {
+        let position = transform.translation;
+        let last_position = stride.last_position.replace(position);
+
+        // Only count planar ground movement, falling or jumping doesn't make footsteps.
+        let grounded = movement_state.vertical_velocity == 0;
+        let Some(last_position) = last_position.filter(|_| grounded) else {
+            stride.travelled = 0.0;
+            continue;
+        };
+
+        stride.travelled += position.xz().distance(last_position.xz());
+        if stride.travelled < STRIDE_LENGTH {
+            continue;
+        }
+        stride.travelled -= STRIDE_LENGTH;
+
+        footsteps.write(Footstep {
+            entity,
+            position,
+            material: movement_state.ground_material.clone(),
+        });
+    }
+}
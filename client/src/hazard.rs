@@ -0,0 +1,112 @@
+//! Hazard zones (see `hazard_zone_tbl`), rendered as translucent boxes tinted by their kind.
+//!
+//! The table holds the zones of every instance, only those of the local actor's [`Zone`] are
+//! shown. The damage is all server side, combat text shows it like any other hit.
+
+use crate::{
+    LocalActor,
+    actor::Zone,
+    module_bindings::{HazardKind, HazardZoneRow},
+};
+use bevy::{platform::collections::HashMap, prelude::*};
+use bevy_spacetimedb::{ReadDeleteMessage, ReadInsertMessage};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<HazardEntityMapping>();
+    app.add_systems(
+        Update,
+        (
+            (on_hazard_zone_inserted, on_hazard_zone_deleted),
+            show_local_zone_hazards,
+        )
+            .chain(),
+    );
+}
+
+/// The instance a hazard zone entity belongs to.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct HazardZone {
+    pub instance_id: u32,
+}
+
+/// Maps hazard zone ids to their entities.
+#[derive(Resource, Default)]
+pub struct HazardEntityMapping(pub HashMap<u32, Entity>);
+
+fn kind_material(kind: &HazardKind) -> StandardMaterial {
+    let (base_color, emissive) = match kind {
+        HazardKind::Lava => (
+            Color::srgba(1.0, 0.35, 0.05, 0.6),
+            LinearRgba::rgb(4.0, 0.8, 0.0),
+        ),
+        HazardKind::Spikes => (Color::srgba(0.45, 0.45, 0.5, 0.5), LinearRgba::BLACK),
+        HazardKind::Poison => (
+            Color::srgba(0.3, 0.8, 0.2, 0.4),
+            LinearRgba::rgb(0.2, 0.8, 0.1),
+        ),
+    };
+    StandardMaterial {
+        base_color,
+        emissive,
+        alpha_mode: AlphaMode::Blend,
+        perceptual_roughness: 1.0,
+        metallic: 0.0,
+        ..default()
+    }
+}
+
+fn on_hazard_zone_inserted(
+    mut commands: Commands,
+    mut msgs: ReadInsertMessage<HazardZoneRow>,
+    mut mapping: ResMut<HazardEntityMapping>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for msg in msgs.read() {
+        let zone = &msg.row;
+        let size = Vec3::from(zone.half_extents.clone()) * 2.0;
+        let entity = commands
+            .spawn((
+                HazardZone {
+                    instance_id: zone.instance_id,
+                },
+                Transform::from_translation(zone.center.clone().into()),
+                Mesh3d(meshes.add(Cuboid::from_size(size))),
+                MeshMaterial3d(materials.add(kind_material(&zone.kind))),
+                // Clicks go through to the ground below.
+                Pickable::IGNORE,
+                Visibility::Hidden,
+            ))
+            .id();
+        if let Some(previous) = mapping.0.insert(zone.id, entity) {
+            commands.entity(previous).despawn();
+        }
+    }
+}
+
+fn on_hazard_zone_deleted(
+    mut commands: Commands,
+    mut msgs: ReadDeleteMessage<HazardZoneRow>,
+    mut mapping: ResMut<HazardEntityMapping>,
+) {
+    for msg in msgs.read() {
+        if let Some(entity) = mapping.0.remove(&msg.row.id) {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn show_local_zone_hazards(
+    local_q: Query<&Zone, With<LocalActor>>,
+    mut hazard_q: Query<(&HazardZone, &mut Visibility)>,
+) {
+    let local_zone = local_q.single().ok().map(|zone| zone.0);
+    for (hazard, mut visibility) in &mut hazard_q {
+        let wanted = if local_zone == Some(hazard.instance_id) {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        visibility.set_if_neq(wanted);
+    }
+}
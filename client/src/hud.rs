@@ -0,0 +1,499 @@
+//! The core HUD: vitals globes, the action bar and the target frame.
+//!
+//! Everything shown is read from replicated state, never from local predictions:
+//!
+//! - Globes fill from the local actor's [`Health`] and [`Mana`].
+//! - Action bar slots call their reducer on `1`-`6`, the cooldown sweep and remaining seconds
+//!   come from the replicated [`Cooldowns`]. A slot on cooldown doesn't call the reducer, the
+//!   server would reject it anyway. Ground targeted slots start targeting instead, see
+//!   [`crate::cursor`].
+//! - The target frame follows the local actor's replicated [`Target`], it's hidden without one
+//!   or while the target is outside the AOI.
+//!
+//! There is no replicated stamina yet, it gets a globe once the server has it.
+
+use crate::{
+    ActorEntityMapping, LocalActor,
+    actor::ActorName,
+    command::command_line_closed,
+    cooldown::{Cooldowns, ServerClock},
+    cursor::GroundTargeting,
+    health::Health,
+    level::Level,
+    loading::LoadState,
+    mana::Mana,
+    module_bindings::{CooldownKind, attack, cast_aoe_ability, cast_projectile_ability, charge_to},
+    server::SpacetimeDB,
+    target::Target,
+};
+use bevy::prelude::*;
+use shared::{CHARGE_ABILITY_ID, GROUND_SLAM, GroundTargetAbility, UPHEAVAL};
+use spacetimedb_sdk::Timestamp;
+
+const GLOBE_SIZE: f32 = 96.0;
+const SLOT_SIZE: f32 = 48.0;
+const TARGET_FRAME_WIDTH: f32 = 220.0;
+const TARGET_BAR_HEIGHT: f32 = 12.0;
+
+/// Distance (pixels) of the HUD from the window edges.
+const HUD_MARGIN: f32 = 12.0;
+
+const HEALTH_COLOR: Color = Color::srgb(0.75, 0.1, 0.1);
+const MANA_COLOR: Color = Color::srgb(0.15, 0.3, 0.85);
+const PANEL_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.6);
+const SWEEP_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.7);
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(Startup, spawn_hud);
+    app.add_systems(
+        Update,
+        (
+            use_action_slots
+                .run_if(command_line_closed)
+                .run_if(in_state(LoadState::Spawned)),
+            update_vitals,
+            update_action_bar,
+            update_target_frame,
+        ),
+    );
+}
+
+/// What an action bar slot does.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SlotAction {
+    /// The basic melee attack on the current target.
+    Attack,
+    /// An AoE ability by id, see the server's `AOE_ABILITIES`.
+    Ability(u16),
+    /// An AoE ability cast at a point picked on the ground, see [`GroundTargeting`].
+    GroundTarget(GroundTargetAbility),
+    /// A projectile ability fired at the current target, see the server's
+    /// `PROJECTILE_ABILITIES`.
+    Projectile(u16),
+    /// A charge at the current target, see the server's `charge_to`.
+    Charge,
+}
+
+impl SlotAction {
+    fn cooldown_kind(self) -> CooldownKind {
+        match self {
+            SlotAction::Attack => CooldownKind::Attack,
+            SlotAction::Ability(id) | SlotAction::Projectile(id) => CooldownKind::Ability(id),
+            SlotAction::GroundTarget(ability) => CooldownKind::Ability(ability.id),
+            SlotAction::Charge => CooldownKind::Ability(CHARGE_ABILITY_ID),
+        }
+    }
+}
+
+struct ActionSlot {
+    key: KeyCode,
+    label: &'static str,
+    action: SlotAction,
+}
+
+/// The action bar, left to right.
+const ACTION_SLOTS: &[ActionSlot] = &[
+    ActionSlot {
+        key: KeyCode::Digit1,
+        label: "Attack",
+        action: SlotAction::Attack,
+    },
+    ActionSlot {
+        key: KeyCode::Digit2,
+        label: "Slam",
+        action: SlotAction::GroundTarget(GROUND_SLAM),
+    },
+    ActionSlot {
+        key: KeyCode::Digit3,
+        label: "Cleave",
+        action: SlotAction::Ability(2),
+    },
+    ActionSlot {
+        key: KeyCode::Digit4,
+        label: "Upheaval",
+        action: SlotAction::GroundTarget(UPHEAVAL),
+    },
+    ActionSlot {
+        key: KeyCode::Digit5,
+        label: "Bolt",
+        action: SlotAction::Projectile(4),
+    },
+    ActionSlot {
+        key: KeyCode::Digit6,
+        label: "Charge",
+        action: SlotAction::Charge,
+    },
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Vital {
+    Health,
+    Mana,
+}
+
+/// The fill of a vitals globe, its height is the vital's fraction.
+#[derive(Component)]
+struct GlobeFill(Vital);
+
+/// The `current / max` text over a globe.
+#[derive(Component)]
+struct GlobeText(Vital);
+
+/// The cooldown sweep over the slot at this index of [`ACTION_SLOTS`].
+#[derive(Component)]
+struct SlotSweep(usize);
+
+/// Remaining cooldown seconds of the slot at this index of [`ACTION_SLOTS`].
+#[derive(Component)]
+struct SlotTimer(usize);
+
+#[derive(Component)]
+struct TargetFrame;
+
+#[derive(Component)]
+struct TargetNameText;
+
+#[derive(Component)]
+struct TargetHealthFill;
+
+fn spawn_hud(mut commands: Commands) {
+    // The globes flank the action bar along the bottom edge, the command line keeps the corner.
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(HUD_MARGIN),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::FlexEnd,
+                column_gap: Val::Px(HUD_MARGIN),
+                ..default()
+            },
+            Pickable::IGNORE,
+        ))
+        .with_children(|row| {
+            spawn_globe(row, Vital::Health);
+            spawn_action_bar(row);
+            spawn_globe(row, Vital::Mana);
+        });
+    spawn_target_frame(&mut commands);
+}
+
+fn spawn_globe(parent: &mut ChildSpawnerCommands, vital: Vital) {
+    let color = match vital {
+        Vital::Health => HEALTH_COLOR,
+        Vital::Mana => MANA_COLOR,
+    };
+    parent
+        .spawn((
+            Node {
+                width: Val::Px(GLOBE_SIZE),
+                height: Val::Px(GLOBE_SIZE),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::FlexEnd,
+                overflow: Overflow::clip(),
+                ..default()
+            },
+            BorderRadius::MAX,
+            BackgroundColor(PANEL_COLOR),
+            Pickable::IGNORE,
+        ))
+        .with_children(|globe| {
+            globe.spawn((
+                GlobeFill(vital),
+                Node {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(0.0),
+                    ..default()
+                },
+                BorderRadius::MAX,
+                BackgroundColor(color),
+                Pickable::IGNORE,
+            ));
+            globe.spawn((
+                GlobeText(vital),
+                Text::default(),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                Node {
+                    position_type: PositionType::Absolute,
+                    top: Val::Percent(42.0),
+                    width: Val::Percent(100.0),
+                    ..default()
+                },
+                TextLayout::new_with_justify(Justify::Center),
+                Pickable::IGNORE,
+            ));
+        });
+}
+
+fn spawn_action_bar(parent: &mut ChildSpawnerCommands) {
+    parent
+        .spawn((
+            Node {
+                column_gap: Val::Px(4.0),
+                ..default()
+            },
+            Pickable::IGNORE,
+        ))
+        .with_children(|bar| {
+            for (index, slot) in ACTION_SLOTS.iter().enumerate() {
+                bar.spawn((
+                    Node {
+                        width: Val::Px(SLOT_SIZE),
+                        height: Val::Px(SLOT_SIZE),
+                        flex_direction: FlexDirection::Column,
+                        justify_content: JustifyContent::SpaceBetween,
+                        align_items: AlignItems::Center,
+                        overflow: Overflow::clip(),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(0.2, 0.2, 0.25, 0.8)),
+                    Pickable::IGNORE,
+                ))
+                .with_children(|cell| {
+                    cell.spawn((
+                        SlotSweep(index),
+                        Node {
+                            position_type: PositionType::Absolute,
+                            top: Val::Px(0.0),
+                            width: Val::Percent(100.0),
+                            height: Val::Percent(0.0),
+                            ..default()
+                        },
+                        BackgroundColor(SWEEP_COLOR),
+                        Pickable::IGNORE,
+                    ));
+                    cell.spawn((
+                        Text::new(format!("{}", index + 1)),
+                        TextFont {
+                            font_size: 11.0,
+                            ..default()
+                        },
+                        TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                        Pickable::IGNORE,
+                    ));
+                    cell.spawn((
+                        SlotTimer(index),
+                        Text::default(),
+                        TextFont {
+                            font_size: 16.0,
+                            ..default()
+                        },
+                        TextColor(Color::WHITE),
+                        Pickable::IGNORE,
+                    ));
+                    cell.spawn((
+                        Text::new(slot.label),
+                        TextFont {
+                            font_size: 10.0,
+                            ..default()
+                        },
+                        TextColor(Color::WHITE),
+                        Pickable::IGNORE,
+                    ));
+                });
+            }
+        });
+}
+
+fn spawn_target_frame(commands: &mut Commands) {
+    commands
+        .spawn((
+            TargetFrame,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(HUD_MARGIN),
+                left: Val::Percent(50.0),
+                margin: UiRect::left(Val::Px(-TARGET_FRAME_WIDTH * 0.5)),
+                width: Val::Px(TARGET_FRAME_WIDTH),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.0),
+                padding: UiRect::all(Val::Px(6.0)),
+                ..default()
+            },
+            BackgroundColor(PANEL_COLOR),
+            Visibility::Hidden,
+            Pickable::IGNORE,
+        ))
+        .with_children(|frame| {
+            frame.spawn((
+                TargetNameText,
+                Text::default(),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                Pickable::IGNORE,
+            ));
+            frame
+                .spawn((
+                    Node {
+                        width: Val::Percent(100.0),
+                        height: Val::Px(TARGET_BAR_HEIGHT),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+                    Pickable::IGNORE,
+                ))
+                .with_child((
+                    TargetHealthFill,
+                    Node {
+                        width: Val::Percent(100.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    BackgroundColor(HEALTH_COLOR),
+                    Pickable::IGNORE,
+                ));
+        });
+}
+
+/// `current / max` as a percentage of the bar, `0` without a max.
+fn fill_percent(current: u16, max: u16) -> f32 {
+    if max == 0 {
+        return 0.0;
+    }
+    (current as f32 / max as f32).clamp(0.0, 1.0) * 100.0
+}
+
+fn use_action_slots(
+    keys: Res<ButtonInput<KeyCode>>,
+    cooldowns: Res<Cooldowns>,
+    clock: Res<ServerClock>,
+    local_q: Query<(), With<LocalActor>>,
+    mut targeting: ResMut<GroundTargeting>,
+    stdb: SpacetimeDB,
+) {
+    if local_q.is_empty() {
+        return;
+    }
+    for slot in ACTION_SLOTS {
+        if !keys.just_pressed(slot.key) || !cooldowns.is_ready(&slot.action.cooldown_kind(), &clock)
+        {
+            continue;
+        }
+        // What the player aims at is as old as the server state it was shown.
+        let seen_at = Some(Timestamp::from_micros_since_unix_epoch(clock.now_micros()));
+        let called = match slot.action {
+            SlotAction::Attack => stdb.reducers().attack(None, seen_at),
+            SlotAction::Ability(id) => stdb.reducers().cast_aoe_ability(id, None, seen_at),
+            SlotAction::Projectile(id) => stdb.reducers().cast_projectile_ability(id, None),
+            SlotAction::Charge => stdb.reducers().charge_to(None),
+            SlotAction::GroundTarget(ability) => {
+                targeting.toggle(ability);
+                continue;
+            }
+        };
+        if let Err(e) = called {
+            println!("Error: {e}");
+        }
+    }
+}
+
+fn update_vitals(
+    local_q: Query<(Option<&Health>, Option<&Mana>), With<LocalActor>>,
+    mut fill_q: Query<(&GlobeFill, &mut Node)>,
+    mut text_q: Query<(&GlobeText, &mut Text)>,
+) {
+    let (health, mana) = local_q.single().unwrap_or_default();
+    let vital = |vital: Vital| match vital {
+        Vital::Health => health.map(|health| (health.current, health.max)),
+        Vital::Mana => mana.map(|mana| (mana.current, mana.max)),
+    };
+    for (fill, mut node) in &mut fill_q {
+        let percent = vital(fill.0).map_or(0.0, |(current, max)| fill_percent(current, max));
+        let height = Val::Percent(percent);
+        if node.height != height {
+            node.height = height;
+        }
+    }
+    for (globe_text, mut text) in &mut text_q {
+        let value = vital(globe_text.0)
+            .map(|(current, max)| format!("{current} / {max}"))
+            .unwrap_or_default();
+        if text.0 != value {
+            text.0 = value;
+        }
+    }
+}
+
+fn update_action_bar(
+    cooldowns: Res<Cooldowns>,
+    clock: Res<ServerClock>,
+    mut sweep_q: Query<(&SlotSweep, &mut Node)>,
+    mut timer_q: Query<(&SlotTimer, &mut Text)>,
+) {
+    let cooldown = |index: usize| {
+        ACTION_SLOTS
+            .get(index)
+            .and_then(|slot| cooldowns.get(&slot.action.cooldown_kind()))
+    };
+    for (sweep, mut node) in &mut sweep_q {
+        let fraction = cooldown(sweep.0).map_or(0.0, |timer| timer.fraction_remaining(&clock));
+        let height = Val::Percent(fraction * 100.0);
+        if node.height != height {
+            node.height = height;
+        }
+    }
+    for (timer, mut text) in &mut timer_q {
+        let remaining = cooldown(timer.0)
+            .map(|timer| timer.remaining(&clock).as_secs_f32())
+            .unwrap_or_default();
+        let value = if remaining > 0.0 {
+            format!("{}", remaining.ceil() as u32)
+        } else {
+            String::new()
+        };
+        if text.0 != value {
+            text.0 = value;
+        }
+    }
+}
+
+fn update_target_frame(
+    local_q: Query<Option<&Target>, With<LocalActor>>,
+    oe_mapping: Res<ActorEntityMapping>,
+    target_q: Query<(Option<&ActorName>, Option<&Level>, Option<&Health>)>,
+    mut frame_q: Query<&mut Visibility, With<TargetFrame>>,
+    mut name_q: Query<&mut Text, With<TargetNameText>>,
+    mut fill_q: Query<&mut Node, With<TargetHealthFill>>,
+) {
+    let target = local_q
+        .single()
+        .ok()
+        .flatten()
+        .and_then(|target| oe_mapping.0.get(&target.0))
+        .and_then(|&entity| target_q.get(entity).ok());
+
+    let Ok(mut visibility) = frame_q.single_mut() else {
+        return;
+    };
+    let Some((name, level, health)) = target else {
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    };
+    visibility.set_if_neq(Visibility::Inherited);
+
+    if let Ok(mut text) = name_q.single_mut() {
+        let name = name.map_or("", |name| name.0.as_str());
+        let value = match level {
+            Some(level) => format!("{name} (Lv {})", level.0),
+            None => name.to_string(),
+        };
+        if text.0 != value {
+            text.0 = value;
+        }
+    }
+    if let Ok(mut node) = fill_q.single_mut() {
+        let width =
+            Val::Percent(health.map_or(0.0, |health| fill_percent(health.current, health.max)));
+        if node.width != width {
+            node.width = width;
+        }
+    }
+}
@@ -1,5 +1,6 @@
-use bevy::prelude::*;
+use bevy::{platform::collections::HashMap, prelude::*};
 use leafwing_input_manager::prelude::*;
+use std::time::Duration;
 
 #[derive(Reflect, Actionlike, Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum InputAction {
@@ -15,4 +16,240 @@ pub(super) fn plugin(app: &mut App) {
     input_map.insert(InputAction::LeftClick, MouseButton::Left);
     app.insert_resource(input_map);
     app.insert_resource(ActionState::<InputAction>::default());
+    app.init_resource::<InputThrottles>();
+}
+
+/// The inputs sending a reducer call per click, each rate limited by its own [`InputThrottle`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ThrottledAction {
+    /// Clicking (and holding) on the ground to move.
+    Move,
+    /// Confirming the aim of a ground targeted ability.
+    GroundTarget,
+}
+
+impl ThrottledAction {
+    fn config(self) -> ThrottleConfig {
+        match self {
+            // Holding the button steers, a few updates a second are enough and the final point
+            // always goes out on release.
+            Self::Move => ThrottleConfig {
+                hold_delay: Some(Duration::from_millis(150)),
+                interval: Duration::from_millis(50),
+                min_distance: 0.25,
+            },
+            // One cast per click, a double click must not cast twice.
+            Self::GroundTarget => ThrottleConfig {
+                hold_delay: None,
+                interval: Duration::from_millis(250),
+                min_distance: 0.0,
+            },
+        }
+    }
+}
+
+/// How often a throttled input may be sent.
+#[derive(Clone, Copy, Debug)]
+pub struct ThrottleConfig {
+    /// How long the button is held before it repeats, `None` never repeats.
+    pub hold_delay: Option<Duration>,
+    /// Minimum time between two sends, also debouncing presses.
+    pub interval: Duration,
+    /// Minimum distance (meters) from the last sent point for a repeat or release to be sent.
+    pub min_distance: f32,
+}
+
+/// The phase of the button press an input happened in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Press {
+    Pressed,
+    Held,
+    Released,
+}
+
+impl Press {
+    pub fn of(actions: &ActionState<InputAction>, action: &InputAction) -> Option<Self> {
+        if actions.just_pressed(action) {
+            Some(Self::Pressed)
+        } else if actions.just_released(action) {
+            Some(Self::Released)
+        } else if actions.pressed(action) {
+            Some(Self::Held)
+        } else {
+            None
+        }
+    }
+}
+
+/// Per action timers and last sent point of a rate limited input.
+#[derive(Debug)]
+pub struct InputThrottle {
+    config: ThrottleConfig,
+    pressed_at: Option<Duration>,
+    last_sent_at: Option<Duration>,
+    last_point: Option<Vec3>,
+}
+
+impl InputThrottle {
+    pub fn new(config: ThrottleConfig) -> Self {
+        Self {
+            config,
+            pressed_at: None,
+            last_sent_at: None,
+            last_point: None,
+        }
+    }
+
+    /// Whether the input at `point` in the `press` phase should be sent at `now`, recorded as
+    /// sent when it should.
+    ///
+    /// Presses are sent unless debounced by [`ThrottleConfig::interval`]. Repeats start after
+    /// [`ThrottleConfig::hold_delay`], spaced by the interval and [`ThrottleConfig::min_distance`].
+    /// Releases of a repeating input send the final point regardless of the interval, so the
+    /// last update isn't lost.
+    pub fn allow(&mut self, now: Duration, press: Press, point: Vec3) -> bool {
+        match press {
+            Press::Pressed => {
+                // Recorded even when debounced, holding the press still repeats.
+                self.pressed_at = Some(now);
+                if self.debounced(now) {
+                    return false;
+                }
+            }
+            Press::Held => {
+                let (Some(pressed_at), Some(hold_delay)) =
+                    (self.pressed_at, self.config.hold_delay)
+                else {
+                    return false;
+                };
+                if now < pressed_at + hold_delay || self.debounced(now) || self.near_last(point) {
+                    return false;
+                }
+            }
+            Press::Released => {
+                let pressed_at = self.pressed_at.take();
+                if pressed_at.is_none() || self.config.hold_delay.is_none() || self.near_last(point)
+                {
+                    return false;
+                }
+            }
+        }
+        self.last_sent_at = Some(now);
+        self.last_point = Some(point);
+        true
+    }
+
+    fn debounced(&self, now: Duration) -> bool {
+        self.last_sent_at
+            .is_some_and(|last_sent_at| now < last_sent_at + self.config.interval)
+    }
+
+    fn near_last(&self, point: Vec3) -> bool {
+        self.last_point
+            .is_some_and(|last_point| last_point.distance(point) < self.config.min_distance)
+    }
+}
+
+/// The [`InputThrottle`] of every [`ThrottledAction`], created on first use.
+#[derive(Resource, Debug, Default)]
+pub struct InputThrottles(HashMap<ThrottledAction, InputThrottle>);
+
+impl InputThrottles {
+    /// See [`InputThrottle::allow`].
+    pub fn allow(
+        &mut self,
+        action: ThrottledAction,
+        now: Duration,
+        press: Press,
+        point: Vec3,
+    ) -> bool {
+        self.0
+            .entry(action)
+            .or_insert_with(|| InputThrottle::new(action.config()))
+            .allow(now, press, point)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOLD_DELAY: Duration = Duration::from_millis(150);
+    const INTERVAL: Duration = Duration::from_millis(50);
+
+    fn throttle() -> InputThrottle {
+        InputThrottle::new(ThrottleConfig {
+            hold_delay: Some(HOLD_DELAY),
+            interval: INTERVAL,
+            min_distance: 0.25,
+        })
+    }
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn holding_repeats_only_after_the_hold_delay() {
+        let mut throttle = throttle();
+        assert!(throttle.allow(ms(0), Press::Pressed, Vec3::ZERO));
+        assert!(!throttle.allow(ms(100), Press::Held, Vec3::X));
+        assert!(throttle.allow(ms(150), Press::Held, Vec3::X));
+    }
+
+    #[test]
+    fn repeats_are_spaced_by_the_interval() {
+        let mut throttle = throttle();
+        assert!(throttle.allow(ms(0), Press::Pressed, Vec3::ZERO));
+        assert!(throttle.allow(ms(150), Press::Held, Vec3::X));
+        assert!(!throttle.allow(ms(180), Press::Held, Vec3::X * 2.0));
+        assert!(throttle.allow(ms(200), Press::Held, Vec3::X * 2.0));
+    }
+
+    #[test]
+    fn repeats_near_the_last_point_are_not_sent() {
+        let mut throttle = throttle();
+        assert!(throttle.allow(ms(0), Press::Pressed, Vec3::ZERO));
+        assert!(!throttle.allow(ms(150), Press::Held, Vec3::X * 0.1));
+        assert!(throttle.allow(ms(160), Press::Held, Vec3::X * 0.3));
+    }
+
+    #[test]
+    fn release_sends_the_final_point_within_the_interval() {
+        let mut throttle = throttle();
+        assert!(throttle.allow(ms(0), Press::Pressed, Vec3::ZERO));
+        assert!(throttle.allow(ms(150), Press::Held, Vec3::X));
+        assert!(throttle.allow(ms(160), Press::Released, Vec3::X * 2.0));
+        // Nothing pressed anymore, holding or releasing again sends nothing.
+        assert!(!throttle.allow(ms(400), Press::Held, Vec3::X * 3.0));
+        assert!(!throttle.allow(ms(400), Press::Released, Vec3::X * 3.0));
+    }
+
+    #[test]
+    fn release_at_the_last_point_is_not_sent() {
+        let mut throttle = throttle();
+        assert!(throttle.allow(ms(0), Press::Pressed, Vec3::ZERO));
+        assert!(!throttle.allow(ms(100), Press::Released, Vec3::ZERO));
+    }
+
+    #[test]
+    fn debounced_press_still_repeats_when_held() {
+        let mut throttle = throttle();
+        assert!(throttle.allow(ms(0), Press::Pressed, Vec3::ZERO));
+        assert!(throttle.allow(ms(10), Press::Released, Vec3::X));
+        assert!(!throttle.allow(ms(30), Press::Pressed, Vec3::X * 2.0));
+        assert!(!throttle.allow(ms(100), Press::Held, Vec3::X * 2.0));
+        assert!(throttle.allow(ms(180), Press::Held, Vec3::X * 2.0));
+    }
+
+    #[test]
+    fn without_hold_delay_only_presses_are_sent() {
+        let mut throttle = InputThrottle::new(ThrottledAction::GroundTarget.config());
+        assert!(throttle.allow(ms(0), Press::Pressed, Vec3::ZERO));
+        assert!(!throttle.allow(ms(500), Press::Held, Vec3::X));
+        assert!(!throttle.allow(ms(500), Press::Released, Vec3::X));
+        // A double click is debounced.
+        assert!(throttle.allow(ms(600), Press::Pressed, Vec3::X));
+        assert!(!throttle.allow(ms(700), Press::Pressed, Vec3::X));
+    }
 }
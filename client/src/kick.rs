@@ -0,0 +1,49 @@
+//! Being kicked or banned. The server can't close the connection, it leaves a notice in
+//! `kick_notice_view` instead, the client shows it over everything and disconnects itself.
+
+use crate::{module_bindings::KickNoticeRow, server::SpacetimeDB};
+use bevy::prelude::*;
+use bevy_spacetimedb::ReadInsertMessage;
+use spacetimedb_sdk::DbContext;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(PreUpdate, on_kick_notice_inserted);
+}
+
+fn on_kick_notice_inserted(
+    mut msgs: ReadInsertMessage<KickNoticeRow>,
+    mut commands: Commands,
+    stdb: SpacetimeDB,
+) {
+    // Only the latest notice matters, there's a single row per identity.
+    let Some(notice) = msgs.read().last() else {
+        return;
+    };
+    warn!("Removed by the server: {}", notice.row.message);
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.85)),
+        // Over the loading screen and the command line, there's nothing left to type.
+        GlobalZIndex(3),
+        children![(
+            Text::new(format!("{}\n\nDisconnected.", notice.row.message)),
+            TextFont {
+                font_size: 24.0,
+                ..default()
+            },
+            TextColor(Color::srgb(1.0, 0.4, 0.4)),
+            TextLayout::new_with_justify(Justify::Center),
+        )],
+    ));
+
+    if let Err(error) = stdb.conn().disconnect() {
+        error!("Failed to disconnect: {error}");
+    }
+}
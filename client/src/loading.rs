@@ -0,0 +1,157 @@
+//! The way into the world, a state machine behind a loading screen:
+//!
+//! - [`LoadState::Connecting`] until the SpacetimeDB connection is up.
+//! - [`LoadState::Subscribing`] until the initial subscription is applied.
+//! - [`LoadState::Preloading`] until the static world is built and the local actor's transform
+//!   arrived. Entering the world is a slash command, the command line stays usable over the
+//!   loading screen.
+//! - [`LoadState::Spawned`] in the world, clicks, the action bar and the camera follow are only
+//!   enabled here.
+//!
+//! Losing the local actor (e.g. leaving the world) goes back to preloading.
+
+use crate::{LocalActor, transform::NetTransform, world::ClientStaticQueryWorld};
+use bevy::prelude::*;
+use bevy_spacetimedb::ReadStdbConnectedMessage;
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
+const SCREEN_COLOR: Color = Color::srgb(0.04, 0.05, 0.08);
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_state::<LoadState>();
+    app.init_resource::<SubscriptionApplied>();
+    app.add_systems(Startup, spawn_loading_screen);
+    app.add_systems(
+        Update,
+        (advance_load_state, update_loading_screen)
+            .chain()
+            .run_if(not(in_state(LoadState::Spawned))),
+    );
+    app.add_systems(
+        Update,
+        leave_when_local_actor_lost.run_if(in_state(LoadState::Spawned)),
+    );
+    app.add_systems(OnEnter(LoadState::Spawned), hide_loading_screen);
+    app.add_systems(OnExit(LoadState::Spawned), show_loading_screen);
+}
+
+#[derive(States, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum LoadState {
+    #[default]
+    Connecting,
+    Subscribing,
+    Preloading,
+    Spawned,
+}
+
+/// Set by the subscription's `on_applied` callback, which runs on the connection's thread.
+#[derive(Resource, Clone, Default)]
+pub struct SubscriptionApplied(Arc<AtomicBool>);
+
+impl SubscriptionApplied {
+    pub fn set(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+
+    fn get(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
+
+#[derive(Component)]
+struct LoadingScreen;
+
+#[derive(Component)]
+struct LoadingText;
+
+fn spawn_loading_screen(mut commands: Commands) {
+    commands
+        .spawn((
+            LoadingScreen,
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(SCREEN_COLOR),
+            // Over the HUD and nameplates, below the command line.
+            GlobalZIndex(1),
+        ))
+        .with_child((
+            LoadingText,
+            Text::default(),
+            TextFont {
+                font_size: 24.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+        ));
+}
+
+fn advance_load_state(
+    mut connected: ReadStdbConnectedMessage,
+    subscription: Res<SubscriptionApplied>,
+    query_world: Res<ClientStaticQueryWorld>,
+    local_q: Query<(), (With<LocalActor>, With<NetTransform>)>,
+    state: Res<State<LoadState>>,
+    mut next_state: ResMut<NextState<LoadState>>,
+) {
+    let connected = connected.read().count() > 0;
+    let next = match state.get() {
+        LoadState::Connecting if connected => LoadState::Subscribing,
+        LoadState::Subscribing if subscription.get() => LoadState::Preloading,
+        LoadState::Preloading if query_world.is_built() && !local_q.is_empty() => {
+            LoadState::Spawned
+        }
+        _ => return,
+    };
+    info!("Load state: {next:?}");
+    next_state.set(next);
+}
+
+fn update_loading_screen(
+    state: Res<State<LoadState>>,
+    query_world: Res<ClientStaticQueryWorld>,
+    mut text_q: Query<&mut Text, With<LoadingText>>,
+) {
+    let status = match state.get() {
+        LoadState::Connecting => "Connecting...",
+        LoadState::Subscribing => "Subscribing...",
+        LoadState::Preloading if !query_world.is_built() => "Loading world...",
+        LoadState::Preloading => "Type /enter to enter the world",
+        LoadState::Spawned => "",
+    };
+    for mut text in &mut text_q {
+        if text.0 != status {
+            text.0 = status.to_string();
+        }
+    }
+}
+
+fn leave_when_local_actor_lost(
+    local_q: Query<(), With<LocalActor>>,
+    mut next_state: ResMut<NextState<LoadState>>,
+) {
+    if local_q.is_empty() {
+        info!("Load state: {:?}", LoadState::Preloading);
+        next_state.set(LoadState::Preloading);
+    }
+}
+
+fn hide_loading_screen(mut screen_q: Query<&mut Visibility, With<LoadingScreen>>) {
+    for mut visibility in &mut screen_q {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+fn show_loading_screen(mut screen_q: Query<&mut Visibility, With<LoadingScreen>>) {
+    for mut visibility in &mut screen_q {
+        *visibility = Visibility::Inherited;
+    }
+}
@@ -5,23 +5,54 @@
 
 #[cfg(feature = "dev_native")]
 mod debug_tools;
+#[cfg(feature = "editor")]
+mod editor;
 
+mod accessibility;
 mod actor;
+mod afk;
+mod ambient;
 mod camera;
+#[cfg(not(target_arch = "wasm32"))]
+mod capture;
+mod combat_log;
+mod combat_text;
+mod command;
+mod cooldown;
+mod culling;
 mod cursor;
+mod effects;
+mod emote;
 mod experience;
 mod extrapolate_move;
+mod foot_placement;
+mod footstep;
+mod hazard;
 mod health;
+mod hud;
 mod input;
+mod kick;
 mod level;
+mod loading;
 mod mana;
 mod module_bindings;
+mod movement;
 mod movement_state;
+mod nameplate;
+mod net_audit;
 mod player;
+mod presentation;
+mod projectile;
 mod secondary_stats;
 mod server;
+mod settings;
+mod spectator;
+mod target;
 mod transform;
+mod tutorial;
+mod who;
 mod world;
+mod world_bounds;
 
 pub use actor::{ActorEntity, ActorEntityMapping, LocalActor, RemoteActor, ensure_actor_entity};
 
@@ -75,8 +106,44 @@ impl Plugin for AppPlugin {
             movement_state::plugin,
             secondary_stats::plugin,
         ));
+        // Plugin tuples are limited to 15 entries.
+        app.add_plugins((
+            movement::plugin,
+            cooldown::plugin,
+            footstep::plugin,
+            combat_text::plugin,
+            target::plugin,
+            command::plugin,
+            who::plugin,
+            settings::plugin,
+            culling::plugin,
+            nameplate::plugin,
+            presentation::plugin,
+            effects::plugin,
+            emote::plugin,
+            ambient::plugin,
+            spectator::plugin,
+        ));
+        app.add_plugins((
+            accessibility::plugin,
+            afk::plugin,
+            combat_log::plugin,
+            foot_placement::plugin,
+            hazard::plugin,
+            hud::plugin,
+            kick::plugin,
+            loading::plugin,
+            net_audit::plugin,
+            projectile::plugin,
+            tutorial::plugin,
+            world_bounds::plugin,
+        ));
 
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_plugins(capture::plugin);
         #[cfg(feature = "dev_native")]
         app.add_plugins(debug_tools::plugin);
+        #[cfg(feature = "editor")]
+        app.add_plugins(editor::plugin);
     }
 }
@@ -0,0 +1,264 @@
+//! Client-side reconciliation of the local actor's movement intents.
+//!
+//! Replicated movement states replace the local one wholesale, so a correction stamped with an
+//! older [`ClientIntentSeq`] than the last click would throw that click away until the server
+//! catches up. Instead every sent intent is kept in the [`IntentBuffer`] until it's acknowledged,
+//! and the unacknowledged ones are re-applied on top of each authoritative state:
+//!
+//! - Intents replace each other, re-simulating the buffer means applying the newest one.
+//! - Intents the server never acknowledges (e.g. rejected while airborne) are dropped after
+//!   [`PENDING_INTENT_TIMEOUT_SECS`], restoring the last authoritative intent.
+
+use super::ClientIntentSeq;
+use crate::{
+    ActorEntityMapping, LocalActor,
+    module_bindings::{MoveIntentData, MovementStateRow},
+    movement_state::MovementState,
+};
+use bevy::prelude::*;
+use bevy_spacetimedb::ReadUpdateMessage;
+use shared::is_tick_newer;
+use std::collections::VecDeque;
+
+/// Time (seconds) after which an unacknowledged intent is considered rejected.
+const PENDING_INTENT_TIMEOUT_SECS: f32 = 1.0;
+
+/// A movement intent sent to the server and not acknowledged yet.
+#[derive(Debug, Clone)]
+struct PendingIntent {
+    seq: u32,
+    intent: MoveIntentData,
+    /// `Time::elapsed_secs` when it was first reconciled, about when it was sent.
+    sent_at: Option<f32>,
+}
+
+/// The local actor's unacknowledged intents, oldest first, and the authoritative state they are
+/// re-applied on top of.
+#[derive(Resource, Debug, Default)]
+pub struct IntentBuffer {
+    pending: VecDeque<PendingIntent>,
+    /// Intent and `should_move` of the last replicated movement state, `None` until one arrives.
+    authoritative: Option<(MoveIntentData, bool)>,
+}
+
+impl IntentBuffer {
+    /// Buffers an intent sent with `seq`, the local actor moves on it right away.
+    pub fn push(&mut self, seq: u32, intent: MoveIntentData) {
+        self.pending.push_back(PendingIntent {
+            seq,
+            intent,
+            sent_at: None,
+        });
+    }
+
+    /// Is any buffered intent acknowledged or timed out?
+    fn has_settled(&self, seq: &ClientIntentSeq, now: f32) -> bool {
+        self.pending
+            .iter()
+            .any(|pending| Self::is_settled(pending, seq, now))
+    }
+
+    fn is_settled(pending: &PendingIntent, seq: &ClientIntentSeq, now: f32) -> bool {
+        seq.is_acked(pending.seq)
+            || pending
+                .sent_at
+                .is_some_and(|sent_at| now - sent_at >= PENDING_INTENT_TIMEOUT_SECS)
+    }
+
+    /// Stamps the intents not reconciled yet as sent at `now`.
+    fn stamp_sent(&mut self, now: f32) {
+        for pending in &mut self.pending {
+            pending.sent_at.get_or_insert(now);
+        }
+    }
+
+    /// Drops the acknowledged and timed out intents.
+    fn prune(&mut self, seq: &ClientIntentSeq, now: f32) {
+        self.pending
+            .retain(|pending| !Self::is_settled(pending, seq, now));
+    }
+
+    /// Re-applies the newest unacknowledged intent on top of the authoritative state.
+    fn reconcile(&self, movement_state: &mut MovementState) {
+        let (intent, should_move) = match (self.pending.back(), &self.authoritative) {
+            (Some(pending), _) => (
+                pending.intent.clone(),
+                movement_state.should_move || !matches!(pending.intent, MoveIntentData::None),
+            ),
+            (None, Some((intent, should_move))) => (intent.clone(), *should_move),
+            (None, None) => return,
+        };
+        movement_state.move_intent = intent;
+        movement_state.should_move = should_move;
+    }
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<IntentBuffer>();
+    app.add_systems(
+        PreUpdate,
+        reconcile_pending_intents
+            .after(super::ack_from_movement_state)
+            .after(super::ack_from_transform)
+            .after(crate::movement_state::on_movement_state_updated)
+            .before(crate::extrapolate_move::extrapolate_move),
+    );
+}
+
+/// Drops settled intents and re-applies the rest whenever the buffer or the local actor's
+/// authoritative movement state changed.
+fn reconcile_pending_intents(
+    time: Res<Time>,
+    seq: Res<ClientIntentSeq>,
+    mut buffer: ResMut<IntentBuffer>,
+    mut msgs: ReadUpdateMessage<MovementStateRow>,
+    oe_mapping: Res<ActorEntityMapping>,
+    mut local_q: Query<(Entity, &mut MovementState), With<LocalActor>>,
+) {
+    let Ok((local_entity, mut movement_state)) = local_q.single_mut() else {
+        return;
+    };
+    let mut corrected = false;
+    for msg in msgs.read() {
+        if oe_mapping.0.get(&msg.new.actor_id) != Some(&local_entity) {
+            continue;
+        }
+        // Same as `on_movement_state_updated`, older ticks were discarded.
+        if is_tick_newer(movement_state.server_tick, msg.new.server_tick) {
+            continue;
+        }
+        buffer.authoritative = Some((msg.new.move_intent.clone(), msg.new.should_move));
+        corrected = true;
+    }
+
+    let now = time.elapsed_secs();
+    if buffer
+        .pending
+        .iter()
+        .any(|pending| pending.sent_at.is_none())
+    {
+        buffer.stamp_sent(now);
+    }
+    if buffer.has_settled(&seq, now) {
+        buffer.prune(&seq, now);
+    }
+    if corrected || buffer.is_changed() {
+        buffer.reconcile(&mut movement_state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::module_bindings::Vec2;
+
+    fn point(x: f32) -> MoveIntentData {
+        MoveIntentData::Point(Vec2 { x, z: 0.0 })
+    }
+
+    fn movement_state() -> MovementState {
+        MovementState {
+            cell_id: 0,
+            should_move: false,
+            move_intent: MoveIntentData::None,
+            vertical_velocity: 0,
+            fall_elapsed: 0.0,
+            ground_material: None,
+            server_tick: 0,
+        }
+    }
+
+    /// A buffer of `count` intents sent with the next sequence numbers, stamped as sent at `0`.
+    fn sent(seq: &mut ClientIntentSeq, count: u32) -> IntentBuffer {
+        let mut buffer = IntentBuffer::default();
+        for i in 0..count {
+            buffer.push(seq.next(), point(i as f32));
+        }
+        buffer.stamp_sent(0.0);
+        buffer
+    }
+
+    fn pending_seqs(buffer: &IntentBuffer) -> Vec<u32> {
+        buffer.pending.iter().map(|pending| pending.seq).collect()
+    }
+
+    #[test]
+    fn the_newest_pushed_intent_is_applied() {
+        let mut seq = ClientIntentSeq::default();
+        let buffer = sent(&mut seq, 3);
+        let mut state = movement_state();
+        buffer.reconcile(&mut state);
+        assert_eq!(state.move_intent, point(2.0));
+        assert!(state.should_move);
+    }
+
+    #[test]
+    fn acked_intents_are_pruned() {
+        let mut seq = ClientIntentSeq::default();
+        let mut buffer = sent(&mut seq, 3);
+        seq.ack(2);
+        assert!(buffer.has_settled(&seq, 0.0));
+        buffer.prune(&seq, 0.0);
+        assert_eq!(pending_seqs(&buffer), vec![3]);
+
+        seq.ack(3);
+        buffer.prune(&seq, 0.0);
+        assert!(buffer.pending.is_empty());
+    }
+
+    #[test]
+    fn without_pending_intents_the_authoritative_one_is_restored() {
+        let mut seq = ClientIntentSeq::default();
+        let mut buffer = sent(&mut seq, 1);
+        buffer.authoritative = Some((MoveIntentData::None, false));
+        seq.ack(1);
+        buffer.prune(&seq, 0.0);
+
+        let mut state = movement_state();
+        state.move_intent = point(0.0);
+        state.should_move = true;
+        buffer.reconcile(&mut state);
+        assert_eq!(state.move_intent, MoveIntentData::None);
+        assert!(!state.should_move);
+    }
+
+    #[test]
+    fn unacked_intents_time_out() {
+        let mut seq = ClientIntentSeq::default();
+        let mut buffer = sent(&mut seq, 2);
+        let before = PENDING_INTENT_TIMEOUT_SECS - 0.1;
+        assert!(!buffer.has_settled(&seq, before));
+        buffer.prune(&seq, PENDING_INTENT_TIMEOUT_SECS);
+        assert!(buffer.pending.is_empty());
+    }
+
+    #[test]
+    fn intents_are_only_timed_out_once_stamped() {
+        let mut seq = ClientIntentSeq::default();
+        let mut buffer = IntentBuffer::default();
+        buffer.push(seq.next(), point(0.0));
+        assert!(!buffer.has_settled(&seq, 10.0));
+        buffer.stamp_sent(10.0);
+        buffer.stamp_sent(20.0);
+        assert!(!buffer.has_settled(&seq, 10.5));
+        assert!(buffer.has_settled(&seq, 11.0));
+    }
+
+    #[test]
+    fn pruning_follows_the_sequence_across_overflow() {
+        let mut seq = ClientIntentSeq {
+            sent: u32::MAX - 2,
+            acked: u32::MAX - 2,
+        };
+        let mut buffer = sent(&mut seq, 4);
+        assert_eq!(pending_seqs(&buffer), vec![u32::MAX - 1, u32::MAX, 0, 1]);
+
+        seq.ack(u32::MAX);
+        buffer.prune(&seq, 0.0);
+        assert_eq!(pending_seqs(&buffer), vec![0, 1]);
+
+        seq.ack(0);
+        buffer.prune(&seq, 0.0);
+        assert_eq!(pending_seqs(&buffer), vec![1]);
+    }
+}
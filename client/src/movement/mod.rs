@@ -0,0 +1,94 @@
+//! Client side of the movement intent reconcile contract.
+//!
+//! - Every `request_move`/`cancel_move` call is stamped with the next [`ClientIntentSeq`].
+//! - The server stores the last applied sequence on `movement_state_tbl.client_intent_seq` and
+//!   copies it onto `transform_tbl.client_intent_seq` with each authoritative write.
+//! - An intent is acknowledged once a replicated row for the local actor carries a sequence
+//!   equal to or newer than the one it was sent with. Older intents are implicitly acknowledged
+//!   by newer ones, even if the server ignored them as duplicates.
+//! - Unacknowledged intents are buffered and re-applied on top of corrections, see
+//!   [`input_buffer`].
+
+mod input_buffer;
+
+pub use input_buffer::IntentBuffer;
+
+use crate::{
+    ActorEntityMapping, LocalActor,
+    module_bindings::{MovementStateRow, TransformRow},
+};
+use bevy::prelude::*;
+use bevy_spacetimedb::ReadUpdateMessage;
+use shared::is_tick_newer;
+
+/// Sequence numbers for movement intents sent by the local player.
+#[derive(Resource, Debug, Default)]
+pub struct ClientIntentSeq {
+    /// The last sequence number handed out.
+    pub sent: u32,
+    /// The newest sequence number acknowledged by the server.
+    pub acked: u32,
+}
+
+impl ClientIntentSeq {
+    /// Allocates the sequence number for a new intent.
+    pub fn next(&mut self) -> u32 {
+        self.sent = self.sent.wrapping_add(1);
+        self.sent
+    }
+
+    /// Records an acknowledgement from the server, ignoring stale ones.
+    pub fn ack(&mut self, seq: u32) {
+        if is_tick_newer(seq, self.acked) {
+            self.acked = seq;
+        }
+    }
+
+    /// Is the given intent acknowledged by the server?
+    pub fn is_acked(&self, seq: u32) -> bool {
+        !is_tick_newer(seq, self.acked)
+    }
+
+    /// Are there intents sent that the server hasn't acknowledged yet?
+    pub fn has_pending(&self) -> bool {
+        self.sent != self.acked
+    }
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.insert_resource(ClientIntentSeq::default());
+    app.add_systems(PreUpdate, (ack_from_movement_state, ack_from_transform));
+    app.add_plugins(input_buffer::plugin);
+}
+
+fn ack_from_movement_state(
+    mut seq: ResMut<ClientIntentSeq>,
+    mut msgs: ReadUpdateMessage<MovementStateRow>,
+    oe_mapping: Res<ActorEntityMapping>,
+    local_q: Query<(), With<LocalActor>>,
+) {
+    for msg in msgs.read() {
+        let Some(&bevy_entity) = oe_mapping.0.get(&msg.new.actor_id) else {
+            continue;
+        };
+        if local_q.get(bevy_entity).is_ok() {
+            seq.ack(msg.new.client_intent_seq);
+        }
+    }
+}
+
+fn ack_from_transform(
+    mut seq: ResMut<ClientIntentSeq>,
+    mut msgs: ReadUpdateMessage<TransformRow>,
+    oe_mapping: Res<ActorEntityMapping>,
+    local_q: Query<(), With<LocalActor>>,
+) {
+    for msg in msgs.read() {
+        let Some(&bevy_entity) = oe_mapping.0.get(&msg.new.actor_id) else {
+            continue;
+        };
+        if local_q.get(bevy_entity).is_ok() {
+            seq.ack(msg.new.client_intent_seq);
+        }
+    }
+}
@@ -1,17 +1,25 @@
 use crate::{
     ActorEntityMapping, ensure_actor_entity,
-    module_bindings::{MoveIntentData, MovementStateRow},
+    module_bindings::{MoveIntentData, MovementStateRow, SurfaceMaterial},
 };
 use bevy::prelude::*;
 use bevy_spacetimedb::{ReadInsertMessage, ReadUpdateMessage};
-use shared::CellId;
+use shared::{CellId, is_tick_newer};
 
 #[derive(Component, Debug)]
 pub struct MovementState {
     pub cell_id: CellId,
     pub should_move: bool,
     pub move_intent: MoveIntentData,
+    /// Quantized like the server's (see `shared::quantize_vertical_velocity`), advanced locally
+    /// while falling by `extrapolate_move`.
     pub vertical_velocity: i8,
+    /// Time (seconds) since `vertical_velocity` was last advanced or received from the server.
+    pub fall_elapsed: f32,
+    /// Surface of the last ground contact, `None` when unknown or unclassified.
+    pub ground_material: Option<SurfaceMaterial>,
+    /// Server tick of the last applied update, older updates are discarded.
+    pub server_tick: u32,
 }
 
 pub(super) fn plugin(app: &mut App) {
@@ -34,11 +42,14 @@ fn on_movement_state_inserted(
             cell_id: msg.row.cell_id,
             should_move: msg.row.should_move,
             vertical_velocity: msg.row.vertical_velocity,
+            fall_elapsed: 0.0,
+            ground_material: msg.row.ground_material.clone(),
+            server_tick: msg.row.server_tick,
         });
     }
 }
 
-fn on_movement_state_updated(
+pub(crate) fn on_movement_state_updated(
     mut movement_state_q: Query<&mut MovementState>,
     mut msgs: ReadUpdateMessage<MovementStateRow>,
     oe_mapping: Res<ActorEntityMapping>,
@@ -51,10 +62,18 @@ fn on_movement_state_updated(
             continue;
         };
 
+        // Discard updates stamped with an older tick than the one already applied.
+        if is_tick_newer(movement_state.server_tick, msg.new.server_tick) {
+            continue;
+        }
+
         // println!("on_movement_state_updated: {:?}", msg.new.actor_id);
         movement_state.move_intent = msg.new.move_intent.clone();
         movement_state.cell_id = msg.new.cell_id;
         movement_state.should_move = msg.new.should_move;
         movement_state.vertical_velocity = msg.new.vertical_velocity;
+        movement_state.fall_elapsed = 0.0;
+        movement_state.ground_material = msg.new.ground_material.clone();
+        movement_state.server_tick = msg.new.server_tick;
     }
 }
@@ -0,0 +1,247 @@
+//! Nameplates and health bars above remote actors.
+//!
+//! - One UI root ([`NameplateLayer`]) holds every plate, plates are plain UI nodes positioned
+//!   each frame by projecting the actor's head to the viewport in a single system.
+//! - Plates fade out with distance from the camera and hide when a world static is between the
+//!   camera and the actor (line of sight against [`ClientStaticQueryWorld`]). The occlusion rays
+//!   are re-cast a few times per second, not every frame.
+//! - Culled actors (see [`crate::culling`]) have no plate shown.
+
+use crate::{
+    ActorEntity, LocalActor,
+    accessibility::Accessibility,
+    actor::{ActorCapsule, ActorKind, ActorName},
+    culling::Culled,
+    health::Health,
+    world::ClientStaticQueryWorld,
+};
+use bevy::{platform::collections::HashMap, prelude::*};
+use nalgebra::Vector3;
+use shared::has_line_of_sight;
+
+/// Plates start fading at this distance (meters) from the camera...
+const FADE_START_DISTANCE: f32 = 40.0;
+/// ...and are hidden beyond this one.
+const FADE_END_DISTANCE: f32 = 70.0;
+
+/// Height (meters) of the plate above the top of the actor's capsule.
+const PLATE_HEIGHT: f32 = 0.4;
+
+const PLATE_WIDTH: f32 = 100.0;
+const BAR_HEIGHT: f32 = 6.0;
+
+/// How often the occlusion rays are re-cast.
+const OCCLUSION_INTERVAL_SECS: f32 = 0.1;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Nameplates>();
+    app.insert_resource(OcclusionTimer(Timer::from_seconds(
+        OCCLUSION_INTERVAL_SECS,
+        TimerMode::Repeating,
+    )));
+    app.add_systems(Startup, spawn_nameplate_layer);
+    app.add_systems(Update, (sync_nameplates, update_nameplates).chain());
+}
+
+/// The UI root all plates are children of.
+#[derive(Component)]
+struct NameplateLayer;
+
+/// Plate entity per actor entity.
+#[derive(Resource, Default)]
+struct Nameplates(HashMap<Entity, Entity>);
+
+#[derive(Resource)]
+struct OcclusionTimer(Timer);
+
+#[derive(Component)]
+struct Nameplate {
+    name_text: Entity,
+    bar_fill: Entity,
+    /// Result of the last occlusion check.
+    occluded: bool,
+}
+
+fn spawn_nameplate_layer(mut commands: Commands) {
+    commands.spawn((
+        NameplateLayer,
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            ..default()
+        },
+        // Below the command line and other panels.
+        GlobalZIndex(-1),
+        // Clicks go through to the world.
+        Pickable::IGNORE,
+    ));
+}
+
+/// Spawns plates for new remote actors and despawns those of actors that are gone.
+fn sync_nameplates(
+    mut commands: Commands,
+    accessibility: Res<Accessibility>,
+    mut nameplates: ResMut<Nameplates>,
+    layer: Single<Entity, With<NameplateLayer>>,
+    new_q: Query<
+        (Entity, &ActorName, Option<&ActorKind>),
+        (With<ActorEntity>, Without<LocalActor>, Added<ActorName>),
+    >,
+    actor_q: Query<(), (With<ActorEntity>, Without<LocalActor>)>,
+) {
+    nameplates.0.retain(|&actor, &mut plate| {
+        let alive = actor_q.contains(actor);
+        if !alive {
+            commands.entity(plate).despawn();
+        }
+        alive
+    });
+
+    for (actor, name, kind) in &new_q {
+        if nameplates.0.contains_key(&actor) {
+            continue;
+        }
+        let name_text = commands
+            .spawn((
+                Text::new(name.0.clone()),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                Pickable::IGNORE,
+            ))
+            .id();
+        let bar_fill = commands
+            .spawn((
+                Node {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    ..default()
+                },
+                BackgroundColor(accessibility.palette.bar_color(kind)),
+                Pickable::IGNORE,
+            ))
+            .id();
+        let bar = commands
+            .spawn((
+                Node {
+                    width: Val::Px(PLATE_WIDTH),
+                    height: Val::Px(BAR_HEIGHT),
+                    ..default()
+                },
+                BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+                Pickable::IGNORE,
+            ))
+            .add_child(bar_fill)
+            .id();
+        let plate = commands
+            .spawn((
+                Nameplate {
+                    name_text,
+                    bar_fill,
+                    occluded: false,
+                },
+                Node {
+                    position_type: PositionType::Absolute,
+                    width: Val::Px(PLATE_WIDTH),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                // Hidden until positioned, avoids a frame at the top left corner.
+                Visibility::Hidden,
+                Pickable::IGNORE,
+                ChildOf(*layer),
+            ))
+            .add_children(&[name_text, bar])
+            .id();
+        nameplates.0.insert(actor, plate);
+    }
+}
+
+/// Projects, fades, occludes and fills every plate.
+fn update_nameplates(
+    time: Res<Time>,
+    accessibility: Res<Accessibility>,
+    mut occlusion_timer: ResMut<OcclusionTimer>,
+    nameplates: Res<Nameplates>,
+    query_world: Res<ClientStaticQueryWorld>,
+    ui_scale: Res<UiScale>,
+    camera: Single<(&Camera, &GlobalTransform), With<Camera3d>>,
+    actor_q: Query<(
+        &GlobalTransform,
+        &ActorCapsule,
+        Option<&ActorKind>,
+        Option<&Health>,
+        Has<Culled>,
+    )>,
+    mut plate_q: Query<(&mut Nameplate, &mut Node, &mut Visibility)>,
+    mut text_q: Query<&mut TextColor>,
+    mut fill_q: Query<(&mut Node, &mut BackgroundColor), Without<Nameplate>>,
+) {
+    let (camera, camera_transform) = *camera;
+    let camera_translation = camera_transform.translation();
+    let check_occlusion = occlusion_timer.0.tick(time.delta()).just_finished();
+
+    for (&actor, &plate) in &nameplates.0 {
+        let (
+            Ok((transform, capsule, kind, health, culled)),
+            Ok((mut nameplate, mut node, mut visibility)),
+        ) = (actor_q.get(actor), plate_q.get_mut(plate))
+        else {
+            continue;
+        };
+
+        let head = transform.translation()
+            + Vec3::Y * (capsule.half_height + capsule.radius + PLATE_HEIGHT);
+        let distance = camera_translation.distance(head);
+        if culled || distance > FADE_END_DISTANCE {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+
+        if check_occlusion {
+            nameplate.occluded = !has_line_of_sight(
+                &query_world.world,
+                Vector3::new(
+                    camera_translation.x,
+                    camera_translation.y,
+                    camera_translation.z,
+                ),
+                Vector3::new(head.x, head.y, head.z),
+            );
+        }
+        let Ok(viewport_pos) = camera.world_to_viewport(camera_transform, head) else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        if nameplate.occluded {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+
+        // UI values are scaled by `UiScale`, viewport positions aren't.
+        let ui_pos = viewport_pos / ui_scale.0;
+        node.left = Val::Px(ui_pos.x - PLATE_WIDTH * 0.5);
+        node.top = Val::Px(ui_pos.y);
+        *visibility = Visibility::Inherited;
+
+        let alpha = 1.0
+            - ((distance - FADE_START_DISTANCE) / (FADE_END_DISTANCE - FADE_START_DISTANCE))
+                .clamp(0.0, 1.0);
+        if let Ok(mut text_color) = text_q.get_mut(nameplate.name_text) {
+            text_color.0.set_alpha(alpha);
+        }
+        if let Ok((mut fill_node, mut fill_color)) = fill_q.get_mut(nameplate.bar_fill) {
+            let fraction = health
+                .filter(|health| health.max > 0)
+                .map(|health| health.current as f32 / health.max as f32)
+                .unwrap_or(1.0);
+            fill_node.width = Val::Percent(fraction * 100.0);
+            // The palette may have changed since the plate was spawned.
+            fill_color.0 = accessibility.palette.bar_color(kind).with_alpha(alpha);
+        }
+    }
+}
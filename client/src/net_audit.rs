@@ -0,0 +1,242 @@
+//! Replication audit, `/netaudit [seconds]` samples the row updates received for the high
+//! traffic views and reports bytes per actor per second, update rates per table and the actors
+//! costing the most. The report stays on screen until `/netaudit off`.
+//!
+//! Sizes are the BSATN encoding of the rows, what the server sends before compression. An update
+//! counts twice, the old row goes out as a delete next to the new one. Good enough to compare
+//! quantization and delta changes against each other, not an exact wire measurement.
+
+use crate::module_bindings::{
+    ActorRow, CombatEventRow, HealthRow, ManaRow, MovementStateRow, ProjectileRow, TransformRow,
+};
+use bevy::{platform::collections::HashMap, prelude::*};
+use bevy_spacetimedb::{ReadDeleteMessage, ReadInsertMessage, ReadUpdateMessage};
+use shared::ActorId;
+use spacetimedb_sdk::__codegen::__lib::{bsatn, ser::Serialize};
+use std::time::Duration;
+
+/// Sample length of `/netaudit` without an argument.
+pub const DEFAULT_AUDIT_SECS: u32 = 10;
+
+/// Upper bound of a sample, keeps the per-actor map bounded.
+pub const MAX_AUDIT_SECS: u32 = 300;
+
+/// How many actors the report lists.
+const TOP_OFFENDERS: usize = 5;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<NetAudit>();
+    app.add_systems(Startup, spawn_net_audit_panel);
+    app.add_systems(
+        PreUpdate,
+        (
+            sample_table::<TransformRow>,
+            sample_table::<MovementStateRow>,
+            sample_table::<ActorRow>,
+            sample_table::<HealthRow>,
+            sample_table::<ManaRow>,
+            sample_table::<CombatEventRow>,
+            sample_table::<ProjectileRow>,
+        ),
+    );
+    app.add_systems(Update, finish_net_audit);
+}
+
+/// A replicated row the audit measures.
+trait AuditedRow: Serialize + Send + Sync + 'static {
+    const TABLE: &'static str;
+
+    /// The actor the row's traffic is attributed to, if any.
+    fn actor_id(&self) -> Option<ActorId>;
+}
+
+macro_rules! audited_row {
+    ($row:ty, $table:literal, |$r:ident| $actor_id:expr) => {
+        impl AuditedRow for $row {
+            const TABLE: &'static str = $table;
+
+            fn actor_id(&self) -> Option<ActorId> {
+                let $r = self;
+                $actor_id
+            }
+        }
+    };
+}
+
+audited_row!(TransformRow, "transform_view", |r| Some(r.actor_id));
+audited_row!(MovementStateRow, "movement_state_view", |r| Some(
+    r.actor_id
+));
+audited_row!(ActorRow, "actor_view", |r| Some(r.id));
+audited_row!(HealthRow, "health_view", |r| Some(r.actor_id));
+audited_row!(ManaRow, "mana_view", |r| Some(r.actor_id));
+audited_row!(CombatEventRow, "combat_event_view", |r| Some(r.target));
+audited_row!(ProjectileRow, "projectile_view", |_r| None);
+
+#[derive(Debug, Default, Clone, Copy)]
+struct TableTraffic {
+    inserts: u32,
+    updates: u32,
+    deletes: u32,
+    bytes: u64,
+}
+
+/// The running sample, `None` timer while idle.
+#[derive(Resource, Debug, Default)]
+pub struct NetAudit {
+    timer: Option<Timer>,
+    tables: HashMap<&'static str, TableTraffic>,
+    actors: HashMap<ActorId, u64>,
+    /// The report of the last finished sample.
+    report: Option<String>,
+}
+
+impl NetAudit {
+    /// Starts a new sample of `secs` seconds, dropping one in progress.
+    pub fn start(&mut self, secs: u32) {
+        *self = Self {
+            timer: Some(Timer::new(
+                Duration::from_secs(u64::from(secs)),
+                TimerMode::Once,
+            )),
+            ..default()
+        };
+    }
+
+    /// Cancels a sample in progress and hides the report.
+    pub fn stop(&mut self) {
+        *self = Self::default();
+    }
+
+    fn record<T: AuditedRow>(&mut self, row: &T, rows: u32, traffic: impl Fn(&mut TableTraffic)) {
+        let bytes = encoded_len(row) * u64::from(rows);
+        let table = self.tables.entry(T::TABLE).or_default();
+        traffic(table);
+        table.bytes += bytes;
+        if let Some(actor_id) = row.actor_id() {
+            *self.actors.entry(actor_id).or_default() += bytes;
+        }
+    }
+
+    fn report(&self, secs: f32) -> String {
+        let mut lines = vec![format!("Net audit over {secs:.0}s (uncompressed BSATN)")];
+
+        let total: u64 = self.tables.values().map(|table| table.bytes).sum();
+        let actors = self.actors.len().max(1);
+        lines.push(format!(
+            "total {:.1} KB/s, {} actors, {:.0} B/actor/s",
+            total as f32 / secs / 1024.0,
+            self.actors.len(),
+            total as f32 / secs / actors as f32
+        ));
+
+        let mut tables: Vec<_> = self.tables.iter().collect();
+        tables.sort_unstable_by(|a, b| b.1.bytes.cmp(&a.1.bytes));
+        for (name, table) in tables {
+            lines.push(format!(
+                "{name}: {:.1} ins/s {:.1} upd/s {:.1} del/s, {:.0} B/s",
+                table.inserts as f32 / secs,
+                table.updates as f32 / secs,
+                table.deletes as f32 / secs,
+                table.bytes as f32 / secs
+            ));
+        }
+
+        let mut offenders: Vec<_> = self.actors.iter().collect();
+        offenders.sort_unstable_by(|a, b| b.1.cmp(a.1));
+        lines.push("top actors:".into());
+        for (actor_id, bytes) in offenders.into_iter().take(TOP_OFFENDERS) {
+            lines.push(format!("  {actor_id}: {:.0} B/s", *bytes as f32 / secs));
+        }
+        lines.join("\n")
+    }
+}
+
+fn encoded_len(row: &impl Serialize) -> u64 {
+    bsatn::to_vec(row).map_or(0, |bytes| bytes.len() as u64)
+}
+
+fn sample_table<T: AuditedRow>(
+    mut inserts: ReadInsertMessage<T>,
+    mut updates: ReadUpdateMessage<T>,
+    mut deletes: ReadDeleteMessage<T>,
+    mut audit: ResMut<NetAudit>,
+) {
+    // Always drained, a sample only counts what arrives after it started.
+    let sampling = audit.timer.is_some();
+    for msg in inserts.read() {
+        if sampling {
+            audit.record(&msg.row, 1, |table| table.inserts += 1);
+        }
+    }
+    for msg in updates.read() {
+        if sampling {
+            audit.record(&msg.new, 2, |table| table.updates += 1);
+        }
+    }
+    for msg in deletes.read() {
+        if sampling {
+            audit.record(&msg.row, 1, |table| table.deletes += 1);
+        }
+    }
+}
+
+#[derive(Component)]
+struct NetAuditPanelText;
+
+fn spawn_net_audit_panel(mut commands: Commands) {
+    commands.spawn((
+        NetAuditPanelText,
+        Text::default(),
+        TextFont {
+            font_size: 13.0,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            right: Val::Px(12.0),
+            bottom: Val::Px(12.0),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+        Visibility::Hidden,
+        Pickable::IGNORE,
+    ));
+}
+
+fn finish_net_audit(
+    time: Res<Time>,
+    mut audit: ResMut<NetAudit>,
+    panel: Single<(&mut Text, &mut Visibility), With<NetAuditPanelText>>,
+) {
+    let (mut text, mut visibility) = panel.into_inner();
+    if audit.timer.is_none() {
+        if audit.is_changed() {
+            text.0 = audit.report.clone().unwrap_or_default();
+            visibility.set_if_neq(if audit.report.is_some() {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            });
+        }
+        return;
+    }
+    let Some(timer) = audit.timer.as_mut() else {
+        return;
+    };
+    if !timer.tick(time.delta()).is_finished() {
+        text.0 = format!("Net audit: {:.0}s left", timer.remaining_secs().ceil());
+        visibility.set_if_neq(Visibility::Inherited);
+        return;
+    }
+
+    let secs = timer.duration().as_secs_f32();
+    audit.timer = None;
+    let report = audit.report(secs);
+    info!("{report}");
+    // A system doesn't see its own changes, the panel is updated here rather than on the next
+    // `is_changed`.
+    text.0 = report.clone();
+    visibility.set_if_neq(Visibility::Inherited);
+    audit.report = Some(report);
+}
@@ -1,23 +1,40 @@
 use crate::{
+    LocalActor,
+    actor::{ActorCapsule, Flags},
     // actor::{LocalActor, MovementData},
-    cursor::{CurrentCursor, set_cursor_to_ability, set_cursor_to_combat, set_cursor_to_default},
-    input::InputAction,
-    module_bindings::{MoveIntentData, cancel_move, create_character, enter_game, request_move},
+    cursor::GroundTargeting,
+    input::{InputAction, InputThrottles, Press, ThrottledAction},
+    module_bindings::{MoveIntentData, request_move},
+    movement::{ClientIntentSeq, IntentBuffer},
     // owner::LocalOwner,
     server::SpacetimeDB,
+    world_bounds::WorldBounds,
 };
 use bevy::{picking::pointer::PointerInteraction, prelude::*};
 use leafwing_input_manager::prelude::ActionState;
+use shared::ActorFlags;
+
+/// Height (meters) above standing height a flying actor moves to when clicking on the ground, so
+/// it arrives in the air instead of pressing into the surface.
+const FLY_CLICK_HOVER: f32 = 0.5;
 
 pub(super) fn handle_lmb_movement(
     // mut local_actor_q: Single<&mut MovementData, With<LocalOwner>>,
     actions: Res<ActionState<InputAction>>,
     interactions: Query<&PointerInteraction>,
+    local_q: Query<(&Flags, &ActorCapsule), With<LocalActor>>,
+    targeting: Res<GroundTargeting>,
+    bounds: Res<WorldBounds>,
+    time: Res<Time<Real>>,
+    mut throttles: ResMut<InputThrottles>,
+    mut intent_seq: ResMut<ClientIntentSeq>,
+    mut intent_buffer: ResMut<IntentBuffer>,
     stdb: SpacetimeDB,
 ) {
-    let pressed = actions.pressed(&InputAction::LeftClick);
-    let just_released = actions.just_released(&InputAction::LeftClick);
-    if !pressed && !just_released {
+    let Some(press) = Press::of(&actions, &InputAction::LeftClick) else {
+        return;
+    };
+    if targeting.captures_click() {
         return;
     }
     let Ok(interaction) = interactions.single() else {
@@ -29,42 +46,28 @@ pub(super) fn handle_lmb_movement(
     let Some(pos) = hit.position else {
         return;
     };
+    // Clicks beyond the bounds move as far as possible, the server rejects them.
+    let pos = bounds.clamp(pos);
+    if !throttles.allow(ThrottledAction::Move, time.elapsed(), press, pos) {
+        return;
+    }
 
-    // TODO: just_released should request path move, for now everything is point
-    if pressed || just_released {
-        match stdb
-            .reducers()
-            .request_move(MoveIntentData::Point(crate::module_bindings::Vec2 {
+    // Flying actors (GM fly mode) move straight to the point above the clicked one.
+    let intent = match local_q.single() {
+        Ok((flags, capsule)) if flags.0.contains(ActorFlags::FLYING) => {
+            MoveIntentData::Point3(crate::module_bindings::Vec3 {
                 x: pos.x,
+                y: pos.y + capsule.half_height + capsule.radius + FLY_CLICK_HOVER,
                 z: pos.z,
-            })) {
-            Ok(_) => {
-                // local_actor_q.move_intent = MoveIntentData::Point(pos.into());
-            }
-            Err(e) => println!("Error: {e}"),
+            })
         }
-        return;
-    }
-}
+        _ => MoveIntentData::Point(crate::module_bindings::Vec2 { x: pos.x, z: pos.z }),
+    };
 
-pub(super) fn handle_enter_world(
-    current_cursor: ResMut<CurrentCursor>,
-    keys: Res<ButtonInput<KeyCode>>,
-    stdb: SpacetimeDB,
-) {
-    if keys.just_pressed(KeyCode::Space) {
-        let _ = stdb.reducers().create_character("Jeff".into());
-        match stdb.reducers().enter_game(1) {
-            Ok(_) => println!("Called enter world without immediate failure"),
-            Err(err) => println!("Immediate failure when calling enter world: {err}"),
-        }
-    } else if keys.just_pressed(KeyCode::Digit1) {
-        set_cursor_to_default(current_cursor);
-    } else if keys.just_pressed(KeyCode::Digit2) {
-        set_cursor_to_ability(current_cursor);
-    } else if keys.just_pressed(KeyCode::Digit3) {
-        set_cursor_to_combat(current_cursor);
-    } else if keys.just_pressed(KeyCode::Period) {
-        let _ = stdb.reducers().cancel_move();
+    // TODO: a release should request path move, for now everything is point
+    let seq = intent_seq.next();
+    match stdb.reducers().request_move(intent.clone(), seq) {
+        Ok(_) => intent_buffer.push(seq, intent),
+        Err(e) => println!("Error: {e}"),
     }
 }
@@ -1,10 +1,11 @@
 mod input;
 
+use crate::loading::LoadState;
 use bevy::prelude::*;
 
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(
         Update,
-        (input::handle_enter_world, input::handle_lmb_movement),
+        input::handle_lmb_movement.run_if(in_state(LoadState::Spawned)),
     );
 }
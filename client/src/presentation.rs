@@ -0,0 +1,79 @@
+//! Smoothing constants of the rendered actors and camera.
+//!
+//! [`PresentationConfig`] starts with the built-in defaults and follows the server's suggestion
+//! (`presentation_config_tbl`) whenever that row changes. Dev builds can tweak the values live
+//! from the debug panel, a tweak lasts until the next server suggestion.
+
+use crate::module_bindings::PresentationConfigRow;
+use bevy::prelude::*;
+use bevy_spacetimedb::{ReadDeleteMessage, ReadInsertMessage, ReadUpdateMessage};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<PresentationConfig>();
+    app.add_systems(
+        PreUpdate,
+        (
+            on_presentation_config_inserted,
+            on_presentation_config_updated,
+            on_presentation_config_deleted,
+        ),
+    );
+}
+
+/// Exponential decay rates (1/s), higher is snappier, lower is smoother.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct PresentationConfig {
+    /// Rendered translation of actors towards the replicated one.
+    pub translation_decay_rate: f32,
+    /// Rendered rotation of actors towards the replicated one.
+    pub rotation_decay_rate: f32,
+    /// Camera following the local actor.
+    pub camera_decay_rate: f32,
+}
+
+impl Default for PresentationConfig {
+    fn default() -> Self {
+        Self {
+            translation_decay_rate: 12.0,
+            rotation_decay_rate: 14.0,
+            camera_decay_rate: 44.0,
+        }
+    }
+}
+
+impl From<&PresentationConfigRow> for PresentationConfig {
+    fn from(row: &PresentationConfigRow) -> Self {
+        Self {
+            translation_decay_rate: row.translation_decay_rate,
+            rotation_decay_rate: row.rotation_decay_rate,
+            camera_decay_rate: row.camera_decay_rate,
+        }
+    }
+}
+
+fn on_presentation_config_inserted(
+    mut msgs: ReadInsertMessage<PresentationConfigRow>,
+    mut config: ResMut<PresentationConfig>,
+) {
+    for msg in msgs.read() {
+        *config = (&msg.row).into();
+    }
+}
+
+fn on_presentation_config_updated(
+    mut msgs: ReadUpdateMessage<PresentationConfigRow>,
+    mut config: ResMut<PresentationConfig>,
+) {
+    for msg in msgs.read() {
+        *config = (&msg.new).into();
+    }
+}
+
+fn on_presentation_config_deleted(
+    mut msgs: ReadDeleteMessage<PresentationConfigRow>,
+    mut config: ResMut<PresentationConfig>,
+) {
+    for _ in msgs.read() {
+        *config = PresentationConfig::default();
+    }
+}
@@ -0,0 +1,152 @@
+//! Projectiles in the AOI, see the server's `projectile_view`.
+//!
+//! The rows only change when a projectile crosses a cell, in between the position is predicted
+//! from `origin + velocity * (t - spawned_at)` against the [`ServerClock`], the same formula the
+//! server sweeps hits with. Updates carry the same trajectory, so nothing snaps. A projectile
+//! past its `expires_at` is hidden until the server deletes the row.
+
+use crate::{cooldown::ServerClock, module_bindings::ProjectileRow};
+use bevy::{platform::collections::HashMap, prelude::*};
+use bevy_spacetimedb::{ReadDeleteMessage, ReadInsertMessage, ReadUpdateMessage};
+
+/// Radius (meters) of the projectile visual.
+const PROJECTILE_VISUAL_RADIUS: f32 = 0.15;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<ProjectileEntityMapping>();
+    app.add_systems(Startup, setup_projectile_assets);
+    app.add_systems(
+        PreUpdate,
+        (
+            on_projectile_inserted,
+            on_projectile_updated,
+            on_projectile_deleted,
+        ),
+    );
+    app.add_systems(Update, predict_projectiles);
+}
+
+/// Maps projectile ids to their entities.
+#[derive(Resource, Default)]
+pub struct ProjectileEntityMapping(pub HashMap<u64, Entity>);
+
+/// A replicated projectile's trajectory, in server time.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Projectile {
+    pub origin: Vec3,
+    pub velocity: Vec3,
+    pub spawned_at_micros: i64,
+    pub expires_at_micros: i64,
+}
+
+impl Projectile {
+    /// Predicted position at the server time `now_micros`, clamped to the flight time.
+    pub fn position_at(&self, now_micros: i64) -> Vec3 {
+        let micros = now_micros.clamp(self.spawned_at_micros, self.expires_at_micros)
+            - self.spawned_at_micros;
+        self.origin + self.velocity * (micros as f32 / 1_000_000.0)
+    }
+}
+
+impl From<&ProjectileRow> for Projectile {
+    fn from(row: &ProjectileRow) -> Self {
+        Self {
+            origin: row.origin.clone().into(),
+            velocity: row.velocity.clone().into(),
+            spawned_at_micros: row.spawned_at.to_micros_since_unix_epoch(),
+            expires_at_micros: row.expires_at.to_micros_since_unix_epoch(),
+        }
+    }
+}
+
+#[derive(Resource)]
+struct ProjectileAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+fn setup_projectile_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(ProjectileAssets {
+        mesh: meshes.add(Sphere::new(PROJECTILE_VISUAL_RADIUS)),
+        material: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.6, 0.4, 1.0),
+            emissive: LinearRgba::rgb(2.0, 1.2, 4.0),
+            unlit: true,
+            ..default()
+        }),
+    });
+}
+
+fn on_projectile_inserted(
+    mut commands: Commands,
+    mut msgs: ReadInsertMessage<ProjectileRow>,
+    mut mapping: ResMut<ProjectileEntityMapping>,
+    mut clock: ResMut<ServerClock>,
+    assets: Res<ProjectileAssets>,
+) {
+    for msg in msgs.read() {
+        // The row can only arrive after it was fired, a clock sample like cooldowns.
+        clock.observe(msg.row.spawned_at);
+        let projectile = Projectile::from(&msg.row);
+        let entity = commands
+            .spawn((
+                projectile,
+                Transform::from_translation(projectile.position_at(clock.now_micros())),
+                Mesh3d(assets.mesh.clone()),
+                MeshMaterial3d(assets.material.clone()),
+                Pickable::IGNORE,
+            ))
+            .id();
+        if let Some(previous) = mapping.0.insert(msg.row.id, entity) {
+            commands.entity(previous).despawn();
+        }
+    }
+}
+
+fn on_projectile_updated(
+    mut msgs: ReadUpdateMessage<ProjectileRow>,
+    mapping: Res<ProjectileEntityMapping>,
+    mut projectile_q: Query<&mut Projectile>,
+) {
+    for msg in msgs.read() {
+        let Some(mut projectile) = mapping
+            .0
+            .get(&msg.new.id)
+            .and_then(|&entity| projectile_q.get_mut(entity).ok())
+        else {
+            continue;
+        };
+        *projectile = Projectile::from(&msg.new);
+    }
+}
+
+fn on_projectile_deleted(
+    mut commands: Commands,
+    mut msgs: ReadDeleteMessage<ProjectileRow>,
+    mut mapping: ResMut<ProjectileEntityMapping>,
+) {
+    for msg in msgs.read() {
+        if let Some(entity) = mapping.0.remove(&msg.row.id) {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn predict_projectiles(
+    clock: Res<ServerClock>,
+    mut projectile_q: Query<(&Projectile, &mut Transform, &mut Visibility)>,
+) {
+    let now_micros = clock.now_micros();
+    for (projectile, mut transform, mut visibility) in &mut projectile_q {
+        transform.translation = projectile.position_at(now_micros);
+        visibility.set_if_neq(if now_micros < projectile.expires_at_micros {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+    }
+}
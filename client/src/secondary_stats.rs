@@ -30,7 +30,12 @@ fn on_secondary_stats_inserted(
     }
 }
 
+/// Patches `SecondaryStats` live so extrapolation always uses the latest `movement_speed`.
+///
+/// If the component is missing (e.g. the update raced ahead of the insert for this entity),
+/// it is inserted instead of dropping the update.
 fn on_secondary_stats_updated(
+    mut commands: Commands,
     mut secondary_stats_q: Query<&mut SecondaryStats>,
     mut msgs: ReadUpdateMessage<SecondaryStatsRow>,
     oe_mapping: Res<ActorEntityMapping>,
@@ -40,6 +45,10 @@ fn on_secondary_stats_updated(
             continue;
         };
         let Ok(mut secondary_stats) = secondary_stats_q.get_mut(bevy_entity) else {
+            commands.entity(bevy_entity).insert(SecondaryStats {
+                movement_speed: msg.new.movement_speed,
+                critical_hit_chance: msg.new.critical_hit_chance,
+            });
             continue;
         };
         secondary_stats.movement_speed = msg.new.movement_speed;
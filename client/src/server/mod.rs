@@ -1,11 +1,19 @@
 pub mod reducers;
 pub mod types;
 
+use crate::loading::SubscriptionApplied;
 use crate::module_bindings::{
-    CharacterInstanceViewTableAccess, DbConnection, ExperienceViewTableAccess,
-    HealthViewTableAccess, LevelViewTableAccess, ManaViewTableAccess, MovementStateViewTableAccess,
-    PrimaryStatsViewTableAccess, RemoteTables, SecondaryStatsViewTableAccess,
-    TransformViewTableAccess, WorldStaticTblTableAccess,
+    ActivityViewTableAccess, ActorViewTableAccess, CharacterInstanceViewTableAccess,
+    CombatEventViewTableAccess, CombatLogViewTableAccess, CooldownViewTableAccess,
+    CorpseViewTableAccess, DbConnection, DuelViewTableAccess, EmoteViewTableAccess,
+    ExperienceViewTableAccess, GuildInviteViewTableAccess, GuildMemberViewTableAccess,
+    GuildTblTableAccess, HazardZoneTblTableAccess, HealthViewTableAccess,
+    KickNoticeViewTableAccess, LevelViewTableAccess, ManaViewTableAccess,
+    MonsterInstanceViewTableAccess, MovementStateViewTableAccess, PlayerSettingViewTableAccess,
+    PresentationConfigTblTableAccess, PrimaryStatsViewTableAccess, ProjectileViewTableAccess,
+    RemoteTables, SecondaryStatsViewTableAccess, SpectatorViewTableAccess, TargetViewTableAccess,
+    TransformViewTableAccess, TutorialProgressViewTableAccess, WhoResultViewTableAccess,
+    WorldStaticViewTableAccess, ZoneAmbientTblTableAccess,
 };
 use bevy::prelude::*;
 use bevy_spacetimedb::{ReadStdbConnectedMessage, StdbConnection, StdbPlugin};
@@ -37,10 +45,31 @@ pub(super) fn plugin(app: &mut App) {
             .add_reducer::<EnterGame>()
             .add_reducer::<CreateCharacter>()
             .add_reducer::<CancelMove>()
+            .add_reducer::<PlaceStatic>()
+            .add_reducer::<UpdateStatic>()
+            .add_reducer::<DeleteStatic>()
+            .add_reducer::<Resurrect>()
+            .add_reducer::<SetTarget>()
+            .add_reducer::<Attack>()
+            .add_reducer::<EnterInstance>()
+            .add_reducer::<RequestDuel>()
+            .add_reducer::<AcceptDuel>()
+            .add_reducer::<CreateGuild>()
+            .add_reducer::<InviteToGuild>()
+            .add_reducer::<AcceptGuildInvite>()
+            .add_reducer::<DeclineGuildInvite>()
+            .add_reducer::<KickFromGuild>()
+            .add_reducer::<LeaveGuild>()
+            .add_reducer::<SetGuildRank>()
+            .add_reducer::<Who>()
+            .add_reducer::<SetPlayerSetting>()
+            .add_reducer::<DeletePlayerSetting>()
+            .add_reducer::<SetFlyMode>()
+            .add_reducer::<Emote>()
             // --------------------------------
             // Register all tables
             // --------------------------------
-            .add_table(RemoteTables::world_static_tbl)
+            .add_view_with_pk(RemoteTables::world_static_view, |r| r.id)
             .add_table_without_pk(RemoteTables::primary_stats_view)
             .add_view_with_pk(RemoteTables::secondary_stats_view, |r| r.actor_id)
             .add_view_with_pk(RemoteTables::movement_state_view, |r| r.actor_id)
@@ -50,27 +79,80 @@ pub(super) fn plugin(app: &mut App) {
             .add_view_with_pk(RemoteTables::transform_view, |r| r.actor_id)
             .add_view_with_pk(RemoteTables::experience_view, |r| r.actor_id)
             .add_view_with_pk(RemoteTables::level_view, |r| r.actor_id)
+            .add_view_with_pk(RemoteTables::cooldown_view, |r| r.id)
+            .add_view_with_pk(RemoteTables::actor_view, |r| r.id)
+            .add_view_with_pk(RemoteTables::monster_instance_view, |r| r.actor_id)
+            .add_view_with_pk(RemoteTables::corpse_view, |r| r.actor_id)
+            .add_view_with_pk(RemoteTables::combat_event_view, |r| r.id)
+            .add_view_with_pk(RemoteTables::combat_log_view, |r| r.id)
+            .add_view_with_pk(RemoteTables::target_view, |r| r.actor_id)
+            .add_view_with_pk(RemoteTables::duel_view, |r| r.id)
+            .add_table(RemoteTables::guild_tbl)
+            .add_view_with_pk(RemoteTables::guild_member_view, |r| r.character_id)
+            .add_view_with_pk(RemoteTables::guild_invite_view, |r| r.id)
+            .add_view_with_pk(RemoteTables::who_result_view, |r| r.id)
+            .add_view_with_pk(RemoteTables::player_setting_view, |r| r.id)
+            .add_table(RemoteTables::presentation_config_tbl)
+            .add_view_with_pk(RemoteTables::emote_view, |r| r.actor_id)
+            .add_table(RemoteTables::zone_ambient_tbl)
+            .add_view_with_pk(RemoteTables::spectator_view, |r| r.identity)
+            .add_table(RemoteTables::hazard_zone_tbl)
+            .add_view_with_pk(RemoteTables::projectile_view, |r| r.id)
+            .add_view_with_pk(RemoteTables::activity_view, |r| r.identity)
+            .add_view_with_pk(RemoteTables::tutorial_progress_view, |r| r.character_id)
+            .add_view_with_pk(RemoteTables::kick_notice_view, |r| r.identity)
+            .add_table(RemoteTables::world_bounds_tbl)
             .with_run_fn(DbConnection::run_threaded),
     );
     app.add_systems(Update, on_connect);
 }
 
-fn on_connect(mut messages: ReadStdbConnectedMessage, stdb: SpacetimeDB) {
+fn on_connect(
+    mut messages: ReadStdbConnectedMessage,
+    subscription_applied: Res<SubscriptionApplied>,
+    stdb: SpacetimeDB,
+) {
     for message in messages.read() {
         println!("SpacetimeDB module connected: {:?}", message.identity);
 
-        stdb.subscription_builder().subscribe(vec![
-            "SELECT * FROM primary_stats_view",
-            "SELECT * FROM secondary_stats_view",
-            "SELECT * FROM health_view",
-            "SELECT * FROM mana_view",
-            "SELECT * FROM experience_view",
-            "SELECT * FROM level_view",
-            "SELECT * FROM world_static_tbl",
-            "SELECT * FROM movement_state_view",
-            "SELECT * FROM character_instance_view",
-            "SELECT * FROM transform_view",
-        ]);
+        let subscription_applied = subscription_applied.clone();
+        stdb.subscription_builder()
+            .on_applied(move |_| subscription_applied.set())
+            .subscribe(vec![
+                "SELECT * FROM primary_stats_view",
+                "SELECT * FROM secondary_stats_view",
+                "SELECT * FROM health_view",
+                "SELECT * FROM mana_view",
+                "SELECT * FROM experience_view",
+                "SELECT * FROM level_view",
+                "SELECT * FROM world_static_view",
+                "SELECT * FROM movement_state_view",
+                "SELECT * FROM character_instance_view",
+                "SELECT * FROM transform_view",
+                "SELECT * FROM cooldown_view",
+                "SELECT * FROM actor_view",
+                "SELECT * FROM monster_instance_view",
+                "SELECT * FROM corpse_view",
+                "SELECT * FROM combat_event_view",
+                "SELECT * FROM combat_log_view",
+                "SELECT * FROM target_view",
+                "SELECT * FROM duel_view",
+                "SELECT * FROM guild_tbl",
+                "SELECT * FROM guild_member_view",
+                "SELECT * FROM guild_invite_view",
+                "SELECT * FROM who_result_view",
+                "SELECT * FROM player_setting_view",
+                "SELECT * FROM presentation_config_tbl",
+                "SELECT * FROM emote_view",
+                "SELECT * FROM zone_ambient_tbl",
+                "SELECT * FROM spectator_view",
+                "SELECT * FROM hazard_zone_tbl",
+                "SELECT * FROM projectile_view",
+                "SELECT * FROM activity_view",
+                "SELECT * FROM tutorial_progress_view",
+                "SELECT * FROM kick_notice_view",
+                "SELECT * FROM world_bounds_tbl",
+            ]);
     }
 }
 
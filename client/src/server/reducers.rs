@@ -1,17 +1,35 @@
 #![allow(dead_code)]
 
 use crate::module_bindings::{
-    DbConnection, MoveIntentData, Reducer, RemoteModule, RemoteReducers,
+    ColliderShape, DbConnection, EmoteKind, GuildRank, MoveIntentData, Reducer, RemoteModule,
+    RemoteReducers, SurfaceMaterial, WhoFilter, WorldStaticPose, accept_duel_reducer::accept_duel,
+    accept_guild_invite_reducer::accept_guild_invite, attack_reducer::attack,
     cancel_move_reducer::cancel_move, create_character_reducer::create_character,
-    enter_game_reducer::enter_game, request_move_reducer::request_move,
+    create_guild_reducer::create_guild, decline_guild_invite_reducer::decline_guild_invite,
+    delete_player_setting_reducer::delete_player_setting, delete_static_reducer::delete_static,
+    emote_reducer::emote, enter_game_reducer::enter_game, enter_instance_reducer::enter_instance,
+    invite_to_guild_reducer::invite_to_guild, kick_from_guild_reducer::kick_from_guild,
+    leave_guild_reducer::leave_guild, place_static_reducer::place_static,
+    request_duel_reducer::request_duel, request_move_reducer::request_move,
+    resurrect_reducer::resurrect, set_fly_mode_reducer::set_fly_mode,
+    set_guild_rank_reducer::set_guild_rank, set_player_setting_reducer::set_player_setting,
+    set_target_reducer::set_target, update_static_reducer::update_static, who_reducer::who,
 };
 use bevy_spacetimedb::RegisterReducerMessage;
-use spacetimedb_sdk::ReducerEvent;
+use spacetimedb_sdk::{ReducerEvent, Timestamp};
+
+#[derive(Debug, RegisterReducerMessage)]
+pub struct Attack {
+    pub event: ReducerEvent<Reducer>,
+    pub target: Option<u32>,
+    pub seen_at: Option<Timestamp>,
+}
 
 #[derive(Debug, RegisterReducerMessage)]
 pub struct RequestMove {
     pub event: ReducerEvent<Reducer>,
     pub intent: MoveIntentData,
+    pub seq: u32,
 }
 
 #[derive(Debug, RegisterReducerMessage)]
@@ -29,9 +47,134 @@ pub struct CreateCharacter {
 #[derive(Debug, RegisterReducerMessage)]
 pub struct CancelMove {
     pub event: ReducerEvent<Reducer>,
+    pub seq: u32,
+}
+
+#[derive(Debug, RegisterReducerMessage)]
+pub struct PlaceStatic {
+    pub event: ReducerEvent<Reducer>,
+    pub pose: WorldStaticPose,
+    pub shape: ColliderShape,
+    pub surface_material: Option<SurfaceMaterial>,
+}
+
+#[derive(Debug, RegisterReducerMessage)]
+pub struct UpdateStatic {
+    pub event: ReducerEvent<Reducer>,
+    pub id: u64,
+    pub pose: WorldStaticPose,
+}
+
+#[derive(Debug, RegisterReducerMessage)]
+pub struct DeleteStatic {
+    pub event: ReducerEvent<Reducer>,
+    pub id: u64,
 }
 
 // #[derive(Debug, RegisterReducerMessage)]
 // pub struct LeaveWorld {
 //     pub event: ReducerEvent<Reducer>,
 // }
+
+#[derive(Debug, RegisterReducerMessage)]
+pub struct Resurrect {
+    pub event: ReducerEvent<Reducer>,
+    pub target: Option<u32>,
+}
+
+#[derive(Debug, RegisterReducerMessage)]
+pub struct SetTarget {
+    pub event: ReducerEvent<Reducer>,
+    pub target: Option<u32>,
+}
+
+#[derive(Debug, RegisterReducerMessage)]
+pub struct EnterInstance {
+    pub event: ReducerEvent<Reducer>,
+    pub instance_id: u32,
+}
+
+#[derive(Debug, RegisterReducerMessage)]
+pub struct RequestDuel {
+    pub event: ReducerEvent<Reducer>,
+    pub target: Option<u32>,
+}
+
+#[derive(Debug, RegisterReducerMessage)]
+pub struct AcceptDuel {
+    pub event: ReducerEvent<Reducer>,
+}
+
+#[derive(Debug, RegisterReducerMessage)]
+pub struct CreateGuild {
+    pub event: ReducerEvent<Reducer>,
+    pub name: String,
+    pub tag: String,
+}
+
+#[derive(Debug, RegisterReducerMessage)]
+pub struct InviteToGuild {
+    pub event: ReducerEvent<Reducer>,
+    pub target: Option<u32>,
+}
+
+#[derive(Debug, RegisterReducerMessage)]
+pub struct AcceptGuildInvite {
+    pub event: ReducerEvent<Reducer>,
+    pub guild_id: u32,
+}
+
+#[derive(Debug, RegisterReducerMessage)]
+pub struct DeclineGuildInvite {
+    pub event: ReducerEvent<Reducer>,
+    pub guild_id: u32,
+}
+
+#[derive(Debug, RegisterReducerMessage)]
+pub struct KickFromGuild {
+    pub event: ReducerEvent<Reducer>,
+    pub character_id: u32,
+}
+
+#[derive(Debug, RegisterReducerMessage)]
+pub struct LeaveGuild {
+    pub event: ReducerEvent<Reducer>,
+}
+
+#[derive(Debug, RegisterReducerMessage)]
+pub struct SetGuildRank {
+    pub event: ReducerEvent<Reducer>,
+    pub character_id: u32,
+    pub rank: GuildRank,
+}
+
+#[derive(Debug, RegisterReducerMessage)]
+pub struct Who {
+    pub event: ReducerEvent<Reducer>,
+    pub filter: WhoFilter,
+}
+
+#[derive(Debug, RegisterReducerMessage)]
+pub struct SetPlayerSetting {
+    pub event: ReducerEvent<Reducer>,
+    pub key: String,
+    pub value: String,
+}
+
+#[derive(Debug, RegisterReducerMessage)]
+pub struct DeletePlayerSetting {
+    pub event: ReducerEvent<Reducer>,
+    pub key: String,
+}
+
+#[derive(Debug, RegisterReducerMessage)]
+pub struct SetFlyMode {
+    pub event: ReducerEvent<Reducer>,
+    pub enabled: bool,
+}
+
+#[derive(Debug, RegisterReducerMessage)]
+pub struct Emote {
+    pub event: ReducerEvent<Reducer>,
+    pub kind: EmoteKind,
+}
@@ -0,0 +1,64 @@
+//! Per-account UI preferences, mirrored from the server's `player_setting_view`.
+//!
+//! Change them with the `set_player_setting`/`delete_player_setting` reducers, the mirror
+//! updates once the server confirms.
+
+use crate::module_bindings::PlayerSettingRow;
+use bevy::{platform::collections::HashMap, prelude::*};
+use bevy_spacetimedb::{ReadDeleteMessage, ReadInsertMessage, ReadUpdateMessage};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<PlayerSettings>();
+    app.add_systems(
+        PreUpdate,
+        (on_setting_inserted, on_setting_updated, on_setting_deleted),
+    );
+}
+
+/// The player's settings by key.
+#[derive(Resource, Debug, Default)]
+pub struct PlayerSettings(HashMap<String, String>);
+
+impl PlayerSettings {
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).map(String::as_str)
+    }
+
+    /// The setting parsed as a bool, `default` if it's unset or not a bool.
+    pub fn get_bool(&self, key: &str, default: bool) -> bool {
+        self.get(key)
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(default)
+    }
+}
+
+fn on_setting_inserted(
+    mut msgs: ReadInsertMessage<PlayerSettingRow>,
+    mut settings: ResMut<PlayerSettings>,
+) {
+    for msg in msgs.read() {
+        settings
+            .0
+            .insert(msg.row.key.clone(), msg.row.value.clone());
+    }
+}
+
+fn on_setting_updated(
+    mut msgs: ReadUpdateMessage<PlayerSettingRow>,
+    mut settings: ResMut<PlayerSettings>,
+) {
+    for msg in msgs.read() {
+        settings
+            .0
+            .insert(msg.new.key.clone(), msg.new.value.clone());
+    }
+}
+
+fn on_setting_deleted(
+    mut msgs: ReadDeleteMessage<PlayerSettingRow>,
+    mut settings: ResMut<PlayerSettings>,
+) {
+    for msg in msgs.read() {
+        settings.0.remove(&msg.row.key);
+    }
+}
@@ -0,0 +1,135 @@
+//! Spectator mode, a free-fly camera over a view-only presence (see `enter_spectator`).
+//!
+//! - The own `spectator_view` row switches the camera from following the local actor to the
+//!   [`Spectator`] observer point, flown locally with WASD, Q/E for down/up and Shift to speed
+//!   up. Clicks keep moving the (no longer followed) character.
+//! - The server only needs the point's cell to center the AOI, it's sent whenever the point
+//!   crosses into another cell.
+
+use crate::{
+    accessibility::Accessibility,
+    camera::CAMERA_OFFSET_GLOBAL,
+    command::command_line_closed,
+    module_bindings::{SpectatorRow, move_spectator},
+    presentation::PresentationConfig,
+    server::SpacetimeDB,
+};
+use bevy::prelude::*;
+use bevy_spacetimedb::{ReadDeleteMessage, ReadInsertMessage};
+use shared::{CellId, encode_cell_id};
+
+/// Observer point speed (meters/second), and with Shift held.
+const FLY_SPEED: f32 = 15.0;
+const FAST_FLY_SPEED: f32 = 60.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Spectator>();
+    app.add_systems(PreUpdate, (on_spectator_inserted, on_spectator_deleted));
+    app.add_systems(
+        Update,
+        (fly_observer.run_if(command_line_closed), sync_observer_cell)
+            .chain()
+            .run_if(is_spectating),
+    );
+    app.add_systems(PostUpdate, follow_observer.run_if(is_spectating));
+}
+
+/// The local observer point while spectating.
+#[derive(Resource, Debug, Default)]
+pub struct Spectator(pub Option<Observer>);
+
+#[derive(Debug, Clone, Copy)]
+pub struct Observer {
+    pub translation: Vec3,
+    /// The cell last sent to the server.
+    sent_cell_id: CellId,
+}
+
+/// Run condition for the spectator systems, and to stop following the local actor.
+pub fn is_spectating(spectator: Res<Spectator>) -> bool {
+    spectator.0.is_some()
+}
+
+fn on_spectator_inserted(
+    mut msgs: ReadInsertMessage<SpectatorRow>,
+    mut spectator: ResMut<Spectator>,
+) {
+    for msg in msgs.read() {
+        // Re-entering moves the point, later moves are driven locally.
+        spectator.0 = Some(Observer {
+            translation: msg.row.translation.clone().into(),
+            sent_cell_id: msg.row.cell_id,
+        });
+    }
+}
+
+fn on_spectator_deleted(
+    mut msgs: ReadDeleteMessage<SpectatorRow>,
+    mut spectator: ResMut<Spectator>,
+) {
+    for _ in msgs.read() {
+        spectator.0 = None;
+    }
+}
+
+fn fly_observer(
+    time: Res<Time>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut spectator: ResMut<Spectator>,
+) {
+    let Some(observer) = spectator.0.as_mut() else {
+        return;
+    };
+    let axis = |negative: KeyCode, positive: KeyCode| {
+        keys.pressed(positive) as i8 as f32 - keys.pressed(negative) as i8 as f32
+    };
+    // The camera looks down +Z from behind, see `CAMERA_OFFSET_GLOBAL`.
+    let direction = Vec3::new(
+        axis(KeyCode::KeyD, KeyCode::KeyA),
+        axis(KeyCode::KeyQ, KeyCode::KeyE),
+        axis(KeyCode::KeyS, KeyCode::KeyW),
+    )
+    .normalize_or_zero();
+    if direction == Vec3::ZERO {
+        return;
+    }
+    let speed = if keys.pressed(KeyCode::ShiftLeft) {
+        FAST_FLY_SPEED
+    } else {
+        FLY_SPEED
+    };
+    observer.translation += direction * speed * time.delta_secs();
+}
+
+fn sync_observer_cell(mut spectator: ResMut<Spectator>, stdb: SpacetimeDB) {
+    let Some(observer) = spectator.0.as_mut() else {
+        return;
+    };
+    let cell_id = encode_cell_id(observer.translation.x, observer.translation.z);
+    if cell_id == observer.sent_cell_id {
+        return;
+    }
+    match stdb.reducers().move_spectator(observer.translation.into()) {
+        Ok(_) => observer.sent_cell_id = cell_id,
+        Err(e) => println!("Error: {e}"),
+    }
+}
+
+fn follow_observer(
+    mut camera_q: Query<&mut Transform, With<Camera3d>>,
+    spectator: Res<Spectator>,
+    time: Res<Time>,
+    config: Res<PresentationConfig>,
+    accessibility: Res<Accessibility>,
+) {
+    let (Ok(mut cam_tf), Some(observer)) = (camera_q.single_mut(), spectator.0) else {
+        return;
+    };
+    let target = observer.translation + CAMERA_OFFSET_GLOBAL;
+    accessibility.follow(
+        &mut cam_tf.translation,
+        target,
+        config.camera_decay_rate,
+        time.delta_secs(),
+    );
+}
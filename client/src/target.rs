@@ -0,0 +1,57 @@
+//! Replicated targets of actors in the AOI, see the server's `target_view`.
+//!
+//! Targets are stored on the targeting actor's entity, target-of-target is found by following
+//! [`Target`] twice through the [`ActorEntityMapping`].
+
+use crate::{ActorEntityMapping, ensure_actor_entity, module_bindings::TargetRow};
+use bevy::prelude::*;
+use bevy_spacetimedb::{ReadDeleteMessage, ReadInsertMessage, ReadUpdateMessage};
+use shared::ActorId;
+
+/// The actor this actor currently has targeted.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Target(pub ActorId);
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        PreUpdate,
+        (on_target_inserted, on_target_updated, on_target_deleted),
+    );
+}
+
+fn on_target_inserted(
+    mut commands: Commands,
+    mut msgs: ReadInsertMessage<TargetRow>,
+    mut oe_mapping: ResMut<ActorEntityMapping>,
+) {
+    for msg in msgs.read() {
+        let bevy_entity = ensure_actor_entity(&mut commands, &mut oe_mapping, msg.row.actor_id);
+        commands.entity(bevy_entity).insert(Target(msg.row.target));
+    }
+}
+
+fn on_target_updated(
+    mut commands: Commands,
+    mut msgs: ReadUpdateMessage<TargetRow>,
+    oe_mapping: Res<ActorEntityMapping>,
+) {
+    for msg in msgs.read() {
+        let Some(&bevy_entity) = oe_mapping.0.get(&msg.new.actor_id) else {
+            continue;
+        };
+        commands.entity(bevy_entity).insert(Target(msg.new.target));
+    }
+}
+
+fn on_target_deleted(
+    mut commands: Commands,
+    mut msgs: ReadDeleteMessage<TargetRow>,
+    oe_mapping: Res<ActorEntityMapping>,
+) {
+    for msg in msgs.read() {
+        let Some(&bevy_entity) = oe_mapping.0.get(&msg.row.actor_id) else {
+            continue;
+        };
+        commands.entity(bevy_entity).remove::<Target>();
+    }
+}
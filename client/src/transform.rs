@@ -1,15 +1,19 @@
 use crate::{
     actor::{ActorEntityMapping, ensure_actor_entity},
     module_bindings::TransformRow,
+    presentation::PresentationConfig,
 };
 use bevy::prelude::*;
 use bevy_spacetimedb::{ReadInsertMessage, ReadUpdateMessage};
+use shared::is_tick_newer;
 
 /// Cached server transform data for an entity.
 #[derive(Component, Debug)]
 pub struct NetTransform {
     pub translation: Vec3,
     pub rotation: Quat,
+    /// Server tick of the last applied update, older updates are discarded.
+    pub server_tick: u32,
 }
 
 pub(super) fn plugin(app: &mut App) {
@@ -42,6 +46,7 @@ fn on_transform_inserted(
             NetTransform {
                 translation,
                 rotation,
+                server_tick: msg.row.server_tick,
             },
         ));
     }
@@ -59,20 +64,28 @@ fn on_transform_updated(
         let Ok(mut net_transform) = transform_q.get_mut(bevy_entity) else {
             continue;
         };
+        // Discard updates stamped with an older tick than the one already applied.
+        if is_tick_newer(net_transform.server_tick, msg.new.server_tick) {
+            continue;
+        }
         // println!("on_transform_updated: {:?}", transform.actor_id);
         net_transform.translation = msg.new.translation.clone().into();
         net_transform.rotation = Quat::from_rotation_y(msg.new.yaw);
+        net_transform.server_tick = msg.new.server_tick;
     }
 }
 
-fn interpolate(time: Res<Time>, mut transform_q: Query<(&mut Transform, &NetTransform)>) {
+fn interpolate(
+    time: Res<Time>,
+    config: Res<PresentationConfig>,
+    mut transform_q: Query<(&mut Transform, &NetTransform)>,
+) {
     let dt = time.delta_secs();
+    let rotation_t = 1.0 - (-config.rotation_decay_rate * dt).exp();
     transform_q.par_iter_mut().for_each(|(mut transform, net)| {
         transform
             .translation
-            .smooth_nudge(&net.translation, 12.0, dt);
-        transform.rotation = transform
-            .rotation
-            .slerp(net.rotation, 1.0 - (-14.0 * dt).exp());
+            .smooth_nudge(&net.translation, config.translation_decay_rate, dt);
+        transform.rotation = transform.rotation.slerp(net.rotation, rotation_t);
     });
 }
@@ -0,0 +1,115 @@
+//! Onboarding hints, one at a time for the first step the server hasn't seen the local
+//! character do yet (see `tutorial_progress_view`). Hints only show in the world, a step done
+//! hides its hint for good.
+
+use crate::{loading::LoadState, module_bindings::TutorialProgressRow};
+use bevy::prelude::*;
+use bevy_spacetimedb::{ReadDeleteMessage, ReadInsertMessage, ReadUpdateMessage};
+use shared::TutorialFlags;
+
+/// The hint of every step, in the order they're shown.
+const HINTS: &[(TutorialFlags, &str)] = &[
+    (
+        TutorialFlags::FIRST_MOVE,
+        "Click on the ground to move there.",
+    ),
+    (
+        TutorialFlags::FIRST_KILL,
+        "Use the action bar (1-6) to fight, defeat a monster.",
+    ),
+    (
+        TutorialFlags::FIRST_LEVEL,
+        "Defeat monsters to earn experience and reach the next level.",
+    ),
+];
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<TutorialProgress>();
+    app.add_systems(Startup, spawn_tutorial_hint);
+    app.add_systems(
+        PreUpdate,
+        (
+            on_tutorial_progress_inserted,
+            on_tutorial_progress_updated,
+            on_tutorial_progress_deleted,
+        ),
+    );
+    app.add_systems(Update, update_tutorial_hint);
+}
+
+/// The steps the local character has done, no row means none.
+#[derive(Resource, Debug, Default)]
+struct TutorialProgress(TutorialFlags);
+
+#[derive(Component)]
+struct TutorialHintText;
+
+fn spawn_tutorial_hint(mut commands: Commands) {
+    commands.spawn((
+        TutorialHintText,
+        Text::default(),
+        TextFont {
+            font_size: 18.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.85, 0.9, 1.0)),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(48.0),
+            width: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        TextLayout::new_with_justify(Justify::Center),
+        Visibility::Hidden,
+        Pickable::IGNORE,
+    ));
+}
+
+fn on_tutorial_progress_inserted(
+    mut msgs: ReadInsertMessage<TutorialProgressRow>,
+    mut progress: ResMut<TutorialProgress>,
+) {
+    for msg in msgs.read() {
+        progress.0 = TutorialFlags::from_bits(msg.row.progress);
+    }
+}
+
+fn on_tutorial_progress_updated(
+    mut msgs: ReadUpdateMessage<TutorialProgressRow>,
+    mut progress: ResMut<TutorialProgress>,
+) {
+    for msg in msgs.read() {
+        progress.0 = TutorialFlags::from_bits(msg.new.progress);
+    }
+}
+
+fn on_tutorial_progress_deleted(
+    mut msgs: ReadDeleteMessage<TutorialProgressRow>,
+    mut progress: ResMut<TutorialProgress>,
+) {
+    for _ in msgs.read() {
+        progress.0 = TutorialFlags::NONE;
+    }
+}
+
+fn update_tutorial_hint(
+    progress: Res<TutorialProgress>,
+    state: Res<State<LoadState>>,
+    hint: Single<(&mut Text, &mut Visibility), With<TutorialHintText>>,
+) {
+    let (mut text, mut visibility) = hint.into_inner();
+    let next_hint = HINTS
+        .iter()
+        .find(|(step, _)| !progress.0.contains(*step))
+        .map(|(_, hint)| *hint)
+        .filter(|_| *state.get() == LoadState::Spawned);
+    let Some(next_hint) = next_hint else {
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    };
+    if text.0 != next_hint {
+        text.0 = next_hint.to_string();
+    }
+    visibility.set_if_neq(Visibility::Inherited);
+}
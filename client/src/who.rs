@@ -0,0 +1,83 @@
+//! A panel listing the results of the last `/who` query, see [`crate::command`].
+
+use crate::module_bindings::WhoResultRow;
+use bevy::{platform::collections::HashMap, prelude::*};
+use bevy_spacetimedb::{ReadDeleteMessage, ReadInsertMessage};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<WhoResults>();
+    app.add_systems(Startup, spawn_who_panel);
+    app.add_systems(PreUpdate, (on_who_result_inserted, on_who_result_deleted));
+    app.add_systems(Update, update_who_panel);
+}
+
+/// The rows of `who_result_view` by id.
+#[derive(Resource, Debug, Default)]
+struct WhoResults(HashMap<u64, WhoResultRow>);
+
+#[derive(Component)]
+struct WhoPanelText;
+
+fn spawn_who_panel(mut commands: Commands) {
+    commands.spawn((
+        WhoPanelText,
+        Text::default(),
+        TextFont {
+            font_size: 14.0,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            right: Val::Px(12.0),
+            top: Val::Px(12.0),
+            ..default()
+        },
+        Visibility::Hidden,
+    ));
+}
+
+fn on_who_result_inserted(
+    mut msgs: ReadInsertMessage<WhoResultRow>,
+    mut results: ResMut<WhoResults>,
+) {
+    for msg in msgs.read() {
+        results.0.insert(msg.row.id, msg.row.clone());
+    }
+}
+
+fn on_who_result_deleted(
+    mut msgs: ReadDeleteMessage<WhoResultRow>,
+    mut results: ResMut<WhoResults>,
+) {
+    for msg in msgs.read() {
+        results.0.remove(&msg.row.id);
+    }
+}
+
+fn update_who_panel(
+    results: Res<WhoResults>,
+    text: Single<(&mut Text, &mut Visibility), With<WhoPanelText>>,
+) {
+    if !results.is_changed() {
+        return;
+    }
+    let (mut text, mut visibility) = text.into_inner();
+    let mut rows: Vec<&WhoResultRow> = results.0.values().collect();
+    rows.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+
+    let mut lines = vec![format!("{} players", rows.len())];
+    lines.extend(rows.into_iter().map(|row| {
+        let guild = row
+            .guild_tag
+            .as_ref()
+            .map(|tag| format!(" <{tag}>"))
+            .unwrap_or_default();
+        format!("{}{} - level {} - {}", row.name, guild, row.level, row.zone)
+    }));
+    text.0 = lines.join("\n");
+    *visibility = if results.0.is_empty() {
+        Visibility::Hidden
+    } else {
+        Visibility::Inherited
+    };
+}
@@ -1,16 +1,106 @@
-use bevy::prelude::*;
-use bevy_spacetimedb::ReadInsertMessage;
+use bevy::{platform::collections::HashMap, prelude::*};
+use bevy_spacetimedb::{ReadDeleteMessage, ReadInsertMessage, ReadUpdateMessage};
+use nalgebra::{Quaternion, UnitQuaternion};
+use shared::{StaticQueryWorld, WorldStaticDef, build_static_query_world};
 
 use crate::module_bindings::{ColliderShape, WorldStatic};
 
 pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<WorldStaticEntityMapping>();
+    app.init_resource::<ClientStaticQueryWorld>();
     app.add_systems(Startup, setup);
-    app.add_systems(Update, load_world);
+    app.add_systems(
+        Update,
+        (
+            (load_world, on_world_static_updated, on_world_static_deleted),
+            rebuild_static_query_world,
+        )
+            .chain(),
+    );
 }
 
 #[derive(Component)]
 pub struct Ground;
 
+/// The server id of the world static an entity was spawned for.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct WorldStaticId(pub u64);
+
+/// Maps world static ids to their entities so admin edits can be applied live.
+#[derive(Resource, Default)]
+pub struct WorldStaticEntityMapping(pub HashMap<u64, Entity>);
+
+/// Client copy of the server's static query world, rebuilt whenever a world static changes.
+#[derive(Resource)]
+pub struct ClientStaticQueryWorld {
+    defs: HashMap<u64, WorldStaticDef>,
+    dirty: bool,
+    pub world: StaticQueryWorld,
+}
+
+impl Default for ClientStaticQueryWorld {
+    fn default() -> Self {
+        Self {
+            defs: HashMap::default(),
+            dirty: false,
+            world: build_static_query_world([], 0.0),
+        }
+    }
+}
+
+impl ClientStaticQueryWorld {
+    pub fn def(&self, id: u64) -> Option<&WorldStaticDef> {
+        self.defs.get(&id)
+    }
+
+    /// Is `world` up to date with every world static received so far?
+    pub fn is_built(&self) -> bool {
+        !self.dirty
+    }
+
+    fn upsert(&mut self, row: &WorldStatic) {
+        self.defs.insert(row.id, to_world_static_def(row));
+        self.dirty = true;
+    }
+
+    fn remove(&mut self, id: u64) {
+        self.defs.remove(&id);
+        self.dirty = true;
+    }
+}
+
+/// Mirror of the server's `row_to_def`, the mapping and scale rules live in `shared`.
+pub fn to_world_static_def(row: &WorldStatic) -> WorldStaticDef {
+    let q = &row.rotation;
+    WorldStaticDef::new(
+        row.id,
+        (&row.translation).into(),
+        UnitQuaternion::from_quaternion(Quaternion::new(q.w, q.x, q.y, q.z)),
+        (&row.scale).into(),
+        shared::collider_shape_def!(ColliderShape, &row.shape),
+    )
+}
+
+/// Mesh used to visualize a collider shape, `None` for shapes without visuals yet.
+pub fn shape_mesh(shape: &ColliderShape) -> Option<Mesh> {
+    match shape {
+        ColliderShape::Cuboid(val) => Some(Cuboid::new(val.x * 2., val.y * 2., val.z * 2.).into()),
+        ColliderShape::Sphere(radius) => Some(Sphere::new(*radius).into()),
+        ColliderShape::CapsuleY(c) => Some(Capsule3d::new(c.radius, c.half_height * 2.).into()),
+        ColliderShape::Cylinder(c) => {
+            Some(bevy::math::primitives::Cylinder::new(c.radius, c.half_height * 2.).into())
+        }
+        ColliderShape::Cone(c) => Some(
+            bevy::math::primitives::Cone {
+                radius: c.radius,
+                height: c.half_height * 2.,
+            }
+            .into(),
+        ),
+        _ => None,
+    }
+}
+
 fn setup(mut commands: Commands) {
     println!("World setup");
 
@@ -29,16 +119,19 @@ fn setup(mut commands: Commands) {
 fn load_world(
     mut commands: Commands,
     mut msgs: ReadInsertMessage<WorldStatic>,
+    mut mapping: ResMut<WorldStaticEntityMapping>,
+    mut query_world: ResMut<ClientStaticQueryWorld>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
     for msg in msgs.read() {
         println!("WorldStatic: {:?}", msg.row.id);
         let world_static = msg.row.clone();
+        query_world.upsert(&world_static);
 
-        match world_static.shape {
-            ColliderShape::Plane(_) => {
-                commands.spawn((
+        let entity = match world_static.shape {
+            ColliderShape::Plane(_) => commands
+                .spawn((
                     Ground,
                     Pickable::default(),
                     Transform {
@@ -60,27 +153,82 @@ fn load_world(
                         metallic: 0.0,
                         ..default()
                     })),
-                ));
+                ))
+                .id(),
+            ref shape => {
+                // Statics can be placed live by admins, don't crash on shapes without visuals yet.
+                let Some(mesh) = shape_mesh(shape) else {
+                    println!(
+                        "WorldStatic {}: no visuals for {:?}",
+                        world_static.id, shape
+                    );
+                    continue;
+                };
+                commands
+                    .spawn((
+                        // Ground,
+                        Pickable::default(),
+                        Transform {
+                            rotation: world_static.rotation.into(),
+                            translation: world_static.translation.into(),
+                            scale: world_static.scale.into(),
+                        },
+                        Mesh3d(meshes.add(mesh)),
+                        MeshMaterial3d(materials.add(StandardMaterial {
+                            base_color: Color::linear_rgb(0.8, 0.1, 0.15),
+                            perceptual_roughness: 1.0,
+                            metallic: 0.0,
+                            ..default()
+                        })),
+                    ))
+                    .id()
             }
-            ColliderShape::Cuboid(val) => {
-                commands.spawn((
-                    // Ground,
-                    Pickable::default(),
-                    Transform {
-                        rotation: world_static.rotation.into(),
-                        translation: world_static.translation.into(),
-                        scale: world_static.scale.into(),
-                    },
-                    Mesh3d(meshes.add(Cuboid::new(val.x * 2., val.y * 2., val.z * 2.))),
-                    MeshMaterial3d(materials.add(StandardMaterial {
-                        base_color: Color::linear_rgb(0.8, 0.1, 0.15),
-                        perceptual_roughness: 1.0,
-                        metallic: 0.0,
-                        ..default()
-                    })),
-                ));
-            }
-            _ => unimplemented!("This shouldn't be reached"),
+        };
+        commands
+            .entity(entity)
+            .insert(WorldStaticId(world_static.id));
+        mapping.0.insert(world_static.id, entity);
+    }
+}
+
+fn on_world_static_updated(
+    mut msgs: ReadUpdateMessage<WorldStatic>,
+    mapping: Res<WorldStaticEntityMapping>,
+    mut query_world: ResMut<ClientStaticQueryWorld>,
+    mut transform_q: Query<&mut Transform>,
+) {
+    for msg in msgs.read() {
+        query_world.upsert(&msg.new);
+        let Some(&entity) = mapping.0.get(&msg.new.id) else {
+            continue;
+        };
+        let Ok(mut transform) = transform_q.get_mut(entity) else {
+            continue;
+        };
+        transform.translation = msg.new.translation.into();
+        transform.rotation = msg.new.rotation.into();
+        transform.scale = msg.new.scale.into();
+    }
+}
+
+fn on_world_static_deleted(
+    mut commands: Commands,
+    mut msgs: ReadDeleteMessage<WorldStatic>,
+    mut mapping: ResMut<WorldStaticEntityMapping>,
+    mut query_world: ResMut<ClientStaticQueryWorld>,
+) {
+    for msg in msgs.read() {
+        query_world.remove(msg.row.id);
+        if let Some(entity) = mapping.0.remove(&msg.row.id) {
+            commands.entity(entity).despawn();
         }
     }
 }
+
+fn rebuild_static_query_world(mut query_world: ResMut<ClientStaticQueryWorld>) {
+    if !query_world.dirty {
+        return;
+    }
+    query_world.world = build_static_query_world(query_world.defs.values().cloned(), 0.0);
+    query_world.dirty = false;
+}
@@ -0,0 +1,131 @@
+//! The edge of the playable area (`world_bounds_tbl`), drawn as a fog wall along the inner side
+//! of the border walls. Move clicks beyond it are clamped onto it, the server rejects targets
+//! beyond the bounds.
+
+use crate::module_bindings::WorldBoundsRow;
+use bevy::prelude::*;
+use bevy_spacetimedb::{ReadDeleteMessage, ReadInsertMessage, ReadUpdateMessage};
+use std::f32::consts::FRAC_PI_2;
+
+/// Height (meters) of the drawn fog wall, the border walls themselves are much taller.
+const FOG_WALL_HEIGHT: f32 = 12.0;
+
+/// How far (meters) inside the bounds the fog wall is drawn, so it isn't hidden in the border
+/// walls.
+const FOG_WALL_INSET: f32 = 0.05;
+
+const FOG_WALL_COLOR: Color = Color::srgba(0.75, 0.8, 0.9, 0.35);
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<WorldBounds>();
+    app.add_systems(
+        PreUpdate,
+        (
+            on_world_bounds_inserted,
+            on_world_bounds_updated,
+            on_world_bounds_deleted,
+        ),
+    );
+    app.add_systems(
+        Update,
+        spawn_fog_walls.run_if(resource_changed::<WorldBounds>),
+    );
+}
+
+/// The playable area, `None` until replicated.
+#[derive(Resource, Debug, Default)]
+pub struct WorldBounds(Option<Rect>);
+
+impl WorldBounds {
+    /// `point` with its XZ clamped onto the bounds, unchanged while they're unknown.
+    pub fn clamp(&self, point: Vec3) -> Vec3 {
+        let Some(rect) = self.0 else {
+            return point;
+        };
+        Vec3::new(
+            point.x.clamp(rect.min.x, rect.max.x),
+            point.y,
+            point.z.clamp(rect.min.y, rect.max.y),
+        )
+    }
+}
+
+impl From<&WorldBoundsRow> for WorldBounds {
+    fn from(row: &WorldBoundsRow) -> Self {
+        Self(Some(Rect::new(row.min.x, row.min.z, row.max.x, row.max.z)))
+    }
+}
+
+#[derive(Component)]
+struct FogWall;
+
+fn on_world_bounds_inserted(
+    mut msgs: ReadInsertMessage<WorldBoundsRow>,
+    mut bounds: ResMut<WorldBounds>,
+) {
+    for msg in msgs.read() {
+        *bounds = WorldBounds::from(&msg.row);
+    }
+}
+
+fn on_world_bounds_updated(
+    mut msgs: ReadUpdateMessage<WorldBoundsRow>,
+    mut bounds: ResMut<WorldBounds>,
+) {
+    for msg in msgs.read() {
+        *bounds = WorldBounds::from(&msg.new);
+    }
+}
+
+fn on_world_bounds_deleted(
+    mut msgs: ReadDeleteMessage<WorldBoundsRow>,
+    mut bounds: ResMut<WorldBounds>,
+) {
+    for _ in msgs.read() {
+        bounds.0 = None;
+    }
+}
+
+/// Replaces the fog walls with ones along the current bounds.
+fn spawn_fog_walls(
+    mut commands: Commands,
+    bounds: Res<WorldBounds>,
+    wall_q: Query<Entity, With<FogWall>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for entity in &wall_q {
+        commands.entity(entity).despawn();
+    }
+    let Some(rect) = bounds.0 else {
+        return;
+    };
+
+    let rect = rect.inflate(-FOG_WALL_INSET);
+    let center = rect.center();
+    let material = materials.add(StandardMaterial {
+        base_color: FOG_WALL_COLOR,
+        alpha_mode: AlphaMode::Blend,
+        unlit: true,
+        cull_mode: None,
+        ..default()
+    });
+    // Every wall is a vertical, double sided quad: its position on the edge, its rotation about
+    // the Y axis (a quad spans X unrotated) and its length.
+    let walls = [
+        (Vec2::new(rect.max.x, center.y), FRAC_PI_2, rect.height()),
+        (Vec2::new(rect.min.x, center.y), FRAC_PI_2, rect.height()),
+        (Vec2::new(center.x, rect.max.y), 0.0, rect.width()),
+        (Vec2::new(center.x, rect.min.y), 0.0, rect.width()),
+    ];
+    for (position, yaw, length) in walls {
+        commands.spawn((
+            FogWall,
+            Mesh3d(meshes.add(Rectangle::new(length, FOG_WALL_HEIGHT))),
+            MeshMaterial3d(material.clone()),
+            Transform::from_xyz(position.x, FOG_WALL_HEIGHT * 0.5, position.y)
+                .with_rotation(Quat::from_rotation_y(yaw)),
+            Pickable::IGNORE,
+        ));
+    }
+}
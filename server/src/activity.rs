@@ -0,0 +1,157 @@
+//! AFK detection and the idle policy.
+//!
+//! Gameplay reducers (movement, combat, targeting, emotes) call [`ActivityRow::touch`] for the
+//! sender. [`activity_tick_reducer`] flags characters idle for longer than the configured
+//! timeout with [`ActorFlags::AFK`] and, when the policy has a grace period, removes them from
+//! the world once it ran out, freeing their AOI capacity. The player stays connected and can
+//! enter the game again.
+//!
+//! See [`GameConfigRow::afk_timeout_secs`] and [`GameConfigRow::afk_despawn_grace_secs`].
+
+use crate::{
+    character_instance_tbl, ActorRow, CharacterRow, EventKind, EventLogRow, GameConfigRow,
+    TimingStatsRow, WriteStats,
+};
+use shared::ActorFlags;
+use spacetimedb::{
+    reducer, table, Identity, ReducerContext, ScheduleAt, Table, TimeDuration, Timestamp,
+    ViewContext,
+};
+use std::time::Duration;
+
+pub(crate) const ACTIVITY_TICK_INTERVAL_MILLIS: u64 = 5_000;
+
+/// Activity closer than this to the last recorded one isn't written, movement intents arrive
+/// several times a second.
+const TOUCH_RESOLUTION_MICROS: i64 = 5_000_000;
+
+/// **Ephemeral**: When a player with a character in the world last did something.
+#[table(name=activity_tbl)]
+pub struct ActivityRow {
+    #[primary_key]
+    pub identity: Identity,
+
+    pub last_active_at: Timestamp,
+
+    /// When the character was flagged AFK, `None` while active.
+    pub afk_since: Option<Timestamp>,
+
+    /// When the AFK character is removed from the world, `None` while active or when the policy
+    /// never removes it. Clients count down to it.
+    pub despawn_at: Option<Timestamp>,
+}
+
+impl ActivityRow {
+    /// Records activity of the sender, clearing its AFK state.
+    pub fn touch(ctx: &ReducerContext) {
+        let Some(mut row) = ctx.db.activity_tbl().identity().find(ctx.sender) else {
+            ctx.db.activity_tbl().insert(Self {
+                identity: ctx.sender,
+                last_active_at: ctx.timestamp,
+                afk_since: None,
+                despawn_at: None,
+            });
+            return;
+        };
+        let recent = ctx
+            .timestamp
+            .time_duration_since(row.last_active_at)
+            .is_some_and(|idle| idle.to_micros() < TOUCH_RESOLUTION_MICROS);
+        if recent && row.afk_since.is_none() {
+            return;
+        }
+        if row.afk_since.is_some() {
+            if let Some(ci) = ctx.db.character_instance_tbl().identity().find(ctx.sender) {
+                ActorRow::set_flags(ctx, ci.actor_id, ActorFlags::AFK, false);
+            }
+        }
+        row.last_active_at = ctx.timestamp;
+        row.afk_since = None;
+        row.despawn_at = None;
+        ctx.db.activity_tbl().identity().update(row);
+    }
+}
+
+#[table(name = activity_timer, scheduled(activity_tick_reducer))]
+pub struct ActivityTimer {
+    #[primary_key]
+    #[auto_inc]
+    pub scheduled_id: u64,
+    pub scheduled_at: ScheduleAt,
+}
+
+pub fn init_activity(ctx: &ReducerContext) {
+    ctx.db.activity_timer().scheduled_id().delete(1);
+    ctx.db.activity_timer().insert(ActivityTimer {
+        scheduled_id: 1,
+        scheduled_at: Duration::from_millis(ACTIVITY_TICK_INTERVAL_MILLIS).into(),
+    });
+    log::info!("init activity");
+}
+
+/// Flags idle characters AFK and removes those past their grace period.
+///
+/// **Performance & Cost**: O(characters in the world), writes only on state changes
+#[reducer]
+fn activity_tick_reducer(ctx: &ReducerContext, _timer: ActivityTimer) -> Result<(), String> {
+    if ctx.sender != ctx.identity() {
+        log::error!("`activity_tick_reducer` may not be invoked by clients.");
+        return Err("`activity_tick_reducer` may not be invoked by clients.".into());
+    }
+
+    let config = GameConfigRow::get(&ctx.as_read_only());
+    let timeout = TimeDuration::from_micros(i64::from(config.afk_timeout_secs) * 1_000_000);
+    let grace = config
+        .afk_despawn_grace_secs
+        .map(|secs| TimeDuration::from_micros(i64::from(secs) * 1_000_000));
+
+    let mut write_stats = WriteStats::default();
+    let instances: Vec<_> = ctx.db.character_instance_tbl().iter().collect();
+    for ci in instances {
+        // Characters enter the world active.
+        let Some(mut row) = ctx.db.activity_tbl().identity().find(ci.identity) else {
+            ctx.db.activity_tbl().insert(ActivityRow {
+                identity: ci.identity,
+                last_active_at: ctx.timestamp,
+                afk_since: None,
+                despawn_at: None,
+            });
+            write_stats.record(true);
+            continue;
+        };
+
+        if let Some(despawn_at) = row.despawn_at {
+            if despawn_at <= ctx.timestamp {
+                CharacterRow::remove_from_world(ctx, ci.identity);
+                EventLogRow::record(
+                    ctx,
+                    EventKind::IdleDespawn,
+                    Some(ci.actor_id),
+                    format!("Removed idle actor {} of {}", ci.actor_id, ci.identity),
+                );
+                ctx.db.activity_tbl().identity().delete(ci.identity);
+                write_stats.record(true);
+            }
+            continue;
+        }
+        if row.afk_since.is_some() || ctx.timestamp < row.last_active_at + timeout {
+            continue;
+        }
+
+        ActorRow::set_flags(ctx, ci.actor_id, ActorFlags::AFK, true);
+        row.afk_since = Some(ctx.timestamp);
+        row.despawn_at = grace.map(|grace| ctx.timestamp + grace);
+        ctx.db.activity_tbl().identity().update(row);
+        write_stats.record(true);
+    }
+
+    TimingStatsRow::record(ctx, TimingStatsRow::ACTIVITY_TICK, write_stats);
+    Ok(())
+}
+
+/// Finds the viewer's own activity row, for the AFK countdown.
+/// Primary key of `identity`
+#[spacetimedb::view(name = activity_view, public)]
+pub fn activity_view(ctx: &ViewContext) -> Option<ActivityRow> {
+    ctx.db.activity_tbl().identity().find(ctx.sender)
+}
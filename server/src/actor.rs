@@ -1,8 +1,46 @@
-use crate::CapsuleY;
-use shared::ActorId;
-use spacetimedb::table;
+use crate::{
+    character_instance_tbl__view, corpse_tbl, dummy_stats_tbl, experience_tbl, get_view_aoi_actors,
+    health_tbl, level_tbl, mana_tbl, monster_instance_tbl, monster_instance_tbl__view,
+    movement_state_tbl, primary_stats_tbl, regen_stats_tbl, secondary_stats_tbl,
+    transform_tbl__view, AirborneRow, CapsuleY, CharacterInstanceRow, CooldownRow, DuelRow,
+    EmoteRow, EncounterMemberRow, HazardOccupantRow, MonsterInstanceRow, PartitionHandoffRow,
+    ScriptedPathRow, SpeedModifierRow, TargetRow, TransformRow,
+};
+use shared::{dist_sq_xz, ActorFlags, ActorId, InstanceId, STEALTH_DETECTION_RADIUS_SQ};
+use spacetimedb::{table, ReducerContext, ViewContext};
+
+/// The per-kind instance row of an actor.
+pub enum ActorKind {
+    Character(CharacterInstanceRow),
+    Monster(MonsterInstanceRow),
+}
+
+impl ActorKind {
+    pub fn character_id(&self) -> Option<u32> {
+        match self {
+            ActorKind::Character(row) => Some(row.character_id),
+            ActorKind::Monster(_) => None,
+        }
+    }
+
+    pub fn archetype_id(&self) -> Option<u16> {
+        match self {
+            ActorKind::Character(_) => None,
+            ActorKind::Monster(row) => Some(row.archetype_id),
+        }
+    }
+}
 
 /// Shared table for all instances
+///
+/// This is the cold half of an actor, it's written on spawn and rarely after (flags). Anything
+/// written by the movement tick belongs on [`crate::MovementStateRow`] (the hot half) so
+/// movement updates never replicate the capsule, and movement speed lives on
+/// [`crate::SecondaryStatsRow`] with the rest of the derived stats.
+///
+/// `ActorId` is the one join key for everything in the world, per-actor data (transform, stats,
+/// movement, ...) is keyed by it and what the actor *is* lives in a per-kind instance table keyed
+/// by it as well, see [`ActorKind`].
 #[table(name=actor_tbl)]
 pub struct ActorRow {
     #[auto_inc]
@@ -10,5 +48,227 @@ pub struct ActorRow {
     pub id: ActorId,
 
     /// 8 bytes right now but could be quantized to 4bytes
+    ///
+    /// Already scaled by [`Self::scale`], this is the collider the simulation uses.
     pub capsule: CapsuleY,
+
+    /// Size relative to the archetype (`1.0` for characters), clients scale the visuals by it.
+    pub scale: f32,
+
+    /// Raw bits of [`ActorFlags`]
+    pub flags: u64,
+
+    /// The instance the actor is in, see [`crate::InstanceRow`]. The actor's transform and cell
+    /// are relative to this instance's world.
+    #[index(btree)]
+    pub instance_id: InstanceId,
+
+    /// Display name for nameplates, the character's or the monster archetype's name at spawn.
+    pub name: String,
+}
+
+impl ActorRow {
+    pub fn find(ctx: &ViewContext, actor_id: ActorId) -> Option<Self> {
+        ctx.db.actor_tbl().id().find(actor_id)
+    }
+
+    /// Resolves what kind of actor `actor_id` is, `None` if it has no instance row.
+    pub fn kind(ctx: &ViewContext, actor_id: ActorId) -> Option<ActorKind> {
+        if let Some(character) = CharacterInstanceRow::find_by_actor_id(ctx, actor_id) {
+            return Some(ActorKind::Character(character));
+        }
+        MonsterInstanceRow::find(ctx, actor_id).map(ActorKind::Monster)
+    }
+
+    pub fn flags(&self) -> ActorFlags {
+        ActorFlags::from_bits(self.flags)
+    }
+
+    /// Sets or clears the given flags, only writing when they change.
+    pub fn set_flags(ctx: &ReducerContext, actor_id: ActorId, flags: ActorFlags, value: bool) {
+        let Some(mut row) = ctx.db.actor_tbl().id().find(actor_id) else {
+            log::error!("Unable to find actor {} to set flags", actor_id);
+            return;
+        };
+        let mut next = row.flags();
+        next.set(flags, value);
+        if next == row.flags() {
+            return;
+        }
+        row.flags = next.bits();
+        ctx.db.actor_tbl().id().update(row);
+    }
+
+    /// Can this actor currently take damage?
+    pub fn is_damageable(ctx: &ViewContext, actor_id: ActorId) -> bool {
+        Self::find(ctx, actor_id)
+            .map(|row| {
+                !row.flags()
+                    .intersects(ActorFlags::INVULNERABLE | ActorFlags::DEAD)
+            })
+            .unwrap_or(false)
+    }
+
+    /// Is this actor dead (a corpse)? Missing actors count as dead, they can't act either.
+    pub fn is_dead(ctx: &ViewContext, actor_id: ActorId) -> bool {
+        Self::find(ctx, actor_id)
+            .map(|row| row.flags().contains(ActorFlags::DEAD))
+            .unwrap_or(true)
+    }
+
+    /// Is this actor in GM fly mode?
+    pub fn is_flying(ctx: &ViewContext, actor_id: ActorId) -> bool {
+        Self::find(ctx, actor_id).is_some_and(|row| row.flags().contains(ActorFlags::FLYING))
+    }
+
+    /// Is this actor a despawned monster waiting to be reused? See
+    /// [`MonsterInstanceRow::release`].
+    pub fn is_pooled(ctx: &ViewContext, actor_id: ActorId) -> bool {
+        Self::find(ctx, actor_id).is_some_and(|row| row.flags().contains(ActorFlags::POOLED))
+    }
+
+    /// Was this actor launched and hasn't landed yet? See [`AirborneRow::launch`].
+    pub fn is_airborne(ctx: &ViewContext, actor_id: ActorId) -> bool {
+        Self::find(ctx, actor_id).is_some_and(|row| row.flags().contains(ActorFlags::AIRBORNE))
+    }
+
+    /// May `source` damage `target`? The damage rules, checked for every source of damage.
+    ///
+    /// - Actors in different instances can't reach each other.
+    /// - Characters only damage each other while dueling, see [`DuelRow`].
+    /// - Anything else (monsters vs characters) is hostile.
+    pub fn can_harm(ctx: &ViewContext, source: ActorId, target: ActorId) -> bool {
+        if source == target || !Self::in_same_instance(ctx, source, target) {
+            return false;
+        }
+        let is_character =
+            |actor_id| CharacterInstanceRow::find_by_actor_id(ctx, actor_id).is_some();
+        if is_character(source) && is_character(target) {
+            return DuelRow::find_active_between(ctx, source, target).is_some();
+        }
+        true
+    }
+
+    /// Are both actors in the same instance? Positions of actors in different instances can't be
+    /// compared, check this before any range check.
+    pub fn in_same_instance(ctx: &ViewContext, a: ActorId, b: ActorId) -> bool {
+        match (Self::find(ctx, a), Self::find(ctx, b)) {
+            (Some(a), Some(b)) => a.instance_id == b.instance_id,
+            _ => false,
+        }
+    }
+
+    /// Deletes the actor and every per-actor row keyed by its id.
+    ///
+    /// Kind specific rows that aren't keyed by `ActorId` alone (e.g. `character_instance_tbl`)
+    /// are left to the caller.
+    pub fn despawn(ctx: &ReducerContext, actor_id: ActorId) {
+        TransformRow::delete(ctx, actor_id);
+        ctx.db.primary_stats_tbl().actor_id().delete(actor_id);
+        ctx.db.secondary_stats_tbl().actor_id().delete(actor_id);
+        ctx.db.regen_stats_tbl().actor_id().delete(actor_id);
+        ctx.db.health_tbl().actor_id().delete(actor_id);
+        ctx.db.mana_tbl().actor_id().delete(actor_id);
+        ctx.db.experience_tbl().actor_id().delete(actor_id);
+        ctx.db.level_tbl().actor_id().delete(actor_id);
+        ctx.db.movement_state_tbl().actor_id().delete(actor_id);
+        ctx.db.dummy_stats_tbl().actor_id().delete(actor_id);
+        ctx.db.monster_instance_tbl().actor_id().delete(actor_id);
+        Self::clear_world_state(ctx, actor_id);
+        ctx.db.actor_tbl().id().delete(actor_id);
+    }
+
+    /// Deletes the per-actor rows of what the actor is up to in the world (its corpse,
+    /// cooldowns, target, duel, movement effects...), keeping the actor and its stats. Shared by
+    /// [`ActorRow::despawn`] and pooling (see [`MonsterInstanceRow::release`]), a pooled actor
+    /// comes back without any of them.
+    pub fn clear_world_state(ctx: &ReducerContext, actor_id: ActorId) {
+        ctx.db.corpse_tbl().actor_id().delete(actor_id);
+        CooldownRow::delete_for_actor(ctx, actor_id);
+        TargetRow::delete_for_actor(ctx, actor_id);
+        DuelRow::delete_for_actor(ctx, actor_id);
+        EmoteRow::delete_for_actor(ctx, actor_id);
+        ScriptedPathRow::delete_for_actor(ctx, actor_id);
+        EncounterMemberRow::delete_for_actor(ctx, actor_id);
+        SpeedModifierRow::delete_for_actor(ctx, actor_id);
+        AirborneRow::delete_for_actor(ctx, actor_id);
+        HazardOccupantRow::delete_for_actor(ctx, actor_id);
+        PartitionHandoffRow::delete_for_actor(ctx, actor_id);
+    }
+
+    /// Should `actor_id` be replicated to the viewer in `viewer_instance`?
+    ///
+    /// - Actors in other instances are never seen, cells are shared between instances.
+    /// - Pooled actors (see [`MonsterInstanceRow::release`]) are out of the world, never seen.
+    /// - GM invisible actors are only seen by themselves.
+    /// - Stealthed actors are seen by allies, and by enemies within
+    ///   [`STEALTH_DETECTION_RADIUS_SQ`].
+    pub fn is_visible_to(
+        ctx: &ViewContext,
+        viewer: ActorId,
+        viewer_instance: InstanceId,
+        actor_id: ActorId,
+    ) -> bool {
+        if viewer == actor_id {
+            return true;
+        }
+        let Some(row) = Self::find(ctx, actor_id) else {
+            return true;
+        };
+        if row.instance_id != viewer_instance {
+            return false;
+        }
+        let flags = row.flags();
+        if flags.intersects(ActorFlags::GM_INVISIBLE | ActorFlags::POOLED) {
+            return false;
+        }
+        if flags.contains(ActorFlags::STEALTHED) && !Self::are_allies(ctx, viewer, actor_id) {
+            return Self::within_detection_radius(ctx, viewer, actor_id);
+        }
+        true
+    }
+
+    /// Should `actor_id` be replicated to a spectator of `instance_id`? Spectators are GMs, they
+    /// see everything in the instance but pooled actors, stealthed and GM invisible ones included.
+    pub fn is_visible_to_spectator(
+        ctx: &ViewContext,
+        instance_id: InstanceId,
+        actor_id: ActorId,
+    ) -> bool {
+        Self::find(ctx, actor_id).is_none_or(|row| {
+            row.instance_id == instance_id && !row.flags().contains(ActorFlags::POOLED)
+        })
+    }
+
+    /// Are the two actors on the same side (self or party)?
+    ///
+    /// There are no parties yet so an actor is only allied with itself, party membership should
+    /// be checked here once it exists.
+    pub fn are_allies(_ctx: &ViewContext, a: ActorId, b: ActorId) -> bool {
+        a == b
+    }
+
+    fn within_detection_radius(ctx: &ViewContext, viewer: ActorId, actor_id: ActorId) -> bool {
+        let (Some(viewer), Some(target)) = (
+            ctx.db.transform_tbl().actor_id().find(viewer),
+            ctx.db.transform_tbl().actor_id().find(actor_id),
+        ) else {
+            return false;
+        };
+        dist_sq_xz(viewer.translation.into(), target.translation.into())
+            <= STEALTH_DETECTION_RADIUS_SQ
+    }
+}
+
+/// Finds the actor data (capsule, flags) for all visible actors within the AOI.
+/// Primary key of `id`
+#[spacetimedb::view(name = actor_view, public)]
+pub fn actor_view(ctx: &ViewContext) -> Vec<ActorRow> {
+    let Some(actors) = get_view_aoi_actors(ctx) else {
+        return vec![];
+    };
+
+    actors
+        .filter_map(|ms| ActorRow::find(ctx, ms.actor_id))
+        .collect()
 }
@@ -0,0 +1,99 @@
+use crate::{EventKind, EventLogRow};
+use spacetimedb::{reducer, table, Identity, ReducerContext, Table, Timestamp};
+
+/// Identities allowed to call privileged (GM/admin) reducers.
+///
+/// Bootstrapped by [`AdminIdentityRow::bootstrap`], the first identity to connect to a database
+/// without admins becomes one, after that admins are managed with [`grant_admin`] and
+/// [`revoke_admin`].
+#[table(name=admin_identity_tbl)]
+pub struct AdminIdentityRow {
+    #[primary_key]
+    pub identity: Identity,
+
+    pub granted_at: Timestamp,
+
+    /// `None` when granted by the bootstrap.
+    pub granted_by: Option<Identity>,
+}
+
+impl AdminIdentityRow {
+    pub fn is_admin(ctx: &ReducerContext, identity: Identity) -> bool {
+        ctx.db
+            .admin_identity_tbl()
+            .identity()
+            .find(identity)
+            .is_some()
+    }
+
+    /// Permission check for privileged reducers, call it before doing anything else.
+    pub fn require(ctx: &ReducerContext, reducer_name: &str) -> Result<(), String> {
+        if Self::is_admin(ctx, ctx.sender) {
+            return Ok(());
+        }
+        log::error!(
+            "`{}` may only be invoked by admins, denied {:?}",
+            reducer_name,
+            ctx.sender
+        );
+        Err(format!("`{}` may only be invoked by admins.", reducer_name))
+    }
+
+    /// Makes the sender an admin when there are no admins yet.
+    pub fn bootstrap(ctx: &ReducerContext) {
+        if ctx.db.admin_identity_tbl().count() > 0 {
+            return;
+        }
+        ctx.db.admin_identity_tbl().insert(Self {
+            identity: ctx.sender,
+            granted_at: ctx.timestamp,
+            granted_by: None,
+        });
+        EventLogRow::record(
+            ctx,
+            EventKind::AdminGranted,
+            None,
+            format!("Bootstrapped {:?} as the first admin", ctx.sender),
+        );
+    }
+}
+
+#[reducer]
+pub fn grant_admin(ctx: &ReducerContext, identity: Identity) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "grant_admin")?;
+    if AdminIdentityRow::is_admin(ctx, identity) {
+        return Ok(());
+    }
+
+    ctx.db.admin_identity_tbl().insert(AdminIdentityRow {
+        identity,
+        granted_at: ctx.timestamp,
+        granted_by: Some(ctx.sender),
+    });
+    EventLogRow::record(
+        ctx,
+        EventKind::AdminGranted,
+        None,
+        format!("{:?} granted admin to {:?}", ctx.sender, identity),
+    );
+    Ok(())
+}
+
+#[reducer]
+pub fn revoke_admin(ctx: &ReducerContext, identity: Identity) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "revoke_admin")?;
+    if ctx.db.admin_identity_tbl().count() <= 1 {
+        return Err("Unable to revoke the last admin".into());
+    }
+    if !ctx.db.admin_identity_tbl().identity().delete(identity) {
+        return Err("Identity is not an admin".into());
+    }
+
+    EventLogRow::record(
+        ctx,
+        EventKind::AdminRevoked,
+        None,
+        format!("{:?} revoked admin from {:?}", ctx.sender, identity),
+    );
+    Ok(())
+}
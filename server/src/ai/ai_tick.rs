@@ -0,0 +1,105 @@
+use crate::{
+    monster_instance_tbl, ActorRow, AiContext, BehaviorNode, BehaviorTreeRow, EncounterMemberRow,
+    LoadSheddingRow, MonsterArchetypeRow, MonsterInstanceRow, TimingStatsRow, WriteStats,
+};
+use shared::ActorId;
+use spacetimedb::{reducer, table, ReducerContext, ScheduleAt, Table};
+use std::{collections::HashMap, time::Duration};
+
+/// How often monsters make their decisions, stretched under load, see [`LoadSheddingRow`].
+pub(crate) const AI_TICK_INTERVAL_MILLIS: u64 = 250;
+
+/// Max behavior nodes evaluated per tick across all monsters. Monsters left over when it runs out
+/// go first next tick.
+const AI_NODE_BUDGET_PER_TICK: u32 = 2_000;
+
+#[table(name = ai_tick_timer, scheduled(ai_tick_reducer))]
+pub struct AiTickTimer {
+    #[primary_key]
+    #[auto_inc]
+    pub scheduled_id: u64,
+    pub scheduled_at: ScheduleAt,
+
+    /// The monster the last tick ran out of budget on, this tick starts with it.
+    pub cursor: ActorId,
+}
+
+pub fn init_ai(ctx: &ReducerContext) {
+    ctx.db.ai_tick_timer().scheduled_id().delete(1);
+    ctx.db.ai_tick_timer().insert(AiTickTimer {
+        scheduled_id: 1,
+        scheduled_at: Duration::from_millis(LoadSheddingRow::ai_tick_interval_millis(
+            &ctx.as_read_only(),
+        ))
+        .into(),
+        cursor: 0,
+    });
+    log::info!("init ai");
+}
+
+/// Evaluates the behavior tree (see [`BehaviorTreeRow`]) of every living monster, within
+/// [`AI_NODE_BUDGET_PER_TICK`].
+///
+/// **Performance & Cost**: O(N log N) over the monsters, plus the index seeks of the evaluated
+/// nodes
+#[reducer]
+fn ai_tick_reducer(ctx: &ReducerContext, mut timer: AiTickTimer) -> Result<(), String> {
+    if ctx.sender != ctx.identity() {
+        log::error!("`ai_tick_reducer` may not be invoked by clients.");
+        return Err("`ai_tick_reducer` may not be invoked by clients.".into());
+    }
+
+    // Resume with the monster the budget ran out on so every monster gets its turn.
+    let mut monsters: Vec<MonsterInstanceRow> = ctx.db.monster_instance_tbl().iter().collect();
+    monsters.sort_unstable_by_key(|monster| monster.actor_id);
+    let start = monsters.partition_point(|monster| monster.actor_id < timer.cursor);
+    monsters.rotate_left(start);
+
+    let view_ctx = ctx.as_read_only();
+    let mut budget = AI_NODE_BUDGET_PER_TICK;
+    // Aggro radius and tree per archetype, `None` for archetypes without a tree.
+    let mut archetypes: HashMap<u16, Option<(f32, Vec<BehaviorNode>)>> = HashMap::new();
+    let mut cursor = 0;
+    let mut write_stats = WriteStats::default();
+    for monster in monsters {
+        let Some((aggro_radius, nodes)) = archetypes
+            .entry(monster.archetype_id)
+            .or_insert_with(|| {
+                let archetype = MonsterArchetypeRow::find(ctx, monster.archetype_id)?;
+                let tree = BehaviorTreeRow::find(ctx, monster.archetype_id)?;
+                Some((archetype.aggro_radius, tree.nodes))
+            })
+            .as_ref()
+        else {
+            continue;
+        };
+        let Some(actor) = ActorRow::find(&view_ctx, monster.actor_id) else {
+            continue;
+        };
+        if ActorRow::is_dead(&view_ctx, monster.actor_id) {
+            continue;
+        }
+
+        let mut ai = AiContext {
+            ctx,
+            actor_id: monster.actor_id,
+            instance_id: actor.instance_id,
+            aggro_radius: *aggro_radius,
+            leashed_home: EncounterMemberRow::update_leash(ctx, monster.actor_id),
+            budget: &mut budget,
+        };
+        let evaluated = ai.evaluate(nodes).is_some();
+        write_stats.record(evaluated);
+        if !evaluated {
+            cursor = monster.actor_id;
+            break;
+        }
+    }
+
+    if timer.cursor != cursor {
+        timer.cursor = cursor;
+        ctx.db.ai_tick_timer().scheduled_id().update(timer);
+    }
+    TimingStatsRow::record(ctx, TimingStatsRow::AI_TICK, write_stats);
+    Ok(())
+}
@@ -0,0 +1,508 @@
+use crate::{
+    current_server_tick, melee_attack, query_aoe_actors, target_tbl, ActorRow, AdminIdentityRow,
+    AoeShape, CharacterInstanceRow, CooldownKind, CooldownRow, HealthRow, MonsterArchetypeRow,
+    MoveIntentData, MovementStateRow, TargetRow, TransformRow, Vec3, MELEE_REACH,
+};
+use shared::{dist_sq_xz, ActorId, InstanceId};
+use spacetimedb::{reducer, table, ReducerContext, SpacetimeType, Table};
+
+/// A target further away (meters) than this many aggro radii is given up on.
+const LEASH_AGGRO_RADII: f32 = 2.0;
+
+/// Max nodes of a single tree.
+const MAX_NODES: usize = 64;
+
+/// A check that succeeds or fails without side effects.
+#[derive(SpacetimeType, Debug, Clone, Copy, PartialEq)]
+pub enum AiCondition {
+    /// The actor has a living, visible target within its leash range.
+    HasTarget,
+    /// The target is within melee reach of the actor.
+    TargetInMeleeReach,
+    /// The actor's health is below the given percentage.
+    HealthBelowPercent(u8),
+    /// The given cooldown is ready.
+    CooldownReady(CooldownKind),
+}
+
+/// Something the actor does, succeeding when it was done.
+#[derive(SpacetimeType, Debug, Clone, Copy, PartialEq)]
+pub enum AiAction {
+    /// Targets the nearest visible, harmable character within the aggro radius.
+    AcquireNearestEnemy,
+    /// Clears the actor's target.
+    ClearTarget,
+    /// Moves towards the target.
+    ChaseTarget,
+    /// Stops chasing, other intents (e.g. a scripted path's waypoint) are left alone.
+    StopChasing,
+    /// Melee attacks the target, see [`melee_attack`].
+    MeleeAttackTarget,
+}
+
+#[derive(SpacetimeType, Debug, Clone, Copy, PartialEq)]
+pub enum BehaviorNodeKind {
+    /// Succeeds with the first child that succeeds, fails when all fail.
+    Selector,
+    /// Fails with the first child that fails, succeeds when all succeed.
+    Sequence,
+    Condition(AiCondition),
+    Action(AiAction),
+}
+
+/// A node of a [`BehaviorTreeRow`], children are indices into the tree's nodes.
+#[derive(SpacetimeType, Debug, Clone, PartialEq)]
+pub struct BehaviorNode {
+    pub kind: BehaviorNodeKind,
+    pub children: Vec<u16>,
+}
+
+impl BehaviorNode {
+    pub fn selector(children: &[u16]) -> Self {
+        Self {
+            kind: BehaviorNodeKind::Selector,
+            children: children.to_vec(),
+        }
+    }
+
+    pub fn sequence(children: &[u16]) -> Self {
+        Self {
+            kind: BehaviorNodeKind::Sequence,
+            children: children.to_vec(),
+        }
+    }
+
+    pub fn condition(condition: AiCondition) -> Self {
+        Self {
+            kind: BehaviorNodeKind::Condition(condition),
+            children: vec![],
+        }
+    }
+
+    pub fn action(action: AiAction) -> Self {
+        Self {
+            kind: BehaviorNodeKind::Action(action),
+            children: vec![],
+        }
+    }
+}
+
+/// The behavior of a monster archetype, evaluated by the AI tick (see [`crate::init_ai`]).
+///
+/// Stored flat since SpacetimeDB types can't be recursive: the root is the first node and
+/// children always come after their parent, so evaluation can't loop. Trees are re-evaluated
+/// from the root every tick, there is no running state.
+#[table(name=behavior_tree_tbl)]
+pub struct BehaviorTreeRow {
+    #[primary_key]
+    pub archetype_id: u16,
+
+    pub nodes: Vec<BehaviorNode>,
+}
+
+impl BehaviorTreeRow {
+    pub fn find(ctx: &ReducerContext, archetype_id: u16) -> Option<Self> {
+        ctx.db.behavior_tree_tbl().archetype_id().find(archetype_id)
+    }
+
+    /// Replaces the archetype's tree.
+    pub fn set(
+        ctx: &ReducerContext,
+        archetype_id: u16,
+        nodes: Vec<BehaviorNode>,
+    ) -> Result<(), String> {
+        Self::validate(&nodes)?;
+        ctx.db
+            .behavior_tree_tbl()
+            .archetype_id()
+            .delete(archetype_id);
+        ctx.db.behavior_tree_tbl().insert(Self {
+            archetype_id,
+            nodes,
+        });
+        Ok(())
+    }
+
+    fn validate(nodes: &[BehaviorNode]) -> Result<(), String> {
+        if nodes.is_empty() || nodes.len() > MAX_NODES {
+            return Err(format!("A behavior tree needs 1–{MAX_NODES} nodes"));
+        }
+        for (i, node) in nodes.iter().enumerate() {
+            let is_composite = matches!(
+                node.kind,
+                BehaviorNodeKind::Selector | BehaviorNodeKind::Sequence
+            );
+            if is_composite == node.children.is_empty() {
+                return Err(format!(
+                    "Node {i}: only selectors and sequences have children"
+                ));
+            }
+            if node
+                .children
+                .iter()
+                .any(|&child| child as usize <= i || child as usize >= nodes.len())
+            {
+                return Err(format!("Node {i}: children must come after their parent"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Chase and hit the nearest enemy, give up once it's out of the leash range.
+    pub fn melee_brute() -> Vec<BehaviorNode> {
+        use AiAction::*;
+        use AiCondition::*;
+        vec![
+            // 0
+            BehaviorNode::selector(&[1, 5, 8, 11]),
+            // 1: Hit the target when it's in reach.
+            BehaviorNode::sequence(&[2, 3, 4]),
+            BehaviorNode::condition(HasTarget),
+            BehaviorNode::condition(TargetInMeleeReach),
+            BehaviorNode::action(MeleeAttackTarget),
+            // 5: Otherwise go after it.
+            BehaviorNode::sequence(&[6, 7]),
+            BehaviorNode::condition(HasTarget),
+            BehaviorNode::action(ChaseTarget),
+            // 8: Look for a new target.
+            BehaviorNode::sequence(&[9, 10]),
+            BehaviorNode::action(AcquireNearestEnemy),
+            BehaviorNode::action(ChaseTarget),
+            // 11: Nothing to do, drop a lost target.
+            BehaviorNode::sequence(&[12, 13]),
+            BehaviorNode::action(ClearTarget),
+            BehaviorNode::action(StopChasing),
+        ]
+    }
+}
+
+/// Replaces the behavior tree of a monster archetype. Admin only.
+///
+/// Takes effect on the next AI tick, until the archetypes are regenerated.
+#[reducer]
+pub fn set_behavior_tree(
+    ctx: &ReducerContext,
+    archetype_id: u16,
+    nodes: Vec<BehaviorNode>,
+) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "set_behavior_tree")?;
+    if MonsterArchetypeRow::find(ctx, archetype_id).is_none() {
+        return Err("Unknown monster archetype".into());
+    }
+    BehaviorTreeRow::set(ctx, archetype_id, nodes)
+}
+
+/// Evaluates the node at `index` and its children, `leaf` runs the conditions and actions.
+/// `None` when the budget ran out midway.
+fn evaluate_node(
+    nodes: &[BehaviorNode],
+    index: usize,
+    budget: &mut u32,
+    leaf: &mut impl FnMut(BehaviorNodeKind) -> bool,
+) -> Option<bool> {
+    *budget = budget.checked_sub(1)?;
+    let node = &nodes[index];
+    match node.kind {
+        BehaviorNodeKind::Selector => {
+            for &child in &node.children {
+                if evaluate_node(nodes, child as usize, budget, leaf)? {
+                    return Some(true);
+                }
+            }
+            Some(false)
+        }
+        BehaviorNodeKind::Sequence => {
+            for &child in &node.children {
+                if !evaluate_node(nodes, child as usize, budget, leaf)? {
+                    return Some(false);
+                }
+            }
+            Some(true)
+        }
+        kind => Some(leaf(kind)),
+    }
+}
+
+/// What the nodes of one actor's evaluation work with.
+pub struct AiContext<'a> {
+    pub ctx: &'a ReducerContext,
+    pub actor_id: ActorId,
+    pub instance_id: InstanceId,
+    pub aggro_radius: f32,
+    /// Where an encounter member walks back to while it's returning from past its leash, see
+    /// [`crate::EncounterMemberRow::update_leash`].
+    pub leashed_home: Option<Vec3>,
+    /// Nodes left to evaluate this tick, shared by all actors.
+    pub budget: &'a mut u32,
+}
+
+impl AiContext<'_> {
+    /// Evaluates the tree from its root, `None` when the budget ran out midway.
+    pub fn evaluate(&mut self, nodes: &[BehaviorNode]) -> Option<bool> {
+        let mut budget = *self.budget;
+        let result = evaluate_node(nodes, 0, &mut budget, &mut |kind| match kind {
+            BehaviorNodeKind::Condition(condition) => self.check(condition),
+            BehaviorNodeKind::Action(action) => self.act(action),
+            BehaviorNodeKind::Selector | BehaviorNodeKind::Sequence => false,
+        });
+        *self.budget = budget;
+        result
+    }
+
+    fn target(&self) -> Option<ActorId> {
+        TargetRow::find(&self.ctx.as_read_only(), self.actor_id).map(|row| row.target)
+    }
+
+    fn distance_sq_to(&self, other: ActorId) -> Option<f32> {
+        let (Some(a), Some(b)) = (
+            TransformRow::find(self.ctx, self.actor_id),
+            TransformRow::find(self.ctx, other),
+        ) else {
+            return None;
+        };
+        Some(dist_sq_xz(a.translation.into(), b.translation.into()))
+    }
+
+    fn check(&self, condition: AiCondition) -> bool {
+        let view_ctx = self.ctx.as_read_only();
+        match condition {
+            AiCondition::HasTarget => {
+                let leash = self.aggro_radius * LEASH_AGGRO_RADII;
+                if self.leashed_home.is_some() {
+                    return false;
+                }
+                self.target().is_some_and(|target| {
+                    !ActorRow::is_dead(&view_ctx, target)
+                        && ActorRow::is_visible_to(
+                            &view_ctx,
+                            self.actor_id,
+                            self.instance_id,
+                            target,
+                        )
+                        && self
+                            .distance_sq_to(target)
+                            .is_some_and(|distance_sq| distance_sq <= leash * leash)
+                })
+            }
+            AiCondition::TargetInMeleeReach => self.target().is_some_and(|target| {
+                let radius = ActorRow::find(&view_ctx, target)
+                    .map(|actor| actor.capsule.radius)
+                    .unwrap_or(0.0);
+                let reach = MELEE_REACH + radius;
+                self.distance_sq_to(target)
+                    .is_some_and(|distance_sq| distance_sq <= reach * reach)
+            }),
+            AiCondition::HealthBelowPercent(percent) => HealthRow::find(&view_ctx, self.actor_id)
+                .is_some_and(|health| {
+                    (health.data.current as u32) * 100 < (health.data.max as u32) * percent as u32
+                }),
+            AiCondition::CooldownReady(kind) => {
+                CooldownRow::is_ready(self.ctx, self.actor_id, kind)
+            }
+        }
+    }
+
+    fn act(&self, action: AiAction) -> bool {
+        let ctx = self.ctx;
+        match action {
+            AiAction::AcquireNearestEnemy => {
+                if self.leashed_home.is_some() {
+                    return false;
+                }
+                let Some(transform) = TransformRow::find(ctx, self.actor_id) else {
+                    return false;
+                };
+                let view_ctx = ctx.as_read_only();
+                let shape = AoeShape {
+                    center: transform.translation,
+                    radius: self.aggro_radius,
+                    cone: None,
+                };
+                let nearest = query_aoe_actors(ctx, &shape, self.instance_id, None)
+                    .into_iter()
+                    .filter(|&actor_id| {
+                        CharacterInstanceRow::find_by_actor_id(&view_ctx, actor_id).is_some()
+                            && !ActorRow::is_dead(&view_ctx, actor_id)
+                            && ActorRow::can_harm(&view_ctx, self.actor_id, actor_id)
+                            && ActorRow::is_visible_to(
+                                &view_ctx,
+                                self.actor_id,
+                                self.instance_id,
+                                actor_id,
+                            )
+                    })
+                    .filter_map(|actor_id| Some((actor_id, self.distance_sq_to(actor_id)?)))
+                    .min_by(|a, b| a.1.total_cmp(&b.1));
+                let Some((target, _)) = nearest else {
+                    return false;
+                };
+                if self.target() != Some(target) {
+                    ctx.db.target_tbl().actor_id().delete(self.actor_id);
+                    ctx.db.target_tbl().insert(TargetRow {
+                        actor_id: self.actor_id,
+                        target,
+                    });
+                }
+                true
+            }
+            AiAction::ClearTarget => {
+                ctx.db.target_tbl().actor_id().delete(self.actor_id);
+                true
+            }
+            AiAction::ChaseTarget => {
+                let Some(target) = self.target() else {
+                    return false;
+                };
+                self.set_intent(MoveIntentData::Actor(target));
+                true
+            }
+            AiAction::StopChasing => {
+                let Some(movement_state) = MovementStateRow::find(ctx, self.actor_id) else {
+                    return true;
+                };
+                // Encounter members past their leash walk back home.
+                match self.leashed_home {
+                    Some(home) => {
+                        if matches!(
+                            movement_state.move_intent,
+                            MoveIntentData::Actor(_) | MoveIntentData::None
+                        ) {
+                            self.set_intent(MoveIntentData::Point(home.xz()));
+                        }
+                    }
+                    None => {
+                        if matches!(movement_state.move_intent, MoveIntentData::Actor(_)) {
+                            self.set_intent(MoveIntentData::None);
+                        }
+                    }
+                }
+                true
+            }
+            AiAction::MeleeAttackTarget => self
+                .target()
+                .is_some_and(|target| melee_attack(ctx, self.actor_id, target, None).is_ok()),
+        }
+    }
+
+    /// Writes the move intent, unchanged intents are not written.
+    fn set_intent(&self, intent: MoveIntentData) {
+        let Some(mut movement_state) = MovementStateRow::find(self.ctx, self.actor_id) else {
+            return;
+        };
+        if movement_state.move_intent == intent {
+            return;
+        }
+        movement_state.should_move =
+            movement_state.vertical_velocity != 0 || intent != MoveIntentData::None;
+        movement_state.move_intent = intent;
+        movement_state.server_tick = current_server_tick(self.ctx);
+        movement_state.update_from_self(self.ctx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn melee_brute_is_valid() {
+        assert!(BehaviorTreeRow::validate(&BehaviorTreeRow::melee_brute()).is_ok());
+    }
+
+    #[test]
+    fn out_of_range_child_is_rejected() {
+        let nodes = [
+            BehaviorNode::selector(&[1, 2]),
+            BehaviorNode::action(AiAction::ClearTarget),
+        ];
+        assert!(BehaviorTreeRow::validate(&nodes).is_err());
+    }
+
+    #[test]
+    fn cycles_are_rejected() {
+        let back_edge = [BehaviorNode::selector(&[1]), BehaviorNode::sequence(&[0])];
+        assert!(BehaviorTreeRow::validate(&back_edge).is_err());
+        let self_loop = [BehaviorNode::selector(&[0])];
+        assert!(BehaviorTreeRow::validate(&self_loop).is_err());
+    }
+
+    #[test]
+    fn children_only_on_composites() {
+        let childless_selector = [BehaviorNode::selector(&[])];
+        assert!(BehaviorTreeRow::validate(&childless_selector).is_err());
+        let mut leaf = BehaviorNode::action(AiAction::ClearTarget);
+        leaf.children = vec![1];
+        let leaf_with_child = [leaf, BehaviorNode::action(AiAction::ClearTarget)];
+        assert!(BehaviorTreeRow::validate(&leaf_with_child).is_err());
+        assert!(BehaviorTreeRow::validate(&[]).is_err());
+    }
+
+    /// The actions `melee_brute` takes in one evaluation, with the given world state.
+    fn melee_brute_actions(has_target: bool, in_reach: bool, enemy_near: bool) -> Vec<AiAction> {
+        let mut actions = vec![];
+        let mut budget = u32::MAX;
+        let result = evaluate_node(
+            &BehaviorTreeRow::melee_brute(),
+            0,
+            &mut budget,
+            &mut |kind| match kind {
+                BehaviorNodeKind::Condition(AiCondition::HasTarget) => has_target,
+                BehaviorNodeKind::Condition(AiCondition::TargetInMeleeReach) => in_reach,
+                BehaviorNodeKind::Condition(_) => false,
+                BehaviorNodeKind::Action(action) => {
+                    actions.push(action);
+                    action != AiAction::AcquireNearestEnemy || enemy_near
+                }
+                BehaviorNodeKind::Selector | BehaviorNodeKind::Sequence => unreachable!(),
+            },
+        );
+        assert_eq!(result, Some(true));
+        actions
+    }
+
+    #[test]
+    fn melee_brute_attacks_a_target_in_reach() {
+        assert_eq!(
+            melee_brute_actions(true, true, true),
+            [AiAction::MeleeAttackTarget]
+        );
+    }
+
+    #[test]
+    fn melee_brute_chases_a_target_out_of_reach() {
+        assert_eq!(
+            melee_brute_actions(true, false, true),
+            [AiAction::ChaseTarget]
+        );
+    }
+
+    #[test]
+    fn melee_brute_acquires_a_new_target() {
+        assert_eq!(
+            melee_brute_actions(false, false, true),
+            [AiAction::AcquireNearestEnemy, AiAction::ChaseTarget]
+        );
+    }
+
+    #[test]
+    fn melee_brute_idles_without_enemies() {
+        assert_eq!(
+            melee_brute_actions(false, false, false),
+            [
+                AiAction::AcquireNearestEnemy,
+                AiAction::ClearTarget,
+                AiAction::StopChasing
+            ]
+        );
+    }
+
+    #[test]
+    fn evaluation_stops_when_the_budget_runs_out() {
+        let mut budget = 3;
+        let result = evaluate_node(&BehaviorTreeRow::melee_brute(), 0, &mut budget, &mut |_| {
+            false
+        });
+        assert_eq!(result, None);
+        assert_eq!(budget, 0);
+    }
+}
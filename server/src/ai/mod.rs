@@ -0,0 +1,5 @@
+pub mod ai_tick;
+pub mod behavior;
+
+pub use ai_tick::*;
+pub use behavior::*;
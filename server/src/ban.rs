@@ -0,0 +1,169 @@
+//! Kicking and banning players.
+//!
+//! SpacetimeDB has no way for a module to close a client's connection. A kick removes the
+//! character from the world and leaves a [`KickNoticeRow`] the client reads to explain itself
+//! and disconnect. A ban is also a kick, further connections and world entries are refused while
+//! the [`BanRow`] lasts.
+
+use crate::{AdminIdentityRow, CharacterRow, EventKind, EventLogRow};
+use spacetimedb::{
+    reducer, table, Identity, ReducerContext, Table, TimeDuration, Timestamp, ViewContext,
+};
+
+/// Identities not allowed to play, private to keep who banned whom out of the clients.
+#[table(name=ban_tbl)]
+pub struct BanRow {
+    #[primary_key]
+    pub identity: Identity,
+
+    pub banned_at: Timestamp,
+
+    pub banned_by: Identity,
+
+    /// `None` for a permanent ban.
+    pub until: Option<Timestamp>,
+
+    pub reason: String,
+}
+
+impl BanRow {
+    /// The ban of `identity` in force, expired bans are lifted on the way.
+    pub fn active(ctx: &ReducerContext, identity: Identity) -> Option<Self> {
+        let ban = ctx.db.ban_tbl().identity().find(identity)?;
+        match ban.until {
+            Some(until) if until <= ctx.timestamp => {
+                ctx.db.ban_tbl().identity().delete(identity);
+                EventLogRow::record(
+                    ctx,
+                    EventKind::Unbanned,
+                    None,
+                    format!("Ban of {:?} expired", identity),
+                );
+                None
+            }
+            _ => Some(ban),
+        }
+    }
+
+    /// Refuses banned identities, for connecting and entering the world.
+    pub fn require_not_banned(ctx: &ReducerContext, identity: Identity) -> Result<(), String> {
+        match Self::active(ctx, identity) {
+            Some(ban) => Err(ban.describe()),
+            None => Ok(()),
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self.until {
+            Some(until) => format!("Banned until {}: {}", until, self.reason),
+            None => format!("Banned: {}", self.reason),
+        }
+    }
+}
+
+/// Why the viewer was removed from the world, replicated so the client can tell the player
+/// before disconnecting. Cleared when the identity connects again.
+#[table(name=kick_notice_tbl)]
+pub struct KickNoticeRow {
+    #[primary_key]
+    pub identity: Identity,
+
+    pub kicked_at: Timestamp,
+
+    pub message: String,
+}
+
+impl KickNoticeRow {
+    /// Removes the character of `identity` from the world and tells its client why.
+    pub fn kick(ctx: &ReducerContext, identity: Identity, message: String) {
+        CharacterRow::remove_from_world(ctx, identity);
+        ctx.db.kick_notice_tbl().identity().delete(identity);
+        ctx.db.kick_notice_tbl().insert(Self {
+            identity,
+            kicked_at: ctx.timestamp,
+            message,
+        });
+    }
+
+    pub fn clear(ctx: &ReducerContext, identity: Identity) {
+        ctx.db.kick_notice_tbl().identity().delete(identity);
+    }
+}
+
+#[reducer]
+pub fn kick_identity(
+    ctx: &ReducerContext,
+    identity: Identity,
+    reason: String,
+) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "kick_identity")?;
+
+    KickNoticeRow::kick(ctx, identity, format!("Kicked: {}", reason));
+    EventLogRow::record(
+        ctx,
+        EventKind::Kicked,
+        None,
+        format!("{:?} kicked {:?}: {}", ctx.sender, identity, reason),
+    );
+    Ok(())
+}
+
+/// Bans `identity` for `duration_secs`, forever without, and kicks it.
+///
+/// Banning an identity again replaces its ban. Admins can't be banned, revoke them first.
+#[reducer]
+pub fn ban_identity(
+    ctx: &ReducerContext,
+    identity: Identity,
+    duration_secs: Option<u32>,
+    reason: String,
+) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "ban_identity")?;
+    if AdminIdentityRow::is_admin(ctx, identity) {
+        return Err("Unable to ban an admin".into());
+    }
+
+    let ban = BanRow {
+        identity,
+        banned_at: ctx.timestamp,
+        banned_by: ctx.sender,
+        until: duration_secs
+            .map(|secs| ctx.timestamp + TimeDuration::from_micros(i64::from(secs) * 1_000_000)),
+        reason,
+    };
+    let message = ban.describe();
+    ctx.db.ban_tbl().identity().delete(identity);
+    ctx.db.ban_tbl().insert(ban);
+
+    KickNoticeRow::kick(ctx, identity, message.clone());
+    EventLogRow::record(
+        ctx,
+        EventKind::Banned,
+        None,
+        format!("{:?} banned {:?}, {}", ctx.sender, identity, message),
+    );
+    Ok(())
+}
+
+#[reducer]
+pub fn unban_identity(ctx: &ReducerContext, identity: Identity) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "unban_identity")?;
+    if !ctx.db.ban_tbl().identity().delete(identity) {
+        return Err("Identity is not banned".into());
+    }
+
+    EventLogRow::record(
+        ctx,
+        EventKind::Unbanned,
+        None,
+        format!("{:?} unbanned {:?}", ctx.sender, identity),
+    );
+    Ok(())
+}
+
+/// Finds the viewer's own kick notice.
+/// Primary key of `identity`
+#[spacetimedb::view(name = kick_notice_view, public)]
+pub fn kick_notice_view(ctx: &ViewContext) -> Option<KickNoticeRow> {
+    ctx.db.kick_notice_tbl().identity().find(ctx.sender)
+}
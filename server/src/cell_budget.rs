@@ -0,0 +1,226 @@
+//! Soft cap of the actors per cell.
+//!
+//! Every per-cell system (AOI views, avoidance, hazards) slows down with the actors of a cell.
+//! Spawns aimed at a cell already holding [`GameConfigRow::max_actors_per_cell`] actors are
+//! moved to the nearest neighboring cell under budget, see [`redirect_spawn`]. Actors walking
+//! into a full cell aren't stopped, the cap is soft.
+
+use crate::{get_static_query_world, movement_state_tbl, ActorRow, CapsuleY, GameConfigRow, Vec3};
+use nalgebra::Vector3;
+use rapier3d::prelude::{QueryFilter, Ray};
+use shared::{
+    decode_cell_min_corner, encode_cell_id, find_free_position, get_aoi_block_clamped, ActorFlags,
+    CellId, InstanceId, StaticQueryWorld, CELL_SIZE, WORLD_BORDER_HEIGHT,
+};
+use spacetimedb::{ReducerContext, ViewContext};
+
+/// Distance (meters) from the cell's border a redirected spawn is placed at, well inside it.
+const REDIRECT_INSET: f32 = 2.0;
+
+/// How far (planar meters) a redirected spawn may move to find a free spot.
+const REDIRECT_SEARCH_RADIUS: f32 = 4.0;
+
+/// The actors of `instance_id` in the world in `cell_id`, pooled monsters aren't. Cells are
+/// shared by all instances, a crowd in one instance doesn't fill the cell for the others.
+///
+/// **Performance & Cost**: an index scan of the cell's movement states, a seek per actor
+pub fn cell_population(ctx: &ViewContext, instance_id: InstanceId, cell_id: CellId) -> u32 {
+    let actors = ctx
+        .db
+        .movement_state_tbl()
+        .cell_id()
+        .filter(cell_id)
+        .filter_map(|ms| ActorRow::find(ctx, ms.actor_id))
+        .map(|actor| {
+            (
+                actor.instance_id,
+                actor.flags().contains(ActorFlags::POOLED),
+            )
+        });
+    instance_population(instance_id, actors)
+}
+
+/// Counts the actors, as `(instance, pooled)`, of `instance_id` that count towards a budget.
+fn instance_population(
+    instance_id: InstanceId,
+    actors: impl Iterator<Item = (InstanceId, bool)>,
+) -> u32 {
+    actors
+        .filter(|&(actor_instance, pooled)| actor_instance == instance_id && !pooled)
+        .count() as u32
+}
+
+/// Where an actor with `capsule` spawning at `translation` (capsule center) should go.
+///
+/// `translation` itself while its cell is under budget. Otherwise the neighboring cells under
+/// budget are tried nearest first, the spawn goes to the first free spot (see
+/// [`find_free_position`]) found on the ground just inside one. Without any, the spawn stays
+/// where it was.
+///
+/// **Performance & Cost**: one cell count when under budget, up to eight more cell counts and
+/// free position searches otherwise
+pub fn redirect_spawn(
+    ctx: &ReducerContext,
+    instance_id: InstanceId,
+    translation: Vec3,
+    capsule: CapsuleY,
+) -> Vec3 {
+    let view_ctx = ctx.as_read_only();
+    let budget = GameConfigRow::get(&view_ctx).max_actors_per_cell;
+    let cell_id = encode_cell_id(translation.x, translation.z);
+    if cell_population(&view_ctx, instance_id, cell_id) < budget {
+        return translation;
+    }
+
+    let query_world = get_static_query_world(ctx, instance_id);
+    let redirected = neighbors_nearest_first(cell_id, translation.into())
+        .into_iter()
+        .filter(|&(neighbor, _)| cell_population(&view_ctx, instance_id, neighbor) < budget)
+        .find_map(|(neighbor, point)| {
+            // The neighbor's ground can be far above or below the spawn's.
+            let center = ground_center(&query_world, point, capsule)?;
+            find_free_position(
+                &query_world,
+                center,
+                capsule.radius,
+                capsule.half_height,
+                REDIRECT_SEARCH_RADIUS,
+            )
+            .filter(|found| encode_cell_id(found.x, found.z) == neighbor)
+        });
+    match redirected {
+        Some(found) => {
+            log::info!(
+                "Cell {} is over its budget of {}, spawn redirected to {:?}",
+                cell_id,
+                budget,
+                found
+            );
+            Vec3::from(found)
+        }
+        None => {
+            log::warn!(
+                "Cell {} is over its budget of {} and no neighbor has room, spawning anyway",
+                cell_id,
+                budget
+            );
+            translation
+        }
+    }
+}
+
+/// The cells around `cell_id` with their points closest to `from` (see [`nearest_point_inside`]),
+/// nearest first.
+fn neighbors_nearest_first(cell_id: CellId, from: Vector3<f32>) -> Vec<(CellId, Vector3<f32>)> {
+    let mut neighbors: Vec<(CellId, Vector3<f32>)> = get_aoi_block_clamped(cell_id)
+        .filter(|&neighbor| neighbor != cell_id)
+        .map(|neighbor| (neighbor, nearest_point_inside(neighbor, from)))
+        .collect();
+    neighbors.sort_by(|a, b| {
+        (a.1 - from)
+            .norm_squared()
+            .total_cmp(&(b.1 - from).norm_squared())
+    });
+    neighbors
+}
+
+/// Where a capsule stands on the topmost ground under `point`, `None` without ground.
+fn ground_center(
+    query_world: &StaticQueryWorld,
+    point: Vector3<f32>,
+    capsule: CapsuleY,
+) -> Option<Vector3<f32>> {
+    let ray = Ray::new(
+        Vector3::new(point.x, WORLD_BORDER_HEIGHT, point.z).into(),
+        -Vector3::y(),
+    );
+    let (_, toi) = query_world
+        .as_query_pipeline(QueryFilter::only_fixed())
+        .cast_ray(&ray, WORLD_BORDER_HEIGHT * 2.0, true)?;
+    Some(Vector3::new(
+        point.x,
+        WORLD_BORDER_HEIGHT - toi + capsule.half_height + capsule.radius,
+        point.z,
+    ))
+}
+
+/// The point of `cell_id` closest to `from`, at least [`REDIRECT_INSET`] inside its border.
+fn nearest_point_inside(cell_id: CellId, from: Vector3<f32>) -> Vector3<f32> {
+    let (min_x, min_z) = decode_cell_min_corner(cell_id);
+    Vector3::new(
+        from.x
+            .clamp(min_x + REDIRECT_INSET, min_x + CELL_SIZE - REDIRECT_INSET),
+        from.y,
+        from.z
+            .clamp(min_z + REDIRECT_INSET, min_z + CELL_SIZE - REDIRECT_INSET),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_actors_of_the_instance_count() {
+        // Two instances sharing one cell: a crowd in instance 1, a single actor in instance 2.
+        let cell = [(1, false), (1, false), (1, false), (2, false)];
+        assert_eq!(instance_population(1, cell.into_iter()), 3);
+        assert_eq!(instance_population(2, cell.into_iter()), 1);
+        assert_eq!(instance_population(3, cell.into_iter()), 0);
+    }
+
+    #[test]
+    fn nearest_point_inside_is_clamped_into_the_inset() {
+        let cell_id = encode_cell_id(0.0, 0.0);
+        let (min_x, min_z) = decode_cell_min_corner(cell_id);
+
+        // Far outside on both axes, pulled to the inset corner. The height is kept.
+        let far = Vector3::new(min_x - 50.0, 3.0, min_z + CELL_SIZE + 50.0);
+        let point = nearest_point_inside(cell_id, far);
+        assert_eq!(point.x, min_x + REDIRECT_INSET);
+        assert_eq!(point.z, min_z + CELL_SIZE - REDIRECT_INSET);
+        assert_eq!(point.y, 3.0);
+        assert_eq!(encode_cell_id(point.x, point.z), cell_id);
+
+        // Already well inside, unchanged.
+        let inside = Vector3::new(min_x + CELL_SIZE * 0.5, 0.0, min_z + CELL_SIZE * 0.5);
+        assert_eq!(nearest_point_inside(cell_id, inside), inside);
+    }
+
+    #[test]
+    fn neighbors_are_tried_nearest_first() {
+        let cell_id = encode_cell_id(0.0, 0.0);
+        let (min_x, min_z) = decode_cell_min_corner(cell_id);
+        // Close to the cell's +x border, a little off center towards +z.
+        let from = Vector3::new(min_x + CELL_SIZE - 1.0, 0.0, min_z + CELL_SIZE * 0.5 + 1.0);
+        let neighbors = neighbors_nearest_first(cell_id, from);
+        assert_eq!(neighbors.len(), 8);
+        assert!(neighbors.iter().all(|&(neighbor, _)| neighbor != cell_id));
+
+        let cell_at = |dx: f32, dz: f32| {
+            encode_cell_id(
+                min_x + CELL_SIZE * (0.5 + dx),
+                min_z + CELL_SIZE * (0.5 + dz),
+            )
+        };
+        let order: Vec<CellId> = neighbors.iter().map(|&(neighbor, _)| neighbor).collect();
+        // Across the near border first, then across the nearer of the side borders, straight
+        // across before the corner.
+        assert_eq!(
+            order[..3],
+            [cell_at(1.0, 0.0), cell_at(0.0, 1.0), cell_at(1.0, 1.0)]
+        );
+        // The far side comes last.
+        assert!(order[5..].contains(&cell_at(-1.0, 0.0)));
+        for pair in neighbors.windows(2) {
+            assert!((pair[0].1 - from).norm_squared() <= (pair[1].1 - from).norm_squared());
+        }
+    }
+
+    #[test]
+    fn pooled_actors_dont_count() {
+        let cell = [(1, false), (1, true), (2, true)];
+        assert_eq!(instance_population(1, cell.into_iter()), 1);
+        assert_eq!(instance_population(2, cell.into_iter()), 0);
+    }
+}
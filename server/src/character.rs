@@ -1,10 +1,10 @@
 use crate::{
-    actor_tbl, character_instance_tbl, experience_tbl, health_tbl, level_tbl, mana_tbl,
-    movement_state_tbl, primary_stats_tbl, transform_tbl, ActorRow, CapsuleY, CharacterInstanceRow,
-    ExperienceRow, HealthData, HealthRow, LevelRow, ManaData, ManaRow, MoveIntentData,
-    MovementStateRow, PrimaryStatsRow, SecondaryStatsRow, TransformRow, Vec3,
+    actor_tbl, character_instance_tbl, current_server_tick, movement_state_tbl, redirect_spawn,
+    ActivityRow, ActorRow, BanRow, CapsuleY, CharacterInstanceRow, CorpseRow, ExperienceRow,
+    HealthData, HealthRow, InstanceRow, LevelRow, ManaData, ManaRow, MoveIntentData,
+    MovementStateRow, PrimaryStatsRow, SecondaryStatsRow, SpawnPointRow, TransformRow, Vec3,
 };
-use shared::{encode_cell_id, CellId};
+use shared::{encode_cell_id, ActorId, CellId, InstanceId};
 use spacetimedb::{reducer, table, Identity, ReducerContext, Table};
 
 /// The persistence layer for a player's characters
@@ -28,6 +28,9 @@ pub struct CharacterRow {
     pub translation: Vec3,
     pub yaw: f32,
 
+    /// The instance `translation` is in.
+    pub instance_id: InstanceId,
+
     // Primary stats
     pub ferocity: u8,
     pub fortitude: u8,
@@ -71,6 +74,7 @@ impl CharacterRow {
             name,
             yaw: 0.,
             translation: Vec3::new(0., 50.0, 0.),
+            instance_id: InstanceRow::OVERWORLD,
             deleted: false,
             capsule: CapsuleY {
                 radius: 0.3,
@@ -97,35 +101,120 @@ impl CharacterRow {
         todo!("delete character todo")
     }
 
-    fn delete_orphaned_rows(ctx: &ReducerContext) {
-        let Some(ci) = ctx.db.character_instance_tbl().identity().find(&ctx.sender) else {
+    fn delete_orphaned_rows(ctx: &ReducerContext, identity: Identity) {
+        let Some(ci) = ctx.db.character_instance_tbl().identity().find(identity) else {
             log::error!("Unable to find actor for orphaned rows.");
             return;
         };
 
-        ctx.db.transform_tbl().actor_id().delete(ci.actor_id);
-        ctx.db.primary_stats_tbl().actor_id().delete(ci.actor_id);
-        ctx.db.health_tbl().actor_id().delete(ci.actor_id);
-        ctx.db.mana_tbl().actor_id().delete(ci.actor_id);
-        ctx.db.experience_tbl().actor_id().delete(ci.actor_id);
-        ctx.db.level_tbl().actor_id().delete(ci.actor_id);
-        ctx.db.movement_state_tbl().actor_id().delete(ci.actor_id);
-        ctx.db.actor_tbl().id().delete(ci.actor_id);
+        ActorRow::despawn(ctx, ci.actor_id);
         ctx.db.character_instance_tbl().delete(ci);
     }
 
+    /// Copies the live actor state (transform, stats, vitals, progression) back onto this
+    /// persistent row so it survives a crash or the character leaving the world.
+    ///
+    /// Returns `true` when the row was written, unchanged state is not written.
+    pub fn save_live_state(mut self, ctx: &ReducerContext, actor_id: ActorId) -> bool {
+        let view_ctx = ctx.as_read_only();
+        let mut changed = false;
+
+        if let Some(transform) = TransformRow::find(ctx, actor_id) {
+            changed |= self.translation != transform.translation || self.yaw != transform.yaw;
+            self.translation = transform.translation;
+            self.yaw = transform.yaw;
+        }
+        if let Some(actor) = ActorRow::find(&view_ctx, actor_id) {
+            changed |= self.instance_id != actor.instance_id;
+            self.instance_id = actor.instance_id;
+        }
+        if let Some(stats) = PrimaryStatsRow::find(&view_ctx, actor_id) {
+            let live = (
+                stats.ferocity,
+                stats.fortitude,
+                stats.intellect,
+                stats.acuity,
+                stats.available_points,
+            );
+            changed |= live
+                != (
+                    self.ferocity,
+                    self.fortitude,
+                    self.intellect,
+                    self.acuity,
+                    self.available_points,
+                );
+            (
+                self.ferocity,
+                self.fortitude,
+                self.intellect,
+                self.acuity,
+                self.available_points,
+            ) = live;
+        }
+        if let Some(health) = HealthRow::find(&view_ctx, actor_id) {
+            changed |= self.health != health.data;
+            self.health = health.data;
+        }
+        if let Some(mana) = ManaRow::find(&view_ctx, actor_id) {
+            changed |= self.mana != mana.data;
+            self.mana = mana.data;
+        }
+        if let Some(experience) = ExperienceRow::find(&view_ctx, actor_id) {
+            changed |= self.experience != experience.xp;
+            self.experience = experience.xp;
+        }
+        if let Some(level) = LevelRow::find(&view_ctx, actor_id) {
+            changed |= self.level != level.level;
+            self.level = level.level;
+        }
+
+        if changed {
+            ctx.db.character_tbl().id().update(self);
+        }
+        changed
+    }
+
     pub fn leave_game(&self, ctx: &ReducerContext) {
-        Self::delete_orphaned_rows(ctx);
+        Self::remove_from_world(ctx, ctx.sender);
+    }
+
+    /// Saves and despawns the active character of `identity`, e.g. for the sender leaving the
+    /// game or an idle player (see [`crate::ActivityRow`]).
+    pub fn remove_from_world(ctx: &ReducerContext, identity: Identity) {
+        if let Some(ci) = ctx.db.character_instance_tbl().identity().find(identity) {
+            if let Some(character) = ctx.db.character_tbl().id().find(ci.character_id) {
+                character.save_live_state(ctx, ci.actor_id);
+            }
+        }
+        Self::delete_orphaned_rows(ctx, identity);
     }
 
     pub fn enter_game(&self, ctx: &ReducerContext) {
         // Prevent multiple player characters from joining the game, only one character per player
         self.leave_game(ctx);
 
-        let cell_id: CellId = encode_cell_id(self.translation.x, self.translation.z);
+        // Instances can be gone by the time the character comes back, e.g. a closed dungeon.
+        let (instance_id, translation) =
+            if InstanceRow::find(&ctx.as_read_only(), self.instance_id).is_some() {
+                (self.instance_id, self.translation)
+            } else {
+                let instance_id = InstanceRow::OVERWORLD;
+                let translation = SpawnPointRow::nearest(ctx, instance_id, self.translation)
+                    .map(|spawn_point| spawn_point.translation)
+                    .unwrap_or(self.translation);
+                (instance_id, translation)
+            };
+        let translation = redirect_spawn(ctx, instance_id, translation, self.capsule);
+
+        let cell_id: CellId = encode_cell_id(translation.x, translation.z);
         let actor = ctx.db.actor_tbl().insert(ActorRow {
             id: 0,
             capsule: self.capsule,
+            scale: 1.0,
+            flags: 0,
+            instance_id,
+            name: self.name.clone(),
         });
         ctx.db
             .character_instance_tbl()
@@ -136,8 +225,11 @@ impl CharacterRow {
             move_intent: MoveIntentData::None,
             vertical_velocity: -1,
             cell_id,
+            ground_material: None,
+            server_tick: current_server_tick(ctx),
+            client_intent_seq: 0,
         });
-        TransformRow::insert(ctx, actor.id, self.translation, self.yaw);
+        TransformRow::insert(ctx, actor.id, translation, self.yaw);
         PrimaryStatsRow::insert(
             ctx,
             actor.id,
@@ -150,11 +242,24 @@ impl CharacterRow {
         let movement_speed = SecondaryStatsRow::compute_movement_speed(self.level, 0.0, 0.0, 0.0);
         let critical_hit_chance =
             SecondaryStatsRow::compute_critical_hit_chance(self.level, self.ferocity, 0.0);
-        SecondaryStatsRow::insert(ctx, actor.id, movement_speed, critical_hit_chance);
+        SecondaryStatsRow::insert(
+            ctx,
+            actor.id,
+            movement_speed,
+            critical_hit_chance,
+            SecondaryStatsRow::compute_armor(self.level, self.fortitude, 0.0),
+            SecondaryStatsRow::compute_resistance(self.level, self.intellect, 0.0),
+        );
         HealthRow::insert(ctx, actor.id, self.health);
         ManaRow::insert(ctx, actor.id, self.mana);
         ExperienceRow::insert(ctx, actor.id, self.experience);
         LevelRow::insert(ctx, actor.id, self.level);
+
+        // Characters that logged out dead come back as a corpse, they're released once it decays.
+        if self.health.current == 0 {
+            CorpseRow::on_death(ctx, actor.id);
+        }
+        ActivityRow::touch(ctx);
     }
 }
 
@@ -168,6 +273,7 @@ pub fn create_character(ctx: &ReducerContext, name: String) -> Result<(), String
 // TODO: make this correct again, this is changed to just find the first char for testing
 #[reducer]
 pub fn enter_game(ctx: &ReducerContext, character_id: u32) -> Result<(), String> {
+    BanRow::require_not_banned(ctx, ctx.sender)?;
     // let Some(character) = ctx.db.character_tbl().owner_id().find(character_id) else {
     //     return Err("Character not found".into());
     // };
@@ -1,4 +1,4 @@
-use crate::{get_view_aoi_block, MovementStateRow};
+use crate::get_view_aoi_actors;
 use shared::ActorId;
 use spacetimedb::{table, Identity, ViewContext};
 
@@ -35,13 +35,12 @@ impl CharacterInstanceRow {
 /// Primary key of `Identity`
 #[spacetimedb::view(name = character_instance_view, public)]
 pub fn character_instance_view(ctx: &ViewContext) -> Vec<CharacterInstanceRow> {
-    let Some(cell_block) = get_view_aoi_block(ctx) else {
+    let Some(actors) = get_view_aoi_actors(ctx) else {
         return vec![];
     };
     log::info!("character_instance_view called");
 
-    cell_block
-        .flat_map(|cell_id| MovementStateRow::by_cell_id(ctx, cell_id))
+    actors
         .filter_map(|ms| CharacterInstanceRow::find_by_actor_id(ctx, ms.actor_id))
         .collect()
 }
@@ -0,0 +1,64 @@
+use crate::{
+    combat::{resolve_attack, AttackOutcome, Combatant, DamageSchool},
+    ActorRow, CombatEventKind, GameConfigRow, HealthRow, LevelRow, SecondaryStatsRow,
+};
+use shared::{ActorFlags, ActorId, Rng};
+use spacetimedb::{ReducerContext, ViewContext};
+
+impl Combatant {
+    /// The combat stats of an actor, zeroed for missing rows.
+    pub fn find(ctx: &ViewContext, actor_id: ActorId) -> Self {
+        let level = LevelRow::find(ctx, actor_id).map_or(0, |row| row.level);
+        let stats = SecondaryStatsRow::find(ctx, actor_id);
+        Self {
+            level,
+            critical_hit_chance: stats.as_ref().map_or(0.0, |row| row.critical_hit_chance),
+            armor: stats.as_ref().map_or(0.0, |row| row.armor),
+            resistance: stats.as_ref().map_or(0.0, |row| row.resistance),
+        }
+    }
+}
+
+/// Resolves an attack of `attacker` on `target` with the combat math (see [`resolve_attack`])
+/// and applies the damage, flagging both actors as in combat when it landed. Critical hits are
+/// recorded as [`CombatEventKind::CriticalDamage`].
+///
+/// Use one `rng` per reducer call for all its attacks, so e.g. the targets of an AoE don't share
+/// the same roll.
+///
+/// Returns `true` when damage was applied.
+pub fn deal_damage(
+    ctx: &ReducerContext,
+    rng: &mut Rng,
+    attacker: ActorId,
+    target: ActorId,
+    base_damage: u16,
+    school: DamageSchool,
+) -> bool {
+    let view_ctx = ctx.as_read_only();
+    let Some(health) = HealthRow::find(&view_ctx, target) else {
+        return false;
+    };
+    let outcome = resolve_attack(
+        rng,
+        base_damage,
+        school,
+        &Combatant::find(&view_ctx, attacker),
+        &Combatant::find(&view_ctx, target),
+        GameConfigRow::get(&view_ctx).critical_hit_multiplier,
+    );
+    let AttackOutcome::Hit { amount, critical } = outcome else {
+        return false;
+    };
+    let kind = if critical {
+        CombatEventKind::CriticalDamage
+    } else {
+        CombatEventKind::Damage
+    };
+    if !health.take_damage(ctx, Some(attacker), amount, kind) {
+        return false;
+    }
+    ActorRow::set_flags(ctx, target, ActorFlags::IN_COMBAT, true);
+    ActorRow::set_flags(ctx, attacker, ActorFlags::IN_COMBAT, true);
+    true
+}
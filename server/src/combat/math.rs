@@ -0,0 +1,293 @@
+//! Hit, critical hit and mitigation formulas, the single place combat balance lives.
+//!
+//! Pure functions over the combatants' stats (see [`Combatant`]), [`resolve_attack`] combines
+//! them into the outcome of one attack. Every damage application path goes through
+//! [`crate::combat::deal_damage`] which uses these.
+
+use shared::Rng;
+
+/// Damage multiplier of a critical hit, tunable through [`crate::GameConfigRow`].
+pub const DEFAULT_CRITICAL_HIT_MULTIPLIER: f32 = 2.0;
+
+/// Hit chance against a defender of the attacker's level.
+const BASE_HIT_CHANCE: f32 = 0.95;
+
+/// Hit chance lost per level the defender is above the attacker, gained per level below.
+const HIT_CHANCE_PER_LEVEL: f32 = 0.02;
+
+/// Bounds of the hit chance, attacks always have some chance to hit and to miss.
+const MIN_HIT_CHANCE: f32 = 0.5;
+const MAX_HIT_CHANCE: f32 = 0.99;
+
+/// Armor (or resistance) that mitigates half the damage of a level 0 attacker...
+const MITIGATION_BASE: f32 = 50.0;
+/// ...plus this much per attacker level, so the same armor is worth less against higher levels.
+const MITIGATION_PER_LEVEL: f32 = 10.0;
+
+/// Mitigation cap as a normalized fraction (0.0–1.0).
+const MAX_MITIGATION: f32 = 0.75;
+
+/// How much landing from a fall hurts, tunable through [`crate::GameConfigRow`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FallDamageRules {
+    /// Falls up to this height (meters) don't hurt.
+    pub safe_distance: f32,
+    /// Fraction of max health lost per meter fallen past `safe_distance`.
+    pub damage_per_meter: f32,
+}
+
+impl FallDamageRules {
+    pub const DEFAULT: Self = Self {
+        safe_distance: 8.0,
+        damage_per_meter: 0.05,
+    };
+}
+
+/// What kind of damage an attack deals, decides which stat mitigates it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DamageSchool {
+    /// Weapons and fists, mitigated by armor.
+    Physical,
+    /// Abilities drawing on The Veil, mitigated by resistance.
+    Veil,
+}
+
+/// The stats of an attacker or defender that combat math reads.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Combatant {
+    pub level: u8,
+    /// Normalized 0.0–1.0 fraction.
+    pub critical_hit_chance: f32,
+    pub armor: f32,
+    pub resistance: f32,
+}
+
+/// The result of one attack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttackOutcome {
+    Miss,
+    Hit { amount: u16, critical: bool },
+}
+
+/// Chance (0.0–1.0) that an attack of an `attacker_level` attacker hits a `defender_level` one.
+pub fn hit_chance(attacker_level: u8, defender_level: u8) -> f32 {
+    let level_difference = defender_level as f32 - attacker_level as f32;
+    (BASE_HIT_CHANCE - level_difference * HIT_CHANCE_PER_LEVEL)
+        .clamp(MIN_HIT_CHANCE, MAX_HIT_CHANCE)
+}
+
+/// Fraction (0.0–[`MAX_MITIGATION`]) of the damage a mitigating stat absorbs.
+///
+/// Diminishing returns: `value / (value + k)` with `k` growing with the attacker's level, so no
+/// amount of armor makes an actor immune.
+fn mitigation(value: f32, attacker_level: u8) -> f32 {
+    if !value.is_finite() || value <= 0.0 {
+        return 0.0;
+    }
+    let k = MITIGATION_BASE + MITIGATION_PER_LEVEL * attacker_level as f32;
+    (value / (value + k)).min(MAX_MITIGATION)
+}
+
+/// Fraction of physical damage absorbed by `armor`, see [`mitigation`].
+pub fn armor_mitigation(armor: f32, attacker_level: u8) -> f32 {
+    mitigation(armor, attacker_level)
+}
+
+/// Fraction of Veil damage absorbed by `resistance`, see [`mitigation`].
+pub fn resistance_mitigation(resistance: f32, attacker_level: u8) -> f32 {
+    mitigation(resistance, attacker_level)
+}
+
+/// `base` damage scaled by `multiplier` and reduced by the `mitigation` fraction. Rounded, and at
+/// least 1 for any non-zero `base` so attacks are never fully absorbed.
+pub fn mitigated_damage(base: u16, multiplier: f32, mitigation: f32) -> u16 {
+    if base == 0 {
+        return 0;
+    }
+    let amount = base as f32 * multiplier * (1.0 - mitigation.clamp(0.0, 1.0));
+    (amount.round() as u16).max(1)
+}
+
+/// Rolls hit and critical hit of an attack and applies the defender's mitigation. Critical hits
+/// deal `critical_hit_multiplier` times the damage.
+pub fn resolve_attack(
+    rng: &mut Rng,
+    base_damage: u16,
+    school: DamageSchool,
+    attacker: &Combatant,
+    defender: &Combatant,
+    critical_hit_multiplier: f32,
+) -> AttackOutcome {
+    if !rng.chance(hit_chance(attacker.level, defender.level)) {
+        return AttackOutcome::Miss;
+    }
+    let critical = rng.chance(attacker.critical_hit_chance);
+    let multiplier = if critical {
+        critical_hit_multiplier
+    } else {
+        1.0
+    };
+    let mitigation = match school {
+        DamageSchool::Physical => armor_mitigation(defender.armor, attacker.level),
+        DamageSchool::Veil => resistance_mitigation(defender.resistance, attacker.level),
+    };
+    AttackOutcome::Hit {
+        amount: mitigated_damage(base_damage, multiplier, mitigation),
+        critical,
+    }
+}
+
+/// Damage of landing from a `fall_distance` (meters) fall for an actor with `max_health`, `0`
+/// within the safe distance. Long enough falls are lethal.
+pub fn fall_damage(rules: &FallDamageRules, fall_distance: f32, max_health: u16) -> u16 {
+    if !fall_distance.is_finite() || fall_distance <= rules.safe_distance {
+        return 0;
+    }
+    let fraction = (fall_distance - rules.safe_distance) * rules.damage_per_meter;
+    (fraction * max_health as f32).round().min(u16::MAX as f32) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn combatant(level: u8, critical_hit_chance: f32, armor: f32) -> Combatant {
+        Combatant {
+            level,
+            critical_hit_chance,
+            armor,
+            resistance: 0.0,
+        }
+    }
+
+    #[test]
+    fn hit_chance_is_clamped() {
+        assert_eq!(hit_chance(10, 10), BASE_HIT_CHANCE);
+        assert_eq!(hit_chance(1, 50), MIN_HIT_CHANCE);
+        assert_eq!(hit_chance(50, 1), MAX_HIT_CHANCE);
+        assert!(hit_chance(10, 12) < hit_chance(10, 10));
+    }
+
+    #[test]
+    fn mitigation_edge_cases() {
+        assert_eq!(armor_mitigation(0.0, 10), 0.0);
+        assert_eq!(armor_mitigation(-5.0, 10), 0.0);
+        assert_eq!(armor_mitigation(f32::NAN, 10), 0.0);
+        assert_eq!(armor_mitigation(1.0e9, 10), MAX_MITIGATION);
+        assert_eq!(armor_mitigation(f32::INFINITY, 10), 0.0);
+        // k = base at level 0, so armor == k halves the damage.
+        assert!((armor_mitigation(MITIGATION_BASE, 0) - 0.5).abs() < 1.0e-6);
+    }
+
+    #[test]
+    fn mitigation_has_diminishing_returns() {
+        let low = armor_mitigation(50.0, 10);
+        let mid = armor_mitigation(100.0, 10);
+        let high = armor_mitigation(150.0, 10);
+        assert!(low < mid && mid < high);
+        assert!(mid - low > high - mid);
+        // The same armor is worth less against higher level attackers.
+        assert!(armor_mitigation(100.0, 40) < armor_mitigation(100.0, 10));
+    }
+
+    #[test]
+    fn fall_damage_starts_past_the_safe_distance() {
+        let rules = FallDamageRules::DEFAULT;
+        let safe = rules.safe_distance;
+        assert_eq!(fall_damage(&rules, 0.0, 100), 0);
+        assert_eq!(fall_damage(&rules, safe, 100), 0);
+        assert_eq!(fall_damage(&rules, f32::NAN, 100), 0);
+        assert_eq!(fall_damage(&rules, safe + 10.0, 100), 50);
+        // Lethal falls are capped by `take_damage`, not here.
+        assert_eq!(fall_damage(&rules, safe + 40.0, 100), 200);
+
+        let harmless = FallDamageRules {
+            damage_per_meter: 0.0,
+            ..rules
+        };
+        assert_eq!(fall_damage(&harmless, safe + 40.0, 100), 0);
+    }
+
+    #[test]
+    fn mitigated_damage_rounds_and_floors_at_one() {
+        assert_eq!(mitigated_damage(0, 2.0, 0.0), 0);
+        assert_eq!(mitigated_damage(10, 1.0, 0.0), 10);
+        assert_eq!(mitigated_damage(10, 2.0, 0.5), 10);
+        assert_eq!(mitigated_damage(10, 1.0, 1.0), 1);
+        assert_eq!(mitigated_damage(10, 1.0, 0.25), 8);
+    }
+
+    #[test]
+    fn resolve_attack_crits_and_mitigates() {
+        let attacker = combatant(10, 1.0, 0.0);
+        let defender = combatant(0, 0.0, 0.0);
+        let mut rng = Rng::new(1, 0);
+        // Level 0 defender, hit chance is at the cap, so retry the rare miss.
+        let outcome = (0..10)
+            .map(|_| {
+                resolve_attack(
+                    &mut rng,
+                    10,
+                    DamageSchool::Physical,
+                    &attacker,
+                    &defender,
+                    DEFAULT_CRITICAL_HIT_MULTIPLIER,
+                )
+            })
+            .find(|outcome| *outcome != AttackOutcome::Miss);
+        assert_eq!(
+            outcome,
+            Some(AttackOutcome::Hit {
+                amount: 20,
+                critical: true
+            })
+        );
+
+        // Armor doesn't apply to Veil damage.
+        let armored = combatant(0, 0.0, 1.0e9);
+        let attacker = combatant(10, 0.0, 0.0);
+        let hits: Vec<_> = (0..20)
+            .filter_map(|_| {
+                match resolve_attack(
+                    &mut rng,
+                    100,
+                    DamageSchool::Veil,
+                    &attacker,
+                    &armored,
+                    DEFAULT_CRITICAL_HIT_MULTIPLIER,
+                ) {
+                    AttackOutcome::Hit { amount, .. } => Some(amount),
+                    AttackOutcome::Miss => None,
+                }
+            })
+            .collect();
+        assert!(!hits.is_empty());
+        assert!(hits.iter().all(|&amount| amount == 100));
+    }
+
+    #[test]
+    fn resolve_attack_uses_the_critical_hit_multiplier() {
+        let attacker = combatant(10, 1.0, 0.0);
+        let defender = combatant(0, 0.0, 0.0);
+        let mut rng = Rng::new(2, 0);
+        let outcome = (0..10)
+            .map(|_| {
+                resolve_attack(
+                    &mut rng,
+                    10,
+                    DamageSchool::Physical,
+                    &attacker,
+                    &defender,
+                    1.5,
+                )
+            })
+            .find(|outcome| *outcome != AttackOutcome::Miss);
+        assert_eq!(
+            outcome,
+            Some(AttackOutcome::Hit {
+                amount: 15,
+                critical: true
+            })
+        );
+    }
+}
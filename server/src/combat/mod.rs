@@ -0,0 +1,5 @@
+pub mod damage;
+pub mod math;
+
+pub use damage::*;
+pub use math::*;
@@ -0,0 +1,135 @@
+use crate::{get_view_aoi_actors, CombatLogRow, TimingStatsRow, TransformRow, Vec3, WriteStats};
+use shared::ActorId;
+use spacetimedb::{
+    reducer, table, ReducerContext, ScheduleAt, SpacetimeType, Table, Timestamp, ViewContext,
+};
+use std::time::Duration;
+
+/// How long combat events are kept, clients only need them long enough to show them.
+const COMBAT_EVENT_RETENTION_MICROS: i64 = 2_000_000;
+
+/// How often expired combat events are deleted.
+pub(crate) const CLEANUP_INTERVAL_MILLIS: u64 = 1_000;
+
+#[derive(SpacetimeType, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CombatEventKind {
+    Damage,
+    Heal,
+    /// Damage from landing after a fall, see [`crate::on_landed`].
+    FallDamage,
+    /// Damage of a critical hit, see [`crate::combat::resolve_attack`].
+    CriticalDamage,
+}
+
+/// **Ephemeral**: A single application of damage or healing, e.g. for floating combat text.
+///
+/// Rows are deleted once older than [`COMBAT_EVENT_RETENTION_MICROS`], clients should react to
+/// inserts only.
+#[table(name=combat_event_tbl)]
+pub struct CombatEventRow {
+    #[auto_inc]
+    #[primary_key]
+    pub id: u64,
+
+    /// The actor that caused the event, `None` for the environment.
+    pub source: Option<ActorId>,
+
+    #[index(btree)]
+    pub target: ActorId,
+
+    /// The amount actually applied, overkill and overheal are not included.
+    pub amount: u16,
+    pub kind: CombatEventKind,
+
+    /// Where the target was when the event happened.
+    pub position: Vec3,
+
+    pub timestamp: Timestamp,
+}
+
+impl CombatEventRow {
+    /// Also adds the event to the [`CombatLogRow`]s of the characters involved.
+    pub fn record(
+        ctx: &ReducerContext,
+        source: Option<ActorId>,
+        target: ActorId,
+        amount: u16,
+        kind: CombatEventKind,
+    ) {
+        if amount == 0 {
+            return;
+        }
+        CombatLogRow::record(ctx, source, target, amount, kind.into(), ctx.timestamp);
+        let Some(transform) = TransformRow::find(ctx, target) else {
+            return;
+        };
+        ctx.db.combat_event_tbl().insert(Self {
+            id: 0,
+            source,
+            target,
+            amount,
+            kind,
+            position: transform.translation,
+            timestamp: ctx.timestamp,
+        });
+    }
+}
+
+/// Finds the combat events targeting things within the AOI.
+/// Primary key of `id`
+#[spacetimedb::view(name = combat_event_view, public)]
+pub fn combat_event_view(ctx: &ViewContext) -> Vec<CombatEventRow> {
+    let Some(actors) = get_view_aoi_actors(ctx) else {
+        return vec![];
+    };
+
+    actors
+        .flat_map(|ms| ctx.db.combat_event_tbl().target().filter(ms.actor_id))
+        .collect()
+}
+
+#[table(name = combat_event_cleanup_timer, scheduled(combat_event_cleanup_reducer))]
+pub struct CombatEventCleanupTimer {
+    #[primary_key]
+    #[auto_inc]
+    pub scheduled_id: u64,
+    pub scheduled_at: ScheduleAt,
+}
+
+pub fn init_combat_event_cleanup(ctx: &ReducerContext) {
+    ctx.db.combat_event_cleanup_timer().scheduled_id().delete(1);
+    ctx.db
+        .combat_event_cleanup_timer()
+        .insert(CombatEventCleanupTimer {
+            scheduled_id: 1,
+            scheduled_at: Duration::from_millis(CLEANUP_INTERVAL_MILLIS).into(),
+        });
+    log::info!("init combat event cleanup");
+}
+
+#[reducer]
+fn combat_event_cleanup_reducer(
+    ctx: &ReducerContext,
+    _timer: CombatEventCleanupTimer,
+) -> Result<(), String> {
+    if ctx.sender != ctx.identity() {
+        log::error!("`combat_event_cleanup_reducer` may not be invoked by clients.");
+        return Err("`combat_event_cleanup_reducer` may not be invoked by clients.".into());
+    }
+
+    let cutoff = ctx.timestamp.to_micros_since_unix_epoch() - COMBAT_EVENT_RETENTION_MICROS;
+    let mut write_stats = WriteStats::default();
+    let expired: Vec<u64> = ctx
+        .db
+        .combat_event_tbl()
+        .iter()
+        .filter(|event| event.timestamp.to_micros_since_unix_epoch() < cutoff)
+        .map(|event| event.id)
+        .collect();
+    for id in expired {
+        write_stats.record(ctx.db.combat_event_tbl().id().delete(id));
+    }
+
+    TimingStatsRow::record(ctx, TimingStatsRow::COMBAT_EVENT_CLEANUP_TICK, write_stats);
+    Ok(())
+}
@@ -0,0 +1,162 @@
+use crate::{CharacterInstanceRow, CombatEventKind, DomainEvent, DomainEventRow, EventActor};
+use shared::ActorId;
+use spacetimedb::{table, ReducerContext, SpacetimeType, Table, Timestamp, ViewContext};
+
+/// Entries kept per character, the oldest are dropped first.
+const MAX_COMBAT_LOG_ENTRIES: usize = 200;
+
+#[derive(SpacetimeType, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CombatLogKind {
+    Damage,
+    Heal,
+    FallDamage,
+    /// The target died, `amount` is 0.
+    Death,
+    /// Damage of a critical hit.
+    CriticalDamage,
+}
+
+impl From<CombatEventKind> for CombatLogKind {
+    fn from(kind: CombatEventKind) -> Self {
+        match kind {
+            CombatEventKind::Damage => CombatLogKind::Damage,
+            CombatEventKind::Heal => CombatLogKind::Heal,
+            CombatEventKind::FallDamage => CombatLogKind::FallDamage,
+            CombatEventKind::CriticalDamage => CombatLogKind::CriticalDamage,
+        }
+    }
+}
+
+/// A combat event as seen by one character, either as the source or the target.
+///
+/// Unlike [`crate::CombatEventRow`] entries outlive the event, up to
+/// [`MAX_COMBAT_LOG_ENTRIES`] per character, so players can audit the numbers afterwards.
+/// Names are copied so entries stay readable once the other actor despawned.
+#[table(name=combat_log_tbl)]
+pub struct CombatLogRow {
+    #[auto_inc]
+    #[primary_key]
+    pub id: u64,
+
+    /// The character the entry belongs to.
+    #[index(btree)]
+    pub character_id: u32,
+
+    /// Whether the character was the source, dealing the damage or healing, rather than the
+    /// target.
+    pub outgoing: bool,
+
+    /// The actor that caused the event, `None` for the environment.
+    pub source: Option<ActorId>,
+    pub source_name: Option<String>,
+
+    pub target: ActorId,
+    pub target_name: String,
+
+    pub kind: CombatLogKind,
+
+    /// The amount actually applied, see [`crate::CombatEventRow::amount`].
+    pub amount: u16,
+
+    pub timestamp: Timestamp,
+}
+
+impl CombatLogRow {
+    /// Adds the event that happened at `timestamp` to the logs of the characters involved,
+    /// monsters don't keep a log.
+    pub fn record(
+        ctx: &ReducerContext,
+        source: Option<ActorId>,
+        target: ActorId,
+        amount: u16,
+        kind: CombatLogKind,
+        timestamp: Timestamp,
+    ) {
+        let view_ctx = ctx.as_read_only();
+        let target = EventActor::snapshot(&view_ctx, target);
+        let source = source.map(|source| EventActor::snapshot(&view_ctx, source));
+        Self::record_snapshots(ctx, source.as_ref(), &target, amount, kind, timestamp);
+    }
+
+    /// [`Self::record`] for actors captured earlier, they may be gone by now.
+    fn record_snapshots(
+        ctx: &ReducerContext,
+        source: Option<&EventActor>,
+        target: &EventActor,
+        amount: u16,
+        kind: CombatLogKind,
+        timestamp: Timestamp,
+    ) {
+        let owners = [
+            Some(target),
+            source.filter(|source| source.actor_id != target.actor_id),
+        ];
+        for owner in owners.into_iter().flatten() {
+            let Some(character_id) = owner.character_id else {
+                continue;
+            };
+            ctx.db.combat_log_tbl().insert(Self {
+                id: 0,
+                character_id,
+                outgoing: owner.actor_id != target.actor_id,
+                source: source.map(|source| source.actor_id),
+                source_name: source.map(|source| source.name.clone()),
+                target: target.actor_id,
+                target_name: target.name.clone(),
+                kind,
+                amount,
+                timestamp,
+            });
+            Self::trim(ctx, character_id);
+        }
+    }
+
+    /// Logs deaths, a consumer of the domain event bus.
+    pub fn on_domain_event(ctx: &ReducerContext, row: &DomainEventRow) {
+        if let DomainEvent::Died { actor, killer } = &row.event {
+            Self::record_snapshots(
+                ctx,
+                killer.as_ref(),
+                actor,
+                0,
+                CombatLogKind::Death,
+                row.published_at,
+            );
+        }
+    }
+
+    /// Drops the oldest entries of `character_id` past [`MAX_COMBAT_LOG_ENTRIES`].
+    ///
+    /// **Performance & Cost**: O(entries) index scan, usually one delete
+    fn trim(ctx: &ReducerContext, character_id: u32) {
+        let mut ids: Vec<u64> = ctx
+            .db
+            .combat_log_tbl()
+            .character_id()
+            .filter(character_id)
+            .map(|entry| entry.id)
+            .collect();
+        if ids.len() <= MAX_COMBAT_LOG_ENTRIES {
+            return;
+        }
+        ids.sort_unstable();
+        let excess = ids.len() - MAX_COMBAT_LOG_ENTRIES;
+        for id in &ids[..excess] {
+            ctx.db.combat_log_tbl().id().delete(id);
+        }
+    }
+}
+
+/// Finds the combat log of the viewer's active character.
+/// Primary key of `id`
+#[spacetimedb::view(name = combat_log_view, public)]
+pub fn combat_log_view(ctx: &ViewContext) -> Vec<CombatLogRow> {
+    let Some(ci) = CharacterInstanceRow::find_by_identity(ctx) else {
+        return vec![];
+    };
+    ctx.db
+        .combat_log_tbl()
+        .character_id()
+        .filter(ci.character_id)
+        .collect()
+}
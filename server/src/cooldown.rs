@@ -0,0 +1,122 @@
+use crate::CharacterInstanceRow;
+use shared::ActorId;
+use spacetimedb::{
+    table, ReducerContext, SpacetimeType, Table, TimeDuration, Timestamp, ViewContext,
+};
+
+/// The things an actor can be waiting on before using them again.
+#[derive(SpacetimeType, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CooldownKind {
+    /// An ability, by ability id.
+    Ability(u16),
+    /// The basic melee attack
+    Attack,
+    /// Interacting with the world (looting, doors, npcs...)
+    Interaction,
+    /// Summoning a mount
+    Mount,
+    /// Playing an emote
+    Emote,
+    /// Getting out of geometry with `/stuck`
+    Unstuck,
+}
+
+/// **Ephemeral**
+///
+/// Active cooldowns for an actor, one row per (actor, kind). Rows are kept after they expire and
+/// reused the next time that cooldown starts, so `ready_at` in the past means "ready".
+#[table(name=cooldown_tbl)]
+pub struct CooldownRow {
+    #[auto_inc]
+    #[primary_key]
+    pub id: u64,
+
+    #[index(btree)]
+    pub actor_id: ActorId,
+
+    pub kind: CooldownKind,
+
+    /// When the cooldown was started, clients use this to estimate the server clock.
+    pub started_at: Timestamp,
+
+    /// When the cooldown is over
+    pub ready_at: Timestamp,
+}
+
+impl CooldownRow {
+    pub fn find(ctx: &ViewContext, actor_id: ActorId, kind: CooldownKind) -> Option<Self> {
+        ctx.db
+            .cooldown_tbl()
+            .actor_id()
+            .filter(actor_id)
+            .find(|row| row.kind == kind)
+    }
+
+    /// Is the given cooldown over (or was it never started)?
+    pub fn is_ready(ctx: &ReducerContext, actor_id: ActorId, kind: CooldownKind) -> bool {
+        Self::find(&ctx.as_read_only(), actor_id, kind)
+            .map(|row| row.ready_at <= ctx.timestamp)
+            .unwrap_or(true)
+    }
+
+    /// Starts (or restarts) a cooldown for the actor lasting `duration` from now.
+    pub fn start(
+        ctx: &ReducerContext,
+        actor_id: ActorId,
+        kind: CooldownKind,
+        duration: TimeDuration,
+    ) {
+        let started_at = ctx.timestamp;
+        let ready_at = started_at + duration;
+        match Self::find(&ctx.as_read_only(), actor_id, kind) {
+            Some(mut row) => {
+                row.started_at = started_at;
+                row.ready_at = ready_at;
+                ctx.db.cooldown_tbl().id().update(row);
+            }
+            None => {
+                ctx.db.cooldown_tbl().insert(Self {
+                    id: 0,
+                    actor_id,
+                    kind,
+                    started_at,
+                    ready_at,
+                });
+            }
+        }
+    }
+
+    /// Starts the cooldown if it is ready, returning an error otherwise.
+    pub fn try_start(
+        ctx: &ReducerContext,
+        actor_id: ActorId,
+        kind: CooldownKind,
+        duration: TimeDuration,
+    ) -> Result<(), String> {
+        if !Self::is_ready(ctx, actor_id, kind) {
+            return Err("Cooldown is not ready".into());
+        }
+        Self::start(ctx, actor_id, kind, duration);
+        Ok(())
+    }
+
+    pub fn delete_for_actor(ctx: &ReducerContext, actor_id: ActorId) {
+        ctx.db.cooldown_tbl().actor_id().delete(actor_id);
+    }
+}
+
+/// Finds the cooldowns for this player's active character only, cooldowns of others aren't
+/// needed by clients so this is intentionally not AOI based.
+/// Primary key of `id`
+#[spacetimedb::view(name = cooldown_view, public)]
+pub fn cooldown_view(ctx: &ViewContext) -> Vec<CooldownRow> {
+    let Some(ci) = CharacterInstanceRow::find_by_identity(ctx) else {
+        return vec![];
+    };
+
+    ctx.db
+        .cooldown_tbl()
+        .actor_id()
+        .filter(ci.actor_id)
+        .collect()
+}
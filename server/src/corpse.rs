@@ -0,0 +1,255 @@
+use crate::{
+    character_instance_tbl, current_server_tick, get_view_aoi_actors, movement_state_tbl,
+    ActorKind, ActorRow, CooldownKind, CooldownRow, HealthRow, InstanceRow, ManaRow,
+    MonsterInstanceRow, MoveIntentData, SpawnPointRow, TimingStatsRow, TransformRow, Vec3,
+    WriteStats,
+};
+use shared::{dist_sq_xz, ActorFlags, ActorId};
+use spacetimedb::{
+    reducer, table, ReducerContext, ScheduleAt, Table, TimeDuration, Timestamp, ViewContext,
+};
+use std::time::Duration;
+
+/// How long a monster corpse stays in the world before it's despawned.
+const MONSTER_CORPSE_DECAY_MICROS: i64 = 2 * 60 * 1_000_000;
+
+/// How long a character corpse waits to be resurrected before the character is released to the
+/// nearest spawn point.
+const CHARACTER_CORPSE_DECAY_MICROS: i64 = 10 * 60 * 1_000_000;
+
+/// How often expired corpses are cleaned up.
+pub(crate) const DECAY_INTERVAL_MILLIS: u64 = 5_000;
+
+/// Max planar distance (squared, meters) between the caster and the corpse to resurrect it.
+const RESURRECT_RANGE_SQ: f32 = 5.0 * 5.0;
+
+/// Percent of max health and mana an actor comes back with.
+const RESURRECT_VITALS_PERCENT: u32 = 50;
+
+/// Cooldown id of the resurrect ability, kept clear of the AoE ability ids.
+pub const RESURRECT_ABILITY_ID: u16 = 100;
+const RESURRECT_COOLDOWN_MICROS: i64 = 10_000_000;
+
+/// What's left of an actor that died.
+///
+/// The actor keeps all of its rows (flagged [`ActorFlags::DEAD`]) so it can be resurrected in
+/// place, the corpse only records where and when it died and until when it's kept around.
+#[table(name=corpse_tbl)]
+pub struct CorpseRow {
+    #[primary_key]
+    pub actor_id: ActorId,
+
+    pub translation: Vec3,
+    pub yaw: f32,
+
+    pub died_at: Timestamp,
+    pub decays_at: Timestamp,
+
+    /// Whether the corpse can be looted, true for monsters.
+    pub lootable: bool,
+}
+
+impl CorpseRow {
+    pub fn find(ctx: &ViewContext, actor_id: ActorId) -> Option<Self> {
+        ctx.db.corpse_tbl().actor_id().find(actor_id)
+    }
+
+    /// Turns the actor into a corpse where it stands, called when its health reaches zero.
+    pub fn on_death(ctx: &ReducerContext, actor_id: ActorId) {
+        let view_ctx = ctx.as_read_only();
+        if ActorRow::is_dead(&view_ctx, actor_id) {
+            return;
+        }
+        let Some(transform) = TransformRow::find(ctx, actor_id) else {
+            log::error!("Unable to find transform for dying actor {}", actor_id);
+            return;
+        };
+
+        let is_monster = matches!(
+            ActorRow::kind(&view_ctx, actor_id),
+            Some(ActorKind::Monster(_))
+        );
+        let decay_micros = if is_monster {
+            MONSTER_CORPSE_DECAY_MICROS
+        } else {
+            CHARACTER_CORPSE_DECAY_MICROS
+        };
+
+        ActorRow::set_flags(ctx, actor_id, ActorFlags::IN_COMBAT, false);
+        ActorRow::set_flags(ctx, actor_id, ActorFlags::DEAD, true);
+
+        // Corpses don't walk, but keep falling so they still land on the ground.
+        if let Some(mut movement_state) = ctx.db.movement_state_tbl().actor_id().find(actor_id) {
+            movement_state.move_intent = MoveIntentData::None;
+            movement_state.should_move = movement_state.vertical_velocity != 0;
+            movement_state.update_from_self(ctx);
+        }
+
+        ctx.db.corpse_tbl().insert(Self {
+            actor_id,
+            translation: transform.translation,
+            yaw: transform.yaw,
+            died_at: ctx.timestamp,
+            decays_at: ctx.timestamp + TimeDuration::from_micros(decay_micros),
+            lootable: is_monster,
+        });
+        log::info!("Actor {} died at {:?}", actor_id, transform.translation);
+    }
+
+    /// Brings the actor back at `translation` with partial vitals and removes the corpse.
+    pub fn restore(self, ctx: &ReducerContext, translation: Vec3) {
+        let actor_id = self.actor_id;
+        let view_ctx = ctx.as_read_only();
+
+        if let Some(transform) = TransformRow::find(ctx, actor_id) {
+            transform.update(ctx, current_server_tick(ctx), translation, self.yaw);
+        }
+        if let Some(mut movement_state) = ctx.db.movement_state_tbl().actor_id().find(actor_id) {
+            // Start falling so the next tick snaps the actor to the ground.
+            movement_state.vertical_velocity = -1;
+            movement_state.should_move = true;
+            movement_state.update_from_self(ctx);
+        }
+        if let Some(health) = HealthRow::find(&view_ctx, actor_id) {
+            let value = partial_vitals(health.data.max);
+            health.set_current(ctx, value);
+        }
+        if let Some(mana) = ManaRow::find(&view_ctx, actor_id) {
+            let value = partial_vitals(mana.data.max);
+            mana.set_current(ctx, value);
+        }
+
+        ActorRow::set_flags(ctx, actor_id, ActorFlags::DEAD, false);
+        ctx.db.corpse_tbl().actor_id().delete(actor_id);
+        log::info!("Actor {} resurrected at {:?}", actor_id, translation);
+    }
+}
+
+fn partial_vitals(max: u16) -> u16 {
+    ((max as u32 * RESURRECT_VITALS_PERCENT / 100) as u16).max(1)
+}
+
+/// Resurrects a dead character at its corpse, the caster has to be alive and close to the
+/// corpse. Monster corpses stay dead until they decay.
+///
+/// Without a `target` the caster's current target (see [`TargetRow`]) is resurrected.
+#[reducer]
+pub fn resurrect(ctx: &ReducerContext, target: Option<ActorId>) -> Result<(), String> {
+    let Some(ci) = ctx.db.character_instance_tbl().identity().find(ctx.sender) else {
+        log::error!("resurrect: no active character for {:?}", ctx.sender);
+        return Err("No active character".into());
+    };
+    let caster = ci.actor_id;
+    let view_ctx = ctx.as_read_only();
+    if ActorRow::is_dead(&view_ctx, caster) {
+        return Err("Dead actors can't resurrect".into());
+    }
+    let Some(target) = TargetRow::resolve(&view_ctx, caster, target) else {
+        return Err("No target".into());
+    };
+    let Some(corpse) = CorpseRow::find(&view_ctx, target) else {
+        return Err("Target is not dead".into());
+    };
+    if !matches!(
+        ActorRow::kind(&view_ctx, target),
+        Some(ActorKind::Character(_))
+    ) {
+        return Err("Only characters can be resurrected".into());
+    }
+    if !ActorRow::in_same_instance(&view_ctx, caster, target) {
+        return Err("Target is out of range".into());
+    }
+    let Some(caster_transform) = TransformRow::find(ctx, caster) else {
+        log::error!("resurrect: no transform for actor {}", caster);
+        return Err("No transform for caster".into());
+    };
+    if dist_sq_xz(
+        caster_transform.translation.into(),
+        corpse.translation.into(),
+    ) > RESURRECT_RANGE_SQ
+    {
+        return Err("Target is out of range".into());
+    }
+
+    CooldownRow::try_start(
+        ctx,
+        caster,
+        CooldownKind::Ability(RESURRECT_ABILITY_ID),
+        TimeDuration::from_micros(RESURRECT_COOLDOWN_MICROS),
+    )?;
+
+    let translation = corpse.translation;
+    corpse.restore(ctx, translation);
+    Ok(())
+}
+
+/// Finds the corpses for all things within the AOI.
+/// Primary key of `ActorId`
+#[spacetimedb::view(name = corpse_view, public)]
+pub fn corpse_view(ctx: &ViewContext) -> Vec<CorpseRow> {
+    let Some(actors) = get_view_aoi_actors(ctx) else {
+        return vec![];
+    };
+
+    actors
+        .filter_map(|ms| CorpseRow::find(ctx, ms.actor_id))
+        .collect()
+}
+
+#[table(name = corpse_decay_timer, scheduled(corpse_decay_reducer))]
+pub struct CorpseDecayTimer {
+    #[primary_key]
+    #[auto_inc]
+    pub scheduled_id: u64,
+    pub scheduled_at: ScheduleAt,
+}
+
+pub fn init_corpse_decay(ctx: &ReducerContext) {
+    ctx.db.corpse_decay_timer().scheduled_id().delete(1);
+    ctx.db.corpse_decay_timer().insert(CorpseDecayTimer {
+        scheduled_id: 1,
+        scheduled_at: Duration::from_millis(DECAY_INTERVAL_MILLIS).into(),
+    });
+    log::info!("init corpse decay");
+}
+
+/// Cleans up expired corpses: monsters are released to their archetype's pool, characters are
+/// released to the nearest spawn point of their instance.
+#[reducer]
+fn corpse_decay_reducer(ctx: &ReducerContext, _timer: CorpseDecayTimer) -> Result<(), String> {
+    if ctx.sender != ctx.identity() {
+        log::error!("`corpse_decay_reducer` may not be invoked by clients.");
+        return Err("`corpse_decay_reducer` may not be invoked by clients.".into());
+    }
+
+    let mut write_stats = WriteStats::default();
+    let expired: Vec<CorpseRow> = ctx
+        .db
+        .corpse_tbl()
+        .iter()
+        .filter(|corpse| corpse.decays_at <= ctx.timestamp)
+        .collect();
+    for corpse in expired {
+        let view_ctx = ctx.as_read_only();
+        match ActorRow::kind(&view_ctx, corpse.actor_id) {
+            Some(ActorKind::Monster(_)) => MonsterInstanceRow::release(ctx, corpse.actor_id),
+            Some(ActorKind::Character(_)) => {
+                let instance_id = ActorRow::find(&view_ctx, corpse.actor_id)
+                    .map(|actor| actor.instance_id)
+                    .unwrap_or(InstanceRow::OVERWORLD);
+                let spawn = SpawnPointRow::nearest(ctx, instance_id, corpse.translation)
+                    .map(|spawn_point| spawn_point.translation)
+                    .unwrap_or(corpse.translation);
+                corpse.restore(ctx, spawn);
+            }
+            None => {
+                log::error!("Corpse of unknown actor {} decayed", corpse.actor_id);
+                ctx.db.corpse_tbl().actor_id().delete(corpse.actor_id);
+            }
+        }
+        write_stats.record(true);
+    }
+
+    TimingStatsRow::record(ctx, TimingStatsRow::CORPSE_DECAY_TICK, write_stats);
+    Ok(())
+}
@@ -0,0 +1,186 @@
+//! Cheat reducers to speed up manual testing, only compiled with the `dev-tools` feature.
+//!
+//! They act on the admin's active character (or its target) and are all gated by
+//! [`AdminIdentityRow::require`] on top of the feature, every use is recorded in the event log.
+
+use crate::{
+    character_instance_tbl, current_server_tick, get_static_query_world, ActorRow,
+    AdminIdentityRow, CombatEventKind, EventKind, EventLogRow, ExperienceRow, HealthRow,
+    MoveIntentData, MovementStateRow, PrimaryStatsRow, TargetRow, TransformRow,
+    TutorialProgressRow, Vec3,
+};
+use nalgebra::Vector3;
+use rapier3d::prelude::{QueryFilter, Ray};
+use shared::{decode_cell_min_corner, ActorId, CellId, CELL_SIZE, WORLD_BORDER_HEIGHT};
+use spacetimedb::{reducer, ReducerContext, SpacetimeType};
+
+/// The primary stat [`set_stat`] changes.
+#[derive(SpacetimeType, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrimaryStat {
+    Ferocity,
+    Fortitude,
+    Intellect,
+    Acuity,
+}
+
+/// The admin's active character.
+fn sender_actor_id(ctx: &ReducerContext) -> Result<ActorId, String> {
+    ctx.db
+        .character_instance_tbl()
+        .identity()
+        .find(ctx.sender)
+        .map(|ci| ci.actor_id)
+        .ok_or_else(|| "No active character".into())
+}
+
+fn record(ctx: &ReducerContext, actor_id: ActorId, message: String) {
+    log::info!("dev tools: {message}");
+    EventLogRow::record(ctx, EventKind::DevTool, Some(actor_id), message);
+}
+
+/// Awards experience to the admin's active character, leveling it up as usual. Admin only.
+#[reducer]
+pub fn give_xp(ctx: &ReducerContext, amount: u32) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "give_xp")?;
+    let actor_id = sender_actor_id(ctx)?;
+    let Some(experience) = ExperienceRow::find(&ctx.as_read_only(), actor_id) else {
+        return Err("Unable to find experience for the active character".into());
+    };
+    experience.add_exp(ctx, amount);
+    record(
+        ctx,
+        actor_id,
+        format!("Gave {amount} xp to actor {actor_id}"),
+    );
+    Ok(())
+}
+
+/// Sets a primary stat of the admin's active character, ignoring the points it has to spend.
+/// Stays within the per-stat bounds. Admin only.
+#[reducer]
+pub fn set_stat(ctx: &ReducerContext, stat: PrimaryStat, value: u8) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "set_stat")?;
+    let actor_id = sender_actor_id(ctx)?;
+    if !(PrimaryStatsRow::MIN_STAT..=PrimaryStatsRow::MAX_STAT).contains(&value) {
+        return Err(format!(
+            "Invalid value {value}, expected {} to {}",
+            PrimaryStatsRow::MIN_STAT,
+            PrimaryStatsRow::MAX_STAT
+        ));
+    }
+    let Some(stats) = PrimaryStatsRow::find(&ctx.as_read_only(), actor_id) else {
+        return Err("Unable to find primary stats for the active character".into());
+    };
+
+    let (mut ferocity, mut fortitude, mut intellect, mut acuity) = (
+        stats.ferocity,
+        stats.fortitude,
+        stats.intellect,
+        stats.acuity,
+    );
+    match stat {
+        PrimaryStat::Ferocity => ferocity = value,
+        PrimaryStat::Fortitude => fortitude = value,
+        PrimaryStat::Intellect => intellect = value,
+        PrimaryStat::Acuity => acuity = value,
+    }
+    stats.update(
+        ctx,
+        ferocity,
+        fortitude,
+        intellect,
+        acuity,
+        stats.available_points,
+    );
+    record(
+        ctx,
+        actor_id,
+        format!("Set {stat:?} of actor {actor_id} to {value}"),
+    );
+    Ok(())
+}
+
+/// Kills the admin's current target with unattributed damage, invulnerable actors survive it.
+/// Admin only.
+#[reducer]
+pub fn kill_target(ctx: &ReducerContext) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "kill_target")?;
+    let actor_id = sender_actor_id(ctx)?;
+    let view_ctx = ctx.as_read_only();
+    let Some(target) = TargetRow::resolve(&view_ctx, actor_id, None) else {
+        return Err("No target".into());
+    };
+    let Some(health) = HealthRow::find(&view_ctx, target) else {
+        return Err(format!("Unable to find health for actor {target}"));
+    };
+    let amount = health.data.current;
+    if !health.take_damage(ctx, None, amount, CombatEventKind::Damage) {
+        return Err("Target can't be damaged".into());
+    }
+    record(ctx, actor_id, format!("Killed actor {target}"));
+    Ok(())
+}
+
+/// Teleports the admin's active character to the center of `cell_id` in its instance, onto the
+/// highest ground there. Admin only.
+#[reducer]
+pub fn goto_cell(ctx: &ReducerContext, cell_id: CellId) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "goto_cell")?;
+    let actor_id = sender_actor_id(ctx)?;
+    let view_ctx = ctx.as_read_only();
+    let (Some(actor), Some(transform), Some(mut movement_state)) = (
+        ActorRow::find(&view_ctx, actor_id),
+        TransformRow::find(ctx, actor_id),
+        MovementStateRow::find(ctx, actor_id),
+    ) else {
+        return Err("Unable to find the active character's actor".into());
+    };
+
+    let (min_x, min_z) = decode_cell_min_corner(cell_id);
+    let (x, z) = (min_x + CELL_SIZE * 0.5, min_z + CELL_SIZE * 0.5);
+    let query_world = get_static_query_world(ctx, actor.instance_id);
+    let ray = Ray::new(
+        Vector3::new(x, WORLD_BORDER_HEIGHT, z).into(),
+        -Vector3::y(),
+    );
+    // No ground keeps the height, falling below the kill plane recovers to a spawn point.
+    let y = query_world
+        .as_query_pipeline(QueryFilter::only_fixed())
+        .cast_ray(&ray, WORLD_BORDER_HEIGHT * 2.0, true)
+        .map(|(_, toi)| {
+            WORLD_BORDER_HEIGHT - toi + actor.capsule.half_height + actor.capsule.radius
+        })
+        .unwrap_or(transform.translation.y);
+    let translation = Vec3 { x, y, z };
+
+    transform.update(ctx, current_server_tick(ctx), translation, transform.yaw);
+    movement_state.move_intent = MoveIntentData::None;
+    // Start falling so the next tick snaps the actor to the ground.
+    movement_state.vertical_velocity = -1;
+    movement_state.should_move = true;
+    movement_state.cell_id = cell_id;
+    movement_state.update_from_self(ctx);
+    record(
+        ctx,
+        actor_id,
+        format!("Moved actor {actor_id} to cell {cell_id} at {translation:?}"),
+    );
+    Ok(())
+}
+
+/// Forgets the tutorial progress of the admin's active character, its onboarding hints show
+/// again. Admin only.
+#[reducer]
+pub fn reset_tutorial(ctx: &ReducerContext) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "reset_tutorial")?;
+    let Some(ci) = ctx.db.character_instance_tbl().identity().find(ctx.sender) else {
+        return Err("No active character".into());
+    };
+    TutorialProgressRow::reset(ctx, ci.character_id);
+    record(
+        ctx,
+        ci.actor_id,
+        format!("Reset the tutorial of character {}", ci.character_id),
+    );
+    Ok(())
+}
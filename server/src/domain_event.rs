@@ -0,0 +1,281 @@
+//! Cross-reducer event bus.
+//!
+//! Reducers [`DomainEventRow::publish`] what happened (an actor died, changed instances...)
+//! instead of calling every system interested in it. Consumers are listed in [`CONSUMERS`], the
+//! domain event tick hands each of them the events it hasn't seen yet, in publish order.
+//!
+//! Every consumer keeps its own offset ([`DomainEventConsumerRow`]), advanced in the same
+//! transaction as the consumer's writes, so each event is handled exactly once per consumer
+//! even when a tick fails and is retried. Events every consumer is past are deleted.
+//!
+//! Events are handled up to a tick after they're published, they carry what their consumers
+//! need (e.g. names, see [`EventActor`]) rather than the consumers looking up rows that may be
+//! gone by then.
+
+use crate::{
+    ActorRow, CharacterInstanceRow, CombatLogRow, TimingStatsRow, TutorialProgressRow, WriteStats,
+};
+use shared::{ActorId, InstanceId};
+use spacetimedb::{
+    reducer, table, ReducerContext, ScheduleAt, SpacetimeType, Table, Timestamp, ViewContext,
+};
+use std::time::Duration;
+
+pub(crate) const DOMAIN_EVENT_TICK_INTERVAL_MILLIS: u64 = 250;
+
+/// Events handed to a consumer per tick at most, the rest waits for the next tick.
+const MAX_EVENTS_PER_CONSUMER: usize = 256;
+
+/// An actor as it was when the event was published, it may have despawned or logged out by the
+/// time the event is handled.
+#[derive(SpacetimeType, Debug, Clone, PartialEq)]
+pub struct EventActor {
+    pub actor_id: ActorId,
+    pub name: String,
+    /// The character the actor is, `None` for monsters.
+    pub character_id: Option<u32>,
+}
+
+impl EventActor {
+    /// **Performance & Cost**: O(1), two index seeks
+    pub fn snapshot(ctx: &ViewContext, actor_id: ActorId) -> Self {
+        Self {
+            actor_id,
+            name: ActorRow::find(ctx, actor_id).map_or_else(String::new, |actor| actor.name),
+            character_id: CharacterInstanceRow::find_by_actor_id(ctx, actor_id)
+                .map(|ci| ci.character_id),
+        }
+    }
+}
+
+/// What happened, published by the reducer that did it.
+#[derive(SpacetimeType, Debug, Clone, PartialEq)]
+pub enum DomainEvent {
+    /// An actor died, `killer` is `None` for the environment (falls, hazards).
+    Died {
+        actor: EventActor,
+        killer: Option<EventActor>,
+    },
+    /// An actor moved to another instance, see [`crate::enter_instance`].
+    InstanceChanged {
+        actor_id: ActorId,
+        from: InstanceId,
+        to: InstanceId,
+    },
+}
+
+/// A consumer of the bus: its name, the key of its offset row, and its handler.
+type Consumer = (&'static str, fn(&ReducerContext, &DomainEventRow));
+
+/// Every consumer of the bus. Renaming one starts it over from the oldest event kept.
+const CONSUMERS: &[Consumer] = &[
+    ("combat_log", CombatLogRow::on_domain_event),
+    ("tutorial", TutorialProgressRow::on_domain_event),
+];
+
+/// An event waiting for its consumers, append only.
+#[table(name=domain_event_tbl, index(name=id_range, btree(columns=[id])))]
+pub struct DomainEventRow {
+    #[auto_inc]
+    #[primary_key]
+    pub id: u64,
+
+    pub published_at: Timestamp,
+
+    pub event: DomainEvent,
+}
+
+/// How far a consumer got, the id of the last event it handled.
+#[table(name=domain_event_consumer_tbl)]
+pub struct DomainEventConsumerRow {
+    #[primary_key]
+    pub consumer: String,
+
+    pub offset: u64,
+
+    pub consumed_at: Timestamp,
+}
+
+impl DomainEventRow {
+    /// Appends `event` for the consumers, handled on the next domain event tick.
+    pub fn publish(ctx: &ReducerContext, event: DomainEvent) {
+        ctx.db.domain_event_tbl().insert(Self {
+            id: 0,
+            published_at: ctx.timestamp,
+            event,
+        });
+    }
+
+    /// Hands `consumer` the events past its offset, at most `limit`, and advances the offset
+    /// past them. Returns how many were handled.
+    ///
+    /// **Performance & Cost**: O(handled events) index range seek, one offset write
+    pub fn consume(
+        ctx: &ReducerContext,
+        consumer: &str,
+        limit: usize,
+        mut handle: impl FnMut(&ReducerContext, &DomainEventRow),
+    ) -> usize {
+        let row = ctx
+            .db
+            .domain_event_consumer_tbl()
+            .consumer()
+            .find(consumer.to_string());
+        let offset = row.as_ref().map_or(0, |row| row.offset);
+
+        // Collected first, the handlers write while the events are handed out.
+        let pending: Vec<_> = ctx
+            .db
+            .domain_event_tbl()
+            .id_range()
+            .filter(offset + 1..)
+            .take(limit)
+            .collect();
+        let (handled, last) =
+            handle_pending(pending.into_iter(), limit, |event| handle(ctx, event));
+        let Some(last) = last else {
+            return 0;
+        };
+
+        let advanced = DomainEventConsumerRow {
+            consumer: consumer.to_string(),
+            offset: last,
+            consumed_at: ctx.timestamp,
+        };
+        match row {
+            Some(_) => {
+                ctx.db
+                    .domain_event_consumer_tbl()
+                    .consumer()
+                    .update(advanced);
+            }
+            None => {
+                ctx.db.domain_event_consumer_tbl().insert(advanced);
+            }
+        }
+        handled
+    }
+
+    /// Deletes the events every consumer handled.
+    ///
+    /// **Performance & Cost**: O(handled events) index range delete
+    fn prune(ctx: &ReducerContext) -> u64 {
+        let consumed = consumed_by_all(CONSUMERS.iter().map(|(name, _)| {
+            ctx.db
+                .domain_event_consumer_tbl()
+                .consumer()
+                .find(name.to_string())
+                .map(|row| row.offset)
+        }));
+        if consumed == 0 {
+            return 0;
+        }
+        ctx.db.domain_event_tbl().id_range().delete(..=consumed)
+    }
+}
+
+/// Hands the first `limit` of `pending` (in id order) to `handle`, returns how many were handled
+/// and the id of the last one.
+fn handle_pending(
+    pending: impl Iterator<Item = DomainEventRow>,
+    limit: usize,
+    mut handle: impl FnMut(&DomainEventRow),
+) -> (usize, Option<u64>) {
+    let mut handled = 0;
+    let mut last = None;
+    for event in pending.take(limit) {
+        handle(&event);
+        handled += 1;
+        last = Some(event.id);
+    }
+    (handled, last)
+}
+
+/// The last event id every consumer is past, given the consumers' offsets. A consumer without an
+/// offset hasn't handled anything yet.
+fn consumed_by_all(offsets: impl Iterator<Item = Option<u64>>) -> u64 {
+    offsets
+        .map(|offset| offset.unwrap_or(0))
+        .min()
+        .unwrap_or(u64::MAX)
+}
+
+#[table(name = domain_event_timer, scheduled(domain_event_tick_reducer))]
+pub struct DomainEventTimer {
+    #[primary_key]
+    #[auto_inc]
+    pub scheduled_id: u64,
+    pub scheduled_at: ScheduleAt,
+}
+
+pub fn init_domain_events(ctx: &ReducerContext) {
+    ctx.db.domain_event_timer().scheduled_id().delete(1);
+    ctx.db.domain_event_timer().insert(DomainEventTimer {
+        scheduled_id: 1,
+        scheduled_at: Duration::from_millis(DOMAIN_EVENT_TICK_INTERVAL_MILLIS).into(),
+    });
+    log::info!("init domain events");
+}
+
+/// Runs every consumer over its pending events, then drops the events all of them handled.
+///
+/// **Performance & Cost**: O(consumers * pending events), nothing written when idle
+#[reducer]
+fn domain_event_tick_reducer(ctx: &ReducerContext, _timer: DomainEventTimer) -> Result<(), String> {
+    if ctx.sender != ctx.identity() {
+        log::error!("`domain_event_tick_reducer` may not be invoked by clients.");
+        return Err("`domain_event_tick_reducer` may not be invoked by clients.".into());
+    }
+
+    let mut write_stats = WriteStats::default();
+    for (name, handle) in CONSUMERS {
+        let handled = DomainEventRow::consume(ctx, name, MAX_EVENTS_PER_CONSUMER, handle);
+        write_stats.record(handled > 0);
+    }
+    write_stats.record(DomainEventRow::prune(ctx) > 0);
+
+    TimingStatsRow::record(ctx, TimingStatsRow::DOMAIN_EVENT_TICK, write_stats);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(id: u64) -> DomainEventRow {
+        DomainEventRow {
+            id,
+            published_at: Timestamp::UNIX_EPOCH,
+            event: DomainEvent::InstanceChanged {
+                actor_id: id as ActorId,
+                from: 0,
+                to: 1,
+            },
+        }
+    }
+
+    #[test]
+    fn pending_events_are_handled_in_order_up_to_the_limit() {
+        let mut seen = vec![];
+        let pending = [3, 4, 7, 9].map(event).into_iter();
+        let (handled, last) = handle_pending(pending, 3, |event| seen.push(event.id));
+        assert_eq!(seen, [3, 4, 7]);
+        assert_eq!(handled, 3);
+        assert_eq!(last, Some(7));
+    }
+
+    #[test]
+    fn nothing_pending_keeps_the_offset() {
+        let (handled, last) = handle_pending(std::iter::empty(), 8, |_| panic!("no events"));
+        assert_eq!(handled, 0);
+        assert_eq!(last, None);
+    }
+
+    #[test]
+    fn events_are_kept_until_the_slowest_consumer_handled_them() {
+        assert_eq!(consumed_by_all([Some(12), Some(5)].into_iter()), 5);
+        // A new consumer starts from the oldest event kept, nothing can go.
+        assert_eq!(consumed_by_all([Some(12), None].into_iter()), 0);
+        assert_eq!(consumed_by_all(std::iter::empty()), u64::MAX);
+    }
+}
@@ -0,0 +1,296 @@
+use crate::{
+    character_instance_tbl, get_view_aoi_actors, is_in_aoi, ActorRow, CharacterInstanceRow,
+    TargetRow, TimingStatsRow, TransformRow, Vec3, WriteStats,
+};
+use shared::{dist_sq_xz, ActorId, InstanceId};
+use spacetimedb::{
+    reducer, table, ReducerContext, ScheduleAt, SpacetimeType, Table, Timestamp, ViewContext,
+};
+use std::time::Duration;
+
+/// Max distance (meters) between the two characters to request a duel.
+const DUEL_REQUEST_RANGE: f32 = 10.0;
+const DUEL_REQUEST_RANGE_SQ: f32 = DUEL_REQUEST_RANGE * DUEL_REQUEST_RANGE;
+
+/// How far (meters) a duelist may move away from where the duel started before it's forfeited.
+const DUEL_LEASH_RADIUS: f32 = 30.0;
+const DUEL_LEASH_RADIUS_SQ: f32 = DUEL_LEASH_RADIUS * DUEL_LEASH_RADIUS;
+
+/// How long a duel request waits to be accepted.
+const DUEL_REQUEST_TIMEOUT_MICROS: i64 = 30_000_000;
+
+/// How often duels are checked for timeouts and leash breaks.
+pub(crate) const DUEL_CHECK_INTERVAL_MILLIS: u64 = 500;
+
+#[derive(SpacetimeType, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuelState {
+    /// Waiting for the opponent to accept.
+    Requested,
+    /// The two duelists can damage each other.
+    Active,
+}
+
+/// Why a duel ended, see [`DuelRow::end`].
+#[derive(Debug, Clone, Copy)]
+pub enum DuelOutcome {
+    /// The loser was brought down to 1 health by the winner.
+    Defeated { winner: ActorId },
+    /// The loser left the leash area or the instance.
+    Fled { winner: ActorId },
+    /// A duelist is gone, or the request was never accepted.
+    Cancelled,
+}
+
+/// **Ephemeral**: A duel between two characters, the only way characters can damage each other.
+///
+/// An actor takes part in at most one duel (requested or active). Duels never kill, damage that
+/// would is capped at 1 health and ends the duel, see [`crate::HealthRow::take_damage`].
+#[table(name=duel_tbl)]
+pub struct DuelRow {
+    #[auto_inc]
+    #[primary_key]
+    pub id: u64,
+
+    #[index(btree)]
+    pub challenger: ActorId,
+
+    #[index(btree)]
+    pub opponent: ActorId,
+
+    pub state: DuelState,
+
+    pub instance_id: InstanceId,
+
+    /// Center of the leash area, the midpoint between the duelists when the duel started.
+    pub center: Vec3,
+
+    pub requested_at: Timestamp,
+}
+
+impl DuelRow {
+    /// The duel `actor_id` takes part in, if any.
+    pub fn find_for_actor(ctx: &ViewContext, actor_id: ActorId) -> Option<Self> {
+        ctx.db
+            .duel_tbl()
+            .challenger()
+            .filter(actor_id)
+            .next()
+            .or_else(|| ctx.db.duel_tbl().opponent().filter(actor_id).next())
+    }
+
+    /// The other duelist.
+    pub fn other(&self, actor_id: ActorId) -> ActorId {
+        if self.challenger == actor_id {
+            self.opponent
+        } else {
+            self.challenger
+        }
+    }
+
+    /// The active duel between `a` and `b`, if any.
+    pub fn find_active_between(ctx: &ViewContext, a: ActorId, b: ActorId) -> Option<Self> {
+        Self::find_for_actor(ctx, a)
+            .filter(|duel| duel.state == DuelState::Active && duel.other(a) == b)
+    }
+
+    /// Ends the duel, deleting its row.
+    pub fn end(self, ctx: &ReducerContext, outcome: DuelOutcome) {
+        log::info!(
+            "Duel {} between {} and {} ended: {:?}",
+            self.id,
+            self.challenger,
+            self.opponent,
+            outcome
+        );
+        ctx.db.duel_tbl().id().delete(self.id);
+    }
+
+    /// Cancels any duel `actor_id` takes part in, e.g. when it despawns.
+    pub fn delete_for_actor(ctx: &ReducerContext, actor_id: ActorId) {
+        ctx.db.duel_tbl().challenger().delete(actor_id);
+        ctx.db.duel_tbl().opponent().delete(actor_id);
+    }
+
+    /// How the duel ends this check, `None` while it goes on.
+    fn check(&self, ctx: &ReducerContext) -> Option<DuelOutcome> {
+        if self.state == DuelState::Requested {
+            let expired = ctx
+                .timestamp
+                .time_duration_since(self.requested_at)
+                .is_some_and(|waited| waited.to_micros() >= DUEL_REQUEST_TIMEOUT_MICROS);
+            return expired.then_some(DuelOutcome::Cancelled);
+        }
+
+        let view_ctx = ctx.as_read_only();
+        for (duelist, other) in [
+            (self.challenger, self.opponent),
+            (self.opponent, self.challenger),
+        ] {
+            let Some(actor) = ActorRow::find(&view_ctx, duelist) else {
+                return Some(DuelOutcome::Cancelled);
+            };
+            let Some(transform) = TransformRow::find(ctx, duelist) else {
+                return Some(DuelOutcome::Cancelled);
+            };
+            let left_leash =
+                dist_sq_xz(transform.translation.into(), self.center.into()) > DUEL_LEASH_RADIUS_SQ;
+            if actor.instance_id != self.instance_id || left_leash {
+                return Some(DuelOutcome::Fled { winner: other });
+            }
+        }
+        None
+    }
+}
+
+/// The player's active character, rejecting dead ones.
+fn living_character(ctx: &ReducerContext, action: &str) -> Result<ActorId, String> {
+    let Some(ci) = ctx.db.character_instance_tbl().identity().find(ctx.sender) else {
+        log::error!("{}: no active character for {:?}", action, ctx.sender);
+        return Err("No active character".into());
+    };
+    if ActorRow::is_dead(&ctx.as_read_only(), ci.actor_id) {
+        return Err("Dead actors can't duel".into());
+    }
+    Ok(ci.actor_id)
+}
+
+/// Challenges the given character, or the current target (see [`TargetRow`]), to a duel.
+///
+/// Both have to be nearby and not in a duel already, the opponent has to [`accept_duel`].
+#[reducer]
+pub fn request_duel(ctx: &ReducerContext, target: Option<ActorId>) -> Result<(), String> {
+    let challenger = living_character(ctx, "request_duel")?;
+    let view_ctx = ctx.as_read_only();
+    let Some(opponent) = TargetRow::resolve(&view_ctx, challenger, target) else {
+        return Err("No target".into());
+    };
+    if opponent == challenger {
+        return Err("Can't duel yourself".into());
+    }
+    if CharacterInstanceRow::find_by_actor_id(&view_ctx, opponent).is_none()
+        || ActorRow::is_dead(&view_ctx, opponent)
+    {
+        return Err("Only living characters can be challenged".into());
+    }
+    if DuelRow::find_for_actor(&view_ctx, challenger).is_some()
+        || DuelRow::find_for_actor(&view_ctx, opponent).is_some()
+    {
+        return Err("Already in a duel".into());
+    }
+    let (Some(actor), Some(from), Some(to)) = (
+        ActorRow::find(&view_ctx, challenger),
+        TransformRow::find(ctx, challenger),
+        TransformRow::find(ctx, opponent),
+    ) else {
+        return Err("Target is out of range".into());
+    };
+    if !is_in_aoi(&view_ctx, challenger, opponent)
+        || dist_sq_xz(from.translation.into(), to.translation.into()) > DUEL_REQUEST_RANGE_SQ
+    {
+        return Err("Target is out of range".into());
+    }
+
+    ctx.db.duel_tbl().insert(DuelRow {
+        id: 0,
+        challenger,
+        opponent,
+        state: DuelState::Requested,
+        instance_id: actor.instance_id,
+        center: from.translation,
+        requested_at: ctx.timestamp,
+    });
+    Ok(())
+}
+
+/// Accepts the pending duel request of the player's active character, starting the duel.
+#[reducer]
+pub fn accept_duel(ctx: &ReducerContext) -> Result<(), String> {
+    let opponent = living_character(ctx, "accept_duel")?;
+    let view_ctx = ctx.as_read_only();
+    let Some(mut duel) = DuelRow::find_for_actor(&view_ctx, opponent)
+        .filter(|duel| duel.state == DuelState::Requested && duel.opponent == opponent)
+    else {
+        return Err("No duel request".into());
+    };
+    let (Some(a), Some(b)) = (
+        TransformRow::find(ctx, duel.challenger),
+        TransformRow::find(ctx, duel.opponent),
+    ) else {
+        return Err("Challenger is gone".into());
+    };
+
+    duel.state = DuelState::Active;
+    duel.center = Vec3::new(
+        (a.translation.x + b.translation.x) * 0.5,
+        (a.translation.y + b.translation.y) * 0.5,
+        (a.translation.z + b.translation.z) * 0.5,
+    );
+    log::info!(
+        "Duel {} between {} and {} started",
+        duel.id,
+        duel.challenger,
+        duel.opponent
+    );
+    ctx.db.duel_tbl().id().update(duel);
+    Ok(())
+}
+
+/// Finds the duels of everyone within the AOI.
+/// Primary key of `id`
+#[spacetimedb::view(name = duel_view, public)]
+pub fn duel_view(ctx: &ViewContext) -> Vec<DuelRow> {
+    let Some(actors) = get_view_aoi_actors(ctx) else {
+        return vec![];
+    };
+
+    let mut duels: Vec<DuelRow> = actors
+        .filter_map(|ms| DuelRow::find_for_actor(ctx, ms.actor_id))
+        .collect();
+    // Both duelists are usually in the AOI.
+    duels.sort_unstable_by_key(|duel| duel.id);
+    duels.dedup_by_key(|duel| duel.id);
+    duels
+}
+
+#[table(name = duel_check_timer, scheduled(duel_check_reducer))]
+pub struct DuelCheckTimer {
+    #[primary_key]
+    #[auto_inc]
+    pub scheduled_id: u64,
+    pub scheduled_at: ScheduleAt,
+}
+
+pub fn init_duel_check(ctx: &ReducerContext) {
+    ctx.db.duel_check_timer().scheduled_id().delete(1);
+    ctx.db.duel_check_timer().insert(DuelCheckTimer {
+        scheduled_id: 1,
+        scheduled_at: Duration::from_millis(DUEL_CHECK_INTERVAL_MILLIS).into(),
+    });
+    log::info!("init duel check");
+}
+
+/// Ends expired duel requests and duels where a duelist fled or is gone.
+///
+/// **Performance & Cost**: O(N) scan of the duels, two index seeks per active duelist
+#[reducer]
+fn duel_check_reducer(ctx: &ReducerContext, _timer: DuelCheckTimer) -> Result<(), String> {
+    if ctx.sender != ctx.identity() {
+        log::error!("`duel_check_reducer` may not be invoked by clients.");
+        return Err("`duel_check_reducer` may not be invoked by clients.".into());
+    }
+
+    let mut write_stats = WriteStats::default();
+    let ended: Vec<(DuelRow, DuelOutcome)> = ctx
+        .db
+        .duel_tbl()
+        .iter()
+        .filter_map(|duel| duel.check(ctx).map(|outcome| (duel, outcome)))
+        .collect();
+    for (duel, outcome) in ended {
+        duel.end(ctx, outcome);
+        write_stats.record(true);
+    }
+
+    TimingStatsRow::record(ctx, TimingStatsRow::DUEL_CHECK_TICK, write_stats);
+    Ok(())
+}
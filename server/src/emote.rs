@@ -0,0 +1,124 @@
+use crate::{
+    character_instance_tbl, get_view_aoi_actors, ActivityRow, ActorRow, CooldownKind, CooldownRow,
+    MovementStateRow,
+};
+use shared::ActorId;
+use spacetimedb::{
+    reducer, table, ReducerContext, SpacetimeType, Table, TimeDuration, Timestamp, ViewContext,
+};
+
+/// Minimum time between two emotes of the same actor.
+const EMOTE_COOLDOWN_MICROS: i64 = 2_000_000;
+
+#[derive(SpacetimeType, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmoteKind {
+    Wave,
+    Bow,
+    Cheer,
+    Dance,
+}
+
+impl EmoteKind {
+    /// How long the emote plays.
+    pub fn duration_micros(self) -> i64 {
+        match self {
+            Self::Wave => 2_000_000,
+            Self::Bow => 1_500_000,
+            Self::Cheer => 2_500_000,
+            Self::Dance => 8_000_000,
+        }
+    }
+}
+
+/// **Ephemeral**
+///
+/// The emote an actor plays or last played, one row per actor. Like cooldowns, rows are kept
+/// after they end and reused by the next emote, so `ends_at` in the past means "not emoting".
+/// Moving ends the emote early, see [`EmoteRow::stop`].
+#[table(name=emote_tbl)]
+pub struct EmoteRow {
+    #[primary_key]
+    pub actor_id: ActorId,
+
+    pub kind: EmoteKind,
+
+    pub started_at: Timestamp,
+
+    pub ends_at: Timestamp,
+}
+
+impl EmoteRow {
+    pub fn find(ctx: &ViewContext, actor_id: ActorId) -> Option<Self> {
+        ctx.db.emote_tbl().actor_id().find(actor_id)
+    }
+
+    /// Starts (or restarts) `kind` for the actor.
+    pub fn start(ctx: &ReducerContext, actor_id: ActorId, kind: EmoteKind) {
+        let row = Self {
+            actor_id,
+            kind,
+            started_at: ctx.timestamp,
+            ends_at: ctx.timestamp + TimeDuration::from_micros(kind.duration_micros()),
+        };
+        if Self::find(&ctx.as_read_only(), actor_id).is_some() {
+            ctx.db.emote_tbl().actor_id().update(row);
+        } else {
+            ctx.db.emote_tbl().insert(row);
+        }
+    }
+
+    /// Ends the actor's emote now if it's still playing.
+    pub fn stop(ctx: &ReducerContext, actor_id: ActorId) {
+        let Some(mut row) = Self::find(&ctx.as_read_only(), actor_id) else {
+            return;
+        };
+        if row.ends_at <= ctx.timestamp {
+            return;
+        }
+        row.ends_at = ctx.timestamp;
+        ctx.db.emote_tbl().actor_id().update(row);
+    }
+
+    pub fn delete_for_actor(ctx: &ReducerContext, actor_id: ActorId) {
+        ctx.db.emote_tbl().actor_id().delete(actor_id);
+    }
+}
+
+/// Plays an emote on the active character, visible to everyone with it in their AOI.
+///
+/// Rate limited by [`CooldownKind::Emote`]. Dead or moving characters can't emote.
+#[reducer]
+pub fn emote(ctx: &ReducerContext, kind: EmoteKind) -> Result<(), String> {
+    let Some(ci) = ctx.db.character_instance_tbl().identity().find(ctx.sender) else {
+        return Err("Unable to find active character".into());
+    };
+    ActivityRow::touch(ctx);
+    if ActorRow::is_dead(&ctx.as_read_only(), ci.actor_id) {
+        return Err("Dead actors can't emote".into());
+    }
+    if MovementStateRow::find(ctx, ci.actor_id).is_some_and(|ms| ms.should_move) {
+        return Err("Can't emote while moving".into());
+    }
+
+    CooldownRow::try_start(
+        ctx,
+        ci.actor_id,
+        CooldownKind::Emote,
+        TimeDuration::from_micros(EMOTE_COOLDOWN_MICROS),
+    )?;
+    EmoteRow::start(ctx, ci.actor_id, kind);
+    Ok(())
+}
+
+/// Finds the emotes of actors within the AOI.
+/// Primary key of `actor_id`
+#[spacetimedb::view(name = emote_view, public)]
+pub fn emote_view(ctx: &ViewContext) -> Vec<EmoteRow> {
+    let Some(actors) = get_view_aoi_actors(ctx) else {
+        return vec![];
+    };
+
+    actors
+        .filter_map(|ms| ctx.db.emote_tbl().actor_id().find(ms.actor_id))
+        .collect()
+}
@@ -0,0 +1,354 @@
+//! Scripted encounters, groups of monsters authored as data and spawned with one call.
+//!
+//! An [`EncounterDefRow`] lists its members (archetype, offset from the encounter's origin and
+//! an optional patrol). [`spawn_encounter`] spawns all of them in the same transaction, a member
+//! failing to spawn rolls the whole group back. Spawned members share an [`EncounterGroupRow`]:
+//! a member further than the group's leash radius from its home gives up its target and walks
+//! back, taking no new targets until it's home again.
+
+use crate::{
+    monster_archetype_tbl, sender_instance_id, transform_tbl, AdminIdentityRow,
+    MonsterArchetypeRow, PathLoopMode, ScriptedPathRow, ScriptedWaypoint, TransformRow, Vec2, Vec3,
+};
+use nalgebra::Vector3;
+use shared::{dist_sq_xz, validate, ActorId, InstanceId};
+use spacetimedb::{reducer, table, ReducerContext, SpacetimeType, Table, Timestamp};
+
+/// Max members of a single encounter.
+const MAX_MEMBERS: usize = 32;
+
+/// Bounds for [`EncounterDefRow::leash_radius`] (meters).
+const MAX_LEASH_RADIUS: f32 = 200.0;
+
+/// Distance (meters) from its home a returning member counts as back, see
+/// [`EncounterMemberRow::returning`].
+const HOME_REACHED_RADIUS: f32 = 1.0;
+
+/// A patrol walked by an encounter member, see [`ScriptedPathRow`].
+#[derive(SpacetimeType, Debug, Clone, PartialEq)]
+pub struct EncounterPatrol {
+    /// Waypoints relative to the encounter's origin.
+    pub waypoints: Vec<ScriptedWaypoint>,
+    pub loop_mode: PathLoopMode,
+    pub speed_override: Option<f32>,
+}
+
+/// One monster of an encounter.
+#[derive(SpacetimeType, Debug, Clone, PartialEq)]
+pub struct EncounterMember {
+    pub archetype_id: u16,
+    /// Spawn position relative to the encounter's origin, also the member's home unless the
+    /// spawn is redirected.
+    pub offset: Vec3,
+    pub yaw: f32,
+    pub patrol: Option<EncounterPatrol>,
+}
+
+/// An authored group of monsters, see [`spawn_encounter`].
+#[table(name=encounter_def_tbl)]
+pub struct EncounterDefRow {
+    #[auto_inc]
+    #[primary_key]
+    pub id: u32,
+
+    #[unique]
+    pub name: String,
+
+    pub members: Vec<EncounterMember>,
+
+    /// Distance (meters) from their home the members chase targets up to.
+    pub leash_radius: f32,
+}
+
+impl EncounterDefRow {
+    /// Inserts or replaces (by name) an encounter after validating it.
+    pub fn set(
+        ctx: &ReducerContext,
+        name: String,
+        members: Vec<EncounterMember>,
+        leash_radius: f32,
+    ) -> Result<Self, String> {
+        if name.trim().is_empty() {
+            return Err("Encounter name can't be empty".into());
+        }
+        if members.is_empty() || members.len() > MAX_MEMBERS {
+            return Err(format!("An encounter needs 1–{MAX_MEMBERS} members"));
+        }
+        if !leash_radius.is_finite() || leash_radius <= 0.0 || leash_radius > MAX_LEASH_RADIUS {
+            return Err(format!(
+                "Leash radius must be within (0, {MAX_LEASH_RADIUS}]"
+            ));
+        }
+        for member in &members {
+            if MonsterArchetypeRow::find(ctx, member.archetype_id).is_none() {
+                return Err(format!("Unknown monster archetype {}", member.archetype_id));
+            }
+            validate::finite_vec3(member.offset.into())?;
+            if !member.yaw.is_finite() {
+                return Err(format!("Invalid yaw {}", member.yaw));
+            }
+        }
+
+        let id = match ctx.db.encounter_def_tbl().name().find(&name) {
+            Some(existing) => {
+                ctx.db.encounter_def_tbl().id().delete(existing.id);
+                existing.id
+            }
+            None => 0,
+        };
+        Ok(ctx.db.encounter_def_tbl().insert(Self {
+            id,
+            name,
+            members,
+            leash_radius,
+        }))
+    }
+
+    /// Spawns every member around `origin`, returns the group. Nothing is left behind on
+    /// errors, the reducer's transaction rolls back.
+    pub fn spawn(
+        &self,
+        ctx: &ReducerContext,
+        instance_id: InstanceId,
+        origin: Vec3,
+    ) -> Result<EncounterGroupRow, String> {
+        let origin: Vector3<f32> = validate::within_world(origin.into())?;
+        let group = ctx.db.encounter_group_tbl().insert(EncounterGroupRow {
+            id: 0,
+            encounter_id: self.id,
+            instance_id,
+            origin: origin.into(),
+            leash_radius: self.leash_radius,
+            spawned_at: ctx.timestamp,
+        });
+
+        for member in &self.members {
+            let Some(archetype) = MonsterArchetypeRow::find(ctx, member.archetype_id) else {
+                return Err(format!("Unknown monster archetype {}", member.archetype_id));
+            };
+            let translation = validate::within_world(origin + Vector3::from(member.offset))?;
+            let actor_id = archetype.spawn(ctx, instance_id, translation.into(), member.yaw);
+            // A crowded cell moves the spawn (see `crate::redirect_spawn`), home is where the
+            // member actually is.
+            let home = TransformRow::find(ctx, actor_id)
+                .map_or(translation.into(), |transform| transform.translation);
+            ctx.db.encounter_member_tbl().insert(EncounterMemberRow {
+                actor_id,
+                group_id: group.id,
+                home,
+                returning: false,
+            });
+
+            if let Some(patrol) = &member.patrol {
+                let waypoints = patrol
+                    .waypoints
+                    .iter()
+                    .map(|waypoint| ScriptedWaypoint {
+                        point: Vec2::new(origin.x + waypoint.point.x, origin.z + waypoint.point.z),
+                        ..*waypoint
+                    })
+                    .collect();
+                ScriptedPathRow::start(
+                    ctx,
+                    actor_id,
+                    waypoints,
+                    patrol.loop_mode,
+                    patrol.speed_override,
+                )?;
+            }
+        }
+        Ok(group)
+    }
+
+    /// Seeding step of [`crate::SchemaVersionRow`], a troll camp for testing fights: two
+    /// guards and a third patrolling around them.
+    pub fn seed(ctx: &ReducerContext) {
+        let Some(troll) = ctx
+            .db
+            .monster_archetype_tbl()
+            .name()
+            .find("Troll".to_string())
+        else {
+            log::error!("Unable to seed the troll camp encounter, no Troll archetype");
+            return;
+        };
+        let patrol = EncounterPatrol {
+            waypoints: [(6.0, 6.0), (-6.0, 6.0), (-6.0, -6.0), (6.0, -6.0)]
+                .into_iter()
+                .map(|(x, z)| ScriptedWaypoint {
+                    point: Vec2::new(x, z),
+                    pause_millis: 1500,
+                })
+                .collect(),
+            loop_mode: PathLoopMode::Loop,
+            speed_override: None,
+        };
+        let members = vec![
+            EncounterMember {
+                archetype_id: troll.id,
+                offset: Vec3::new(-2.0, 0.0, 0.0),
+                yaw: 0.0,
+                patrol: None,
+            },
+            EncounterMember {
+                archetype_id: troll.id,
+                offset: Vec3::new(2.0, 0.0, 0.0),
+                yaw: 0.0,
+                patrol: None,
+            },
+            EncounterMember {
+                archetype_id: troll.id,
+                offset: Vec3::new(6.0, 0.0, 6.0),
+                yaw: 0.0,
+                patrol: Some(patrol),
+            },
+        ];
+        if let Err(err) = Self::set(ctx, "Troll camp".into(), members, 25.0) {
+            log::error!("Unable to seed the troll camp encounter: {err}");
+        }
+    }
+}
+
+/// A spawned encounter, alive while any of its members is.
+#[table(name=encounter_group_tbl)]
+pub struct EncounterGroupRow {
+    #[auto_inc]
+    #[primary_key]
+    pub id: u64,
+
+    pub encounter_id: u32,
+
+    pub instance_id: InstanceId,
+
+    pub origin: Vec3,
+
+    /// Copied from the definition, edits don't change spawned groups.
+    pub leash_radius: f32,
+
+    pub spawned_at: Timestamp,
+}
+
+/// A monster spawned by an encounter.
+#[table(name=encounter_member_tbl)]
+pub struct EncounterMemberRow {
+    #[primary_key]
+    pub actor_id: ActorId,
+
+    #[index(btree)]
+    pub group_id: u64,
+
+    /// Where the member spawned and walks back to when leashed.
+    pub home: Vec3,
+
+    /// Set once the member passed its leash radius, cleared when it's back home. A returning
+    /// member takes no targets, it would otherwise turn around on the leash edge.
+    pub returning: bool,
+}
+
+impl EncounterMemberRow {
+    /// Updates whether the member is returning (see [`Self::returning`]) and returns its home
+    /// while it is, `None` for members that aren't or actors not spawned by an encounter.
+    ///
+    /// **Performance & Cost**: three index seeks, a write when the member starts or stops
+    /// returning
+    pub fn update_leash(ctx: &ReducerContext, actor_id: ActorId) -> Option<Vec3> {
+        let mut member = ctx.db.encounter_member_tbl().actor_id().find(actor_id)?;
+        let group = ctx.db.encounter_group_tbl().id().find(member.group_id)?;
+        let transform = ctx.db.transform_tbl().actor_id().find(actor_id)?;
+        let distance_sq = dist_sq_xz(transform.translation.into(), member.home.into());
+        let returning = is_returning(member.returning, distance_sq, group.leash_radius);
+        let home = member.home;
+        if returning != member.returning {
+            member.returning = returning;
+            ctx.db.encounter_member_tbl().actor_id().update(member);
+        }
+        returning.then_some(home)
+    }
+
+    /// Forgets the member, and its group once it was the last one.
+    ///
+    /// Returns `true` when the actor was a member.
+    pub fn delete_for_actor(ctx: &ReducerContext, actor_id: ActorId) -> bool {
+        let Some(member) = ctx.db.encounter_member_tbl().actor_id().find(actor_id) else {
+            return false;
+        };
+        ctx.db.encounter_member_tbl().actor_id().delete(actor_id);
+        if ctx
+            .db
+            .encounter_member_tbl()
+            .group_id()
+            .filter(member.group_id)
+            .next()
+            .is_none()
+        {
+            ctx.db.encounter_group_tbl().id().delete(member.group_id);
+        }
+        true
+    }
+}
+
+/// Whether a member `distance_sq` (meters squared) from its home is returning: passing the
+/// leash radius starts it, getting back home ends it.
+fn is_returning(returning: bool, distance_sq: f32, leash_radius: f32) -> bool {
+    if distance_sq > leash_radius * leash_radius {
+        return true;
+    }
+    returning && distance_sq > HOME_REACHED_RADIUS * HOME_REACHED_RADIUS
+}
+
+/// Adds or replaces (by name) an encounter definition. Admin only.
+#[reducer]
+pub fn set_encounter_def(
+    ctx: &ReducerContext,
+    name: String,
+    members: Vec<EncounterMember>,
+    leash_radius: f32,
+) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "set_encounter_def")?;
+    let def = EncounterDefRow::set(ctx, name, members, leash_radius)?;
+    log::info!("Set encounter {} ({})", def.name, def.id);
+    Ok(())
+}
+
+/// Spawns every member of an encounter around `origin` in the admin's current instance, all or
+/// none of them. Admin only.
+#[reducer]
+pub fn spawn_encounter(
+    ctx: &ReducerContext,
+    encounter_id: u32,
+    origin: Vec3,
+) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "spawn_encounter")?;
+    let Some(def) = ctx.db.encounter_def_tbl().id().find(encounter_id) else {
+        return Err("Unable to find encounter".into());
+    };
+
+    let instance_id = sender_instance_id(ctx);
+    let group = def.spawn(ctx, instance_id, origin)?;
+    log::info!(
+        "Spawned encounter {} as group {} in instance {}",
+        def.name,
+        group.id,
+        instance_id
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passing_the_leash_starts_returning() {
+        assert!(!is_returning(false, 24.0 * 24.0, 25.0));
+        assert!(is_returning(false, 26.0 * 26.0, 25.0));
+    }
+
+    #[test]
+    fn returning_lasts_until_home() {
+        // Back inside the leash, e.g. right on its edge, isn't enough.
+        assert!(is_returning(true, 24.9 * 24.9, 25.0));
+        assert!(is_returning(true, 2.0 * 2.0, 25.0));
+        assert!(!is_returning(true, 0.5 * 0.5, 25.0));
+    }
+}
@@ -0,0 +1,77 @@
+use shared::ActorId;
+use spacetimedb::{table, ReducerContext, SpacetimeType, Table, Timestamp};
+
+/// The kinds of notable server-side events worth keeping an audit trail of.
+#[derive(SpacetimeType, Debug, Clone, Copy, PartialEq)]
+pub enum EventKind {
+    /// An actor fell below the kill plane or left the playable area and was moved to a spawn point.
+    OutOfBoundsRecovery,
+    /// An identity was made an admin.
+    AdminGranted,
+    /// An identity's admin permission was removed.
+    AdminRevoked,
+    /// A world static was placed, moved or deleted by an admin.
+    WorldEdited,
+    /// A missing or misconfigured scheduled timer row was repaired by the timer watchdog.
+    TimerRepaired,
+    /// A seeding or migration step of the authored data ran, see [`crate::SchemaVersionRow`].
+    SchemaMigrated,
+    /// The AI tick interval was stretched or restored with the server load, see
+    /// [`crate::LoadSheddingRow`].
+    LoadShedding,
+    /// A cheat reducer of the `dev-tools` feature was used by an admin.
+    DevTool,
+    /// An AFK character was removed from the world after its grace period, see
+    /// [`crate::ActivityRow`].
+    IdleDespawn,
+    /// A world partition was handed to another module or back, see [`crate::PartitionRow`].
+    PartitionOwnerChanged,
+    /// A player freed their character from geometry with `/stuck`, see
+    /// [`crate::request_unstuck`].
+    Unstuck,
+    /// An admin removed a player from the world, see [`crate::kick_identity`].
+    Kicked,
+    /// An admin banned an identity, see [`crate::BanRow`].
+    Banned,
+    /// A ban was lifted by an admin or expired.
+    Unbanned,
+}
+
+/// Append-only log of notable server events for debugging and auditing.
+///
+/// Private on purpose, this is read by operators via SQL and not replicated to clients.
+#[table(name=event_log_tbl)]
+pub struct EventLogRow {
+    #[auto_inc]
+    #[primary_key]
+    pub id: u64,
+
+    pub created_at: Timestamp,
+
+    pub kind: EventKind,
+
+    /// The actor this event is about, if any.
+    pub actor_id: Option<ActorId>,
+
+    /// Human readable details about the event
+    pub message: String,
+}
+
+impl EventLogRow {
+    pub fn record(
+        ctx: &ReducerContext,
+        kind: EventKind,
+        actor_id: Option<ActorId>,
+        message: impl Into<String>,
+    ) {
+        let message = message.into();
+        log::info!("[{:?}] {:?}: {}", kind, actor_id, message);
+        ctx.db.event_log_tbl().insert(Self {
+            id: 0,
+            created_at: ctx.timestamp,
+            kind,
+            actor_id,
+            message,
+        });
+    }
+}
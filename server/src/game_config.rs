@@ -0,0 +1,196 @@
+use crate::{combat::DEFAULT_CRITICAL_HIT_MULTIPLIER, AdminIdentityRow, FallDamageRules};
+use spacetimedb::{reducer, table, ReducerContext, Table, Timestamp, ViewContext};
+
+/// Upper bound of the fall damage rate, a fraction of max health per meter.
+const MAX_FALL_DAMAGE_PER_METER: f32 = 1.0;
+
+/// Upper bound of the critical hit multiplier.
+const MAX_CRITICAL_HIT_MULTIPLIER: f32 = 5.0;
+
+/// Default idle time before a player is flagged AFK.
+const DEFAULT_AFK_TIMEOUT_SECS: u32 = 300;
+
+/// Default time an AFK player keeps its character in the world.
+const DEFAULT_AFK_DESPAWN_GRACE_SECS: Option<u32> = Some(600);
+
+/// Lower bound of the AFK timeout, shorter would flag players reading a tooltip.
+const MIN_AFK_TIMEOUT_SECS: u32 = 30;
+
+/// Default soft cap of the actors per cell, see [`crate::cell_budget`].
+const DEFAULT_MAX_ACTORS_PER_CELL: u32 = 64;
+
+/// Server tuned gameplay rules, a single row.
+///
+/// Private, the server is the only reader. Without the row the built-in defaults apply, see
+/// [`GameConfigRow::get`].
+#[table(name=game_config_tbl)]
+pub struct GameConfigRow {
+    /// Always [`GameConfigRow::ID`].
+    #[primary_key]
+    pub id: u8,
+
+    /// Falls up to this height (meters) don't hurt.
+    pub safe_fall_distance: f32,
+
+    /// Fraction of max health lost per meter fallen past `safe_fall_distance`.
+    pub fall_damage_per_meter: f32,
+
+    /// Idle time (seconds) before a player is flagged AFK, see [`crate::ActivityRow`].
+    pub afk_timeout_secs: u32,
+
+    /// Time (seconds) an AFK player keeps its character in the world before it's removed,
+    /// `None` to never remove it.
+    pub afk_despawn_grace_secs: Option<u32>,
+
+    /// Damage multiplier of critical hits, see [`crate::combat::resolve_attack`].
+    pub critical_hit_multiplier: f32,
+
+    /// Spawns into a cell holding this many actors are redirected, see [`crate::cell_budget`].
+    pub max_actors_per_cell: u32,
+
+    pub updated_at: Timestamp,
+}
+
+impl GameConfigRow {
+    const ID: u8 = 0;
+
+    /// The configured rules, or the defaults when there's no row.
+    pub fn get(ctx: &ViewContext) -> Self {
+        ctx.db
+            .game_config_tbl()
+            .id()
+            .find(Self::ID)
+            .unwrap_or(Self {
+                id: Self::ID,
+                safe_fall_distance: FallDamageRules::DEFAULT.safe_distance,
+                fall_damage_per_meter: FallDamageRules::DEFAULT.damage_per_meter,
+                afk_timeout_secs: DEFAULT_AFK_TIMEOUT_SECS,
+                afk_despawn_grace_secs: DEFAULT_AFK_DESPAWN_GRACE_SECS,
+                critical_hit_multiplier: DEFAULT_CRITICAL_HIT_MULTIPLIER,
+                max_actors_per_cell: DEFAULT_MAX_ACTORS_PER_CELL,
+                updated_at: Timestamp::UNIX_EPOCH,
+            })
+    }
+
+    pub fn fall_damage_rules(&self) -> FallDamageRules {
+        FallDamageRules {
+            safe_distance: self.safe_fall_distance,
+            damage_per_meter: self.fall_damage_per_meter,
+        }
+    }
+}
+
+/// Sets the fall damage rules. Admin only.
+#[reducer]
+pub fn set_fall_damage_rules(
+    ctx: &ReducerContext,
+    safe_fall_distance: f32,
+    fall_damage_per_meter: f32,
+) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "set_fall_damage_rules")?;
+    if !safe_fall_distance.is_finite() || safe_fall_distance < 0.0 {
+        return Err("safe_fall_distance must be a non-negative distance".into());
+    }
+    if !fall_damage_per_meter.is_finite()
+        || !(0.0..=MAX_FALL_DAMAGE_PER_METER).contains(&fall_damage_per_meter)
+    {
+        return Err(format!(
+            "fall_damage_per_meter must be in [0, {MAX_FALL_DAMAGE_PER_METER}]"
+        ));
+    }
+
+    let row = GameConfigRow {
+        safe_fall_distance,
+        fall_damage_per_meter,
+        updated_at: ctx.timestamp,
+        ..GameConfigRow::get(&ctx.as_read_only())
+    };
+    upsert(ctx, row);
+    Ok(())
+}
+
+/// Sets when idle players are flagged AFK and whether their characters are removed from the
+/// world after a grace period. Admin only.
+#[reducer]
+pub fn set_afk_policy(
+    ctx: &ReducerContext,
+    afk_timeout_secs: u32,
+    afk_despawn_grace_secs: Option<u32>,
+) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "set_afk_policy")?;
+    if afk_timeout_secs < MIN_AFK_TIMEOUT_SECS {
+        return Err(format!(
+            "afk_timeout_secs must be at least {MIN_AFK_TIMEOUT_SECS}"
+        ));
+    }
+
+    let row = GameConfigRow {
+        afk_timeout_secs,
+        afk_despawn_grace_secs,
+        updated_at: ctx.timestamp,
+        ..GameConfigRow::get(&ctx.as_read_only())
+    };
+    upsert(ctx, row);
+    Ok(())
+}
+
+/// Sets the damage multiplier of critical hits. Admin only.
+#[reducer]
+pub fn set_critical_hit_multiplier(ctx: &ReducerContext, multiplier: f32) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "set_critical_hit_multiplier")?;
+    if !multiplier.is_finite() || !(1.0..=MAX_CRITICAL_HIT_MULTIPLIER).contains(&multiplier) {
+        return Err(format!(
+            "multiplier must be in [1, {MAX_CRITICAL_HIT_MULTIPLIER}]"
+        ));
+    }
+
+    let row = GameConfigRow {
+        critical_hit_multiplier: multiplier,
+        updated_at: ctx.timestamp,
+        ..GameConfigRow::get(&ctx.as_read_only())
+    };
+    upsert(ctx, row);
+    Ok(())
+}
+
+/// Sets the soft cap of the actors per cell spawns are redirected at. Admin only.
+#[reducer]
+pub fn set_max_actors_per_cell(
+    ctx: &ReducerContext,
+    max_actors_per_cell: u32,
+) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "set_max_actors_per_cell")?;
+    if max_actors_per_cell == 0 {
+        return Err("max_actors_per_cell must be at least 1".into());
+    }
+
+    let row = GameConfigRow {
+        max_actors_per_cell,
+        updated_at: ctx.timestamp,
+        ..GameConfigRow::get(&ctx.as_read_only())
+    };
+    upsert(ctx, row);
+    Ok(())
+}
+
+/// Restores the built-in defaults of every rule. Admin only.
+#[reducer]
+pub fn reset_game_config(ctx: &ReducerContext) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "reset_game_config")?;
+    ctx.db.game_config_tbl().id().delete(GameConfigRow::ID);
+    Ok(())
+}
+
+fn upsert(ctx: &ReducerContext, row: GameConfigRow) {
+    if ctx
+        .db
+        .game_config_tbl()
+        .id()
+        .find(GameConfigRow::ID)
+        .is_some()
+    {
+        ctx.db.game_config_tbl().id().update(row);
+    } else {
+        ctx.db.game_config_tbl().insert(row);
+    }
+}
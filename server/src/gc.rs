@@ -0,0 +1,339 @@
+use crate::{
+    actor_tbl, airborne_tbl, character_instance_tbl, cooldown_tbl, corpse_tbl, duel_tbl,
+    dummy_stats_tbl, emote_tbl, encounter_member_tbl, experience_tbl, hazard_occupant_tbl,
+    health_tbl, level_tbl, mana_tbl, monster_instance_tbl, movement_state_tbl,
+    partition_handoff_tbl, primary_stats_tbl, regen_stats_tbl, scripted_path_tbl,
+    secondary_stats_tbl, speed_modifier_tbl, target_tbl, transform_keyframe_tbl, transform_tbl,
+    EncounterMemberRow, TimingStatsRow, WriteStats,
+};
+use shared::ActorId;
+use spacetimedb::{reducer, table, ReducerContext, ScheduleAt, Table, Timestamp};
+use std::{collections::HashSet, time::Duration};
+
+/// How often the orphan GC runs, orphans only come from bugs so this can be rare.
+pub(crate) const GC_INTERVAL_MILLIS: u64 = 60_000;
+
+/// Results of the orphan GC, one row per scanned table.
+///
+/// Private, operators spot leaking despawn paths through the `gc_deleted` [`crate::MetricRow`]s
+/// or via SQL. Anything non-zero here is a bug in the code that should have deleted those rows
+/// (see [`crate::ActorRow::despawn`]).
+#[table(name=gc_stats_tbl)]
+pub struct GcStatsRow {
+    /// The name of the scanned table, e.g. `transform_tbl`.
+    #[primary_key]
+    pub table_name: String,
+
+    pub last_run_at: Timestamp,
+
+    /// Orphaned rows deleted during the last run.
+    pub last_deleted: u32,
+
+    /// Orphaned rows deleted across all runs.
+    pub total_deleted: u64,
+}
+
+impl GcStatsRow {
+    /// Upserts the stats row for the given table with the results of this run.
+    fn record(ctx: &ReducerContext, table_name: &str, deleted: u32) {
+        match ctx
+            .db
+            .gc_stats_tbl()
+            .table_name()
+            .find(table_name.to_string())
+        {
+            Some(mut row) => {
+                row.last_run_at = ctx.timestamp;
+                row.last_deleted = deleted;
+                row.total_deleted = row.total_deleted.saturating_add(deleted as u64);
+                ctx.db.gc_stats_tbl().table_name().update(row);
+            }
+            None => {
+                ctx.db.gc_stats_tbl().insert(Self {
+                    table_name: table_name.to_string(),
+                    last_run_at: ctx.timestamp,
+                    last_deleted: deleted,
+                    total_deleted: deleted as u64,
+                });
+            }
+        }
+    }
+}
+
+#[table(name = gc_timer, scheduled(gc_reducer))]
+pub struct GcTimer {
+    #[primary_key]
+    #[auto_inc]
+    pub scheduled_id: u64,
+    pub scheduled_at: ScheduleAt,
+}
+
+pub fn init_gc(ctx: &ReducerContext) {
+    ctx.db.gc_timer().scheduled_id().delete(1);
+    ctx.db.gc_timer().insert(GcTimer {
+        scheduled_id: 1,
+        scheduled_at: Duration::from_millis(GC_INTERVAL_MILLIS).into(),
+    });
+    log::info!("init gc");
+}
+
+/// The ids that don't belong to an existing actor.
+fn orphans(actors: &HashSet<ActorId>, ids: impl Iterator<Item = ActorId>) -> Vec<ActorId> {
+    ids.filter(|id| !actors.contains(id)).collect()
+}
+
+/// Deletes the rows with the given keys, recording the outcome for `table_name`.
+fn prune<K>(
+    ctx: &ReducerContext,
+    table_name: &str,
+    orphans: Vec<K>,
+    delete: impl Fn(K) -> bool,
+    write_stats: &mut WriteStats,
+) {
+    let mut deleted = 0;
+    for key in orphans {
+        if delete(key) {
+            deleted += 1;
+        }
+    }
+    if deleted > 0 {
+        log::warn!("GC: deleted {} orphaned rows from {}", deleted, table_name);
+    }
+    write_stats.record(deleted > 0);
+    GcStatsRow::record(ctx, table_name, deleted);
+}
+
+/// Deletes per-actor rows whose actor no longer exists.
+///
+/// **Performance & Cost**: O(N) scan of every per-actor table, hence the long interval.
+#[reducer]
+fn gc_reducer(ctx: &ReducerContext, _timer: GcTimer) -> Result<(), String> {
+    if ctx.sender != ctx.identity() {
+        log::error!("`gc_reducer` may not be invoked by clients.");
+        return Err("`gc_reducer` may not be invoked by clients.".into());
+    }
+
+    let actors: HashSet<ActorId> = ctx.db.actor_tbl().iter().map(|actor| actor.id).collect();
+    let db = &ctx.db;
+    let mut write_stats = WriteStats::default();
+
+    prune(
+        ctx,
+        "transform_tbl",
+        orphans(&actors, db.transform_tbl().iter().map(|row| row.actor_id)),
+        |id| db.transform_tbl().actor_id().delete(id),
+        &mut write_stats,
+    );
+    prune(
+        ctx,
+        "transform_keyframe_tbl",
+        orphans(
+            &actors,
+            db.transform_keyframe_tbl().iter().map(|row| row.actor_id),
+        ),
+        |id| db.transform_keyframe_tbl().actor_id().delete(id),
+        &mut write_stats,
+    );
+    prune(
+        ctx,
+        "movement_state_tbl",
+        orphans(
+            &actors,
+            db.movement_state_tbl().iter().map(|row| row.actor_id),
+        ),
+        |id| db.movement_state_tbl().actor_id().delete(id),
+        &mut write_stats,
+    );
+    prune(
+        ctx,
+        "primary_stats_tbl",
+        orphans(
+            &actors,
+            db.primary_stats_tbl().iter().map(|row| row.actor_id),
+        ),
+        |id| db.primary_stats_tbl().actor_id().delete(id),
+        &mut write_stats,
+    );
+    prune(
+        ctx,
+        "secondary_stats_tbl",
+        orphans(
+            &actors,
+            db.secondary_stats_tbl().iter().map(|row| row.actor_id),
+        ),
+        |id| db.secondary_stats_tbl().actor_id().delete(id),
+        &mut write_stats,
+    );
+    prune(
+        ctx,
+        "regen_stats_tbl",
+        orphans(&actors, db.regen_stats_tbl().iter().map(|row| row.actor_id)),
+        |id| db.regen_stats_tbl().actor_id().delete(id),
+        &mut write_stats,
+    );
+    prune(
+        ctx,
+        "health_tbl",
+        orphans(&actors, db.health_tbl().iter().map(|row| row.actor_id)),
+        |id| db.health_tbl().actor_id().delete(id),
+        &mut write_stats,
+    );
+    prune(
+        ctx,
+        "mana_tbl",
+        orphans(&actors, db.mana_tbl().iter().map(|row| row.actor_id)),
+        |id| db.mana_tbl().actor_id().delete(id),
+        &mut write_stats,
+    );
+    prune(
+        ctx,
+        "experience_tbl",
+        orphans(&actors, db.experience_tbl().iter().map(|row| row.actor_id)),
+        |id| db.experience_tbl().actor_id().delete(id),
+        &mut write_stats,
+    );
+    prune(
+        ctx,
+        "level_tbl",
+        orphans(&actors, db.level_tbl().iter().map(|row| row.actor_id)),
+        |id| db.level_tbl().actor_id().delete(id),
+        &mut write_stats,
+    );
+    prune(
+        ctx,
+        "corpse_tbl",
+        orphans(&actors, db.corpse_tbl().iter().map(|row| row.actor_id)),
+        |id| db.corpse_tbl().actor_id().delete(id),
+        &mut write_stats,
+    );
+    prune(
+        ctx,
+        "monster_instance_tbl",
+        orphans(
+            &actors,
+            db.monster_instance_tbl().iter().map(|row| row.actor_id),
+        ),
+        |id| db.monster_instance_tbl().actor_id().delete(id),
+        &mut write_stats,
+    );
+    prune(
+        ctx,
+        "character_instance_tbl",
+        orphans(
+            &actors,
+            db.character_instance_tbl().iter().map(|row| row.actor_id),
+        ),
+        |id| db.character_instance_tbl().actor_id().delete(id),
+        &mut write_stats,
+    );
+    // Targets are also orphaned when the targeted actor is gone.
+    prune(
+        ctx,
+        "target_tbl",
+        db.target_tbl()
+            .iter()
+            .filter(|row| !actors.contains(&row.actor_id) || !actors.contains(&row.target))
+            .map(|row| row.actor_id)
+            .collect(),
+        |id| db.target_tbl().actor_id().delete(id),
+        &mut write_stats,
+    );
+    prune(
+        ctx,
+        "cooldown_tbl",
+        db.cooldown_tbl()
+            .iter()
+            .filter(|row| !actors.contains(&row.actor_id))
+            .map(|row| row.id)
+            .collect(),
+        |id| db.cooldown_tbl().id().delete(id),
+        &mut write_stats,
+    );
+    // Duels are orphaned as soon as either duelist is gone.
+    prune(
+        ctx,
+        "duel_tbl",
+        db.duel_tbl()
+            .iter()
+            .filter(|row| !actors.contains(&row.challenger) || !actors.contains(&row.opponent))
+            .map(|row| row.id)
+            .collect(),
+        |id| db.duel_tbl().id().delete(id),
+        &mut write_stats,
+    );
+    prune(
+        ctx,
+        "dummy_stats_tbl",
+        orphans(&actors, db.dummy_stats_tbl().iter().map(|row| row.actor_id)),
+        |id| db.dummy_stats_tbl().actor_id().delete(id),
+        &mut write_stats,
+    );
+    prune(
+        ctx,
+        "emote_tbl",
+        orphans(&actors, db.emote_tbl().iter().map(|row| row.actor_id)),
+        |id| db.emote_tbl().actor_id().delete(id),
+        &mut write_stats,
+    );
+    prune(
+        ctx,
+        "speed_modifier_tbl",
+        orphans(
+            &actors,
+            db.speed_modifier_tbl().iter().map(|row| row.actor_id),
+        ),
+        |id| db.speed_modifier_tbl().actor_id().delete(id),
+        &mut write_stats,
+    );
+    prune(
+        ctx,
+        "airborne_tbl",
+        orphans(&actors, db.airborne_tbl().iter().map(|row| row.actor_id)),
+        |id| db.airborne_tbl().actor_id().delete(id),
+        &mut write_stats,
+    );
+    prune(
+        ctx,
+        "hazard_occupant_tbl",
+        orphans(
+            &actors,
+            db.hazard_occupant_tbl().iter().map(|row| row.actor_id),
+        ),
+        |id| db.hazard_occupant_tbl().actor_id().delete(id),
+        &mut write_stats,
+    );
+    prune(
+        ctx,
+        "partition_handoff_tbl",
+        orphans(
+            &actors,
+            db.partition_handoff_tbl().iter().map(|row| row.actor_id),
+        ),
+        |id| db.partition_handoff_tbl().actor_id().delete(id),
+        &mut write_stats,
+    );
+    prune(
+        ctx,
+        "scripted_path_tbl",
+        orphans(
+            &actors,
+            db.scripted_path_tbl().iter().map(|row| row.actor_id),
+        ),
+        |id| db.scripted_path_tbl().actor_id().delete(id),
+        &mut write_stats,
+    );
+    // Through the row's own delete, so a group left without members goes with it.
+    prune(
+        ctx,
+        "encounter_member_tbl",
+        orphans(
+            &actors,
+            db.encounter_member_tbl().iter().map(|row| row.actor_id),
+        ),
+        |id| EncounterMemberRow::delete_for_actor(ctx, id),
+        &mut write_stats,
+    );
+
+    TimingStatsRow::record(ctx, TimingStatsRow::GC_TICK, write_stats);
+    Ok(())
+}
@@ -0,0 +1,387 @@
+use crate::{
+    character_instance_tbl, get_view_aoi_actors, is_in_aoi, CharacterInstanceRow, TargetRow,
+};
+use shared::ActorId;
+use spacetimedb::{reducer, table, ReducerContext, SpacetimeType, Table, Timestamp, ViewContext};
+
+/// Ranks within a guild, ordered from lowest to highest.
+#[derive(SpacetimeType, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum GuildRank {
+    Member,
+    /// Can invite and kick members.
+    Officer,
+    /// Can do anything, including changing ranks. There's exactly one per guild.
+    Leader,
+}
+
+impl GuildRank {
+    pub fn can_invite(self) -> bool {
+        self >= GuildRank::Officer
+    }
+
+    /// Officers can kick members, the leader can kick anyone.
+    pub fn can_kick(self, other: GuildRank) -> bool {
+        self >= GuildRank::Officer && self > other
+    }
+
+    pub fn can_set_rank(self) -> bool {
+        self == GuildRank::Leader
+    }
+}
+
+/// A guild, public so nameplates can show the tag of any member.
+#[table(name=guild_tbl, public)]
+pub struct GuildRow {
+    #[auto_inc]
+    #[primary_key]
+    pub id: u32,
+
+    #[unique]
+    pub name: String,
+
+    /// Short tag shown on nameplates, e.g. `AV`.
+    #[unique]
+    pub tag: String,
+
+    pub created_at: Timestamp,
+}
+
+/// The persistence layer for guild membership, keyed by character so it outlives sessions.
+///
+/// A character is in at most one guild.
+#[table(name=guild_member_tbl)]
+pub struct GuildMemberRow {
+    #[primary_key]
+    pub character_id: u32,
+
+    #[index(btree)]
+    pub guild_id: u32,
+
+    pub rank: GuildRank,
+
+    pub joined_at: Timestamp,
+}
+
+impl GuildMemberRow {
+    pub fn find(ctx: &ViewContext, character_id: u32) -> Option<Self> {
+        ctx.db.guild_member_tbl().character_id().find(character_id)
+    }
+}
+
+/// A pending invite into a guild, deleted once the character accepts or declines.
+#[table(name=guild_invite_tbl)]
+pub struct GuildInviteRow {
+    #[auto_inc]
+    #[primary_key]
+    pub id: u64,
+
+    #[index(btree)]
+    pub guild_id: u32,
+
+    /// The invited character.
+    #[index(btree)]
+    pub character_id: u32,
+
+    /// The character that sent the invite.
+    pub invited_by: u32,
+
+    pub created_at: Timestamp,
+}
+
+/// The sender's active character, see [`CharacterInstanceRow`].
+fn sender_character(ctx: &ReducerContext, action: &str) -> Result<CharacterInstanceRow, String> {
+    ctx.db
+        .character_instance_tbl()
+        .identity()
+        .find(ctx.sender)
+        .ok_or_else(|| {
+            log::error!("{}: no active character for {:?}", action, ctx.sender);
+            "No active character".into()
+        })
+}
+
+/// The sender's active character and its guild membership.
+fn sender_membership(ctx: &ReducerContext, action: &str) -> Result<GuildMemberRow, String> {
+    let ci = sender_character(ctx, action)?;
+    GuildMemberRow::find(&ctx.as_read_only(), ci.character_id)
+        .ok_or_else(|| "Not in a guild".into())
+}
+
+/// Deletes a guild with its members and pending invites.
+fn disband(ctx: &ReducerContext, guild_id: u32) {
+    ctx.db.guild_member_tbl().guild_id().delete(guild_id);
+    ctx.db.guild_invite_tbl().guild_id().delete(guild_id);
+    ctx.db.guild_tbl().id().delete(guild_id);
+    log::info!("Guild {} disbanded", guild_id);
+}
+
+fn validate_guild_name(name: &str) -> Result<(), String> {
+    let length = name.chars().count();
+    if !(3..=32).contains(&length) {
+        return Err("Guild name must be 3–32 characters".into());
+    }
+    if !name.chars().all(|c| c.is_alphanumeric() || c == ' ') || name.trim() != name {
+        return Err("Guild name must be alphanumeric".into());
+    }
+    Ok(())
+}
+
+fn validate_guild_tag(tag: &str) -> Result<(), String> {
+    let length = tag.chars().count();
+    if !(2..=5).contains(&length) || !tag.chars().all(|c| c.is_alphanumeric()) {
+        return Err("Guild tag must be 2–5 alphanumeric characters".into());
+    }
+    Ok(())
+}
+
+/// Creates a guild led by the player's active character.
+#[reducer]
+pub fn create_guild(ctx: &ReducerContext, name: String, tag: String) -> Result<(), String> {
+    let ci = sender_character(ctx, "create_guild")?;
+    validate_guild_name(&name)?;
+    validate_guild_tag(&tag)?;
+    if GuildMemberRow::find(&ctx.as_read_only(), ci.character_id).is_some() {
+        return Err("Already in a guild".into());
+    }
+    if ctx.db.guild_tbl().name().find(&name).is_some() {
+        return Err("Guild name is taken".into());
+    }
+    if ctx.db.guild_tbl().tag().find(&tag).is_some() {
+        return Err("Guild tag is taken".into());
+    }
+
+    let guild = ctx.db.guild_tbl().insert(GuildRow {
+        id: 0,
+        name,
+        tag,
+        created_at: ctx.timestamp,
+    });
+    ctx.db.guild_member_tbl().insert(GuildMemberRow {
+        character_id: ci.character_id,
+        guild_id: guild.id,
+        rank: GuildRank::Leader,
+        joined_at: ctx.timestamp,
+    });
+    // Invites into other guilds are moot now.
+    ctx.db
+        .guild_invite_tbl()
+        .character_id()
+        .delete(ci.character_id);
+    Ok(())
+}
+
+/// Invites the given character, or the current target (see [`TargetRow`]), into the guild.
+/// Officers and up only.
+#[reducer]
+pub fn invite_to_guild(ctx: &ReducerContext, target: Option<ActorId>) -> Result<(), String> {
+    let ci = sender_character(ctx, "invite_to_guild")?;
+    let view_ctx = ctx.as_read_only();
+    let Some(member) = GuildMemberRow::find(&view_ctx, ci.character_id) else {
+        return Err("Not in a guild".into());
+    };
+    if !member.rank.can_invite() {
+        return Err("Your rank can't invite".into());
+    }
+    let Some(target) = TargetRow::resolve(&view_ctx, ci.actor_id, target) else {
+        return Err("No target".into());
+    };
+    let Some(invitee) = CharacterInstanceRow::find_by_actor_id(&view_ctx, target) else {
+        return Err("Only characters can be invited".into());
+    };
+    if !is_in_aoi(&view_ctx, ci.actor_id, target) {
+        return Err("Target is not in range".into());
+    }
+    if GuildMemberRow::find(&view_ctx, invitee.character_id).is_some() {
+        return Err("Target is already in a guild".into());
+    }
+    if ctx
+        .db
+        .guild_invite_tbl()
+        .character_id()
+        .filter(invitee.character_id)
+        .any(|invite| invite.guild_id == member.guild_id)
+    {
+        return Err("Target is already invited".into());
+    }
+
+    ctx.db.guild_invite_tbl().insert(GuildInviteRow {
+        id: 0,
+        guild_id: member.guild_id,
+        character_id: invitee.character_id,
+        invited_by: ci.character_id,
+        created_at: ctx.timestamp,
+    });
+    Ok(())
+}
+
+/// Accepts an invite into the given guild, any other pending invites are dropped.
+#[reducer]
+pub fn accept_guild_invite(ctx: &ReducerContext, guild_id: u32) -> Result<(), String> {
+    let ci = sender_character(ctx, "accept_guild_invite")?;
+    if !ctx
+        .db
+        .guild_invite_tbl()
+        .character_id()
+        .filter(ci.character_id)
+        .any(|invite| invite.guild_id == guild_id)
+    {
+        return Err("No invite from that guild".into());
+    }
+    if GuildMemberRow::find(&ctx.as_read_only(), ci.character_id).is_some() {
+        return Err("Already in a guild".into());
+    }
+
+    ctx.db
+        .guild_invite_tbl()
+        .character_id()
+        .delete(ci.character_id);
+    ctx.db.guild_member_tbl().insert(GuildMemberRow {
+        character_id: ci.character_id,
+        guild_id,
+        rank: GuildRank::Member,
+        joined_at: ctx.timestamp,
+    });
+    Ok(())
+}
+
+/// Declines an invite into the given guild.
+#[reducer]
+pub fn decline_guild_invite(ctx: &ReducerContext, guild_id: u32) -> Result<(), String> {
+    let ci = sender_character(ctx, "decline_guild_invite")?;
+    let invites: Vec<GuildInviteRow> = ctx
+        .db
+        .guild_invite_tbl()
+        .character_id()
+        .filter(ci.character_id)
+        .filter(|invite| invite.guild_id == guild_id)
+        .collect();
+    if invites.is_empty() {
+        return Err("No invite from that guild".into());
+    }
+    for invite in invites {
+        ctx.db.guild_invite_tbl().id().delete(invite.id);
+    }
+    Ok(())
+}
+
+/// Removes a character from the guild, the kicker has to outrank it (see [`GuildRank::can_kick`]).
+#[reducer]
+pub fn kick_from_guild(ctx: &ReducerContext, character_id: u32) -> Result<(), String> {
+    let member = sender_membership(ctx, "kick_from_guild")?;
+    let Some(kicked) = GuildMemberRow::find(&ctx.as_read_only(), character_id)
+        .filter(|kicked| kicked.guild_id == member.guild_id)
+    else {
+        return Err("Not a member of your guild".into());
+    };
+    if !member.rank.can_kick(kicked.rank) {
+        return Err("Your rank can't kick that member".into());
+    }
+
+    ctx.db
+        .guild_member_tbl()
+        .character_id()
+        .delete(character_id);
+    Ok(())
+}
+
+/// Leaves the guild. A leaving leader hands over to the highest ranked, longest standing member,
+/// the last member leaving disbands the guild.
+#[reducer]
+pub fn leave_guild(ctx: &ReducerContext) -> Result<(), String> {
+    let member = sender_membership(ctx, "leave_guild")?;
+    ctx.db
+        .guild_member_tbl()
+        .character_id()
+        .delete(member.character_id);
+
+    let successor = ctx
+        .db
+        .guild_member_tbl()
+        .guild_id()
+        .filter(member.guild_id)
+        .max_by(|a, b| a.rank.cmp(&b.rank).then(b.joined_at.cmp(&a.joined_at)));
+    match successor {
+        None => disband(ctx, member.guild_id),
+        Some(mut successor) if member.rank == GuildRank::Leader => {
+            successor.rank = GuildRank::Leader;
+            ctx.db.guild_member_tbl().character_id().update(successor);
+        }
+        Some(_) => {}
+    }
+    Ok(())
+}
+
+/// Changes the rank of a member. Handing over leadership demotes the current leader to officer.
+/// Leader only.
+#[reducer]
+pub fn set_guild_rank(
+    ctx: &ReducerContext,
+    character_id: u32,
+    rank: GuildRank,
+) -> Result<(), String> {
+    let mut member = sender_membership(ctx, "set_guild_rank")?;
+    if !member.rank.can_set_rank() {
+        return Err("Your rank can't change ranks".into());
+    }
+    if character_id == member.character_id {
+        return Err("Can't change your own rank".into());
+    }
+    let Some(mut promoted) = GuildMemberRow::find(&ctx.as_read_only(), character_id)
+        .filter(|promoted| promoted.guild_id == member.guild_id)
+    else {
+        return Err("Not a member of your guild".into());
+    };
+
+    promoted.rank = rank;
+    ctx.db.guild_member_tbl().character_id().update(promoted);
+    if rank == GuildRank::Leader {
+        member.rank = GuildRank::Officer;
+        ctx.db.guild_member_tbl().character_id().update(member);
+    }
+    Ok(())
+}
+
+/// Finds the guild memberships of the characters within the AOI (for guild tags on nameplates)
+/// and the whole roster of the player's own guild.
+/// Primary key of `character_id`
+#[spacetimedb::view(name = guild_member_view, public)]
+pub fn guild_member_view(ctx: &ViewContext) -> Vec<GuildMemberRow> {
+    let Some(ci) = CharacterInstanceRow::find_by_identity(ctx) else {
+        return vec![];
+    };
+
+    let mut members: Vec<GuildMemberRow> = GuildMemberRow::find(ctx, ci.character_id)
+        .map(|own| {
+            ctx.db
+                .guild_member_tbl()
+                .guild_id()
+                .filter(own.guild_id)
+                .collect()
+        })
+        .unwrap_or_default();
+    if let Some(actors) = get_view_aoi_actors(ctx) {
+        members.extend(
+            actors
+                .filter_map(|ms| CharacterInstanceRow::find_by_actor_id(ctx, ms.actor_id))
+                .filter_map(|ci| GuildMemberRow::find(ctx, ci.character_id)),
+        );
+    }
+    members.sort_unstable_by_key(|member| member.character_id);
+    members.dedup_by_key(|member| member.character_id);
+    members
+}
+
+/// Finds the pending guild invites of the player's active character.
+/// Primary key of `id`
+#[spacetimedb::view(name = guild_invite_view, public)]
+pub fn guild_invite_view(ctx: &ViewContext) -> Vec<GuildInviteRow> {
+    let Some(ci) = CharacterInstanceRow::find_by_identity(ctx) else {
+        return vec![];
+    };
+
+    ctx.db
+        .guild_invite_tbl()
+        .character_id()
+        .filter(ci.character_id)
+        .collect()
+}
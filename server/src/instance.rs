@@ -0,0 +1,335 @@
+use crate::{
+    actor_tbl, character_instance_tbl, current_server_tick, insert_instance_base,
+    movement_state_tbl, ActorRow, AdminIdentityRow, CharacterInstanceRow, DomainEvent,
+    DomainEventRow, EventKind, EventLogRow, MoveIntentData, SpawnPointRow, SpectatorRow, TargetRow,
+    TransformRow, Vec3,
+};
+use shared::{dist_sq_xz, InstanceId};
+use spacetimedb::{reducer, table, ReducerContext, Table, Timestamp, ViewContext};
+
+/// A separate copy of the world, e.g. the overworld or a dungeon.
+///
+/// Not to be confused with the per-kind actor instance tables ([`CharacterInstanceRow`],
+/// [`crate::MonsterInstanceRow`]). Statics, spawn points and actors carry the id of the instance
+/// they're in and only interact within it. Cells are shared, so AOI views filter actors by the
+/// viewer's instance, see [`ActorRow::is_visible_to`].
+#[table(name=instance_tbl, public)]
+pub struct InstanceRow {
+    #[primary_key]
+    pub id: InstanceId,
+
+    pub name: String,
+
+    pub created_at: Timestamp,
+}
+
+impl InstanceRow {
+    /// The instance everything starts in, it always exists.
+    pub const OVERWORLD: InstanceId = 0;
+
+    pub fn find(ctx: &ViewContext, id: InstanceId) -> Option<Self> {
+        ctx.db.instance_tbl().id().find(id)
+    }
+
+    /// Makes sure the overworld row exists, other instances are left alone.
+    pub fn regenerate(ctx: &ReducerContext) {
+        if ctx.db.instance_tbl().id().find(Self::OVERWORLD).is_some() {
+            return;
+        }
+        ctx.db.instance_tbl().insert(Self {
+            id: Self::OVERWORLD,
+            name: "Overworld".into(),
+            created_at: ctx.timestamp,
+        });
+    }
+}
+
+/// A character allowed into an instance from anywhere, e.g. the members of a dungeon run.
+#[table(name=instance_member_tbl)]
+pub struct InstanceMemberRow {
+    #[auto_inc]
+    #[primary_key]
+    pub id: u32,
+
+    #[index(btree)]
+    pub instance_id: InstanceId,
+
+    pub character_id: u32,
+}
+
+impl InstanceMemberRow {
+    /// **Performance & Cost**: O(M) over the instance's members
+    pub fn is_member(ctx: &ReducerContext, instance_id: InstanceId, character_id: u32) -> bool {
+        ctx.db
+            .instance_member_tbl()
+            .instance_id()
+            .filter(instance_id)
+            .any(|member| member.character_id == character_id)
+    }
+}
+
+/// A spot in one instance leading into another, characters within `radius` meters (planar) of it
+/// may enter the other instance.
+#[table(name=instance_entrance_tbl)]
+pub struct InstanceEntranceRow {
+    #[auto_inc]
+    #[primary_key]
+    pub id: u32,
+
+    /// The instance the entrance is in.
+    #[index(btree)]
+    pub instance_id: InstanceId,
+
+    /// The instance it leads into.
+    pub to_instance_id: InstanceId,
+
+    pub translation: Vec3,
+    pub radius: f32,
+}
+
+impl InstanceEntranceRow {
+    /// Whether an entrance in `instance_id` leads into `to_instance_id` from `translation`.
+    ///
+    /// **Performance & Cost**: O(E) over the instance's entrances
+    pub fn is_near(
+        ctx: &ReducerContext,
+        instance_id: InstanceId,
+        to_instance_id: InstanceId,
+        translation: Vec3,
+    ) -> bool {
+        ctx.db
+            .instance_entrance_tbl()
+            .instance_id()
+            .filter(instance_id)
+            .any(|entrance| {
+                entrance.to_instance_id == to_instance_id
+                    && dist_sq_xz(entrance.translation.into(), translation.into())
+                        <= entrance.radius * entrance.radius
+            })
+    }
+}
+
+/// The instance the sender spectates or their active character is in for views, the overworld
+/// without either.
+///
+/// **Performance & Cost**: O(1), up to three index seeks
+pub fn view_instance_id(ctx: &ViewContext) -> InstanceId {
+    if let Some(spectator) = SpectatorRow::find_by_identity(ctx) {
+        return spectator.instance_id;
+    }
+    CharacterInstanceRow::find_by_identity(ctx)
+        .and_then(|ci| ActorRow::find(ctx, ci.actor_id))
+        .map(|actor| actor.instance_id)
+        .unwrap_or(InstanceRow::OVERWORLD)
+}
+
+/// The reducer side counterpart of [`view_instance_id`].
+pub fn sender_instance_id(ctx: &ReducerContext) -> InstanceId {
+    view_instance_id(&ctx.as_read_only())
+}
+
+/// Creates a new, empty instance with world borders, a ground plane and a spawn point at its
+/// center. Statics are added with `place_static` from within the instance. Admin only.
+#[reducer]
+pub fn create_instance(ctx: &ReducerContext, name: String) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "create_instance")?;
+    if name.trim().is_empty() {
+        return Err("Instance name can't be empty".into());
+    }
+
+    // Not auto_inc, the overworld is always 0.
+    let id = ctx
+        .db
+        .instance_tbl()
+        .iter()
+        .map(|row| row.id)
+        .max()
+        .unwrap_or(InstanceRow::OVERWORLD)
+        .checked_add(1)
+        .ok_or("Out of instance ids")?;
+    ctx.db.instance_tbl().insert(InstanceRow {
+        id,
+        name,
+        created_at: ctx.timestamp,
+    });
+    insert_instance_base(ctx, id);
+    SpawnPointRow::insert(ctx, id, Vec3::new(0.0, 2.0, 0.0));
+
+    EventLogRow::record(
+        ctx,
+        EventKind::WorldEdited,
+        None,
+        format!("{:?} created instance {}", ctx.sender, id),
+    );
+    Ok(())
+}
+
+/// Moves the player's active character into the given instance, at the spawn point nearest to
+/// where it currently is.
+///
+/// Only members of the instance (see [`InstanceMemberRow`]), characters standing at an entrance
+/// to it (see [`InstanceEntranceRow`]) and admins get in. The overworld is open to everyone, so
+/// nobody is stuck in an instance.
+///
+/// Targets are cleared both ways, the character is out of everyone's reach.
+#[reducer]
+pub fn enter_instance(ctx: &ReducerContext, instance_id: InstanceId) -> Result<(), String> {
+    let Some(ci) = ctx.db.character_instance_tbl().identity().find(ctx.sender) else {
+        log::error!("enter_instance: no active character for {:?}", ctx.sender);
+        return Err("No active character".into());
+    };
+    let actor_id = ci.actor_id;
+    let view_ctx = ctx.as_read_only();
+    if ActorRow::is_dead(&view_ctx, actor_id) {
+        return Err("Dead actors can't change instances".into());
+    }
+    if InstanceRow::find(&view_ctx, instance_id).is_none() {
+        return Err("Unknown instance".into());
+    }
+    let (Some(mut actor), Some(transform)) = (
+        ActorRow::find(&view_ctx, actor_id),
+        TransformRow::find(ctx, actor_id),
+    ) else {
+        log::error!("enter_instance: no actor or transform for {}", actor_id);
+        return Err("No transform for actor".into());
+    };
+    if actor.instance_id == instance_id {
+        return Err("Already in that instance".into());
+    }
+    let allowed = instance_id == InstanceRow::OVERWORLD
+        || InstanceMemberRow::is_member(ctx, instance_id, ci.character_id)
+        || InstanceEntranceRow::is_near(ctx, actor.instance_id, instance_id, transform.translation)
+        || AdminIdentityRow::is_admin(ctx, ctx.sender);
+    if !allowed {
+        return Err("Not allowed into that instance".into());
+    }
+    let Some(spawn_point) = SpawnPointRow::nearest(ctx, instance_id, transform.translation) else {
+        log::error!("enter_instance: no spawn point in instance {}", instance_id);
+        return Err("Instance has no spawn point".into());
+    };
+
+    let from = actor.instance_id;
+    actor.instance_id = instance_id;
+    ctx.db.actor_tbl().id().update(actor);
+    TargetRow::delete_for_actor(ctx, actor_id);
+    transform.update(
+        ctx,
+        current_server_tick(ctx),
+        spawn_point.translation,
+        transform.yaw,
+    );
+    if let Some(mut movement_state) = ctx.db.movement_state_tbl().actor_id().find(actor_id) {
+        movement_state.move_intent = MoveIntentData::None;
+        // Start falling so the next tick snaps the actor to the ground at the spawn point.
+        movement_state.vertical_velocity = -1;
+        movement_state.should_move = true;
+        movement_state.update_from_self(ctx);
+    }
+
+    DomainEventRow::publish(
+        ctx,
+        DomainEvent::InstanceChanged {
+            actor_id,
+            from,
+            to: instance_id,
+        },
+    );
+    log::info!(
+        "Actor {} moved from instance {} to {}",
+        actor_id,
+        from,
+        instance_id
+    );
+    Ok(())
+}
+
+/// Lets a character into an instance from anywhere, or takes that away. Admin only.
+#[reducer]
+pub fn set_instance_member(
+    ctx: &ReducerContext,
+    instance_id: InstanceId,
+    character_id: u32,
+    member: bool,
+) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "set_instance_member")?;
+    if InstanceRow::find(&ctx.as_read_only(), instance_id).is_none() {
+        return Err("Unknown instance".into());
+    }
+    let existing: Vec<_> = ctx
+        .db
+        .instance_member_tbl()
+        .instance_id()
+        .filter(instance_id)
+        .filter(|row| row.character_id == character_id)
+        .collect();
+    match (member, existing.is_empty()) {
+        (true, true) => {
+            ctx.db.instance_member_tbl().insert(InstanceMemberRow {
+                id: 0,
+                instance_id,
+                character_id,
+            });
+        }
+        (false, false) => {
+            for row in existing {
+                ctx.db.instance_member_tbl().delete(row);
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Places an entrance into `to_instance_id` in the sender's instance. Admin only.
+#[reducer]
+pub fn place_instance_entrance(
+    ctx: &ReducerContext,
+    to_instance_id: InstanceId,
+    translation: Vec3,
+    radius: f32,
+) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "place_instance_entrance")?;
+    let instance_id = sender_instance_id(ctx);
+    if InstanceRow::find(&ctx.as_read_only(), to_instance_id).is_none() {
+        return Err("Unknown instance".into());
+    }
+    if to_instance_id == instance_id {
+        return Err("An entrance can't lead into its own instance".into());
+    }
+    if !(radius.is_finite() && radius > 0.0) {
+        return Err("Radius must be positive".into());
+    }
+    let row = ctx.db.instance_entrance_tbl().insert(InstanceEntranceRow {
+        id: 0,
+        instance_id,
+        to_instance_id,
+        translation,
+        radius,
+    });
+    EventLogRow::record(
+        ctx,
+        EventKind::WorldEdited,
+        None,
+        format!(
+            "{:?} placed entrance {} into instance {} in instance {}",
+            ctx.sender, row.id, to_instance_id, instance_id
+        ),
+    );
+    Ok(())
+}
+
+/// Removes an entrance placed with [`place_instance_entrance`]. Admin only.
+#[reducer]
+pub fn remove_instance_entrance(ctx: &ReducerContext, id: u32) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "remove_instance_entrance")?;
+    if !ctx.db.instance_entrance_tbl().id().delete(id) {
+        return Err("Unknown entrance".into());
+    }
+    EventLogRow::record(
+        ctx,
+        EventKind::WorldEdited,
+        None,
+        format!("{:?} removed entrance {}", ctx.sender, id),
+    );
+    Ok(())
+}
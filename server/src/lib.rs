@@ -1,48 +1,149 @@
+pub mod activity;
 pub mod actor;
+pub mod admin;
+pub mod ai;
+pub mod ban;
+pub mod cell_budget;
 pub mod character;
 pub mod character_instance;
+pub mod combat;
+pub mod combat_event;
+pub mod combat_log;
+pub mod cooldown;
+pub mod corpse;
+#[cfg(feature = "dev-tools")]
+pub mod dev_tools;
+pub mod domain_event;
+pub mod duel;
+pub mod emote;
+pub mod encounter;
+pub mod event_log;
+pub mod game_config;
+pub mod gc;
+pub mod guild;
+pub mod instance;
+pub mod load_shedding;
+pub mod metrics;
 pub mod monster;
 pub mod monster_instance;
 pub mod movement;
 pub mod npc;
+pub mod partition;
+pub mod persistence;
 pub mod player;
+pub mod player_setting;
+pub mod presentation_config;
 pub mod primitives;
 pub mod progression;
+pub mod schema_version;
+pub mod spawn_point;
+pub mod spectator;
 pub mod stat;
+pub mod target;
+pub mod timer_watchdog;
+pub mod timing_stats;
+pub mod training_dummy;
 pub mod transform;
+pub mod tutorial;
 pub mod util;
+pub mod who;
+pub mod world;
+pub mod world_bounds;
 pub mod world_static;
+pub mod zone_ambient;
 
+pub use activity::*;
 pub use actor::*;
+pub use admin::*;
+pub use ai::*;
+pub use ban::*;
+pub use cell_budget::*;
 pub use character::*;
 pub use character_instance::*;
+pub use combat::*;
+pub use combat_event::*;
+pub use combat_log::*;
+pub use cooldown::*;
+pub use corpse::*;
+#[cfg(feature = "dev-tools")]
+pub use dev_tools::*;
+pub use domain_event::*;
+pub use duel::*;
+pub use emote::*;
+pub use encounter::*;
+pub use event_log::*;
+pub use game_config::*;
+pub use gc::*;
+pub use guild::*;
+pub use instance::*;
+pub use load_shedding::*;
+pub use metrics::*;
 pub use monster::*;
 pub use monster_instance::*;
 pub use movement::*;
 pub use npc::*;
+pub use partition::*;
+pub use persistence::*;
 pub use player::*;
+pub use player_setting::*;
+pub use presentation_config::*;
 pub use primitives::*;
 pub use progression::*;
+pub use schema_version::*;
+pub use spawn_point::*;
+pub use spectator::*;
 pub use stat::*;
+pub use target::*;
+pub use timer_watchdog::*;
+pub use timing_stats::*;
+pub use training_dummy::*;
 pub use transform::*;
+pub use tutorial::*;
 pub use util::*;
+pub use who::*;
+pub use world::*;
+pub use world_bounds::*;
 pub use world_static::*;
+pub use zone_ambient::*;
 
 use spacetimedb::*;
 
 #[reducer(init)]
 pub fn init(ctx: &ReducerContext) -> Result<(), String> {
     log::info!("Database initializing...");
-    regenerate_static_world(ctx);
+    InstanceRow::regenerate(ctx);
+    SchemaVersionRow::migrate(ctx);
     init_movement_tick(ctx);
     init_health_and_mana_regen(ctx);
+    init_persistence(ctx);
+    init_corpse_decay(ctx);
+    init_combat_event_cleanup(ctx);
+    init_gc(ctx);
+    init_metrics(ctx);
+    init_duel_check(ctx);
+    init_scripted_path(ctx);
+    init_ai(ctx);
+    init_load_shedding(ctx);
+    init_hazards(ctx);
+    init_projectiles(ctx);
+    init_activity(ctx);
+    init_domain_events(ctx);
+    init_timer_watchdog(ctx);
     Ok(())
 }
 
 #[spacetimedb::reducer(client_connected)]
-pub fn client_connected(ctx: &ReducerContext) {
+pub fn client_connected(ctx: &ReducerContext) -> Result<(), String> {
     log::info!("Client connected: {:?}", ctx.sender);
+    AdminIdentityRow::bootstrap(ctx);
+    // `init` only runs on the first publish, module updates are migrated here.
+    SchemaVersionRow::migrate(ctx);
+    // Failing rejects the connection.
+    BanRow::require_not_banned(ctx, ctx.sender)?;
+    KickNoticeRow::clear(ctx, ctx.sender);
     PlayerRow::connect(ctx);
+    ensure_timers(ctx, &mut WriteStats::default());
+    Ok(())
 }
 
 #[spacetimedb::reducer(client_disconnected)]
@@ -0,0 +1,212 @@
+//! Load shedding, trading monster reaction time for movement tick headroom.
+//!
+//! Every check compares the movement tick's interval EMA (see [`TimingStatsRow`]) to its
+//! scheduled interval. The movement tick records every run, idle ones included, so the EMA is how
+//! late it runs: the scheduler falls behind when ticks take longer than their interval, an EMA
+//! above [`OVERLOAD_RATIO`] of it means the movement tick is regularly over budget:
+//!
+//! - Overloaded, the AI tick interval doubles, up to the configured maximum.
+//! - Below [`RECOVERED_RATIO`], it halves back towards [`AI_TICK_INTERVAL_MILLIS`].
+//! - Without recent stats (the movement tick isn't running) it halves too, there's no movement
+//!   load to make room for.
+//!
+//! The gap between the two ratios keeps the interval from flapping. Every change reschedules the
+//! AI timer and is recorded to the event log, the timer watchdog expects the shed interval (see
+//! [`LoadSheddingRow::ai_tick_interval_millis`]).
+
+use crate::{
+    ai::ai_tick::AI_TICK_INTERVAL_MILLIS, ai_tick_timer, timing_stats_tbl, AdminIdentityRow,
+    EventKind, EventLogRow, TimingStatsRow, WriteStats,
+};
+use shared::MOVEMENT_TICK_INTERVAL_MICROS;
+use spacetimedb::{reducer, table, ReducerContext, ScheduleAt, Table, Timestamp, ViewContext};
+use std::time::Duration;
+
+pub(crate) const LOAD_SHEDDING_INTERVAL_MILLIS: u64 = 5_000;
+
+/// Movement tick interval EMA, relative to its scheduled interval, above which load is shed...
+const OVERLOAD_RATIO: f32 = 1.25;
+/// ...and below which it's restored.
+const RECOVERED_RATIO: f32 = 1.1;
+
+/// Stats of a movement tick that hasn't run for this long (microseconds) are stale. A few
+/// intervals, an overloaded tick runs late.
+const STALE_STATS_MICROS: i64 = 3 * MOVEMENT_TICK_INTERVAL_MICROS;
+
+/// Default and upper bound of the configurable maximum AI tick interval.
+const DEFAULT_MAX_AI_TICK_INTERVAL_MILLIS: u64 = 1_000;
+const MAX_AI_TICK_INTERVAL_MILLIS: u64 = 5_000;
+
+/// The current load shedding state and its bounds, a single row.
+///
+/// Private, read by operators via SQL. Without the row nothing is shed, see
+/// [`LoadSheddingRow::get`].
+#[table(name=load_shedding_tbl)]
+pub struct LoadSheddingRow {
+    /// Always [`LoadSheddingRow::ID`].
+    #[primary_key]
+    pub id: u8,
+
+    /// The interval the AI tick is scheduled at.
+    pub ai_tick_interval_millis: u64,
+
+    /// How far the AI tick interval may be stretched under load.
+    pub max_ai_tick_interval_millis: u64,
+
+    pub updated_at: Timestamp,
+}
+
+impl LoadSheddingRow {
+    const ID: u8 = 0;
+
+    /// The current state, or nothing shed with the default bounds when there's no row.
+    pub fn get(ctx: &ViewContext) -> Self {
+        ctx.db
+            .load_shedding_tbl()
+            .id()
+            .find(Self::ID)
+            .unwrap_or(Self {
+                id: Self::ID,
+                ai_tick_interval_millis: AI_TICK_INTERVAL_MILLIS,
+                max_ai_tick_interval_millis: DEFAULT_MAX_AI_TICK_INTERVAL_MILLIS,
+                updated_at: Timestamp::UNIX_EPOCH,
+            })
+    }
+
+    /// The interval the AI tick should be scheduled at right now.
+    pub fn ai_tick_interval_millis(ctx: &ViewContext) -> u64 {
+        Self::get(ctx).ai_tick_interval_millis
+    }
+
+    fn upsert(self, ctx: &ReducerContext) {
+        if ctx.db.load_shedding_tbl().id().find(Self::ID).is_some() {
+            ctx.db.load_shedding_tbl().id().update(self);
+        } else {
+            ctx.db.load_shedding_tbl().insert(self);
+        }
+    }
+}
+
+#[table(name = load_shedding_timer, scheduled(load_shedding_reducer))]
+pub struct LoadSheddingTimer {
+    #[primary_key]
+    #[auto_inc]
+    pub scheduled_id: u64,
+    pub scheduled_at: ScheduleAt,
+}
+
+pub fn init_load_shedding(ctx: &ReducerContext) {
+    ctx.db.load_shedding_timer().scheduled_id().delete(1);
+    ctx.db.load_shedding_timer().insert(LoadSheddingTimer {
+        scheduled_id: 1,
+        scheduled_at: Duration::from_millis(LOAD_SHEDDING_INTERVAL_MILLIS).into(),
+    });
+    log::info!("init load shedding");
+}
+
+/// The movement tick's interval EMA relative to its scheduled interval, `None` when its stats
+/// are missing or stale.
+fn movement_tick_load(ctx: &ReducerContext) -> Option<f32> {
+    let stats = ctx
+        .db
+        .timing_stats_tbl()
+        .name()
+        .find(TimingStatsRow::MOVEMENT_TICK.to_string())?;
+    let since_last_run = ctx.timestamp.time_duration_since(stats.last_run_at)?;
+    if since_last_run.to_micros() > STALE_STATS_MICROS {
+        return None;
+    }
+    Some(stats.interval_ema_micros / MOVEMENT_TICK_INTERVAL_MICROS as f32)
+}
+
+/// Reschedules the AI tick at `millis`, keeping its cursor.
+fn reschedule_ai_tick(ctx: &ReducerContext, millis: u64) {
+    let Some(mut timer) = ctx.db.ai_tick_timer().scheduled_id().find(1) else {
+        // The timer watchdog recreates it at the shed interval.
+        return;
+    };
+    timer.scheduled_at = Duration::from_millis(millis).into();
+    ctx.db.ai_tick_timer().scheduled_id().update(timer);
+}
+
+/// Moves the AI tick to `next` millis, recording the transition.
+fn transition(ctx: &ReducerContext, mut row: LoadSheddingRow, next: u64, reason: String) {
+    EventLogRow::record(
+        ctx,
+        EventKind::LoadShedding,
+        None,
+        format!(
+            "{reason}, AI tick interval {}ms -> {}ms",
+            row.ai_tick_interval_millis, next
+        ),
+    );
+    row.ai_tick_interval_millis = next;
+    row.updated_at = ctx.timestamp;
+    row.upsert(ctx);
+    reschedule_ai_tick(ctx, next);
+}
+
+/// Sheds or restores load, see the module docs.
+///
+/// **Performance & Cost**: two primary key seeks, plus three writes on a transition
+#[reducer]
+fn load_shedding_reducer(ctx: &ReducerContext, _timer: LoadSheddingTimer) -> Result<(), String> {
+    if ctx.sender != ctx.identity() {
+        log::error!("`load_shedding_reducer` may not be invoked by clients.");
+        return Err("`load_shedding_reducer` may not be invoked by clients.".into());
+    }
+
+    let row = LoadSheddingRow::get(&ctx.as_read_only());
+    let current = row.ai_tick_interval_millis;
+    let load = movement_tick_load(ctx);
+    let next = match load {
+        Some(load) if load > OVERLOAD_RATIO => {
+            (current * 2).min(row.max_ai_tick_interval_millis.max(AI_TICK_INTERVAL_MILLIS))
+        }
+        Some(load) if load >= RECOVERED_RATIO => current,
+        _ => (current / 2).max(AI_TICK_INTERVAL_MILLIS),
+    };
+
+    let mut write_stats = WriteStats::default();
+    write_stats.update_if_changed(&current, &next, || {
+        let reason = match load {
+            Some(load) => format!("Movement tick at {:.0}% of its interval", load * 100.0),
+            None => "No recent movement tick stats".to_string(),
+        };
+        transition(ctx, row, next, reason);
+    });
+    TimingStatsRow::record(ctx, TimingStatsRow::LOAD_SHEDDING_TICK, write_stats);
+    Ok(())
+}
+
+/// Sets how far the AI tick interval may be stretched under load, restoring it right away when
+/// it's currently stretched further. Admin only.
+#[reducer]
+pub fn set_load_shedding_bounds(
+    ctx: &ReducerContext,
+    max_ai_tick_interval_millis: u64,
+) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "set_load_shedding_bounds")?;
+    if !(AI_TICK_INTERVAL_MILLIS..=MAX_AI_TICK_INTERVAL_MILLIS)
+        .contains(&max_ai_tick_interval_millis)
+    {
+        return Err(format!(
+            "max_ai_tick_interval_millis must be in [{AI_TICK_INTERVAL_MILLIS}, {MAX_AI_TICK_INTERVAL_MILLIS}]"
+        ));
+    }
+
+    let mut row = LoadSheddingRow::get(&ctx.as_read_only());
+    row.max_ai_tick_interval_millis = max_ai_tick_interval_millis;
+    if row.ai_tick_interval_millis > max_ai_tick_interval_millis {
+        transition(
+            ctx,
+            row,
+            max_ai_tick_interval_millis,
+            "Load shedding bounds lowered".to_string(),
+        );
+        return Ok(());
+    }
+    row.updated_at = ctx.timestamp;
+    row.upsert(ctx);
+    Ok(())
+}
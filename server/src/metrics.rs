@@ -0,0 +1,229 @@
+use crate::{
+    actor_tbl, cell_population, character_instance_tbl, corpse_tbl, gc_stats_tbl,
+    monster_instance_tbl, movement_state_tbl, timing_stats_tbl, ActorRow, AdminIdentityRow,
+    GameConfigRow, TimingStatsRow, WriteStats,
+};
+use shared::{ActorFlags, CellId, InstanceId};
+use spacetimedb::{reducer, table, ReducerContext, ScheduleAt, Table};
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+/// How often a metrics snapshot is taken.
+pub(crate) const METRICS_INTERVAL_MILLIS: u64 = 10_000;
+
+/// How many of the most populated cells are reported.
+const HOTSPOT_COUNT: usize = 5;
+
+/// One value of the latest metrics snapshot, see [`take_metrics_snapshot`].
+///
+/// Public and flat so an external scraper can export it as-is (`name{label} value`) and the
+/// client's debug panel can show it without knowing every metric.
+#[table(name=metrics_tbl, public)]
+pub struct MetricRow {
+    /// `name` and `label` joined, e.g. `tick_writes_ema:movement_tick`.
+    #[primary_key]
+    pub key: String,
+
+    pub name: String,
+
+    /// Distinguishes metrics sharing a name, e.g. the tick or cell, empty when unused.
+    pub label: String,
+
+    pub value: f64,
+}
+
+impl MetricRow {
+    fn new(name: &str, label: impl Into<String>, value: f64) -> Self {
+        let label = label.into();
+        let key = if label.is_empty() {
+            name.to_string()
+        } else {
+            format!("{name}:{label}")
+        };
+        Self {
+            key,
+            name: name.to_string(),
+            label,
+            value,
+        }
+    }
+}
+
+/// Aggregates the current state of the world into `metrics_tbl`.
+///
+/// Unchanged values are not written and metrics that are no longer reported (e.g. a cell that
+/// stopped being a hotspot) are deleted.
+///
+/// **Performance & Cost**: O(N) scans of the actors for the pool size and of the movement states
+/// for the cell counts
+pub fn take_metrics_snapshot(ctx: &ReducerContext) -> WriteStats {
+    let mut metrics = vec![
+        MetricRow::new("actors", "", ctx.db.actor_tbl().count() as f64),
+        MetricRow::new(
+            "characters",
+            "",
+            ctx.db.character_instance_tbl().count() as f64,
+        ),
+        MetricRow::new("monsters", "", ctx.db.monster_instance_tbl().count() as f64),
+        MetricRow::new(
+            "pooled_monsters",
+            "",
+            ctx.db
+                .actor_tbl()
+                .iter()
+                .filter(|actor| actor.flags().contains(ActorFlags::POOLED))
+                .count() as f64,
+        ),
+        MetricRow::new("dead_actors", "", ctx.db.corpse_tbl().count() as f64),
+        MetricRow::new(
+            "moving_actors",
+            "",
+            ctx.db
+                .movement_state_tbl()
+                .should_move()
+                .filter(true)
+                .count() as f64,
+        ),
+    ];
+
+    for tick in ctx.db.timing_stats_tbl().iter() {
+        metrics.push(MetricRow::new("tick_runs", &tick.name, tick.runs as f64));
+        metrics.push(MetricRow::new(
+            "tick_interval_ema_micros",
+            &tick.name,
+            tick.interval_ema_micros as f64,
+        ));
+        metrics.push(MetricRow::new(
+            "tick_writes_ema",
+            &tick.name,
+            tick.writes_ema as f64,
+        ));
+        metrics.push(MetricRow::new(
+            "tick_suppressed_writes",
+            &tick.name,
+            tick.suppressed_writes as f64,
+        ));
+        metrics.push(MetricRow::new(
+            "tick_total_suppressed_writes",
+            &tick.name,
+            tick.total_suppressed_writes as f64,
+        ));
+    }
+
+    for gc in ctx.db.gc_stats_tbl().iter() {
+        metrics.push(MetricRow::new(
+            "gc_deleted",
+            &gc.table_name,
+            gc.total_deleted as f64,
+        ));
+    }
+
+    let mut cells: HashMap<CellId, u32> = HashMap::new();
+    for movement_state in ctx.db.movement_state_tbl().iter() {
+        *cells.entry(movement_state.cell_id).or_default() += 1;
+    }
+    metrics.push(MetricRow::new("occupied_cells", "", cells.len() as f64));
+    // Only cells at the budget counting every instance and pooled monsters can be over it,
+    // recount just those per instance present.
+    let view_ctx = ctx.as_read_only();
+    let budget = GameConfigRow::get(&view_ctx).max_actors_per_cell;
+    let cells_over_budget: usize = cells
+        .iter()
+        .filter(|&(_, &actors)| actors >= budget)
+        .map(|(&cell_id, _)| {
+            let instances: HashSet<InstanceId> = ctx
+                .db
+                .movement_state_tbl()
+                .cell_id()
+                .filter(cell_id)
+                .filter_map(|ms| ActorRow::find(&view_ctx, ms.actor_id))
+                .map(|actor| actor.instance_id)
+                .collect();
+            instances
+                .into_iter()
+                .filter(|&instance_id| cell_population(&view_ctx, instance_id, cell_id) >= budget)
+                .count()
+        })
+        .sum();
+    metrics.push(MetricRow::new("cell_budget", "", budget as f64));
+    metrics.push(MetricRow::new(
+        "cells_over_budget",
+        "",
+        cells_over_budget as f64,
+    ));
+    let mut hotspots: Vec<(CellId, u32)> = cells.into_iter().collect();
+    hotspots.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    for (cell_id, actors) in hotspots.into_iter().take(HOTSPOT_COUNT) {
+        metrics.push(MetricRow::new(
+            "cell_actors",
+            cell_id.to_string(),
+            actors as f64,
+        ));
+    }
+
+    let mut write_stats = WriteStats::default();
+    let mut stale: HashMap<String, MetricRow> = ctx
+        .db
+        .metrics_tbl()
+        .iter()
+        .map(|row| (row.key.clone(), row))
+        .collect();
+    for metric in metrics {
+        match stale.remove(&metric.key) {
+            Some(existing) => {
+                let value = metric.value;
+                write_stats.update_if_changed(&existing.value, &value, || {
+                    ctx.db.metrics_tbl().key().update(metric);
+                });
+            }
+            None => {
+                ctx.db.metrics_tbl().insert(metric);
+                write_stats.record(true);
+            }
+        }
+    }
+    for key in stale.into_keys() {
+        write_stats.record(ctx.db.metrics_tbl().key().delete(key));
+    }
+
+    write_stats
+}
+
+/// Takes a metrics snapshot right away instead of waiting for the next scheduled one.
+#[reducer]
+pub fn metrics_snapshot(ctx: &ReducerContext) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "metrics_snapshot")?;
+    take_metrics_snapshot(ctx);
+    Ok(())
+}
+
+#[table(name = metrics_timer, scheduled(metrics_reducer))]
+pub struct MetricsTimer {
+    #[primary_key]
+    #[auto_inc]
+    pub scheduled_id: u64,
+    pub scheduled_at: ScheduleAt,
+}
+
+pub fn init_metrics(ctx: &ReducerContext) {
+    ctx.db.metrics_timer().scheduled_id().delete(1);
+    ctx.db.metrics_timer().insert(MetricsTimer {
+        scheduled_id: 1,
+        scheduled_at: Duration::from_millis(METRICS_INTERVAL_MILLIS).into(),
+    });
+    log::info!("init metrics");
+}
+
+#[reducer]
+fn metrics_reducer(ctx: &ReducerContext, _timer: MetricsTimer) -> Result<(), String> {
+    if ctx.sender != ctx.identity() {
+        log::error!("`metrics_reducer` may not be invoked by clients.");
+        return Err("`metrics_reducer` may not be invoked by clients.".into());
+    }
+
+    let write_stats = take_metrics_snapshot(ctx);
+    TimingStatsRow::record(ctx, TimingStatsRow::METRICS_TICK, write_stats);
+    Ok(())
+}
@@ -1,77 +1,271 @@
-use super::CapsuleY;
-use spacetimedb::{table, ReducerContext, Table};
+use crate::{
+    actor_tbl, behavior_tree_tbl, current_server_tick, monster_instance_tbl, movement_state_tbl,
+    redirect_spawn, sender_instance_id, ActorRow, AdminIdentityRow, BehaviorTreeRow, CapsuleY,
+    HealthData, HealthRow, LevelRow, ManaData, ManaRow, MonsterInstanceRow, MoveIntentData,
+    MovementStateRow, PrimaryStatsRow, SecondaryStatsRow, TransformRow, Vec3,
+};
+use shared::{encode_cell_id, validate, ActorId, InstanceId};
+use spacetimedb::{reducer, table, ReducerContext, Table};
 
-/// Monster "definition" (type).
+/// Monster archetype (definition/type).
 ///
-/// One row per monster kind/type you can spawn (e.g. Troll, Black Spider, Bug).
-/// This is NOT a spawned world instance.
-#[table(name=monster_tbl)]
-pub struct MonsterRow {
+/// One row per monster kind you can spawn (e.g. Troll, Black Spider, Bug), content is defined as
+/// data here and reused by spawners, AI, combat and loot.
+/// This is NOT a spawned world instance, see [`MonsterInstanceRow`].
+#[table(name=monster_archetype_tbl)]
+pub struct MonsterArchetypeRow {
     #[auto_inc]
     #[primary_key]
     pub id: u16,
 
+    #[unique]
     pub name: String,
 
+    pub level: u8,
+
+    // Primary stats
+    pub ferocity: u8,
+    pub fortitude: u8,
+    pub intellect: u8,
+    pub acuity: u8,
+
+    /// Unscaled capsule, see [`Self::scaled_capsule`].
     pub capsule: CapsuleY,
+
+    /// Size of spawned monsters relative to [`Self::capsule`], validated by
+    /// [`shared::validate::scaled_capsule`].
+    pub scale: f32,
+
+    /// Movement speed (meters/second)
+    pub movement_speed: f32,
+
+    /// Distance (meters) at which the monster notices enemies.
+    pub aggro_radius: f32,
+
+    /// Keeps the monster from walking off ledges that drop more than this (meters), `None` lets
+    /// it walk off anything. See `shared::MovementStepInput::max_drop`.
+    pub max_drop: Option<f32>,
+
+    /// Experience awarded for defeating this monster.
+    pub xp_reward: u32,
+
+    /// UNIMPLEMENTED: The loot table rolled when this monster is defeated.
+    pub loot_table_id: Option<u32>,
 }
 
-impl MonsterRow {
-    pub fn insert(name: impl Into<String>, capsule: CapsuleY) -> Self {
-        Self {
-            id: 0,
-            name: name.into(),
-            capsule,
+impl MonsterArchetypeRow {
+    pub fn find(ctx: &ReducerContext, id: u16) -> Option<Self> {
+        ctx.db.monster_archetype_tbl().id().find(id)
+    }
+
+    pub fn insert(ctx: &ReducerContext, archetype: Self) -> Self {
+        ctx.db.monster_archetype_tbl().insert(archetype)
+    }
+
+    /// The capsule spawned monsters collide with, [`Self::capsule`] times [`Self::scale`].
+    pub fn scaled_capsule(&self) -> CapsuleY {
+        CapsuleY {
+            radius: self.capsule.radius * self.scale,
+            half_height: self.capsule.half_height * self.scale,
         }
     }
 
-    /// Spawn a new monster instance (an [`Actor`]) from this monster definition.
+    /// Spawn a new monster instance (an actor) from this archetype.
     ///
-    /// This allocates a fresh `owner_id` via `monster_instance_tbl` so multiple monsters of the
-    /// same type can exist at once.
-    // pub fn spawn_instance(&self, ctx: &ReducerContext) -> Result<Owner, String> {
-    //     // Allocate a new instance id (owner_id) that will become the Actor/Owner key.
-    //     let instance = ctx.db.monster_instance_tbl().insert(MonsterInstanceRow {
-    //         owner_id: 0,
-    //         monster_id: self.id,
-    //     });
-
-    //     let owner = pack_owner(instance.owner_id, OwnerKind::Monster);
-    //     // Spawn at origin by default for now; call sites can update transform after spawn
-    //     // (or you can extend this API to accept a transform).
-    //     let transform: TransformData = Default::default();
-
-    //     let cell_id = encode_cell_id(transform.translation.x, transform.translation.z);
-    //     // Ephemeral component rows keyed by Owner.
-    //     ctx.db.movement_state_tbl().insert(MovementStateRow {
-    //         owner,
-    //         grounded: false,
-    //         should_move: true,
-    //         move_intent: None,
-    //         vertical_velocity: 0.0,
-    //         cell_id,
-    //         capsule: self.capsule,
-    //     });
-    //     TransformRow::insert(ctx, owner, transform);
-    //     PrimaryStatsRow::insert(ctx, owner, PrimaryStatsData::default());
-    //     HealthRow::insert(ctx, owner, HealthData::new(100));
-    //     ManaRow::insert(ctx, owner, ManaData::new(100));
-    //     StatusFlags::insert(ctx, owner, StatusFlagsData::default());
-
-    //     Ok(owner)
-    // }
+    /// This allocates a fresh actor so multiple monsters of the same type can exist at once.
+    pub fn spawn(
+        &self,
+        ctx: &ReducerContext,
+        instance_id: InstanceId,
+        translation: Vec3,
+        yaw: f32,
+    ) -> ActorId {
+        let translation = redirect_spawn(ctx, instance_id, translation, self.scaled_capsule());
+        if let Some(actor_id) = MonsterInstanceRow::take_pooled(ctx, self.id) {
+            self.respawn_pooled(ctx, actor_id, instance_id, translation, yaw);
+            return actor_id;
+        }
 
-    pub fn regenerate(ctx: &ReducerContext) {
-        ctx.db.monster_tbl().iter().for_each(|row| {
-            ctx.db.monster_tbl().delete(row);
+        let actor = ctx.db.actor_tbl().insert(ActorRow {
+            id: 0,
+            capsule: self.scaled_capsule(),
+            scale: self.scale,
+            flags: 0,
+            instance_id,
+            name: self.name.clone(),
+        });
+        ctx.db.monster_instance_tbl().insert(MonsterInstanceRow {
+            actor_id: actor.id,
+            archetype_id: self.id,
+        });
+        ctx.db.movement_state_tbl().insert(MovementStateRow {
+            actor_id: actor.id,
+            should_move: true,
+            move_intent: MoveIntentData::None,
+            vertical_velocity: -1,
+            cell_id: encode_cell_id(translation.x, translation.z),
+            ground_material: None,
+            server_tick: current_server_tick(ctx),
+            client_intent_seq: 0,
         });
+        TransformRow::insert(ctx, actor.id, translation, yaw);
+        PrimaryStatsRow::insert(
+            ctx,
+            actor.id,
+            self.ferocity,
+            self.fortitude,
+            self.intellect,
+            self.acuity,
+            0,
+        );
+        let critical_hit_chance =
+            SecondaryStatsRow::compute_critical_hit_chance(self.level, self.ferocity, 0.0);
+        SecondaryStatsRow::insert(
+            ctx,
+            actor.id,
+            self.movement_speed,
+            critical_hit_chance,
+            SecondaryStatsRow::compute_armor(self.level, self.fortitude, 0.0),
+            SecondaryStatsRow::compute_resistance(self.level, self.intellect, 0.0),
+        );
+        HealthRow::insert(
+            ctx,
+            actor.id,
+            HealthData::new(HealthData::compute_max(self.level, self.fortitude)),
+        );
+        ManaRow::insert(
+            ctx,
+            actor.id,
+            ManaData::new(ManaData::compute_max(self.level, self.intellect)),
+        );
+        LevelRow::insert(ctx, actor.id, self.level);
+
+        actor.id
+    }
+
+    /// Puts a pooled actor of this archetype back into the world, see
+    /// [`MonsterInstanceRow::release`]. Its stat rows are kept as they were, vitals already full.
+    fn respawn_pooled(
+        &self,
+        ctx: &ReducerContext,
+        actor_id: ActorId,
+        instance_id: InstanceId,
+        translation: Vec3,
+        yaw: f32,
+    ) {
+        ctx.db.actor_tbl().id().update(ActorRow {
+            id: actor_id,
+            capsule: self.scaled_capsule(),
+            scale: self.scale,
+            flags: 0,
+            instance_id,
+            name: self.name.clone(),
+        });
+        let server_tick = current_server_tick(ctx);
+        if let Some(transform) = TransformRow::find(ctx, actor_id) {
+            transform.update(ctx, server_tick, translation, yaw);
+        }
+        ctx.db
+            .movement_state_tbl()
+            .actor_id()
+            .update(MovementStateRow {
+                actor_id,
+                should_move: true,
+                move_intent: MoveIntentData::None,
+                vertical_velocity: -1,
+                cell_id: encode_cell_id(translation.x, translation.z),
+                ground_material: None,
+                server_tick,
+                client_intent_seq: 0,
+            });
+        SecondaryStatsRow::refresh_movement_speed(ctx, actor_id);
+    }
 
-        MonsterRow::insert(
-            "Troll",
-            CapsuleY {
-                radius: 0.3,
-                half_height: 0.9,
+    /// Deletes all archetypes and their behavior trees and re-inserts the defaults
+    pub fn regenerate(ctx: &ReducerContext) {
+        // Archetype ids are reassigned, pooled actors would be reused as the wrong monster.
+        MonsterInstanceRow::drain_pool(ctx);
+        for row in ctx.db.monster_archetype_tbl().iter() {
+            ctx.db.monster_archetype_tbl().delete(row);
+        }
+        for row in ctx.db.behavior_tree_tbl().iter() {
+            ctx.db.behavior_tree_tbl().delete(row);
+        }
+
+        let troll = Self::insert(
+            ctx,
+            Self {
+                id: 0,
+                name: "Troll".into(),
+                level: 5,
+                ferocity: 8,
+                fortitude: 10,
+                intellect: 1,
+                acuity: 2,
+                capsule: CapsuleY {
+                    radius: 0.3,
+                    half_height: 0.9,
+                },
+                scale: 1.0,
+                movement_speed: 3.5,
+                aggro_radius: 12.0,
+                max_drop: Some(1.0),
+                xp_reward: 50,
+                loot_table_id: None,
             },
         );
+        if let Err(err) = BehaviorTreeRow::set(ctx, troll.id, BehaviorTreeRow::melee_brute()) {
+            log::error!("Unable to set the Troll's behavior tree: {err}");
+        }
     }
 }
+
+/// Spawns a monster of the given archetype at the given position in the admin's current
+/// instance. Admin only.
+#[reducer]
+pub fn spawn_monster(
+    ctx: &ReducerContext,
+    archetype_id: u16,
+    translation: Vec3,
+) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "spawn_monster")?;
+    let translation = Vec3::from(validate::within_world(translation.into())?);
+    let Some(archetype) = MonsterArchetypeRow::find(ctx, archetype_id) else {
+        log::error!("Unable to find monster archetype {}", archetype_id);
+        return Err("Unable to find monster archetype".into());
+    };
+
+    let instance_id = sender_instance_id(ctx);
+    let actor_id = archetype.spawn(ctx, instance_id, translation, 0.0);
+    log::info!(
+        "Spawned {} as actor {} in instance {}",
+        archetype.name,
+        actor_id,
+        instance_id
+    );
+    Ok(())
+}
+
+/// Resizes the monsters of an archetype, e.g. to make a big variant. Monsters already spawned
+/// keep their size, the next ones spawn with it. Admin only.
+#[reducer]
+pub fn set_monster_archetype_scale(
+    ctx: &ReducerContext,
+    archetype_id: u16,
+    scale: f32,
+) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "set_monster_archetype_scale")?;
+    let Some(mut archetype) = MonsterArchetypeRow::find(ctx, archetype_id) else {
+        return Err(format!("Unable to find monster archetype {archetype_id}"));
+    };
+    validate::scaled_capsule(
+        archetype.capsule.radius,
+        archetype.capsule.half_height,
+        scale,
+    )?;
+
+    archetype.scale = scale;
+    log::info!("Scaled monster archetype {} to {}", archetype.name, scale);
+    ctx.db.monster_archetype_tbl().id().update(archetype);
+    Ok(())
+}
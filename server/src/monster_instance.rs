@@ -1,13 +1,113 @@
-use shared::ActorId;
-use spacetimedb::table;
+use crate::{
+    actor_tbl, get_view_aoi_actors, movement_state_tbl, ActorRow, HealthRow, ManaRow,
+    MoveIntentData,
+};
+use shared::{ActorFlags, ActorId};
+use spacetimedb::{table, ReducerContext, ViewContext};
+
+/// Pooled actors kept per archetype, monsters despawned beyond this are deleted for good.
+const MAX_POOLED_PER_ARCHETYPE: usize = 32;
 
 /// A spawned monster instance in the world.
+///
+/// Despawned monsters are pooled rather than deleted (see [`MonsterInstanceRow::release`]), the
+/// next spawn of the same archetype reuses the actor and its rows, keeping its id.
 #[table(name=monster_instance_tbl)]
 pub struct MonsterInstanceRow {
     #[primary_key]
     pub actor_id: ActorId,
 
-    /// Monster definition/type id from `monster_tbl`.
+    /// Monster archetype id from `monster_archetype_tbl`.
     #[index(btree)]
-    pub monster_id: u16,
+    pub archetype_id: u16,
+}
+
+impl MonsterInstanceRow {
+    pub fn find(ctx: &ViewContext, actor_id: ActorId) -> Option<Self> {
+        ctx.db.monster_instance_tbl().actor_id().find(actor_id)
+    }
+
+    /// The pooled actors of an archetype.
+    ///
+    /// **Performance & Cost**: O(N) over the archetype's monsters, an actor seek per monster
+    fn pooled(ctx: &ViewContext, archetype_id: u16) -> impl Iterator<Item = ActorId> + '_ {
+        ctx.db
+            .monster_instance_tbl()
+            .archetype_id()
+            .filter(archetype_id)
+            .map(|monster| monster.actor_id)
+            .filter(move |&actor_id| ActorRow::is_pooled(ctx, actor_id))
+    }
+
+    /// Takes the monster out of the world and parks it in its archetype's pool, or despawns it
+    /// when the pool is full.
+    ///
+    /// A pooled actor keeps its actor, transform, movement and stat rows, flagged
+    /// [`ActorFlags::POOLED`] (and still [`ActorFlags::DEAD`]) so views and gameplay ignore it.
+    /// Everything that doesn't outlive a spawn is deleted (see [`ActorRow::clear_world_state`]) and
+    /// vitals are topped up right away, so regeneration doesn't keep writing to pooled actors.
+    pub fn release(ctx: &ReducerContext, actor_id: ActorId) {
+        let view_ctx = ctx.as_read_only();
+        let Some(monster) = Self::find(&view_ctx, actor_id) else {
+            log::error!("Unable to find monster {} to release", actor_id);
+            return;
+        };
+        if Self::pooled(&view_ctx, monster.archetype_id).count() >= MAX_POOLED_PER_ARCHETYPE {
+            ActorRow::despawn(ctx, actor_id);
+            return;
+        }
+
+        ActorRow::clear_world_state(ctx, actor_id);
+        if let Some(health) = HealthRow::find(&view_ctx, actor_id) {
+            let max = health.data.max;
+            health.set_current(ctx, max);
+        }
+        if let Some(mana) = ManaRow::find(&view_ctx, actor_id) {
+            let max = mana.data.max;
+            mana.set_current(ctx, max);
+        }
+        if let Some(mut movement_state) = ctx.db.movement_state_tbl().actor_id().find(actor_id) {
+            movement_state.move_intent = MoveIntentData::None;
+            movement_state.vertical_velocity = 0;
+            movement_state.should_move = false;
+            movement_state.update_from_self(ctx);
+        }
+        if let Some(mut actor) = ctx.db.actor_tbl().id().find(actor_id) {
+            actor.flags = (ActorFlags::DEAD | ActorFlags::POOLED).bits();
+            ctx.db.actor_tbl().id().update(actor);
+        }
+    }
+
+    /// Takes a pooled actor of the archetype out of the pool, the caller resets its rows.
+    pub fn take_pooled(ctx: &ReducerContext, archetype_id: u16) -> Option<ActorId> {
+        Self::pooled(&ctx.as_read_only(), archetype_id).next()
+    }
+
+    /// Despawns every pooled actor, e.g. when the archetypes they'd be reused for are replaced.
+    pub fn drain_pool(ctx: &ReducerContext) {
+        let view_ctx = ctx.as_read_only();
+        let pooled: Vec<ActorId> = ctx
+            .db
+            .monster_instance_tbl()
+            .iter()
+            .map(|monster| monster.actor_id)
+            .filter(|&actor_id| ActorRow::is_pooled(&view_ctx, actor_id))
+            .collect();
+        for actor_id in pooled {
+            ActorRow::despawn(ctx, actor_id);
+        }
+    }
+}
+
+/// Finds the monster instances for all things within the AOI.
+/// Primary key of `ActorId`
+#[spacetimedb::view(name = monster_instance_view, public)]
+pub fn monster_instance_view(ctx: &ViewContext) -> Vec<MonsterInstanceRow> {
+    let Some(actors) = get_view_aoi_actors(ctx) else {
+        return vec![];
+    };
+
+    actors
+        .filter_map(|ms| MonsterInstanceRow::find(ctx, ms.actor_id))
+        .collect()
 }
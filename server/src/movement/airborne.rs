@@ -0,0 +1,167 @@
+use crate::{
+    current_server_tick, fall_damage, ActorRow, AdminIdentityRow, CombatEventKind, GameConfigRow,
+    HealthRow, MoveIntentData, MovementStateRow, TransformRow,
+};
+use shared::{quantize_vertical_velocity, ActorFlags, ActorId};
+use spacetimedb::{reducer, table, ReducerContext, Table};
+
+/// Upper bound of a launch (meters/second), keeps a bad ability definition or admin call from
+/// throwing an actor out of the world.
+const MAX_LAUNCH_MPS: f32 = 20.0;
+
+/// **Ephemeral**
+///
+/// An actor off the ground, launched or falling, from the tick it leaves the ground until the
+/// tick it lands. Tracks the highest point reached so the landing knows how far it fell.
+#[table(name=airborne_tbl)]
+pub struct AirborneRow {
+    #[primary_key]
+    pub actor_id: ActorId,
+
+    /// Highest height (meters) reached since leaving the ground, the fall's start height.
+    pub peak_y: f32,
+
+    /// Set by launches that shouldn't hurt on landing, see [`AirborneRow::launch`].
+    pub safe: bool,
+}
+
+/// An actor touching down, see [`AirborneRow::land`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Landing {
+    pub actor_id: ActorId,
+    /// Height (meters) between the highest point reached and where the actor landed.
+    pub fall_distance: f32,
+    /// The fall was a launch exempt from fall damage.
+    pub safe: bool,
+}
+
+impl AirborneRow {
+    /// Launches the actor straight up at `velocity_mps`, gravity brings it back down (see
+    /// [`shared::utils::advance_vertical_velocity`]). Until it lands the actor is
+    /// [`ActorFlags::AIRBORNE`] and its move intents are ignored. A `safe` launch deals no fall
+    /// damage on landing, wherever it lands.
+    ///
+    /// Flying and dead actors aren't launched.
+    pub fn launch(
+        ctx: &ReducerContext,
+        actor_id: ActorId,
+        velocity_mps: f32,
+        safe: bool,
+    ) -> Result<(), String> {
+        if !velocity_mps.is_finite() || !(0.0..=MAX_LAUNCH_MPS).contains(&velocity_mps) {
+            return Err("Launch velocity out of range".into());
+        }
+        let view_ctx = ctx.as_read_only();
+        if ActorRow::is_flying(&view_ctx, actor_id) || ActorRow::is_dead(&view_ctx, actor_id) {
+            return Ok(());
+        }
+        let (Some(mut movement_state), Some(transform)) = (
+            MovementStateRow::find(ctx, actor_id),
+            TransformRow::find(ctx, actor_id),
+        ) else {
+            return Err("Unable to find movement state for actor".into());
+        };
+        let vertical_velocity = quantize_vertical_velocity(velocity_mps);
+        if vertical_velocity <= 0 {
+            return Ok(());
+        }
+
+        // Launched mid-fall the fall so far still counts, unless this launch is safe.
+        let peak_y = transform.translation.y;
+        match ctx.db.airborne_tbl().actor_id().find(actor_id) {
+            Some(mut row) => {
+                row.peak_y = row.peak_y.max(peak_y);
+                row.safe |= safe;
+                ctx.db.airborne_tbl().actor_id().update(row);
+            }
+            None => {
+                ctx.db.airborne_tbl().insert(Self {
+                    actor_id,
+                    peak_y,
+                    safe,
+                });
+            }
+        }
+        ActorRow::set_flags(ctx, actor_id, ActorFlags::AIRBORNE, true);
+        movement_state.vertical_velocity = vertical_velocity;
+        movement_state.move_intent = MoveIntentData::None;
+        movement_state.should_move = true;
+        movement_state.server_tick = current_server_tick(ctx);
+        movement_state.update_from_self(ctx);
+        Ok(())
+    }
+
+    /// Records the actor being off the ground at `y` for this tick, only writing when it's a new
+    /// peak.
+    pub fn track(ctx: &ReducerContext, actor_id: ActorId, y: f32) {
+        match ctx.db.airborne_tbl().actor_id().find(actor_id) {
+            Some(row) if row.peak_y >= y => {}
+            Some(mut row) => {
+                row.peak_y = y;
+                ctx.db.airborne_tbl().actor_id().update(row);
+            }
+            None => {
+                ctx.db.airborne_tbl().insert(Self {
+                    actor_id,
+                    peak_y: y,
+                    safe: false,
+                });
+            }
+        }
+    }
+
+    /// Ends the actor's time in the air at `y`, clearing [`ActorFlags::AIRBORNE`].
+    pub fn land(ctx: &ReducerContext, actor_id: ActorId, y: f32) -> Landing {
+        let (peak_y, safe) = ctx
+            .db
+            .airborne_tbl()
+            .actor_id()
+            .find(actor_id)
+            .map_or((y, false), |row| (row.peak_y, row.safe));
+        Self::delete_for_actor(ctx, actor_id);
+        Landing {
+            actor_id,
+            fall_distance: (peak_y - y).max(0.0),
+            safe,
+        }
+    }
+
+    /// Ends the actor's time in the air without a landing, clearing [`ActorFlags::AIRBORNE`].
+    pub fn delete_for_actor(ctx: &ReducerContext, actor_id: ActorId) {
+        ctx.db.airborne_tbl().actor_id().delete(actor_id);
+        if ActorRow::is_airborne(&ctx.as_read_only(), actor_id) {
+            ActorRow::set_flags(ctx, actor_id, ActorFlags::AIRBORNE, false);
+        }
+    }
+}
+
+/// The landing rules, run for every actor touching down: falls further than the configured safe
+/// distance hurt (see [`fall_damage`] and [`GameConfigRow`]), except after safe launches.
+pub fn on_landed(ctx: &ReducerContext, landing: Landing) {
+    if landing.safe {
+        return;
+    }
+    let view_ctx = ctx.as_read_only();
+    let Some(health) = HealthRow::find(&view_ctx, landing.actor_id) else {
+        return;
+    };
+    let rules = GameConfigRow::get(&view_ctx).fall_damage_rules();
+    let amount = fall_damage(&rules, landing.fall_distance, health.data.max);
+    if amount == 0 {
+        return;
+    }
+    health.take_damage(ctx, None, amount, CombatEventKind::FallDamage);
+}
+
+/// Launches any actor straight up at `velocity_mps`, a `safe` launch never deals fall damage.
+/// Admin only.
+#[reducer]
+pub fn launch_actor(
+    ctx: &ReducerContext,
+    actor_id: ActorId,
+    velocity_mps: f32,
+    safe: bool,
+) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "launch_actor")?;
+    AirborneRow::launch(ctx, actor_id, velocity_mps, safe)
+}
@@ -0,0 +1,37 @@
+use crate::{
+    character_instance_tbl, current_server_tick, ActorRow, AdminIdentityRow, MoveIntentData,
+    MovementStateRow,
+};
+use shared::ActorFlags;
+use spacetimedb::{reducer, ReducerContext};
+
+/// Toggles GM fly mode for the admin's active character. Admin only.
+///
+/// While flying the movement tick skips gravity and ground snapping and accepts
+/// [`MoveIntentData::Point3`] targets, landing again starts a fall onto the ground below.
+#[reducer]
+pub fn set_fly_mode(ctx: &ReducerContext, enabled: bool) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "set_fly_mode")?;
+    let Some(ci) = ctx.db.character_instance_tbl().identity().find(ctx.sender) else {
+        return Err("Unable to find active character".into());
+    };
+    let Some(mut movement_state) = MovementStateRow::find(ctx, ci.actor_id) else {
+        return Err("Unable to find movement state for the active character".into());
+    };
+
+    ActorRow::set_flags(ctx, ci.actor_id, ActorFlags::FLYING, enabled);
+    if enabled {
+        movement_state.vertical_velocity = 0;
+    } else {
+        if matches!(movement_state.move_intent, MoveIntentData::Point3(_)) {
+            movement_state.move_intent = MoveIntentData::None;
+        }
+        movement_state.vertical_velocity = -1;
+    }
+    movement_state.should_move =
+        movement_state.vertical_velocity != 0 || movement_state.move_intent != MoveIntentData::None;
+    movement_state.server_tick = current_server_tick(ctx);
+    movement_state.update_from_self(ctx);
+    log::info!("Fly mode {} for actor {}", enabled, ci.actor_id);
+    Ok(())
+}
@@ -1,9 +1,21 @@
+pub mod airborne;
+pub mod fly_mode;
 pub mod move_intent;
+pub mod movement_history;
 pub mod movement_state;
 pub mod movement_tick;
+pub mod replay_capture;
 pub mod request_move;
+pub mod scripted_path;
+pub mod unstuck;
 
+pub use airborne::*;
+pub use fly_mode::*;
 pub use move_intent::*;
+pub use movement_history::*;
 pub use movement_state::*;
 pub use movement_tick::*;
+pub use replay_capture::*;
 pub use request_move::*;
+pub use scripted_path::*;
+pub use unstuck::*;
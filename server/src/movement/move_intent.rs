@@ -1,4 +1,4 @@
-use crate::{transform_tbl__view, Vec2};
+use crate::{transform_tbl__view, Vec2, Vec3};
 use rapier3d::parry::utils::hashmap::HashMap;
 use shared::ActorId;
 use spacetimedb::*;
@@ -15,6 +15,8 @@ pub enum MoveIntentData {
     Path(Vec<Vec2>),
     /// Movement toward an entity in the world (Actor)
     Actor(ActorId),
+    /// Movement straight toward a position in the air, only for flying actors (GM fly mode).
+    Point3(Vec3),
 }
 
 impl MoveIntentData {
@@ -24,6 +26,7 @@ impl MoveIntentData {
             MoveIntentData::None => None,
             MoveIntentData::Point(point) => Some(*point),
             MoveIntentData::Path(path) => path.first().copied(),
+            MoveIntentData::Point3(point) => Some(point.xz()),
             MoveIntentData::Actor(actor_id) => db
                 .transform_tbl()
                 .actor_id()
@@ -32,6 +35,14 @@ impl MoveIntentData {
         }
     }
 
+    /// The height of a 3D point target, `None` for the planar intents.
+    pub fn target_height(&self) -> Option<f32> {
+        match &self {
+            MoveIntentData::Point3(point) => Some(point.y),
+            _ => None,
+        }
+    }
+
     /// Gets the next target position for the given MoveIntent, preferring the
     /// cached position of the target actor when possible. Avoid additional index seeks, when
     /// there is an actor multiple others are trying to follow.
@@ -44,6 +55,7 @@ impl MoveIntentData {
             MoveIntentData::None => None,
             MoveIntentData::Point(point) => Some(*point),
             MoveIntentData::Path(path) => path.first().copied(),
+            MoveIntentData::Point3(point) => Some(point.xz()),
             MoveIntentData::Actor(actor_id) => match cache.get(actor_id) {
                 Some(pos) => Some(*pos),
                 None => db.transform_tbl().actor_id().find(actor_id).map(|t| {
@@ -0,0 +1,261 @@
+//! Recent positions of every moving actor, for lag compensation.
+//!
+//! The movement tick records where it moved each actor, [`position_at`] rewinds an actor to
+//! the moment a client saw the world so hit validation judges what the player actually aimed
+//! at. Attacks rewind through a [`Rewind`], capped at [`MAX_REWIND_MICROS`] so a client
+//! claiming an older view gets no more than that.
+//!
+//! The history is module memory, like the cached query worlds. It lives outside of the
+//! transaction and is lost on a module update, any actor without history is simply judged at
+//! its current position.
+
+use crate::{TransformRow, Vec3};
+use nalgebra::Vector3;
+use shared::ActorId;
+use spacetimedb::{ReducerContext, TimeDuration, Timestamp};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+};
+
+/// How far back (microseconds) positions are kept.
+pub const MOVEMENT_HISTORY_MICROS: i64 = 500_000;
+
+/// How far back (microseconds) an attack is compensated at most, within the history so a
+/// rewind always has samples around it.
+pub const MAX_REWIND_MICROS: i64 = 400_000;
+
+/// Upper bound of samples per actor, whatever the tick rate.
+const MAX_SAMPLES: usize = 64;
+
+#[derive(Debug, Clone, Copy)]
+struct PositionSample {
+    at: Timestamp,
+    translation: Vector3<f32>,
+}
+
+thread_local! {
+    /// Ring buffer of positions per actor, oldest first.
+    static HISTORY: RefCell<HashMap<ActorId, VecDeque<PositionSample>>> =
+        RefCell::new(HashMap::new());
+}
+
+fn window_start(now: Timestamp) -> Timestamp {
+    now - TimeDuration::from_micros(MOVEMENT_HISTORY_MICROS)
+}
+
+/// Records that the movement tick moved `actor_id` from `from` at `last_tick` to `to` at `now`.
+///
+/// An actor that starts moving after standing still gets its resting position at the previous
+/// tick as well, so the rewind doesn't slide it over the whole time it stood.
+pub fn record_movement(
+    actor_id: ActorId,
+    last_tick: Timestamp,
+    from: Vector3<f32>,
+    now: Timestamp,
+    to: Vector3<f32>,
+) {
+    HISTORY.with_borrow_mut(|history| {
+        let samples = history.entry(actor_id).or_default();
+        if samples.back().is_none_or(|last| last.at < last_tick) {
+            samples.push_back(PositionSample {
+                at: last_tick,
+                translation: from,
+            });
+        }
+        samples.push_back(PositionSample {
+            at: now,
+            translation: to,
+        });
+
+        // Keep the last sample before the window, the rewind interpolates from it.
+        let start = window_start(now);
+        while samples.len() > MAX_SAMPLES || samples.get(1).is_some_and(|next| next.at <= start) {
+            samples.pop_front();
+        }
+    });
+}
+
+/// Drops the history of actors that haven't moved within the window, they're judged at their
+/// current position anyway.
+///
+/// **Performance & Cost**: O(actors with history), no table access
+pub fn forget_stale_movement(now: Timestamp) {
+    let start = window_start(now);
+    HISTORY.with_borrow_mut(|history| {
+        history.retain(|_, samples| samples.back().is_some_and(|last| last.at > start));
+    });
+}
+
+/// The moment an attack is judged at: the attacker's perceived time, see [`Rewind::new`].
+#[derive(Debug, Clone, Copy)]
+pub struct Rewind {
+    at: Timestamp,
+}
+
+impl Rewind {
+    /// Judges at `seen_at`, the client's estimate of the server time of the world it saw.
+    ///
+    /// The client isn't trusted with it: a time in the future judges now and one further back
+    /// than [`MAX_REWIND_MICROS`] is clamped, a slow client gets compensated only so much.
+    pub fn new(ctx: &ReducerContext, seen_at: Option<Timestamp>) -> Self {
+        Self::clamped(ctx.timestamp, seen_at)
+    }
+
+    fn clamped(now: Timestamp, seen_at: Option<Timestamp>) -> Self {
+        let earliest = now - TimeDuration::from_micros(MAX_REWIND_MICROS);
+        Self {
+            at: seen_at.map_or(now, |seen_at| seen_at.clamp(earliest, now)),
+        }
+    }
+
+    /// Where `actor_id` was at the rewound time, see [`position_at`].
+    ///
+    /// **Performance & Cost**: a transform seek and a scan of the actor's samples
+    pub fn position(&self, ctx: &ReducerContext, actor_id: ActorId) -> Option<Vec3> {
+        position_at(ctx, actor_id, self.at)
+    }
+}
+
+/// The recorded position of `actor_id` at `at`, interpolated between samples. `None` when
+/// it hasn't moved since, or without any history.
+fn rewound_translation(actor_id: ActorId, at: Timestamp, now: Timestamp) -> Option<Vector3<f32>> {
+    let at = at.max(window_start(now));
+    HISTORY.with_borrow(|history| {
+        let samples = history.get(&actor_id)?;
+        if samples.back()?.at <= at {
+            return None;
+        }
+        let after = samples.iter().position(|sample| sample.at > at)?;
+        let Some(before) = after.checked_sub(1).map(|i| samples[i]) else {
+            return Some(samples[after].translation);
+        };
+        let after = samples[after];
+        let span = after.at.time_duration_since(before.at)?.to_micros();
+        let elapsed = at.time_duration_since(before.at)?.to_micros();
+        let t = if span > 0 {
+            elapsed as f32 / span as f32
+        } else {
+            1.0
+        };
+        Some(before.translation.lerp(&after.translation, t))
+    })
+}
+
+/// Where `actor_id` was at `at`, as far as the history reaches back.
+///
+/// Times after the last recorded movement (or without any) resolve to the current transform,
+/// `None` when the actor has none.
+///
+/// **Performance & Cost**: a transform seek and a scan of the actor's samples
+pub fn position_at(ctx: &ReducerContext, actor_id: ActorId, at: Timestamp) -> Option<Vec3> {
+    let current = TransformRow::find(ctx, actor_id)?.translation;
+    Some(rewound_translation(actor_id, at, ctx.timestamp).map_or(current, Vec3::from))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An arbitrary server time, far from the epoch.
+    const T0: i64 = 1_700_000_000_000_000;
+
+    fn at(micros: i64) -> Timestamp {
+        Timestamp::from_micros_since_unix_epoch(T0 + micros)
+    }
+
+    #[test]
+    fn interpolates_between_samples() {
+        record_movement(
+            1,
+            at(0),
+            Vector3::zeros(),
+            at(100_000),
+            Vector3::new(10.0, 0.0, 0.0),
+        );
+        record_movement(
+            1,
+            at(100_000),
+            Vector3::new(10.0, 0.0, 0.0),
+            at(200_000),
+            Vector3::new(10.0, 0.0, 4.0),
+        );
+        let now = at(200_000);
+
+        let first = rewound_translation(1, at(25_000), now).unwrap();
+        assert!((first - Vector3::new(2.5, 0.0, 0.0)).norm() < 1e-4);
+        let second = rewound_translation(1, at(150_000), now).unwrap();
+        assert!((second - Vector3::new(10.0, 0.0, 2.0)).norm() < 1e-4);
+        // Right on a sample.
+        let on_sample = rewound_translation(1, at(100_000), now).unwrap();
+        assert!((on_sample - Vector3::new(10.0, 0.0, 0.0)).norm() < 1e-4);
+    }
+
+    #[test]
+    fn standing_still_before_moving_doesnt_slide() {
+        // Stood at the origin, then moved on the last tick only.
+        record_movement(
+            2,
+            at(300_000),
+            Vector3::zeros(),
+            at(400_000),
+            Vector3::new(4.0, 0.0, 0.0),
+        );
+        let now = at(400_000);
+        let before = rewound_translation(2, at(100_000), now).unwrap();
+        assert_eq!(before, Vector3::zeros());
+    }
+
+    #[test]
+    fn rewind_is_clamped() {
+        let now = at(1_000_000);
+        assert_eq!(Rewind::clamped(now, None).at, now);
+        // The future judges now.
+        assert_eq!(Rewind::clamped(now, Some(at(1_500_000))).at, now);
+        assert_eq!(Rewind::clamped(now, Some(at(800_000))).at, at(800_000));
+        // No further back than `MAX_REWIND_MICROS`.
+        assert_eq!(
+            Rewind::clamped(now, Some(at(0))).at,
+            at(1_000_000 - MAX_REWIND_MICROS)
+        );
+    }
+
+    #[test]
+    fn clamped_rewind_interpolates_at_the_limit() {
+        // Moving 1m along x every 100ms tick for 1s.
+        for tick in 0..10 {
+            record_movement(
+                4,
+                at(tick * 100_000),
+                Vector3::new(tick as f32, 0.0, 0.0),
+                at((tick + 1) * 100_000),
+                Vector3::new((tick + 1) as f32, 0.0, 0.0),
+            );
+        }
+        let now = at(1_000_000);
+        // Seen long ago, judged 400ms back, on the sample at 600ms.
+        let rewind = Rewind::clamped(now, Some(at(50_000)));
+        let translation = rewound_translation(4, rewind.at, now).unwrap();
+        assert!((translation - Vector3::new(6.0, 0.0, 0.0)).norm() < 1e-4);
+        let rewind = Rewind::clamped(now, Some(at(650_000)));
+        let translation = rewound_translation(4, rewind.at, now).unwrap();
+        assert!((translation - Vector3::new(6.5, 0.0, 0.0)).norm() < 1e-4);
+    }
+
+    #[test]
+    fn without_history_is_the_current_position() {
+        assert_eq!(rewound_translation(5, at(0), at(100_000)), None);
+    }
+
+    #[test]
+    fn after_the_last_movement_is_the_current_position() {
+        record_movement(
+            3,
+            at(0),
+            Vector3::zeros(),
+            at(100_000),
+            Vector3::new(1.0, 0.0, 0.0),
+        );
+        assert_eq!(rewound_translation(3, at(100_000), at(300_000)), None);
+    }
+}
@@ -1,9 +1,12 @@
-use crate::{get_view_aoi_block, MoveIntentData};
+use crate::{get_view_aoi_actors, MoveIntentData, SurfaceMaterial};
 use shared::{ActorId, CellId};
 use spacetimedb::{table, ReducerContext, ViewContext};
 
 /// Ephemeral/computed & cached state for the owner's movement. This doesn't need to be persisted
 /// and can be removed when the owner is removed from the world.
+///
+/// This is the hot half of an actor (see [`crate::ActorRow`] for the cold half), keep fields
+/// here small and limited to what the movement tick writes.
 #[table(name=movement_state_tbl)]
 pub struct MovementStateRow {
     #[primary_key]
@@ -13,7 +16,7 @@ pub struct MovementStateRow {
     pub cell_id: CellId,
 
     /// Index-able column for the `move_intent` because SpacetimeType cannot be indexed.
-    /// Represents vertical_velocity < 0 || Some(move_intent)
+    /// Represents vertical_velocity != 0 || Some(move_intent)
     #[index(btree)]
     pub should_move: bool,
 
@@ -22,10 +25,21 @@ pub struct MovementStateRow {
     ///
     /// - `0` means grounded / no vertical motion.
     /// - Negative values mean falling downward.
+    /// - Positive values mean rising after a launch, see [`crate::AirborneRow::launch`].
     pub vertical_velocity: i8,
 
     /// The player's movement intentions
     pub move_intent: MoveIntentData,
+
+    /// The server tick this row was last written on, see [`crate::current_server_tick`].
+    pub server_tick: u32,
+
+    /// The surface material of the last ground contact, for footstep audio and effects.
+    pub ground_material: Option<SurfaceMaterial>,
+
+    /// The latest client intent sequence number applied to this actor (see `request_move`).
+    /// Carried on every write so the client can tell which of its predictions are acknowledged.
+    pub client_intent_seq: u32,
 }
 
 impl MovementStateRow {
@@ -50,11 +64,9 @@ impl MovementStateRow {
 /// Primary key of `ActorId`
 #[spacetimedb::view(name = movement_state_view, public)]
 pub fn movement_state_view(ctx: &ViewContext) -> Vec<MovementStateRow> {
-    let Some(cell_block) = get_view_aoi_block(ctx) else {
+    let Some(actors) = get_view_aoi_actors(ctx) else {
         return vec![];
     };
 
-    cell_block
-        .flat_map(|cell_id| MovementStateRow::by_cell_id(ctx, cell_id))
-        .collect()
+    actors.collect()
 }
@@ -1,25 +1,58 @@
 use crate::{
-    actor_tbl, movement_state_tbl, row_to_def, to_isometry3, world_static_tbl, MoveIntentData,
-    SecondaryStatsRow, TransformRow, Vec2,
-};
-use nalgebra::Vector2;
-use rapier3d::{
-    control::{CharacterAutostep, CharacterLength, KinematicCharacterController},
-    parry::utils::hashmap::HashMap,
-    prelude::{Capsule, QueryFilter},
+    actor_tbl, forget_stale_movement, get_static_query_world, movement_state_tbl, on_landed,
+    record_movement, world_static_tbl, AirborneRow, EventKind, EventLogRow, MonsterArchetypeRow,
+    MonsterInstanceRow, MoveIntentData, MovementStateRow, PartitionRow, ReplayCaptureRow,
+    ReplayFrameRow, ScriptedPathRow, SecondaryStatsRow, SpawnPointRow, SurfaceMaterial,
+    TimingStatsRow, TransformKeyframeRow, TransformRow, Vec2, WorldVersionRow, WriteStats,
 };
+use nalgebra::{Vector2, Vector3};
+use rapier3d::{parry::utils::hashmap::HashMap, prelude::QueryFilter};
 use shared::{
-    advance_vertical_velocity, constants::MICROS_1HZ, encode_cell_id, get_desired_delta,
-    is_at_target_planar, utils::build_static_query_world, yaw_from_xz, ActorId,
+    avoidance_direction, cells_in_radius, consume_reached_waypoint, encode_cell_id,
+    ground_static_id, is_at_target, is_at_target_planar, movement_kcc, movement_step_actor,
+    replay::ReplayFrame, utils::StaticQueryWorld, ActorFlags, ActorId, AvoidanceNeighbor,
+    InstanceId, MovementStepInput, AVOIDANCE_LOOKAHEAD, KILL_PLANE_Y,
+    MOVEMENT_TICK_INTERVAL_MICROS, MOVEMENT_TICK_INTERVAL_SECS, TRANSLATION_EPS_SQ,
 };
 use spacetimedb::{reducer, ReducerContext, ScheduleAt, Table, TimeDuration, Timestamp};
-use std::iter::once;
+use std::{cell::Cell, iter::once, rc::Rc};
 
 pub fn delta_time(now: Timestamp, last: Timestamp) -> Option<f32> {
     now.time_duration_since(last)
         .map(|dur| dur.to_micros() as f32 / 1_000_000.0)
 }
 
+/// Moves an actor that fell below the kill plane to the nearest spawn point of its instance and
+/// records the recovery in the event log. Returns `true` when the actor was moved.
+fn recover_out_of_bounds(
+    ctx: &ReducerContext,
+    instance_id: InstanceId,
+    transform: &mut TransformRow,
+    movement_state: &mut MovementStateRow,
+) -> bool {
+    let from = transform.translation;
+    let Some(spawn_point) = SpawnPointRow::nearest(ctx, instance_id, from) else {
+        log::error!("No spawn point to recover actor {}", transform.actor_id);
+        return false;
+    };
+
+    transform.translation = spawn_point.translation;
+    movement_state.move_intent = MoveIntentData::None;
+    // Start falling so the next tick snaps the actor to the ground at the spawn point.
+    movement_state.vertical_velocity = -1;
+
+    EventLogRow::record(
+        ctx,
+        EventKind::OutOfBoundsRecovery,
+        Some(transform.actor_id),
+        format!(
+            "Fell below kill plane at {:?}, moved to spawn point {}",
+            from, spawn_point.id
+        ),
+    );
+    true
+}
+
 #[spacetimedb::table(
     name = movement_tick_timer,
     scheduled(movement_tick_reducer)
@@ -32,10 +65,131 @@ pub struct MovementTickTimer {
 
     // Custom data for scheduled reducer:
     pub last_tick: Timestamp,
+
+    /// Monotonically increasing (wrapping) server tick number, incremented on every processed
+    /// tick and stamped onto the rows the tick writes.
+    pub tick: u32,
+}
+
+const TICK_INTERVAL_MICROS: i64 = MOVEMENT_TICK_INTERVAL_MICROS;
+pub(crate) const TICK_INTERVAL_SECS: f32 = MOVEMENT_TICK_INTERVAL_SECS;
+
+/// Per-tick caches kept between movement ticks so the hot loop reuses their allocations.
+#[derive(Default)]
+struct MovementTickScratch {
+    /// Planar positions of actors being moved towards. Rapier exposes a much faster HashMap,
+    /// 10x fewer CPU instructions.
+    target_xz_cache: HashMap<ActorId, Vec2>,
+    /// Surface material per world static id.
+    surface_cache: HashMap<u64, Option<SurfaceMaterial>>,
+    /// Ledge constraint per monster archetype id.
+    max_drop_cache: HashMap<u16, Option<f32>>,
+    /// Static query world and its version per instance, saves the version seek per actor.
+    query_world_cache: HashMap<InstanceId, (u64, Rc<StaticQueryWorld>)>,
+    /// Instance and capsule radius of actors met as avoidance neighbors.
+    neighbor_cache: HashMap<ActorId, Option<(InstanceId, f32)>>,
+}
+
+impl MovementTickScratch {
+    fn clear(&mut self) {
+        self.target_xz_cache.clear();
+        self.surface_cache.clear();
+        self.max_drop_cache.clear();
+        self.query_world_cache.clear();
+        self.neighbor_cache.clear();
+    }
+
+    /// The capacity of each cache, compared before and after a tick to count the ones that grew.
+    fn capacities(&self) -> [usize; 5] {
+        [
+            self.target_xz_cache.capacity(),
+            self.surface_cache.capacity(),
+            self.max_drop_cache.capacity(),
+            self.query_world_cache.capacity(),
+            self.neighbor_cache.capacity(),
+        ]
+    }
+}
+
+thread_local! {
+    static SCRATCH: Cell<MovementTickScratch> = Cell::default();
+}
+
+/// The [`SCRATCH`] caches lent to a tick, cleared, and handed back when dropped so they're
+/// reused however the tick ends.
+struct ScratchLoan(MovementTickScratch);
+
+impl ScratchLoan {
+    fn take() -> Self {
+        let mut scratch = SCRATCH.take();
+        scratch.clear();
+        Self(scratch)
+    }
 }
 
-const TICK_INTERVAL_MICROS: i64 = MICROS_1HZ;
-const TICK_INTERVAL_SECS: f32 = TICK_INTERVAL_MICROS as f32 / 1_000_000.0;
+impl Drop for ScratchLoan {
+    fn drop(&mut self) {
+        SCRATCH.set(std::mem::take(&mut self.0));
+    }
+}
+
+/// Extra distance (meters) below the capsule's bottom to look for the ground it's standing on.
+const GROUND_PROBE_SLACK: f32 = 0.2;
+
+/// Radius (meters) around a moving monster searched for actors to steer around.
+const AVOIDANCE_QUERY_RADIUS: f32 = 3.0;
+
+/// Bends a moving monster's planar target around the actors near it (see
+/// [`avoidance_direction`]), keeping its distance so the step's speed is unchanged. The actor
+/// being chased is the goal, not an obstacle.
+///
+/// **Performance & Cost**: O(cells * actors), a transform seek per candidate and an actor seek
+/// per distinct candidate per tick
+fn steer_around_neighbors(
+    ctx: &ReducerContext,
+    neighbor_cache: &mut HashMap<ActorId, Option<(InstanceId, f32)>>,
+    actor_id: ActorId,
+    instance_id: InstanceId,
+    chased: Option<ActorId>,
+    mover: AvoidanceNeighbor,
+    target: Vector2<f32>,
+) -> Vector2<f32> {
+    let AvoidanceNeighbor {
+        position: current,
+        radius,
+    } = mover;
+    let to_target = target - current;
+    let distance = to_target.norm();
+    // Close to the goal the neighbors are likely there for the same reason, just arrive.
+    if distance <= radius + AVOIDANCE_LOOKAHEAD {
+        return target;
+    }
+
+    let neighbors = cells_in_radius(current.x, current.y, AVOIDANCE_QUERY_RADIUS)
+        .flat_map(|cell_id| ctx.db.movement_state_tbl().cell_id().filter(cell_id))
+        .filter(|ms| ms.actor_id != actor_id && Some(ms.actor_id) != chased)
+        .filter_map(|ms| {
+            let (neighbor_instance, neighbor_radius) =
+                (*neighbor_cache.entry(ms.actor_id).or_insert_with(|| {
+                    ctx.db
+                        .actor_tbl()
+                        .id()
+                        .find(ms.actor_id)
+                        .filter(|actor| !actor.flags().contains(ActorFlags::POOLED))
+                        .map(|actor| (actor.instance_id, actor.capsule.radius))
+                }))?;
+            if neighbor_instance != instance_id {
+                return None;
+            }
+            let transform = TransformRow::find(ctx, ms.actor_id)?;
+            Some(AvoidanceNeighbor {
+                position: transform.translation.xz().into(),
+                radius: neighbor_radius,
+            })
+        });
+    let direction = avoidance_direction(current, radius, to_target / distance, neighbors);
+    current + direction * distance
+}
 
 pub fn init_movement_tick(ctx: &ReducerContext) {
     ctx.db.movement_tick_timer().scheduled_id().delete(1);
@@ -43,10 +197,22 @@ pub fn init_movement_tick(ctx: &ReducerContext) {
         scheduled_id: 1,
         scheduled_at: ScheduleAt::Interval(TimeDuration::from_micros(TICK_INTERVAL_MICROS)),
         last_tick: ctx.timestamp,
+        tick: 0,
     });
     log::info!("init movement_tick");
 }
 
+/// The most recent server tick number, used to stamp rows written outside of the movement tick
+/// (e.g. by reducers) so clients can order all authoritative updates.
+pub fn current_server_tick(ctx: &ReducerContext) -> u32 {
+    ctx.db
+        .movement_tick_timer()
+        .scheduled_id()
+        .find(1)
+        .map(|timer| timer.tick)
+        .unwrap_or(0)
+}
+
 #[reducer]
 fn movement_tick_reducer(ctx: &ReducerContext, mut timer: MovementTickTimer) -> Result<(), String> {
     if ctx.sender != ctx.identity() {
@@ -58,125 +224,252 @@ fn movement_tick_reducer(ctx: &ReducerContext, mut timer: MovementTickTimer) ->
     let mut movement_states = ctx.db.movement_state_tbl().should_move().filter(true);
     let Some(first_movement_state) = movement_states.next() else {
         log::info!("No movement states to process");
+        // Idle runs count too, the interval between runs is what load shedding measures (see
+        // `crate::load_shedding`) and an idle gap isn't load.
+        TimingStatsRow::record(ctx, TimingStatsRow::MOVEMENT_TICK, WriteStats::default());
         return Ok(());
     };
 
+    timer.tick = timer.tick.wrapping_add(1);
+    let server_tick = timer.tick;
+    // Rows written during this tick by shared helpers stamp `current_server_tick`, which reads
+    // the stored timer: store the new tick before anything else.
+    if let Some(mut stored) = ctx
+        .db
+        .movement_tick_timer()
+        .scheduled_id()
+        .find(timer.scheduled_id)
+    {
+        stored.tick = server_tick;
+        ctx.db.movement_tick_timer().scheduled_id().update(stored);
+    }
+
     let dt = delta_time(ctx.timestamp, timer.last_tick)
         .unwrap_or(TICK_INTERVAL_SECS)
         .min(TICK_INTERVAL_SECS * 1.2);
 
-    let kcc = KinematicCharacterController {
-        autostep: Some(CharacterAutostep {
-            include_dynamic_bodies: false,
-            max_height: CharacterLength::Relative(0.4),
-            ..CharacterAutostep::default()
-        }),
-        offset: CharacterLength::Relative(0.025),
-        ..KinematicCharacterController::default()
-    };
-
-    // Build the rapier physics world
-    let world_defs = ctx.db.world_static_tbl().iter().map(row_to_def);
-    let query_world = build_static_query_world(world_defs, dt);
-    let query_pipeline = query_world.as_query_pipeline(QueryFilter::only_fixed());
+    let kcc = movement_kcc();
 
-    // Initialize a actor location cache. Rapier exposes a much faster HashMap, 10x fewer CPU instructions.
-    let mut target_xz_cache: HashMap<ActorId, Vec2> = HashMap::default();
+    // Reuse the caches from the previous tick, they keep their capacity so the loop below doesn't
+    // allocate once warmed up.
+    let mut scratch = ScratchLoan::take();
+    let capacities = scratch.0.capacities();
+    let MovementTickScratch {
+        target_xz_cache,
+        surface_cache,
+        max_drop_cache,
+        query_world_cache,
+        neighbor_cache,
+    } = &mut scratch.0;
     let view_ctx = ctx.as_read_only();
+    let replay_capture = ReplayCaptureRow::find(ctx);
+    let mut write_stats = WriteStats::default();
     for mut movement_state in once(first_movement_state).chain(movement_states) {
         let actor_id = movement_state.actor_id;
         let Some(mut owner_transform) = TransformRow::find(ctx, actor_id) else {
             log::error!("Failed to find transform for actor_id {}", actor_id);
             continue;
         };
-        let Some(capsule) = ctx.db.actor_tbl().id().find(actor_id).map(|a| a.capsule) else {
+        let Some((capsule, instance_id, flying, airborne)) =
+            ctx.db.actor_tbl().id().find(actor_id).map(|a| {
+                (
+                    a.capsule,
+                    a.instance_id,
+                    a.flags().contains(ActorFlags::FLYING),
+                    a.flags().contains(ActorFlags::AIRBORNE),
+                )
+            })
+        else {
             log::error!("Failed to find transform for actor_id {}", actor_id);
             continue;
         };
 
-        let current_planar: Vector2<f32> = owner_transform.translation.xz().into();
-        let target_planar: Vector2<f32> = movement_state
-            .move_intent
-            .target_position_with_cache(&view_ctx.db, &mut target_xz_cache)
-            .map(|pos| pos.into())
-            .unwrap_or(current_planar);
+        // The rapier physics world of each instance is only rebuilt when its static world changes
+        let (world_version, query_world) = query_world_cache
+            .entry(instance_id)
+            .or_insert_with(|| {
+                (
+                    WorldVersionRow::current(ctx, instance_id),
+                    get_static_query_world(ctx, instance_id),
+                )
+            })
+            .clone();
+        let query_pipeline = query_world.as_query_pipeline(QueryFilter::only_fixed());
 
-        let mut movement_state_dirty = false;
-        let is_falling = movement_state.vertical_velocity < 0;
-        if is_falling {
-            let vq = advance_vertical_velocity(movement_state.vertical_velocity, dt);
-            if vq != movement_state.vertical_velocity {
-                movement_state.vertical_velocity = vq;
-                movement_state_dirty = true;
-            }
-        }
+        let current_planar: Vector2<f32> = owner_transform.translation.xz().into();
+        // Launched actors go straight up and down, whatever they intend to do waits for the landing.
+        let target_planar: Vector2<f32> = if airborne {
+            current_planar
+        } else {
+            movement_state
+                .move_intent
+                .target_position_with_cache(&view_ctx.db, target_xz_cache)
+                .map(|pos| pos.into())
+                .unwrap_or(current_planar)
+        };
+        let target_y = if flying {
+            movement_state.move_intent.target_height()
+        } else {
+            None
+        };
 
-        let Some(movement_speed_mps) = SecondaryStatsRow::find(&view_ctx, actor_id)
-            .map(|secondary_stats| secondary_stats.movement_speed)
-        else {
+        let monster = MonsterInstanceRow::find(&view_ctx, actor_id);
+        // Monsters steer around each other, players go where they clicked.
+        let steered_planar = if monster.is_some() && !flying {
+            let chased = match movement_state.move_intent {
+                MoveIntentData::Actor(chased) => Some(chased),
+                _ => None,
+            };
+            steer_around_neighbors(
+                ctx,
+                neighbor_cache,
+                actor_id,
+                instance_id,
+                chased,
+                AvoidanceNeighbor {
+                    position: current_planar,
+                    radius: capsule.radius,
+                },
+                target_planar,
+            )
+        } else {
+            target_planar
+        };
+        // Only monsters follow scripted paths, players don't pay for the seek.
+        let speed_override = monster
+            .as_ref()
+            .and_then(|_| ScriptedPathRow::speed_override(ctx, actor_id));
+        // The effective speed, slows and hastes are already resolved into it (see
+        // `SpeedModifierRow`) so it matches what clients extrapolate with.
+        let Some(movement_speed_mps) = speed_override.or_else(|| {
+            SecondaryStatsRow::find(&view_ctx, actor_id)
+                .map(|secondary_stats| secondary_stats.movement_speed)
+        }) else {
             log::error!("Failed to find secondary stats for entity {}", actor_id);
             continue;
         };
 
-        let direction = (target_planar - current_planar)
-            .try_normalize(0.0)
-            .unwrap_or_default();
+        // Only monsters are kept from walking off ledges, players go wherever they click.
+        let max_drop = monster.and_then(|monster| {
+            *max_drop_cache
+                .entry(monster.archetype_id)
+                .or_insert_with(|| {
+                    MonsterArchetypeRow::find(ctx, monster.archetype_id)
+                        .and_then(|archetype| archetype.max_drop)
+                })
+        });
 
-        if let Some(yaw) = yaw_from_xz(direction) {
-            owner_transform.yaw = yaw;
+        let input = MovementStepInput {
+            translation: owner_transform.translation.into(),
+            yaw: owner_transform.yaw,
+            capsule_radius: capsule.radius,
+            capsule_half_height: capsule.half_height,
+            target_planar: steered_planar,
+            movement_speed_mps,
+            vertical_velocity: movement_state.vertical_velocity,
+            max_drop,
+            flying,
+            target_y,
+        };
+        let step = movement_step_actor(&kcc, &query_pipeline, &input, dt);
+        if let Some(capture) = &replay_capture {
+            if capture.should_record(actor_id) {
+                ReplayFrameRow::record(
+                    ctx,
+                    instance_id,
+                    &ReplayFrame {
+                        server_tick,
+                        actor_id,
+                        dt,
+                        world_version,
+                        input,
+                        output: step,
+                    },
+                );
+            }
         }
 
-        let correction = kcc.move_shape(
-            dt,
-            &query_pipeline,
-            &Capsule::new_y(capsule.half_height, capsule.radius),
-            &to_isometry3(&owner_transform),
-            get_desired_delta(
-                current_planar,
-                target_planar,
-                movement_speed_mps,
-                movement_state.vertical_velocity,
-                dt,
-            ),
-            |_| {},
-        );
-
-        owner_transform.translation.x += correction.translation.x;
-        owner_transform.translation.y += correction.translation.y;
-        owner_transform.translation.z += correction.translation.z;
-
-        // Ground truth for grounding comes from KCC.
-        //
-        // - If KCC reports grounded, we stop falling (set vv=0).
-        // - If KCC reports not grounded, we ensure falling has started (vv is at least -1),
-        //   even if vv was previously 0 for any reason.
-        if correction.grounded {
-            if movement_state.vertical_velocity != 0 {
-                movement_state.vertical_velocity = 0;
-                movement_state_dirty = true;
-            }
-        } else {
-            if movement_state.vertical_velocity == 0 {
-                movement_state.vertical_velocity = -1;
+        let mut transform_dirty = false;
+        if owner_transform.yaw != step.yaw {
+            owner_transform.yaw = step.yaw;
+            transform_dirty = true;
+        }
+        let moved = (step.translation - input.translation).norm_squared() > TRANSLATION_EPS_SQ;
+        if moved {
+            transform_dirty = true;
+        }
+        owner_transform.translation = step.translation.into();
+
+        let mut movement_state_dirty = false;
+        if movement_state.vertical_velocity != step.vertical_velocity {
+            movement_state.vertical_velocity = step.vertical_velocity;
+            movement_state_dirty = true;
+        }
+
+        // Track the peak of the time in the air, the landing turns it into the fall distance.
+        if step.vertical_velocity != 0 {
+            AirborneRow::track(ctx, actor_id, input.translation.y.max(step.translation.y));
+        } else if input.vertical_velocity != 0 {
+            on_landed(
+                ctx,
+                AirborneRow::land(ctx, actor_id, owner_transform.translation.y),
+            );
+        }
+
+        if owner_transform.translation.y < KILL_PLANE_Y
+            && recover_out_of_bounds(ctx, instance_id, &mut owner_transform, &mut movement_state)
+        {
+            // The fall below the kill plane isn't held against the actor at the spawn point.
+            AirborneRow::delete_for_actor(ctx, actor_id);
+            transform_dirty = true;
+            movement_state_dirty = true;
+        }
+
+        if step.grounded {
+            let ground_material = ground_static_id(
+                &query_world,
+                &query_pipeline,
+                owner_transform.translation.into(),
+                capsule.half_height + capsule.radius + GROUND_PROBE_SLACK,
+            )
+            .and_then(|id| {
+                *surface_cache.entry(id).or_insert_with(|| {
+                    ctx.db
+                        .world_static_tbl()
+                        .id()
+                        .find(id)
+                        .and_then(|ws| ws.surface_material)
+                })
+            });
+            if movement_state.ground_material != ground_material {
+                movement_state.ground_material = ground_material;
                 movement_state_dirty = true;
             }
         }
 
         let cell_id = encode_cell_id(owner_transform.translation.x, owner_transform.translation.z);
         if movement_state.cell_id != cell_id {
+            PartitionRow::hand_off(ctx, actor_id, instance_id, movement_state.cell_id, cell_id);
             movement_state.cell_id = cell_id;
             movement_state_dirty = true;
         }
 
-        if is_at_target_planar(owner_transform.translation.xz().into(), target_planar) {
+        let at_target = match target_y {
+            Some(target_y) => is_at_target(
+                owner_transform.translation.into(),
+                Vector3::new(target_planar.x, target_y, target_planar.y),
+            ),
+            None => is_at_target_planar(owner_transform.translation.xz().into(), target_planar),
+        };
+        if at_target {
             let clear_intent = match &mut movement_state.move_intent {
                 MoveIntentData::Point(_) => true,
+                MoveIntentData::Point3(_) => true,
                 MoveIntentData::Actor(_) => true,
                 MoveIntentData::Path(path) => {
-                    if !path.is_empty() {
-                        path.remove(0);
-                    }
-                    path.is_empty()
+                    consume_reached_waypoint(path, owner_transform.translation.xz().into(), |p| {
+                        (*p).into()
+                    })
                 }
                 MoveIntentData::None => false,
             };
@@ -185,19 +478,56 @@ fn movement_tick_reducer(ctx: &ReducerContext, mut timer: MovementTickTimer) ->
                 movement_state_dirty = true;
             }
         }
-        let should_move =
-            movement_state.move_intent != MoveIntentData::None || !correction.grounded;
+        // Flying actors hover where they stopped, only falling (or rising) actors keep moving
+        // without intent.
+        let should_move = movement_state.move_intent != MoveIntentData::None
+            || movement_state.vertical_velocity != 0
+            || (!step.grounded && !flying);
         if movement_state.should_move != should_move {
             movement_state.should_move = should_move;
             movement_state_dirty = true;
         }
 
-        owner_transform.update_from_self(ctx);
+        if moved {
+            record_movement(
+                actor_id,
+                timer.last_tick,
+                input.translation,
+                ctx.timestamp,
+                owner_transform.translation.into(),
+            );
+        }
+        if transform_dirty {
+            owner_transform.server_tick = server_tick;
+            owner_transform.client_intent_seq = movement_state.client_intent_seq;
+        }
+
+        // Far viewers get a keyframe on the actor's staggered keyframe tick, or as soon as it
+        // comes to rest so they never see a stale final position.
+        if TransformKeyframeRow::is_keyframe_tick(actor_id, server_tick) || !should_move {
+            write_stats.record(TransformKeyframeRow::from(&owner_transform).update_if_changed(ctx));
+        }
+
+        // Flagged as they change above rather than compared, moves within `TRANSLATION_EPS_SQ`
+        // aren't replicated.
+        if transform_dirty {
+            owner_transform.update_from_self(ctx);
+        }
+        write_stats.record(transform_dirty);
         if movement_state_dirty {
+            movement_state.server_tick = server_tick;
             movement_state.update_from_self(ctx);
         }
+        write_stats.record(movement_state_dirty);
     }
 
+    write_stats.scratch_growths = capacities
+        .into_iter()
+        .zip(scratch.0.capacities())
+        .filter(|(before, after)| after > before)
+        .count() as u32;
+    forget_stale_movement(ctx.timestamp);
+    TimingStatsRow::record(ctx, TimingStatsRow::MOVEMENT_TICK, write_stats);
     timer.last_tick = ctx.timestamp;
     ctx.db.movement_tick_timer().scheduled_id().update(timer);
 
@@ -0,0 +1,216 @@
+use crate::{get_static_query_world, AdminIdentityRow, CapsuleY, Vec2, Vec3, WorldVersionRow};
+use shared::{
+    replay::{replay_frames, ReplayFrame},
+    ActorId, InstanceId, MovementStepInput, MovementStepOutput,
+};
+use spacetimedb::{reducer, table, ReducerContext, Table, Timestamp};
+use std::collections::BTreeMap;
+
+/// Singleton row, present while the movement tick is recording [`ReplayFrameRow`]s.
+///
+/// Debug only and admin controlled, capture writes a row per moved actor per tick.
+#[table(name=replay_capture_tbl)]
+pub struct ReplayCaptureRow {
+    #[primary_key]
+    pub id: u8,
+
+    /// Only record this actor, `None` records every moved actor.
+    pub actor_id: Option<ActorId>,
+
+    pub started_at: Timestamp,
+}
+
+impl ReplayCaptureRow {
+    const ID: u8 = 0;
+
+    pub fn find(ctx: &ReducerContext) -> Option<Self> {
+        ctx.db.replay_capture_tbl().id().find(Self::ID)
+    }
+
+    pub fn should_record(&self, actor_id: ActorId) -> bool {
+        self.actor_id.is_none_or(|id| id == actor_id)
+    }
+}
+
+/// All inputs and the result of one actor's movement step, see [`shared::replay`].
+///
+/// Private, frames hold other players' inputs and positions: export them as the module owner
+/// (e.g. `spacetime sql`), or verify them in place with [`verify_replay_capture`].
+#[table(name=replay_frame_tbl)]
+pub struct ReplayFrameRow {
+    #[auto_inc]
+    #[primary_key]
+    pub id: u64,
+
+    pub server_tick: u32,
+    pub actor_id: ActorId,
+    pub dt: f32,
+
+    /// The instance the actor moved in, `world_version` is that instance's version.
+    pub instance_id: InstanceId,
+    pub world_version: u64,
+
+    // Input
+    pub translation: Vec3,
+    pub yaw: f32,
+    pub capsule: CapsuleY,
+    pub target_planar: Vec2,
+    pub movement_speed: f32,
+    pub vertical_velocity: i8,
+    pub max_drop: Option<f32>,
+    pub flying: bool,
+    pub target_y: Option<f32>,
+
+    // Output
+    pub out_translation: Vec3,
+    pub out_yaw: f32,
+    pub out_vertical_velocity: i8,
+    pub out_grounded: bool,
+}
+
+impl ReplayFrameRow {
+    pub fn record(ctx: &ReducerContext, instance_id: InstanceId, frame: &ReplayFrame) {
+        ctx.db
+            .replay_frame_tbl()
+            .insert(Self::new(instance_id, frame));
+    }
+
+    fn new(instance_id: InstanceId, frame: &ReplayFrame) -> Self {
+        Self {
+            id: 0,
+            server_tick: frame.server_tick,
+            actor_id: frame.actor_id,
+            dt: frame.dt,
+            instance_id,
+            world_version: frame.world_version,
+            translation: frame.input.translation.into(),
+            yaw: frame.input.yaw,
+            capsule: CapsuleY {
+                radius: frame.input.capsule_radius,
+                half_height: frame.input.capsule_half_height,
+            },
+            target_planar: frame.input.target_planar.into(),
+            movement_speed: frame.input.movement_speed_mps,
+            vertical_velocity: frame.input.vertical_velocity,
+            max_drop: frame.input.max_drop,
+            flying: frame.input.flying,
+            target_y: frame.input.target_y,
+            out_translation: frame.output.translation.into(),
+            out_yaw: frame.output.yaw,
+            out_vertical_velocity: frame.output.vertical_velocity,
+            out_grounded: frame.output.grounded,
+        }
+    }
+}
+
+impl From<ReplayFrameRow> for ReplayFrame {
+    fn from(row: ReplayFrameRow) -> Self {
+        Self {
+            server_tick: row.server_tick,
+            actor_id: row.actor_id,
+            dt: row.dt,
+            world_version: row.world_version,
+            input: MovementStepInput {
+                translation: row.translation.into(),
+                yaw: row.yaw,
+                capsule_radius: row.capsule.radius,
+                capsule_half_height: row.capsule.half_height,
+                target_planar: row.target_planar.into(),
+                movement_speed_mps: row.movement_speed,
+                vertical_velocity: row.vertical_velocity,
+                max_drop: row.max_drop,
+                flying: row.flying,
+                target_y: row.target_y,
+            },
+            output: MovementStepOutput {
+                translation: row.out_translation.into(),
+                yaw: row.out_yaw,
+                vertical_velocity: row.out_vertical_velocity,
+                grounded: row.out_grounded,
+            },
+        }
+    }
+}
+
+/// Starts recording movement tick frames, clearing any previous capture.
+#[reducer]
+pub fn start_replay_capture(ctx: &ReducerContext, actor_id: Option<ActorId>) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "start_replay_capture")?;
+    for row in ctx.db.replay_frame_tbl().iter() {
+        ctx.db.replay_frame_tbl().delete(row);
+    }
+    ctx.db
+        .replay_capture_tbl()
+        .id()
+        .delete(ReplayCaptureRow::ID);
+    ctx.db.replay_capture_tbl().insert(ReplayCaptureRow {
+        id: ReplayCaptureRow::ID,
+        actor_id,
+        started_at: ctx.timestamp,
+    });
+    log::info!("Started replay capture for {:?}", actor_id);
+    Ok(())
+}
+
+/// Stops recording, the captured frames are kept until the next capture starts.
+#[reducer]
+pub fn stop_replay_capture(ctx: &ReducerContext) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "stop_replay_capture")?;
+    ctx.db
+        .replay_capture_tbl()
+        .id()
+        .delete(ReplayCaptureRow::ID);
+    log::info!(
+        "Stopped replay capture, {} frames recorded",
+        ctx.db.replay_frame_tbl().count()
+    );
+    Ok(())
+}
+
+/// Replays the captured frames against the current static world of their instance and logs the
+/// first divergence.
+///
+/// Frames captured against another world version can't be replayed here, export the rows and
+/// use [`shared::replay::replay_frames`] with that world instead.
+#[reducer]
+pub fn verify_replay_capture(ctx: &ReducerContext) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "verify_replay_capture")?;
+    let mut rows: Vec<ReplayFrameRow> = ctx.db.replay_frame_tbl().iter().collect();
+    // Recording order, the server tick wraps.
+    rows.sort_by_key(|row| row.id);
+    let mut instances: BTreeMap<InstanceId, Vec<ReplayFrame>> = BTreeMap::new();
+    for row in rows {
+        if row.world_version != WorldVersionRow::current(ctx, row.instance_id) {
+            return Err("Replay frames were captured against another world version".into());
+        }
+        instances
+            .entry(row.instance_id)
+            .or_default()
+            .push(ReplayFrame::from(row));
+    }
+
+    for (instance_id, frames) in instances {
+        match replay_frames(&get_static_query_world(ctx, instance_id), &frames) {
+            Ok(()) => {
+                log::info!(
+                    "Replayed {} frames of instance {} without divergence",
+                    frames.len(),
+                    instance_id
+                );
+            }
+            Err(divergence) => {
+                log::error!(
+                    "Replay diverged at frame {} of instance {} (tick {}, actor {}): recorded {:?}, replayed {:?}",
+                    divergence.index,
+                    instance_id,
+                    divergence.frame.server_tick,
+                    divergence.frame.actor_id,
+                    divergence.frame.output,
+                    divergence.replayed
+                );
+                return Err("Replay diverged".into());
+            }
+        }
+    }
+    Ok(())
+}
@@ -1,6 +1,13 @@
-use crate::{character_instance_tbl, movement_state_tbl, transform_tbl, MoveIntentData};
+use crate::{
+    character_instance_tbl, current_server_tick, movement_state_tbl, transform_tbl, ActivityRow,
+    ActorRow, EmoteRow, MoveIntentData, TutorialProgressRow, Vec2, WorldBoundsRow,
+};
 use nalgebra::Vector2;
-use shared::utils::{is_move_too_close, is_move_too_far};
+use shared::{
+    is_tick_newer,
+    utils::{is_move_too_close, is_move_too_far},
+    validate, TutorialFlags,
+};
 use spacetimedb::{reducer, ReducerContext};
 
 /// Request a movement intent for the player's active character.
@@ -9,12 +16,54 @@ use spacetimedb::{reducer, ReducerContext};
 /// - `movement_state_tbl.move_intent` stores the current intent.
 /// - `movement_state_tbl.should_move` is kept consistent with the movement tick:
 ///     `should_move = (move_intent != MoveIntentData::None) || !grounded`
+///
+/// Acknowledgement contract:
+/// - `seq` is a client-side, monotonically increasing (wrapping) intent sequence number.
+/// - Requests with a `seq` older than the last applied one are stale and dropped.
+/// - An applied request stores `seq` as `movement_state_tbl.client_intent_seq`, the movement tick
+///   then copies it onto `transform_tbl.client_intent_seq` with every authoritative write.
+/// - Ignored duplicates are not written, they're acknowledged implicitly by any later `seq`.
+///
+/// Point and path targets beyond the world bounds (see [`WorldBoundsRow`]) or non-finite are
+/// rejected, 3D targets have their height clamped to the world. 3D point targets are only
+/// accepted while the character is flying, see [`crate::set_fly_mode`].
 #[reducer]
-pub fn request_move(ctx: &ReducerContext, intent: MoveIntentData) -> Result<(), String> {
+pub fn request_move(ctx: &ReducerContext, intent: MoveIntentData, seq: u32) -> Result<(), String> {
+    let bounds = WorldBoundsRow::get(&ctx.as_read_only());
+    let intent = match intent {
+        MoveIntentData::Point(point) => {
+            MoveIntentData::Point(bounds.check_xz(point.into())?.into())
+        }
+        MoveIntentData::Path(path) => MoveIntentData::Path(
+            path.into_iter()
+                .map(|point| bounds.check_xz(point.into()).map(Vec2::from))
+                .collect::<Result<_, _>>()?,
+        ),
+        MoveIntentData::Point3(point) => {
+            let point = validate::clamped_to_world(point.into())?;
+            bounds.check_xz(point.xz())?;
+            MoveIntentData::Point3(point.into())
+        }
+        intent => intent,
+    };
+
     let Some(ci) = ctx.db.character_instance_tbl().identity().find(ctx.sender) else {
         log::error!("Unable to find active character");
         return Err("Unable to find active character".into());
     };
+    ActivityRow::touch(ctx);
+
+    if ActorRow::is_dead(&ctx.as_read_only(), ci.actor_id) {
+        return Err("Dead actors can't move".into());
+    }
+    if ActorRow::is_airborne(&ctx.as_read_only(), ci.actor_id) {
+        return Err("Airborne actors can't move".into());
+    }
+    if matches!(intent, MoveIntentData::Point3(_))
+        && !ActorRow::is_flying(&ctx.as_read_only(), ci.actor_id)
+    {
+        return Err("Only flying actors can move in 3D".into());
+    }
 
     let Some(transform_row) = ctx.db.transform_tbl().actor_id().find(ci.actor_id) else {
         log::error!("Unable to find transform for the active character");
@@ -29,6 +78,11 @@ pub fn request_move(ctx: &ReducerContext, intent: MoveIntentData) -> Result<(),
         return Err("Unable to find movement state for the active character".into());
     };
 
+    if is_tick_newer(movement_state.client_intent_seq, seq) {
+        log::info!("Ignoring stale move intent seq {}", seq);
+        return Ok(());
+    }
+
     // Should we ignore this request based on our current intent?
     if movement_state.move_intent != MoveIntentData::None {
         let current_intent = &movement_state.move_intent;
@@ -62,6 +116,14 @@ pub fn request_move(ctx: &ReducerContext, intent: MoveIntentData) -> Result<(),
                 return Err("Distance from current position too close".into());
             }
         }
+        MoveIntentData::Point3(point) => {
+            if is_move_too_far(current, point.xz().into()) {
+                log::info!(
+                    "Ignoring move intent due to distance from current position being too far"
+                );
+                return Err("Distance from current position too far".into());
+            }
+        }
         MoveIntentData::Path(path) => {
             if path.iter().any(|x| is_move_too_far(current, (*x).into())) {
                 log::info!(
@@ -86,9 +148,15 @@ pub fn request_move(ctx: &ReducerContext, intent: MoveIntentData) -> Result<(),
         }
     }
 
+    if intent != MoveIntentData::None {
+        EmoteRow::stop(ctx, ci.actor_id);
+        TutorialProgressRow::complete(ctx, ci.actor_id, TutorialFlags::FIRST_MOVE);
+    }
     movement_state.should_move =
-        movement_state.vertical_velocity < 0 || intent != MoveIntentData::None;
+        movement_state.vertical_velocity != 0 || intent != MoveIntentData::None;
     movement_state.move_intent = intent;
+    movement_state.server_tick = current_server_tick(ctx);
+    movement_state.client_intent_seq = seq;
 
     ctx.db
         .movement_state_tbl()
@@ -98,8 +166,10 @@ pub fn request_move(ctx: &ReducerContext, intent: MoveIntentData) -> Result<(),
     Ok(())
 }
 
+/// Cancels the active character's movement intent, following the same `seq` contract as
+/// [`request_move`].
 #[reducer]
-pub fn cancel_move(ctx: &ReducerContext) -> Result<(), String> {
+pub fn cancel_move(ctx: &ReducerContext, seq: u32) -> Result<(), String> {
     let Some(ci) = ctx.db.character_instance_tbl().identity().find(ctx.sender) else {
         return Err("Unable to find active character".into());
     };
@@ -108,8 +178,15 @@ pub fn cancel_move(ctx: &ReducerContext) -> Result<(), String> {
         return Err("Unable to find movement state for the active character".into());
     };
 
+    if is_tick_newer(movement_state.client_intent_seq, seq) {
+        log::info!("Ignoring stale cancel move seq {}", seq);
+        return Ok(());
+    }
+
     movement_state.move_intent = MoveIntentData::None;
-    movement_state.should_move = movement_state.vertical_velocity < 0;
+    movement_state.should_move = movement_state.vertical_velocity != 0;
+    movement_state.server_tick = current_server_tick(ctx);
+    movement_state.client_intent_seq = seq;
 
     ctx.db
         .movement_state_tbl()
@@ -0,0 +1,289 @@
+use crate::{
+    current_server_tick, ActorRow, AdminIdentityRow, MonsterInstanceRow, MoveIntentData,
+    MovementStateRow, TimingStatsRow, TransformRow, Vec2, WriteStats,
+};
+use nalgebra::Vector3;
+use shared::{planar_distance_sq, validate, ActorId};
+use spacetimedb::{reducer, table, ReducerContext, ScheduleAt, SpacetimeType, Table, Timestamp};
+use std::time::Duration;
+
+/// How often scripted paths hand out their next waypoint.
+pub(crate) const SCRIPTED_PATH_INTERVAL_MILLIS: u64 = 250;
+
+/// How close (meters) an actor has to get to a waypoint for it to count as reached. Looser than
+/// the movement tick's arrival check so actors blocked just short of a waypoint still go on.
+const WAYPOINT_REACHED_RADIUS: f32 = 0.5;
+const WAYPOINT_REACHED_RADIUS_SQ: f32 = WAYPOINT_REACHED_RADIUS * WAYPOINT_REACHED_RADIUS;
+
+/// Bounds for [`ScriptedPathRow::speed_override`] (meters/second).
+pub(crate) const MAX_SPEED_OVERRIDE: f32 = 20.0;
+
+/// Max waypoints of a single path.
+const MAX_WAYPOINTS: usize = 64;
+
+/// What a scripted path does once its last waypoint is reached.
+#[derive(SpacetimeType, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathLoopMode {
+    /// The path is deleted and the actor stays at the last waypoint.
+    Once,
+    /// Continues from the first waypoint, e.g. a guard's patrol circuit.
+    Loop,
+    /// Walks the waypoints back in reverse, e.g. a caravan between two towns.
+    PingPong,
+}
+
+#[derive(SpacetimeType, Debug, Clone, Copy, PartialEq)]
+pub struct ScriptedWaypoint {
+    pub point: Vec2,
+    /// How long to wait once the waypoint is reached.
+    pub pause_millis: u32,
+}
+
+/// A route an AI actor walks on its own, authored as data (see [`set_scripted_path`]).
+///
+/// The path hands out one waypoint at a time as a [`MoveIntentData::Point`], the movement tick
+/// does the walking. While the actor has another intent (e.g. it's chasing something) the path
+/// waits and picks up again from its current waypoint.
+#[table(name=scripted_path_tbl)]
+pub struct ScriptedPathRow {
+    #[primary_key]
+    pub actor_id: ActorId,
+
+    pub waypoints: Vec<ScriptedWaypoint>,
+
+    pub loop_mode: PathLoopMode,
+
+    /// Movement speed (meters/second) while the path exists, instead of the actor's own.
+    pub speed_override: Option<f32>,
+
+    /// Index of the waypoint being walked to, or paused at.
+    pub current: u32,
+
+    /// Walking the waypoints backwards, only for [`PathLoopMode::PingPong`].
+    pub reverse: bool,
+
+    /// When the actor started pausing at the current waypoint.
+    pub paused_at: Option<Timestamp>,
+}
+
+impl ScriptedPathRow {
+    /// The speed override of the actor's path, if any.
+    pub fn speed_override(ctx: &ReducerContext, actor_id: ActorId) -> Option<f32> {
+        ctx.db
+            .scripted_path_tbl()
+            .actor_id()
+            .find(actor_id)
+            .and_then(|path| path.speed_override)
+    }
+
+    pub fn delete_for_actor(ctx: &ReducerContext, actor_id: ActorId) {
+        ctx.db.scripted_path_tbl().actor_id().delete(actor_id);
+    }
+
+    /// Makes the given monster walk the waypoints, replacing its previous path.
+    pub fn start(
+        ctx: &ReducerContext,
+        actor_id: ActorId,
+        waypoints: Vec<ScriptedWaypoint>,
+        loop_mode: PathLoopMode,
+        speed_override: Option<f32>,
+    ) -> Result<(), String> {
+        // Characters are driven by their players.
+        if MonsterInstanceRow::find(&ctx.as_read_only(), actor_id).is_none() {
+            return Err("Only monsters can follow scripted paths".into());
+        }
+        if waypoints.is_empty() || waypoints.len() > MAX_WAYPOINTS {
+            return Err(format!("A path needs 1–{MAX_WAYPOINTS} waypoints"));
+        }
+        if waypoints.len() == 1 && loop_mode != PathLoopMode::Once {
+            return Err("A repeating path needs at least 2 waypoints".into());
+        }
+        for waypoint in &waypoints {
+            validate::within_world(Vector3::new(waypoint.point.x, 0.0, waypoint.point.z))?;
+        }
+        if let Some(speed) = speed_override {
+            if !speed.is_finite() || speed <= 0.0 || speed > MAX_SPEED_OVERRIDE {
+                return Err(format!(
+                    "Speed override must be within (0, {MAX_SPEED_OVERRIDE}]"
+                ));
+            }
+        }
+
+        Self::delete_for_actor(ctx, actor_id);
+        ctx.db.scripted_path_tbl().insert(Self {
+            actor_id,
+            waypoints,
+            loop_mode,
+            speed_override,
+            current: 0,
+            reverse: false,
+            paused_at: None,
+        });
+        Ok(())
+    }
+
+    fn waypoint(&self) -> Option<ScriptedWaypoint> {
+        self.waypoints.get(self.current as usize).copied()
+    }
+
+    /// Moves on to the next waypoint, returns `false` once a [`PathLoopMode::Once`] path is done.
+    fn advance(&mut self) -> bool {
+        let last = self.waypoints.len().saturating_sub(1) as u32;
+        match self.loop_mode {
+            PathLoopMode::Once if self.current >= last => return false,
+            PathLoopMode::Once => self.current += 1,
+            PathLoopMode::Loop => {
+                self.current = if self.current >= last {
+                    0
+                } else {
+                    self.current + 1
+                }
+            }
+            PathLoopMode::PingPong => {
+                if (self.reverse && self.current == 0) || (!self.reverse && self.current >= last) {
+                    self.reverse = !self.reverse;
+                }
+                self.current = if self.reverse {
+                    self.current.saturating_sub(1)
+                } else {
+                    (self.current + 1).min(last)
+                };
+            }
+        }
+        true
+    }
+
+    /// Hands out the next waypoint when the actor is idle. Returns `false` once the path is done.
+    fn tick(&mut self, ctx: &ReducerContext, write_stats: &mut WriteStats) -> bool {
+        let Some(mut movement_state) = MovementStateRow::find(ctx, self.actor_id) else {
+            return false;
+        };
+        // Busy with something else, or still falling.
+        if movement_state.move_intent != MoveIntentData::None || movement_state.should_move {
+            return true;
+        }
+        if ActorRow::is_dead(&ctx.as_read_only(), self.actor_id) {
+            return true;
+        }
+        let (Some(waypoint), Some(transform)) =
+            (self.waypoint(), TransformRow::find(ctx, self.actor_id))
+        else {
+            return false;
+        };
+
+        if let Some(paused_at) = self.paused_at {
+            let waited = ctx
+                .timestamp
+                .time_duration_since(paused_at)
+                .is_some_and(|waited| waited.to_micros() >= waypoint.pause_millis as i64 * 1000);
+            if !waited {
+                return true;
+            }
+            self.paused_at = None;
+            if !self.advance() {
+                return false;
+            }
+        } else if planar_distance_sq(transform.translation.xz().into(), waypoint.point.into())
+            <= WAYPOINT_REACHED_RADIUS_SQ
+        {
+            if waypoint.pause_millis > 0 {
+                self.paused_at = Some(ctx.timestamp);
+                return true;
+            }
+            if !self.advance() {
+                return false;
+            }
+        }
+
+        let Some(next) = self.waypoint() else {
+            return false;
+        };
+        movement_state.move_intent = MoveIntentData::Point(next.point);
+        movement_state.should_move = true;
+        movement_state.server_tick = current_server_tick(ctx);
+        movement_state.update_from_self(ctx);
+        write_stats.record(true);
+        true
+    }
+}
+
+/// Makes the given monster walk the waypoints, replacing its previous path. Admin only.
+#[reducer]
+pub fn set_scripted_path(
+    ctx: &ReducerContext,
+    actor_id: ActorId,
+    waypoints: Vec<ScriptedWaypoint>,
+    loop_mode: PathLoopMode,
+    speed_override: Option<f32>,
+) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "set_scripted_path")?;
+    ScriptedPathRow::start(ctx, actor_id, waypoints, loop_mode, speed_override)
+}
+
+/// Stops the given actor's scripted path, it halts where it is. Admin only.
+#[reducer]
+pub fn clear_scripted_path(ctx: &ReducerContext, actor_id: ActorId) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "clear_scripted_path")?;
+    if ctx
+        .db
+        .scripted_path_tbl()
+        .actor_id()
+        .find(actor_id)
+        .is_none()
+    {
+        return Err("Actor has no scripted path".into());
+    }
+    ScriptedPathRow::delete_for_actor(ctx, actor_id);
+    if let Some(mut movement_state) = MovementStateRow::find(ctx, actor_id) {
+        movement_state.move_intent = MoveIntentData::None;
+        movement_state.server_tick = current_server_tick(ctx);
+        movement_state.update_from_self(ctx);
+    }
+    Ok(())
+}
+
+#[table(name = scripted_path_timer, scheduled(scripted_path_reducer))]
+pub struct ScriptedPathTimer {
+    #[primary_key]
+    #[auto_inc]
+    pub scheduled_id: u64,
+    pub scheduled_at: ScheduleAt,
+}
+
+pub fn init_scripted_path(ctx: &ReducerContext) {
+    ctx.db.scripted_path_timer().scheduled_id().delete(1);
+    ctx.db.scripted_path_timer().insert(ScriptedPathTimer {
+        scheduled_id: 1,
+        scheduled_at: Duration::from_millis(SCRIPTED_PATH_INTERVAL_MILLIS).into(),
+    });
+    log::info!("init scripted path");
+}
+
+/// Gives idle actors on a scripted path their next waypoint and deletes finished paths.
+///
+/// **Performance & Cost**: O(N) scan of the paths, three index seeks per path
+#[reducer]
+fn scripted_path_reducer(ctx: &ReducerContext, _timer: ScriptedPathTimer) -> Result<(), String> {
+    if ctx.sender != ctx.identity() {
+        log::error!("`scripted_path_reducer` may not be invoked by clients.");
+        return Err("`scripted_path_reducer` may not be invoked by clients.".into());
+    }
+
+    let mut write_stats = WriteStats::default();
+    let paths: Vec<ScriptedPathRow> = ctx.db.scripted_path_tbl().iter().collect();
+    for mut path in paths {
+        let (current, reverse, paused_at) = (path.current, path.reverse, path.paused_at);
+        if !path.tick(ctx, &mut write_stats) {
+            ScriptedPathRow::delete_for_actor(ctx, path.actor_id);
+            write_stats.record(true);
+            continue;
+        }
+        let next = (path.current, path.reverse, path.paused_at);
+        write_stats.update_if_changed(&(current, reverse, paused_at), &next, || {
+            ctx.db.scripted_path_tbl().actor_id().update(path);
+        });
+    }
+
+    TimingStatsRow::record(ctx, TimingStatsRow::SCRIPTED_PATH_TICK, write_stats);
+    Ok(())
+}
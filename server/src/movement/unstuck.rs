@@ -0,0 +1,88 @@
+use crate::{
+    character_instance_tbl, current_server_tick, get_static_query_world, ActivityRow, ActorRow,
+    CooldownKind, CooldownRow, EventKind, EventLogRow, MoveIntentData, MovementStateRow,
+    SpawnPointRow, TransformRow, Vec3,
+};
+use nalgebra::Vector3;
+use shared::find_free_position;
+use spacetimedb::{reducer, ReducerContext, TimeDuration};
+
+/// Minimum time between two `/stuck` of the same actor.
+const UNSTUCK_COOLDOWN_MICROS: i64 = 60_000_000;
+
+/// How far (planar meters) a free spot is searched for before falling back to a spawn point.
+const UNSTUCK_SEARCH_RADIUS: f32 = 8.0;
+
+/// Frees the player's active character from geometry it's wedged in (`/stuck`).
+///
+/// The character is moved to the nearest spot its capsule fits on the ground (see
+/// [`find_free_position`]), or to the nearest spawn point of its instance when there's none
+/// close by. Every use is recorded in the event log, a player using it a lot is either
+/// unlucky with the level design or using it to get through walls.
+#[reducer]
+pub fn request_unstuck(ctx: &ReducerContext) -> Result<(), String> {
+    let Some(ci) = ctx.db.character_instance_tbl().identity().find(ctx.sender) else {
+        return Err("Unable to find active character".into());
+    };
+    let actor_id = ci.actor_id;
+    ActivityRow::touch(ctx);
+    let view_ctx = ctx.as_read_only();
+    if ActorRow::is_dead(&view_ctx, actor_id) {
+        return Err("Dead actors can't get unstuck".into());
+    }
+    let (Some(actor), Some(transform), Some(mut movement_state)) = (
+        ActorRow::find(&view_ctx, actor_id),
+        TransformRow::find(ctx, actor_id),
+        MovementStateRow::find(ctx, actor_id),
+    ) else {
+        log::error!("request_unstuck: no actor, transform or movement state for {actor_id}");
+        return Err("Unable to find the active character".into());
+    };
+
+    CooldownRow::try_start(
+        ctx,
+        actor_id,
+        CooldownKind::Unstuck,
+        TimeDuration::from_micros(UNSTUCK_COOLDOWN_MICROS),
+    )?;
+
+    let from = transform.translation;
+    let query_world = get_static_query_world(ctx, actor.instance_id);
+    let free = find_free_position(
+        &query_world,
+        Vector3::from(from),
+        actor.capsule.radius,
+        actor.capsule.half_height,
+        UNSTUCK_SEARCH_RADIUS,
+    );
+    let (to, message) = match free {
+        Some(free) => {
+            let to = Vec3::from(free);
+            (to, format!("Unstuck from {from:?} to {to:?}"))
+        }
+        None => {
+            let Some(spawn_point) = SpawnPointRow::nearest(ctx, actor.instance_id, from) else {
+                log::error!(
+                    "request_unstuck: no spawn point in instance {}",
+                    actor.instance_id
+                );
+                return Err("Nowhere to move to".into());
+            };
+            let message = format!(
+                "Unstuck from {:?} to spawn point {}, no free spot within {}m",
+                from, spawn_point.id, UNSTUCK_SEARCH_RADIUS
+            );
+            (spawn_point.translation, message)
+        }
+    };
+
+    transform.update(ctx, current_server_tick(ctx), to, transform.yaw);
+    movement_state.move_intent = MoveIntentData::None;
+    // Start falling so the next tick settles the actor onto the ground.
+    movement_state.vertical_velocity = -1;
+    movement_state.should_move = true;
+    movement_state.update_from_self(ctx);
+
+    EventLogRow::record(ctx, EventKind::Unstuck, Some(actor_id), message);
+    Ok(())
+}
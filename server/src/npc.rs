@@ -1,8 +1,9 @@
 use spacetimedb::table;
 
-/// The persistence layer for the types of enemies that can be spawned into the world (Actor)
+/// The persistence layer for the types of NPCs that can be spawned into the world (Actor)
 ///
-/// **Possible source of `owner` found in other tables.**
+/// Like monsters, spawned NPCs should get an instance table keyed by `ActorId` and a variant in
+/// [`crate::ActorKind`].
 #[table(name=npc_tbl)]
 pub struct NpcRow {
     #[auto_inc]
@@ -0,0 +1,160 @@
+//! Ownership of the world partitions (see [`shared::partition`]), groundwork for splitting the
+//! world over several modules.
+//!
+//! Partitions split the overworld only, instances are small and always simulated in full by the
+//! module they're in. Cells are shared between instances, so everything here checks the
+//! instance first.
+//!
+//! Today this module owns every partition and nothing changes for players. What's in place:
+//!
+//! - [`PartitionRow`] records which module owns each partition, `None` for this one.
+//! - The movement tick calls [`PartitionRow::hand_off`] when an actor crosses a partition
+//!   border. Crossings into a remote partition are queued as [`PartitionHandoffRow`] for the
+//!   relay that will move actors between modules, until then the actor stays simulated here.
+//! - AOI views leave out the cells of remote partitions at seams (see
+//!   [`remote_aoi_partitions`]), their actors will come from the owner.
+
+use crate::{AdminIdentityRow, EventKind, EventLogRow, InstanceRow};
+use shared::{
+    aoi_block_partitions, partition_of_cell, ActorId, CellId, InstanceId, PartitionId,
+    PARTITION_COUNT,
+};
+use spacetimedb::{reducer, table, ReducerContext, Table, Timestamp, ViewContext};
+
+/// A world partition and who owns it.
+#[table(name=partition_tbl)]
+pub struct PartitionRow {
+    #[primary_key]
+    pub id: PartitionId,
+
+    /// The module simulating the partition, `None` for this one.
+    pub owner: Option<String>,
+
+    /// Actors that crossed into the partition since it was seeded.
+    pub handoffs_in: u64,
+
+    /// Actors that crossed out of the partition since it was seeded.
+    pub handoffs_out: u64,
+}
+
+/// **Ephemeral**: An actor that crossed into a partition owned by another module, waiting to be
+/// transferred there.
+#[table(name=partition_handoff_tbl)]
+pub struct PartitionHandoffRow {
+    #[primary_key]
+    pub actor_id: ActorId,
+
+    pub from: PartitionId,
+
+    #[index(btree)]
+    pub to: PartitionId,
+
+    pub crossed_at: Timestamp,
+}
+
+impl PartitionRow {
+    /// Inserts the partitions that are missing, all owned by this module.
+    pub fn seed(ctx: &ReducerContext) {
+        for id in 0..PARTITION_COUNT {
+            if ctx.db.partition_tbl().id().find(id).is_none() {
+                ctx.db.partition_tbl().insert(Self {
+                    id,
+                    owner: None,
+                    handoffs_in: 0,
+                    handoffs_out: 0,
+                });
+            }
+        }
+    }
+
+    /// Is the partition simulated by this module? Unknown partitions are.
+    pub fn is_local(ctx: &ViewContext, id: PartitionId) -> bool {
+        ctx.db
+            .partition_tbl()
+            .id()
+            .find(id)
+            .is_none_or(|row| row.owner.is_none())
+    }
+
+    /// Books an actor of `instance_id` moving from `from_cell` to `to_cell`, a no-op within a
+    /// partition and outside of the overworld.
+    ///
+    /// **Performance & Cost**: nothing unless a border is crossed, then two seeks and updates
+    pub fn hand_off(
+        ctx: &ReducerContext,
+        actor_id: ActorId,
+        instance_id: InstanceId,
+        from_cell: CellId,
+        to_cell: CellId,
+    ) {
+        let (from, to) = (partition_of_cell(from_cell), partition_of_cell(to_cell));
+        if from == to || instance_id != InstanceRow::OVERWORLD {
+            return;
+        }
+
+        if let Some(mut row) = ctx.db.partition_tbl().id().find(from) {
+            row.handoffs_out += 1;
+            ctx.db.partition_tbl().id().update(row);
+        }
+        let Some(mut row) = ctx.db.partition_tbl().id().find(to) else {
+            return;
+        };
+        row.handoffs_in += 1;
+        let remote = row.owner.is_some();
+        ctx.db.partition_tbl().id().update(row);
+
+        // Crossing back before the transfer happened cancels it.
+        ctx.db.partition_handoff_tbl().actor_id().delete(actor_id);
+        if remote {
+            ctx.db.partition_handoff_tbl().insert(PartitionHandoffRow {
+                actor_id,
+                from,
+                to,
+                crossed_at: ctx.timestamp,
+            });
+        }
+    }
+}
+
+impl PartitionHandoffRow {
+    pub fn delete_for_actor(ctx: &ReducerContext, actor_id: ActorId) {
+        ctx.db.partition_handoff_tbl().actor_id().delete(actor_id);
+    }
+}
+
+/// The partitions other than the viewer's own that the AOI block around `cell_id` of
+/// `instance_id` reaches into and this module doesn't own. Empty away from seams and outside of
+/// the overworld, without any seek.
+///
+/// **Performance & Cost**: O(1), up to three seeks at seams
+pub fn remote_aoi_partitions(
+    ctx: &ViewContext,
+    instance_id: InstanceId,
+    cell_id: CellId,
+) -> Vec<PartitionId> {
+    if instance_id != InstanceRow::OVERWORLD {
+        return vec![];
+    }
+    aoi_block_partitions(cell_id)
+        .skip(1)
+        .filter(|&id| !PartitionRow::is_local(ctx, id))
+        .collect()
+}
+
+/// Hands a partition to another module, or back to this one with `None`. Admin only.
+#[reducer]
+pub fn set_partition_owner(
+    ctx: &ReducerContext,
+    id: PartitionId,
+    owner: Option<String>,
+) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "set_partition_owner")?;
+    let Some(mut row) = ctx.db.partition_tbl().id().find(id) else {
+        return Err(format!("Unknown partition {id}"));
+    };
+    let message = format!("Partition {} owner {:?} -> {:?}", id, row.owner, owner);
+    row.owner = owner;
+    ctx.db.partition_tbl().id().update(row);
+    EventLogRow::record(ctx, EventKind::PartitionOwnerChanged, None, message);
+    Ok(())
+}
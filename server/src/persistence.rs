@@ -0,0 +1,63 @@
+use crate::{character_instance_tbl, character_tbl, TimingStatsRow, WriteStats};
+use spacetimedb::{reducer, table, ReducerContext, ScheduleAt, Table};
+use std::time::Duration;
+
+/// How often a persistence batch runs.
+pub(crate) const PERSISTENCE_INTERVAL_MILLIS: u64 = 5_000;
+
+/// Characters are split into this many batches by actor id, one batch is saved per run so the
+/// writes are spread out instead of spiking. Every character is saved once per
+/// `PERSISTENCE_INTERVAL_MILLIS * PERSISTENCE_BATCHES` (30s).
+const PERSISTENCE_BATCHES: u32 = 6;
+
+#[table(name = persistence_timer, scheduled(persistence_reducer))]
+pub struct PersistenceTimer {
+    #[primary_key]
+    #[auto_inc]
+    pub scheduled_id: u64,
+    pub scheduled_at: ScheduleAt,
+
+    /// The batch saved by the next run, in `[0, PERSISTENCE_BATCHES)`.
+    pub batch: u32,
+}
+
+pub fn init_persistence(ctx: &ReducerContext) {
+    ctx.db.persistence_timer().scheduled_id().delete(1);
+    ctx.db.persistence_timer().insert(PersistenceTimer {
+        scheduled_id: 1,
+        scheduled_at: Duration::from_millis(PERSISTENCE_INTERVAL_MILLIS).into(),
+        batch: 0,
+    });
+    log::info!("init persistence");
+}
+
+/// Snapshots the live actor state of one batch of in-game characters back to `character_tbl`,
+/// so a crash only loses the state since that character's last snapshot.
+#[reducer]
+fn persistence_reducer(ctx: &ReducerContext, mut timer: PersistenceTimer) -> Result<(), String> {
+    if ctx.sender != ctx.identity() {
+        log::error!("`persistence_reducer` may not be invoked by clients.");
+        return Err("`persistence_reducer` may not be invoked by clients.".into());
+    }
+
+    let batch = timer.batch;
+    let mut write_stats = WriteStats::default();
+    for ci in ctx
+        .db
+        .character_instance_tbl()
+        .iter()
+        .filter(|ci| ci.actor_id % PERSISTENCE_BATCHES == batch)
+    {
+        let Some(character) = ctx.db.character_tbl().id().find(ci.character_id) else {
+            log::error!("Persistence: unable to find character {}", ci.character_id);
+            continue;
+        };
+        write_stats.record(character.save_live_state(ctx, ci.actor_id));
+    }
+
+    TimingStatsRow::record(ctx, TimingStatsRow::PERSISTENCE_TICK, write_stats);
+    timer.batch = (batch + 1) % PERSISTENCE_BATCHES;
+    ctx.db.persistence_timer().scheduled_id().update(timer);
+
+    Ok(())
+}
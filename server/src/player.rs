@@ -1,4 +1,4 @@
-use crate::{character_instance_tbl, character_tbl};
+use crate::{activity_tbl, character_instance_tbl, character_tbl, SpectatorRow, WhoResultRow};
 use spacetimedb::{table, Identity, ReducerContext, Table, Timestamp};
 
 /// Main persistence table a person's "account"
@@ -12,7 +12,7 @@ pub struct PlayerRow {
     #[index(btree)]
     pub online: bool,
 
-    /// UNIMPLEMENTED: Whether this player is allowed to play the game
+    /// UNUSED: bans live in [`crate::BanRow`], with a duration and a reason.
     pub banned: bool,
 }
 
@@ -39,6 +39,9 @@ impl PlayerRow {
         };
         player.online = false;
         ctx.db.player_tbl().identity().update(player);
+        WhoResultRow::delete_for_viewer(ctx, ctx.sender);
+        SpectatorRow::delete_for_identity(ctx, ctx.sender);
+        ctx.db.activity_tbl().identity().delete(ctx.sender);
 
         let Some(ci) = ctx.db.character_instance_tbl().identity().find(ctx.sender) else {
             log::info!("Disconnect: Unable to find active char: {:?}", ctx.sender);
@@ -0,0 +1,112 @@
+use spacetimedb::{reducer, table, Identity, ReducerContext, Table, Timestamp, ViewContext};
+
+/// Max length (bytes) of a setting key.
+const MAX_KEY_LEN: usize = 64;
+
+/// Max length (bytes) of a setting value.
+const MAX_VALUE_LEN: usize = 1024;
+
+/// Max number of settings per player.
+const MAX_SETTINGS_PER_PLAYER: usize = 128;
+
+/// A UI preference of a player's account, e.g. `nameplates.visible` = `true`.
+///
+/// Keyed by identity rather than character so the preference roams with the account. Values are
+/// opaque strings, interpreting them is up to the client.
+#[table(name=player_setting_tbl)]
+pub struct PlayerSettingRow {
+    #[auto_inc]
+    #[primary_key]
+    pub id: u64,
+
+    #[index(btree)]
+    pub identity: Identity,
+
+    pub key: String,
+
+    pub value: String,
+
+    pub updated_at: Timestamp,
+}
+
+impl PlayerSettingRow {
+    pub fn find(ctx: &ViewContext, identity: Identity, key: &str) -> Option<Self> {
+        ctx.db
+            .player_setting_tbl()
+            .identity()
+            .filter(identity)
+            .find(|setting| setting.key == key)
+    }
+}
+
+fn validate_setting_key(key: &str) -> Result<(), String> {
+    if key.is_empty() || key.len() > MAX_KEY_LEN {
+        return Err(format!("Setting key must be 1–{MAX_KEY_LEN} bytes"));
+    }
+    if !key
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+    {
+        return Err("Setting key must be alphanumeric, `.`, `_` or `-`".into());
+    }
+    Ok(())
+}
+
+/// Sets one of the player's settings, replacing its previous value.
+#[reducer]
+pub fn set_player_setting(ctx: &ReducerContext, key: String, value: String) -> Result<(), String> {
+    validate_setting_key(&key)?;
+    if value.len() > MAX_VALUE_LEN {
+        return Err(format!(
+            "Setting value must be at most {MAX_VALUE_LEN} bytes"
+        ));
+    }
+
+    if let Some(mut setting) = PlayerSettingRow::find(&ctx.as_read_only(), ctx.sender, &key) {
+        setting.value = value;
+        setting.updated_at = ctx.timestamp;
+        ctx.db.player_setting_tbl().id().update(setting);
+        return Ok(());
+    }
+    if ctx
+        .db
+        .player_setting_tbl()
+        .identity()
+        .filter(ctx.sender)
+        .count()
+        >= MAX_SETTINGS_PER_PLAYER
+    {
+        return Err(format!(
+            "At most {MAX_SETTINGS_PER_PLAYER} settings per player"
+        ));
+    }
+    ctx.db.player_setting_tbl().insert(PlayerSettingRow {
+        id: 0,
+        identity: ctx.sender,
+        key,
+        value,
+        updated_at: ctx.timestamp,
+    });
+    Ok(())
+}
+
+/// Deletes one of the player's settings, reverting it to the client's default.
+#[reducer]
+pub fn delete_player_setting(ctx: &ReducerContext, key: String) -> Result<(), String> {
+    let Some(setting) = PlayerSettingRow::find(&ctx.as_read_only(), ctx.sender, &key) else {
+        return Err("No such setting".into());
+    };
+    ctx.db.player_setting_tbl().id().delete(setting.id);
+    Ok(())
+}
+
+/// Finds the player's settings.
+/// Primary key of `id`
+#[spacetimedb::view(name = player_setting_view, public)]
+pub fn player_setting_view(ctx: &ViewContext) -> Vec<PlayerSettingRow> {
+    ctx.db
+        .player_setting_tbl()
+        .identity()
+        .filter(ctx.sender)
+        .collect()
+}
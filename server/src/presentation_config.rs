@@ -0,0 +1,83 @@
+use crate::AdminIdentityRow;
+use spacetimedb::{reducer, table, ReducerContext, Table, Timestamp};
+
+/// Upper bound for the suggested decay rates (1/s), beyond this smoothing is effectively off.
+const MAX_DECAY_RATE: f32 = 1000.0;
+
+/// Server suggested client presentation (smoothing) constants, a single row.
+///
+/// Public so clients pick up changes live, without the row clients use their built-in defaults.
+/// Purely cosmetic, nothing on the server reads these.
+#[table(name=presentation_config_tbl, public)]
+pub struct PresentationConfigRow {
+    /// Always [`PresentationConfigRow::ID`].
+    #[primary_key]
+    pub id: u8,
+
+    /// Decay rate (1/s) of remote actors' rendered translation towards the replicated one.
+    pub translation_decay_rate: f32,
+
+    /// Decay rate (1/s) of remote actors' rendered rotation towards the replicated one.
+    pub rotation_decay_rate: f32,
+
+    /// Decay rate (1/s) of the camera following the local actor.
+    pub camera_decay_rate: f32,
+
+    pub updated_at: Timestamp,
+}
+
+impl PresentationConfigRow {
+    const ID: u8 = 0;
+}
+
+fn validate_decay_rate(name: &str, rate: f32) -> Result<(), String> {
+    if !rate.is_finite() || rate <= 0.0 || rate > MAX_DECAY_RATE {
+        return Err(format!("{name} must be in (0, {MAX_DECAY_RATE}]"));
+    }
+    Ok(())
+}
+
+/// Suggests presentation constants to every client. Admin only.
+#[reducer]
+pub fn set_presentation_config(
+    ctx: &ReducerContext,
+    translation_decay_rate: f32,
+    rotation_decay_rate: f32,
+    camera_decay_rate: f32,
+) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "set_presentation_config")?;
+    validate_decay_rate("translation_decay_rate", translation_decay_rate)?;
+    validate_decay_rate("rotation_decay_rate", rotation_decay_rate)?;
+    validate_decay_rate("camera_decay_rate", camera_decay_rate)?;
+
+    let row = PresentationConfigRow {
+        id: PresentationConfigRow::ID,
+        translation_decay_rate,
+        rotation_decay_rate,
+        camera_decay_rate,
+        updated_at: ctx.timestamp,
+    };
+    if ctx
+        .db
+        .presentation_config_tbl()
+        .id()
+        .find(PresentationConfigRow::ID)
+        .is_some()
+    {
+        ctx.db.presentation_config_tbl().id().update(row);
+    } else {
+        ctx.db.presentation_config_tbl().insert(row);
+    }
+    Ok(())
+}
+
+/// Removes the suggestion, clients fall back to their defaults. Admin only.
+#[reducer]
+pub fn reset_presentation_config(ctx: &ReducerContext) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "reset_presentation_config")?;
+    ctx.db
+        .presentation_config_tbl()
+        .id()
+        .delete(PresentationConfigRow::ID);
+    Ok(())
+}
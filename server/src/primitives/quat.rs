@@ -40,6 +40,15 @@ impl Quat {
         Quat { x, y, z, w }
     }
 }
+
+impl From<Quat> for Quaternion<f32> {
+    #[inline(always)]
+    fn from(q: Quat) -> Self {
+        // nalgebra: Quaternion::new(w, i, j, k)
+        Quaternion::new(q.w, q.x, q.y, q.z)
+    }
+}
+
 impl From<Quat> for UnitQuaternion<f32> {
     #[inline(always)]
     fn from(q: Quat) -> Self {
@@ -105,6 +105,84 @@ pub enum ColliderShape {
     RoundCone(RoundCone),
 }
 
+impl ColliderShape {
+    /// Checks every dimension is finite, positive and at most `max_extent` meters, so user
+    /// supplied shapes can't poison the query world.
+    pub fn validate(&self, max_extent: f32) -> Result<(), String> {
+        let check = |name: &str, value: f32| {
+            if value.is_finite() && value > 0.0 && value <= max_extent {
+                Ok(())
+            } else {
+                Err(format!(
+                    "Invalid {} {}, expected 0 < x <= {}",
+                    name, value, max_extent
+                ))
+            }
+        };
+        let check_border = |border_radius: f32| {
+            if border_radius.is_finite() && border_radius >= 0.0 && border_radius <= max_extent {
+                Ok(())
+            } else {
+                Err(format!("Invalid border_radius {}", border_radius))
+            }
+        };
+
+        match *self {
+            ColliderShape::Plane(offset) => {
+                if offset.is_finite() {
+                    Ok(())
+                } else {
+                    Err(format!("Invalid plane offset {}", offset))
+                }
+            }
+            ColliderShape::Cuboid(half_extents) => {
+                check("half_extents.x", half_extents.x)?;
+                check("half_extents.y", half_extents.y)?;
+                check("half_extents.z", half_extents.z)
+            }
+            ColliderShape::Sphere(radius) => check("radius", radius),
+            ColliderShape::CapsuleY(CapsuleY {
+                radius,
+                half_height,
+            })
+            | ColliderShape::Cylinder(Cylinder {
+                radius,
+                half_height,
+            })
+            | ColliderShape::Cone(Cone {
+                radius,
+                half_height,
+            }) => {
+                check("radius", radius)?;
+                check("half_height", half_height)
+            }
+            ColliderShape::RoundCuboid(RoundCuboid {
+                half_extents,
+                border_radius,
+            }) => {
+                check("half_extents.x", half_extents.x)?;
+                check("half_extents.y", half_extents.y)?;
+                check("half_extents.z", half_extents.z)?;
+                check_border(border_radius)
+            }
+            ColliderShape::RoundCylinder(RoundCylinder {
+                radius,
+                half_height,
+                border_radius,
+            })
+            | ColliderShape::RoundCone(RoundCone {
+                radius,
+                half_height,
+                border_radius,
+            }) => {
+                check("radius", radius)?;
+                check("half_height", half_height)?;
+                check_border(border_radius)
+            }
+        }
+    }
+}
+
 impl From<ColliderShape> for SharedShape {
     fn from(shape: ColliderShape) -> Self {
         match shape {
@@ -57,6 +57,12 @@ impl Vec3 {
     pub const fn xz(&self) -> Vec2 {
         Vec2::new(self.x, self.z)
     }
+
+    /// Returns true if no component is NaN or infinite.
+    #[inline(always)]
+    pub fn is_finite(&self) -> bool {
+        self.x.is_finite() && self.y.is_finite() && self.z.is_finite()
+    }
 }
 
 impl From<Vector3<f32>> for Vec3 {
@@ -1,5 +1,5 @@
-use crate::{level_tbl, CharacterInstanceRow, EXPERIENCE_PER_LEVEL};
-use shared::ActorId;
+use crate::{level_tbl, CharacterInstanceRow, TutorialProgressRow, EXPERIENCE_PER_LEVEL};
+use shared::{ActorId, TutorialFlags};
 use spacetimedb::{table, ReducerContext, Table, ViewContext};
 
 /// The amount of experience this person has accumulated
@@ -31,6 +31,7 @@ impl ExperienceRow {
 
         if new_level > level_row.level {
             level_row.update(ctx, new_level);
+            TutorialProgressRow::complete(ctx, level_row.actor_id, TutorialFlags::FIRST_LEVEL);
         }
     }
 
@@ -1,6 +1,6 @@
 use crate::{
-    get_view_aoi_block, HealthData, HealthRow, ManaData, ManaRow, MovementStateRow,
-    PrimaryStatsRow, SecondaryStatsRow, MAX_LEVEL, TIER_INTERVAL,
+    get_view_aoi_actors, HealthData, HealthRow, ManaData, ManaRow, PrimaryStatsRow,
+    SecondaryStatsRow, MAX_LEVEL, TIER_INTERVAL,
 };
 use shared::ActorId;
 use spacetimedb::{table, ReducerContext, Table, ViewContext};
@@ -67,27 +67,17 @@ impl LevelRow {
         }
 
         // Update secondary stats when we change level
-        if let Some(mut secondary_stats) = SecondaryStatsRow::find(&view_ctx, self.actor_id) {
-            secondary_stats.movement_speed =
-                SecondaryStatsRow::compute_movement_speed(res.level, 0., 0., 0.);
-            secondary_stats.critical_hit_chance = SecondaryStatsRow::compute_critical_hit_chance(
-                res.level,
-                primary_stats.ferocity,
-                0.,
-            );
-            SecondaryStatsRow::update_from_self(secondary_stats, ctx);
-        }
+        SecondaryStatsRow::refresh(ctx, self.actor_id, res.level, &primary_stats);
     }
 }
 
 #[spacetimedb::view(name = level_view, public)]
 pub fn level_view(ctx: &ViewContext) -> Vec<LevelRow> {
-    let Some(cell_block) = get_view_aoi_block(ctx) else {
+    let Some(actors) = get_view_aoi_actors(ctx) else {
         return vec![];
     };
 
-    cell_block
-        .flat_map(|cell_id| MovementStateRow::by_cell_id(ctx, cell_id))
+    actors
         .filter_map(|ms| {
             LevelRow::find(ctx, ms.actor_id).map(|row| LevelRow {
                 actor_id: ms.actor_id,
@@ -0,0 +1,105 @@
+//! Versioned seeding and migration of authored data.
+//!
+//! Seeding the world, spawn points and monster archetypes replaces whatever is in those tables,
+//! running it on every publish would wipe edits made in game (see the world editing reducers).
+//! Instead every seeding or migration step is an entry of [`MIGRATIONS`], and
+//! [`SchemaVersionRow`] remembers how many of them ran. Publishing a module update only runs the
+//! new steps.
+//!
+//! To change authored data in an update, append a step, never edit or reorder existing ones.
+
+use crate::{
+    regenerate_static_world, DummyStatsRow, EncounterDefRow, EventKind, EventLogRow,
+    MonsterArchetypeRow, PartitionRow, SpawnPointRow, WorldBoundsRow,
+};
+use spacetimedb::{table, ReducerContext, Table, Timestamp};
+
+/// A seeding or migration step, moves the data from its index in [`MIGRATIONS`] as version to
+/// the next version.
+type Migration = fn(&ReducerContext);
+
+/// Every migration step in order, the schema version is the number of steps applied.
+const MIGRATIONS: &[(&str, Migration)] = &[
+    ("seed overworld", seed_overworld),
+    (
+        "seed training dummy archetype",
+        DummyStatsRow::seed_archetype,
+    ),
+    ("seed world partitions", PartitionRow::seed),
+    ("seed world bounds", WorldBoundsRow::seed),
+    ("seed troll camp encounter", EncounterDefRow::seed),
+];
+
+/// Version 0 -> 1, the initial overworld statics, spawn point and monster archetypes.
+fn seed_overworld(ctx: &ReducerContext) {
+    regenerate_static_world(ctx);
+    SpawnPointRow::regenerate(ctx);
+    MonsterArchetypeRow::regenerate(ctx);
+}
+
+/// The version of the authored data, a single row. No row means version 0 (nothing seeded).
+#[table(name=schema_version_tbl)]
+pub struct SchemaVersionRow {
+    /// Always [`SchemaVersionRow::ID`].
+    #[primary_key]
+    pub id: u8,
+
+    /// Number of [`MIGRATIONS`] steps applied.
+    pub version: u32,
+
+    pub migrated_at: Timestamp,
+}
+
+impl SchemaVersionRow {
+    const ID: u8 = 0;
+
+    /// The version the data would have after all migrations ran.
+    pub const LATEST: u32 = MIGRATIONS.len() as u32;
+
+    pub fn current(ctx: &ReducerContext) -> u32 {
+        ctx.db
+            .schema_version_tbl()
+            .id()
+            .find(Self::ID)
+            .map(|row| row.version)
+            .unwrap_or(0)
+    }
+
+    /// Runs the migration steps newer than the stored version, each step is recorded to the
+    /// event log. Cheap when up to date, a single seek.
+    pub fn migrate(ctx: &ReducerContext) {
+        let from = Self::current(ctx);
+        if from > Self::LATEST {
+            log::error!(
+                "Schema version {} is newer than this module's {}, not migrating",
+                from,
+                Self::LATEST
+            );
+            return;
+        }
+        if from == Self::LATEST {
+            return;
+        }
+
+        for (version, (name, migration)) in MIGRATIONS.iter().enumerate().skip(from as usize) {
+            migration(ctx);
+            EventLogRow::record(
+                ctx,
+                EventKind::SchemaMigrated,
+                None,
+                format!("Migrated schema {} -> {}: {}", version, version + 1, name),
+            );
+        }
+
+        let row = Self {
+            id: Self::ID,
+            version: Self::LATEST,
+            migrated_at: ctx.timestamp,
+        };
+        if ctx.db.schema_version_tbl().id().find(Self::ID).is_some() {
+            ctx.db.schema_version_tbl().id().update(row);
+        } else {
+            ctx.db.schema_version_tbl().insert(row);
+        }
+    }
+}
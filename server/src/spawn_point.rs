@@ -0,0 +1,57 @@
+use crate::{InstanceRow, Vec3};
+use shared::{dist_sq_xz, InstanceId};
+use spacetimedb::{table, ReducerContext, Table};
+
+/// Safe locations actors can be placed at, e.g. when recovering from falling out of the world.
+#[table(name=spawn_point_tbl)]
+pub struct SpawnPointRow {
+    #[auto_inc]
+    #[primary_key]
+    pub id: u32,
+
+    /// The instance this spawn point is in.
+    #[index(btree)]
+    pub instance_id: InstanceId,
+
+    pub translation: Vec3,
+}
+
+impl SpawnPointRow {
+    /// Finds the spawn point of the instance closest (planar XZ) to the given position.
+    ///
+    /// **Performance & Cost**: O(N) scan of the instance's spawn points, only intended for rare
+    /// events.
+    pub fn nearest(
+        ctx: &ReducerContext,
+        instance_id: InstanceId,
+        translation: Vec3,
+    ) -> Option<Self> {
+        ctx.db
+            .spawn_point_tbl()
+            .instance_id()
+            .filter(instance_id)
+            .min_by(|a, b| {
+                let da = dist_sq_xz(translation.into(), a.translation.into());
+                let db = dist_sq_xz(translation.into(), b.translation.into());
+                da.total_cmp(&db)
+            })
+    }
+
+    pub fn insert(ctx: &ReducerContext, instance_id: InstanceId, translation: Vec3) -> Self {
+        ctx.db.spawn_point_tbl().insert(Self {
+            id: 0,
+            instance_id,
+            translation,
+        })
+    }
+
+    /// Deletes all overworld spawn points and re-inserts the defaults
+    pub fn regenerate(ctx: &ReducerContext) {
+        let instance_id = InstanceRow::OVERWORLD;
+        for row in ctx.db.spawn_point_tbl().instance_id().filter(instance_id) {
+            ctx.db.spawn_point_tbl().delete(row);
+        }
+
+        Self::insert(ctx, instance_id, Vec3::new(0.0, 2.0, 0.0));
+    }
+}
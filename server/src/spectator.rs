@@ -0,0 +1,100 @@
+//! Spectator mode, a view-only presence for GMs and for debugging crowded cells.
+//!
+//! A spectator has no actor, collider or movement state, nothing in the simulation sees it. The
+//! AOI views center on its observer point instead of the active character (see
+//! [`crate::get_view_aoi_actors`]), which keeps being simulated where it was left. The client
+//! flies the observer point locally and moves it here as it crosses cells.
+
+use crate::{AdminIdentityRow, InstanceRow, Vec3};
+use shared::{encode_cell_id, validate, CellId, InstanceId};
+use spacetimedb::{reducer, table, Identity, ReducerContext, Table, Timestamp, ViewContext};
+
+/// A spectating identity and its observer point.
+#[table(name=spectator_tbl)]
+pub struct SpectatorRow {
+    #[primary_key]
+    pub identity: Identity,
+
+    pub instance_id: InstanceId,
+
+    /// The observer point, as last sent by the client.
+    pub translation: Vec3,
+
+    /// Cell of the observer point, the AOI block is centered on it.
+    pub cell_id: CellId,
+
+    pub updated_at: Timestamp,
+}
+
+impl SpectatorRow {
+    /// The sender's spectator row, `None` when not spectating.
+    pub fn find_by_identity(ctx: &ViewContext) -> Option<Self> {
+        ctx.db.spectator_tbl().identity().find(ctx.sender)
+    }
+
+    /// Stops spectating, e.g. on disconnect.
+    pub fn delete_for_identity(ctx: &ReducerContext, identity: Identity) {
+        ctx.db.spectator_tbl().identity().delete(identity);
+    }
+}
+
+/// The sender's own spectator row, so the client knows to fly the observer point.
+/// Primary key of `Identity`
+#[spacetimedb::view(name = spectator_view, public)]
+pub fn spectator_view(ctx: &ViewContext) -> Vec<SpectatorRow> {
+    SpectatorRow::find_by_identity(ctx).into_iter().collect()
+}
+
+/// Starts spectating `instance_id` from `translation`, or moves an existing spectator there.
+/// Admin only.
+#[reducer]
+pub fn enter_spectator(
+    ctx: &ReducerContext,
+    instance_id: InstanceId,
+    translation: Vec3,
+) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "enter_spectator")?;
+    if InstanceRow::find(&ctx.as_read_only(), instance_id).is_none() {
+        return Err(format!("Unable to find instance {instance_id}"));
+    }
+    let translation = Vec3::from(validate::within_world(translation.into())?);
+
+    let row = SpectatorRow {
+        identity: ctx.sender,
+        instance_id,
+        translation,
+        cell_id: encode_cell_id(translation.x, translation.z),
+        updated_at: ctx.timestamp,
+    };
+    if SpectatorRow::find_by_identity(&ctx.as_read_only()).is_some() {
+        ctx.db.spectator_tbl().identity().update(row);
+    } else {
+        ctx.db.spectator_tbl().insert(row);
+    }
+    log::info!("{:?} spectating instance {}", ctx.sender, instance_id);
+    Ok(())
+}
+
+/// Moves the sender's observer point, the AOI follows once it's in another cell.
+#[reducer]
+pub fn move_spectator(ctx: &ReducerContext, translation: Vec3) -> Result<(), String> {
+    let Some(mut row) = SpectatorRow::find_by_identity(&ctx.as_read_only()) else {
+        return Err("Not spectating".into());
+    };
+    let translation = Vec3::from(validate::within_world(translation.into())?);
+    row.translation = translation;
+    row.cell_id = encode_cell_id(translation.x, translation.z);
+    row.updated_at = ctx.timestamp;
+    ctx.db.spectator_tbl().identity().update(row);
+    Ok(())
+}
+
+/// Stops spectating, the views return to the active character.
+#[reducer]
+pub fn leave_spectator(ctx: &ReducerContext) -> Result<(), String> {
+    if SpectatorRow::find_by_identity(&ctx.as_read_only()).is_none() {
+        return Err("Not spectating".into());
+    }
+    SpectatorRow::delete_for_identity(ctx, ctx.sender);
+    Ok(())
+}
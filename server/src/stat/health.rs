@@ -1,4 +1,7 @@
-use crate::{get_view_aoi_block, MovementStateRow};
+use crate::{
+    get_view_aoi_actors, ActorRow, CombatEventKind, CombatEventRow, CorpseRow, DomainEvent,
+    DomainEventRow, DuelOutcome, DuelRow, DummyStatsRow, EventActor,
+};
 use shared::ActorId;
 use spacetimedb::{table, ReducerContext, SpacetimeType, Table, ViewContext};
 
@@ -33,15 +36,18 @@ impl HealthRow {
         ctx.db.health_tbl().actor_id().find(actor_id)
     }
 
-    /// Adds to the current value, clamping and computing is_full
-    pub fn add(mut self, ctx: &ReducerContext, amount: u16) {
-        if amount == 0 {
-            return;
+    /// Adds to the current value, clamping and computing is_full.
+    ///
+    /// Returns `true` when the row was written, unchanged values are not written.
+    pub fn add(mut self, ctx: &ReducerContext, amount: u16) -> bool {
+        let previous = self.data.current;
+        self.data = self.data.added(amount);
+        if self.data.current == previous {
+            return false;
         }
-        self.data.current = self.data.current.saturating_add(amount);
-        self.clamp();
         self.is_full = self.data.current == self.data.max;
         ctx.db.health_tbl().actor_id().update(self);
+        true
     }
 
     /// Subtracts from the current value, clamping and computing is_full
@@ -55,6 +61,80 @@ impl HealthRow {
         ctx.db.health_tbl().actor_id().update(self);
     }
 
+    /// Applies damage from combat, ignored for actors that can't be damaged (e.g. invulnerable or
+    /// already dead) or that the source may not harm (see [`ActorRow::can_harm`]). Damage that
+    /// drops health to zero kills the actor, leaving a corpse.
+    ///
+    /// Duels never kill, lethal damage between duelists leaves the loser at 1 health and ends the
+    /// duel. Training dummies (see [`DummyStatsRow`]) record the damage instead of losing health.
+    ///
+    /// `kind` is the [`CombatEventKind`] recorded for clients, a damage kind.
+    ///
+    /// Returns `true` when the damage was applied.
+    pub fn take_damage(
+        self,
+        ctx: &ReducerContext,
+        source: Option<ActorId>,
+        amount: u16,
+        kind: CombatEventKind,
+    ) -> bool {
+        let view_ctx = ctx.as_read_only();
+        let actor_id = self.actor_id;
+        if !ActorRow::is_damageable(&view_ctx, actor_id)
+            || source.is_some_and(|source| !ActorRow::can_harm(&view_ctx, source, actor_id))
+        {
+            return false;
+        }
+        if DummyStatsRow::is_dummy(ctx, actor_id) {
+            DummyStatsRow::record_hit(ctx, actor_id, amount);
+            CombatEventRow::record(ctx, source, actor_id, amount, kind);
+            return true;
+        }
+        let duel =
+            source.and_then(|source| DuelRow::find_active_between(&view_ctx, source, actor_id));
+        let lethal = self.data.current <= amount;
+        let amount = match &duel {
+            Some(_) if lethal => self.data.current.saturating_sub(1),
+            _ => amount,
+        };
+        let dealt = amount.min(self.data.current);
+        let killed = self.data.current > 0 && self.data.current <= amount;
+        self.sub(ctx, amount);
+        CombatEventRow::record(ctx, source, actor_id, dealt, kind);
+        if let (Some(duel), Some(winner)) = (duel, source) {
+            if lethal {
+                duel.end(ctx, DuelOutcome::Defeated { winner });
+            }
+        }
+        if killed {
+            DomainEventRow::publish(
+                ctx,
+                DomainEvent::Died {
+                    actor: EventActor::snapshot(&view_ctx, actor_id),
+                    killer: source.map(|source| EventActor::snapshot(&view_ctx, source)),
+                },
+            );
+            CorpseRow::on_death(ctx, actor_id);
+        }
+        true
+    }
+
+    /// Applies healing from combat, ignored for dead actors, they have to be resurrected.
+    ///
+    /// Returns `true` when health changed.
+    pub fn heal(self, ctx: &ReducerContext, source: Option<ActorId>, amount: u16) -> bool {
+        if ActorRow::is_dead(&ctx.as_read_only(), self.actor_id) {
+            return false;
+        }
+        let actor_id = self.actor_id;
+        let healed = amount.min(self.data.max.saturating_sub(self.data.current));
+        if !self.add(ctx, amount) {
+            return false;
+        }
+        CombatEventRow::record(ctx, source, actor_id, healed, CombatEventKind::Heal);
+        true
+    }
+
     /// Sets the current value, clamping to max and computing is_full
     pub fn set_current(mut self, ctx: &ReducerContext, value: u16) {
         if value == self.data.current {
@@ -89,6 +169,14 @@ impl HealthData {
         Self { current: max, max }
     }
 
+    /// The data with `amount` added to the current value, clamped to max.
+    fn added(self, amount: u16) -> Self {
+        Self {
+            current: self.current.saturating_add(amount).min(self.max),
+            ..self
+        }
+    }
+
     /// Formula to compute the maximum health based on level and fortitude.
     /// TBD on if this should exist in the shared crate
     pub fn compute_max(level: u8, fortitude: u8) -> u16 {
@@ -107,12 +195,11 @@ impl HealthData {
 /// Primary key of `ActorId`
 #[spacetimedb::view(name = health_view, public)]
 pub fn health_view(ctx: &ViewContext) -> Vec<HealthRow> {
-    let Some(cell_block) = get_view_aoi_block(ctx) else {
+    let Some(actors) = get_view_aoi_actors(ctx) else {
         return vec![];
     };
 
-    cell_block
-        .flat_map(|cell_id| MovementStateRow::by_cell_id(ctx, cell_id))
+    actors
         .filter_map(|ms| {
             HealthRow::find(ctx, ms.actor_id).map(|row| HealthRow {
                 actor_id: ms.actor_id,
@@ -122,3 +209,25 @@ pub fn health_view(ctx: &ViewContext) -> Vec<HealthRow> {
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn healing_a_full_actor_changes_nothing() {
+        let full = HealthData::new(200);
+        assert_eq!(full.added(50), full);
+        assert_eq!(full.added(0), full);
+    }
+
+    #[test]
+    fn healing_clamps_to_max() {
+        let hurt = HealthData {
+            current: 150,
+            max: 200,
+        };
+        assert_eq!(hurt.added(20).current, 170);
+        assert_eq!(hurt.added(80), HealthData::new(200));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{get_view_aoi_block, MovementStateRow};
+use crate::get_view_aoi_actors;
 use shared::ActorId;
 use spacetimedb::{table, ReducerContext, SpacetimeType, Table, ViewContext};
 
@@ -29,15 +29,22 @@ impl ManaRow {
         ctx.db.mana_tbl().actor_id().find(actor_id)
     }
 
-    /// Adds to the current value, clamping and computing is_full
-    pub fn add(mut self, ctx: &ReducerContext, amount: u16) {
+    /// Adds to the current value, clamping and computing is_full.
+    ///
+    /// Returns `true` when the row was written, unchanged values are not written.
+    pub fn add(mut self, ctx: &ReducerContext, amount: u16) -> bool {
         if amount == 0 || self.is_full {
-            return;
+            return false;
         }
 
+        let previous = self.data.current;
         self.data.current = self.data.current.saturating_add(amount).min(self.data.max);
+        if self.data.current == previous {
+            return false;
+        }
         self.is_full = self.data.current == self.data.max;
         ctx.db.mana_tbl().actor_id().update(self);
+        true
     }
 
     /// Subtracts from the current value, clamping and computing is_full
@@ -100,15 +107,14 @@ impl ManaData {
 }
 
 /// Finds the mana for all things within the AOI.
-/// Primary key of `Owner`
+/// Primary key of `ActorId`
 #[spacetimedb::view(name = mana_view, public)]
 pub fn mana_view(ctx: &ViewContext) -> Vec<ManaRow> {
-    let Some(cell_block) = get_view_aoi_block(ctx) else {
+    let Some(actors) = get_view_aoi_actors(ctx) else {
         return vec![];
     };
 
-    cell_block
-        .flat_map(|cell_id| MovementStateRow::by_cell_id(ctx, cell_id))
+    actors
         .filter_map(|ms| {
             ManaRow::find(ctx, ms.actor_id).map(|row| ManaRow {
                 actor_id: ms.actor_id,
@@ -3,9 +3,11 @@ pub mod mana;
 pub mod primary_stats;
 pub mod regen_stats;
 pub mod secondary_stats;
+pub mod speed_modifier;
 
 pub use health::*;
 pub use mana::*;
 pub use primary_stats::*;
 pub use regen_stats::*;
 pub use secondary_stats::*;
+pub use speed_modifier::*;
@@ -64,7 +64,6 @@ impl PrimaryStatsRow {
         acuity: u8,
         available_points: u8,
     ) {
-        let original_ferocity = self.ferocity;
         let primary_stats = ctx.db.primary_stats_tbl().actor_id().update(Self {
             actor_id: self.actor_id,
             ferocity,
@@ -74,22 +73,17 @@ impl PrimaryStatsRow {
             available_points,
         });
 
-        // TODO: Update derived stats when the base values change. Right now only ferocity is used.
-        if original_ferocity == ferocity {
+        // Acuity doesn't feed into secondary stats.
+        if (self.ferocity, self.fortitude, self.intellect) == (ferocity, fortitude, intellect) {
             return;
         }
 
-        let view_ctx = ctx.as_read_only();
-        if let Some(mut secondary_stats) = SecondaryStatsRow::find(&view_ctx, self.actor_id) {
-            let Some(level) = LevelRow::find(&view_ctx, self.actor_id).map(|r| r.level) else {
-                log::error!("Unable to find level for actor: {:?}", self.actor_id);
-                return;
-            };
-
-            secondary_stats.critical_hit_chance =
-                SecondaryStatsRow::compute_critical_hit_chance(level, primary_stats.ferocity, 0.);
-            secondary_stats.update_from_self(ctx);
-        }
+        let Some(level) = LevelRow::find(&ctx.as_read_only(), self.actor_id).map(|r| r.level)
+        else {
+            log::error!("Unable to find level for actor: {:?}", self.actor_id);
+            return;
+        };
+        SecondaryStatsRow::refresh(ctx, self.actor_id, level, &primary_stats);
     }
 
     /// Determines if stats are within bounds of the available points, level, and and min/max
@@ -116,7 +110,7 @@ impl PrimaryStatsRow {
 
 /// Finds the primary stats for this actor.
 ///
-/// Primary key of `Owner`
+/// Primary key of `ActorId`
 #[spacetimedb::view(name = primary_stats_view, public)]
 pub fn primary_stats_view(ctx: &ViewContext) -> Option<PrimaryStatsRow> {
     let Some(active_character) = ctx.db.character_instance_tbl().identity().find(&ctx.sender)
@@ -1,4 +1,4 @@
-use crate::{health_tbl, mana_tbl};
+use crate::{health_tbl, mana_tbl, CorpseRow, TimingStatsRow, WriteStats};
 use shared::ActorId;
 use spacetimedb::{reducer, table, ReducerContext, ScheduleAt, Table, ViewContext};
 use std::{collections::HashMap, time::Duration};
@@ -54,7 +54,7 @@ pub struct RegenTimer {
 }
 
 /// Regen tick rate is once per second, amount changes per player/monster
-const DT_MILLIS: u64 = 1000;
+pub(crate) const DT_MILLIS: u64 = 1000;
 pub fn init_health_and_mana_regen(ctx: &ReducerContext) {
     ctx.db.regen_tick_timer().scheduled_id().delete(&1);
     ctx.db.regen_tick_timer().insert(RegenTimer {
@@ -72,7 +72,12 @@ fn regen_reducer(ctx: &ReducerContext, _timer: RegenTimer) -> Result<(), String>
 
     let mut regen_cache: HashMap<ActorId, f32> = HashMap::new();
     let view_ctx = ctx.as_read_only();
+    let mut write_stats = WriteStats::default();
     for health_row in ctx.db.health_tbl().is_full().filter(false) {
+        // Corpses don't regenerate, vitals are restored on resurrection.
+        if CorpseRow::find(&view_ctx, health_row.actor_id).is_some() {
+            continue;
+        }
         let Some(row) = RegenStatsRow::find(&view_ctx, health_row.actor_id) else {
             continue;
         };
@@ -80,10 +85,13 @@ fn regen_reducer(ctx: &ReducerContext, _timer: RegenTimer) -> Result<(), String>
         let max = health_row.data.max;
         let rate = RegenStatsRow::compute_regen_rate(row.health_regen_bonus);
         regen_cache.insert(health_row.actor_id, row.mana_regen_bonus);
-        health_row.add(ctx, compute_delta(max, rate));
+        write_stats.record(health_row.add(ctx, compute_delta(max, rate)));
     }
 
     for mana_row in ctx.db.mana_tbl().is_full().filter(false) {
+        if CorpseRow::find(&view_ctx, mana_row.actor_id).is_some() {
+            continue;
+        }
         // Try to get regen info from in-memory cache instead of a DB index seek
         let mana_regen = if let Some(v) = regen_cache.get(&mana_row.actor_id) {
             *v
@@ -95,7 +103,9 @@ fn regen_reducer(ctx: &ReducerContext, _timer: RegenTimer) -> Result<(), String>
 
         let max = mana_row.data.max;
         let rate = RegenStatsRow::compute_regen_rate(mana_regen);
-        mana_row.add(ctx, compute_delta(max, rate));
+        write_stats.record(mana_row.add(ctx, compute_delta(max, rate)));
     }
+
+    TimingStatsRow::record(ctx, TimingStatsRow::REGEN_TICK, write_stats);
     Ok(())
 }
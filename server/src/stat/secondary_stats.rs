@@ -1,4 +1,4 @@
-use crate::{get_view_aoi_block, MovementStateRow};
+use crate::{get_view_aoi_actors, PrimaryStatsRow, SpeedModifierRow};
 use shared::ActorId;
 use spacetimedb::{table, ReducerContext, Table, ViewContext};
 
@@ -7,12 +7,22 @@ pub struct SecondaryStatsRow {
     #[primary_key]
     pub actor_id: ActorId,
 
+    /// Effective movement speed (meters/second), the base speed with the actor's slows and
+    /// hastes applied, see [`SecondaryStatsRow::resolve_movement_speed`].
     pub movement_speed: f32,
 
+    /// Movement speed before modifiers, from the level or the monster archetype.
+    pub base_movement_speed: f32,
+
     /// Critical hit chance normalized to a 0.0–1.0 fraction.
     /// Example: 0.05 = 5% chance.
     pub critical_hit_chance: f32,
-    // pub armor: f32
+
+    /// Mitigates physical damage, see [`crate::combat::armor_mitigation`].
+    pub armor: f32,
+
+    /// Mitigates Veil damage, see [`crate::combat::resistance_mitigation`].
+    pub resistance: f32,
     // pub attack_speed: f32
 }
 
@@ -26,11 +36,17 @@ impl SecondaryStatsRow {
         actor_id: ActorId,
         movement_speed: f32,
         critical_hit_chance: f32,
+        armor: f32,
+        resistance: f32,
     ) {
         ctx.db.secondary_stats_tbl().insert(Self {
             actor_id,
+            // A new actor has no speed modifiers yet.
             movement_speed,
+            base_movement_speed: movement_speed,
             critical_hit_chance,
+            armor,
+            resistance,
         });
     }
     /// Updates from given self, caller should have updated the state with the latest values.
@@ -38,6 +54,62 @@ impl SecondaryStatsRow {
         ctx.db.secondary_stats_tbl().actor_id().update(self);
     }
 
+    /// Recomputes all derived values from their inputs and writes the row only when something
+    /// actually changed.
+    ///
+    /// This is the single entry point for input changes (level, primary stats, buffs, gear) so
+    /// a change to `movement_speed` is always replicated to clients that extrapolate with it.
+    pub fn refresh(ctx: &ReducerContext, actor_id: ActorId, level: u8, primary: &PrimaryStatsRow) {
+        let Some(mut row) = ctx.db.secondary_stats_tbl().actor_id().find(actor_id) else {
+            log::error!("Unable to find secondary stats for actor: {:?}", actor_id);
+            return;
+        };
+
+        let base_movement_speed = Self::compute_movement_speed(level, 0., 0., 0.);
+        let movement_speed =
+            Self::resolve_movement_speed(&ctx.as_read_only(), actor_id, base_movement_speed);
+        let critical_hit_chance = Self::compute_critical_hit_chance(level, primary.ferocity, 0.);
+        let armor = Self::compute_armor(level, primary.fortitude, 0.);
+        let resistance = Self::compute_resistance(level, primary.intellect, 0.);
+        if row.movement_speed == movement_speed
+            && row.base_movement_speed == base_movement_speed
+            && row.critical_hit_chance == critical_hit_chance
+            && row.armor == armor
+            && row.resistance == resistance
+        {
+            return;
+        }
+
+        row.movement_speed = movement_speed;
+        row.base_movement_speed = base_movement_speed;
+        row.critical_hit_chance = critical_hit_chance;
+        row.armor = armor;
+        row.resistance = resistance;
+        row.update_from_self(ctx);
+    }
+
+    /// Re-resolves the effective movement speed after the actor's speed modifiers changed,
+    /// writing the row only when it actually changed.
+    pub fn refresh_movement_speed(ctx: &ReducerContext, actor_id: ActorId) {
+        let Some(mut row) = ctx.db.secondary_stats_tbl().actor_id().find(actor_id) else {
+            log::error!("Unable to find secondary stats for actor: {:?}", actor_id);
+            return;
+        };
+        let movement_speed =
+            Self::resolve_movement_speed(&ctx.as_read_only(), actor_id, row.base_movement_speed);
+        if row.movement_speed == movement_speed {
+            return;
+        }
+        row.movement_speed = movement_speed;
+        row.update_from_self(ctx);
+    }
+
+    /// `base` with the actor's speed modifiers (see [`SpeedModifierRow`]) applied, within
+    /// `0..=MAX_MOVEMENT_SPEED`.
+    pub fn resolve_movement_speed(ctx: &ViewContext, actor_id: ActorId, base: f32) -> f32 {
+        SpeedModifierRow::apply_all(ctx, actor_id, base).clamp(0.0, Self::MAX_MOVEMENT_SPEED)
+    }
+
     const MAX_MOVEMENT_SPEED: f32 = 6.5;
 
     /// Critical hit chance cap as a normalized fraction (0.0–1.0).
@@ -79,23 +151,43 @@ impl SecondaryStatsRow {
         (base_chance * (1. + ferocity_bonus + level_bonus) * gear_multiplier)
             .min(Self::MAX_CRITICAL_HIT_CHANCE)
     }
+
+    /// Armor is determined by level, fortitude (primary stat), and gear.
+    ///
+    /// Note: the gear bonus is a decimal percentage (normalized between 0 and 1).
+    ///
+    /// TODO: implement gear
+    pub fn compute_armor(level: u8, fortitude: u8, gear: f32) -> f32 {
+        (fortitude as f32 * 2.0 + level as f32 * 1.5) * (1. + gear)
+    }
+
+    /// Resistance is determined by level, intellect (primary stat), and gear.
+    ///
+    /// Note: the gear bonus is a decimal percentage (normalized between 0 and 1).
+    ///
+    /// TODO: implement gear
+    pub fn compute_resistance(level: u8, intellect: u8, gear: f32) -> f32 {
+        (intellect as f32 * 2.0 + level as f32 * 1.5) * (1. + gear)
+    }
 }
 
 /// Finds the secondary stats for all actors within the AOI.
 /// Primary key of `ActorId`
 #[spacetimedb::view(name = secondary_stats_view, public)]
 pub fn secondary_stats_view(ctx: &ViewContext) -> Vec<SecondaryStatsRow> {
-    let Some(cell_block) = get_view_aoi_block(ctx) else {
+    let Some(actors) = get_view_aoi_actors(ctx) else {
         return vec![];
     };
 
-    cell_block
-        .flat_map(|cell_id| MovementStateRow::by_cell_id(ctx, cell_id))
+    actors
         .filter_map(|ms| {
             SecondaryStatsRow::find(ctx, ms.actor_id).map(|row| SecondaryStatsRow {
                 actor_id: ms.actor_id,
                 movement_speed: row.movement_speed,
+                base_movement_speed: row.base_movement_speed,
                 critical_hit_chance: row.critical_hit_chance,
+                armor: row.armor,
+                resistance: row.resistance,
             })
         })
         .collect()
@@ -0,0 +1,200 @@
+use crate::{AdminIdentityRow, SecondaryStatsRow};
+use shared::ActorId;
+use spacetimedb::{
+    reducer, table, ReducerContext, ScheduleAt, SpacetimeType, Table, TimeDuration, Timestamp,
+    ViewContext,
+};
+use std::time::Duration;
+
+/// Bounds of a single modifier, keeps a bad ability definition or admin call from freezing or
+/// launching an actor. The resolved speed is capped separately, see
+/// [`SecondaryStatsRow::resolve_movement_speed`].
+const MAX_ADDITIVE_MPS: f32 = 5.0;
+const MAX_MULTIPLIER: f32 = 2.0;
+
+/// Where a modifier came from. An actor has at most one modifier per source, applying the same
+/// source again replaces it (refreshing a slow doesn't stack it).
+#[derive(SpacetimeType, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpeedModifierSource {
+    /// An ability, by ability id.
+    Ability(u16),
+    /// Applied by an admin, see [`apply_speed_modifier`].
+    Admin,
+}
+
+#[derive(SpacetimeType, Debug, Clone, Copy, PartialEq)]
+pub enum SpeedModifierOp {
+    /// Adds meters/second to the base speed, negative to slow.
+    Add(f32),
+    /// Multiplies the speed after all additions, below 1 to slow.
+    Multiply(f32),
+}
+
+impl SpeedModifierOp {
+    fn validate(self) -> Result<Self, String> {
+        match self {
+            Self::Add(mps) if mps.is_finite() && mps.abs() <= MAX_ADDITIVE_MPS => Ok(self),
+            Self::Multiply(factor)
+                if factor.is_finite() && (0.0..=MAX_MULTIPLIER).contains(&factor) =>
+            {
+                Ok(self)
+            }
+            _ => Err("Speed modifier out of range".into()),
+        }
+    }
+}
+
+/// **Ephemeral**
+///
+/// Slows and hastes on an actor, resolved into the replicated
+/// [`SecondaryStatsRow::movement_speed`] whenever the stack changes. The movement tick and the
+/// clients' extrapolation both read that resolved value, so prediction always agrees with the
+/// server.
+#[table(name=speed_modifier_tbl)]
+pub struct SpeedModifierRow {
+    #[auto_inc]
+    #[primary_key]
+    pub id: u64,
+
+    #[index(btree)]
+    pub actor_id: ActorId,
+
+    pub source: SpeedModifierSource,
+
+    pub op: SpeedModifierOp,
+
+    /// `None` lasts until removed, see [`SpeedModifierRow::remove`].
+    pub expires_at: Option<Timestamp>,
+}
+
+/// One-shot timer removing a modifier once it expires.
+#[table(name = speed_modifier_expiry_timer, scheduled(speed_modifier_expiry_reducer))]
+pub struct SpeedModifierExpiryTimer {
+    #[primary_key]
+    #[auto_inc]
+    pub scheduled_id: u64,
+    pub scheduled_at: ScheduleAt,
+    pub modifier_id: u64,
+}
+
+impl SpeedModifierRow {
+    pub fn for_actor(ctx: &ViewContext, actor_id: ActorId) -> impl Iterator<Item = Self> + '_ {
+        ctx.db.speed_modifier_tbl().actor_id().filter(actor_id)
+    }
+
+    /// `base` movement speed with the actor's modifiers applied: additions first, then
+    /// multipliers. Not clamped.
+    pub fn apply_all(ctx: &ViewContext, actor_id: ActorId, base: f32) -> f32 {
+        let (added, multiplier) =
+            Self::for_actor(ctx, actor_id).fold((0.0, 1.0), |(added, multiplier), row| {
+                match row.op {
+                    SpeedModifierOp::Add(mps) => (added + mps, multiplier),
+                    SpeedModifierOp::Multiply(factor) => (added, multiplier * factor),
+                }
+            });
+        (base + added) * multiplier
+    }
+
+    /// Applies (or replaces) the modifier of `source` on the actor, lasting `duration` or until
+    /// removed, and updates the actor's movement speed.
+    pub fn apply(
+        ctx: &ReducerContext,
+        actor_id: ActorId,
+        source: SpeedModifierSource,
+        op: SpeedModifierOp,
+        duration: Option<TimeDuration>,
+    ) -> Result<(), String> {
+        let op = op.validate()?;
+        let expires_at = duration.map(|duration| ctx.timestamp + duration);
+        let existing =
+            Self::for_actor(&ctx.as_read_only(), actor_id).find(|row| row.source == source);
+        let row = match existing {
+            Some(mut row) => {
+                row.op = op;
+                row.expires_at = expires_at;
+                ctx.db.speed_modifier_tbl().id().update(row)
+            }
+            None => ctx.db.speed_modifier_tbl().insert(Self {
+                id: 0,
+                actor_id,
+                source,
+                op,
+                expires_at,
+            }),
+        };
+        // A replaced modifier's earlier timer still fires, it finds the new `expires_at` in the
+        // future and leaves the row alone.
+        if let Some(expires_at) = expires_at {
+            ctx.db
+                .speed_modifier_expiry_timer()
+                .insert(SpeedModifierExpiryTimer {
+                    scheduled_id: 0,
+                    scheduled_at: ScheduleAt::Time(expires_at),
+                    modifier_id: row.id,
+                });
+        }
+        SecondaryStatsRow::refresh_movement_speed(ctx, actor_id);
+        Ok(())
+    }
+
+    /// Removes the modifier of `source` from the actor, if any, and updates its movement speed.
+    pub fn remove(ctx: &ReducerContext, actor_id: ActorId, source: SpeedModifierSource) {
+        let Some(row) =
+            Self::for_actor(&ctx.as_read_only(), actor_id).find(|row| row.source == source)
+        else {
+            return;
+        };
+        ctx.db.speed_modifier_tbl().id().delete(row.id);
+        SecondaryStatsRow::refresh_movement_speed(ctx, actor_id);
+    }
+
+    pub fn delete_for_actor(ctx: &ReducerContext, actor_id: ActorId) {
+        ctx.db.speed_modifier_tbl().actor_id().delete(actor_id);
+    }
+}
+
+#[reducer]
+fn speed_modifier_expiry_reducer(
+    ctx: &ReducerContext,
+    timer: SpeedModifierExpiryTimer,
+) -> Result<(), String> {
+    if ctx.sender != ctx.identity() {
+        log::error!("`speed_modifier_expiry_reducer` may not be invoked by clients.");
+        return Err("`speed_modifier_expiry_reducer` may not be invoked by clients.".into());
+    }
+
+    let Some(row) = ctx.db.speed_modifier_tbl().id().find(timer.modifier_id) else {
+        return Ok(());
+    };
+    if row
+        .expires_at
+        .is_none_or(|expires_at| expires_at > ctx.timestamp)
+    {
+        return Ok(());
+    }
+    ctx.db.speed_modifier_tbl().id().delete(row.id);
+    SecondaryStatsRow::refresh_movement_speed(ctx, row.actor_id);
+    Ok(())
+}
+
+/// Applies a speed modifier to any actor, for `duration_millis` or until cleared with
+/// [`clear_speed_modifier`]. Admin only.
+#[reducer]
+pub fn apply_speed_modifier(
+    ctx: &ReducerContext,
+    actor_id: ActorId,
+    op: SpeedModifierOp,
+    duration_millis: Option<u64>,
+) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "apply_speed_modifier")?;
+    let duration = duration_millis.map(|millis| Duration::from_millis(millis).into());
+    SpeedModifierRow::apply(ctx, actor_id, SpeedModifierSource::Admin, op, duration)
+}
+
+/// Removes the modifier applied by [`apply_speed_modifier`]. Admin only.
+#[reducer]
+pub fn clear_speed_modifier(ctx: &ReducerContext, actor_id: ActorId) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "clear_speed_modifier")?;
+    SpeedModifierRow::remove(ctx, actor_id, SpeedModifierSource::Admin);
+    Ok(())
+}
@@ -0,0 +1,92 @@
+use crate::{character_instance_tbl, get_view_aoi_actors, is_in_aoi, ActivityRow, ActorRow};
+use shared::ActorId;
+use spacetimedb::{reducer, table, ReducerContext, Table, ViewContext};
+
+/// The actor an actor currently has targeted, missing when it has no target.
+///
+/// Replicated to everyone in the AOI so others can assist (target-of-target).
+#[table(name=target_tbl)]
+pub struct TargetRow {
+    #[primary_key]
+    pub actor_id: ActorId,
+
+    #[index(btree)]
+    pub target: ActorId,
+}
+
+impl TargetRow {
+    pub fn find(ctx: &ViewContext, actor_id: ActorId) -> Option<Self> {
+        ctx.db.target_tbl().actor_id().find(actor_id)
+    }
+
+    /// The target an action of `actor_id` applies to, the explicit one when given and the stored
+    /// one otherwise.
+    ///
+    /// The stored target was checked when it was set, it's checked again here since the target
+    /// may have left the AOI or turned invisible since, see [`is_in_aoi`].
+    pub fn resolve(
+        ctx: &ViewContext,
+        actor_id: ActorId,
+        explicit: Option<ActorId>,
+    ) -> Option<ActorId> {
+        explicit.or_else(|| Self::find_visible(ctx, actor_id).map(|row| row.target))
+    }
+
+    /// [`Self::find`], but only while `actor_id` can still see its target.
+    ///
+    /// **Performance & Cost**: O(1), five index seeks
+    pub fn find_visible(ctx: &ViewContext, actor_id: ActorId) -> Option<Self> {
+        Self::find(ctx, actor_id).filter(|row| is_in_aoi(ctx, actor_id, row.target))
+    }
+
+    /// Removes `actor_id`'s target and clears it as the target of everyone else.
+    pub fn delete_for_actor(ctx: &ReducerContext, actor_id: ActorId) {
+        ctx.db.target_tbl().actor_id().delete(actor_id);
+        ctx.db.target_tbl().target().delete(actor_id);
+    }
+}
+
+/// Sets the current target of the player's active character, `None` clears it.
+///
+/// The target has to be in the character's AOI and visible to it.
+#[reducer]
+pub fn set_target(ctx: &ReducerContext, target: Option<ActorId>) -> Result<(), String> {
+    let Some(ci) = ctx.db.character_instance_tbl().identity().find(ctx.sender) else {
+        log::error!("set_target: no active character for {:?}", ctx.sender);
+        return Err("No active character".into());
+    };
+    ActivityRow::touch(ctx);
+    let actor_id = ci.actor_id;
+
+    let Some(target) = target else {
+        ctx.db.target_tbl().actor_id().delete(actor_id);
+        return Ok(());
+    };
+
+    let view_ctx = ctx.as_read_only();
+    if ActorRow::find(&view_ctx, target).is_none() || !is_in_aoi(&view_ctx, actor_id, target) {
+        return Err("Target is not in range".into());
+    }
+
+    let row = TargetRow { actor_id, target };
+    if TargetRow::find(&view_ctx, actor_id).is_some() {
+        ctx.db.target_tbl().actor_id().update(row);
+    } else {
+        ctx.db.target_tbl().insert(row);
+    }
+    Ok(())
+}
+
+/// Finds the targets of all actors within the AOI, leaving out targets their actor can no
+/// longer see.
+/// Primary key of `ActorId`
+#[spacetimedb::view(name = target_view, public)]
+pub fn target_view(ctx: &ViewContext) -> Vec<TargetRow> {
+    let Some(actors) = get_view_aoi_actors(ctx) else {
+        return vec![];
+    };
+
+    actors
+        .filter_map(|ms| TargetRow::find_visible(ctx, ms.actor_id))
+        .collect()
+}
@@ -0,0 +1,253 @@
+//! Self-healing of the scheduled timer rows.
+//!
+//! A scheduled reducer only runs while its timer row exists, a row deleted or rewritten (e.g. by a
+//! migration or a manual SQL edit) silently stops that tick. The watchdog checks every timer row
+//! for its expected interval, recreates missing rows, resets wrong intervals and records each
+//! repair to the event log.
+//!
+//! Besides its own timer the watchdog also runs on client connect, so a deleted watchdog timer
+//! is recreated as well.
+
+use crate::{
+    activity_timer, ai_tick_timer, combat_event_cleanup_timer, corpse_decay_timer,
+    domain_event_timer, duel_check_timer, gc_timer, hazard_tick_timer, init_activity, init_ai,
+    init_combat_event_cleanup, init_corpse_decay, init_domain_events, init_duel_check, init_gc,
+    init_hazards, init_health_and_mana_regen, init_load_shedding, init_metrics, init_movement_tick,
+    init_persistence, init_projectiles, init_scripted_path, load_shedding_timer, metrics_timer,
+    movement_tick_timer, persistence_timer, projectile_tick_timer, regen_tick_timer,
+    scripted_path_timer, EventKind, EventLogRow, LoadSheddingRow, TimingStatsRow, WriteStats,
+};
+use shared::MOVEMENT_TICK_INTERVAL_MICROS;
+use spacetimedb::{reducer, table, ReducerContext, ScheduleAt, Table, TimeDuration};
+use std::time::Duration;
+
+const TIMER_WATCHDOG_INTERVAL_MILLIS: u64 = 30_000;
+
+#[table(name = timer_watchdog_timer, scheduled(timer_watchdog_reducer))]
+pub struct TimerWatchdogTimer {
+    #[primary_key]
+    #[auto_inc]
+    pub scheduled_id: u64,
+    pub scheduled_at: ScheduleAt,
+}
+
+pub fn init_timer_watchdog(ctx: &ReducerContext) {
+    ctx.db.timer_watchdog_timer().scheduled_id().delete(1);
+    ctx.db.timer_watchdog_timer().insert(TimerWatchdogTimer {
+        scheduled_id: 1,
+        scheduled_at: interval_millis(TIMER_WATCHDOG_INTERVAL_MILLIS),
+    });
+    log::info!("init timer watchdog");
+}
+
+fn interval_millis(millis: u64) -> ScheduleAt {
+    Duration::from_millis(millis).into()
+}
+
+/// Repairs the timer `name` with `repair` unless `found` is its expected interval.
+/// Returns `true` when the timer was repaired.
+fn ensure_timer(
+    ctx: &ReducerContext,
+    name: &str,
+    found: Option<ScheduleAt>,
+    expected: ScheduleAt,
+    repair: impl FnOnce(&ReducerContext),
+) -> bool {
+    let message = match found {
+        Some(found) if found == expected => return false,
+        Some(found) => format!("{name}: interval was {found:?}, reset to {expected:?}"),
+        None => format!("{name}: timer row was missing, recreated"),
+    };
+    repair(ctx);
+    EventLogRow::record(ctx, EventKind::TimerRepaired, None, message);
+    true
+}
+
+/// Checks every timer row (scheduled id 1) and repairs the broken ones, see the module docs.
+///
+/// **Performance & Cost**: one primary key seek per timer.
+pub fn ensure_timers(ctx: &ReducerContext, write_stats: &mut WriteStats) {
+    let db = &ctx.db;
+
+    // Re-initializing would restart the server tick numbers clients order updates by, a wrong
+    // interval only reschedules the existing row.
+    let movement_tick = db.movement_tick_timer().scheduled_id().find(1);
+    let movement_interval =
+        ScheduleAt::Interval(TimeDuration::from_micros(MOVEMENT_TICK_INTERVAL_MICROS));
+    write_stats.record(ensure_timer(
+        ctx,
+        "movement_tick_timer",
+        movement_tick.as_ref().map(|timer| timer.scheduled_at),
+        movement_interval,
+        |ctx| match movement_tick {
+            Some(mut timer) => {
+                timer.scheduled_at = movement_interval;
+                ctx.db.movement_tick_timer().scheduled_id().update(timer);
+            }
+            None => init_movement_tick(ctx),
+        },
+    ));
+
+    let timers: [(&str, Option<ScheduleAt>, u64, fn(&ReducerContext)); 14] = [
+        (
+            "regen_tick_timer",
+            db.regen_tick_timer()
+                .scheduled_id()
+                .find(1)
+                .map(|timer| timer.scheduled_at),
+            crate::stat::regen_stats::DT_MILLIS,
+            init_health_and_mana_regen,
+        ),
+        (
+            "persistence_timer",
+            db.persistence_timer()
+                .scheduled_id()
+                .find(1)
+                .map(|timer| timer.scheduled_at),
+            crate::persistence::PERSISTENCE_INTERVAL_MILLIS,
+            init_persistence,
+        ),
+        (
+            "corpse_decay_timer",
+            db.corpse_decay_timer()
+                .scheduled_id()
+                .find(1)
+                .map(|timer| timer.scheduled_at),
+            crate::corpse::DECAY_INTERVAL_MILLIS,
+            init_corpse_decay,
+        ),
+        (
+            "combat_event_cleanup_timer",
+            db.combat_event_cleanup_timer()
+                .scheduled_id()
+                .find(1)
+                .map(|timer| timer.scheduled_at),
+            crate::combat_event::CLEANUP_INTERVAL_MILLIS,
+            init_combat_event_cleanup,
+        ),
+        (
+            "gc_timer",
+            db.gc_timer()
+                .scheduled_id()
+                .find(1)
+                .map(|timer| timer.scheduled_at),
+            crate::gc::GC_INTERVAL_MILLIS,
+            init_gc,
+        ),
+        (
+            "metrics_timer",
+            db.metrics_timer()
+                .scheduled_id()
+                .find(1)
+                .map(|timer| timer.scheduled_at),
+            crate::metrics::METRICS_INTERVAL_MILLIS,
+            init_metrics,
+        ),
+        (
+            "duel_check_timer",
+            db.duel_check_timer()
+                .scheduled_id()
+                .find(1)
+                .map(|timer| timer.scheduled_at),
+            crate::duel::DUEL_CHECK_INTERVAL_MILLIS,
+            init_duel_check,
+        ),
+        (
+            "scripted_path_timer",
+            db.scripted_path_timer()
+                .scheduled_id()
+                .find(1)
+                .map(|timer| timer.scheduled_at),
+            crate::movement::scripted_path::SCRIPTED_PATH_INTERVAL_MILLIS,
+            init_scripted_path,
+        ),
+        (
+            "ai_tick_timer",
+            db.ai_tick_timer()
+                .scheduled_id()
+                .find(1)
+                .map(|timer| timer.scheduled_at),
+            // Possibly stretched under load, see `LoadSheddingRow`.
+            LoadSheddingRow::ai_tick_interval_millis(&ctx.as_read_only()),
+            init_ai,
+        ),
+        (
+            "load_shedding_timer",
+            db.load_shedding_timer()
+                .scheduled_id()
+                .find(1)
+                .map(|timer| timer.scheduled_at),
+            crate::load_shedding::LOAD_SHEDDING_INTERVAL_MILLIS,
+            init_load_shedding,
+        ),
+        (
+            "hazard_tick_timer",
+            db.hazard_tick_timer()
+                .scheduled_id()
+                .find(1)
+                .map(|timer| timer.scheduled_at),
+            crate::world::hazard::HAZARD_TICK_INTERVAL_MILLIS,
+            init_hazards,
+        ),
+        (
+            "projectile_tick_timer",
+            db.projectile_tick_timer()
+                .scheduled_id()
+                .find(1)
+                .map(|timer| timer.scheduled_at),
+            crate::world::projectile::PROJECTILE_TICK_INTERVAL_MILLIS,
+            init_projectiles,
+        ),
+        (
+            "activity_timer",
+            db.activity_timer()
+                .scheduled_id()
+                .find(1)
+                .map(|timer| timer.scheduled_at),
+            crate::activity::ACTIVITY_TICK_INTERVAL_MILLIS,
+            init_activity,
+        ),
+        (
+            "domain_event_timer",
+            db.domain_event_timer()
+                .scheduled_id()
+                .find(1)
+                .map(|timer| timer.scheduled_at),
+            crate::domain_event::DOMAIN_EVENT_TICK_INTERVAL_MILLIS,
+            init_domain_events,
+        ),
+    ];
+    for (name, found, millis, init) in timers {
+        write_stats.record(ensure_timer(
+            ctx,
+            name,
+            found,
+            interval_millis(millis),
+            init,
+        ));
+    }
+
+    write_stats.record(ensure_timer(
+        ctx,
+        "timer_watchdog_timer",
+        db.timer_watchdog_timer()
+            .scheduled_id()
+            .find(1)
+            .map(|timer| timer.scheduled_at),
+        interval_millis(TIMER_WATCHDOG_INTERVAL_MILLIS),
+        init_timer_watchdog,
+    ));
+}
+
+#[reducer]
+fn timer_watchdog_reducer(ctx: &ReducerContext, _timer: TimerWatchdogTimer) -> Result<(), String> {
+    if ctx.sender != ctx.identity() {
+        log::error!("`timer_watchdog_reducer` may not be invoked by clients.");
+        return Err("`timer_watchdog_reducer` may not be invoked by clients.".into());
+    }
+
+    let mut write_stats = WriteStats::default();
+    ensure_timers(ctx, &mut write_stats);
+    TimingStatsRow::record(ctx, TimingStatsRow::TIMER_WATCHDOG_TICK, write_stats);
+    Ok(())
+}
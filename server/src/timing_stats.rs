@@ -0,0 +1,154 @@
+use spacetimedb::{table, ReducerContext, Table, Timestamp};
+
+/// Per-tick counters used to guard against replicating rows that didn't change.
+///
+/// Every write a tick *could* make goes through [`WriteStats::update_if_changed`], which compares
+/// the old and new values and performs the write only when they differ, tallying the outcome.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct WriteStats {
+    /// Rows written this tick.
+    pub writes: u32,
+    /// Writes skipped this tick because the row was unchanged.
+    pub suppressed: u32,
+    /// Scratch buffers kept between runs (e.g. the movement tick's caches) that had to grow this
+    /// tick, zero once they're warmed up.
+    pub scratch_growths: u32,
+}
+
+impl WriteStats {
+    /// Runs `write` only when `new` differs from `old`, counting either the write or the
+    /// suppression.
+    #[inline]
+    pub fn update_if_changed<T: PartialEq + ?Sized>(
+        &mut self,
+        old: &T,
+        new: &T,
+        write: impl FnOnce(),
+    ) {
+        if old != new {
+            write();
+            self.writes += 1;
+        } else {
+            self.suppressed += 1;
+        }
+    }
+
+    /// Records the outcome of a write that was already guarded, by the callee or a dirty flag.
+    #[inline]
+    pub fn record(&mut self, written: bool) {
+        if written {
+            self.writes += 1;
+        } else {
+            self.suppressed += 1;
+        }
+    }
+}
+
+/// Bookkeeping for each scheduled tick, one row per tick name.
+///
+/// Private, it's written on every tick: operators and clients read it through
+/// [`crate::MetricRow`]s, refreshed by the metrics tick.
+#[table(name=timing_stats_tbl)]
+pub struct TimingStatsRow {
+    /// The name of the tick, e.g. `movement_tick`.
+    #[primary_key]
+    pub name: String,
+
+    pub last_run_at: Timestamp,
+
+    /// Total number of times this tick has run.
+    pub runs: u64,
+
+    /// Rows written during the last run.
+    pub writes: u32,
+
+    /// Writes suppressed during the last run because nothing changed.
+    pub suppressed_writes: u32,
+
+    /// Writes suppressed across all runs.
+    pub total_suppressed_writes: u64,
+
+    /// Scratch buffers that had to grow during the last run, see [`WriteStats::scratch_growths`].
+    pub scratch_growths: u32,
+
+    /// Scratch buffer growths across all runs, stops rising once the buffers are warmed up.
+    pub total_scratch_growths: u64,
+
+    /// Exponential moving average of the time between runs (microseconds), drifts above the
+    /// scheduled interval when the scheduler falls behind.
+    pub interval_ema_micros: f32,
+
+    /// Exponential moving average of the rows written per run.
+    pub writes_ema: f32,
+}
+
+impl TimingStatsRow {
+    /// Weight of the latest run in the moving averages.
+    const EMA_ALPHA: f32 = 0.1;
+
+    pub const MOVEMENT_TICK: &'static str = "movement_tick";
+    pub const REGEN_TICK: &'static str = "regen_tick";
+    pub const PERSISTENCE_TICK: &'static str = "persistence_tick";
+    pub const CORPSE_DECAY_TICK: &'static str = "corpse_decay_tick";
+    pub const COMBAT_EVENT_CLEANUP_TICK: &'static str = "combat_event_cleanup_tick";
+    pub const GC_TICK: &'static str = "gc_tick";
+    pub const METRICS_TICK: &'static str = "metrics_tick";
+    pub const DUEL_CHECK_TICK: &'static str = "duel_check_tick";
+    pub const SCRIPTED_PATH_TICK: &'static str = "scripted_path_tick";
+    pub const AI_TICK: &'static str = "ai_tick";
+    pub const TIMER_WATCHDOG_TICK: &'static str = "timer_watchdog_tick";
+    pub const LOAD_SHEDDING_TICK: &'static str = "load_shedding_tick";
+    pub const HAZARD_TICK: &'static str = "hazard_tick";
+    pub const PROJECTILE_TICK: &'static str = "projectile_tick";
+    pub const ACTIVITY_TICK: &'static str = "activity_tick";
+    pub const DOMAIN_EVENT_TICK: &'static str = "domain_event_tick";
+
+    /// Upserts the stats row for the given tick with the results of this run.
+    pub fn record(ctx: &ReducerContext, name: &str, stats: WriteStats) {
+        match ctx.db.timing_stats_tbl().name().find(name.to_string()) {
+            Some(mut row) => {
+                let interval_micros = ctx
+                    .timestamp
+                    .time_duration_since(row.last_run_at)
+                    .map_or(0.0, |interval| interval.to_micros() as f32);
+                // The first interval seeds the average instead of being pulled towards zero.
+                row.interval_ema_micros = if row.runs == 1 {
+                    interval_micros
+                } else {
+                    ema(row.interval_ema_micros, interval_micros)
+                };
+                row.writes_ema = ema(row.writes_ema, stats.writes as f32);
+                row.last_run_at = ctx.timestamp;
+                row.runs = row.runs.saturating_add(1);
+                row.writes = stats.writes;
+                row.suppressed_writes = stats.suppressed;
+                row.total_suppressed_writes = row
+                    .total_suppressed_writes
+                    .saturating_add(stats.suppressed as u64);
+                row.scratch_growths = stats.scratch_growths;
+                row.total_scratch_growths = row
+                    .total_scratch_growths
+                    .saturating_add(stats.scratch_growths as u64);
+                ctx.db.timing_stats_tbl().name().update(row);
+            }
+            None => {
+                ctx.db.timing_stats_tbl().insert(Self {
+                    name: name.to_string(),
+                    last_run_at: ctx.timestamp,
+                    runs: 1,
+                    writes: stats.writes,
+                    suppressed_writes: stats.suppressed,
+                    total_suppressed_writes: stats.suppressed as u64,
+                    scratch_growths: stats.scratch_growths,
+                    total_scratch_growths: stats.scratch_growths as u64,
+                    interval_ema_micros: 0.0,
+                    writes_ema: stats.writes as f32,
+                });
+            }
+        }
+    }
+}
+
+fn ema(average: f32, value: f32) -> f32 {
+    average + (value - average) * TimingStatsRow::EMA_ALPHA
+}
@@ -0,0 +1,200 @@
+use crate::{
+    dummy_stats_tbl, monster_archetype_tbl, sender_instance_id, ActorRow, AdminIdentityRow,
+    CapsuleY, MonsterArchetypeRow, Vec3,
+};
+use shared::{validate, ActorId};
+use spacetimedb::{reducer, table, ReducerContext, SpacetimeType, Table, Timestamp};
+
+/// Rolling windows (seconds) the dummy DPS is reported over.
+const SHORT_WINDOW_SECS: i64 = 10;
+const LONG_WINDOW_SECS: i64 = 60;
+
+/// Bound on [`DummyStatsRow::recent_hits`], a dummy hit more often than this within
+/// [`LONG_WINDOW_SECS`] under-reports its long window.
+const MAX_RECENT_HITS: usize = 1024;
+
+/// DPS of a fight shorter than this (seconds) is reported over this duration instead, so a
+/// single hit doesn't report an absurd DPS.
+const MIN_ELAPSED_SECS: f32 = 1.0;
+
+/// Name of the training dummy archetype, seeded by the schema migrations.
+pub const TRAINING_DUMMY_ARCHETYPE: &str = "Training Dummy";
+
+/// One hit on a dummy, for the rolling DPS windows.
+#[derive(SpacetimeType, Debug, Clone, Copy, PartialEq)]
+pub struct DummyHit {
+    pub at: Timestamp,
+    pub amount: u16,
+}
+
+/// Damage statistics of a training dummy, the row also marks the actor as a dummy.
+///
+/// Dummies are monsters without a behavior tree that never lose health: damage is resolved as
+/// usual (combat math, combat events) and recorded here instead of applied, see
+/// [`crate::HealthRow::take_damage`]. Read by developers via SQL.
+#[table(name=dummy_stats_tbl)]
+pub struct DummyStatsRow {
+    #[primary_key]
+    pub actor_id: ActorId,
+
+    /// Damage taken since the spawn or the last [`reset_dummy_stats`].
+    pub total_damage: u64,
+    pub hits: u32,
+
+    pub first_hit_at: Option<Timestamp>,
+    pub last_hit_at: Option<Timestamp>,
+
+    /// Average DPS from the first to the last hit.
+    pub overall_dps: f32,
+
+    /// DPS over the last [`SHORT_WINDOW_SECS`] before the last hit.
+    pub short_window_dps: f32,
+
+    /// DPS over the last [`LONG_WINDOW_SECS`] before the last hit.
+    pub long_window_dps: f32,
+
+    /// The hits within [`LONG_WINDOW_SECS`] of the last hit, oldest first.
+    pub recent_hits: Vec<DummyHit>,
+}
+
+fn elapsed_secs(from: Timestamp, to: Timestamp) -> f32 {
+    to.time_duration_since(from)
+        .map(|duration| duration.to_micros() as f32 / 1_000_000.0)
+        .unwrap_or(0.0)
+}
+
+impl DummyStatsRow {
+    pub fn is_dummy(ctx: &ReducerContext, actor_id: ActorId) -> bool {
+        ctx.db.dummy_stats_tbl().actor_id().find(actor_id).is_some()
+    }
+
+    fn empty(actor_id: ActorId) -> Self {
+        Self {
+            actor_id,
+            total_damage: 0,
+            hits: 0,
+            first_hit_at: None,
+            last_hit_at: None,
+            overall_dps: 0.0,
+            short_window_dps: 0.0,
+            long_window_dps: 0.0,
+            recent_hits: Vec::new(),
+        }
+    }
+
+    /// DPS of the recent hits within `window_secs` of `now`.
+    fn window_dps(&self, now: Timestamp, window_secs: i64) -> f32 {
+        let cutoff = now.to_micros_since_unix_epoch() - window_secs * 1_000_000;
+        let mut hits = self
+            .recent_hits
+            .iter()
+            .filter(|hit| hit.at.to_micros_since_unix_epoch() >= cutoff);
+        let Some(first) = hits.next() else {
+            return 0.0;
+        };
+        let damage: u32 = first.amount as u32 + hits.map(|hit| hit.amount as u32).sum::<u32>();
+        let elapsed = elapsed_secs(first.at, now).clamp(MIN_ELAPSED_SECS, window_secs as f32);
+        damage as f32 / elapsed
+    }
+
+    /// Records a hit on the dummy `actor_id`, does nothing for other actors.
+    pub fn record_hit(ctx: &ReducerContext, actor_id: ActorId, amount: u16) {
+        let Some(mut row) = ctx.db.dummy_stats_tbl().actor_id().find(actor_id) else {
+            return;
+        };
+        let now = ctx.timestamp;
+        let first_hit_at = *row.first_hit_at.get_or_insert(now);
+        row.total_damage += amount as u64;
+        row.hits += 1;
+        row.last_hit_at = Some(now);
+
+        let cutoff = now.to_micros_since_unix_epoch() - LONG_WINDOW_SECS * 1_000_000;
+        row.recent_hits
+            .retain(|hit| hit.at.to_micros_since_unix_epoch() >= cutoff);
+        if row.recent_hits.len() >= MAX_RECENT_HITS {
+            row.recent_hits.remove(0);
+        }
+        row.recent_hits.push(DummyHit { at: now, amount });
+
+        row.overall_dps =
+            row.total_damage as f32 / elapsed_secs(first_hit_at, now).max(MIN_ELAPSED_SECS);
+        row.short_window_dps = row.window_dps(now, SHORT_WINDOW_SECS);
+        row.long_window_dps = row.window_dps(now, LONG_WINDOW_SECS);
+        ctx.db.dummy_stats_tbl().actor_id().update(row);
+    }
+
+    /// The training dummy archetype, see [`TRAINING_DUMMY_ARCHETYPE`].
+    pub fn seed_archetype(ctx: &ReducerContext) {
+        MonsterArchetypeRow::insert(
+            ctx,
+            MonsterArchetypeRow {
+                id: 0,
+                name: TRAINING_DUMMY_ARCHETYPE.into(),
+                level: 1,
+                ferocity: 0,
+                fortitude: 10,
+                intellect: 0,
+                acuity: 0,
+                capsule: CapsuleY {
+                    radius: 0.3,
+                    half_height: 0.9,
+                },
+                scale: 1.0,
+                movement_speed: 0.0,
+                aggro_radius: 0.0,
+                max_drop: None,
+                xp_reward: 0,
+                loot_table_id: None,
+            },
+        );
+    }
+}
+
+/// Spawns a stationary training dummy at the given position in the admin's current instance.
+/// Admin only.
+#[reducer]
+pub fn spawn_training_dummy(ctx: &ReducerContext, translation: Vec3) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "spawn_training_dummy")?;
+    let translation = Vec3::from(validate::within_world(translation.into())?);
+    let Some(archetype) = ctx
+        .db
+        .monster_archetype_tbl()
+        .name()
+        .find(TRAINING_DUMMY_ARCHETYPE.to_string())
+    else {
+        log::error!("Unable to find the {} archetype", TRAINING_DUMMY_ARCHETYPE);
+        return Err("Unable to find the training dummy archetype".into());
+    };
+
+    let actor_id = archetype.spawn(ctx, sender_instance_id(ctx), translation, 0.0);
+    ctx.db
+        .dummy_stats_tbl()
+        .insert(DummyStatsRow::empty(actor_id));
+    log::info!("Spawned training dummy {}", actor_id);
+    Ok(())
+}
+
+/// Clears the damage statistics of a training dummy. Admin only.
+#[reducer]
+pub fn reset_dummy_stats(ctx: &ReducerContext, actor_id: ActorId) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "reset_dummy_stats")?;
+    if !DummyStatsRow::is_dummy(ctx, actor_id) {
+        return Err("Not a training dummy".into());
+    }
+    ctx.db
+        .dummy_stats_tbl()
+        .actor_id()
+        .update(DummyStatsRow::empty(actor_id));
+    Ok(())
+}
+
+/// Despawns a training dummy. Admin only.
+#[reducer]
+pub fn despawn_training_dummy(ctx: &ReducerContext, actor_id: ActorId) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "despawn_training_dummy")?;
+    if !DummyStatsRow::is_dummy(ctx, actor_id) {
+        return Err("Not a training dummy".into());
+    }
+    ActorRow::despawn(ctx, actor_id);
+    Ok(())
+}
@@ -1,6 +1,6 @@
-use crate::{get_view_aoi_block, MovementStateRow, Vec3};
+use crate::{current_server_tick, get_view_aoi_actors, get_view_cell_id, AirborneRow, Vec3};
 use nalgebra::{Isometry3, UnitQuaternion, Vector3};
-use shared::ActorId;
+use shared::{ActorId, FAR_TRANSFORM_INTERVAL_TICKS};
 use spacetimedb::{table, ReducerContext, Table, ViewContext};
 
 /// Ephemeral
@@ -18,6 +18,14 @@ pub struct TransformRow {
     pub yaw: f32,
 
     pub translation: Vec3,
+
+    /// The server tick this row was last written on, see [`crate::current_server_tick`].
+    /// Clients use this to order authoritative updates and discard stale ones.
+    pub server_tick: u32,
+
+    /// The latest client intent sequence number reflected in this transform.
+    /// Copied from the movement state whenever the movement tick writes this row.
+    pub client_intent_seq: u32,
 }
 
 impl TransformRow {
@@ -25,22 +33,110 @@ impl TransformRow {
         ctx.db.transform_tbl().actor_id().find(actor_id)
     }
     pub fn insert(ctx: &ReducerContext, actor_id: ActorId, translation: Vec3, yaw: f32) {
-        ctx.db.transform_tbl().insert(Self {
+        let row = ctx.db.transform_tbl().insert(Self {
             actor_id,
             translation,
             yaw,
+            server_tick: current_server_tick(ctx),
+            client_intent_seq: 0,
         });
+        ctx.db
+            .transform_keyframe_tbl()
+            .insert(TransformKeyframeRow::from(&row));
+    }
+    pub fn delete(ctx: &ReducerContext, actor_id: ActorId) {
+        ctx.db.transform_tbl().actor_id().delete(actor_id);
+        ctx.db.transform_keyframe_tbl().actor_id().delete(actor_id);
     }
     /// Updates from given self, caller should have updated the state with the latest values.
     pub fn update_from_self(self, ctx: &ReducerContext) {
         ctx.db.transform_tbl().actor_id().update(self);
     }
-    pub fn update(&self, ctx: &ReducerContext, translation: Vec3, yaw: f32) {
-        ctx.db.transform_tbl().actor_id().update(Self {
+    /// Teleport style update, the keyframe is written right away so far viewers don't lag behind.
+    ///
+    /// A teleport ends any time in the air, the next landing starts counting the fall where the
+    /// actor arrived (see [`AirborneRow`]).
+    ///
+    /// `server_tick` stamps the row, callers outside the movement tick pass
+    /// [`crate::current_server_tick`].
+    pub fn update(&self, ctx: &ReducerContext, server_tick: u32, translation: Vec3, yaw: f32) {
+        AirborneRow::delete_for_actor(ctx, self.actor_id);
+        let row = ctx.db.transform_tbl().actor_id().update(Self {
             actor_id: self.actor_id,
             translation,
             yaw,
+            server_tick,
+            client_intent_seq: self.client_intent_seq,
         });
+        TransformKeyframeRow::from(&row).update_if_changed(ctx);
+    }
+}
+
+/// **Ephemeral**
+///
+/// A throttled copy of [`TransformRow`] replicated to viewers whose own cell doesn't contain the
+/// actor. The movement tick only refreshes it on the actor's keyframe ticks (every
+/// [`FAR_TRANSFORM_INTERVAL_TICKS`]) and when the actor comes to rest, so dense outer rings cost a
+/// fraction of the bandwidth of nearby actors.
+#[table(name=transform_keyframe_tbl)]
+pub struct TransformKeyframeRow {
+    #[primary_key]
+    pub actor_id: ActorId,
+    pub yaw: f32,
+    pub translation: Vec3,
+    pub server_tick: u32,
+    pub client_intent_seq: u32,
+}
+
+impl TransformKeyframeRow {
+    /// Is `server_tick` this actor's keyframe tick? Actors are staggered across ticks by id so the
+    /// keyframe writes are spread out evenly.
+    pub fn is_keyframe_tick(actor_id: ActorId, server_tick: u32) -> bool {
+        server_tick % FAR_TRANSFORM_INTERVAL_TICKS == actor_id % FAR_TRANSFORM_INTERVAL_TICKS
+    }
+
+    /// Writes the keyframe when the position or yaw differ from the stored one.
+    ///
+    /// Returns `true` when the row was written.
+    pub fn update_if_changed(self, ctx: &ReducerContext) -> bool {
+        let Some(current) = ctx
+            .db
+            .transform_keyframe_tbl()
+            .actor_id()
+            .find(self.actor_id)
+        else {
+            ctx.db.transform_keyframe_tbl().insert(self);
+            return true;
+        };
+        if current.translation == self.translation && current.yaw == self.yaw {
+            return false;
+        }
+        ctx.db.transform_keyframe_tbl().actor_id().update(self);
+        true
+    }
+}
+
+impl From<&TransformRow> for TransformKeyframeRow {
+    fn from(row: &TransformRow) -> Self {
+        Self {
+            actor_id: row.actor_id,
+            yaw: row.yaw,
+            translation: row.translation,
+            server_tick: row.server_tick,
+            client_intent_seq: row.client_intent_seq,
+        }
+    }
+}
+
+impl From<TransformKeyframeRow> for TransformRow {
+    fn from(row: TransformKeyframeRow) -> Self {
+        Self {
+            actor_id: row.actor_id,
+            yaw: row.yaw,
+            translation: row.translation,
+            server_tick: row.server_tick,
+            client_intent_seq: row.client_intent_seq,
+        }
     }
 }
 
@@ -49,16 +145,29 @@ pub fn to_isometry3(row: &TransformRow) -> Isometry3<f32> {
     Isometry3::from_parts(row.translation.into(), rotation)
 }
 
-/// Finds the active character for all things within the AOI.
-/// Primary key of `Identity`
+/// Finds the transforms for all things within the AOI.
+///
+/// Actors in the viewer's own cell get every update, actors in the outer ring get the throttled
+/// [`TransformKeyframeRow`] instead.
+/// Primary key of `ActorId`
 #[spacetimedb::view(name = transform_view, public)]
 pub fn transform_view(ctx: &ViewContext) -> Vec<TransformRow> {
-    let Some(cell_block) = get_view_aoi_block(ctx) else {
+    let (Some(viewer_cell_id), Some(actors)) = (get_view_cell_id(ctx), get_view_aoi_actors(ctx))
+    else {
         return vec![];
     };
 
-    cell_block
-        .flat_map(|cell_id| MovementStateRow::by_cell_id(ctx, cell_id))
-        .filter_map(|ms| ctx.db.transform_tbl().actor_id().find(&ms.actor_id))
+    actors
+        .filter_map(|ms| {
+            if ms.cell_id == viewer_cell_id {
+                ctx.db.transform_tbl().actor_id().find(&ms.actor_id)
+            } else {
+                ctx.db
+                    .transform_keyframe_tbl()
+                    .actor_id()
+                    .find(&ms.actor_id)
+                    .map(TransformRow::from)
+            }
+        })
         .collect()
 }
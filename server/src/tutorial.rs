@@ -0,0 +1,94 @@
+use crate::{CharacterInstanceRow, DomainEvent, DomainEventRow, EventActor};
+use shared::{ActorId, TutorialFlags};
+use spacetimedb::{table, ReducerContext, Table, Timestamp, ViewContext};
+
+/// The onboarding steps a character has done, see [`TutorialFlags`].
+///
+/// The client shows a hint for every step not done yet. No row means nothing done.
+#[table(name=tutorial_progress_tbl)]
+pub struct TutorialProgressRow {
+    #[primary_key]
+    pub character_id: u32,
+
+    /// [`TutorialFlags`] bits.
+    pub progress: u32,
+
+    pub updated_at: Timestamp,
+}
+
+impl TutorialProgressRow {
+    pub fn flags(&self) -> TutorialFlags {
+        TutorialFlags::from_bits(self.progress)
+    }
+
+    /// Marks `steps` done for the character `actor_id` plays, nothing for other actors.
+    ///
+    /// **Performance & Cost**: two index seeks, a write only when a step is new
+    pub fn complete(ctx: &ReducerContext, actor_id: ActorId, steps: TutorialFlags) {
+        let Some(ci) = CharacterInstanceRow::find_by_actor_id(&ctx.as_read_only(), actor_id) else {
+            return;
+        };
+        Self::complete_character(ctx, ci.character_id, steps);
+    }
+
+    /// [`Self::complete`] by character, whether or not it's playing.
+    ///
+    /// **Performance & Cost**: one index seek, a write only when a step is new
+    pub fn complete_character(ctx: &ReducerContext, character_id: u32, steps: TutorialFlags) {
+        match ctx
+            .db
+            .tutorial_progress_tbl()
+            .character_id()
+            .find(character_id)
+        {
+            Some(row) if row.flags().contains(steps) => {}
+            Some(mut row) => {
+                row.progress = (row.flags() | steps).bits();
+                row.updated_at = ctx.timestamp;
+                ctx.db.tutorial_progress_tbl().character_id().update(row);
+            }
+            None => {
+                ctx.db.tutorial_progress_tbl().insert(Self {
+                    character_id,
+                    progress: steps.bits(),
+                    updated_at: ctx.timestamp,
+                });
+            }
+        }
+    }
+
+    /// Forgets every step of the character, its hints show again.
+    pub fn reset(ctx: &ReducerContext, character_id: u32) {
+        ctx.db
+            .tutorial_progress_tbl()
+            .character_id()
+            .delete(character_id);
+    }
+
+    /// Domain event consumer, a kill completes the killer's [`TutorialFlags::FIRST_KILL`], even
+    /// when the killer logged out before the event was handled.
+    pub fn on_domain_event(ctx: &ReducerContext, row: &DomainEventRow) {
+        if let DomainEvent::Died {
+            killer:
+                Some(EventActor {
+                    character_id: Some(character_id),
+                    ..
+                }),
+            ..
+        } = row.event
+        {
+            Self::complete_character(ctx, character_id, TutorialFlags::FIRST_KILL);
+        }
+    }
+}
+
+/// Finds the tutorial progress of the viewer's active character.
+/// Primary key of `character_id`
+#[spacetimedb::view(name = tutorial_progress_view, public)]
+pub fn tutorial_progress_view(ctx: &ViewContext) -> Option<TutorialProgressRow> {
+    let ci = CharacterInstanceRow::find_by_identity(ctx)?;
+    ctx.db
+        .tutorial_progress_tbl()
+        .character_id()
+        .find(ci.character_id)
+}
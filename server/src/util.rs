@@ -1,23 +1,126 @@
-use crate::{character_instance_tbl__view, movement_state_tbl__view};
-use shared::{get_aoi_block, CellId};
-use spacetimedb::ViewContext;
+use crate::{
+    character_instance_tbl__view, movement_state_tbl__view, remote_aoi_partitions, ActorRow,
+    InstanceRow, MovementStateRow, SpectatorRow,
+};
+use shared::{
+    get_aoi_block_clamped, is_in_aoi_block, partition_of_cell, ActorId, CellId, InstanceId, Rng,
+};
+use spacetimedb::{ReducerContext, ViewContext};
 
-/// Finds this character's AOI block for views
+/// Who the AOI views are built for.
+#[derive(Clone, Copy)]
+enum Viewer {
+    /// The sender's active character.
+    Actor(ActorId),
+    /// The sender's observer point, see [`SpectatorRow`].
+    Spectator(InstanceId),
+}
+
+/// Finds who the views are built for and the cell their AOI is centered on, a spectator's
+/// observer point takes precedence over the active character.
 ///
-/// **Performance & Cost**: O(1), two index seeks
-pub fn get_view_aoi_block(ctx: &ViewContext) -> Option<impl Iterator<Item = CellId>> {
-    let Some(ci) = ctx.db.character_instance_tbl().identity().find(ctx.sender) else {
-        return None;
-    };
-    let Some(cell_id) = ctx
+/// **Performance & Cost**: O(1), up to three index seeks
+fn find_view_aoi(ctx: &ViewContext) -> Option<(Viewer, CellId)> {
+    if let Some(spectator) = SpectatorRow::find_by_identity(ctx) {
+        return Some((Viewer::Spectator(spectator.instance_id), spectator.cell_id));
+    }
+    let ci = ctx
+        .db
+        .character_instance_tbl()
+        .identity()
+        .find(ctx.sender)?;
+    let cell_id = ctx
         .db
         .movement_state_tbl()
         .actor_id()
         .find(&ci.actor_id)
-        .map(|row| row.cell_id)
-    else {
-        return None;
+        .map(|row| row.cell_id)?;
+
+    Some((Viewer::Actor(ci.actor_id), cell_id))
+}
+
+/// Finds this character's (or observer point's) AOI block for views
+///
+/// **Performance & Cost**: O(1), up to three index seeks
+pub fn get_view_aoi_block(ctx: &ViewContext) -> Option<impl Iterator<Item = CellId>> {
+    find_view_aoi(ctx).map(|(_, cell_id)| get_aoi_block_clamped(cell_id))
+}
+
+/// Finds the cell this character (or observer point) is in for views
+///
+/// **Performance & Cost**: O(1), up to three index seeks
+pub fn get_view_cell_id(ctx: &ViewContext) -> Option<CellId> {
+    find_view_aoi(ctx).map(|(_, cell_id)| cell_id)
+}
+
+/// The instance `actor_id` is in, the overworld for missing actors.
+fn instance_of(ctx: &ViewContext, actor_id: ActorId) -> InstanceId {
+    ActorRow::find(ctx, actor_id)
+        .map(|row| row.instance_id)
+        .unwrap_or(InstanceRow::OVERWORLD)
+}
+
+/// Finds the movement states of all actors within this character's AOI that are visible to it,
+/// see [`ActorRow::is_visible_to`]. AOI views should build on this rather than the raw block.
+///
+/// The block is clamped at the world edges (see [`get_aoi_block_clamped`]), actors on the far
+/// side of the world never leak in. Cells are shared between instances, actors of other
+/// instances are filtered out here. While
+/// spectating the AOI is the observer point's, see [`ActorRow::is_visible_to_spectator`].
+///
+/// At overworld partition seams the cells of partitions owned by another module are left out,
+/// see [`remote_aoi_partitions`].
+///
+/// **Performance & Cost**: O(cells * actors), one extra seek per actor for the flags
+pub fn get_view_aoi_actors(
+    ctx: &ViewContext,
+) -> Option<impl Iterator<Item = MovementStateRow> + '_> {
+    let (viewer, cell_id) = find_view_aoi(ctx)?;
+    let viewer_instance = match viewer {
+        Viewer::Actor(actor_id) => instance_of(ctx, actor_id),
+        Viewer::Spectator(instance_id) => instance_id,
+    };
+
+    let remote = remote_aoi_partitions(ctx, viewer_instance, cell_id);
+
+    Some(
+        get_aoi_block_clamped(cell_id)
+            .filter(move |&cell_id| !remote.contains(&partition_of_cell(cell_id)))
+            .flat_map(|cell_id| MovementStateRow::by_cell_id(ctx, cell_id))
+            .filter(move |ms| match viewer {
+                Viewer::Actor(actor_id) => {
+                    ActorRow::is_visible_to(ctx, actor_id, viewer_instance, ms.actor_id)
+                }
+                Viewer::Spectator(_) => {
+                    ActorRow::is_visible_to_spectator(ctx, viewer_instance, ms.actor_id)
+                }
+            }),
+    )
+}
+
+/// Is `actor_id` within `viewer`'s AOI and visible to it? The reducer side counterpart of
+/// [`get_view_aoi_actors`], e.g. to validate client supplied targets.
+///
+/// **Performance & Cost**: O(1), four index seeks
+pub fn is_in_aoi(ctx: &ViewContext, viewer: ActorId, actor_id: ActorId) -> bool {
+    let find_cell = |id: ActorId| {
+        ctx.db
+            .movement_state_tbl()
+            .actor_id()
+            .find(&id)
+            .map(|row| row.cell_id)
     };
+    let (Some(viewer_cell), Some(cell_id)) = (find_cell(viewer), find_cell(actor_id)) else {
+        return false;
+    };
+
+    is_in_aoi_block(viewer_cell, cell_id)
+        && ActorRow::is_visible_to(ctx, viewer, instance_of(ctx, viewer), actor_id)
+}
 
-    Some(get_aoi_block(cell_id).into_iter())
+/// The random number generator for gameplay rolls in a reducer, seeded from the reducer's
+/// timestamp. Use a distinct `stream` (e.g. the rolling actor's id) per independent roller so
+/// rolls within the same reducer don't repeat each other.
+pub fn gameplay_rng(ctx: &ReducerContext, stream: u64) -> Rng {
+    Rng::new(ctx.timestamp.to_micros_since_unix_epoch() as u64, stream)
 }
@@ -0,0 +1,166 @@
+use crate::{
+    character_instance_tbl, character_tbl, guild_tbl, ActorRow, GuildMemberRow, InstanceRow,
+    LevelRow,
+};
+use shared::ActorFlags;
+use spacetimedb::{
+    reducer, table, Identity, ReducerContext, SpacetimeType, Table, Timestamp, ViewContext,
+};
+
+/// How often a player may run a /who query.
+const WHO_COOLDOWN_MICROS: i64 = 5_000_000;
+
+/// Max entries returned by a single /who query.
+const MAX_WHO_RESULTS: usize = 50;
+
+/// Optional filters for [`who`], all given filters have to match.
+#[derive(SpacetimeType, Debug, Clone, Default)]
+pub struct WhoFilter {
+    /// Case insensitive substring of the character name.
+    pub name: Option<String>,
+    pub min_level: Option<u8>,
+    pub max_level: Option<u8>,
+    /// Case insensitive guild tag.
+    pub guild_tag: Option<String>,
+    /// Case insensitive substring of the zone (instance) name.
+    pub zone: Option<String>,
+}
+
+impl WhoFilter {
+    fn matches(&self, entry: &WhoResultRow) -> bool {
+        let contains =
+            |haystack: &str, needle: &str| haystack.to_lowercase().contains(&needle.to_lowercase());
+        self.name
+            .as_deref()
+            .is_none_or(|name| contains(&entry.name, name))
+            && self.min_level.is_none_or(|min| entry.level >= min)
+            && self.max_level.is_none_or(|max| entry.level <= max)
+            && self.guild_tag.as_deref().is_none_or(|tag| {
+                entry
+                    .guild_tag
+                    .as_deref()
+                    .is_some_and(|entry_tag| entry_tag.eq_ignore_ascii_case(tag))
+            })
+            && self
+                .zone
+                .as_deref()
+                .is_none_or(|zone| contains(&entry.zone, zone))
+    }
+}
+
+/// One in-world player found by a viewer's last [`who`] query.
+///
+/// Replaced on every query, see [`who_result_view`].
+#[table(name=who_result_tbl)]
+pub struct WhoResultRow {
+    #[auto_inc]
+    #[primary_key]
+    pub id: u64,
+
+    /// The identity that ran the query.
+    #[index(btree)]
+    pub viewer: Identity,
+
+    pub name: String,
+    pub level: u8,
+
+    /// The name of the instance the character is in.
+    pub zone: String,
+
+    pub guild_tag: Option<String>,
+}
+
+impl WhoResultRow {
+    /// Deletes the results of `viewer`'s last query.
+    pub fn delete_for_viewer(ctx: &ReducerContext, viewer: Identity) {
+        ctx.db.who_result_tbl().viewer().delete(viewer);
+        ctx.db.who_request_tbl().viewer().delete(viewer);
+    }
+}
+
+/// When each viewer last ran a /who query, for rate limiting.
+#[table(name=who_request_tbl)]
+pub struct WhoRequestRow {
+    #[primary_key]
+    pub viewer: Identity,
+
+    pub requested_at: Timestamp,
+}
+
+/// Lists the players currently in the world that match the filter, replacing the sender's
+/// previous results in [`who_result_view`].
+///
+/// GM invisible characters are never listed. Rate limited to one query per
+/// [`WHO_COOLDOWN_MICROS`] per identity.
+///
+/// **Performance & Cost**: O(N) over the characters in the world, five index seeks each
+#[reducer]
+pub fn who(ctx: &ReducerContext, filter: WhoFilter) -> Result<(), String> {
+    if let Some(mut request) = ctx.db.who_request_tbl().viewer().find(ctx.sender) {
+        let too_soon = ctx
+            .timestamp
+            .time_duration_since(request.requested_at)
+            .is_none_or(|waited| waited.to_micros() < WHO_COOLDOWN_MICROS);
+        if too_soon {
+            return Err("Too many /who queries, try again in a few seconds".into());
+        }
+        request.requested_at = ctx.timestamp;
+        ctx.db.who_request_tbl().viewer().update(request);
+    } else {
+        ctx.db.who_request_tbl().insert(WhoRequestRow {
+            viewer: ctx.sender,
+            requested_at: ctx.timestamp,
+        });
+    }
+
+    let view_ctx = ctx.as_read_only();
+    let mut entries: Vec<WhoResultRow> = ctx
+        .db
+        .character_instance_tbl()
+        .iter()
+        .filter_map(|ci| {
+            let actor = ActorRow::find(&view_ctx, ci.actor_id)?;
+            if actor.flags().contains(ActorFlags::GM_INVISIBLE) {
+                return None;
+            }
+            let character = ctx.db.character_tbl().id().find(ci.character_id)?;
+            let level = LevelRow::find(&view_ctx, ci.actor_id)
+                .map(|row| row.level)
+                .unwrap_or(character.level);
+            let zone = InstanceRow::find(&view_ctx, actor.instance_id)
+                .map(|instance| instance.name)
+                .unwrap_or_default();
+            let guild_tag = GuildMemberRow::find(&view_ctx, ci.character_id)
+                .and_then(|member| ctx.db.guild_tbl().id().find(member.guild_id))
+                .map(|guild| guild.tag);
+            Some(WhoResultRow {
+                id: 0,
+                viewer: ctx.sender,
+                name: character.name,
+                level,
+                zone,
+                guild_tag,
+            })
+        })
+        .filter(|entry| filter.matches(entry))
+        .collect();
+    entries.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+    entries.truncate(MAX_WHO_RESULTS);
+
+    ctx.db.who_result_tbl().viewer().delete(ctx.sender);
+    for entry in entries {
+        ctx.db.who_result_tbl().insert(entry);
+    }
+    Ok(())
+}
+
+/// The results of the player's last [`who`] query.
+/// Primary key of `id`
+#[spacetimedb::view(name = who_result_view, public)]
+pub fn who_result_view(ctx: &ViewContext) -> Vec<WhoResultRow> {
+    ctx.db
+        .who_result_tbl()
+        .viewer()
+        .filter(ctx.sender)
+        .collect()
+}
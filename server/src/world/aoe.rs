@@ -0,0 +1,326 @@
+use crate::{
+    actor_tbl, character_instance_tbl, deal_damage, gameplay_rng, get_actor_collider_layer,
+    get_static_query_world, movement::scripted_path::MAX_SPEED_OVERRIDE, ActivityRow, ActorRow,
+    AirborneRow, CooldownKind, CooldownRow, DamageSchool, Rewind, SpeedModifierOp,
+    SpeedModifierRow, SpeedModifierSource, TargetRow, TransformRow, Vec3, MAX_REWIND_MICROS,
+};
+use nalgebra::{Isometry3, Vector3};
+use rapier3d::{
+    parry::query::intersection_test,
+    prelude::{Ball, Capsule},
+};
+use shared::{
+    dist_sq_xz, has_line_of_sight, is_within_sector, offset_xz, validate, ActorId, InstanceId,
+    GROUND_SLAM, UPHEAVAL,
+};
+use spacetimedb::{reducer, ReducerContext, TimeDuration, Timestamp};
+
+/// Restricts an AoE to a planar cone in front of the caster.
+#[derive(Debug, Clone, Copy)]
+pub struct AoeCone {
+    /// The direction the cone faces, see [`shared::math::yaw`].
+    pub yaw: f32,
+    /// Half of the cone's opening angle (radians).
+    pub half_angle: f32,
+}
+
+/// A sphere, optionally narrowed to a cone, used to find the actors hit by an area effect.
+#[derive(Debug, Clone, Copy)]
+pub struct AoeShape {
+    pub center: Vec3,
+    pub radius: f32,
+    pub cone: Option<AoeCone>,
+}
+
+impl AoeShape {
+    /// Does this shape overlap the actor's capsule at the given translation?
+    fn hits(&self, translation: Vec3, capsule: &Capsule) -> bool {
+        let Ok(intersects) = intersection_test(
+            &Isometry3::translation(self.center.x, self.center.y, self.center.z),
+            &Ball::new(self.radius),
+            &Isometry3::translation(translation.x, translation.y, translation.z),
+            capsule,
+        ) else {
+            return false;
+        };
+        if !intersects {
+            return false;
+        }
+
+        let Some(cone) = self.cone else {
+            return true;
+        };
+
+        let to_target = offset_xz(self.center.into(), translation.into());
+        is_within_sector(cone.yaw, cone.half_angle, to_target, capsule.radius)
+    }
+}
+
+/// Distance (meters) beyond the shape searched for candidates of a rewound query: as far as the
+/// fastest walker, a monster on a scripted path at [`MAX_SPEED_OVERRIDE`], gets within
+/// [`MAX_REWIND_MICROS`] (8m). Characters are capped at a third of that speed.
+const REWIND_QUERY_SLACK: f32 = MAX_SPEED_OVERRIDE * MAX_REWIND_MICROS as f32 / 1_000_000.0;
+
+/// Finds all living actors of the instance overlapping the AoE shape, where they were at
+/// `rewind` when given (lag compensation, see [`Rewind`]).
+///
+/// Candidates are the capsules of the instance's actor layer (see
+/// [`get_actor_collider_layer`]) within the shape's sphere, grown by the distance actors may
+/// have moved since the rewound time. A precise capsule-vs-shape test at the rewound position
+/// then decides what was hit, and applies the cone.
+///
+/// **Performance & Cost**: one broad phase query, two index seeks per candidate
+pub fn query_aoe_actors(
+    ctx: &ReducerContext,
+    shape: &AoeShape,
+    instance_id: InstanceId,
+    rewind: Option<&Rewind>,
+) -> Vec<ActorId> {
+    let slack = if rewind.is_some() {
+        REWIND_QUERY_SLACK
+    } else {
+        0.0
+    };
+    let layer = get_actor_collider_layer(ctx, instance_id);
+    let candidates = layer.intersect_shape(
+        Isometry3::translation(shape.center.x, shape.center.y, shape.center.z),
+        &Ball::new(shape.radius + slack),
+    );
+
+    candidates
+        .filter_map(|actor_id| {
+            let actor = ctx.db.actor_tbl().id().find(actor_id)?;
+            let translation = match rewind {
+                Some(rewind) => rewind.position(ctx, actor_id)?,
+                None => TransformRow::find(ctx, actor_id)?.translation,
+            };
+            let capsule = Capsule::new_y(actor.capsule.half_height, actor.capsule.radius);
+            shape.hits(translation, &capsule).then_some(actor_id)
+        })
+        .collect()
+}
+
+/// Definition of an ability that affects an area.
+#[derive(Debug, Clone, Copy)]
+pub struct AoeAbilityDef {
+    pub id: u16,
+    /// How far from the caster the AoE can be centered (meters).
+    pub range: f32,
+    pub radius: f32,
+    /// When set, the AoE is a cone in front of the caster with this half angle (radians) instead
+    /// of a sphere at the target point.
+    pub cone_half_angle: Option<f32>,
+    pub damage: u16,
+    pub school: DamageSchool,
+    pub cooldown_micros: i64,
+    /// Slows the actors hit, see [`AoeSlow`].
+    pub slow: Option<AoeSlow>,
+    /// Launches the actors hit, see [`AoeLaunch`].
+    pub launch: Option<AoeLaunch>,
+}
+
+/// A launch straight up of the actors an AoE hits, see [`AirborneRow::launch`].
+#[derive(Debug, Clone, Copy)]
+pub struct AoeLaunch {
+    pub velocity_mps: f32,
+    /// Landing never deals fall damage, even when knocked off a ledge.
+    pub safe: bool,
+}
+
+/// A movement speed multiplier applied to the actors an AoE hits for a while.
+#[derive(Debug, Clone, Copy)]
+pub struct AoeSlow {
+    pub multiplier: f32,
+    pub duration_micros: i64,
+}
+
+/// The AoE abilities that can be cast.
+pub const AOE_ABILITIES: &[AoeAbilityDef] = &[
+    AoeAbilityDef {
+        id: GROUND_SLAM.id,
+        range: GROUND_SLAM.range,
+        radius: GROUND_SLAM.radius,
+        cone_half_angle: None,
+        damage: 25,
+        // Ranged and ground targeted, a burst of Veil energy rather than a weapon strike.
+        school: DamageSchool::Veil,
+        cooldown_micros: 5_000_000,
+        slow: Some(AoeSlow {
+            multiplier: 0.6,
+            duration_micros: 3_000_000,
+        }),
+        launch: None,
+    },
+    // Cleave
+    AoeAbilityDef {
+        id: 2,
+        range: 0.0,
+        radius: 3.0,
+        cone_half_angle: Some(std::f32::consts::FRAC_PI_4),
+        damage: 15,
+        school: DamageSchool::Physical,
+        cooldown_micros: 2_000_000,
+        slow: None,
+        launch: None,
+    },
+    AoeAbilityDef {
+        id: UPHEAVAL.id,
+        range: UPHEAVAL.range,
+        radius: UPHEAVAL.radius,
+        cone_half_angle: None,
+        damage: 10,
+        school: DamageSchool::Veil,
+        cooldown_micros: 12_000_000,
+        slow: None,
+        // Apex ~2.3m up (v²/2g), the 1s tick samples it at ~1.1m after the first tick. Well
+        // within the safe fall distance on flat ground, knocking someone off a cliff with it is
+        // fair game.
+        launch: Some(AoeLaunch {
+            velocity_mps: 8.0,
+            safe: false,
+        }),
+    },
+];
+
+impl AoeAbilityDef {
+    pub fn find(id: u16) -> Option<&'static Self> {
+        AOE_ABILITIES.iter().find(|def| def.id == id)
+    }
+}
+
+/// Casts an AoE ability centered at `target`, damaging (and possibly slowing) every other actor
+/// it hits.
+///
+/// Without a `target` the ability is centered at the caster's current target (see
+/// [`TargetRow`]). An explicit `target` is a ground target and needs line of sight from the
+/// caster. Cone abilities ignore `target` and are cast from the caster in the direction it
+/// faces.
+///
+/// The actors hit are judged where they were at `seen_at`, the client's estimate of the server
+/// time of the world it saw (see [`Rewind`]).
+#[reducer]
+pub fn cast_aoe_ability(
+    ctx: &ReducerContext,
+    ability_id: u16,
+    target: Option<Vec3>,
+    seen_at: Option<Timestamp>,
+) -> Result<(), String> {
+    let Some(ci) = ctx.db.character_instance_tbl().identity().find(ctx.sender) else {
+        log::error!("cast_aoe_ability: no active character for {:?}", ctx.sender);
+        return Err("No active character".into());
+    };
+    ActivityRow::touch(ctx);
+    let caster = ci.actor_id;
+    let view_ctx = ctx.as_read_only();
+    if ActorRow::is_dead(&view_ctx, caster) {
+        return Err("Dead actors can't cast".into());
+    }
+    let Some(ability) = AoeAbilityDef::find(ability_id) else {
+        return Err("Unknown ability".into());
+    };
+    let (Some(caster_actor), Some(caster_transform)) = (
+        ActorRow::find(&view_ctx, caster),
+        TransformRow::find(ctx, caster),
+    ) else {
+        log::error!("cast_aoe_ability: no transform for actor {}", caster);
+        return Err("No transform for caster".into());
+    };
+
+    let rewind = Rewind::new(ctx, seen_at);
+    let shape = match ability.cone_half_angle {
+        Some(half_angle) => AoeShape {
+            center: caster_transform.translation,
+            radius: ability.radius,
+            cone: Some(AoeCone {
+                yaw: caster_transform.yaw,
+                half_angle,
+            }),
+        },
+        None => {
+            let target = match target {
+                Some(target) => {
+                    let target = validate::within_world(target.into())?;
+                    let query_world = get_static_query_world(ctx, caster_actor.instance_id);
+                    if !has_line_of_sight(
+                        &query_world,
+                        caster_transform.translation.into(),
+                        target + Vector3::y() * GROUND_TARGET_SIGHT_HEIGHT,
+                    ) {
+                        return Err("Target is out of sight".into());
+                    }
+                    Vec3::from(target)
+                }
+                None => TargetRow::resolve(&view_ctx, caster, None)
+                    .and_then(|target| rewind.position(ctx, target))
+                    .ok_or("No target")?,
+            };
+            let range_sq = ability.range * ability.range;
+            if dist_sq_xz(caster_transform.translation.into(), target.into()) > range_sq {
+                return Err("Target is out of range".into());
+            }
+            AoeShape {
+                center: target,
+                radius: ability.radius,
+                cone: None,
+            }
+        }
+    };
+
+    CooldownRow::try_start(
+        ctx,
+        caster,
+        CooldownKind::Ability(ability.id),
+        TimeDuration::from_micros(ability.cooldown_micros),
+    )?;
+
+    let hits = query_aoe_actors(ctx, &shape, caster_actor.instance_id, Some(&rewind));
+    let mut rng = gameplay_rng(ctx, caster as u64);
+    for actor_id in hits.into_iter().filter(|&id| id != caster) {
+        let hit = deal_damage(
+            ctx,
+            &mut rng,
+            caster,
+            actor_id,
+            ability.damage,
+            ability.school,
+        );
+        if let (true, Some(launch)) = (hit, ability.launch) {
+            AirborneRow::launch(ctx, actor_id, launch.velocity_mps, launch.safe)?;
+        }
+        if let (true, Some(slow)) = (hit, ability.slow) {
+            SpeedModifierRow::apply(
+                ctx,
+                actor_id,
+                SpeedModifierSource::Ability(ability.id),
+                SpeedModifierOp::Multiply(slow.multiplier),
+                Some(TimeDuration::from_micros(slow.duration_micros)),
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Height (meters) above a ground target that line of sight is checked to, so the ground it rests
+/// on doesn't block the view of it.
+const GROUND_TARGET_SIGHT_HEIGHT: f32 = 0.5;
+
+/// Casts a ground targeted AoE ability centered at `position`, see [`cast_aoe_ability`].
+///
+/// The position needs line of sight from the caster, the client's ground-target reticle checks
+/// the same before confirming.
+#[reducer]
+pub fn cast_ability_at_position(
+    ctx: &ReducerContext,
+    ability_id: u16,
+    position: Vec3,
+    seen_at: Option<Timestamp>,
+) -> Result<(), String> {
+    let Some(ability) = AoeAbilityDef::find(ability_id) else {
+        return Err("Unknown ability".into());
+    };
+    if ability.cone_half_angle.is_some() {
+        return Err("Ability isn't ground targeted".into());
+    }
+    cast_aoe_ability(ctx, ability_id, Some(position), seen_at)
+}
@@ -0,0 +1,177 @@
+use crate::{
+    character_instance_tbl, current_server_tick, deal_damage, gameplay_rng, get_static_query_world,
+    is_in_aoi, ActivityRow, ActorRow, CooldownKind, CooldownRow, DamageSchool, MoveIntentData,
+    MovementStateRow, SpeedModifierOp, SpeedModifierRow, SpeedModifierSource, TargetRow,
+    TransformRow, Vec3, MELEE_REACH,
+};
+use nalgebra::Vector3;
+use rapier3d::prelude::QueryFilter;
+use shared::{
+    charge_sweep, dist_sq_xz, has_line_of_sight, movement_kcc, ActorFlags, ActorId,
+    CHARGE_ABILITY_ID,
+};
+use spacetimedb::{reducer, ReducerContext, TimeDuration};
+
+/// Farthest (planar meters) a charge can start from its target.
+const CHARGE_RANGE: f32 = 20.0;
+
+/// Closer than this (planar meters) there's nothing to charge, just attack.
+const CHARGE_MIN_RANGE: f32 = 4.0;
+
+/// How far (meters) from the target's capsule the charge stops, well within [`MELEE_REACH`].
+const CHARGE_STOP_DISTANCE: f32 = 0.5 * MELEE_REACH;
+
+const CHARGE_COOLDOWN_MICROS: i64 = 10_000_000;
+
+/// Damage of the hit at the end of a charge.
+const CHARGE_DAMAGE: u16 = 12;
+
+/// The slow applied to the target by the hit at the end of a charge.
+const CHARGE_SLOW_MULTIPLIER: f32 = 0.5;
+const CHARGE_SLOW_MICROS: i64 = 2_000_000;
+
+/// How a charge ended, see [`charge`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChargeOutcome {
+    /// Reached melee range and hit the target.
+    Hit,
+    /// Reached melee range, the target dodged or resisted the hit.
+    Missed,
+    /// A wall was in the way, the charger stopped against it.
+    Blocked,
+}
+
+/// Charges the given actor, or the charger's current target (see [`TargetRow`]).
+///
+/// The character rushes in a straight line at the target and stops in melee range, or against
+/// the first wall in the way. Reaching the target hits and slows it.
+#[reducer]
+pub fn charge_to(ctx: &ReducerContext, target: Option<ActorId>) -> Result<(), String> {
+    let Some(ci) = ctx.db.character_instance_tbl().identity().find(ctx.sender) else {
+        log::error!("charge_to: no active character for {:?}", ctx.sender);
+        return Err("No active character".into());
+    };
+    ActivityRow::touch(ctx);
+    let charger = ci.actor_id;
+    let view_ctx = ctx.as_read_only();
+    if ActorRow::is_dead(&view_ctx, charger) {
+        return Err("Dead actors can't charge".into());
+    }
+    let Some(target) = TargetRow::resolve(&view_ctx, charger, target) else {
+        return Err("No target".into());
+    };
+    charge(ctx, charger, target).map(|_| ())
+}
+
+/// The charge of [`charge_to`] for any actor, e.g. a monster's AI. The charger has to be alive,
+/// this checks the target and starts the cooldown.
+///
+/// The target has to be visible to the charger (see [`is_in_aoi`]). The line is swept in one go
+/// (see [`charge_sweep`]) and the charger placed where it stopped, the movement tick settles it
+/// onto the ground from there. Unlike [`crate::melee_attack`] the
+/// target is judged where it is now, a charge isn't aimed at what the client saw.
+pub fn charge(
+    ctx: &ReducerContext,
+    charger: ActorId,
+    target: ActorId,
+) -> Result<ChargeOutcome, String> {
+    let view_ctx = ctx.as_read_only();
+    // Only what the charger can see, with the same error as for unknown actors so hidden ones
+    // can't be probed, like targeting (see `crate::set_target`).
+    if !ActorRow::can_harm(&view_ctx, charger, target)
+        || ActorRow::is_dead(&view_ctx, target)
+        || !is_in_aoi(&view_ctx, charger, target)
+    {
+        return Err("Invalid target".into());
+    }
+    let (Some(charger_actor), Some(target_actor)) = (
+        ActorRow::find(&view_ctx, charger),
+        ActorRow::find(&view_ctx, target),
+    ) else {
+        return Err("Unknown target".into());
+    };
+    if charger_actor
+        .flags()
+        .intersects(ActorFlags::AIRBORNE | ActorFlags::FLYING)
+    {
+        return Err("Can't charge while in the air".into());
+    }
+    let (Some(transform), Some(target_transform), Some(mut movement_state)) = (
+        TransformRow::find(ctx, charger),
+        TransformRow::find(ctx, target),
+        MovementStateRow::find(ctx, charger),
+    ) else {
+        log::error!("charge: no transform or movement state for {charger} or {target}");
+        return Err("Unknown target".into());
+    };
+
+    let from: Vector3<f32> = transform.translation.into();
+    let to: Vector3<f32> = target_transform.translation.into();
+    let distance_sq = dist_sq_xz(from, to);
+    if distance_sq > CHARGE_RANGE * CHARGE_RANGE {
+        return Err("Target is out of range".into());
+    }
+    if distance_sq < CHARGE_MIN_RANGE * CHARGE_MIN_RANGE {
+        return Err("Target is too close".into());
+    }
+    let query_world = get_static_query_world(ctx, charger_actor.instance_id);
+    if !has_line_of_sight(&query_world, from, to) {
+        return Err("Target is out of sight".into());
+    }
+
+    CooldownRow::try_start(
+        ctx,
+        charger,
+        CooldownKind::Ability(CHARGE_ABILITY_ID),
+        TimeDuration::from_micros(CHARGE_COOLDOWN_MICROS),
+    )?;
+
+    // Stop in front of the target's capsule, on the line towards it.
+    let line = to.xz() - from.xz();
+    let stop = target_actor.capsule.radius + charger_actor.capsule.radius + CHARGE_STOP_DISTANCE;
+    let end = from.xz() + line * (1.0 - stop / line.norm()).max(0.0);
+    let sweep = charge_sweep(
+        &movement_kcc(),
+        &query_world.as_query_pipeline(QueryFilter::only_fixed()),
+        from,
+        end,
+        charger_actor.capsule.radius,
+        charger_actor.capsule.half_height,
+        transform.yaw,
+    );
+
+    transform.update(
+        ctx,
+        current_server_tick(ctx),
+        Vec3::from(sweep.translation),
+        sweep.yaw,
+    );
+    movement_state.move_intent = MoveIntentData::None;
+    // Start falling so the next tick settles the actor onto the ground.
+    movement_state.vertical_velocity = -1;
+    movement_state.should_move = true;
+    movement_state.update_from_self(ctx);
+
+    if sweep.blocked {
+        return Ok(ChargeOutcome::Blocked);
+    }
+    let hit = deal_damage(
+        ctx,
+        &mut gameplay_rng(ctx, charger as u64),
+        charger,
+        target,
+        CHARGE_DAMAGE,
+        DamageSchool::Physical,
+    );
+    if !hit {
+        return Ok(ChargeOutcome::Missed);
+    }
+    SpeedModifierRow::apply(
+        ctx,
+        target,
+        SpeedModifierSource::Ability(CHARGE_ABILITY_ID),
+        SpeedModifierOp::Multiply(CHARGE_SLOW_MULTIPLIER),
+        Some(TimeDuration::from_micros(CHARGE_SLOW_MICROS)),
+    )?;
+    Ok(ChargeOutcome::Hit)
+}
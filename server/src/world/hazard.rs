@@ -0,0 +1,310 @@
+//! Hazard zones, volumes (lava, spikes...) that damage every actor inside them periodically.
+//!
+//! Zones are public so clients render them, the damage is applied by [`hazard_tick_reducer`]:
+//!
+//! - An actor entering a zone is tracked in a [`HazardOccupantRow`] and only takes damage once
+//!   it stayed [`ENTRY_GRACE_MICROS`], brushing past an edge is free.
+//! - Stepping out keeps the occupant for [`EXIT_GRACE_MICROS`], stepping back in within it
+//!   doesn't restart the entry grace.
+
+use crate::{
+    actor_tbl, movement_state_tbl, sender_instance_id, transform_tbl, AdminIdentityRow, CapsuleY,
+    CombatEventKind, HealthRow, TimingStatsRow, Vec3, WriteStats,
+};
+use shared::{
+    cells_in_radius, validate, ActorFlags, ActorId, InstanceId, MAX_ACTOR_CAPSULE_RADIUS,
+};
+use spacetimedb::{reducer, table, ReducerContext, ScheduleAt, SpacetimeType, Table, Timestamp};
+use std::{collections::HashSet, time::Duration};
+
+pub(crate) const HAZARD_TICK_INTERVAL_MILLIS: u64 = 1_000;
+
+/// Time (microseconds) an actor must stay inside a zone before taking damage.
+const ENTRY_GRACE_MICROS: i64 = 500_000;
+
+/// Time (microseconds) an actor may be out of a zone without losing its place in it.
+const EXIT_GRACE_MICROS: i64 = 2_000_000;
+
+/// Upper bound of a zone's half extents (meters).
+const MAX_HAZARD_HALF_EXTENT: f32 = 50.0;
+
+/// What a hazard looks like, purely cosmetic.
+#[derive(SpacetimeType, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HazardKind {
+    Lava,
+    Spikes,
+    Poison,
+}
+
+/// An axis-aligned box damaging every actor overlapping it each hazard tick.
+#[table(name=hazard_zone_tbl, public)]
+pub struct HazardZoneRow {
+    #[auto_inc]
+    #[primary_key]
+    pub id: u32,
+
+    #[index(btree)]
+    pub instance_id: InstanceId,
+
+    pub center: Vec3,
+
+    pub half_extents: Vec3,
+
+    /// Unmitigated damage dealt per [`HAZARD_TICK_INTERVAL_MILLIS`].
+    pub damage_per_tick: u16,
+
+    pub kind: HazardKind,
+}
+
+impl HazardZoneRow {
+    /// Does an actor's capsule at `translation` overlap the box? Conservative, the capsule is
+    /// treated as its bounding box.
+    fn overlaps(&self, translation: Vec3, capsule: &CapsuleY) -> bool {
+        let reach_y = capsule.half_height + capsule.radius;
+        (translation.x - self.center.x).abs() <= self.half_extents.x + capsule.radius
+            && (translation.z - self.center.z).abs() <= self.half_extents.z + capsule.radius
+            && (translation.y - self.center.y).abs() <= self.half_extents.y + reach_y
+    }
+
+    /// Planar distance (meters) from the center within which an actor may overlap the box, the
+    /// box grown by the widest capsule's radius. An actor brushing the edge has its center
+    /// outside the box.
+    fn reach(&self) -> f32 {
+        (self.half_extents.x + MAX_ACTOR_CAPSULE_RADIUS)
+            .hypot(self.half_extents.z + MAX_ACTOR_CAPSULE_RADIUS)
+    }
+
+    /// The living actors of the zone's instance overlapping it.
+    ///
+    /// **Performance & Cost**: O(cells * actors), two index seeks per candidate
+    fn actors_inside(&self, ctx: &ReducerContext) -> Vec<ActorId> {
+        cells_in_radius(self.center.x, self.center.z, self.reach())
+            .flat_map(|cell_id| ctx.db.movement_state_tbl().cell_id().filter(cell_id))
+            .filter_map(|ms| {
+                let actor = ctx.db.actor_tbl().id().find(ms.actor_id)?;
+                if actor.instance_id != self.instance_id || actor.flags().contains(ActorFlags::DEAD)
+                {
+                    return None;
+                }
+                let transform = ctx.db.transform_tbl().actor_id().find(ms.actor_id)?;
+                self.overlaps(transform.translation, &actor.capsule)
+                    .then_some(actor.id)
+            })
+            .collect()
+    }
+}
+
+/// **Ephemeral**
+///
+/// An actor inside (or just out of) a hazard zone, see the module docs for the graces.
+#[table(name=hazard_occupant_tbl)]
+pub struct HazardOccupantRow {
+    #[auto_inc]
+    #[primary_key]
+    pub id: u64,
+
+    #[index(btree)]
+    pub actor_id: ActorId,
+
+    #[index(btree)]
+    pub hazard_id: u32,
+
+    pub entered_at: Timestamp,
+
+    pub last_inside_at: Timestamp,
+}
+
+impl HazardOccupantRow {
+    fn find(ctx: &ReducerContext, actor_id: ActorId, hazard_id: u32) -> Option<Self> {
+        ctx.db
+            .hazard_occupant_tbl()
+            .actor_id()
+            .filter(actor_id)
+            .find(|row| row.hazard_id == hazard_id)
+    }
+
+    pub fn delete_for_actor(ctx: &ReducerContext, actor_id: ActorId) {
+        ctx.db.hazard_occupant_tbl().actor_id().delete(actor_id);
+    }
+}
+
+#[table(name = hazard_tick_timer, scheduled(hazard_tick_reducer))]
+pub struct HazardTickTimer {
+    #[primary_key]
+    #[auto_inc]
+    pub scheduled_id: u64,
+    pub scheduled_at: ScheduleAt,
+}
+
+pub fn init_hazards(ctx: &ReducerContext) {
+    ctx.db.hazard_tick_timer().scheduled_id().delete(1);
+    ctx.db.hazard_tick_timer().insert(HazardTickTimer {
+        scheduled_id: 1,
+        scheduled_at: Duration::from_millis(HAZARD_TICK_INTERVAL_MILLIS).into(),
+    });
+    log::info!("init hazards");
+}
+
+/// Microseconds from `from` to `to`, `0` when `to` is earlier.
+fn micros_between(from: Timestamp, to: Timestamp) -> i64 {
+    to.time_duration_since(from)
+        .map_or(0, |duration| duration.to_micros())
+}
+
+/// Tracks the actors inside each zone and damages those past the entry grace.
+///
+/// **Performance & Cost**: O(zones * cells * actors) for the overlaps, one write per occupant
+/// plus its damage
+#[reducer]
+fn hazard_tick_reducer(ctx: &ReducerContext, _timer: HazardTickTimer) -> Result<(), String> {
+    if ctx.sender != ctx.identity() {
+        log::error!("`hazard_tick_reducer` may not be invoked by clients.");
+        return Err("`hazard_tick_reducer` may not be invoked by clients.".into());
+    }
+
+    let mut write_stats = WriteStats::default();
+    let mut seen = HashSet::new();
+    for zone in ctx.db.hazard_zone_tbl().iter() {
+        for actor_id in zone.actors_inside(ctx) {
+            let occupant = match HazardOccupantRow::find(ctx, actor_id, zone.id) {
+                Some(mut occupant) => {
+                    occupant.last_inside_at = ctx.timestamp;
+                    ctx.db.hazard_occupant_tbl().id().update(occupant)
+                }
+                None => ctx.db.hazard_occupant_tbl().insert(HazardOccupantRow {
+                    id: 0,
+                    actor_id,
+                    hazard_id: zone.id,
+                    entered_at: ctx.timestamp,
+                    last_inside_at: ctx.timestamp,
+                }),
+            };
+            write_stats.record(true);
+            seen.insert(occupant.id);
+
+            if micros_between(occupant.entered_at, ctx.timestamp) < ENTRY_GRACE_MICROS {
+                continue;
+            }
+            let Some(health) = HealthRow::find(&ctx.as_read_only(), actor_id) else {
+                continue;
+            };
+            write_stats.record(health.take_damage(
+                ctx,
+                None,
+                zone.damage_per_tick,
+                CombatEventKind::Damage,
+            ));
+        }
+    }
+
+    let left: Vec<u64> = ctx
+        .db
+        .hazard_occupant_tbl()
+        .iter()
+        .filter(|row| {
+            !seen.contains(&row.id)
+                && micros_between(row.last_inside_at, ctx.timestamp) > EXIT_GRACE_MICROS
+        })
+        .map(|row| row.id)
+        .collect();
+    for id in left {
+        ctx.db.hazard_occupant_tbl().id().delete(id);
+        write_stats.record(true);
+    }
+
+    TimingStatsRow::record(ctx, TimingStatsRow::HAZARD_TICK, write_stats);
+    Ok(())
+}
+
+/// Places a hazard zone in the admin's instance. Admin only.
+#[reducer]
+pub fn place_hazard_zone(
+    ctx: &ReducerContext,
+    center: Vec3,
+    half_extents: Vec3,
+    damage_per_tick: u16,
+    kind: HazardKind,
+) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "place_hazard_zone")?;
+    let center = Vec3::from(validate::within_world(center.into())?);
+    for extent in [half_extents.x, half_extents.y, half_extents.z] {
+        if !(extent.is_finite() && extent > 0.0 && extent <= MAX_HAZARD_HALF_EXTENT) {
+            return Err(format!(
+                "Invalid half extent {extent}, expected 0 < x <= {MAX_HAZARD_HALF_EXTENT}"
+            ));
+        }
+    }
+
+    let zone = ctx.db.hazard_zone_tbl().insert(HazardZoneRow {
+        id: 0,
+        instance_id: sender_instance_id(ctx),
+        center,
+        half_extents,
+        damage_per_tick,
+        kind,
+    });
+    log::info!(
+        "Placed hazard zone {} in instance {}",
+        zone.id,
+        zone.instance_id
+    );
+    Ok(())
+}
+
+/// Deletes a hazard zone and its occupants. Admin only.
+#[reducer]
+pub fn delete_hazard_zone(ctx: &ReducerContext, id: u32) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "delete_hazard_zone")?;
+    if !ctx.db.hazard_zone_tbl().id().delete(id) {
+        return Err(format!("Unable to find hazard zone {id}"));
+    }
+    ctx.db.hazard_occupant_tbl().hazard_id().delete(id);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zone() -> HazardZoneRow {
+        HazardZoneRow {
+            id: 1,
+            instance_id: 0,
+            center: Vec3::default(),
+            half_extents: Vec3 {
+                x: 2.0,
+                y: 1.0,
+                z: 2.0,
+            },
+            damage_per_tick: 10,
+            kind: HazardKind::Lava,
+        }
+    }
+
+    #[test]
+    fn capsule_brushing_the_edge_overlaps() {
+        let capsule = CapsuleY {
+            radius: 0.5,
+            half_height: 0.5,
+        };
+        let at = |x| Vec3 { x, y: 0.0, z: 0.0 };
+        assert!(zone().overlaps(at(2.4), &capsule));
+        assert!(!zone().overlaps(at(2.6), &capsule));
+    }
+
+    #[test]
+    fn reach_covers_the_widest_capsule_at_a_corner() {
+        let zone = zone();
+        let corner = Vec3 {
+            x: zone.half_extents.x + MAX_ACTOR_CAPSULE_RADIUS,
+            y: 0.0,
+            z: zone.half_extents.z + MAX_ACTOR_CAPSULE_RADIUS,
+        };
+        let capsule = CapsuleY {
+            radius: MAX_ACTOR_CAPSULE_RADIUS,
+            half_height: 0.5,
+        };
+        assert!(zone.overlaps(corner, &capsule));
+        assert!(corner.x.hypot(corner.z) <= zone.reach());
+    }
+}
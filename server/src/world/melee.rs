@@ -0,0 +1,108 @@
+use crate::{
+    character_instance_tbl, deal_damage, gameplay_rng, get_static_query_world, ActivityRow,
+    ActorRow, CooldownKind, CooldownRow, DamageSchool, Rewind, TargetRow, TransformRow,
+};
+use shared::{ActorId, MeleeArc};
+use spacetimedb::{reducer, ReducerContext, TimeDuration, Timestamp};
+
+/// Max distance (meters) from the attacker to the surface of the target's capsule.
+pub const MELEE_REACH: f32 = 2.0;
+
+/// Half of the melee arc's opening angle (radians), 120 degrees in total.
+const MELEE_HALF_ANGLE: f32 = std::f32::consts::FRAC_PI_3;
+
+const MELEE_DAMAGE: u16 = 10;
+const MELEE_COOLDOWN_MICROS: i64 = 1_500_000;
+
+/// Melee attacks the given actor, or the attacker's current target (see [`TargetRow`]).
+///
+/// The target has to be within the arc in front of the attacker, within reach and not behind a
+/// world static. `seen_at` is the client's estimate of the server time of the world it saw, the
+/// target is judged where it was then (see [`Rewind`]).
+#[reducer]
+pub fn attack(
+    ctx: &ReducerContext,
+    target: Option<ActorId>,
+    seen_at: Option<Timestamp>,
+) -> Result<(), String> {
+    let Some(ci) = ctx.db.character_instance_tbl().identity().find(ctx.sender) else {
+        log::error!("attack: no active character for {:?}", ctx.sender);
+        return Err("No active character".into());
+    };
+    ActivityRow::touch(ctx);
+    let attacker = ci.actor_id;
+    let view_ctx = ctx.as_read_only();
+    if ActorRow::is_dead(&view_ctx, attacker) {
+        return Err("Dead actors can't attack".into());
+    }
+    let Some(target) = TargetRow::resolve(&view_ctx, attacker, target) else {
+        return Err("No target".into());
+    };
+    melee_attack(ctx, attacker, target, seen_at)
+}
+
+/// The melee attack of [`attack`] for any actor, e.g. a monster's AI. The attacker has to be
+/// alive, this checks the target and starts the attack cooldown.
+///
+/// With `seen_at` the target is rewound to that time (see [`Rewind::new`]), server-side
+/// attackers pass `None` and hit where the target is now.
+pub fn melee_attack(
+    ctx: &ReducerContext,
+    attacker: ActorId,
+    target: ActorId,
+    seen_at: Option<Timestamp>,
+) -> Result<(), String> {
+    if target == attacker {
+        return Err("Can't attack yourself".into());
+    }
+
+    let view_ctx = ctx.as_read_only();
+    let Some(attacker_transform) = TransformRow::find(ctx, attacker) else {
+        log::error!("attack: no transform for actor {}", attacker);
+        return Err("No transform for attacker".into());
+    };
+    let (Some(attacker_actor), Some(target_actor), Some(target_translation)) = (
+        ActorRow::find(&view_ctx, attacker),
+        ActorRow::find(&view_ctx, target),
+        Rewind::new(ctx, seen_at).position(ctx, target),
+    ) else {
+        return Err("Unknown target".into());
+    };
+    if attacker_actor.instance_id != target_actor.instance_id {
+        return Err("Target is out of reach".into());
+    }
+
+    let arc = MeleeArc {
+        yaw: attacker_transform.yaw,
+        reach: MELEE_REACH,
+        half_angle: MELEE_HALF_ANGLE,
+    };
+    let query_world = get_static_query_world(ctx, attacker_actor.instance_id);
+    if !arc.hits_capsule(
+        attacker_transform.translation.into(),
+        target_translation.into(),
+        target_actor.capsule.radius,
+        target_actor.capsule.half_height,
+        Some(&query_world),
+    ) {
+        return Err("Target is out of reach".into());
+    }
+
+    CooldownRow::try_start(
+        ctx,
+        attacker,
+        CooldownKind::Attack,
+        TimeDuration::from_micros(MELEE_COOLDOWN_MICROS),
+    )?;
+
+    deal_damage(
+        ctx,
+        &mut gameplay_rng(ctx, attacker as u64),
+        attacker,
+        target,
+        MELEE_DAMAGE,
+        DamageSchool::Physical,
+    );
+
+    Ok(())
+}
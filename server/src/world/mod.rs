@@ -0,0 +1,13 @@
+pub mod aoe;
+pub mod charge;
+pub mod hazard;
+pub mod melee;
+pub mod projectile;
+pub mod query_world;
+
+pub use aoe::*;
+pub use charge::*;
+pub use hazard::*;
+pub use melee::*;
+pub use projectile::*;
+pub use query_world::*;
@@ -0,0 +1,324 @@
+//! Projectiles, abilities that travel to their target instead of hitting instantly.
+//!
+//! A projectile flies in a straight line, its position is a pure function of the row
+//! (`origin + velocity * (t - spawned_at)`), so the row is only written when the projectile
+//! changes cell. Clients predict the position from the same formula, see [`projectile_view`].
+//!
+//! [`projectile_tick_reducer`] sweeps each projectile over the stretch it flew since the last
+//! tick, against the static world and the actor capsules (see [`shared::SceneQuery`]). Actors
+//! the owner can't harm, and the dead, are flown through. The first hit ends it, so does
+//! [`ProjectileRow::expires_at`].
+
+use crate::{
+    character_instance_tbl, deal_damage, gameplay_rng, get_actor_collider_layer,
+    get_static_query_world, get_view_aoi_block, is_in_aoi, view_instance_id, ActivityRow, ActorRow,
+    CooldownKind, CooldownRow, DamageSchool, TargetRow, TimingStatsRow, TransformRow, Vec3,
+    WriteStats,
+};
+use nalgebra::Vector3;
+use rapier3d::prelude::Ray;
+use shared::{encode_cell_id, ActorId, CellId, InstanceId, SceneHit, SceneQuery};
+use spacetimedb::{
+    reducer, table, ReducerContext, ScheduleAt, Table, TimeDuration, Timestamp, ViewContext,
+};
+use std::time::Duration;
+
+pub(crate) const PROJECTILE_TICK_INTERVAL_MILLIS: u64 = 100;
+
+/// Radius (meters) of a projectile, added to the capsules it's tested against.
+const PROJECTILE_RADIUS: f32 = 0.15;
+
+/// Definition of an ability that fires a projectile at the caster's target.
+#[derive(Debug, Clone, Copy)]
+pub struct ProjectileAbilityDef {
+    /// Shares the id space (and so the cooldowns) with the AoE abilities.
+    pub id: u16,
+    /// Meters/second.
+    pub speed: f32,
+    /// Distance (meters) flown before the projectile fizzles, also the range to the target.
+    pub range: f32,
+    pub damage: u16,
+    pub school: DamageSchool,
+    pub cooldown_micros: i64,
+}
+
+/// The projectile abilities that can be cast.
+pub const PROJECTILE_ABILITIES: &[ProjectileAbilityDef] = &[
+    // Veil bolt
+    ProjectileAbilityDef {
+        id: 4,
+        speed: 18.0,
+        range: 25.0,
+        damage: 20,
+        school: DamageSchool::Veil,
+        cooldown_micros: 1_500_000,
+    },
+];
+
+impl ProjectileAbilityDef {
+    pub fn find(id: u16) -> Option<&'static Self> {
+        PROJECTILE_ABILITIES.iter().find(|def| def.id == id)
+    }
+}
+
+/// **Ephemeral**
+///
+/// A projectile in flight.
+#[table(name=projectile_tbl)]
+pub struct ProjectileRow {
+    #[auto_inc]
+    #[primary_key]
+    pub id: u64,
+
+    pub ability_id: u16,
+
+    /// The caster, damage is dealt as this actor.
+    pub owner: ActorId,
+
+    pub instance_id: InstanceId,
+
+    /// Cell of the current position, updated as the projectile crosses cells.
+    #[index(btree)]
+    pub cell_id: CellId,
+
+    /// Position at `spawned_at`.
+    pub origin: Vec3,
+
+    /// Meters/second.
+    pub velocity: Vec3,
+
+    pub spawned_at: Timestamp,
+
+    pub expires_at: Timestamp,
+}
+
+impl ProjectileRow {
+    /// Position at `at`, clamped to the flight time.
+    pub fn position_at(&self, at: Timestamp) -> Vector3<f32> {
+        let secs = at
+            .time_duration_since(self.spawned_at)
+            .map_or(0.0, |duration| duration.to_micros() as f32 / 1_000_000.0);
+        let flight_secs = self
+            .expires_at
+            .time_duration_since(self.spawned_at)
+            .map_or(0.0, |duration| duration.to_micros() as f32 / 1_000_000.0);
+        Vector3::from(self.origin) + Vector3::from(self.velocity) * secs.min(flight_secs)
+    }
+
+    /// Finds what the projectile hits moving from `from` to `to`, `None` when nothing is in the
+    /// way. `Some(None)` is the static world, `Some(Some(actor))` an actor the owner can harm.
+    ///
+    /// **Performance & Cost**: one scene ray cast, the actor layer is shared by the tick
+    fn sweep(
+        &self,
+        ctx: &ReducerContext,
+        from: Vector3<f32>,
+        to: Vector3<f32>,
+    ) -> Option<Option<ActorId>> {
+        let delta = to - from;
+        let length = delta.norm();
+        if length <= f32::EPSILON {
+            return None;
+        }
+        let ray = Ray::new(from.into(), delta / length);
+
+        let view_ctx = ctx.as_read_only();
+        let statics = get_static_query_world(ctx, self.instance_id);
+        let actors = get_actor_collider_layer(ctx, self.instance_id);
+        let (hit, _) = SceneQuery::new(&statics, &actors).cast_ray(
+            &ray,
+            length,
+            PROJECTILE_RADIUS,
+            |actor_id| {
+                ActorRow::can_harm(&view_ctx, self.owner, actor_id)
+                    && !ActorRow::is_dead(&view_ctx, actor_id)
+            },
+        )?;
+        match hit {
+            SceneHit::Static(_) => Some(None),
+            SceneHit::Actor(actor_id) => Some(Some(actor_id)),
+        }
+    }
+}
+
+#[table(name = projectile_tick_timer, scheduled(projectile_tick_reducer))]
+pub struct ProjectileTickTimer {
+    #[primary_key]
+    #[auto_inc]
+    pub scheduled_id: u64,
+    pub scheduled_at: ScheduleAt,
+
+    /// When the previous tick ran, projectiles are swept from their position at this time.
+    pub last_tick: Timestamp,
+}
+
+pub fn init_projectiles(ctx: &ReducerContext) {
+    ctx.db.projectile_tick_timer().scheduled_id().delete(1);
+    ctx.db.projectile_tick_timer().insert(ProjectileTickTimer {
+        scheduled_id: 1,
+        scheduled_at: Duration::from_millis(PROJECTILE_TICK_INTERVAL_MILLIS).into(),
+        last_tick: ctx.timestamp,
+    });
+    log::info!("init projectiles");
+}
+
+/// Moves every projectile along its flight, resolving hits and expiry.
+///
+/// **Performance & Cost**: O(projectiles * cells * actors) for the sweeps, writes only on hits,
+/// expiry and cell changes, nothing without projectiles
+#[reducer]
+fn projectile_tick_reducer(
+    ctx: &ReducerContext,
+    mut timer: ProjectileTickTimer,
+) -> Result<(), String> {
+    if ctx.sender != ctx.identity() {
+        log::error!("`projectile_tick_reducer` may not be invoked by clients.");
+        return Err("`projectile_tick_reducer` may not be invoked by clients.".into());
+    }
+
+    let projectiles: Vec<ProjectileRow> = ctx.db.projectile_tbl().iter().collect();
+    if projectiles.is_empty() {
+        // Nothing to advance, and projectiles fired from now on sweep from `spawned_at`: leave
+        // `last_tick` stale rather than write the timer row every tick.
+        return Ok(());
+    }
+
+    let mut write_stats = WriteStats::default();
+    for mut projectile in projectiles {
+        let from_at = projectile.spawned_at.max(timer.last_tick);
+        let from = projectile.position_at(from_at);
+        let to = projectile.position_at(ctx.timestamp);
+
+        if let Some(hit) = projectile.sweep(ctx, from, to) {
+            if let (Some(target), Some(ability)) =
+                (hit, ProjectileAbilityDef::find(projectile.ability_id))
+            {
+                let mut rng = gameplay_rng(ctx, projectile.id);
+                write_stats.record(deal_damage(
+                    ctx,
+                    &mut rng,
+                    projectile.owner,
+                    target,
+                    ability.damage,
+                    ability.school,
+                ));
+            }
+            ctx.db.projectile_tbl().id().delete(projectile.id);
+            write_stats.record(true);
+            continue;
+        }
+        if projectile.expires_at <= ctx.timestamp {
+            ctx.db.projectile_tbl().id().delete(projectile.id);
+            write_stats.record(true);
+            continue;
+        }
+
+        let cell_id = encode_cell_id(to.x, to.z);
+        if cell_id != projectile.cell_id {
+            projectile.cell_id = cell_id;
+            ctx.db.projectile_tbl().id().update(projectile);
+            write_stats.record(true);
+        }
+    }
+
+    timer.last_tick = ctx.timestamp;
+    ctx.db.projectile_tick_timer().scheduled_id().update(timer);
+    TimingStatsRow::record(ctx, TimingStatsRow::PROJECTILE_TICK, write_stats);
+    Ok(())
+}
+
+/// Fires a projectile ability at `target`, or the caster's current target without one.
+#[reducer]
+pub fn cast_projectile_ability(
+    ctx: &ReducerContext,
+    ability_id: u16,
+    target: Option<ActorId>,
+) -> Result<(), String> {
+    let Some(ci) = ctx.db.character_instance_tbl().identity().find(ctx.sender) else {
+        log::error!(
+            "cast_projectile_ability: no active character for {:?}",
+            ctx.sender
+        );
+        return Err("No active character".into());
+    };
+    ActivityRow::touch(ctx);
+    let caster = ci.actor_id;
+    let view_ctx = ctx.as_read_only();
+    if ActorRow::is_dead(&view_ctx, caster) {
+        return Err("Dead actors can't cast".into());
+    }
+    let Some(ability) = ProjectileAbilityDef::find(ability_id) else {
+        return Err("Unknown ability".into());
+    };
+    let Some(target) = TargetRow::resolve(&view_ctx, caster, target) else {
+        return Err("No target".into());
+    };
+    if target == caster {
+        return Err("Can't target yourself".into());
+    }
+    // Only what the caster can see, with the same error as for unknown actors so hidden ones
+    // can't be probed, like `crate::charge`.
+    if !ActorRow::can_harm(&view_ctx, caster, target)
+        || ActorRow::is_dead(&view_ctx, target)
+        || !is_in_aoi(&view_ctx, caster, target)
+    {
+        return Err("Invalid target".into());
+    }
+    let (Some(caster_actor), Some(caster_transform), Some(target_transform)) = (
+        ActorRow::find(&view_ctx, caster),
+        TransformRow::find(ctx, caster),
+        TransformRow::find(ctx, target),
+    ) else {
+        return Err("Target is not in range".into());
+    };
+    if ActorRow::find(&view_ctx, target)
+        .is_none_or(|actor| actor.instance_id != caster_actor.instance_id)
+    {
+        return Err("Target is not in range".into());
+    }
+
+    let origin = Vector3::from(caster_transform.translation);
+    let to_target = Vector3::from(target_transform.translation) - origin;
+    let distance = to_target.norm();
+    if distance > ability.range {
+        return Err("Target is out of range".into());
+    }
+    let Some(direction) = to_target.try_normalize(f32::EPSILON) else {
+        return Err("Target is out of range".into());
+    };
+
+    CooldownRow::try_start(
+        ctx,
+        caster,
+        CooldownKind::Ability(ability.id),
+        TimeDuration::from_micros(ability.cooldown_micros),
+    )?;
+
+    let flight_micros = (ability.range / ability.speed * 1_000_000.0) as i64;
+    ctx.db.projectile_tbl().insert(ProjectileRow {
+        id: 0,
+        ability_id: ability.id,
+        owner: caster,
+        instance_id: caster_actor.instance_id,
+        cell_id: encode_cell_id(origin.x, origin.z),
+        origin: origin.into(),
+        velocity: (direction * ability.speed).into(),
+        spawned_at: ctx.timestamp,
+        expires_at: ctx.timestamp + TimeDuration::from_micros(flight_micros),
+    });
+    Ok(())
+}
+
+/// Finds the projectiles within the AOI, of the viewer's instance.
+/// Primary key of `id`
+#[spacetimedb::view(name = projectile_view, public)]
+pub fn projectile_view(ctx: &ViewContext) -> Vec<ProjectileRow> {
+    let Some(cells) = get_view_aoi_block(ctx) else {
+        return vec![];
+    };
+    let instance_id = view_instance_id(ctx);
+    cells
+        .flat_map(|cell_id| ctx.db.projectile_tbl().cell_id().filter(cell_id))
+        .filter(|projectile| projectile.instance_id == instance_id)
+        .collect()
+}
@@ -0,0 +1,158 @@
+use crate::{actor_tbl, row_to_def, transform_tbl, world_static_tbl, TICK_INTERVAL_SECS};
+use shared::{
+    utils::{build_static_query_world, StaticQueryWorld},
+    ActorCollider, ActorColliderLayer, ActorFlags, InstanceId,
+};
+use spacetimedb::{table, ReducerContext, Table, Timestamp};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    rc::Rc,
+};
+
+/// Version stamped on every change to an instance's rows in `world_static_tbl`.
+///
+/// The cached query worlds are keyed on this so an instance's world is only rebuilt when its
+/// static world changes. Versions only increase, but not one by one: they come from a counter
+/// shared by all instances that isn't rolled back with the transaction, see [`Self::bump`].
+#[table(name=world_version_tbl)]
+pub struct WorldVersionRow {
+    #[primary_key]
+    pub instance_id: InstanceId,
+
+    pub version: u64,
+}
+
+impl WorldVersionRow {
+    pub fn current(ctx: &ReducerContext, instance_id: InstanceId) -> u64 {
+        ctx.db
+            .world_version_tbl()
+            .instance_id()
+            .find(instance_id)
+            .map(|row| row.version)
+            .unwrap_or(0)
+    }
+
+    /// Stamps the instance with a version never handed out before by this module.
+    ///
+    /// A reducer that bumps the version, caches a world for it and then fails rolls the row
+    /// back but not [`LAST_VERSION`], so the next bump can't reuse the key of that world.
+    pub fn bump(ctx: &ReducerContext, instance_id: InstanceId) {
+        let current = Self::current(ctx, instance_id);
+        let version = LAST_VERSION.get().max(current) + 1;
+        LAST_VERSION.set(version);
+        match ctx.db.world_version_tbl().instance_id().find(instance_id) {
+            Some(mut row) => {
+                row.version = version;
+                ctx.db.world_version_tbl().instance_id().update(row);
+            }
+            None => {
+                ctx.db.world_version_tbl().insert(Self {
+                    instance_id,
+                    version,
+                });
+            }
+        }
+    }
+}
+
+thread_local! {
+    /// The latest version [`WorldVersionRow::bump`] handed out. Starts over with the module,
+    /// along with the caches, and is raised past the stored version before each use.
+    static LAST_VERSION: Cell<u64> = const { Cell::new(0) };
+
+    /// The static query worlds by instance and the [`WorldVersionRow::version`] they were built
+    /// for, one version per instance.
+    static QUERY_WORLDS: RefCell<HashMap<(InstanceId, u64), Rc<StaticQueryWorld>>> =
+        RefCell::new(HashMap::new());
+
+    /// The actor collider layer per instance, built for the reducer call at a timestamp.
+    static ACTOR_LAYERS: RefCell<HashMap<InstanceId, (Timestamp, Rc<ActorColliderLayer>)>> =
+        RefCell::new(HashMap::new());
+}
+
+/// Returns the Rapier query world for an instance's static world, shared between ticks.
+///
+/// Each world is built at most once per world version and kept at module level, so movement
+/// (and any other tick doing scene queries) only pays one index seek per call instead of a table
+/// scan plus broad phase build.
+///
+/// The cache lives outside of the transaction: a world cached by a reducer that's rolled back
+/// stays, it's never read again since versions aren't reused (see [`WorldVersionRow::bump`]).
+pub fn get_static_query_world(
+    ctx: &ReducerContext,
+    instance_id: InstanceId,
+) -> Rc<StaticQueryWorld> {
+    let version = WorldVersionRow::current(ctx, instance_id);
+    QUERY_WORLDS.with_borrow_mut(|cached| {
+        if let Some(world) = cached.get(&(instance_id, version)) {
+            return world.clone();
+        }
+
+        log::info!(
+            "Building static query world for instance {} version {}",
+            instance_id,
+            version
+        );
+        // Collected first, the filtered index scan can't tell the builder how many to presize for.
+        let world_defs: Vec<_> = ctx
+            .db
+            .world_static_tbl()
+            .instance_id()
+            .filter(instance_id)
+            .map(row_to_def)
+            .collect();
+        let world = Rc::new(build_static_query_world(world_defs, TICK_INTERVAL_SECS));
+        cached.retain(|(cached_instance_id, _), _| *cached_instance_id != instance_id);
+        cached.insert((instance_id, version), world.clone());
+        world
+    })
+}
+
+/// Returns the capsules of the instance's living actors as a query layer, see
+/// [`shared::SceneQuery`].
+///
+/// The layer is transient: built on the first query of a reducer call and reused by every other
+/// query of that call (a tick sweeping all projectiles, the AI tick acquiring targets), the next
+/// call rebuilds it. It's a snapshot, actors moved or killed later in the same call keep their
+/// collider until then.
+///
+/// **Performance & Cost**: an index scan of the instance's actors and a transform seek per actor
+/// once per reducer call, an index seek after that
+pub fn get_actor_collider_layer(
+    ctx: &ReducerContext,
+    instance_id: InstanceId,
+) -> Rc<ActorColliderLayer> {
+    ACTOR_LAYERS.with_borrow_mut(|cached| {
+        if let Some((built_at, layer)) = cached.get(&instance_id) {
+            if *built_at == ctx.timestamp {
+                return layer.clone();
+            }
+        }
+
+        let actors = ctx
+            .db
+            .actor_tbl()
+            .instance_id()
+            .filter(instance_id)
+            .filter(|actor| {
+                !actor
+                    .flags()
+                    .intersects(ActorFlags::DEAD | ActorFlags::POOLED)
+            })
+            .filter_map(|actor| {
+                let transform = ctx.db.transform_tbl().actor_id().find(actor.id)?;
+                Some(ActorCollider {
+                    actor_id: actor.id,
+                    translation: transform.translation.into(),
+                    radius: actor.capsule.radius,
+                    half_height: actor.capsule.half_height,
+                })
+            });
+        let layer = Rc::new(ActorColliderLayer::build(actors, TICK_INTERVAL_SECS));
+        // Layers of earlier calls are never read again.
+        cached.retain(|_, (built_at, _)| *built_at == ctx.timestamp);
+        cached.insert(instance_id, (ctx.timestamp, layer.clone()));
+        layer
+    })
+}
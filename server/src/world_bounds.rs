@@ -0,0 +1,59 @@
+use crate::Vec2;
+use nalgebra::Vector2;
+use shared::{
+    validate::{self, ValidationError},
+    PLAYABLE_HALF_EXTENT, WORLD_BORDER_HEIGHT,
+};
+use spacetimedb::{table, ReducerContext, Table, ViewContext};
+
+/// The playable area inside the world border walls, a single row.
+///
+/// Derived from the grid constants (the walls are generated from the same, see
+/// [`crate::regenerate_static_world`]). Public so clients can draw the boundary, move targets
+/// beyond it are rejected by [`crate::request_move`].
+#[table(name=world_bounds_tbl, public)]
+pub struct WorldBoundsRow {
+    /// Always [`WorldBoundsRow::ID`].
+    #[primary_key]
+    pub id: u8,
+
+    pub min: Vec2,
+
+    pub max: Vec2,
+
+    /// Height (meters) of the border walls.
+    pub height: f32,
+}
+
+impl WorldBoundsRow {
+    const ID: u8 = 0;
+
+    fn derived() -> Self {
+        Self {
+            id: Self::ID,
+            min: Vec2::new(-PLAYABLE_HALF_EXTENT, -PLAYABLE_HALF_EXTENT),
+            max: Vec2::new(PLAYABLE_HALF_EXTENT, PLAYABLE_HALF_EXTENT),
+            height: WORLD_BORDER_HEIGHT,
+        }
+    }
+
+    /// Seeding step of [`crate::SchemaVersionRow`], (re)derives the row.
+    pub fn seed(ctx: &ReducerContext) {
+        ctx.db.world_bounds_tbl().id().delete(Self::ID);
+        ctx.db.world_bounds_tbl().insert(Self::derived());
+    }
+
+    /// The stored bounds, the derived ones before seeding.
+    pub fn get(ctx: &ViewContext) -> Self {
+        ctx.db
+            .world_bounds_tbl()
+            .id()
+            .find(Self::ID)
+            .unwrap_or_else(Self::derived)
+    }
+
+    /// Rejects non-finite planar points and ones beyond the bounds.
+    pub fn check_xz(&self, point: Vector2<f32>) -> Result<Vector2<f32>, ValidationError> {
+        validate::within_bounds_xz(point, self.min.into(), self.max.into())
+    }
+}
@@ -1,18 +1,45 @@
-use crate::{ColliderShape, Cone, Cylinder, Quat, RoundCone, RoundCuboid, RoundCylinder, Vec3};
-use shared::{ColliderShapeDef, WorldStaticDef};
-use spacetimedb::{table, ReducerContext, Table};
+use crate::{
+    sender_instance_id, view_instance_id, AdminIdentityRow, ColliderShape, EventKind, EventLogRow,
+    InstanceRow, Quat, Vec3, WorldVersionRow,
+};
+use nalgebra::Vector3;
+use shared::{
+    test_course, validate, InstanceId, WorldStaticDef, WORLD_BORDER_HEIGHT, WORLD_BORDER_THICKNESS,
+    WORLD_OFFSET,
+};
+use spacetimedb::{reducer, table, ReducerContext, SpacetimeType, Table, ViewContext};
+
+/// Largest dimension (meters) accepted for shapes placed by the world editing reducers.
+const MAX_STATIC_EXTENT: f32 = WORLD_OFFSET;
+
+/// What a static collider's surface is made of, used for footstep audio and effects.
+#[derive(SpacetimeType, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SurfaceMaterial {
+    Dirt,
+    Grass,
+    Stone,
+    Wood,
+    Sand,
+    Metal,
+    Water,
+}
 
 /// Static collider rows used to build the immutable world collision geometry.
 ///
-/// The server reads these rows into an in-memory Rapier query world for use in
-/// scene queries and the kinematic character controller (KCC).
-#[table(name = world_static_tbl, public)]
+/// The server reads these rows into an in-memory Rapier query world per instance for use in
+/// scene queries and the kinematic character controller (KCC). Clients get the statics of their
+/// own instance through [`world_static_view`].
+#[table(name = world_static_tbl)]
 pub struct WorldStatic {
     /// Unique id (primary key).
     #[primary_key]
     #[auto_inc]
     pub id: u64,
 
+    /// The instance this static is part of.
+    #[index(btree)]
+    pub instance_id: InstanceId,
+
     /// World transform applied to the shape.
     pub translation: Vec3,
     pub rotation: Quat,
@@ -20,92 +47,200 @@ pub struct WorldStatic {
 
     /// Collider shape definition.
     pub shape: ColliderShape,
+
+    /// The surface actors standing on this collider walk on, `None` when it isn't meant to be
+    /// walked on (or uses the client's default).
+    pub surface_material: Option<SurfaceMaterial>,
 }
 impl WorldStatic {
+    /// All writes go through these helpers so the world version is bumped and
+    /// the cached query world is rebuilt, see [`crate::get_static_query_world`].
     pub fn insert(ctx: &ReducerContext, ws: WorldStatic) -> Self {
+        WorldVersionRow::bump(ctx, ws.instance_id);
         ctx.db.world_static_tbl().insert(ws)
     }
-    pub fn clear(ctx: &ReducerContext) {
-        for row in ctx.db.world_static_tbl().iter() {
+    pub fn update(ctx: &ReducerContext, ws: WorldStatic) -> Self {
+        WorldVersionRow::bump(ctx, ws.instance_id);
+        ctx.db.world_static_tbl().id().update(ws)
+    }
+    pub fn delete(ctx: &ReducerContext, id: u64) -> bool {
+        let Some(row) = ctx.db.world_static_tbl().id().find(id) else {
+            return false;
+        };
+        WorldVersionRow::bump(ctx, row.instance_id);
+        ctx.db.world_static_tbl().delete(row)
+    }
+    pub fn clear(ctx: &ReducerContext, instance_id: InstanceId) {
+        WorldVersionRow::bump(ctx, instance_id);
+        for row in ctx.db.world_static_tbl().instance_id().filter(instance_id) {
             ctx.db.world_static_tbl().delete(row);
         }
     }
 }
 
-/// Convert a single `WorldStatic` row to the shared schema-agnostic definition.
-pub fn row_to_def(row: WorldStatic) -> WorldStaticDef {
-    let shape = match row.shape {
-        ColliderShape::Plane(offset_along_normal) => ColliderShapeDef::Plane {
-            offset_along_normal,
-        },
-        ColliderShape::Cuboid(half_extents) => ColliderShapeDef::Cuboid {
-            half_extents: half_extents.into(),
-        },
-        ColliderShape::Sphere(radius) => ColliderShapeDef::Sphere { radius },
-        ColliderShape::CapsuleY(dim) => ColliderShapeDef::CapsuleY {
-            radius: dim.radius,
-            half_height: dim.half_height,
-        },
-        ColliderShape::Cylinder(Cylinder {
-            radius,
-            half_height,
-        }) => ColliderShapeDef::CylinderY {
-            radius,
-            half_height,
-        },
-        ColliderShape::Cone(Cone {
-            radius,
-            half_height,
-        }) => ColliderShapeDef::ConeY {
-            radius,
-            half_height,
-        },
-        ColliderShape::RoundCuboid(RoundCuboid {
-            half_extents,
-            border_radius,
-        }) => ColliderShapeDef::RoundCuboid {
-            half_extents: half_extents.into(),
-            border_radius,
-        },
-        ColliderShape::RoundCylinder(RoundCylinder {
-            radius,
-            half_height,
-            border_radius,
-        }) => ColliderShapeDef::RoundCylinderY {
-            radius,
-            half_height,
-            border_radius,
-        },
-        ColliderShape::RoundCone(RoundCone {
-            radius,
-            half_height,
-            border_radius,
-        }) => ColliderShapeDef::RoundConeY {
-            radius,
-            half_height,
-            border_radius,
+/// Where a world static is and how it's oriented, set by the world editing reducers.
+#[derive(SpacetimeType, Debug, Clone, Copy, PartialEq)]
+pub struct WorldStaticPose {
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+}
+
+impl WorldStaticPose {
+    /// Rejects NaNs, degenerate rotations/scales and positions outside the playable world.
+    ///
+    /// `shape` is checked scaled (see [`row_to_def`]): every dimension times the largest scale
+    /// component has to stay within [`MAX_STATIC_EXTENT`].
+    pub fn validate(&self, shape: &ColliderShape) -> Result<(), String> {
+        validate::within_world(self.translation.into())?;
+        validate::unit_quat_or_err(self.rotation.into())?;
+        let scale = validate::finite_vec3(self.scale.into())?;
+        if scale.iter().any(|&c| c <= 0.0) {
+            return Err(format!("Invalid scale {:?}", self.scale));
+        }
+        shape.validate(MAX_STATIC_EXTENT / scale.max())
+    }
+}
+
+/// Places a new static collider in the admin's current instance. Admin only.
+#[reducer]
+pub fn place_static(
+    ctx: &ReducerContext,
+    pose: WorldStaticPose,
+    shape: ColliderShape,
+    surface_material: Option<SurfaceMaterial>,
+) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "place_static")?;
+    pose.validate(&shape)?;
+
+    let row = WorldStatic::insert(
+        ctx,
+        WorldStatic {
+            id: 0,
+            instance_id: sender_instance_id(ctx),
+            translation: pose.translation,
+            rotation: pose.rotation,
+            scale: pose.scale,
+            shape,
+            surface_material,
         },
+    );
+    EventLogRow::record(
+        ctx,
+        EventKind::WorldEdited,
+        None,
+        format!(
+            "{:?} placed static {} {:?} in instance {}",
+            ctx.sender, row.id, row.shape, row.instance_id
+        ),
+    );
+    Ok(())
+}
+
+/// Moves an existing static collider. Admin only.
+#[reducer]
+pub fn update_static(ctx: &ReducerContext, id: u64, pose: WorldStaticPose) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "update_static")?;
+    let Some(mut row) = ctx.db.world_static_tbl().id().find(id) else {
+        return Err("Unable to find world static".into());
     };
+    pose.validate(&row.shape)?;
 
-    WorldStaticDef {
-        id: row.id,
-        translation: row.translation.into(),
-        rotation: row.rotation.into(),
-        shape,
+    row.translation = pose.translation;
+    row.rotation = pose.rotation;
+    row.scale = pose.scale;
+    WorldStatic::update(ctx, row);
+    EventLogRow::record(
+        ctx,
+        EventKind::WorldEdited,
+        None,
+        format!("{:?} moved static {} to {:?}", ctx.sender, id, pose),
+    );
+    Ok(())
+}
+
+/// Deletes a static collider. Admin only.
+#[reducer]
+pub fn delete_static(ctx: &ReducerContext, id: u64) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "delete_static")?;
+    if !WorldStatic::delete(ctx, id) {
+        return Err("Unable to find world static".into());
     }
+
+    EventLogRow::record(
+        ctx,
+        EventKind::WorldEdited,
+        None,
+        format!("{:?} deleted static {}", ctx.sender, id),
+    );
+    Ok(())
 }
 
-/// Deletes all static world entries and re-inserts them to build the world
-pub fn regenerate_static_world(ctx: &ReducerContext) {
-    for row in ctx.db.world_static_tbl().iter() {
-        ctx.db.world_static_tbl().delete(row);
+/// Convert a single `WorldStatic` row to the shared schema-agnostic definition, with its scale
+/// applied (see [`shared::ColliderShapeDef::scaled`]).
+pub fn row_to_def(row: WorldStatic) -> WorldStaticDef {
+    WorldStaticDef::new(
+        row.id,
+        row.translation.into(),
+        row.rotation.into(),
+        row.scale.into(),
+        shared::collider_shape_def!(ColliderShape, &row.shape),
+    )
+}
+
+/// Inserts four walls along the edges of the cell grid so actors can never reach the area where
+/// `encode_cell_id` starts clamping positions.
+///
+/// The walls sit just inside the grid, derived from `WORLD_OFFSET` (half the world span).
+fn insert_world_borders(ctx: &ReducerContext, instance_id: InstanceId) {
+    let half_thickness = WORLD_BORDER_THICKNESS * 0.5;
+    let half_height = WORLD_BORDER_HEIGHT * 0.5;
+    let edge = WORLD_OFFSET - half_thickness;
+
+    let walls = [
+        // East (+X) and West (-X)
+        (
+            Vec3::new(edge, half_height, 0.0),
+            Vec3::new(half_thickness, half_height, WORLD_OFFSET),
+        ),
+        (
+            Vec3::new(-edge, half_height, 0.0),
+            Vec3::new(half_thickness, half_height, WORLD_OFFSET),
+        ),
+        // North (+Z) and South (-Z)
+        (
+            Vec3::new(0.0, half_height, edge),
+            Vec3::new(WORLD_OFFSET, half_height, half_thickness),
+        ),
+        (
+            Vec3::new(0.0, half_height, -edge),
+            Vec3::new(WORLD_OFFSET, half_height, half_thickness),
+        ),
+    ];
+
+    for (translation, half_extents) in walls {
+        WorldStatic::insert(
+            ctx,
+            WorldStatic {
+                id: 0,
+                instance_id,
+                translation,
+                rotation: Quat::IDENTITY,
+                scale: Vec3::ONE,
+                shape: ColliderShape::Cuboid(half_extents),
+                surface_material: None,
+            },
+        );
     }
+}
 
-    // Infinite ground plane at y = 0.
+/// Inserts the infinite ground plane at y = 0.
+fn insert_ground_plane(ctx: &ReducerContext, instance_id: InstanceId) {
     WorldStatic::insert(
         ctx,
         WorldStatic {
             id: 0,
+            instance_id,
             translation: Vec3::ZERO,
             rotation: Quat {
                 x: 0.0,
@@ -116,67 +251,123 @@ pub fn regenerate_static_world(ctx: &ReducerContext) {
             // Visual-only for planes.
             scale: Vec3::new(10.0, 1.0, 10.0),
             shape: ColliderShape::Plane(0.0),
+            surface_material: Some(SurfaceMaterial::Grass),
         },
     );
+}
+
+/// Inserts the bare minimum for a new instance: the world borders and the ground plane.
+pub fn insert_instance_base(ctx: &ReducerContext, instance_id: InstanceId) {
+    insert_world_borders(ctx, instance_id);
+    insert_ground_plane(ctx, instance_id);
+}
+
+/// Deletes all static world entries of the overworld and re-inserts them to build the world.
+///
+/// Other instances are left alone, they're built by whoever created them.
+pub fn regenerate_static_world(ctx: &ReducerContext) {
+    let instance_id = InstanceRow::OVERWORLD;
+    WorldStatic::clear(ctx, instance_id);
+
+    insert_instance_base(ctx, instance_id);
 
     // A simple oriented cuboid test object.
     WorldStatic::insert(
         ctx,
         WorldStatic {
             id: 0,
+            instance_id,
             translation: Vec3::new(3.0, 1.0, 0.0),
             rotation: Quat::IDENTITY,
             scale: Vec3::ONE,
-            // Half-extents (hx, hy, hz) before scale is applied by the server's world loader.
+            // Half-extents (hx, hy, hz) before scale is applied, see `row_to_def`.
             shape: ColliderShape::Cuboid(Vec3::ONE),
+            surface_material: Some(SurfaceMaterial::Metal),
         },
     );
 
     // A downhill ramp (tilted cuboid) to test snap-to-ground and slope behavior.
-    // Tilt around X by -20 degrees so moving +Z goes "uphill".
     WorldStatic::insert(
         ctx,
         WorldStatic {
             id: 0,
-            translation: Vec3::new(-3.0, 0.0, 6.0),
-            rotation: Quat {
-                x: -0.17364818,
-                y: 0.0,
-                z: 0.0,
-                w: 0.98480775,
-            },
+            instance_id,
+            translation: Vector3::from(test_course::RAMP_CENTER).into(),
+            rotation: test_course::ramp_rotation().into(),
             scale: Vec3::ONE,
-            shape: ColliderShape::Cuboid(Vec3::new(1.0, 1.0, 10.0)),
+            shape: ColliderShape::Cuboid(Vector3::from(test_course::RAMP_HALF_EXTENTS).into()),
+            surface_material: Some(SurfaceMaterial::Dirt),
         },
     );
 
-    // A simple staircase to test autostep up/down.
-    let stairs_origin = Vec3::new(0.0, 0.0, -6.0);
-    let step_run: f32 = 0.55;
-    let step_rise: f32 = 0.4;
-    let step_count: u32 = 20;
-
-    // Half extents for each step: total height = 0.20, total depth = 0.50.
-    let step_half = Vec3::new(step_run * 0.5, step_rise * 0.5, 1.5);
-
-    for i in 0..step_count {
-        let ix = i as u32;
-        let fx = ix as f32;
-
-        // Center of the step in world space.
-        let cx = stairs_origin.x + fx * step_run;
-        let cy = stairs_origin.y + (fx * step_rise) + step_half.y;
-        let cz = stairs_origin.z;
-
+    // A simple staircase to test autostep up/down. The shared scenarios in `test_course` walk it.
+    for i in 0..test_course::STAIR_COUNT {
+        let (center, half_extents) = test_course::stair_step(i);
         WorldStatic::insert(
             ctx,
             WorldStatic {
                 id: 0,
-                translation: Vec3::new(cx, cy, cz),
+                instance_id,
+                translation: center.into(),
                 rotation: Quat::IDENTITY,
                 scale: Vec3::ONE,
-                shape: ColliderShape::Cuboid(step_half),
+                shape: ColliderShape::Cuboid(half_extents.into()),
+                surface_material: Some(SurfaceMaterial::Wood),
             },
         );
     }
 }
+
+/// The world statics of the viewer's instance.
+/// Primary key of `id`
+#[spacetimedb::view(name = world_static_view, public)]
+pub fn world_static_view(ctx: &ViewContext) -> Vec<WorldStatic> {
+    ctx.db
+        .world_static_tbl()
+        .instance_id()
+        .filter(view_instance_id(ctx))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pose(scale: Vec3) -> WorldStaticPose {
+        WorldStaticPose {
+            translation: Vec3::new(3.0, 1.0, 0.0),
+            rotation: Quat::IDENTITY,
+            scale,
+        }
+    }
+
+    #[test]
+    fn accepts_scaled_shapes_within_the_extent() {
+        let shape = ColliderShape::Cuboid(Vec3::ONE);
+        assert!(pose(Vec3::ONE).validate(&shape).is_ok());
+        assert!(pose(Vec3::new(4.0, 1.0, 2.0)).validate(&shape).is_ok());
+    }
+
+    #[test]
+    fn rejects_scales_growing_a_shape_past_the_extent() {
+        // Fine unscaled, far too large once scaled.
+        let shape = ColliderShape::Cuboid(Vec3::new(MAX_STATIC_EXTENT * 0.5, 1.0, 1.0));
+        assert!(pose(Vec3::ONE).validate(&shape).is_ok());
+        assert!(pose(Vec3::new(1.0, 4.0, 1.0)).validate(&shape).is_err());
+        assert!(pose(Vec3::ONE)
+            .validate(&ColliderShape::Sphere(1.0))
+            .is_ok());
+        assert!(pose(Vec3::new(1.0e6, 1.0, 1.0))
+            .validate(&ColliderShape::Sphere(1.0))
+            .is_err());
+    }
+
+    #[test]
+    fn rejects_degenerate_scales() {
+        let shape = ColliderShape::Cuboid(Vec3::ONE);
+        assert!(pose(Vec3::new(0.0, 1.0, 1.0)).validate(&shape).is_err());
+        assert!(pose(Vec3::new(f32::NAN, 1.0, 1.0))
+            .validate(&shape)
+            .is_err());
+    }
+}
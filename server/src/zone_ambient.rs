@@ -0,0 +1,79 @@
+use crate::{AdminIdentityRow, InstanceRow};
+use shared::InstanceId;
+use spacetimedb::{reducer, table, ReducerContext, SpacetimeType, Table, Timestamp};
+
+/// The acoustics of a zone, how sounds played in it reverberate.
+#[derive(SpacetimeType, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReverbProfile {
+    /// Open air, no reverb.
+    Outdoors,
+    /// A small enclosed space.
+    Room,
+    /// A large enclosed space with long, bright echoes.
+    Hall,
+    /// Rock walls, long and dark echoes.
+    Cave,
+}
+
+/// The soundscape of a zone (instance), a row per configured instance.
+///
+/// Public so clients switch soundscapes as soon as they enter a zone, zones without a row are
+/// silent. Purely cosmetic, nothing on the server reads these.
+#[table(name=zone_ambient_tbl, public)]
+pub struct ZoneAmbientRow {
+    #[primary_key]
+    pub instance_id: InstanceId,
+
+    /// The looping music track, `None` for no music.
+    pub music_track_id: Option<u16>,
+
+    /// The looping ambient sound set (wind, birds, dripping water...), `None` for none.
+    pub ambient_sound_set: Option<u16>,
+
+    pub reverb: ReverbProfile,
+
+    pub updated_at: Timestamp,
+}
+
+/// Sets the soundscape of an instance. Admin only.
+#[reducer]
+pub fn set_zone_ambient(
+    ctx: &ReducerContext,
+    instance_id: InstanceId,
+    music_track_id: Option<u16>,
+    ambient_sound_set: Option<u16>,
+    reverb: ReverbProfile,
+) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "set_zone_ambient")?;
+    if InstanceRow::find(&ctx.as_read_only(), instance_id).is_none() {
+        return Err("Unknown instance".into());
+    }
+
+    let row = ZoneAmbientRow {
+        instance_id,
+        music_track_id,
+        ambient_sound_set,
+        reverb,
+        updated_at: ctx.timestamp,
+    };
+    if ctx
+        .db
+        .zone_ambient_tbl()
+        .instance_id()
+        .find(instance_id)
+        .is_some()
+    {
+        ctx.db.zone_ambient_tbl().instance_id().update(row);
+    } else {
+        ctx.db.zone_ambient_tbl().insert(row);
+    }
+    Ok(())
+}
+
+/// Removes the soundscape of an instance, it falls silent. Admin only.
+#[reducer]
+pub fn clear_zone_ambient(ctx: &ReducerContext, instance_id: InstanceId) -> Result<(), String> {
+    AdminIdentityRow::require(ctx, "clear_zone_ambient")?;
+    ctx.db.zone_ambient_tbl().instance_id().delete(instance_id);
+    Ok(())
+}
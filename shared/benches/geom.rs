@@ -0,0 +1,65 @@
+//! Times the planar geometry helpers of `shared::geom` over a fixed cloud of points, the hot
+//! loops of avoidance, AI range checks and the movement step.
+//!
+//! Run with `cargo bench -p shared --bench geom`.
+
+use nalgebra::{Vector2, Vector3};
+use shared::{
+    Rng, clamp_planar, dist_sq_xz, penetration_normal, planar_distance_sq, soft_intrusion,
+};
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+const POINTS: usize = 1024;
+const ROUNDS: u32 = 200;
+
+fn points() -> Vec<Vector3<f32>> {
+    let mut rng = Rng::new(0x6e0, 0);
+    (0..POINTS)
+        .map(|_| {
+            let xz = rng.point_in_disc(50.0);
+            Vector3::new(xz.x, rng.range_f32(-2.0, 2.0), xz.y)
+        })
+        .collect()
+}
+
+/// Runs `f` over every pair of neighboring points `ROUNDS` times.
+fn time(name: &str, points: &[Vector3<f32>], mut f: impl FnMut(Vector3<f32>, Vector3<f32>) -> f32) {
+    let start = Instant::now();
+    let mut acc = 0.0;
+    for _ in 0..ROUNDS {
+        for pair in points.windows(2) {
+            acc += f(pair[0], pair[1]);
+        }
+    }
+    black_box(acc);
+    let elapsed = start.elapsed();
+    let calls = ROUNDS * (points.len() as u32 - 1);
+    println!(
+        "{name:<20} {elapsed:?} total, {:?}/call",
+        Duration::from_nanos((elapsed.as_nanos() / u128::from(calls)) as u64)
+    );
+}
+
+fn main() {
+    let points = points();
+    println!("{} points, {} rounds", points.len(), ROUNDS);
+
+    time("planar_distance_sq", &points, |a, b| {
+        planar_distance_sq(black_box(a.xz()), black_box(b.xz()))
+    });
+    time("dist_sq_xz", &points, |a, b| {
+        dist_sq_xz(black_box(a), black_box(b))
+    });
+    time("clamp_planar", &points, |a, b| {
+        clamp_planar(black_box(b.xz() - a.xz()), 5.0).x
+    });
+    time("soft_intrusion", &points, |a, b| {
+        soft_intrusion(black_box(dist_sq_xz(a, b).sqrt()), 10.0)
+    });
+    time("penetration_normal", &points, |a, b| {
+        penetration_normal(black_box(a.xz()), black_box(b.xz()), Vector2::x()).1
+    });
+}
@@ -0,0 +1,76 @@
+//! Compares rebuilding the static query world every tick against building it once and reusing
+//! it, which is what the server's version-stamped cache does while the world doesn't change.
+//!
+//! Run with `cargo bench -p shared --bench static_query_world`.
+
+use nalgebra::{UnitQuaternion, Vector3};
+use rapier3d::prelude::QueryFilter;
+use shared::{
+    ColliderShapeDef, StaticQueryWorld, WorldStaticDef, build_static_query_world, ground_static_id,
+};
+use std::{hint::black_box, time::Instant};
+
+const TICKS: u32 = 500;
+const DT: f32 = 1.0 / 30.0;
+/// Roughly a small zone: ground plane plus a grid of boxes.
+const GRID: i32 = 32;
+
+fn world_defs() -> Vec<WorldStaticDef> {
+    let mut defs = vec![WorldStaticDef {
+        id: 0,
+        translation: Vector3::zeros(),
+        rotation: UnitQuaternion::identity(),
+        shape: ColliderShapeDef::Plane {
+            offset_along_normal: 0.0,
+        },
+    }];
+    for x in -GRID / 2..GRID / 2 {
+        for z in -GRID / 2..GRID / 2 {
+            defs.push(WorldStaticDef {
+                id: defs.len() as u64,
+                translation: Vector3::new(x as f32 * 4.0, 0.5, z as f32 * 4.0),
+                rotation: UnitQuaternion::identity(),
+                shape: ColliderShapeDef::Cuboid {
+                    half_extents: Vector3::new(1.0, 0.5, 1.0),
+                },
+            });
+        }
+    }
+    defs
+}
+
+/// One ground probe per tick stands in for the scene queries a tick does.
+fn probe(world: &StaticQueryWorld) -> Option<u64> {
+    let pipeline = world.as_query_pipeline(QueryFilter::only_fixed());
+    ground_static_id(world, &pipeline, Vector3::new(0.0, 2.0, 0.0), 3.0)
+}
+
+fn main() {
+    let defs = world_defs();
+
+    let start = Instant::now();
+    for _ in 0..TICKS {
+        let world = build_static_query_world(defs.iter().cloned(), DT);
+        black_box(probe(&world));
+    }
+    let rebuild = start.elapsed();
+
+    let start = Instant::now();
+    let world = build_static_query_world(defs.iter().cloned(), DT);
+    for _ in 0..TICKS {
+        black_box(probe(&world));
+    }
+    let cached = start.elapsed();
+
+    println!("{} colliders, {} ticks", defs.len(), TICKS);
+    println!(
+        "rebuild every tick: {:?} total, {:?}/tick",
+        rebuild,
+        rebuild / TICKS
+    );
+    println!(
+        "cached world:       {:?} total, {:?}/tick",
+        cached,
+        cached / TICKS
+    );
+}
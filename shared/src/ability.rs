@@ -0,0 +1,28 @@
+//! Ability numbers both sides need: the server casts with them, the client aims and shows
+//! cooldowns with them. Ability ids share one id space, they also key the server's cooldowns.
+
+/// An AoE ability cast at a point on the ground, what the client's targeting reticle shows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GroundTargetAbility {
+    pub id: u16,
+    /// How far from the caster the ability can be centered (meters).
+    pub range: f32,
+    pub radius: f32,
+}
+
+/// Ground slam, a burst at a point on the ground that slows.
+pub const GROUND_SLAM: GroundTargetAbility = GroundTargetAbility {
+    id: 1,
+    range: 20.0,
+    radius: 4.0,
+};
+
+/// Upheaval, launches the actors at a point on the ground.
+pub const UPHEAVAL: GroundTargetAbility = GroundTargetAbility {
+    id: 3,
+    range: 15.0,
+    radius: 3.0,
+};
+
+/// The charge at the actor's target.
+pub const CHARGE_ABILITY_ID: u16 = 5;
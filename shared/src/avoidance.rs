@@ -0,0 +1,96 @@
+//! Local avoidance steering: moving actors bend their heading around nearby actors instead of
+//! walking into them.
+//!
+//! Boids style separation weighted towards neighbors ahead. Only the direction of travel changes,
+//! the movement step (see [`crate::movement_step_actor`]) still does the moving and collision.
+
+use crate::{penetration_normal, planar_distance_sq, soft_intrusion};
+use nalgebra::Vector2;
+
+/// Extra distance (meters) beyond touching capsules at which neighbors start to steer an actor.
+pub const AVOIDANCE_LOOKAHEAD: f32 = 1.5;
+
+/// How strongly avoidance bends the desired direction, relative to it.
+const AVOIDANCE_WEIGHT: f32 = 1.5;
+
+/// Neighbors behind the actor still push a little so overlapping actors separate.
+const BEHIND_WEIGHT: f32 = 0.25;
+
+/// A nearby actor to steer around.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AvoidanceNeighbor {
+    pub position: Vector2<f32>,
+    pub radius: f32,
+}
+
+/// Steers the unit `desired` direction of an actor at `position` away from its `neighbors`.
+///
+/// Each neighbor within its touching distance plus [`AVOIDANCE_LOOKAHEAD`] pushes the actor away,
+/// stronger the closer it is and the more directly ahead. Returns a unit direction, `desired` when
+/// nothing is in the way or the pushes cancel it out.
+pub fn avoidance_direction(
+    position: Vector2<f32>,
+    radius: f32,
+    desired: Vector2<f32>,
+    neighbors: impl IntoIterator<Item = AvoidanceNeighbor>,
+) -> Vector2<f32> {
+    let mut push = Vector2::zeros();
+    for neighbor in neighbors {
+        let range = radius + neighbor.radius + AVOIDANCE_LOOKAHEAD;
+        if planar_distance_sq(position, neighbor.position) >= range * range {
+            continue;
+        }
+        // Exactly on top of each other, sidestep to the right of the desired direction.
+        let (away_dir, distance) = penetration_normal(
+            position,
+            neighbor.position,
+            Vector2::new(-desired.y, desired.x),
+        );
+        let ahead = (-away_dir).dot(&desired);
+        let weight = if ahead > 0.0 { ahead } else { BEHIND_WEIGHT };
+        push += away_dir * soft_intrusion(distance, range) * weight;
+    }
+
+    if push == Vector2::zeros() {
+        return desired;
+    }
+    (desired + push * AVOIDANCE_WEIGHT)
+        .try_normalize(1.0e-6)
+        .unwrap_or(desired)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn neighbor(x: f32, z: f32) -> AvoidanceNeighbor {
+        AvoidanceNeighbor {
+            position: Vector2::new(x, z),
+            radius: 0.3,
+        }
+    }
+
+    #[test]
+    fn unobstructed_keeps_desired_direction() {
+        let desired = Vector2::new(0.0, -1.0);
+        let steered = avoidance_direction(Vector2::zeros(), 0.3, desired, [neighbor(10.0, 10.0)]);
+        assert_eq!(steered, desired);
+    }
+
+    #[test]
+    fn steers_around_neighbor_ahead() {
+        let desired = Vector2::new(0.0, -1.0);
+        // Slightly to the right of the straight line ahead, so the actor bends left.
+        let steered = avoidance_direction(Vector2::zeros(), 0.3, desired, [neighbor(0.2, -1.0)]);
+        assert!((steered.norm() - 1.0).abs() < 1.0e-5);
+        assert!(steered.x < 0.0);
+        assert!(steered.y < 0.0, "still heading forward: {steered:?}");
+    }
+
+    #[test]
+    fn coincident_neighbor_sidesteps() {
+        let desired = Vector2::new(1.0, 0.0);
+        let steered = avoidance_direction(Vector2::zeros(), 0.3, desired, [neighbor(0.0, 0.0)]);
+        assert!(steered.y.abs() > 0.1);
+    }
+}
@@ -0,0 +1,152 @@
+/// Defines a `Copy` newtype over an unsigned integer where each named constant is a single bit.
+///
+/// Tables store the raw integer (`bits()`), the newtype only exists to give those bits names and
+/// convenient set operations on both the server and the client.
+///
+/// ```
+/// shared::define_bitmask_flags! {
+///     /// Example flags
+///     pub struct ExampleFlags: u8 {
+///         A = 0,
+///         B = 1,
+///     }
+/// }
+///
+/// let flags = ExampleFlags::A | ExampleFlags::B;
+/// assert!(flags.contains(ExampleFlags::B));
+/// ```
+#[macro_export]
+macro_rules! define_bitmask_flags {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident: $ty:ty {
+            $(
+                $(#[$flag_meta:meta])*
+                $flag:ident = $bit:expr
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+        $vis struct $name($ty);
+
+        #[allow(dead_code)]
+        impl $name {
+            $(
+                $(#[$flag_meta])*
+                pub const $flag: Self = Self(1 << $bit);
+            )*
+
+            pub const NONE: Self = Self(0);
+
+            pub const fn from_bits(bits: $ty) -> Self {
+                Self(bits)
+            }
+
+            pub const fn bits(self) -> $ty {
+                self.0
+            }
+
+            pub const fn is_empty(self) -> bool {
+                self.0 == 0
+            }
+
+            /// Are all of the bits in `other` set?
+            pub const fn contains(self, other: Self) -> bool {
+                self.0 & other.0 == other.0
+            }
+
+            /// Is any of the bits in `other` set?
+            pub const fn intersects(self, other: Self) -> bool {
+                self.0 & other.0 != 0
+            }
+
+            pub fn insert(&mut self, other: Self) {
+                self.0 |= other.0;
+            }
+
+            pub fn remove(&mut self, other: Self) {
+                self.0 &= !other.0;
+            }
+
+            pub fn set(&mut self, other: Self, value: bool) {
+                if value {
+                    self.insert(other);
+                } else {
+                    self.remove(other);
+                }
+            }
+        }
+
+        impl ::core::ops::BitOr for $name {
+            type Output = Self;
+            fn bitor(self, rhs: Self) -> Self {
+                Self(self.0 | rhs.0)
+            }
+        }
+
+        impl ::core::ops::BitOrAssign for $name {
+            fn bitor_assign(&mut self, rhs: Self) {
+                self.0 |= rhs.0;
+            }
+        }
+
+        impl ::core::ops::BitAnd for $name {
+            type Output = Self;
+            fn bitand(self, rhs: Self) -> Self {
+                Self(self.0 & rhs.0)
+            }
+        }
+
+        impl From<$ty> for $name {
+            fn from(bits: $ty) -> Self {
+                Self(bits)
+            }
+        }
+
+        impl From<$name> for $ty {
+            fn from(flags: $name) -> Self {
+                flags.0
+            }
+        }
+    };
+}
+
+define_bitmask_flags! {
+    /// State flags replicated on every actor.
+    pub struct ActorFlags: u64 {
+        /// Cannot take damage.
+        INVULNERABLE = 0,
+        /// Recently dealt or received damage.
+        IN_COMBAT = 1,
+        /// Hidden from other actors, though still replicated for visual effects.
+        STEALTHED = 2,
+        /// The owning player lost connection but the actor is still in the world.
+        LINK_DEAD = 3,
+        /// Filtered from all views except the actor's own.
+        GM_INVISIBLE = 4,
+        /// Died and left a corpse, can't move, act or take damage until resurrected.
+        DEAD = 5,
+        /// GM fly mode, moves through the air without gravity, see `set_fly_mode`.
+        FLYING = 6,
+        /// Launched into the air, move intents are ignored until it lands, see `launch_actor`.
+        AIRBORNE = 7,
+        /// A despawned monster parked for reuse, out of the world until its next spawn. Always
+        /// with `DEAD`.
+        POOLED = 8,
+        /// The owning player has been idle past the AFK timeout, see the server's `ActivityRow`.
+        AFK = 9,
+    }
+}
+
+define_bitmask_flags! {
+    /// Onboarding steps a character has done, each hides its tutorial hint for good.
+    pub struct TutorialFlags: u32 {
+        /// Asked to move for the first time.
+        FIRST_MOVE = 0,
+        /// Killed another actor.
+        FIRST_KILL = 1,
+        /// Reached its second level.
+        FIRST_LEVEL = 2,
+    }
+}
@@ -19,8 +19,9 @@
 //!
 //! # AOI
 //! `get_aoi_block` returns a 3x3 block around a center cell, using wrapping arithmetic
-//! to match the prior behavior (fast, branchless). If you need clamped-at-edges behavior,
-//! add a separate helper.
+//! to match the prior behavior (fast, branchless). Wrapping pulls in cells from the opposite
+//! world edge, so views and anything else deciding who sees whom use the clamped
+//! `get_aoi_block_clamped` / `is_in_aoi_block` instead.
 
 use crate::{
     CellId,
@@ -97,7 +98,9 @@ pub fn get_aoi_block(cell_id: CellId) -> [CellId; 9] {
     let z_north = z.wrapping_add(1);
     let z_south = z.wrapping_sub(1);
 
-    let pack = |gx: u16, gz: u16| -> CellId { gx.wrapping_mul(GRID_SIDE).wrapping_add(gz) };
+    // `gz` wraps past the u16 range, bring it back into the grid before packing.
+    let pack =
+        |gx: u16, gz: u16| -> CellId { gx.wrapping_mul(GRID_SIDE).wrapping_add(gz % GRID_SIDE) };
 
     [
         pack(x_west, z_north), // NW
@@ -112,6 +115,54 @@ pub fn get_aoi_block(cell_id: CellId) -> [CellId; 9] {
     ]
 }
 
+/// Returns the cells of the 3x3 AOI block around `cell_id` that exist, in the same order as
+/// [`get_aoi_block`]. Nothing wraps: a cell on the world edge has a 2x3 block, a corner cell a
+/// 2x2 one.
+pub fn get_aoi_block_clamped(cell_id: CellId) -> impl Iterator<Item = CellId> {
+    let (x, z) = decode_cell_coords(cell_id);
+    let max = max_cell_coord();
+    let neighbor = move |dx: i8, dz: i8| {
+        let gx = x.checked_add_signed(dx as i16).filter(|&gx| gx <= max)?;
+        let gz = z.checked_add_signed(dz as i16).filter(|&gz| gz <= max)?;
+        Some(gx * GRID_SIDE + gz)
+    };
+
+    // (dx, dz) in `get_aoi_block` order, NW to SE.
+    [
+        (-1, 1),
+        (0, 1),
+        (1, 1),
+        (-1, 0),
+        (0, 0),
+        (1, 0),
+        (-1, -1),
+        (0, -1),
+        (1, -1),
+    ]
+    .into_iter()
+    .filter_map(move |(dx, dz)| neighbor(dx, dz))
+}
+
+/// Is `cell_id` within the clamped AOI block around `center`, see [`get_aoi_block_clamped`]?
+#[inline]
+pub fn is_in_aoi_block(center: CellId, cell_id: CellId) -> bool {
+    let (cx, cz) = decode_cell_coords(center);
+    let (x, z) = decode_cell_coords(cell_id);
+    cx.abs_diff(x) <= 1 && cz.abs_diff(z) <= 1
+}
+
+/// Returns every cell overlapped by the planar (XZ) circle at `(x, z)` with `radius` meters.
+///
+/// Cells are clamped to the grid (no wrapping), so circles near the world edge only return the
+/// cells that exist.
+pub fn cells_in_radius(x: f32, z: f32, radius: f32) -> impl Iterator<Item = CellId> {
+    let radius = radius.max(0.0);
+    let (min_x, min_z) = decode_cell_coords(encode_cell_id(x - radius, z - radius));
+    let (max_x, max_z) = decode_cell_coords(encode_cell_id(x + radius, z + radius));
+
+    (min_x..=max_x).flat_map(move |gx| (min_z..=max_z).map(move |gz| gx * GRID_SIDE + gz))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -203,6 +254,108 @@ mod tests {
         assert_eq!(block[6], expected_sw); // SW
     }
 
+    #[test]
+    fn clamped_aoi_block_matches_wrapping_in_the_interior() {
+        for &(gx, gz) in &[(1, 1), (42, 133), (128, 128), (254, 254)] {
+            let center = gx * GRID_SIDE + gz;
+            let clamped: Vec<_> = get_aoi_block_clamped(center).collect();
+            assert_eq!(clamped, get_aoi_block(center).to_vec());
+        }
+    }
+
+    #[test]
+    fn clamped_aoi_block_golden_edge_cases() {
+        let cell = |gx: u16, gz: u16| gx * GRID_SIDE + gz;
+        let max = max_cell_coord();
+        let cases: &[((u16, u16), &[(u16, u16)])] = &[
+            // South-west corner, only the north and east neighbors exist.
+            ((0, 0), &[(0, 1), (1, 1), (0, 0), (1, 0)]),
+            // North-east corner.
+            (
+                (max, max),
+                &[
+                    (max - 1, max),
+                    (max, max),
+                    (max - 1, max - 1),
+                    (max, max - 1),
+                ],
+            ),
+            // West edge.
+            ((0, 7), &[(0, 8), (1, 8), (0, 7), (1, 7), (0, 6), (1, 6)]),
+            // North edge.
+            (
+                (7, max),
+                &[
+                    (6, max),
+                    (7, max),
+                    (8, max),
+                    (6, max - 1),
+                    (7, max - 1),
+                    (8, max - 1),
+                ],
+            ),
+        ];
+        for ((gx, gz), expected) in cases {
+            let block: Vec<_> = get_aoi_block_clamped(cell(*gx, *gz)).collect();
+            let expected: Vec<_> = expected.iter().map(|&(x, z)| cell(x, z)).collect();
+            assert_eq!(block, expected, "block around ({gx}, {gz})");
+        }
+    }
+
+    #[test]
+    fn clamped_aoi_block_never_leaks_across_the_world_edge() {
+        let max = max_cell_coord();
+        let border = (0..=max).flat_map(|i| [(0, i), (max, i), (i, 0), (i, max)]);
+        for (gx, gz) in border {
+            let center = gx * GRID_SIDE + gz;
+            for cell_id in get_aoi_block_clamped(center) {
+                let (x, z) = decode_cell_coords(cell_id);
+                assert!(
+                    gx.abs_diff(x) <= 1 && gz.abs_diff(z) <= 1,
+                    "({x}, {z}) leaked into the block around ({gx}, {gz})"
+                );
+                assert!(is_in_aoi_block(center, cell_id));
+            }
+        }
+    }
+
+    #[test]
+    fn is_in_aoi_block_does_not_wrap() {
+        let max = max_cell_coord();
+        let corner = 0;
+        assert!(is_in_aoi_block(corner, GRID_SIDE + 1));
+        assert!(!is_in_aoi_block(corner, max * GRID_SIDE));
+        assert!(!is_in_aoi_block(corner, max));
+        assert!(!is_in_aoi_block(corner, max * GRID_SIDE + max));
+        // The wrapping block does contain the far side, this is what the clamped one avoids.
+        assert!(get_aoi_block(corner).contains(&(max * GRID_SIDE + max)));
+
+        let center = 42 * GRID_SIDE + 133;
+        for cell_id in [0, center - 2, center + 2 * GRID_SIDE, u16::MAX] {
+            assert_eq!(
+                is_in_aoi_block(center, cell_id),
+                get_aoi_block_clamped(center).any(|id| id == cell_id)
+            );
+        }
+        assert!(get_aoi_block_clamped(center).all(|id| is_in_aoi_block(center, id)));
+    }
+
+    #[test]
+    fn cells_in_radius_covers_neighbors_without_wrapping() {
+        // A small circle in the middle of a cell only touches that cell.
+        let (cx, cz) = decode_cell_min_corner(encode_cell_id(0.0, 0.0));
+        let mid = (cx + CELL_SIZE * 0.5, cz + CELL_SIZE * 0.5);
+        let cells: Vec<_> = cells_in_radius(mid.0, mid.1, 1.0).collect();
+        assert_eq!(cells, vec![encode_cell_id(mid.0, mid.1)]);
+
+        // A circle on a corner touches the four cells sharing it.
+        assert_eq!(cells_in_radius(cx, cz, 1.0).count(), 4);
+
+        // At the grid corner nothing wraps around to the far side.
+        let edge: Vec<_> = cells_in_radius(-WORLD_OFFSET, -WORLD_OFFSET, 1.0).collect();
+        assert_eq!(edge, vec![0]);
+    }
+
     #[test]
     fn world_span_and_offset_are_consistent() {
         // WORLD_OFFSET should be half the world span for centered mapping.
@@ -0,0 +1,217 @@
+//! The straight-line sweep of a charge: an actor rushing at a target in a single move, stopped
+//! by the first wall in the way.
+//!
+//! The line is walked in short steps with the same character controller as regular movement
+//! (see [`crate::movement_kcc`]), so a charge climbs the steps and follows the slopes a walk
+//! would. A step that can't make most of its way along the line is blocked, the charge ends
+//! there instead of sliding along the wall. So is a step that leaves the ground or drops further
+//! than a step up, the charge stops at the edge of a pit or ledge instead of gliding over it.
+
+use crate::{MOVEMENT_TICK_INTERVAL_SECS, autostep_max_height, math::yaw::yaw_from_xz};
+use nalgebra::{Isometry3, UnitQuaternion, Vector2, Vector3};
+use rapier3d::{
+    control::{CharacterAutostep, CharacterLength, KinematicCharacterController},
+    prelude::{Capsule, QueryPipeline},
+};
+
+/// Fraction of a step's length it has to move along the line to not count as blocked.
+const MIN_STEP_PROGRESS: f32 = 0.9;
+
+/// Longest step (meters) of the sweep, shorter for thin capsules so none tunnels through a wall.
+const MAX_STEP_LENGTH: f32 = 0.5;
+
+/// Result of [`charge_sweep`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChargeSweep {
+    /// Where the capsule's center ended up.
+    pub translation: Vector3<f32>,
+    /// The direction of the line, see [`crate::math::yaw`].
+    pub yaw: f32,
+    /// A static, or the lack of ground ahead, stopped the capsule before the end of the line.
+    pub blocked: bool,
+}
+
+/// Sweeps a Y-aligned capsule centered at `from` along the planar line to `to`, returns where it
+/// stops.
+///
+/// **Performance & Cost**: a character controller move per step, at most `MAX_STEP_LENGTH`
+/// meters each
+pub fn charge_sweep(
+    kcc: &KinematicCharacterController,
+    query_pipeline: &QueryPipeline,
+    from: Vector3<f32>,
+    to: Vector2<f32>,
+    capsule_radius: f32,
+    capsule_half_height: f32,
+    yaw: f32,
+) -> ChargeSweep {
+    let line = to - from.xz();
+    let length = line.norm();
+    let Some(direction) = line.try_normalize(f32::EPSILON) else {
+        return ChargeSweep {
+            translation: from,
+            yaw,
+            blocked: false,
+        };
+    };
+    let yaw = yaw_from_xz(direction).unwrap_or(yaw);
+
+    let kcc = KinematicCharacterController {
+        autostep: kcc.autostep.map(|autostep| CharacterAutostep {
+            max_height: CharacterLength::Absolute(autostep_max_height(
+                capsule_radius,
+                capsule_half_height,
+            )),
+            ..autostep
+        }),
+        ..*kcc
+    };
+    let capsule = Capsule::new_y(capsule_half_height, capsule_radius);
+    let rotation = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), yaw);
+    let max_step = capsule_radius.min(MAX_STEP_LENGTH);
+    let max_drop = autostep_max_height(capsule_radius, capsule_half_height);
+
+    let mut translation = from;
+    let mut travelled = 0.0;
+    while length - travelled > f32::EPSILON {
+        let step = max_step.min(length - travelled);
+        let desired = Vector3::new(direction.x, 0.0, direction.y) * step;
+        let correction = kcc.move_shape(
+            MOVEMENT_TICK_INTERVAL_SECS,
+            query_pipeline,
+            &capsule,
+            &Isometry3::from_parts(translation.into(), rotation),
+            desired,
+            |_| {},
+        );
+        // Off the ground the step isn't taken, the charge ends on the last supported point.
+        if !correction.grounded || -correction.translation.y > max_drop {
+            return ChargeSweep {
+                translation,
+                yaw,
+                blocked: true,
+            };
+        }
+        translation += correction.translation;
+        let progress = correction.translation.xz().dot(&direction);
+        if progress < step * MIN_STEP_PROGRESS {
+            return ChargeSweep {
+                translation,
+                yaw,
+                blocked: true,
+            };
+        }
+        travelled += step;
+    }
+    ChargeSweep {
+        translation,
+        yaw,
+        blocked: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ColliderShapeDef, StaticQueryWorld, WorldStaticDef, build_static_query_world, movement_kcc,
+    };
+    use rapier3d::prelude::QueryFilter;
+
+    const RADIUS: f32 = 0.3;
+    const HALF_HEIGHT: f32 = 0.9;
+
+    /// A ground plane and, when given, a wall across the -Z axis at `wall_z`.
+    fn world(wall_z: Option<f32>) -> StaticQueryWorld {
+        let ground = WorldStaticDef {
+            id: 0,
+            translation: Vector3::zeros(),
+            rotation: UnitQuaternion::identity(),
+            shape: ColliderShapeDef::Plane {
+                offset_along_normal: 0.0,
+            },
+        };
+        let wall = wall_z.map(|z| WorldStaticDef {
+            id: 1,
+            translation: Vector3::new(0.0, 2.0, z),
+            rotation: UnitQuaternion::identity(),
+            shape: ColliderShapeDef::Cuboid {
+                half_extents: Vector3::new(5.0, 2.0, 0.2),
+            },
+        });
+        build_static_query_world(
+            [ground].into_iter().chain(wall),
+            MOVEMENT_TICK_INTERVAL_SECS,
+        )
+    }
+
+    /// Two floors with their tops at `y = 0`, split by a pit from `z = -3` to `z = -5`.
+    fn gap_world() -> StaticQueryWorld {
+        let floor = |id, z| WorldStaticDef {
+            id,
+            translation: Vector3::new(0.0, -0.5, z),
+            rotation: UnitQuaternion::identity(),
+            shape: ColliderShapeDef::Cuboid {
+                half_extents: Vector3::new(5.0, 0.5, 2.5),
+            },
+        };
+        build_static_query_world(
+            [floor(0, -0.5), floor(1, -7.5)],
+            MOVEMENT_TICK_INTERVAL_SECS,
+        )
+    }
+
+    fn sweep(world: &StaticQueryWorld, to: Vector2<f32>) -> ChargeSweep {
+        let query_pipeline = world.as_query_pipeline(QueryFilter::only_fixed());
+        let from = Vector3::new(0.0, HALF_HEIGHT + RADIUS + 0.05, 0.0);
+        charge_sweep(
+            &movement_kcc(),
+            &query_pipeline,
+            from,
+            to,
+            RADIUS,
+            HALF_HEIGHT,
+            0.0,
+        )
+    }
+
+    #[test]
+    fn open_ground_reaches_the_end() {
+        let world = world(None);
+        let result = sweep(&world, Vector2::new(4.0, -6.0));
+        assert!(!result.blocked);
+        assert!((result.translation.xz() - Vector2::new(4.0, -6.0)).norm() < 0.05);
+    }
+
+    #[test]
+    fn wall_stops_the_charge_in_front_of_it() {
+        let world = world(Some(-4.0));
+        let result = sweep(&world, Vector2::new(0.0, -8.0));
+        assert!(result.blocked);
+        // Against the wall's near face, not through it.
+        let near_face = -4.0 + 0.2 + RADIUS;
+        assert!(result.translation.z >= near_face - 0.05);
+        assert!(result.translation.z <= near_face + MAX_STEP_LENGTH);
+        assert!(result.translation.x.abs() < 0.05);
+    }
+
+    #[test]
+    fn gap_stops_the_charge_at_its_edge() {
+        let world = gap_world();
+        let result = sweep(&world, Vector2::new(0.0, -8.0));
+        assert!(result.blocked);
+        // On the near side of the pit, close to its edge, still standing on the floor.
+        assert!(result.translation.z >= -3.0 - RADIUS - 0.05);
+        assert!(result.translation.z <= -3.0 + 1.0);
+        assert!(result.translation.y > HALF_HEIGHT);
+    }
+
+    #[test]
+    fn zero_length_line_stays_put() {
+        let world = world(None);
+        let result = sweep(&world, Vector2::zeros());
+        assert!(!result.blocked);
+        assert_eq!(result.yaw, 0.0);
+        assert_eq!(result.translation.xz(), Vector2::zeros());
+    }
+}
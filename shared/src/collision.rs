@@ -67,6 +67,157 @@ pub enum ColliderShapeDef {
     },
 }
 
+impl WorldStaticDef {
+    /// The definition of a static posed with `translation`, `rotation` and a per-axis `scale`,
+    /// applied to `shape` by [`ColliderShapeDef::scaled`].
+    pub fn new(
+        id: u64,
+        translation: Vector<f32>,
+        rotation: UnitQuaternion<f32>,
+        scale: Vector<f32>,
+        shape: ColliderShapeDef,
+    ) -> Self {
+        Self {
+            id,
+            translation,
+            rotation,
+            shape: shape.scaled(scale),
+        }
+    }
+}
+
+impl ColliderShapeDef {
+    /// The shape with a per-axis `scale` applied, the one rule both the server and the client
+    /// build colliders with:
+    /// - half-extents and half-heights scale with their own axis,
+    /// - radii around Y scale with the larger of X and Z, a sphere's with the largest axis, so
+    ///   the collider never ends up thinner than the scaled shape,
+    /// - border radii scale with the smallest axis, so the rounding never outgrows the shape,
+    /// - planes are infinite, scale doesn't change them.
+    ///
+    /// Scales are taken as absolute values, mirroring doesn't change a collider.
+    pub fn scaled(&self, scale: Vector<f32>) -> Self {
+        let scale = scale.abs();
+        let radial = scale.x.max(scale.z);
+        let smallest = scale.min();
+        match *self {
+            Self::Plane {
+                offset_along_normal,
+            } => Self::Plane {
+                offset_along_normal,
+            },
+            Self::Cuboid { half_extents } => Self::Cuboid {
+                half_extents: half_extents.component_mul(&scale),
+            },
+            Self::Sphere { radius } => Self::Sphere {
+                radius: radius * scale.max(),
+            },
+            Self::CapsuleY {
+                radius,
+                half_height,
+            } => Self::CapsuleY {
+                radius: radius * radial,
+                half_height: half_height * scale.y,
+            },
+            Self::CylinderY {
+                radius,
+                half_height,
+            } => Self::CylinderY {
+                radius: radius * radial,
+                half_height: half_height * scale.y,
+            },
+            Self::ConeY {
+                radius,
+                half_height,
+            } => Self::ConeY {
+                radius: radius * radial,
+                half_height: half_height * scale.y,
+            },
+            Self::RoundCuboid {
+                half_extents,
+                border_radius,
+            } => Self::RoundCuboid {
+                half_extents: half_extents.component_mul(&scale),
+                border_radius: border_radius * smallest,
+            },
+            Self::RoundCylinderY {
+                radius,
+                half_height,
+                border_radius,
+            } => Self::RoundCylinderY {
+                radius: radius * radial,
+                half_height: half_height * scale.y,
+                border_radius: border_radius * smallest,
+            },
+            Self::RoundConeY {
+                radius,
+                half_height,
+                border_radius,
+            } => Self::RoundConeY {
+                radius: radius * radial,
+                half_height: half_height * scale.y,
+                border_radius: border_radius * smallest,
+            },
+        }
+    }
+}
+
+/// Maps a `ColliderShape` table enum (the server's type or the client's generated binding, they
+/// share variant and field names) to its unscaled [`ColliderShapeDef`]. The server and the client
+/// each have their own type, this keeps the mapping in one place.
+///
+/// ```ignore
+/// let shape = shared::collider_shape_def!(ColliderShape, &row.shape);
+/// ```
+#[macro_export]
+macro_rules! collider_shape_def {
+    ($shape_ty:ident, $shape:expr) => {
+        match $shape {
+            $shape_ty::Plane(offset_along_normal) => $crate::ColliderShapeDef::Plane {
+                offset_along_normal: *offset_along_normal,
+            },
+            $shape_ty::Cuboid(half_extents) => $crate::ColliderShapeDef::Cuboid {
+                half_extents: ::nalgebra::Vector3::new(
+                    half_extents.x,
+                    half_extents.y,
+                    half_extents.z,
+                ),
+            },
+            $shape_ty::Sphere(radius) => $crate::ColliderShapeDef::Sphere { radius: *radius },
+            $shape_ty::CapsuleY(capsule) => $crate::ColliderShapeDef::CapsuleY {
+                radius: capsule.radius,
+                half_height: capsule.half_height,
+            },
+            $shape_ty::Cylinder(cylinder) => $crate::ColliderShapeDef::CylinderY {
+                radius: cylinder.radius,
+                half_height: cylinder.half_height,
+            },
+            $shape_ty::Cone(cone) => $crate::ColliderShapeDef::ConeY {
+                radius: cone.radius,
+                half_height: cone.half_height,
+            },
+            $shape_ty::RoundCuboid(round_cuboid) => $crate::ColliderShapeDef::RoundCuboid {
+                half_extents: ::nalgebra::Vector3::new(
+                    round_cuboid.half_extents.x,
+                    round_cuboid.half_extents.y,
+                    round_cuboid.half_extents.z,
+                ),
+                border_radius: round_cuboid.border_radius,
+            },
+            $shape_ty::RoundCylinder(round_cylinder) => $crate::ColliderShapeDef::RoundCylinderY {
+                radius: round_cylinder.radius,
+                half_height: round_cylinder.half_height,
+                border_radius: round_cylinder.border_radius,
+            },
+            $shape_ty::RoundCone(round_cone) => $crate::ColliderShapeDef::RoundConeY {
+                radius: round_cone.radius,
+                half_height: round_cone.half_height,
+                border_radius: round_cone.border_radius,
+            },
+        }
+    };
+}
+
 /// Build a Rapier collider from a `WorldStaticDef`.
 ///
 /// This uses the pose stored on the rigid-body as the collider parent transform.
@@ -139,3 +290,119 @@ pub fn collider_from_def(def: &WorldStaticDef) -> Collider {
         } => ColliderBuilder::round_cone(*half_height, *radius, *border_radius).build(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Stand-in for the `ColliderShape` table enums, with the same variant and field names.
+    #[allow(dead_code)]
+    enum ColliderShape {
+        Plane(f32),
+        Cuboid(Vector<f32>),
+        Sphere(f32),
+        CapsuleY(Dims),
+        Cylinder(Dims),
+        Cone(Dims),
+        RoundCuboid(RoundBox),
+        RoundCylinder(RoundDims),
+        RoundCone(RoundDims),
+    }
+
+    struct Dims {
+        radius: f32,
+        half_height: f32,
+    }
+
+    struct RoundBox {
+        half_extents: Vector<f32>,
+        border_radius: f32,
+    }
+
+    struct RoundDims {
+        radius: f32,
+        half_height: f32,
+        border_radius: f32,
+    }
+
+    #[test]
+    fn maps_table_shapes() {
+        let cuboid = ColliderShape::Cuboid(Vector::new(1.0, 2.0, 3.0));
+        assert!(matches!(
+            crate::collider_shape_def!(ColliderShape, &cuboid),
+            ColliderShapeDef::Cuboid { half_extents } if half_extents == Vector::new(1.0, 2.0, 3.0)
+        ));
+
+        let round_cone = ColliderShape::RoundCone(RoundDims {
+            radius: 1.0,
+            half_height: 2.0,
+            border_radius: 0.1,
+        });
+        assert!(matches!(
+            crate::collider_shape_def!(ColliderShape, &round_cone),
+            ColliderShapeDef::RoundConeY {
+                radius: 1.0,
+                half_height: 2.0,
+                border_radius: 0.1,
+            }
+        ));
+    }
+
+    #[test]
+    fn scale_applies_per_axis_to_half_extents() {
+        let shape = ColliderShapeDef::Cuboid {
+            half_extents: Vector::new(1.0, 2.0, 3.0),
+        };
+        let ColliderShapeDef::Cuboid { half_extents } = shape.scaled(Vector::new(2.0, 0.5, -1.0))
+        else {
+            panic!("shape changed kind");
+        };
+        assert_eq!(half_extents, Vector::new(2.0, 1.0, 3.0));
+    }
+
+    #[test]
+    fn scale_applies_widest_axis_to_radii_and_smallest_to_borders() {
+        let shape = ColliderShapeDef::RoundCylinderY {
+            radius: 1.0,
+            half_height: 1.0,
+            border_radius: 0.2,
+        };
+        let ColliderShapeDef::RoundCylinderY {
+            radius,
+            half_height,
+            border_radius,
+        } = shape.scaled(Vector::new(2.0, 3.0, 0.5))
+        else {
+            panic!("shape changed kind");
+        };
+        assert_eq!((radius, half_height, border_radius), (2.0, 3.0, 0.1));
+
+        let ColliderShapeDef::Sphere { radius } =
+            ColliderShapeDef::Sphere { radius: 1.0 }.scaled(Vector::new(1.0, 4.0, 2.0))
+        else {
+            panic!("shape changed kind");
+        };
+        assert_eq!(radius, 4.0);
+    }
+
+    #[test]
+    fn unit_scale_keeps_the_shape() {
+        let def = WorldStaticDef::new(
+            7,
+            Vector::zeros(),
+            UnitQuaternion::identity(),
+            Vector::repeat(1.0),
+            ColliderShapeDef::CapsuleY {
+                radius: 0.5,
+                half_height: 1.5,
+            },
+        );
+        assert!(matches!(
+            def.shape,
+            ColliderShapeDef::CapsuleY {
+                radius: 0.5,
+                half_height: 1.5,
+            }
+        ));
+    }
+}
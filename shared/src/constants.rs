@@ -18,6 +18,9 @@ pub const MAX_INTENT_PATH_LEN: usize = 5;
 /// Minimum planar motion required to update yaw (meters per tick).
 pub const YAW_EPS: f32 = 1.0e-6;
 
+/// Enemies within this planar distance squared (meters) can see stealthed actors.
+pub const STEALTH_DETECTION_RADIUS_SQ: f32 = 6.0 * 6.0;
+
 /// Size of one grid cell in world units (meters).
 /// All cells are square
 pub const CELL_SIZE: f32 = 50.0;
@@ -29,6 +32,12 @@ pub const INV_CELL_SIZE: f32 = 1.0 / CELL_SIZE;
 pub const GRID_SIDE: u16 = 256;
 pub const GRID_SIDE_F: f32 = GRID_SIDE as f32;
 
+/// Side length (cells per axis) of a world partition, see [`crate::partition`].
+pub const PARTITION_SIDE_CELLS: u16 = 32;
+
+/// Side length (partitions per axis) of the partition grid.
+pub const PARTITION_GRID_SIDE: u16 = GRID_SIDE / PARTITION_SIDE_CELLS;
+
 /// Offset applied when converting world positions to grid coordinates.
 ///
 /// For the `u16` cell-id grid, we use a fixed `GRID_SIDE × GRID_SIDE` world. To support negative
@@ -39,6 +48,20 @@ pub const GRID_SIDE_F: f32 = GRID_SIDE as f32;
 /// Offset (meters): `world_span / 2`
 pub const WORLD_OFFSET: f32 = GRID_SIDE_F * CELL_SIZE * 0.5;
 
+/// Any actor below this height (meters) is considered out of the world and is recovered to the
+/// nearest spawn point.
+pub const KILL_PLANE_Y: f32 = -100.0;
+
+/// Thickness (meters) of the generated world border walls.
+pub const WORLD_BORDER_THICKNESS: f32 = 1.0;
+
+/// Height (meters) of the generated world border walls.
+pub const WORLD_BORDER_HEIGHT: f32 = 200.0;
+
+/// Half the side (meters) of the playable square inside the world border walls, centered on the
+/// origin.
+pub const PLAYABLE_HALF_EXTENT: f32 = WORLD_OFFSET - WORLD_BORDER_THICKNESS;
+
 /// Gravity acceleration (meters/second^2). Negative is downward.
 pub const GRAVITY_MPS2: f32 = -13.81;
 
@@ -53,8 +76,32 @@ pub const TERMINAL_FALL_SPEED_MPS: f32 = GRAVITY_MPS2 * 3.;
 /// With `0.25`, `i8` covers approximately [-32.0, +31.75] m/s.
 pub const VERTICAL_VELOCITY_Q_MPS: f32 = 0.25;
 
+/// Actors outside the viewer's own cell (the outer AOI ring) only receive a transform keyframe
+/// every this many movement ticks, e.g. every 3 s instead of every second at the 1 Hz tick (see
+/// [`MOVEMENT_TICK_INTERVAL_MICROS`]).
+pub const FAR_TRANSFORM_INTERVAL_TICKS: u32 = 3;
+
 pub const MICROS_60HZ: i64 = 16_666;
 pub const MICROS_30HZ: i64 = 33_333;
 pub const MICROS_20HZ: i64 = 50_000;
 pub const MICROS_10HZ: i64 = 100_000;
 pub const MICROS_1HZ: i64 = 1_000_000;
+
+/// Interval of the server movement tick.
+///
+/// Shared so the client advances falling vertical velocity on the same cadence as the server,
+/// see [`crate::advance_vertical_velocity`].
+pub const MOVEMENT_TICK_INTERVAL_MICROS: i64 = MICROS_1HZ;
+pub const MOVEMENT_TICK_INTERVAL_SECS: f32 = MOVEMENT_TICK_INTERVAL_MICROS as f32 / 1_000_000.0;
+
+/// Range of the scale factor applied to an actor's archetype capsule and visuals.
+pub const MIN_ACTOR_SCALE: f32 = 0.25;
+pub const MAX_ACTOR_SCALE: f32 = 4.0;
+
+/// Largest capsule radius (meters) an actor may have once scaled. Monster avoidance only looks
+/// for neighbors this far around twice over, a wider actor would be bumped into unnoticed.
+pub const MAX_ACTOR_CAPSULE_RADIUS: f32 = 1.5;
+
+/// Largest full capsule height (meters, `2 * (half_height + radius)`) an actor may have once
+/// scaled.
+pub const MAX_ACTOR_CAPSULE_HEIGHT: f32 = 8.0;
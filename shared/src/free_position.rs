@@ -0,0 +1,149 @@
+//! Finding a spot where an actor's capsule fits, e.g. to free an actor wedged in geometry.
+//!
+//! Candidates are probed in rings of growing radius around the start, each one snapped to the
+//! ground below it, the first whose capsule overlaps no static of the [`StaticQueryWorld`] wins.
+
+use crate::utils::StaticQueryWorld;
+use nalgebra::{Isometry3, Vector3};
+use rapier3d::prelude::{Capsule, QueryFilter, Ray};
+
+/// Distance (meters) between two rings of candidates.
+pub const FREE_POSITION_STEP: f32 = 0.5;
+
+/// Candidates on the widest rings, so far searches don't probe thousands of spots.
+const MAX_CANDIDATES_PER_RING: usize = 32;
+
+/// How far (meters) below the start's feet a candidate may snap down to the ground.
+const MAX_SNAP_DROP: f32 = 4.0;
+
+/// Gap (meters) left between a placed capsule and the ground, like the controller's skin.
+const GROUND_CLEARANCE: f32 = 0.05;
+
+/// Finds the nearest position within `max_distance` (planar meters) of `from` where a capsule
+/// of `radius` and `half_height` stands on the ground without overlapping any static. `from` and
+/// the result are capsule centers. `from` itself is a candidate.
+///
+/// Ground is probed downwards from the top of the capsule at `from`, actors are never lifted
+/// onto anything higher than their head.
+///
+/// **Performance & Cost**: a ray cast and an overlap test per candidate, up to
+/// `MAX_CANDIDATES_PER_RING * max_distance / FREE_POSITION_STEP` of each
+pub fn find_free_position(
+    world: &StaticQueryWorld,
+    from: Vector3<f32>,
+    radius: f32,
+    half_height: f32,
+    max_distance: f32,
+) -> Option<Vector3<f32>> {
+    let query_pipeline = world.as_query_pipeline(QueryFilter::only_fixed());
+    let capsule = Capsule::new_y(half_height, radius);
+    let probe_top = from.y + half_height + radius;
+    let probe_length = 2.0 * (half_height + radius) + MAX_SNAP_DROP;
+
+    let rings = (max_distance.max(0.0) / FREE_POSITION_STEP) as usize;
+    (0..=rings)
+        .flat_map(|ring| {
+            let distance = ring as f32 * FREE_POSITION_STEP;
+            let count = (8 * ring).clamp(1, MAX_CANDIDATES_PER_RING);
+            (0..count).map(move |i| {
+                let angle = i as f32 * std::f32::consts::TAU / count as f32;
+                Vector3::new(
+                    from.x + distance * angle.cos(),
+                    probe_top,
+                    from.z + distance * angle.sin(),
+                )
+            })
+        })
+        .filter_map(|probe| {
+            let ray = Ray::new(probe.into(), -Vector3::y());
+            let (_, toi) = query_pipeline.cast_ray(&ray, probe_length, true)?;
+            let ground = probe.y - toi;
+            Some(Vector3::new(
+                probe.x,
+                ground + half_height + radius + GROUND_CLEARANCE,
+                probe.z,
+            ))
+        })
+        .find(|center| {
+            let pose = Isometry3::translation(center.x, center.y, center.z);
+            !world.overlaps_static(pose, &capsule, None)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColliderShapeDef, WorldStaticDef, build_static_query_world};
+    use nalgebra::UnitQuaternion;
+
+    const RADIUS: f32 = 0.3;
+    const HALF_HEIGHT: f32 = 0.9;
+
+    /// A ground plane and a box of `half_extents` resting on it at the origin.
+    fn world_with_box(half_extents: Vector3<f32>) -> StaticQueryWorld {
+        let defs = [
+            WorldStaticDef {
+                id: 0,
+                translation: Vector3::zeros(),
+                rotation: UnitQuaternion::identity(),
+                shape: ColliderShapeDef::Plane {
+                    offset_along_normal: 0.0,
+                },
+            },
+            WorldStaticDef {
+                id: 1,
+                translation: Vector3::y() * half_extents.y,
+                rotation: UnitQuaternion::identity(),
+                shape: ColliderShapeDef::Cuboid { half_extents },
+            },
+        ];
+        build_static_query_world(defs, 1.0 / 30.0)
+    }
+
+    fn standing_at(x: f32, z: f32) -> Vector3<f32> {
+        Vector3::new(x, HALF_HEIGHT + RADIUS, z)
+    }
+
+    #[test]
+    fn free_start_is_kept_and_snapped_to_the_ground() {
+        let world = world_with_box(Vector3::new(1.0, 1.0, 1.0));
+        let from = standing_at(5.0, 0.0) + Vector3::y() * 0.5;
+        let found = find_free_position(&world, from, RADIUS, HALF_HEIGHT, 4.0).unwrap();
+        assert_eq!(found.xz(), from.xz());
+        assert!((found.y - (HALF_HEIGHT + RADIUS + GROUND_CLEARANCE)).abs() < 1.0e-4);
+    }
+
+    #[test]
+    fn wedged_actor_is_moved_out_of_the_box() {
+        let half_extents = Vector3::new(1.0, 3.0, 1.0);
+        let world = world_with_box(half_extents);
+        let from = standing_at(0.6, 0.0);
+        let found = find_free_position(&world, from, RADIUS, HALF_HEIGHT, 4.0).unwrap();
+
+        let outside =
+            found.x.abs() >= half_extents.x + RADIUS || found.z.abs() >= half_extents.z + RADIUS;
+        assert!(outside, "still in the box at {found:?}");
+        assert!((found.y - (HALF_HEIGHT + RADIUS + GROUND_CLEARANCE)).abs() < 1.0e-4);
+        // The nearest way out, through the side it's closest to.
+        assert!((found.xz() - from.xz()).norm() <= 1.0 + FREE_POSITION_STEP);
+    }
+
+    #[test]
+    fn low_box_is_stood_on() {
+        let world = world_with_box(Vector3::new(2.0, 0.25, 2.0));
+        let from = standing_at(0.0, 0.0);
+        let found = find_free_position(&world, from, RADIUS, HALF_HEIGHT, 4.0).unwrap();
+        assert_eq!(found.xz(), from.xz());
+        assert!((found.y - (0.5 + HALF_HEIGHT + RADIUS + GROUND_CLEARANCE)).abs() < 1.0e-4);
+    }
+
+    #[test]
+    fn nothing_found_within_the_search_radius() {
+        let world = world_with_box(Vector3::new(10.0, 3.0, 10.0));
+        let from = standing_at(0.0, 0.0);
+        assert_eq!(
+            find_free_position(&world, from, RADIUS, HALF_HEIGHT, 4.0),
+            None
+        );
+    }
+}
@@ -0,0 +1,117 @@
+//! The canonical planar geometry helpers, used by movement, avoidance, AI and the server's range
+//! checks so they all measure the same way.
+//!
+//! Planar means the XZ plane, a `Vector2` holds `(x, z)`. Benchmarked in `benches/geom.rs`.
+
+use nalgebra::{Vector2, Vector3};
+
+/// Planar (XZ) distance squared between two world positions (meters^2).
+pub fn planar_distance_sq(a: Vector2<f32>, b: Vector2<f32>) -> f32 {
+    let x = b.x - a.x;
+    let z = b.y - a.y;
+    x * x + z * z
+}
+
+/// Planar (XZ) distance squared between two 3D world positions, ignoring height (meters^2).
+pub fn dist_sq_xz(a: Vector3<f32>, b: Vector3<f32>) -> f32 {
+    let x = b.x - a.x;
+    let z = b.z - a.z;
+    x * x + z * z
+}
+
+/// Planar (XZ) offset from `from` to `to`, ignoring height.
+pub fn offset_xz(from: Vector3<f32>, to: Vector3<f32>) -> Vector2<f32> {
+    Vector2::new(to.x - from.x, to.z - from.z)
+}
+
+/// `v` scaled down to at most `max_length`, unchanged when already shorter.
+pub fn clamp_planar(v: Vector2<f32>, max_length: f32) -> Vector2<f32> {
+    let length_sq = v.norm_squared();
+    if length_sq <= max_length * max_length {
+        return v;
+    }
+    v * (max_length / length_sq.sqrt())
+}
+
+/// How deep (`0` to `1`) something at `distance` is within `range`: `1` at the center, falling
+/// off linearly to `0` at the edge and beyond.
+pub fn soft_intrusion(distance: f32, range: f32) -> f32 {
+    if range <= 0.0 {
+        return 0.0;
+    }
+    (1.0 - distance / range).clamp(0.0, 1.0)
+}
+
+/// The unit direction pushing `position` away from `other` and the distance between them.
+///
+/// Coincident positions have no direction of their own, they're pushed along `fallback` at a
+/// distance of `0`.
+pub fn penetration_normal(
+    position: Vector2<f32>,
+    other: Vector2<f32>,
+    fallback: Vector2<f32>,
+) -> (Vector2<f32>, f32) {
+    let away = position - other;
+    let distance_sq = away.norm_squared();
+    if distance_sq <= 1.0e-12 {
+        return (fallback, 0.0);
+    }
+    let distance = distance_sq.sqrt();
+    (away / distance, distance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dist_sq_xz_ignores_height() {
+        let a = Vector3::new(1.0, 5.0, 2.0);
+        let b = Vector3::new(4.0, -3.0, 6.0);
+        assert_eq!(dist_sq_xz(a, b), 25.0);
+        assert_eq!(
+            dist_sq_xz(a, b),
+            planar_distance_sq(a.xz(), b.xz()),
+            "both planar distances agree"
+        );
+    }
+
+    #[test]
+    fn offset_xz_ignores_height() {
+        let a = Vector3::new(1.0, 5.0, 2.0);
+        let b = Vector3::new(4.0, -3.0, 6.0);
+        assert_eq!(offset_xz(a, b), Vector2::new(3.0, 4.0));
+        assert_eq!(offset_xz(a, b).norm_squared(), dist_sq_xz(a, b));
+    }
+
+    #[test]
+    fn clamp_planar_only_shortens() {
+        let short = Vector2::new(0.3, 0.4);
+        assert_eq!(clamp_planar(short, 1.0), short);
+        let clamped = clamp_planar(Vector2::new(3.0, 4.0), 2.0);
+        assert!((clamped.norm() - 2.0).abs() < 1.0e-6);
+        assert!((clamped - Vector2::new(1.2, 1.6)).norm() < 1.0e-6);
+    }
+
+    #[test]
+    fn soft_intrusion_falls_off_linearly() {
+        assert_eq!(soft_intrusion(0.0, 2.0), 1.0);
+        assert_eq!(soft_intrusion(1.0, 2.0), 0.5);
+        assert_eq!(soft_intrusion(3.0, 2.0), 0.0);
+        assert_eq!(soft_intrusion(1.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn penetration_normal_points_away() {
+        let (normal, distance) =
+            penetration_normal(Vector2::new(0.0, 2.0), Vector2::zeros(), Vector2::x());
+        assert_eq!(normal, Vector2::new(0.0, 1.0));
+        assert_eq!(distance, 2.0);
+
+        let fallback = Vector2::new(-1.0, 0.0);
+        assert_eq!(
+            penetration_normal(Vector2::zeros(), Vector2::zeros(), fallback),
+            (fallback, 0.0)
+        );
+    }
+}
@@ -0,0 +1,187 @@
+//! Property based fuzzing of the client-facing input path.
+//!
+//! Reducers can't run outside SpacetimeDB, but everything they do with untrusted arguments goes
+//! through shared code: floats through [`crate::validate`], move targets through the movement
+//! step, intent sequence numbers through [`is_tick_newer`]. These properties throw adversarial
+//! input (NaN, infinities, huge and denormal values, stale sequences) at that path and check it
+//! never panics and keeps its invariants: sanitized values are finite and inside the world, and
+//! a capsule moved by the step never ends up inside a static or below the ground.
+
+use crate::{
+    ColliderShapeDef, MAX_ACTOR_CAPSULE_HEIGHT, MAX_ACTOR_CAPSULE_RADIUS,
+    MOVEMENT_TICK_INTERVAL_SECS, MovementStepInput, StaticQueryWorld, WORLD_BORDER_HEIGHT,
+    WORLD_OFFSET, WorldStaticDef, build_static_query_world, is_tick_newer, movement_kcc,
+    movement_step_actor,
+    validate::{self, ValidationError},
+};
+use nalgebra::{Isometry3, Quaternion, UnitQuaternion, Vector2, Vector3};
+use proptest::{num::f32 as any_f32, prelude::*};
+use rapier3d::prelude::{Capsule, QueryFilter};
+
+/// The server's step, the longest moves a capsule makes in one go.
+const DT: f32 = MOVEMENT_TICK_INTERVAL_SECS;
+
+/// Any float, including NaN, the infinities, subnormals and huge values.
+fn adversarial_f32() -> impl Strategy<Value = f32> {
+    prop_oneof![
+        any_f32::ANY,
+        Just(f32::NAN),
+        Just(f32::INFINITY),
+        Just(f32::NEG_INFINITY),
+        Just(f32::MAX),
+        Just(f32::MIN),
+        Just(f32::MIN_POSITIVE),
+        -2.0 * WORLD_OFFSET..2.0 * WORLD_OFFSET,
+    ]
+}
+
+fn adversarial_vec2() -> impl Strategy<Value = Vector2<f32>> {
+    (adversarial_f32(), adversarial_f32()).prop_map(|(x, z)| Vector2::new(x, z))
+}
+
+fn adversarial_vec3() -> impl Strategy<Value = Vector3<f32>> {
+    (adversarial_f32(), adversarial_f32(), adversarial_f32())
+        .prop_map(|(x, y, z)| Vector3::new(x, y, z))
+}
+
+/// A ground plane with a ring of boxes around the origin, where actors start.
+fn arena() -> StaticQueryWorld {
+    let mut defs = vec![WorldStaticDef {
+        id: 0,
+        translation: Vector3::zeros(),
+        rotation: UnitQuaternion::identity(),
+        shape: ColliderShapeDef::Plane {
+            offset_along_normal: 0.0,
+        },
+    }];
+    for x in -2..=2 {
+        for z in -2..=2 {
+            if x == 0 && z == 0 {
+                continue;
+            }
+            defs.push(WorldStaticDef {
+                id: defs.len() as u64,
+                translation: Vector3::new(x as f32 * 4.0, 1.0, z as f32 * 4.0),
+                rotation: UnitQuaternion::identity(),
+                shape: ColliderShapeDef::Cuboid {
+                    half_extents: Vector3::new(1.0, 1.0, 1.0),
+                },
+            });
+        }
+    }
+    build_static_query_world(defs, DT)
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(256))]
+
+    #[test]
+    fn clamped_move_targets_are_finite_and_in_world(v in adversarial_vec2()) {
+        match validate::clamped_to_world_xz(v) {
+            Ok(clamped) => {
+                prop_assert!(v.iter().all(|c| c.is_finite()));
+                prop_assert!(clamped.iter().all(|c| c.abs() <= WORLD_OFFSET));
+            }
+            Err(err) => {
+                prop_assert_eq!(err, ValidationError::NonFinite);
+                prop_assert!(v.iter().any(|c| !c.is_finite()));
+            }
+        }
+    }
+
+    #[test]
+    fn clamped_3d_targets_are_finite_and_in_world(v in adversarial_vec3()) {
+        if let Ok(clamped) = validate::clamped_to_world(v) {
+            prop_assert!(validate::within_world(clamped).is_ok());
+        }
+    }
+
+    #[test]
+    fn rotations_are_unit_or_rejected(
+        w in adversarial_f32(),
+        i in adversarial_f32(),
+        j in adversarial_f32(),
+        k in adversarial_f32(),
+    ) {
+        if let Ok(rotation) = validate::unit_quat_or_err(Quaternion::new(w, i, j, k)) {
+            prop_assert!((rotation.into_inner().norm() - 1.0).abs() < 1.0e-3);
+        }
+    }
+
+    #[test]
+    fn scaled_capsules_stay_within_bounds(
+        radius in adversarial_f32(),
+        half_height in adversarial_f32(),
+        scale in adversarial_f32(),
+    ) {
+        if let Ok((radius, half_height)) = validate::scaled_capsule(radius, half_height, scale) {
+            prop_assert!(radius <= MAX_ACTOR_CAPSULE_RADIUS);
+            prop_assert!(2.0 * (half_height + radius) <= MAX_ACTOR_CAPSULE_HEIGHT);
+        }
+    }
+
+    /// Replaying any earlier sequence number is never taken as a new intent.
+    #[test]
+    fn stale_sequences_are_never_newer(last in any::<u32>(), age in 0u32..u32::MAX / 2) {
+        prop_assert!(!is_tick_newer(last.wrapping_sub(age), last));
+    }
+
+    /// Drives the movement step with sanitized adversarial targets, the capsule must never end
+    /// up inside a box or below the ground plane.
+    #[test]
+    fn movement_never_enters_statics(
+        targets in prop::collection::vec(adversarial_vec2(), 1..4),
+        radius in 0.2f32..0.8,
+        half_height in 0.3f32..1.0,
+        speed in 0.0f32..20.0,
+    ) {
+        let world = arena();
+        let query_pipeline = world.as_query_pipeline(QueryFilter::only_fixed());
+        let kcc = movement_kcc();
+        // Slack for the controller's skin offset.
+        let probe = Capsule::new_y(half_height, radius * 0.9);
+
+        let mut input = MovementStepInput {
+            translation: Vector3::new(0.0, half_height + radius + 0.05, 0.0),
+            yaw: 0.0,
+            capsule_radius: radius,
+            capsule_half_height: half_height,
+            target_planar: Vector2::zeros(),
+            movement_speed_mps: speed,
+            vertical_velocity: -1,
+            max_drop: None,
+            flying: false,
+            target_y: None,
+        };
+        for target in targets {
+            // Non-finite targets are rejected by the reducer before they reach the step.
+            let Ok(target) = validate::clamped_to_world_xz(target) else {
+                continue;
+            };
+            input.target_planar = target;
+            for _ in 0..30 {
+                let step = movement_step_actor(&kcc, &query_pipeline, &input, DT);
+                prop_assert!(step.translation.iter().all(|c| c.is_finite()));
+                prop_assert!(step.translation.y.abs() <= WORLD_BORDER_HEIGHT);
+                prop_assert!(
+                    step.translation.y - half_height - radius >= -0.05,
+                    "below the ground: {:?}",
+                    step.translation
+                );
+                let position = Isometry3::translation(
+                    step.translation.x,
+                    step.translation.y,
+                    step.translation.z,
+                );
+                prop_assert!(
+                    !world.overlaps_static(position, &probe, None),
+                    "inside a static: {:?}",
+                    step.translation
+                );
+                input.translation = step.translation;
+                input.yaw = step.yaw;
+                input.vertical_velocity = step.vertical_velocity;
+            }
+        }
+    }
+}
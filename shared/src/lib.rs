@@ -1,16 +1,45 @@
+pub mod ability;
+pub mod avoidance;
+pub mod bitmask_flags;
 pub mod cell;
+pub mod charge;
 pub mod collision;
 pub mod constants;
+pub mod free_position;
+pub mod geom;
+#[cfg(test)]
+mod input_fuzz;
+pub mod math;
+pub mod melee;
+pub mod movement_step;
+pub mod partition;
 pub mod quantize;
+pub mod replay;
+pub mod rng;
+pub mod scene_query;
+pub mod test_course;
 pub mod utils;
+pub mod validate;
 
+pub use ability::*;
+pub use avoidance::*;
+pub use bitmask_flags::{ActorFlags, TutorialFlags};
 pub use cell::{
-    decode_cell_coords, decode_cell_min_corner, encode_cell_id, get_aoi_block, max_cell_coord,
-    world_span_m,
+    cells_in_radius, decode_cell_coords, decode_cell_min_corner, encode_cell_id, get_aoi_block,
+    get_aoi_block_clamped, is_in_aoi_block, max_cell_coord, world_span_m,
 };
+pub use charge::*;
 pub use collision::{ColliderShapeDef, WorldStaticDef, collider_from_def};
 pub use constants::*;
+pub use free_position::*;
+pub use geom::*;
+pub use math::*;
+pub use melee::*;
+pub use movement_step::*;
+pub use partition::*;
 pub use quantize::*;
+pub use rng::Rng;
+pub use scene_query::*;
 pub use utils::*;
 
 /// 4byte unique identifier for an actor.
@@ -20,3 +49,10 @@ pub type ActorId = u32;
 
 /// Compact cell identifier for AOI + spatial views.
 pub type CellId = u16;
+
+/// Identifies a square group of cells that one module owns, see [`partition`].
+pub type PartitionId = u16;
+
+/// Identifies a separate copy of the world (the overworld, a dungeon, a test arena...). Cells,
+/// statics and actors only interact within the same instance.
+pub type InstanceId = u32;
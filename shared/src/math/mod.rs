@@ -0,0 +1,3 @@
+pub mod yaw;
+
+pub use yaw::*;
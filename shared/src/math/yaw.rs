@@ -0,0 +1,129 @@
+//! The single home for yaw math, shared by the server and client.
+//!
+//! Convention: yaw is a rotation in radians about +Y, the same as `Quat::from_rotation_y` (bevy)
+//! and `UnitQuaternion::from_axis_angle(&Vector3::y_axis(), yaw)` (nalgebra). A yaw of `0` faces
+//! forward, which is -Z, so the forward direction for a yaw is `(-sin(yaw), -cos(yaw))` in XZ.
+//!
+//! Quantized yaws store the wrapped `[0, TAU)` angle, see [`wrap_yaw`].
+
+use crate::YAW_EPS;
+use nalgebra::Vector2;
+use std::f32::consts::{PI, TAU};
+
+/// The yaw facing along the planar (XZ) direction, `None` when the direction is too small to
+/// have a meaningful heading.
+pub fn yaw_from_xz(xz: Vector2<f32>) -> Option<f32> {
+    if xz.norm_squared() > YAW_EPS {
+        return Some((-xz.x).atan2(-xz.y));
+    }
+
+    None
+}
+
+/// The unit planar (XZ) forward direction for a yaw, the inverse of [`yaw_from_xz`].
+pub fn yaw_to_xz(yaw: f32) -> Vector2<f32> {
+    let (sin, cos) = yaw.sin_cos();
+    Vector2::new(-sin, -cos)
+}
+
+/// Wraps any yaw into `[0, TAU)`.
+pub fn wrap_yaw(yaw: f32) -> f32 {
+    let wrapped = yaw.rem_euclid(TAU);
+    // rem_euclid can round up to exactly TAU for tiny negative inputs.
+    if wrapped >= TAU { 0.0 } else { wrapped }
+}
+
+/// The shortest signed rotation from `from` to `to`, in `[-PI, PI)`.
+pub fn yaw_delta(from: f32, to: f32) -> f32 {
+    (to - from + PI).rem_euclid(TAU) - PI
+}
+
+/// Quantize a yaw into a `u8`, ~1.4 degrees of precision.
+pub fn yaw_to_u8(yaw: f32) -> u8 {
+    const SCALE: f32 = 256.0 / TAU;
+    ((wrap_yaw(yaw) * SCALE).round() as u32 % 256) as u8
+}
+
+/// Dequantize a `u8` yaw back into radians in `[0, TAU)`.
+pub fn yaw_from_u8(code: u8) -> f32 {
+    code as f32 * (TAU / 256.0)
+}
+
+/// Quantize a yaw into a `u16`, ~0.0055 degrees of precision.
+pub fn yaw_to_u16(yaw: f32) -> u16 {
+    const SCALE: f32 = 65536.0 / TAU;
+    ((wrap_yaw(yaw) * SCALE).round() as u32 % 65536) as u16
+}
+
+/// Dequantize a `u16` yaw back into radians in `[0, TAU)`.
+pub fn yaw_from_u16(code: u16) -> f32 {
+    code as f32 * (TAU / 65536.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    const ANGLE_EPS: f32 = 1.0e-4;
+
+    fn assert_same_yaw(a: f32, b: f32, eps: f32) {
+        let d = yaw_delta(a, b).abs();
+        assert!(d <= eps, "yaw {a} and {b} differ by {d}");
+    }
+
+    #[test]
+    fn zero_yaw_faces_negative_z() {
+        assert_same_yaw(
+            yaw_from_xz(Vector2::new(0.0, -1.0)).unwrap(),
+            0.0,
+            ANGLE_EPS,
+        );
+        let forward = yaw_to_xz(0.0);
+        assert!((forward - Vector2::new(0.0, -1.0)).norm() < ANGLE_EPS);
+    }
+
+    #[test]
+    fn tiny_direction_has_no_yaw() {
+        assert_eq!(yaw_from_xz(Vector2::zeros()), None);
+    }
+
+    #[test]
+    fn u16_codes_round_trip_exactly() {
+        for code in 0..=u16::MAX {
+            assert_eq!(yaw_to_u16(yaw_from_u16(code)), code);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn xz_round_trip(yaw in -10.0f32..10.0) {
+            let back = yaw_from_xz(yaw_to_xz(yaw)).unwrap();
+            assert_same_yaw(back, yaw, ANGLE_EPS);
+        }
+
+        #[test]
+        fn wrap_is_in_range_and_same_heading(yaw in -1000.0f32..1000.0) {
+            let wrapped = wrap_yaw(yaw);
+            prop_assert!((0.0..TAU).contains(&wrapped));
+            assert_same_yaw(wrapped, yaw, 1.0e-3);
+        }
+
+        #[test]
+        fn delta_is_shortest(from in -10.0f32..10.0, to in -10.0f32..10.0) {
+            let d = yaw_delta(from, to);
+            prop_assert!((-PI..PI).contains(&d));
+            assert_same_yaw(from + d, to, 1.0e-3);
+        }
+
+        #[test]
+        fn u8_round_trip_within_half_step(yaw in -10.0f32..10.0) {
+            assert_same_yaw(yaw_from_u8(yaw_to_u8(yaw)), yaw, TAU / 512.0 + ANGLE_EPS);
+        }
+
+        #[test]
+        fn u16_round_trip_within_half_step(yaw in -10.0f32..10.0) {
+            assert_same_yaw(yaw_from_u16(yaw_to_u16(yaw)), yaw, TAU / 131072.0 + ANGLE_EPS);
+        }
+    }
+}
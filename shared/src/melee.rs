@@ -0,0 +1,148 @@
+//! Melee hit detection: is a target within an attacker's arc and reach, and not behind a wall?
+//!
+//! Shared so the server's attack reducer and any client side prediction agree on what was hit.
+
+use crate::{StaticQueryWorld, math::yaw::yaw_to_xz, offset_xz};
+use nalgebra::{Vector2, Vector3};
+use rapier3d::prelude::{Collider, QueryFilter, Ray};
+
+/// A planar sector (cone) in front of an attacker.
+#[derive(Debug, Clone, Copy)]
+pub struct MeleeArc {
+    /// The direction the attacker faces, see [`crate::math::yaw`].
+    pub yaw: f32,
+    /// Max distance (meters) from the attacker's origin to the surface of the target's capsule.
+    pub reach: f32,
+    /// Half of the arc's opening angle (radians).
+    pub half_angle: f32,
+}
+
+impl MeleeArc {
+    /// Does the arc from `origin` hit the Y-aligned capsule centered at `target`?
+    ///
+    /// With a `query_world` the hit also needs line of sight from `origin` to the capsule's
+    /// center, see [`has_line_of_sight`].
+    pub fn hits_capsule(
+        &self,
+        origin: Vector3<f32>,
+        target: Vector3<f32>,
+        capsule_radius: f32,
+        capsule_half_height: f32,
+        query_world: Option<&StaticQueryWorld>,
+    ) -> bool {
+        // Distance to the closest point of the capsule's segment, minus its radius.
+        let to_target = offset_xz(origin, target);
+        let vertical = ((target.y - origin.y).abs() - capsule_half_height).max(0.0);
+        let surface_distance =
+            (to_target.norm_squared() + vertical * vertical).sqrt() - capsule_radius;
+        if surface_distance > self.reach {
+            return false;
+        }
+
+        if !is_within_sector(self.yaw, self.half_angle, to_target, capsule_radius) {
+            return false;
+        }
+
+        query_world.is_none_or(|query_world| has_line_of_sight(query_world, origin, target))
+    }
+}
+
+/// Is a circle of `radius` at the planar offset `to_target` within the sector facing `yaw`?
+///
+/// The half angle is widened by the angle the circle's radius covers so targets straddling the
+/// sector's edge still count, and targets overlapping the apex always count.
+pub fn is_within_sector(yaw: f32, half_angle: f32, to_target: Vector2<f32>, radius: f32) -> bool {
+    let distance = to_target.norm();
+    if distance <= radius {
+        return true;
+    }
+    let angle = yaw_to_xz(yaw).angle(&to_target);
+    let slack = (radius / distance).atan();
+    angle <= half_angle + slack
+}
+
+/// Is the straight line from `from` to `to` free of world statics?
+///
+/// The infinite ground planes are ignored, like [`StaticQueryWorld::overlaps_static`].
+pub fn has_line_of_sight(
+    query_world: &StaticQueryWorld,
+    from: Vector3<f32>,
+    to: Vector3<f32>,
+) -> bool {
+    let delta = to - from;
+    let distance = delta.norm();
+    if distance <= f32::EPSILON {
+        return true;
+    }
+    let predicate = |_, collider: &Collider| collider.shape().as_halfspace().is_none();
+    let ray = Ray::new(from.into(), delta / distance);
+    query_world
+        .as_query_pipeline(QueryFilter::only_fixed().predicate(&predicate))
+        .cast_ray(&ray, distance, true)
+        .is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColliderShapeDef, WorldStaticDef, build_static_query_world};
+    use nalgebra::UnitQuaternion;
+    use std::f32::consts::FRAC_PI_4;
+
+    const RADIUS: f32 = 0.3;
+    const HALF_HEIGHT: f32 = 0.9;
+
+    /// Facing -Z (yaw 0) with a 90 degree arc.
+    const ARC: MeleeArc = MeleeArc {
+        yaw: 0.0,
+        reach: 2.0,
+        half_angle: FRAC_PI_4,
+    };
+
+    fn hits(target: Vector3<f32>, query_world: Option<&StaticQueryWorld>) -> bool {
+        ARC.hits_capsule(Vector3::zeros(), target, RADIUS, HALF_HEIGHT, query_world)
+    }
+
+    #[test]
+    fn hits_in_front_within_reach() {
+        assert!(hits(Vector3::new(0.0, 0.0, -2.0), None));
+        assert!(!hits(Vector3::new(0.0, 0.0, -2.5), None));
+    }
+
+    #[test]
+    fn misses_behind_and_to_the_side() {
+        assert!(!hits(Vector3::new(0.0, 0.0, 1.0), None));
+        assert!(!hits(Vector3::new(1.5, 0.0, 0.0), None));
+    }
+
+    #[test]
+    fn edge_of_arc_counts_capsule_radius() {
+        // Just outside 45 degrees, but the capsule still overlaps the arc.
+        let target = Vector3::new(1.1, 0.0, -1.0);
+        assert!(hits(target, None));
+        assert!(!is_within_sector(ARC.yaw, ARC.half_angle, target.xz(), 0.0));
+    }
+
+    #[test]
+    fn vertical_offset_uses_capsule_segment() {
+        // The capsule's segment reaches down to the attacker, only the planar distance counts.
+        assert!(hits(Vector3::new(0.0, HALF_HEIGHT, -2.0), None));
+        assert!(!hits(Vector3::new(0.0, HALF_HEIGHT + 2.5, -1.0), None));
+    }
+
+    #[test]
+    fn walls_block_line_of_sight() {
+        let wall = WorldStaticDef {
+            id: 1,
+            translation: Vector3::new(0.0, 0.0, -1.0),
+            rotation: UnitQuaternion::identity(),
+            shape: ColliderShapeDef::Cuboid {
+                half_extents: Vector3::new(2.0, 2.0, 0.1),
+            },
+        };
+        let query_world = build_static_query_world([wall], 1.0);
+        let target = Vector3::new(0.0, 0.0, -2.0);
+        assert!(hits(target, None));
+        assert!(!hits(target, Some(&query_world)));
+    }
+}
@@ -0,0 +1,475 @@
+use crate::{
+    advance_vertical_velocity, dequantize_vertical_velocity, get_desired_delta, get_fly_delta,
+    math::yaw::yaw_from_xz,
+};
+use nalgebra::{Isometry3, Point3, UnitQuaternion, Vector2, Vector3};
+use rapier3d::{
+    control::{CharacterAutostep, CharacterLength, KinematicCharacterController},
+    prelude::{Capsule, QueryPipeline, Ray},
+};
+
+/// Translation changes smaller than this (squared, meters) are not worth replicating.
+pub const TRANSLATION_EPS_SQ: f32 = 1.0e-8;
+
+/// Height (meters) above the feet the ledge probe starts from, so steps up are found as ground.
+const LEDGE_PROBE_HEIGHT: f32 = 0.5;
+
+/// Fraction of its full capsule height an actor can step up, see [`autostep_max_height`].
+const AUTOSTEP_HEIGHT_RATIO: f32 = 0.4;
+
+/// Highest step (meters) any actor climbs however big it is, a scaled up monster would
+/// otherwise walk up walls.
+pub const MAX_AUTOSTEP_HEIGHT: f32 = 1.0;
+
+/// Everything a single actor's movement step depends on besides the static world and `dt`.
+///
+/// Resolving the target (actor lookups, path waypoints) and the movement speed happens before
+/// the step, so these values are all that's needed to re-run it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MovementStepInput {
+    pub translation: Vector3<f32>,
+    pub yaw: f32,
+    pub capsule_radius: f32,
+    pub capsule_half_height: f32,
+    pub target_planar: Vector2<f32>,
+    pub movement_speed_mps: f32,
+    pub vertical_velocity: i8,
+    /// When set, a grounded actor won't step off a ledge that drops more than this (meters),
+    /// see [`supported_length`]. `None` lets the actor walk off anything.
+    pub max_drop: Option<f32>,
+    /// GM fly mode: no gravity or ground snapping, the actor moves straight towards
+    /// `target_planar` at the height `target_y`, see [`fly_step_actor`].
+    pub flying: bool,
+    /// Height of a 3D point target, only used while `flying`. `None` keeps the current height.
+    pub target_y: Option<f32>,
+}
+
+/// Result of [`movement_step_actor`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MovementStepOutput {
+    pub translation: Vector3<f32>,
+    pub yaw: f32,
+    pub vertical_velocity: i8,
+    /// Whether the KCC ended the step standing on the ground.
+    pub grounded: bool,
+}
+
+/// The character controller settings used by the server movement tick.
+pub fn movement_kcc() -> KinematicCharacterController {
+    KinematicCharacterController {
+        autostep: Some(CharacterAutostep {
+            include_dynamic_bodies: false,
+            max_height: CharacterLength::Relative(AUTOSTEP_HEIGHT_RATIO),
+            ..CharacterAutostep::default()
+        }),
+        offset: CharacterLength::Relative(0.025),
+        ..KinematicCharacterController::default()
+    }
+}
+
+/// Highest step (meters) a capsule climbs, proportional to its height up to
+/// [`MAX_AUTOSTEP_HEIGHT`].
+pub fn autostep_max_height(capsule_radius: f32, capsule_half_height: f32) -> f32 {
+    (2.0 * (capsule_half_height + capsule_radius) * AUTOSTEP_HEIGHT_RATIO).min(MAX_AUTOSTEP_HEIGHT)
+}
+
+/// Moves one actor by one tick against the static world.
+///
+/// This is a pure function of its inputs so the server tick and the replayer (see
+/// [`crate::replay`]) produce the same result for the same frame.
+pub fn movement_step_actor(
+    kcc: &KinematicCharacterController,
+    query_pipeline: &QueryPipeline,
+    input: &MovementStepInput,
+    dt: f32,
+) -> MovementStepOutput {
+    if input.flying {
+        return fly_step_actor(kcc, query_pipeline, input, dt);
+    }
+
+    let current_planar = input.translation.xz();
+
+    let mut vertical_velocity = input.vertical_velocity;
+    // Airborne actors move at the average of the velocity they start and end the tick with.
+    // Moving at the end velocity alone would apply a whole tick of gravity before a launch moves.
+    let mut average_vertical_mps = 0.0;
+    if vertical_velocity != 0 {
+        vertical_velocity = advance_vertical_velocity(vertical_velocity, dt);
+        average_vertical_mps = 0.5
+            * (dequantize_vertical_velocity(input.vertical_velocity)
+                + dequantize_vertical_velocity(vertical_velocity));
+    }
+    let rising = average_vertical_mps > 0.0;
+
+    let direction = (input.target_planar - current_planar)
+        .try_normalize(0.0)
+        .unwrap_or_default();
+    let yaw = yaw_from_xz(direction).unwrap_or(input.yaw);
+
+    let mut desired_delta = get_desired_delta(
+        current_planar,
+        input.target_planar,
+        input.movement_speed_mps,
+        vertical_velocity,
+        dt,
+    );
+    if vertical_velocity != 0 {
+        desired_delta.y = average_vertical_mps * dt;
+    }
+    if let Some(max_drop) = input.max_drop {
+        let planar = desired_delta.xz();
+        if vertical_velocity == 0 && planar != Vector2::zeros() {
+            // Up to the ledge, not a step short of it.
+            let scale = supported_length(query_pipeline, input, planar, max_drop) / planar.norm();
+            desired_delta.x *= scale;
+            desired_delta.z *= scale;
+        }
+    }
+
+    // Snapping to the ground would undo a launch on the tick it leaves the ground.
+    let kcc = KinematicCharacterController {
+        autostep: kcc.autostep.map(|autostep| CharacterAutostep {
+            max_height: CharacterLength::Absolute(autostep_max_height(
+                input.capsule_radius,
+                input.capsule_half_height,
+            )),
+            ..autostep
+        }),
+        snap_to_ground: if rising { None } else { kcc.snap_to_ground },
+        ..*kcc
+    };
+    let rotation = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), yaw);
+    let correction = kcc.move_shape(
+        dt,
+        query_pipeline,
+        &Capsule::new_y(input.capsule_half_height, input.capsule_radius),
+        &Isometry3::from_parts(input.translation.into(), rotation),
+        desired_delta,
+        |_| {},
+    );
+
+    // Ground truth for grounding comes from KCC.
+    //
+    // - If KCC reports grounded, we stop falling (set vv=0). A rising actor keeps rising, the
+    //   KCC can still report the ground it's just leaving.
+    // - If KCC reports not grounded, we ensure falling has started (vv is at least -1),
+    //   even if vv was previously 0 for any reason.
+    if correction.grounded && !rising {
+        vertical_velocity = 0;
+    } else if vertical_velocity == 0 {
+        vertical_velocity = -1;
+    }
+
+    MovementStepOutput {
+        translation: input.translation + correction.translation,
+        yaw,
+        vertical_velocity,
+        grounded: correction.grounded,
+    }
+}
+
+/// The flying branch of [`movement_step_actor`]: straight towards the 3D target, still colliding
+/// with the static world but without gravity, autostep or ground snapping.
+pub fn fly_step_actor(
+    kcc: &KinematicCharacterController,
+    query_pipeline: &QueryPipeline,
+    input: &MovementStepInput,
+    dt: f32,
+) -> MovementStepOutput {
+    let target = Vector3::new(
+        input.target_planar.x,
+        input.target_y.unwrap_or(input.translation.y),
+        input.target_planar.y,
+    );
+    let direction = (input.target_planar - input.translation.xz())
+        .try_normalize(0.0)
+        .unwrap_or_default();
+    let yaw = yaw_from_xz(direction).unwrap_or(input.yaw);
+    let desired_delta = get_fly_delta(input.translation, target, input.movement_speed_mps, dt);
+
+    let kcc = KinematicCharacterController {
+        autostep: None,
+        snap_to_ground: None,
+        ..*kcc
+    };
+    let rotation = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), yaw);
+    let correction = kcc.move_shape(
+        dt,
+        query_pipeline,
+        &Capsule::new_y(input.capsule_half_height, input.capsule_radius),
+        &Isometry3::from_parts(input.translation.into(), rotation),
+        desired_delta,
+        |_| {},
+    );
+
+    MovementStepOutput {
+        translation: input.translation + correction.translation,
+        yaw,
+        vertical_velocity: 0,
+        grounded: correction.grounded,
+    }
+}
+
+/// How far (meters) the actor can move along `planar` with ground at most `max_drop` meters below
+/// the leading edge of its capsule, all of `planar`'s length when the ground never drops further.
+///
+/// The path is probed every capsule radius (see [`has_ground_ahead`]), a long step can't jump a
+/// gap the capsule would fall into.
+///
+/// **Performance & Cost**: a ray cast per capsule radius of the step
+pub fn supported_length(
+    query_pipeline: &QueryPipeline,
+    input: &MovementStepInput,
+    planar: Vector2<f32>,
+    max_drop: f32,
+) -> f32 {
+    let length = planar.norm();
+    let direction = planar / length;
+    let probes = (length / input.capsule_radius).ceil().max(1.0) as u32;
+    let mut supported = 0.0;
+    for probe in 1..=probes {
+        let distance = length * probe as f32 / probes as f32;
+        if !has_ground_ahead(query_pipeline, input, direction * distance, max_drop) {
+            break;
+        }
+        supported = distance;
+    }
+    supported
+}
+
+/// Returns true if there's ground at most `max_drop` meters below the actor's feet at the leading
+/// edge of its capsule after moving `planar`.
+pub fn has_ground_ahead(
+    query_pipeline: &QueryPipeline,
+    input: &MovementStepInput,
+    planar: Vector2<f32>,
+    max_drop: f32,
+) -> bool {
+    let ahead = planar + planar.normalize() * input.capsule_radius;
+    let feet_y = input.translation.y - input.capsule_half_height - input.capsule_radius;
+    let origin = Point3::new(
+        input.translation.x + ahead.x,
+        feet_y + LEDGE_PROBE_HEIGHT,
+        input.translation.z + ahead.y,
+    );
+    let ray = Ray::new(origin, -Vector3::y());
+    query_pipeline
+        .cast_ray(&ray, LEDGE_PROBE_HEIGHT + max_drop, true)
+        .is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ColliderShapeDef, MOVEMENT_TICK_INTERVAL_SECS, StaticQueryWorld, WorldStaticDef,
+        build_static_query_world, is_at_target, quantize_vertical_velocity,
+    };
+    use rapier3d::prelude::QueryFilter;
+
+    const STANDING_Y: f32 = 1.2;
+    const FLY_SPEED_MPS: f32 = 5.0;
+
+    /// Floors with their tops at `y = 0`, each spanning `x` in [-5, 5] and the given `z` range.
+    fn floors(ranges: &[(f32, f32)]) -> StaticQueryWorld {
+        let defs = ranges
+            .iter()
+            .enumerate()
+            .map(|(id, &(near, far))| WorldStaticDef {
+                id: id as u64,
+                translation: Vector3::new(0.0, -0.5, (near + far) * 0.5),
+                rotation: UnitQuaternion::identity(),
+                shape: ColliderShapeDef::Cuboid {
+                    half_extents: Vector3::new(5.0, 0.5, (near - far).abs() * 0.5),
+                },
+            });
+        build_static_query_world(defs, MOVEMENT_TICK_INTERVAL_SECS)
+    }
+
+    /// Walks a grounded actor from `z` towards `target_z` for `ticks`, wary of drops over 1m.
+    fn walk(
+        query_world: &StaticQueryWorld,
+        z: f32,
+        target_z: f32,
+        ticks: u32,
+    ) -> MovementStepOutput {
+        let query_pipeline = query_world.as_query_pipeline(QueryFilter::only_fixed());
+        let kcc = movement_kcc();
+        let mut input = MovementStepInput {
+            translation: Vector3::new(0.0, STANDING_Y, z),
+            yaw: 0.0,
+            capsule_radius: 0.3,
+            capsule_half_height: 0.9,
+            target_planar: Vector2::new(0.0, target_z),
+            movement_speed_mps: 3.5,
+            vertical_velocity: 0,
+            max_drop: Some(1.0),
+            flying: false,
+            target_y: None,
+        };
+        let mut output =
+            movement_step_actor(&kcc, &query_pipeline, &input, MOVEMENT_TICK_INTERVAL_SECS);
+        for _ in 1..ticks {
+            input.translation = output.translation;
+            input.vertical_velocity = output.vertical_velocity;
+            output =
+                movement_step_actor(&kcc, &query_pipeline, &input, MOVEMENT_TICK_INTERVAL_SECS);
+        }
+        output
+    }
+
+    #[test]
+    fn ledge_stops_the_actor_at_its_edge() {
+        // The floor ends at z = -3, nothing below.
+        let output = walk(&floors(&[(2.0, -3.0)]), 0.0, -8.0, 3);
+        assert_eq!(output.vertical_velocity, 0);
+        assert!((output.translation.y - STANDING_Y).abs() < 0.1);
+        // Right at the edge, not a step short of it.
+        assert!(output.translation.z >= -3.0);
+        assert!(output.translation.z <= -3.0 + 0.5);
+    }
+
+    #[test]
+    fn gap_narrower_than_a_step_is_not_jumped() {
+        // A gap the capsule fits into, between z = -3 and z = -3.8. A whole 3.5m step from z = -1
+        // ends on the far floor.
+        let output = walk(&floors(&[(2.0, -3.0), (-3.8, -10.0)]), -1.0, -8.0, 3);
+        assert_eq!(output.vertical_velocity, 0);
+        assert!((output.translation.y - STANDING_Y).abs() < 0.1);
+        assert!(output.translation.z >= -3.0);
+    }
+
+    #[test]
+    fn launch_rises_then_lands() {
+        let query_world = floors(&[(10.0, -10.0)]);
+        let query_pipeline = query_world.as_query_pipeline(QueryFilter::only_fixed());
+        let kcc = movement_kcc();
+        let mut input = MovementStepInput {
+            translation: Vector3::new(0.0, STANDING_Y, 0.0),
+            yaw: 0.0,
+            capsule_radius: 0.3,
+            capsule_half_height: 0.9,
+            target_planar: Vector2::zeros(),
+            movement_speed_mps: 0.0,
+            vertical_velocity: quantize_vertical_velocity(8.0),
+            max_drop: None,
+            flying: false,
+            target_y: None,
+        };
+
+        let dt = MOVEMENT_TICK_INTERVAL_SECS;
+        let launched = movement_step_actor(&kcc, &query_pipeline, &input, dt);
+        assert!(
+            launched.translation.y > STANDING_Y + 0.5,
+            "launch should rise, got y = {}",
+            launched.translation.y
+        );
+        assert!(!launched.grounded);
+        assert_ne!(launched.vertical_velocity, 0);
+
+        let mut output = launched;
+        for _ in 0..5 {
+            input.translation = output.translation;
+            input.vertical_velocity = output.vertical_velocity;
+            output = movement_step_actor(&kcc, &query_pipeline, &input, dt);
+            if output.vertical_velocity == 0 {
+                break;
+            }
+        }
+        assert_eq!(output.vertical_velocity, 0);
+        assert!(output.grounded);
+        assert!((output.translation.y - STANDING_Y).abs() < 0.1);
+    }
+
+    /// Flies an actor from `from` towards `to` for `ticks`.
+    fn fly(
+        query_world: &StaticQueryWorld,
+        from: Vector3<f32>,
+        to: Vector3<f32>,
+        ticks: u32,
+    ) -> MovementStepOutput {
+        let query_pipeline = query_world.as_query_pipeline(QueryFilter::only_fixed());
+        let kcc = movement_kcc();
+        let mut input = MovementStepInput {
+            translation: from,
+            yaw: 0.0,
+            capsule_radius: 0.3,
+            capsule_half_height: 0.9,
+            target_planar: to.xz(),
+            movement_speed_mps: FLY_SPEED_MPS,
+            vertical_velocity: 0,
+            max_drop: None,
+            flying: true,
+            target_y: Some(to.y),
+        };
+        let mut output = fly_step_actor(&kcc, &query_pipeline, &input, MOVEMENT_TICK_INTERVAL_SECS);
+        for _ in 1..ticks {
+            input.translation = output.translation;
+            output = fly_step_actor(&kcc, &query_pipeline, &input, MOVEMENT_TICK_INTERVAL_SECS);
+        }
+        output
+    }
+
+    /// Ticks to fly `distance` meters, with a couple to spare.
+    fn fly_ticks(distance: f32) -> u32 {
+        (distance / (FLY_SPEED_MPS * MOVEMENT_TICK_INTERVAL_SECS)).ceil() as u32 + 2
+    }
+
+    #[test]
+    fn fly_delta_is_capped_by_the_speed() {
+        let dt = MOVEMENT_TICK_INTERVAL_SECS;
+        let delta = get_fly_delta(Vector3::zeros(), Vector3::new(0.0, 30.0, -40.0), 5.0, dt);
+        assert!((delta.norm() - 5.0 * dt).abs() < 1.0e-4);
+        assert!((delta.normalize() - Vector3::new(0.0, 0.6, -0.8)).norm() < 1.0e-4);
+        // The last step ends on the target, not past it.
+        let near = Vector3::new(0.0, 1.0e-2, 0.0);
+        assert_eq!(get_fly_delta(Vector3::zeros(), near, 5.0, dt), near);
+    }
+
+    #[test]
+    fn flying_reaches_a_3d_target() {
+        let query_world = floors(&[]);
+        let from = Vector3::new(0.0, 2.0, 0.0);
+        let to = Vector3::new(3.0, 6.0, -4.0);
+        let output = fly(&query_world, from, to, fly_ticks((to - from).norm()));
+        assert!(
+            is_at_target(output.translation, to),
+            "ended at {:?}",
+            output.translation
+        );
+        assert_eq!(output.vertical_velocity, 0);
+    }
+
+    #[test]
+    fn flying_stops_on_arrival() {
+        let query_world = floors(&[]);
+        let to = Vector3::new(3.0, 6.0, -4.0);
+        let arrived = fly(&query_world, to, to, 1);
+        assert_eq!(arrived.translation, to);
+        assert_eq!(
+            get_fly_delta(to, to, FLY_SPEED_MPS, MOVEMENT_TICK_INTERVAL_SECS),
+            Vector3::zeros()
+        );
+    }
+
+    #[test]
+    fn flying_is_stopped_by_walls() {
+        // A wall across the path, its near face at z = -3.
+        let wall = WorldStaticDef {
+            id: 0,
+            translation: Vector3::new(0.0, 5.0, -3.5),
+            rotation: UnitQuaternion::identity(),
+            shape: ColliderShapeDef::Cuboid {
+                half_extents: Vector3::new(5.0, 5.0, 0.5),
+            },
+        };
+        let query_world = build_static_query_world([wall], MOVEMENT_TICK_INTERVAL_SECS);
+        let from = Vector3::new(0.0, 5.0, 0.0);
+        let to = Vector3::new(0.0, 5.0, -8.0);
+        let output = fly(&query_world, from, to, fly_ticks(8.0));
+        assert!(!is_at_target(output.translation, to));
+        // Against the wall (give or take the KCC offset), not through or inside it.
+        assert!(output.translation.z >= -3.0 + 0.3 - 0.05);
+        assert!(output.translation.z <= -3.0 + 0.3 + 0.15);
+    }
+}
@@ -0,0 +1,123 @@
+//! World partitions, square groups of cells that can later be owned by separate modules.
+//!
+//! # Model
+//! - A partition is `PARTITION_SIDE_CELLS x PARTITION_SIDE_CELLS` cells, the cell grid is
+//!   `PARTITION_GRID_SIDE x PARTITION_GRID_SIDE` partitions.
+//! - Partition ids are linearized in X-major order like cell ids:
+//!   `id = (gx / PARTITION_SIDE_CELLS) * PARTITION_GRID_SIDE + gz / PARTITION_SIDE_CELLS`.
+//!
+//! # Seams
+//! A cell whose AOI block reaches into another partition is on a seam. Viewers there see actors
+//! of both partitions, so views have to ask the owner of each one (see
+//! [`aoi_block_partitions`]). Away from seams the viewer's own partition is all there is.
+
+use crate::{
+    CellId, PartitionId,
+    cell::{decode_cell_coords, get_aoi_block_clamped},
+    constants::{PARTITION_GRID_SIDE, PARTITION_SIDE_CELLS},
+};
+
+/// Number of partitions in the world.
+pub const PARTITION_COUNT: u16 = PARTITION_GRID_SIDE * PARTITION_GRID_SIDE;
+
+/// The partition `cell_id` belongs to.
+#[inline]
+pub fn partition_of_cell(cell_id: CellId) -> PartitionId {
+    let (gx, gz) = decode_cell_coords(cell_id);
+    (gx / PARTITION_SIDE_CELLS) * PARTITION_GRID_SIDE + gz / PARTITION_SIDE_CELLS
+}
+
+/// Is the AOI block around `cell_id` spread over more than one partition?
+#[inline]
+pub fn is_seam_cell(cell_id: CellId) -> bool {
+    let (gx, gz) = decode_cell_coords(cell_id);
+    let on_edge = |g: u16| {
+        let within = g % PARTITION_SIDE_CELLS;
+        let partition = g / PARTITION_SIDE_CELLS;
+        (within == 0 && partition > 0)
+            || (within == PARTITION_SIDE_CELLS - 1 && partition < PARTITION_GRID_SIDE - 1)
+    };
+    on_edge(gx) || on_edge(gz)
+}
+
+/// The distinct partitions the clamped AOI block around `cell_id` covers, its own first. One
+/// partition unless the cell is on a seam, at most four at a partition corner.
+pub fn aoi_block_partitions(cell_id: CellId) -> impl Iterator<Item = PartitionId> {
+    let own = partition_of_cell(cell_id);
+    let mut partitions = [own; 4];
+    let mut len = 1;
+    if is_seam_cell(cell_id) {
+        for partition in get_aoi_block_clamped(cell_id).map(partition_of_cell) {
+            if !partitions[..len].contains(&partition) {
+                partitions[len] = partition;
+                len += 1;
+            }
+        }
+    }
+    partitions.into_iter().take(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::GRID_SIDE;
+
+    fn cell(gx: u16, gz: u16) -> CellId {
+        gx * GRID_SIDE + gz
+    }
+
+    #[test]
+    fn partitions_tile_the_grid() {
+        assert_eq!(GRID_SIDE % PARTITION_SIDE_CELLS, 0);
+        assert_eq!(partition_of_cell(cell(0, 0)), 0);
+        assert_eq!(partition_of_cell(cell(31, 31)), 0);
+        assert_eq!(partition_of_cell(cell(0, 32)), 1);
+        assert_eq!(partition_of_cell(cell(32, 0)), PARTITION_GRID_SIDE);
+        assert_eq!(
+            partition_of_cell(cell(GRID_SIDE - 1, GRID_SIDE - 1)),
+            PARTITION_COUNT - 1
+        );
+    }
+
+    #[test]
+    fn seams_are_the_cells_next_to_another_partition() {
+        assert!(!is_seam_cell(cell(5, 5)));
+        assert!(is_seam_cell(cell(31, 5)));
+        assert!(is_seam_cell(cell(32, 5)));
+        assert!(is_seam_cell(cell(5, 63)));
+        // The world edge isn't a seam, there's nothing beyond it.
+        assert!(!is_seam_cell(cell(0, 5)));
+        assert!(!is_seam_cell(cell(GRID_SIDE - 1, 5)));
+    }
+
+    #[test]
+    fn aoi_block_partitions_are_distinct_and_own_first() {
+        assert_eq!(
+            aoi_block_partitions(cell(5, 5)).collect::<Vec<_>>(),
+            vec![0]
+        );
+        assert_eq!(
+            aoi_block_partitions(cell(31, 5)).collect::<Vec<_>>(),
+            vec![0, PARTITION_GRID_SIDE]
+        );
+        let mut corner: Vec<_> = aoi_block_partitions(cell(32, 32)).collect();
+        assert_eq!(corner[0], PARTITION_GRID_SIDE + 1);
+        corner.sort_unstable();
+        assert_eq!(
+            corner,
+            vec![0, 1, PARTITION_GRID_SIDE, PARTITION_GRID_SIDE + 1]
+        );
+    }
+
+    #[test]
+    fn seam_matches_aoi_block() {
+        for gx in 0..GRID_SIDE {
+            for gz in [0, 1, 30, 31, 32, 33, 100, GRID_SIDE - 1] {
+                let id = cell(gx, gz);
+                let spans = get_aoi_block_clamped(id)
+                    .any(|c| partition_of_cell(c) != partition_of_cell(id));
+                assert_eq!(is_seam_cell(id), spans, "cell ({gx}, {gz})");
+            }
+        }
+    }
+}
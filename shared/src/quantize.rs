@@ -24,37 +24,6 @@
 //     (max - min) / (u16::MAX as f32)
 // }
 
-// pub fn yaw_to_u8(yaw_radians: f32) -> u8 {
-//     const SCALE: f32 = 256.0 / TAU;
-
-//     // 1. Multiply to get range approx [-128.0, 128.0]
-//     // 2. Cast to i32 to handle the negative sign
-//     // 3. Cast to u8 to truncate to the 0..255 range
-//     (yaw_radians * SCALE) as i32 as u8
-// }
-
-// /// Dequantize `u8` yaw back into radians in [0, 2π).
-// pub fn yaw_from_u8(code: u8) -> f32 {
-//     (code as f32) * (TAU / 256.0)
-// }
-
-// /// Quantize radians into a u16 [0, 65535].
-// pub fn yaw_to_u16(yaw_radians: f32) -> u16 {
-//     const SCALE: f32 = 65536.0 / TAU;
-
-//     // 1. Multiply to get range approx [-32768.0, 32768.0] (if input is -PI to PI)
-//     // 2. Cast to i32 to handle negative signs via bit wrapping
-//     // 3. Cast to u16 to truncate to the 0..65535 range
-//     (yaw_radians * SCALE) as i32 as u16
-// }
-
-// /// Dequantize `u16` yaw back into radians in [0, 2π).
-// pub fn yaw_from_u16(code: u16) -> f32 {
-//     const REV_SCALE: f32 = TAU / 65536.0;
-
-//     (code as f32) * REV_SCALE
-// }
-//
 use crate::VERTICAL_VELOCITY_Q_MPS;
 
 pub fn quantize_vertical_velocity(vel: f32) -> i8 {
@@ -0,0 +1,141 @@
+//! Deterministic pseudo random numbers for gameplay rolls.
+//!
+//! xoshiro128++ with its state expanded from a seed and a stream id by splitmix64. The same seed
+//! and stream always produce the same sequence, so rolls can be reproduced in tests and replays.
+//! The server seeds from the reducer timestamp and uses the stream to keep rolls of different
+//! actors or purposes within one reducer independent. Not cryptographically secure.
+
+use nalgebra::Vector2;
+
+/// Deterministic random number generator, see the module docs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rng {
+    state: [u32; 4],
+}
+
+/// One step of splitmix64, used to expand the seed into the generator state.
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+impl Rng {
+    pub fn new(seed: u64, stream: u64) -> Self {
+        // Mix the stream in with an odd constant so (seed, stream) pairs don't alias.
+        let mut sm = seed ^ stream.wrapping_mul(0xD605_BBB5_8C8A_BBB3);
+        let a = splitmix64(&mut sm);
+        let b = splitmix64(&mut sm);
+        let state = [a as u32, (a >> 32) as u32, b as u32, (b >> 32) as u32];
+        // The all-zero state is the one xoshiro can't leave, splitmix64 practically never yields
+        // it but a fixed fallback keeps the generator valid regardless.
+        if state == [0; 4] {
+            return Self {
+                state: [1, 2, 3, 4],
+            };
+        }
+        Self { state }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let [s0, s1, s2, s3] = &mut self.state;
+        let result = s0.wrapping_add(*s3).rotate_left(7).wrapping_add(*s0);
+        let t = *s1 << 9;
+        *s2 ^= *s0;
+        *s3 ^= *s1;
+        *s1 ^= *s2;
+        *s0 ^= *s3;
+        *s2 ^= t;
+        *s3 = s3.rotate_left(11);
+        result
+    }
+
+    /// Uniform in `[0, 1)`.
+    pub fn next_f32(&mut self) -> f32 {
+        // The top 24 bits fit the f32 mantissa exactly.
+        (self.next_u32() >> 8) as f32 * (1.0 / (1u32 << 24) as f32)
+    }
+
+    /// Uniform in `[min, max)`, `min` when the range is empty.
+    pub fn range_f32(&mut self, min: f32, max: f32) -> f32 {
+        if max <= min {
+            return min;
+        }
+        min + (max - min) * self.next_f32()
+    }
+
+    /// Uniform in `[min, max)`, `min` when the range is empty.
+    pub fn range_u32(&mut self, min: u32, max: u32) -> u32 {
+        if max <= min {
+            return min;
+        }
+        // Multiply-shift reduction, the bias is negligible for gameplay sized ranges.
+        let span = (max - min) as u64;
+        min + ((self.next_u32() as u64 * span) >> 32) as u32
+    }
+
+    /// `true` with probability `p`, clamped to `[0, 1]`.
+    pub fn chance(&mut self, p: f32) -> bool {
+        self.next_f32() < p
+    }
+
+    /// Uniform point within a disc of `radius` around the origin.
+    pub fn point_in_disc(&mut self, radius: f32) -> Vector2<f32> {
+        // sqrt keeps the density uniform over the area instead of bunching up at the center.
+        let distance = radius * self.next_f32().sqrt();
+        let angle = self.next_f32() * std::f32::consts::TAU;
+        Vector2::new(angle.cos(), angle.sin()) * distance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_and_stream_repeat() {
+        let mut a = Rng::new(42, 7);
+        let mut b = Rng::new(42, 7);
+        for _ in 0..100 {
+            assert_eq!(a.next_u32(), b.next_u32());
+        }
+    }
+
+    #[test]
+    fn streams_are_independent() {
+        let mut a = Rng::new(42, 1);
+        let mut b = Rng::new(42, 2);
+        let same = (0..100).filter(|_| a.next_u32() == b.next_u32()).count();
+        assert!(same < 3, "{same} equal outputs");
+    }
+
+    #[test]
+    fn ranges_stay_in_bounds() {
+        let mut rng = Rng::new(1, 0);
+        for _ in 0..1000 {
+            let f = rng.range_f32(-2.0, 3.0);
+            assert!((-2.0..3.0).contains(&f));
+            let u = rng.range_u32(5, 9);
+            assert!((5..9).contains(&u));
+        }
+        assert_eq!(rng.range_u32(4, 4), 4);
+        assert_eq!(rng.range_f32(1.0, 0.0), 1.0);
+    }
+
+    #[test]
+    fn chance_extremes() {
+        let mut rng = Rng::new(3, 0);
+        assert!((0..100).all(|_| !rng.chance(0.0)));
+        assert!((0..100).all(|_| rng.chance(1.0)));
+    }
+
+    #[test]
+    fn disc_points_within_radius() {
+        let mut rng = Rng::new(9, 4);
+        for _ in 0..1000 {
+            assert!(rng.point_in_disc(2.5).norm() <= 2.5 + 1.0e-5);
+        }
+    }
+}
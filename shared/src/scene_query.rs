@@ -0,0 +1,253 @@
+//! Scene queries over the static world and the actors together.
+//!
+//! Actors aren't part of the [`StaticQueryWorld`], they move every tick. An
+//! [`ActorColliderLayer`] is a transient query world of their capsules, built for one tick and
+//! thrown away, each collider tagged with its actor's id. [`SceneQuery`] runs ray casts and
+//! overlap tests against both and reports what was hit as a [`SceneHit`], so systems ask one
+//! place instead of walking the cells for candidates themselves.
+
+use crate::{
+    ActorId,
+    utils::{StaticQueryWorld, build_query_world},
+};
+use nalgebra::{Isometry3, Vector3};
+use rapier3d::{
+    parry::query::RayCast,
+    prelude::{Capsule, ColliderBuilder, QueryFilter, Ray, Shape},
+};
+
+/// An actor's capsule as of the tick the layer is built for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ActorCollider {
+    pub actor_id: ActorId,
+    /// Center of the capsule.
+    pub translation: Vector3<f32>,
+    pub radius: f32,
+    pub half_height: f32,
+}
+
+/// The actor capsules of one instance at one tick, see the module docs.
+pub struct ActorColliderLayer {
+    world: StaticQueryWorld,
+    len: usize,
+}
+
+impl ActorColliderLayer {
+    pub fn build(actors: impl IntoIterator<Item = ActorCollider>, dt: f32) -> Self {
+        let colliders: Vec<_> = actors
+            .into_iter()
+            .map(|actor| {
+                ColliderBuilder::capsule_y(actor.half_height, actor.radius)
+                    .translation(actor.translation)
+                    .user_data(actor.actor_id as u128)
+                    .build()
+            })
+            .collect();
+        let len = colliders.len();
+        Self {
+            world: build_query_world(colliders.into_iter(), dt),
+            len,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The actors whose capsules overlap `shape` at `shape_pos`, in no particular order.
+    pub fn intersect_shape(
+        &self,
+        shape_pos: Isometry3<f32>,
+        shape: &dyn Shape,
+    ) -> impl Iterator<Item = ActorId> {
+        self.world
+            .as_query_pipeline(QueryFilter::only_fixed())
+            .intersect_shape(shape_pos, shape)
+            .map(|(_, collider)| collider.user_data as ActorId)
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    /// The first actor along `ray` within `max_toi`, with its capsule grown by `margin` (e.g. the
+    /// radius of a projectile), among those `hittable` accepts.
+    fn cast_ray(
+        &self,
+        ray: &Ray,
+        max_toi: f32,
+        margin: f32,
+        hittable: impl Fn(ActorId) -> bool,
+    ) -> Option<(ActorId, f32)> {
+        // Candidates are the capsules the swept margin touches, then each is hit precisely.
+        let end = ray.point_at(max_toi);
+        let sweep = Capsule::new(ray.origin, end, margin.max(f32::EPSILON));
+        self.world
+            .as_query_pipeline(QueryFilter::only_fixed())
+            .intersect_shape(Isometry3::identity(), &sweep)
+            .filter_map(|(_, collider)| {
+                let actor_id = collider.user_data as ActorId;
+                if !hittable(actor_id) {
+                    return None;
+                }
+                let capsule = collider.shape().as_capsule()?;
+                let grown = Capsule {
+                    segment: capsule.segment,
+                    radius: capsule.radius + margin,
+                };
+                grown
+                    .cast_ray(collider.position(), ray, max_toi, true)
+                    .map(|toi| (actor_id, toi))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+    }
+}
+
+/// What a scene query hit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SceneHit {
+    /// A world static, by [`crate::WorldStaticDef::id`].
+    Static(u64),
+    Actor(ActorId),
+}
+
+/// The static world and the actors of one instance, queried together.
+#[derive(Clone, Copy)]
+pub struct SceneQuery<'a> {
+    pub statics: &'a StaticQueryWorld,
+    pub actors: &'a ActorColliderLayer,
+}
+
+impl<'a> SceneQuery<'a> {
+    pub fn new(statics: &'a StaticQueryWorld, actors: &'a ActorColliderLayer) -> Self {
+        Self { statics, actors }
+    }
+
+    /// The first static or actor along `ray` within `max_toi`, and the time of impact. Actor
+    /// capsules are grown by `margin`, actors `hittable` rejects (e.g. the shooter) are passed
+    /// through.
+    pub fn cast_ray(
+        &self,
+        ray: &Ray,
+        max_toi: f32,
+        margin: f32,
+        hittable: impl Fn(ActorId) -> bool,
+    ) -> Option<(SceneHit, f32)> {
+        let static_hit = self
+            .statics
+            .as_query_pipeline(QueryFilter::only_fixed())
+            .cast_ray(ray, max_toi, true)
+            .and_then(|(handle, toi)| {
+                let id = self.statics.world_static_id(handle)?;
+                Some((SceneHit::Static(id), toi))
+            });
+        let actor_hit = self
+            .actors
+            .cast_ray(ray, max_toi, margin, hittable)
+            .map(|(actor_id, toi)| (SceneHit::Actor(actor_id), toi));
+
+        match (static_hit, actor_hit) {
+            (Some(static_hit), Some(actor_hit)) if static_hit.1 < actor_hit.1 => Some(static_hit),
+            (_, Some(actor_hit)) => Some(actor_hit),
+            (static_hit, None) => static_hit,
+        }
+    }
+
+    /// The actors overlapping `shape` at `shape_pos`, see [`ActorColliderLayer::intersect_shape`].
+    pub fn intersections_with_shape(
+        &self,
+        shape_pos: Isometry3<f32>,
+        shape: &dyn Shape,
+    ) -> impl Iterator<Item = ActorId> {
+        self.actors.intersect_shape(shape_pos, shape)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ColliderShapeDef, MOVEMENT_TICK_INTERVAL_SECS, WorldStaticDef, build_static_query_world,
+    };
+    use nalgebra::{Point3, UnitQuaternion};
+    use rapier3d::prelude::Ball;
+
+    const DT: f32 = MOVEMENT_TICK_INTERVAL_SECS;
+
+    fn actor(actor_id: ActorId, x: f32, z: f32) -> ActorCollider {
+        ActorCollider {
+            actor_id,
+            translation: Vector3::new(x, 1.2, z),
+            radius: 0.3,
+            half_height: 0.9,
+        }
+    }
+
+    /// A wall across X at `x`.
+    fn wall(x: f32) -> StaticQueryWorld {
+        build_static_query_world(
+            [WorldStaticDef {
+                id: 7,
+                translation: Vector3::new(x, 2.0, 0.0),
+                rotation: UnitQuaternion::identity(),
+                shape: ColliderShapeDef::Cuboid {
+                    half_extents: Vector3::new(0.5, 2.0, 10.0),
+                },
+            }],
+            DT,
+        )
+    }
+
+    fn ray_along_x() -> Ray {
+        Ray::new(Point3::new(0.0, 1.2, 0.0), Vector3::x())
+    }
+
+    #[test]
+    fn ray_hits_the_nearest_of_actors_and_statics() {
+        let statics = wall(6.0);
+        let actors = ActorColliderLayer::build([actor(1, 3.0, 0.0), actor(2, 9.0, 0.0)], DT);
+        let scene = SceneQuery::new(&statics, &actors);
+
+        let (hit, toi) = scene.cast_ray(&ray_along_x(), 20.0, 0.0, |_| true).unwrap();
+        assert_eq!(hit, SceneHit::Actor(1));
+        assert!((toi - 2.7).abs() < 1.0e-3);
+
+        // Without the first actor the wall is in front of the second.
+        let (hit, _) = scene
+            .cast_ray(&ray_along_x(), 20.0, 0.0, |id| id != 1)
+            .unwrap();
+        assert_eq!(hit, SceneHit::Static(7));
+    }
+
+    #[test]
+    fn margin_grows_the_capsules() {
+        let statics = build_static_query_world([], DT);
+        let actors = ActorColliderLayer::build([actor(1, 3.0, 0.5)], DT);
+        let scene = SceneQuery::new(&statics, &actors);
+
+        assert_eq!(scene.cast_ray(&ray_along_x(), 20.0, 0.0, |_| true), None);
+        let (hit, _) = scene
+            .cast_ray(&ray_along_x(), 20.0, 0.25, |_| true)
+            .unwrap();
+        assert_eq!(hit, SceneHit::Actor(1));
+    }
+
+    #[test]
+    fn shape_overlaps_return_actor_ids() {
+        let statics = build_static_query_world([], DT);
+        let actors = ActorColliderLayer::build(
+            [actor(1, 0.0, 0.0), actor(2, 2.0, 0.0), actor(3, 10.0, 0.0)],
+            DT,
+        );
+        assert_eq!(actors.len(), 3);
+        let scene = SceneQuery::new(&statics, &actors);
+
+        let mut hits: Vec<_> = scene
+            .intersections_with_shape(Isometry3::translation(1.0, 1.2, 0.0), &Ball::new(1.5))
+            .collect();
+        hits.sort_unstable();
+        assert_eq!(hits, vec![1, 2]);
+    }
+}
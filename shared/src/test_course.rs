@@ -0,0 +1,325 @@
+//! The test course the server seeds into the overworld next to the spawn: a box, a ramp and a
+//! staircase for checking slopes, snap-to-ground and autostep by hand.
+//!
+//! The geometry lives here so the regression scenarios below walk the exact course players see.
+//! Tuning the KCC or autostep has to keep them passing.
+
+use nalgebra::{UnitQuaternion, Vector3};
+
+/// First step of the staircase, centered on its front edge on the ground.
+pub const STAIRS_ORIGIN: [f32; 3] = [0.0, 0.0, -6.0];
+
+/// Depth of one step along +X (meters).
+pub const STAIR_RUN: f32 = 0.55;
+
+/// Height of one step (meters).
+pub const STAIR_RISE: f32 = 0.4;
+
+pub const STAIR_COUNT: u32 = 20;
+
+/// Half the width of the staircase along Z (meters).
+pub const STAIR_HALF_WIDTH: f32 = 1.5;
+
+/// Center of the ramp, tilted by [`RAMP_TILT_DEGREES`] so moving +Z goes uphill.
+pub const RAMP_CENTER: [f32; 3] = [-3.0, 0.0, 6.0];
+
+pub const RAMP_HALF_EXTENTS: [f32; 3] = [1.0, 1.0, 10.0];
+
+pub const RAMP_TILT_DEGREES: f32 = 20.0;
+
+/// Center and half extents of step `i`, counted from the bottom.
+pub fn stair_step(i: u32) -> (Vector3<f32>, Vector3<f32>) {
+    let half_extents = Vector3::new(STAIR_RUN * 0.5, STAIR_RISE * 0.5, STAIR_HALF_WIDTH);
+    let origin = Vector3::from(STAIRS_ORIGIN);
+    let center = origin + Vector3::new(i as f32 * STAIR_RUN, i as f32 * STAIR_RISE, 0.0);
+    (center + Vector3::y() * half_extents.y, half_extents)
+}
+
+pub fn ramp_rotation() -> UnitQuaternion<f32> {
+    UnitQuaternion::from_axis_angle(&Vector3::x_axis(), -RAMP_TILT_DEGREES.to_radians())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ColliderShapeDef, MOVEMENT_TICK_INTERVAL_SECS, MovementStepInput, MovementStepOutput,
+        StaticQueryWorld, WorldStaticDef, autostep_max_height, build_static_query_world,
+        movement_kcc, movement_step_actor,
+    };
+    use nalgebra::Vector2;
+    use rapier3d::prelude::QueryFilter;
+
+    /// Short client-rate steps, and the server tick's, which cover several stair runs each.
+    const DTS: [f32; 2] = [1.0 / 30.0, MOVEMENT_TICK_INTERVAL_SECS];
+
+    /// The player character's capsule.
+    const RADIUS: f32 = 0.3;
+    const HALF_HEIGHT: f32 = 0.9;
+
+    const SPEED_MPS: f32 = 5.0;
+
+    /// Slack for the controller's skin offset and snapping distance.
+    const HEIGHT_TOLERANCE: f32 = 0.15;
+
+    /// The seeded course on a ground plane.
+    fn course() -> StaticQueryWorld {
+        let mut defs = vec![
+            WorldStaticDef {
+                id: 0,
+                translation: Vector3::zeros(),
+                rotation: UnitQuaternion::identity(),
+                shape: ColliderShapeDef::Plane {
+                    offset_along_normal: 0.0,
+                },
+            },
+            WorldStaticDef {
+                id: 1,
+                translation: Vector3::from(RAMP_CENTER),
+                rotation: ramp_rotation(),
+                shape: ColliderShapeDef::Cuboid {
+                    half_extents: Vector3::from(RAMP_HALF_EXTENTS),
+                },
+            },
+        ];
+        for i in 0..STAIR_COUNT {
+            let (center, half_extents) = stair_step(i);
+            defs.push(WorldStaticDef {
+                id: defs.len() as u64,
+                translation: center,
+                rotation: UnitQuaternion::identity(),
+                shape: ColliderShapeDef::Cuboid { half_extents },
+            });
+        }
+        build_static_query_world(defs, MOVEMENT_TICK_INTERVAL_SECS)
+    }
+
+    fn feet(step: &MovementStepOutput) -> f32 {
+        step.translation.y - HALF_HEIGHT - RADIUS
+    }
+
+    /// Height of the ramp's top face above `z`, ignoring where it sinks below the ground.
+    fn ramp_surface_height(z: f32) -> f32 {
+        let tilt = RAMP_TILT_DEGREES.to_radians();
+        let top_center = RAMP_CENTER[1] + RAMP_HALF_EXTENTS[1] / tilt.cos();
+        top_center + (z - RAMP_CENTER[2]) * tilt.tan()
+    }
+
+    /// Per tick diagnostics of a walk, so a failing scenario says where it went wrong.
+    struct Walk {
+        dt: f32,
+        ticks: Vec<MovementStepOutput>,
+    }
+
+    impl Walk {
+        /// Walks from `start` (feet on the ground) towards `target`, in ticks of `dt`.
+        fn run(
+            world: &StaticQueryWorld,
+            dt: f32,
+            start: Vector3<f32>,
+            target: Vector2<f32>,
+        ) -> Self {
+            Self::settle_at(world, dt, start).continue_to(world, target)
+        }
+
+        /// Drops an actor at `start` and lets it settle onto the ground.
+        fn settle_at(world: &StaticQueryWorld, dt: f32, start: Vector3<f32>) -> Self {
+            let query_pipeline = world.as_query_pipeline(QueryFilter::only_fixed());
+            let kcc = movement_kcc();
+            let mut input = MovementStepInput {
+                translation: start + Vector3::y() * (HALF_HEIGHT + RADIUS + 0.05),
+                yaw: 0.0,
+                capsule_radius: RADIUS,
+                capsule_half_height: HALF_HEIGHT,
+                target_planar: start.xz(),
+                movement_speed_mps: SPEED_MPS,
+                vertical_velocity: -1,
+                max_drop: None,
+                flying: false,
+                target_y: None,
+            };
+            let mut step = movement_step_actor(&kcc, &query_pipeline, &input, dt);
+            for _ in 0..30 {
+                input.translation = step.translation;
+                input.vertical_velocity = step.vertical_velocity;
+                step = movement_step_actor(&kcc, &query_pipeline, &input, dt);
+            }
+            Self {
+                dt,
+                ticks: vec![step],
+            }
+        }
+
+        /// Walks on from where the last walk ended, keeping only that tick of it.
+        fn continue_to(mut self, world: &StaticQueryWorld, target: Vector2<f32>) -> Self {
+            let query_pipeline = world.as_query_pipeline(QueryFilter::only_fixed());
+            let kcc = movement_kcc();
+            let last = self.last();
+            let mut input = MovementStepInput {
+                translation: last.translation,
+                yaw: last.yaw,
+                capsule_radius: RADIUS,
+                capsule_half_height: HALF_HEIGHT,
+                target_planar: target,
+                movement_speed_mps: SPEED_MPS,
+                vertical_velocity: last.vertical_velocity,
+                max_drop: None,
+                flying: false,
+                target_y: None,
+            };
+            let distance = (target - last.translation.xz()).norm();
+            // Twice the flat walking time, plus time to settle.
+            let max_ticks = (2.0 * distance / SPEED_MPS / self.dt) as usize + 30;
+            self.ticks = vec![last];
+            for _ in 0..max_ticks {
+                let step = movement_step_actor(&kcc, &query_pipeline, &input, self.dt);
+                self.ticks.push(step);
+                input.translation = step.translation;
+                input.yaw = step.yaw;
+                input.vertical_velocity = step.vertical_velocity;
+                if (step.translation.xz() - target).norm() < 1.0e-3 && step.grounded {
+                    break;
+                }
+            }
+            self
+        }
+
+        fn last(&self) -> MovementStepOutput {
+            *self.ticks.last().unwrap()
+        }
+
+        /// Height change of every tick, with the tick's index.
+        fn gains(&self) -> impl Iterator<Item = (usize, f32)> + '_ {
+            self.ticks
+                .windows(2)
+                .enumerate()
+                .map(|(i, pair)| (i + 1, feet(&pair[1]) - feet(&pair[0])))
+        }
+
+        /// Total height gained (`up`) or lost over the walk, a bounce counts both ways.
+        fn total_climb(&self, up: bool) -> f32 {
+            self.gains()
+                .map(|(_, gain)| if up { gain.max(0.0) } else { (-gain).max(0.0) })
+                .sum()
+        }
+
+        fn airborne_ticks(&self) -> usize {
+            self.ticks.iter().filter(|step| !step.grounded).count()
+        }
+
+        fn assert_reached(&self, target: Vector2<f32>, feet_height: f32) {
+            let last = self.last();
+            assert!(
+                (last.translation.xz() - target).norm() < 0.05,
+                "dt {}: stopped short of {target:?} at {:?} after {} ticks",
+                self.dt,
+                last.translation,
+                self.ticks.len()
+            );
+            assert!(
+                last.grounded,
+                "dt {}: not grounded at the end: {last:?}",
+                self.dt
+            );
+            assert!(
+                (feet(&last) - feet_height).abs() < HEIGHT_TOLERANCE,
+                "dt {}: feet at {} instead of {feet_height}",
+                self.dt,
+                feet(&last)
+            );
+        }
+    }
+
+    #[test]
+    fn seeded_stairs_are_climbed_up_and_down() {
+        let world = course();
+        let bottom = Vector2::new(STAIRS_ORIGIN[0] - 2.0, STAIRS_ORIGIN[2]);
+        let (top_step, top_half) = stair_step(STAIR_COUNT - 1);
+        let top = top_step.xz();
+        let top_height = top_step.y + top_half.y;
+        let max_step = autostep_max_height(RADIUS, HALF_HEIGHT);
+        assert!(
+            STAIR_RISE < max_step,
+            "the stairs are steeper than autostep climbs"
+        );
+
+        for dt in DTS {
+            let up = Walk::run(&world, dt, Vector3::new(bottom.x, 0.0, bottom.y), top);
+            for (tick, gain) in up.gains() {
+                assert!(
+                    gain > -0.05,
+                    "dt {dt}: tick {tick} slid back down by {gain}"
+                );
+            }
+            up.assert_reached(top, top_height);
+            // A tick at the server's rate crosses several stairs, per tick bounds say little.
+            // Popping up over a step and falling back onto it shows in the totals instead.
+            let climbed = up.total_climb(true);
+            assert!(
+                climbed <= top_height + HEIGHT_TOLERANCE,
+                "dt {dt}: climbed {climbed} in total, the stairs are {top_height} high"
+            );
+            assert!(
+                up.airborne_ticks() <= up.ticks.len() / 10,
+                "dt {dt}: airborne on {} of {} ticks going up",
+                up.airborne_ticks(),
+                up.ticks.len()
+            );
+
+            let down = up.continue_to(&world, bottom);
+            for (tick, gain) in down.gains() {
+                assert!(
+                    gain < 0.05,
+                    "dt {dt}: tick {tick} went up by {gain} going down"
+                );
+            }
+            down.assert_reached(bottom, 0.0);
+            let dropped = down.total_climb(false);
+            assert!(
+                dropped <= top_height + HEIGHT_TOLERANCE,
+                "dt {dt}: dropped {dropped} in total, the stairs are {top_height} high"
+            );
+            // Walking down, not falling from step to step.
+            assert!(
+                down.airborne_ticks() <= down.ticks.len() / 2,
+                "dt {dt}: airborne on {} of {} ticks going down",
+                down.airborne_ticks(),
+                down.ticks.len()
+            );
+        }
+    }
+
+    #[test]
+    fn seeded_ramp_is_walked_up_and_down() {
+        let world = course();
+        let bottom = Vector2::new(RAMP_CENTER[0], 0.0);
+        let top = Vector2::new(RAMP_CENTER[0], 14.0);
+
+        for dt in DTS {
+            let up = Walk::run(&world, dt, Vector3::new(bottom.x, 0.0, bottom.y), top);
+            for (tick, gain) in up.gains() {
+                assert!(
+                    gain > -0.05,
+                    "dt {dt}: tick {tick} slid back down by {gain}"
+                );
+            }
+            up.assert_reached(top, ramp_surface_height(top.y));
+
+            // Snap-to-ground keeps the actor on the slope instead of skipping down it.
+            let down = up.continue_to(&world, bottom);
+            for (tick, gain) in down.gains() {
+                assert!(
+                    gain < 0.05,
+                    "dt {dt}: tick {tick} went up by {gain} going down"
+                );
+            }
+            assert!(
+                down.airborne_ticks() <= down.ticks.len() / 10,
+                "dt {dt}: airborne on {} of {} ticks going down",
+                down.airborne_ticks(),
+                down.ticks.len()
+            );
+            down.assert_reached(bottom, 0.0);
+        }
+    }
+}
@@ -1,29 +1,37 @@
 use crate::{
     GRAVITY_MPS2, MAX_INTENT_DISTANCE_SQ, SMALLEST_REQUEST_DISTANCE_SQ, TERMINAL_FALL_SPEED_MPS,
-    WorldStaticDef, YAW_EPS, collider_from_def, dequantize_vertical_velocity,
-    quantize_vertical_velocity,
+    WorldStaticDef, clamp_planar, collider_from_def, dequantize_vertical_velocity,
+    planar_distance_sq, quantize_vertical_velocity,
 };
-use nalgebra::{Isometry, Translation3, Vector2, Vector3};
+use nalgebra::{Isometry, Isometry3, Translation3, Vector2, Vector3};
 use rapier3d::prelude::{
-    BroadPhaseBvh, ColliderSet, IntegrationParameters, NarrowPhase, QueryFilter, QueryPipeline,
-    RigidBodySet,
+    BroadPhaseBvh, Collider, ColliderHandle, ColliderSet, IntegrationParameters, NarrowPhase,
+    QueryFilter, QueryPipeline, Ray, RigidBodySet, Shape,
 };
-// use std::f32::consts::TAU;
-
-pub fn yaw_from_xz(xz: Vector2<f32>) -> Option<f32> {
-    if xz.norm_squared() > YAW_EPS {
-        return Some((-xz[0]).atan2(-xz[1]));
-    }
-
-    None
-}
-
 /// Returns true if two world positions are within the planar (XZ) acceptance radius.
 pub fn is_at_target_planar(current: Vector2<f32>, target: Vector2<f32>) -> bool {
     const CM_SQ: f32 = 1.0e-4;
     (target - current).norm_squared() <= CM_SQ
 }
 
+/// Pops the first waypoint of a path intent once `current` has reached it.
+///
+/// This is the single definition of path consumption, the server movement tick and the client
+/// extrapolation both use it so predicted movement follows multi-waypoint paths the same way.
+/// Returns `true` when the path is now empty and the intent should be cleared.
+pub fn consume_reached_waypoint<T>(
+    path: &mut Vec<T>,
+    current: Vector2<f32>,
+    to_planar: impl Fn(&T) -> Vector2<f32>,
+) -> bool {
+    if let Some(first) = path.first() {
+        if is_at_target_planar(current, to_planar(first)) {
+            path.remove(0);
+        }
+    }
+    path.is_empty()
+}
+
 pub fn get_desired_delta(
     current_planar: Vector2<f32>,
     target_planar: Vector2<f32>,
@@ -35,17 +43,12 @@ pub fn get_desired_delta(
     const AIR_CONTROL_REDUCTION: f32 = 0.5;
     const MM_SQ: f32 = 1.0e-6;
 
-    let max_step = movement_speed_mps * dt;
-    let dx = target_planar.x - current_planar.x;
-    let dz = target_planar.y - current_planar.y;
-    let dist_sq = dx * dx + dz * dz;
-
-    let (x, z) = if dist_sq <= MM_SQ {
+    let to_target = target_planar - current_planar;
+    let (x, z) = if to_target.norm_squared() <= MM_SQ {
         (0.0, 0.0)
     } else {
-        let dist = dist_sq.sqrt();
-        let scale = max_step.min(dist) / dist;
-        (dx * scale, dz * scale)
+        let step = clamp_planar(to_target, movement_speed_mps * dt);
+        (step.x, step.y)
     };
 
     if vertical_velocity == 0 {
@@ -63,9 +66,35 @@ pub fn get_desired_delta(
     }
 }
 
-/// Gets the next vertical velocity step while falling
+/// The free-fly counterpart of [`get_desired_delta`]: straight towards `target` in 3D at
+/// `movement_speed_mps`, without gravity or ground bias.
+pub fn get_fly_delta(
+    current: Vector3<f32>,
+    target: Vector3<f32>,
+    movement_speed_mps: f32,
+    dt: f32,
+) -> Vector3<f32> {
+    let delta = target - current;
+    let dist = delta.norm();
+    if dist <= 1.0e-3 {
+        return Vector3::zeros();
+    }
+    delta * ((movement_speed_mps * dt).min(dist) / dist)
+}
+
+/// Returns true if two world positions are within the acceptance radius in 3D, the flying
+/// counterpart of [`is_at_target_planar`].
+pub fn is_at_target(current: Vector3<f32>, target: Vector3<f32>) -> bool {
+    const CM_SQ: f32 = 1.0e-4;
+    (target - current).norm_squared() <= CM_SQ
+}
+
+/// Gets the next vertical velocity step while airborne, rising (after a launch) or falling.
+///
+/// `0` means grounded and stays `0`. A rising actor passing its apex goes straight to `-1`
+/// (falling) rather than through `0`, which would read as landed.
 pub fn advance_vertical_velocity(vel_q: i8, dt: f32) -> i8 {
-    if vel_q >= 0 {
+    if vel_q == 0 {
         return 0;
     }
 
@@ -74,20 +103,26 @@ pub fn advance_vertical_velocity(vel_q: i8, dt: f32) -> i8 {
     // Semi-implicit Euler: v(t+dt) = v(t) + g*dt
     let mut v1_mps = v0_mps + GRAVITY_MPS2 * dt;
 
-    // Clamp to terminal fall speed (negative/downward). We only do this when already falling.
+    // Clamp to terminal fall speed (negative/downward).
     if v1_mps < TERMINAL_FALL_SPEED_MPS {
         v1_mps = TERMINAL_FALL_SPEED_MPS;
     }
 
     // Re-quantize to i8.
-    quantize_vertical_velocity(v1_mps)
+    match quantize_vertical_velocity(v1_mps) {
+        0 => -1,
+        v1_q => v1_q,
+    }
 }
 
-/// Planar (XZ) distance squared between two world positions (meters^2).
-pub fn planar_distance_sq(a: Vector2<f32>, b: Vector2<f32>) -> f32 {
-    let x = b.x - a.x;
-    let z = b.y - a.y;
-    x * x + z * z
+/// Returns true if server tick `a` is strictly newer than server tick `b`.
+///
+/// Ticks are `u32` counters that wrap, so this compares using wrapping arithmetic and is correct
+/// as long as the two ticks are less than `u32::MAX / 2` apart. Client intent sequence numbers
+/// wrap the same way and are compared with it too.
+pub fn is_tick_newer(a: u32, b: u32) -> bool {
+    let diff = a.wrapping_sub(b);
+    diff != 0 && diff < u32::MAX / 2
 }
 
 /// Are two positions within a planar movement range (meters)?
@@ -116,20 +151,71 @@ impl StaticQueryWorld {
             filter,
         )
     }
+
+    /// The [`WorldStaticDef::id`] of the collider a query hit.
+    pub fn world_static_id(&self, handle: ColliderHandle) -> Option<u64> {
+        self.colliders.get(handle).map(|c| c.user_data as u64)
+    }
+
+    /// Returns true if `shape` at `shape_pos` overlaps any static collider, ignoring the infinite
+    /// ground planes (everything rests on those) and the static with id `ignore_id`.
+    pub fn overlaps_static(
+        &self,
+        shape_pos: Isometry3<f32>,
+        shape: &dyn Shape,
+        ignore_id: Option<u64>,
+    ) -> bool {
+        let predicate = |_, collider: &Collider| {
+            collider.shape().as_halfspace().is_none()
+                && Some(collider.user_data as u64) != ignore_id
+        };
+        self.as_query_pipeline(QueryFilter::only_fixed().predicate(&predicate))
+            .intersect_shape(shape_pos, shape)
+            .next()
+            .is_some()
+    }
+}
+
+/// Finds the [`WorldStaticDef::id`] of the ground directly below `origin` within `max_distance`
+/// meters, e.g. to classify the surface an actor is standing on.
+pub fn ground_static_id(
+    query_world: &StaticQueryWorld,
+    query_pipeline: &QueryPipeline,
+    origin: Vector3<f32>,
+    max_distance: f32,
+) -> Option<u64> {
+    let ray = Ray::new(origin.into(), -Vector3::y());
+    query_pipeline
+        .cast_ray(&ray, max_distance, true)
+        .and_then(|(handle, _)| query_world.world_static_id(handle))
 }
 
 pub fn build_static_query_world(
     world_statics: impl IntoIterator<Item = WorldStaticDef>,
     dt: f32,
 ) -> StaticQueryWorld {
-    let bodies = RigidBodySet::new();
-    let mut colliders = ColliderSet::new();
-    let mut modified_colliders = Vec::new();
-
-    world_statics.into_iter().for_each(|def| {
+    let colliders = world_statics.into_iter().map(|def| {
         let mut collider = collider_from_def(&def);
+        collider.user_data = def.id as u128;
         let iso = Isometry::from_parts(Translation3::from(def.translation), def.rotation);
         collider.set_position(iso);
+        collider
+    });
+    build_query_world(colliders, dt)
+}
+
+/// Builds a query world over parentless (fixed) colliders, already positioned and tagged with
+/// their `user_data`.
+pub(crate) fn build_query_world(
+    positioned: impl Iterator<Item = Collider>,
+    dt: f32,
+) -> StaticQueryWorld {
+    let capacity = positioned.size_hint().0;
+    let bodies = RigidBodySet::new();
+    let mut colliders = ColliderSet::with_capacity(capacity);
+    let mut modified_colliders = Vec::with_capacity(capacity);
+
+    positioned.for_each(|collider| {
         let co_handle = colliders.insert(collider);
         modified_colliders.push(co_handle);
     });
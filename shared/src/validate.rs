@@ -0,0 +1,241 @@
+//! Sanitization of untrusted floats from reducer arguments.
+//!
+//! Every reducer taking positions, directions or rotations runs them through these before use, so
+//! NaN/inf and out of world input is rejected the same way everywhere. The helpers return the
+//! (possibly normalized or clamped) value so callers use the sanitized one.
+
+use crate::{
+    MAX_ACTOR_CAPSULE_HEIGHT, MAX_ACTOR_CAPSULE_RADIUS, MAX_ACTOR_SCALE, MIN_ACTOR_SCALE,
+    WORLD_BORDER_HEIGHT, WORLD_OFFSET,
+};
+use nalgebra::{Quaternion, UnitQuaternion, Vector2, Vector3};
+
+/// Smallest squared norm a quaternion may have to be normalized into a rotation.
+const MIN_QUAT_NORM_SQ: f32 = 1.0e-6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationError {
+    /// A component is NaN or infinite.
+    NonFinite,
+    /// The position is outside the playable world, see [`within_world`].
+    OutOfWorld,
+    /// The position is beyond the world bounds inside the border walls, see [`within_bounds_xz`].
+    OutOfBounds,
+    /// The quaternion is (close to) zero and can't be normalized.
+    DegenerateRotation,
+    /// The scale factor is outside [`MIN_ACTOR_SCALE`, `MAX_ACTOR_SCALE`].
+    ScaleOutOfRange,
+    /// The scaled capsule is too large to navigate, see [`scaled_capsule`].
+    CapsuleTooLarge,
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ValidationError::NonFinite => "Value is not finite",
+            ValidationError::OutOfWorld => "Position is outside the world",
+            ValidationError::OutOfBounds => "Position is beyond the world bounds",
+            ValidationError::DegenerateRotation => "Rotation is degenerate",
+            ValidationError::ScaleOutOfRange => "Scale is out of range",
+            ValidationError::CapsuleTooLarge => "Scaled capsule is too large",
+        })
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Reducers return `Result<(), String>`, this lets them use `?` on the helpers.
+impl From<ValidationError> for String {
+    fn from(err: ValidationError) -> Self {
+        err.to_string()
+    }
+}
+
+pub fn finite_vec2(v: Vector2<f32>) -> Result<Vector2<f32>, ValidationError> {
+    if v.iter().all(|c| c.is_finite()) {
+        Ok(v)
+    } else {
+        Err(ValidationError::NonFinite)
+    }
+}
+
+pub fn finite_vec3(v: Vector3<f32>) -> Result<Vector3<f32>, ValidationError> {
+    if v.iter().all(|c| c.is_finite()) {
+        Ok(v)
+    } else {
+        Err(ValidationError::NonFinite)
+    }
+}
+
+/// Rejects positions outside the world grid (XZ) or beyond the border walls' height (Y).
+pub fn within_world(v: Vector3<f32>) -> Result<Vector3<f32>, ValidationError> {
+    let v = finite_vec3(v)?;
+    if v.x.abs() > WORLD_OFFSET || v.z.abs() > WORLD_OFFSET || v.y.abs() > WORLD_BORDER_HEIGHT {
+        return Err(ValidationError::OutOfWorld);
+    }
+    Ok(v)
+}
+
+/// Like [`within_world`] but clamps positions outside the world onto its edge instead, for input
+/// where "as far as possible" is a sensible reading, e.g. a move target past the border.
+pub fn clamped_to_world(v: Vector3<f32>) -> Result<Vector3<f32>, ValidationError> {
+    let v = finite_vec3(v)?;
+    Ok(Vector3::new(
+        v.x.clamp(-WORLD_OFFSET, WORLD_OFFSET),
+        v.y.clamp(-WORLD_BORDER_HEIGHT, WORLD_BORDER_HEIGHT),
+        v.z.clamp(-WORLD_OFFSET, WORLD_OFFSET),
+    ))
+}
+
+/// The planar (XZ) counterpart of [`clamped_to_world`].
+pub fn clamped_to_world_xz(v: Vector2<f32>) -> Result<Vector2<f32>, ValidationError> {
+    let v = finite_vec2(v)?;
+    Ok(v.map(|c| c.clamp(-WORLD_OFFSET, WORLD_OFFSET)))
+}
+
+/// Rejects planar positions outside the `min`..=`max` rectangle, e.g. move targets beyond the
+/// world bounds (see [`crate::PLAYABLE_HALF_EXTENT`]).
+pub fn within_bounds_xz(
+    v: Vector2<f32>,
+    min: Vector2<f32>,
+    max: Vector2<f32>,
+) -> Result<Vector2<f32>, ValidationError> {
+    let v = finite_vec2(v)?;
+    if v.x < min.x || v.y < min.y || v.x > max.x || v.y > max.y {
+        return Err(ValidationError::OutOfBounds);
+    }
+    Ok(v)
+}
+
+/// Normalizes `q` into a rotation, rejecting non-finite and (near) zero quaternions.
+pub fn unit_quat_or_err(q: Quaternion<f32>) -> Result<UnitQuaternion<f32>, ValidationError> {
+    let norm_sq = q.norm_squared();
+    if !norm_sq.is_finite() {
+        return Err(ValidationError::NonFinite);
+    }
+    if norm_sq <= MIN_QUAT_NORM_SQ {
+        return Err(ValidationError::DegenerateRotation);
+    }
+    Ok(UnitQuaternion::from_quaternion(q))
+}
+
+/// Scales a capsule's `(radius, half_height)` by `scale`, rejecting scales out of range and
+/// capsules past [`MAX_ACTOR_CAPSULE_RADIUS`] or [`MAX_ACTOR_CAPSULE_HEIGHT`].
+pub fn scaled_capsule(
+    radius: f32,
+    half_height: f32,
+    scale: f32,
+) -> Result<(f32, f32), ValidationError> {
+    if !(radius.is_finite() && half_height.is_finite() && scale.is_finite()) {
+        return Err(ValidationError::NonFinite);
+    }
+    if !(MIN_ACTOR_SCALE..=MAX_ACTOR_SCALE).contains(&scale) {
+        return Err(ValidationError::ScaleOutOfRange);
+    }
+    let (radius, half_height) = (radius * scale, half_height * scale);
+    if radius > MAX_ACTOR_CAPSULE_RADIUS || 2.0 * (half_height + radius) > MAX_ACTOR_CAPSULE_HEIGHT
+    {
+        return Err(ValidationError::CapsuleTooLarge);
+    }
+    Ok((radius, half_height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_non_finite() {
+        assert_eq!(
+            finite_vec3(Vector3::new(0.0, f32::NAN, 0.0)),
+            Err(ValidationError::NonFinite)
+        );
+        assert_eq!(
+            within_world(Vector3::new(f32::INFINITY, 0.0, 0.0)),
+            Err(ValidationError::NonFinite)
+        );
+        assert_eq!(
+            clamped_to_world(Vector3::new(0.0, 0.0, f32::NEG_INFINITY)),
+            Err(ValidationError::NonFinite)
+        );
+        assert_eq!(
+            clamped_to_world_xz(Vector2::new(f32::NAN, 0.0)),
+            Err(ValidationError::NonFinite)
+        );
+    }
+
+    #[test]
+    fn within_world_rejects_outside_positions() {
+        let inside = Vector3::new(WORLD_OFFSET, 0.0, -WORLD_OFFSET);
+        assert_eq!(within_world(inside), Ok(inside));
+        assert_eq!(
+            within_world(Vector3::new(WORLD_OFFSET + 1.0, 0.0, 0.0)),
+            Err(ValidationError::OutOfWorld)
+        );
+        assert_eq!(
+            within_world(Vector3::new(0.0, WORLD_BORDER_HEIGHT + 1.0, 0.0)),
+            Err(ValidationError::OutOfWorld)
+        );
+    }
+
+    #[test]
+    fn within_bounds_rejects_beyond_the_rectangle() {
+        let (min, max) = (Vector2::new(-10.0, -5.0), Vector2::new(10.0, 5.0));
+        let edge = Vector2::new(10.0, -5.0);
+        assert_eq!(within_bounds_xz(edge, min, max), Ok(edge));
+        assert_eq!(
+            within_bounds_xz(Vector2::new(0.0, 5.5), min, max),
+            Err(ValidationError::OutOfBounds)
+        );
+        assert_eq!(
+            within_bounds_xz(Vector2::new(-10.5, 0.0), min, max),
+            Err(ValidationError::OutOfBounds)
+        );
+        assert_eq!(
+            within_bounds_xz(Vector2::new(f32::NAN, 0.0), min, max),
+            Err(ValidationError::NonFinite)
+        );
+    }
+
+    #[test]
+    fn clamped_to_world_clamps_onto_the_edge() {
+        let clamped = clamped_to_world(Vector3::new(WORLD_OFFSET * 2.0, 1.0, -WORLD_OFFSET * 2.0));
+        assert_eq!(clamped, Ok(Vector3::new(WORLD_OFFSET, 1.0, -WORLD_OFFSET)));
+    }
+
+    #[test]
+    fn unit_quat_normalizes_and_rejects_degenerate() {
+        let q = unit_quat_or_err(Quaternion::new(2.0, 0.0, 0.0, 0.0)).unwrap();
+        assert!((q.into_inner().norm() - 1.0).abs() < 1.0e-6);
+        assert_eq!(
+            unit_quat_or_err(Quaternion::new(0.0, 0.0, 0.0, 0.0)),
+            Err(ValidationError::DegenerateRotation)
+        );
+        assert_eq!(
+            unit_quat_or_err(Quaternion::new(f32::NAN, 0.0, 0.0, 1.0)),
+            Err(ValidationError::NonFinite)
+        );
+    }
+
+    #[test]
+    fn scaled_capsule_checks_range_and_size() {
+        assert_eq!(scaled_capsule(0.3, 0.9, 2.0), Ok((0.6, 1.8)));
+        assert_eq!(
+            scaled_capsule(0.3, 0.9, MAX_ACTOR_SCALE + 1.0),
+            Err(ValidationError::ScaleOutOfRange)
+        );
+        assert_eq!(
+            scaled_capsule(0.3, 0.9, 0.0),
+            Err(ValidationError::ScaleOutOfRange)
+        );
+        // In range, but 4 * 2.4m tall doesn't fit.
+        assert_eq!(
+            scaled_capsule(0.3, 0.9, 4.0),
+            Err(ValidationError::CapsuleTooLarge)
+        );
+        assert_eq!(
+            scaled_capsule(0.5, 0.5, 3.5),
+            Err(ValidationError::CapsuleTooLarge)
+        );
+    }
+}